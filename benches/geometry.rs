@@ -0,0 +1,145 @@
+//! Benchmarks of parsing, single queries, all-pairs visibility and indexed
+//! against linear scans, over generated neighbourhoods of several sizes.  Run
+//! with `cargo bench`, optionally followed by `-- <filter>` to run only the
+//! benchmarks whose names contain `filter`, for example `cargo bench -- query`.
+
+use neighbours::domain::{
+    parse_points, parse_points_ndjson, visibility_graph, visible_points_from_neighbours,
+    visible_points_into, Direction, LevelOfDetail, Point,
+};
+use std::time::{Duration, Instant};
+
+/// Neighbourhood sizes benchmarked, with all-pairs visibility only run on the
+/// smaller ones
+const SIZES: [usize; 3] = [1_000, 10_000, 100_000];
+const ALL_PAIRS_SIZES: [usize; 2] = [1_000, 5_000];
+
+/// Shortest time spent timing each benchmark
+const TARGET_TIME: Duration = Duration::from_millis(500);
+
+const ANGLE: u32 = 45;
+const RADIUS: u32 = 50;
+
+/// Points spread evenly over a square with about ten points per `RADIUS`
+/// squared, generated from a fixed seed, so runs are comparable
+fn neighbourhood(size: usize) -> Vec<Point> {
+    let side = ((size as f64 / 10.0).sqrt() * f64::from(RADIUS)) as u64 + 1;
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = move || {
+        state = state
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        state >> 33
+    };
+    (0..size)
+        .map(|index| Point {
+            coordinates: ((next() % side) as i32, (next() % side) as i32),
+            number: index as u32 + 1,
+            direction: match next() % 4 {
+                0 => Direction::North,
+                1 => Direction::East,
+                2 => Direction::South,
+                _ => Direction::West,
+            },
+        })
+        .collect()
+}
+
+fn to_json_line(point: &Point) -> String {
+    format!(
+        "{{\"x\": {}, \"y\": {}, \"number\": {}, \"direction\": \"{:?}\"}}",
+        point.coordinates.0, point.coordinates.1, point.number, point.direction
+    )
+}
+
+/// Runs benchmarks matching the command line filter, printing mean times
+struct Bencher {
+    filter: Option<String>,
+
+    /// Sum of benchmark results, printed so no result is optimised away
+    checksum: usize,
+}
+
+impl Bencher {
+    /// Time `routine`, repeating it until [`TARGET_TIME`] has passed
+    fn run<F: FnMut() -> usize>(&mut self, name: &str, mut routine: F) {
+        if let Some(filter) = &self.filter {
+            if !name.contains(filter.as_str()) {
+                return;
+            }
+        }
+        self.checksum = self.checksum.wrapping_add(routine());
+        let start = Instant::now();
+        let mut iterations: u32 = 0;
+        while start.elapsed() < TARGET_TIME {
+            self.checksum = self.checksum.wrapping_add(routine());
+            iterations += 1;
+        }
+        let mean = start.elapsed() / iterations;
+        println!("{name:<40} {mean:>12.2?} per iteration ({iterations} iterations)");
+    }
+}
+
+fn main() {
+    let mut bencher = Bencher {
+        filter: std::env::args()
+            .skip(1)
+            .find(|value| !value.starts_with("--")),
+        checksum: 0,
+    };
+
+    for size in SIZES {
+        let points = neighbourhood(size);
+        let json = format!(
+            "{{\"points\": [{}]}}",
+            points
+                .iter()
+                .map(to_json_line)
+                .collect::<Vec<String>>()
+                .join(", ")
+        );
+        let ndjson: String = points
+            .iter()
+            .map(|point| to_json_line(point) + "\n")
+            .collect();
+        bencher.run(&format!("parse/json/{size}"), || {
+            parse_points(&json).map_or(0, |value| value.len())
+        });
+        bencher.run(&format!("parse/ndjson/{size}"), || {
+            parse_points_ndjson(ndjson.as_bytes()).map_or(0, |value| value.len())
+        });
+
+        let last = size as u32;
+        bencher.run(&format!("query/linear/{size}"), || {
+            visible_points_from_neighbours(last, ANGLE, RADIUS, &points).len()
+        });
+        let mut visible = vec![];
+        bencher.run(&format!("query/linear_into/{size}"), || {
+            visible_points_into(last, ANGLE, RADIUS, &points, &mut visible);
+            visible.len()
+        });
+        let hierarchy = LevelOfDetail::new(points.clone(), 16);
+        bencher.run(&format!("query/quadtree/{size}"), || {
+            hierarchy
+                .visible_points(last, ANGLE, RADIUS, 0.0)
+                .estimated_count()
+        });
+        bencher.run(&format!("query/quadtree_approximate/{size}"), || {
+            hierarchy
+                .visible_points(last, ANGLE, RADIUS, 0.5)
+                .estimated_count()
+        });
+    }
+
+    for size in ALL_PAIRS_SIZES {
+        let points = neighbourhood(size);
+        bencher.run(&format!("all_pairs/graph/{size}"), || {
+            visibility_graph(ANGLE, RADIUS, &points)
+                .edges
+                .iter()
+                .map(Vec::len)
+                .sum()
+        });
+    }
+    println!("checksum {}", bencher.checksum);
+}
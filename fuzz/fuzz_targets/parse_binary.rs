@@ -0,0 +1,46 @@
+//! Binary inputs: Protocol Buffers, SQLite, Parquet, shapefile and FlatGeobuf
+//! points files, GeoTIFF elevation models, and the points and visibility graph
+//! caches.  The first byte picks the parser.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use neighbours::domain::{
+    parse_points_flatgeobuf, parse_points_parquet, parse_points_protobuf, parse_points_shapefile,
+    parse_points_sqlite, points_from_bytes, Dem, VisibilityGraph, DEFAULT_DBF_FIELDS,
+    DEFAULT_SQLITE_QUERY,
+};
+
+fuzz_target!(|input: &[u8]| {
+    let (selector, bytes) = match input.split_first() {
+        Some(value) => value,
+        None => return,
+    };
+    match selector % 8 {
+        0 => {
+            let _ = parse_points_protobuf(bytes);
+        }
+        1 => {
+            let _ = parse_points_sqlite(bytes, DEFAULT_SQLITE_QUERY);
+        }
+        2 => {
+            let _ = parse_points_parquet(bytes);
+        }
+        3 => {
+            let _ = points_from_bytes(bytes);
+        }
+        4 => {
+            let (shp, dbf) = bytes.split_at(bytes.len() / 2);
+            let _ = parse_points_shapefile(shp, dbf, DEFAULT_DBF_FIELDS);
+        }
+        5 => {
+            let _ = parse_points_flatgeobuf(bytes, None);
+        }
+        6 => {
+            let _ = Dem::from_geotiff(bytes);
+        }
+        _ => {
+            let _ = VisibilityGraph::from_bytes(bytes);
+        }
+    }
+});
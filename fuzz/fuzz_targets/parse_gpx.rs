@@ -0,0 +1,10 @@
+//! GPX documents, as read by `parse_points_file` for `.gpx` files
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use neighbours::domain::parse_points_gpx;
+
+fuzz_target!(|input: &str| {
+    let _ = parse_points_gpx(input);
+});
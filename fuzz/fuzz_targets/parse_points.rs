@@ -0,0 +1,13 @@
+//! JSON points, as read by `parse_points_file` for `.json` files, and by
+//! `parse_borrowed_points`
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use neighbours::domain::{parse_borrowed_points, parse_points};
+
+fuzz_target!(|input: &str| {
+    let points = parse_points(input);
+    let borrowed = parse_borrowed_points(input);
+    assert_eq!(points.is_ok(), borrowed.is_ok());
+});
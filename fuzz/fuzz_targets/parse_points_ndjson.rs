@@ -0,0 +1,13 @@
+//! Newline delimited JSON points, parsed serially and in parallel chunks,
+//! which must agree
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use neighbours::domain::{parse_points_ndjson, parse_points_ndjson_parallel};
+
+fuzz_target!(|input: &str| {
+    let serial = parse_points_ndjson(input.as_bytes()).map_err(|error| error.to_string());
+    let parallel = parse_points_ndjson_parallel(input, 3).map_err(|error| error.to_string());
+    assert_eq!(serial, parallel);
+});
@@ -0,0 +1,14 @@
+//! Batch queries, as CSV or JSON, and single query lines, as read by the
+//! `pipe` command
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use neighbours::domain::{parse_queries, parse_query_line};
+
+fuzz_target!(|input: &str| {
+    let _ = parse_queries(input);
+    for line in input.lines() {
+        let _ = parse_query_line(line);
+    }
+});
@@ -0,0 +1,18 @@
+//! Point shapefiles, as read by `parse_points_file` for `.shp` files.  The
+//! first two bytes give the length of the `.shp` file, and the rest of the
+//! input, after it, is the `.dbf` table.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use neighbours::domain::{parse_points_shapefile, DEFAULT_DBF_FIELDS};
+
+fuzz_target!(|input: &[u8]| {
+    if input.len() < 2 {
+        return;
+    }
+    let (length, rest) = input.split_at(2);
+    let length = usize::from(u16::from_le_bytes([length[0], length[1]])).min(rest.len());
+    let (shp, dbf) = rest.split_at(length);
+    let _ = parse_points_shapefile(shp, dbf, DEFAULT_DBF_FIELDS);
+});
@@ -0,0 +1,1883 @@
+mod progress;
+
+pub use progress::ProgressBar;
+
+use neighbours::{
+    domain::{
+        BearingBand, BoundingBox, DuplicateStrategy, Facing, Origin, OriginCombination, PathLoss,
+        SetOperation, Sightline,
+    },
+    utilities::AppError,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    str::FromStr,
+};
+
+/// Points file read when no `--file` option is given
+const DEFAULT_POINTS_FILE: &str = "./points.json";
+
+/// Address the server listens on when no `--bind` option is given
+const DEFAULT_BIND_ADDRESS: &str = "127.0.0.1:8080";
+
+/// Redis hash holding live positions, when `--redis-key` is not given
+const DEFAULT_REDIS_KEY: &str = "neighbours:points";
+#[cfg(feature = "stream")]
+const DEFAULT_MQTT_TOPIC: &str = "neighbours/positions";
+
+/// Options which take no value
+const FLAGS: &[&str] = &[
+    "3d",
+    "cache-points",
+    "earth-curvature",
+    "hull",
+    "profile",
+    "progress",
+    "relative",
+    "sweep",
+    "verbose",
+];
+
+/// Output formats for heatmaps
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum HeatmapFormat {
+    Csv,
+    Pgm,
+}
+
+impl FromStr for HeatmapFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "csv" => Ok(HeatmapFormat::Csv),
+            "pgm" => Ok(HeatmapFormat::Pgm),
+            _ => Err(format!(
+                "unknown heatmap format `{value}`, expected `csv` or `pgm`"
+            )),
+        }
+    }
+}
+
+/// Output formats for visibility matrices
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MatrixFormat {
+    Bitset,
+    Csv,
+    DistanceCsv,
+}
+
+impl FromStr for MatrixFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "bitset" => Ok(MatrixFormat::Bitset),
+            "csv" => Ok(MatrixFormat::Csv),
+            "distance-csv" => Ok(MatrixFormat::DistanceCsv),
+            _ => Err(format!(
+                "unknown matrix format `{value}`, expected `bitset`, `csv` or `distance-csv`"
+            )),
+        }
+    }
+}
+
+/// Output formats for 3D scenes
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SceneFormat {
+    Glb,
+    Obj,
+}
+
+impl FromStr for SceneFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "glb" => Ok(SceneFormat::Glb),
+            "obj" => Ok(SceneFormat::Obj),
+            _ => Err(format!(
+                "unknown scene format `{value}`, expected `glb` or `obj`"
+            )),
+        }
+    }
+}
+
+/// Output formats for per-point summaries
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SummaryFormat {
+    Csv,
+    Json,
+}
+
+impl FromStr for SummaryFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "csv" => Ok(SummaryFormat::Csv),
+            "json" => Ok(SummaryFormat::Json),
+            _ => Err(format!(
+                "unknown summary format `{value}`, expected `csv` or `json`"
+            )),
+        }
+    }
+}
+
+/// Comma separated point numbers, such as `3,9`
+#[derive(Clone, Debug, PartialEq)]
+pub struct PointNumbers(pub Vec<u32>);
+
+impl FromStr for PointNumbers {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        value
+            .split(',')
+            .map(|number| {
+                number
+                    .trim()
+                    .parse()
+                    .map_err(|_| format!("invalid point number `{number}`"))
+            })
+            .collect::<Result<Vec<u32>, String>>()
+            .map(PointNumbers)
+    }
+}
+
+/// Options shared by commands which query a neighbourhood
+#[derive(Clone, Debug, PartialEq)]
+pub struct QueryOptions {
+    /// Path of the points JSON file
+    pub file: PathBuf,
+
+    /// SQL query selecting points, when `file` is a SQLite database, or a
+    /// PostgreSQL connection URL
+    pub query: Option<String>,
+
+    /// Mapping of `.dbf` fields to point numbers and directions, such as
+    /// `number=ID,direction=HEADING`, when `file` is a shapefile, or of point
+    /// attributes, when it is a PLY or LAS point cloud
+    pub dbf_fields: Option<String>,
+
+    /// Only read points within this box, searching the spatial index of
+    /// FlatGeobuf files
+    pub bbox: Option<BoundingBox>,
+
+    /// Half arc central angle of each segment, in degrees
+    pub angle: u32,
+
+    /// Radius of each segment
+    pub radius: u32,
+
+    /// Show progress bars on standard error for long operations
+    pub progress: bool,
+
+    /// Reuse a binary copy of the parsed points, saved next to `file`, while
+    /// `file` is unchanged
+    pub cache_points: bool,
+
+    /// Remove repeated point records after reading them
+    pub dedup: Option<DuplicateStrategy>,
+
+    /// Derive each point's direction, facing its nearest neighbour or the
+    /// centroid of all points, for points with no recorded orientation
+    pub face: Option<Facing>,
+
+    /// Thin the points to one per square cell this many units across, keeping
+    /// the point nearest the middle of each
+    pub downsample: Option<u32>,
+
+    /// Save, as JSON, the numbers of the points each kept point stands for,
+    /// after `--downsample`
+    pub downsample_map: Option<PathBuf>,
+
+    /// Ignore points whose `observed_at` time is more than this many seconds
+    /// ago
+    pub max_age: Option<u64>,
+}
+
+/// Command line, parsed into the command to run, and options shared by all
+/// commands
+#[derive(Clone, Debug, PartialEq)]
+pub struct Arguments {
+    pub command: Command,
+
+    /// Log timings and other diagnostics to standard error, as with
+    /// `RUST_LOG=debug`
+    pub verbose: bool,
+
+    /// Print a breakdown of time spent reading, parsing, querying and writing
+    /// output to standard error, once the command finishes
+    pub profile: bool,
+}
+
+/// Command to run, parsed from command line arguments
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    /// List points visible from `point`, optionally with their convex hull.
+    /// With `id`, points in `file` carry string identifiers, and the point
+    /// identified by `id` is queried instead.  With `bearings`, points whose
+    /// bearing lies in the band are listed, whichever way `point` faces.  With
+    /// `origin`, points visible from that position and heading are listed.
+    /// With `relative`, each point is listed with its distance, and its bearing
+    /// both from north and relative to the heading.  With `sweep`, points are
+    /// listed by bearing, from the left of the heading round to the right,
+    /// nearest first, rather than by number.  With `dem`, points hidden by
+    /// the terrain of that GeoTIFF elevation model are left out, and with
+    /// Earth curvature in `sightline`, points below the horizon.  With a
+    /// frequency in `sightline`, the Fresnel zone clearance and `path_loss`
+    /// of each link are reported.  With `occlusion`, points hidden behind
+    /// other points, each standing as tall as its `height` and with a
+    /// footprint of that radius, are left out, and with `vertical_angle`,
+    /// points further above or below the observer’s horizontal, in degrees.
+    /// With `three_d`, points are taken in 3D, each at its `z`, and seen
+    /// within that vertical half angle, or all round vertically.
+    Visible {
+        query: QueryOptions,
+        point: u32,
+        id: Option<String>,
+        bearings: Option<BearingBand>,
+        origin: Option<Origin>,
+        dem: Option<PathBuf>,
+        path_loss: PathLoss,
+        sightline: Sightline,
+        occlusion: Option<f64>,
+        vertical_angle: Option<f64>,
+        three_d: bool,
+        hull: bool,
+        relative: bool,
+        sweep: bool,
+    },
+
+    /// List points visible from several origin points at once, combined by
+    /// `combination`
+    Cover {
+        query: QueryOptions,
+        points: Vec<u32>,
+        combination: OriginCombination,
+    },
+
+    /// List bearings within the sector of `point`, or of `origin`, where no
+    /// visible point lies, widest first
+    Gaps {
+        query: QueryOptions,
+        point: u32,
+        origin: Option<Origin>,
+    },
+
+    /// Report the fraction of the sector of `point`, or of `origin`, split
+    /// into `buckets` of bearing, holding a visible point between
+    /// `min_distance` and `max_distance` away.  Without `buckets`, buckets are
+    /// one degree wide, and without `max_distance`, the radius is used.
+    Coverage {
+        query: QueryOptions,
+        point: u32,
+        origin: Option<Origin>,
+        buckets: Option<usize>,
+        min_distance: f64,
+        max_distance: Option<f64>,
+    },
+
+    /// Split the sector of `point`, or of `origin`, into `buckets` of
+    /// bearing, listing the nearest visible point in each, from left to
+    /// right, as a simple range sensor would
+    Scan {
+        query: QueryOptions,
+        point: u32,
+        origin: Option<Origin>,
+        buckets: usize,
+    },
+
+    /// Write a JSON histogram of distances to points visible from `point`, or
+    /// from `origin`, in bins `bin_width` units wide
+    Histogram {
+        query: QueryOptions,
+        point: u32,
+        origin: Option<Origin>,
+        bin_width: f64,
+        output: Option<PathBuf>,
+    },
+
+    /// Write GeoJSON layers for the origin, sector, visible and invisible
+    /// points of a query from `point`, or from `origin`, to `output_directory`,
+    /// ready to add to a web map
+    GeoJson {
+        query: QueryOptions,
+        point: u32,
+        origin: Option<Origin>,
+        output_directory: PathBuf,
+    },
+
+    /// Write a KML document with the origin, sector and visible points of a
+    /// query from `point`, or from `origin`, for reviewing in Google Earth
+    Kml {
+        query: QueryOptions,
+        point: u32,
+        origin: Option<Origin>,
+        output: Option<PathBuf>,
+    },
+
+    /// Write a 3D scene of the points, each at its `z`, and their view cones,
+    /// spanning `angle` across and `vertical_angle` up and down, as binary glTF
+    /// or OBJ, for reviewing coverage in Blender or a game engine
+    Scene {
+        query: QueryOptions,
+        vertical_angle: Option<f64>,
+        format: SceneFormat,
+        output: Option<PathBuf>,
+    },
+
+    /// Time a linear scan and each index backend answering `queries` range
+    /// queries on the points, recommending the quickest of those finding at
+    /// least `min_recall` of the points in range, and write a JSON report
+    BenchIndex {
+        query: QueryOptions,
+        queries: usize,
+        min_recall: f64,
+        output: Option<PathBuf>,
+    },
+
+    /// Count how many points see each cell of a grid over the neighbourhood
+    Heatmap {
+        query: QueryOptions,
+        cell_size: u32,
+        format: HeatmapFormat,
+        output: Option<PathBuf>,
+    },
+
+    /// Write the all-pairs visibility matrix, optionally reusing a graph
+    /// cached in `cache_directory`
+    Matrix {
+        query: QueryOptions,
+        format: MatrixFormat,
+        output: Option<PathBuf>,
+        cache_directory: Option<PathBuf>,
+    },
+
+    /// Write, for every point, how many points it sees, their mean distance,
+    /// and how many see it back, as CSV, or JSON lines
+    Summary {
+        query: QueryOptions,
+        format: SummaryFormat,
+        output: Option<PathBuf>,
+    },
+
+    /// Stream every point's visible neighbours, one JSON line each, from
+    /// `tile_size` tiles of `file` saved in `tile_directory`, so inputs
+    /// larger than memory can be processed
+    AllPairs {
+        file: PathBuf,
+        angle: u32,
+        radius: u32,
+        tile_size: Option<u32>,
+        tile_directory: Option<PathBuf>,
+        output: Option<PathBuf>,
+    },
+
+    /// Query a saved visibility graph, without the points file, for points
+    /// visible from `point`, a path from `point` to `to`, or points reachable
+    /// from `point` within `hops` hops
+    Graph {
+        graph: PathBuf,
+        point: u32,
+        to: Option<u32>,
+        hops: Option<usize>,
+    },
+
+    /// List points reachable from `point` through chains of at most `hops`
+    /// visibility edges, with the hops needed to reach each
+    Reach {
+        query: QueryOptions,
+        point: u32,
+        hops: usize,
+    },
+
+    /// List points seen by no other point, points which see no other point,
+    /// and a small set of sentinel points which between them see every other
+    /// point
+    Audit { query: QueryOptions },
+
+    /// Report the heading from which `point` would see the most points, or
+    /// the greatest total of each point's numeric `weight_field`, or, with
+    /// `k`, the smallest radius at which it sees at least `k` points, facing
+    /// its own direction
+    Optimise {
+        query: QueryOptions,
+        point: u32,
+        k: Option<usize>,
+        weight_field: Option<String>,
+    },
+
+    /// List neighbours visible from `point`, with how fast each is closing,
+    /// and when and how near its closest approach is, from the `vx` and `vy`
+    /// velocities in `file`
+    Contacts {
+        file: PathBuf,
+        point: u32,
+        angle: u32,
+        radius: u32,
+    },
+
+    /// Move points in `file` on by their `vx` and `vy` velocities, `steps`
+    /// times, `step_seconds` apart, writing which points each sees at every
+    /// step, as one JSON line per step, or one file per step in
+    /// `frames_directory`
+    Simulate {
+        file: PathBuf,
+        angle: u32,
+        radius: u32,
+        steps: usize,
+        step_seconds: f64,
+        frames_directory: Option<PathBuf>,
+    },
+
+    /// Simulate points moving, as for `Simulate`, drawing each step as a
+    /// `size` pixel square frame, shown `frames_per_second`, stitched into a
+    /// GIF at `output`, or an MP4, with `ffmpeg`, when `output` ends `.mp4`
+    #[cfg(feature = "animation")]
+    Animate {
+        file: PathBuf,
+        angle: u32,
+        radius: u32,
+        steps: usize,
+        step_seconds: f64,
+        output: PathBuf,
+        size: usize,
+        frames_per_second: u32,
+    },
+
+    /// Report points added, removed or moved between two points files
+    Diff { old: PathBuf, new: PathBuf },
+
+    /// Combine saved query results from two files by `operation`, keyed by
+    /// point number, writing one JSON line per point
+    Combine {
+        left: PathBuf,
+        right: PathBuf,
+        operation: SetOperation,
+        output: Option<PathBuf>,
+    },
+
+    /// Report, for each point, neighbours which became visible or stopped
+    /// being visible between two points files
+    Compare {
+        old: PathBuf,
+        new: PathBuf,
+        angle: u32,
+        radius: u32,
+    },
+
+    /// Run every query in the `queries` file against the neighbourhood in
+    /// `file`, writing one JSON line per query.  With `threads`, NDJSON
+    /// points files are parsed in parallel, on that many threads.
+    Batch {
+        file: PathBuf,
+        queries: PathBuf,
+        output: Option<PathBuf>,
+        threads: Option<usize>,
+    },
+
+    /// Answer queries read from standard input, one per line, writing one
+    /// JSON result line to standard output for each
+    Pipe { file: PathBuf },
+
+    /// Apply position updates, read as JSON lines from standard input, or
+    /// messages published on `topic` to the `mqtt` broker, to the
+    /// neighbourhood, printing what `point` sees whenever it changes
+    #[cfg(feature = "stream")]
+    Monitor {
+        query: QueryOptions,
+        point: u32,
+        mqtt: Option<String>,
+        topic: String,
+    },
+
+    /// Serve queries against the neighbourhood in `file` over HTTP, with
+    /// Prometheus metrics at `/metrics`
+    Serve {
+        file: PathBuf,
+
+        /// SQL query selecting points, when `file` is a database
+        query: Option<String>,
+
+        /// URL of a Redis server holding live positions, read for every query
+        /// instead of `file`
+        redis: Option<String>,
+
+        /// Redis hash holding positions
+        redis_key: String,
+
+        bind: String,
+        max_body_bytes: usize,
+        max_batch_queries: usize,
+        requests_per_minute: Option<u32>,
+
+        /// Longest time answering one request, in milliseconds, before
+        /// giving up with a `503` response
+        timeout_ms: Option<u64>,
+
+        /// Most connections handled at once
+        max_connections: usize,
+
+        /// Longest time reading a request from, or writing a response to, a
+        /// client, in milliseconds
+        connection_timeout_ms: u64,
+    },
+}
+
+/// `--name value` options, `--name` flags, and positional arguments, following
+/// the subcommand
+struct Options {
+    values: HashMap<String, String>,
+    flags: HashSet<String>,
+    positional: Vec<String>,
+}
+
+impl Options {
+    fn parse<I: Iterator<Item = String>>(arguments: I) -> Result<Self, AppError> {
+        let mut values = HashMap::new();
+        let mut flags = HashSet::new();
+        let mut positional = vec![];
+        let mut arguments = arguments;
+        while let Some(argument) = arguments.next() {
+            let name = match argument.strip_prefix("--") {
+                Some(value) => value.to_string(),
+                None => {
+                    positional.push(argument);
+                    continue;
+                }
+            };
+            if FLAGS.contains(&name.as_str()) {
+                flags.insert(name);
+                continue;
+            }
+            match arguments.next() {
+                Some(value) => {
+                    values.insert(name, value);
+                }
+                None => {
+                    return Err(AppError::ArgumentError(format!(
+                        "missing value for `--{name}`"
+                    )))
+                }
+            }
+        }
+        Ok(Options {
+            values,
+            flags,
+            positional,
+        })
+    }
+
+    /// Remove and parse the value of option `name`, if it was given
+    fn take<T>(&mut self, name: &str) -> Result<Option<T>, AppError>
+    where
+        T: FromStr,
+        T::Err: std::fmt::Display,
+    {
+        match self.values.remove(name) {
+            Some(value) => value.parse().map(Some).map_err(|error| {
+                AppError::ArgumentError(format!("invalid value for `--{name}`: {error}"))
+            }),
+            None => Ok(None),
+        }
+    }
+
+    /// Remove the next positional argument, which is required and describes
+    /// `name`
+    fn take_positional<T>(&mut self, name: &str) -> Result<T, AppError>
+    where
+        T: FromStr,
+        T::Err: std::fmt::Display,
+    {
+        if self.positional.is_empty() {
+            return Err(AppError::ArgumentError(format!("missing {name} argument")));
+        }
+        self.positional.remove(0).parse().map_err(|error| {
+            AppError::ArgumentError(format!("invalid value for {name} argument: {error}"))
+        })
+    }
+
+    /// Remove flag `name`, returning true if it was given
+    fn take_flag(&mut self, name: &str) -> bool {
+        self.flags.remove(name)
+    }
+
+    fn take_or<T>(&mut self, name: &str, default: T) -> Result<T, AppError>
+    where
+        T: FromStr,
+        T::Err: std::fmt::Display,
+    {
+        Ok(self.take(name)?.unwrap_or(default))
+    }
+
+    fn query_options(&mut self) -> Result<QueryOptions, AppError> {
+        let options = QueryOptions {
+            file: self.take_or("file", PathBuf::from(DEFAULT_POINTS_FILE))?,
+            query: self.take("query")?,
+            dbf_fields: self.take("dbf-fields")?,
+            bbox: self.take("bbox")?,
+            angle: self.take_or("angle", 45)?,
+            radius: self.take_or("radius", 20)?,
+            progress: self.take_flag("progress"),
+            cache_points: self.take_flag("cache-points"),
+            dedup: self.take("dedup")?,
+            face: self.take("face")?,
+            downsample: self.take("downsample")?,
+            downsample_map: self.take("downsample-map")?,
+            max_age: self.take("max-age")?,
+        };
+        if options.max_age.is_some() && (options.query.is_some() || options.cache_points) {
+            return Err(AppError::ArgumentError(String::from(
+                "`--max-age` needs observation times, which `--query` and `--cache-points` do not keep",
+            )));
+        }
+        if options.dbf_fields.is_some() && (options.query.is_some() || options.max_age.is_some()) {
+            return Err(AppError::ArgumentError(String::from(
+                "`--dbf-fields` reads shapefiles and point clouds, so cannot be combined with `--query` or `--max-age`",
+            )));
+        }
+        if options.cache_points && (options.query.is_some() || options.dbf_fields.is_some()) {
+            return Err(AppError::ArgumentError(String::from(
+                "`--cache-points` caches the whole points file, so cannot be combined with `--query` or `--dbf-fields`",
+            )));
+        }
+        if options.downsample == Some(0) {
+            return Err(AppError::ArgumentError(String::from(
+                "`--downsample` needs a cell size of at least 1",
+            )));
+        }
+        if options.downsample_map.is_some() && options.downsample.is_none() {
+            return Err(AppError::ArgumentError(String::from(
+                "`--downsample-map` needs `--downsample`",
+            )));
+        }
+        Ok(options)
+    }
+
+    /// Returns an error if options are given which read points without their
+    /// `z` and orientation, or rework them on the plan, as `name` needs for
+    /// points in space
+    fn check_space_options(&self, name: &str) -> Result<(), AppError> {
+        if self.flags.contains("cache-points")
+            || ["query", "dbf-fields", "dedup", "downsample", "face"]
+                .iter()
+                .any(|option| self.values.contains_key(*option))
+        {
+            return Err(AppError::ArgumentError(format!(
+                "`{name}` reads each point's `z` and orientation from its record, so cannot be combined with `--query`, `--dbf-fields`, `--cache-points`, `--dedup`, `--downsample` or `--face`",
+            )));
+        }
+        Ok(())
+    }
+
+    /// Half angle, in degrees, of each point's vertical field of view, if given
+    fn vertical_angle(&mut self) -> Result<Option<f64>, AppError> {
+        let result: Option<f64> = self.take("vertical-angle")?;
+        if result.map_or(false, |value| !(0.0..=90.0).contains(&value)) {
+            return Err(AppError::ArgumentError(String::from(
+                "`--vertical-angle` should be a half angle from 0 to 90 degrees",
+            )));
+        }
+        Ok(result)
+    }
+
+    /// How sightlines are judged, in metres, for terrain, occlusion and long
+    /// range visibility
+    fn sightline(&mut self) -> Result<Sightline, AppError> {
+        let observer_height: f64 = self.take_or("observer-height", 0.0)?;
+        if !(observer_height.is_finite() && observer_height >= 0.0) {
+            return Err(AppError::ArgumentError(String::from(
+                "`--observer-height` should be a height of zero or more",
+            )));
+        }
+        let target_height: f64 = self.take_or("target-height", 0.0)?;
+        if !(target_height.is_finite() && target_height >= 0.0) {
+            return Err(AppError::ArgumentError(String::from(
+                "`--target-height` should be a height of zero or more",
+            )));
+        }
+        let earth_curvature = self.take_flag("earth-curvature");
+        let refraction: Option<f64> = self.take("refraction")?;
+        match refraction {
+            Some(_) if !earth_curvature => {
+                return Err(AppError::ArgumentError(String::from(
+                    "`--refraction` bends sightlines over the curved Earth, so needs `--earth-curvature`",
+                )))
+            }
+            Some(value) if !(value.is_finite() && value < 1.0) => {
+                return Err(AppError::ArgumentError(String::from(
+                    "`--refraction` should be a coefficient less than 1, such as 0.13",
+                )))
+            }
+            _ => {}
+        }
+        let frequency: Option<f64> = self.take("frequency")?;
+        if frequency.map_or(false, |value| !(value.is_finite() && value > 0.0)) {
+            return Err(AppError::ArgumentError(String::from(
+                "`--frequency` should be a radio frequency in MHz, such as 2400",
+            )));
+        }
+        let fresnel_clearance: Option<f64> = self.take("fresnel-clearance")?;
+        match fresnel_clearance {
+            Some(_) if frequency.is_none() => {
+                return Err(AppError::ArgumentError(String::from(
+                    "`--fresnel-clearance` sizes the Fresnel zone from the wavelength, so needs `--frequency`",
+                )))
+            }
+            Some(value) if !(0.0..=1.0).contains(&value) => {
+                return Err(AppError::ArgumentError(String::from(
+                    "`--fresnel-clearance` should be a fraction from 0 to 1, such as 0.6",
+                )))
+            }
+            _ => {}
+        }
+        Ok(Sightline {
+            observer_height,
+            target_height,
+            earth_curvature,
+            refraction: refraction.unwrap_or(0.0),
+            frequency,
+            fresnel_clearance: fresnel_clearance.unwrap_or(0.0),
+        })
+    }
+
+    /// Model of path loss along radio links, reported with `--frequency`
+    fn path_loss(&mut self, sightline: &Sightline) -> Result<PathLoss, AppError> {
+        let exponent: Option<f64> = self.take("path-loss-exponent")?;
+        let reference_distance: Option<f64> = self.take("reference-distance")?;
+        match (exponent, reference_distance) {
+            (None, None) => Ok(PathLoss::FreeSpace),
+            (None, Some(_)) => Err(AppError::ArgumentError(String::from(
+                "`--reference-distance` starts log-distance path loss, so needs `--path-loss-exponent`",
+            ))),
+            (Some(_), _) if sightline.frequency.is_none() => Err(AppError::ArgumentError(
+                String::from("`--path-loss-exponent` needs `--frequency`"),
+            )),
+            (Some(exponent), reference_distance) => {
+                let reference_distance = reference_distance.unwrap_or(1.0);
+                if !(exponent.is_finite() && exponent > 0.0) {
+                    return Err(AppError::ArgumentError(String::from(
+                        "`--path-loss-exponent` should be a positive number, such as 3",
+                    )));
+                }
+                if !(reference_distance.is_finite() && reference_distance > 0.0) {
+                    return Err(AppError::ArgumentError(String::from(
+                        "`--reference-distance` should be a distance of more than zero",
+                    )));
+                }
+                Ok(PathLoss::LogDistance {
+                    exponent,
+                    reference_distance,
+                })
+            }
+        }
+    }
+
+    /// Error if any options were given which the command does not use
+    fn finish(self) -> Result<(), AppError> {
+        if let Some(argument) = self.positional.first() {
+            return Err(AppError::ArgumentError(format!(
+                "unexpected argument `{argument}`"
+            )));
+        }
+        let mut unused: Vec<String> = self.values.into_keys().chain(self.flags).collect();
+        unused.sort();
+        match unused.first() {
+            Some(name) => Err(AppError::ArgumentError(format!(
+                "unknown option `--{name}`"
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Parse command line `arguments`, excluding the program name.  With no
+/// subcommand, points visible from point `1` in `./points.json` are listed.
+pub fn parse_arguments<I: IntoIterator<Item = String>>(
+    arguments: I,
+) -> Result<Arguments, AppError> {
+    let mut arguments = arguments.into_iter().peekable();
+    let subcommand = match arguments.peek() {
+        Some(value) if !value.starts_with("--") => arguments.next(),
+        _ => None,
+    };
+    let mut options = Options::parse(arguments)?;
+    let verbose = options.take_flag("verbose");
+    let profile = options.take_flag("profile");
+
+    let command = match subcommand.as_deref() {
+        None | Some("visible") => {
+            let sightline = options.sightline()?;
+            let occlusion: Option<f64> = options.take("occlusion")?;
+            if occlusion.map_or(false, |value| !(value.is_finite() && value >= 0.0)) {
+                return Err(AppError::ArgumentError(String::from(
+                    "`--occlusion` should be a blocker radius of zero or more",
+                )));
+            }
+            let vertical_angle = options.vertical_angle()?;
+            let three_d = options.take_flag("3d");
+            if three_d
+                && (occlusion.is_some()
+                    || ["id", "origin", "bearings", "dem"]
+                        .iter()
+                        .any(|name| options.values.contains_key(*name)))
+            {
+                return Err(AppError::ArgumentError(String::from(
+                    "`--3d` cannot be combined with `--id`, `--origin`, `--bearings`, `--dem` or `--occlusion`",
+                )));
+            }
+            if three_d {
+                options.check_space_options("--3d")?;
+            }
+            if options.values.contains_key("id")
+                && (options.flags.contains("cache-points")
+                    || ["query", "dbf-fields", "max-age", "dedup", "downsample"]
+                        .iter()
+                        .any(|name| options.values.contains_key(*name)))
+            {
+                return Err(AppError::ArgumentError(String::from(
+                    "`--id` reads points numbered by their place in the file, so cannot be combined with `--query`, `--dbf-fields`, `--cache-points`, `--max-age`, `--dedup` or `--downsample`",
+                )));
+            }
+            if (occlusion.is_some() || vertical_angle.is_some())
+                && (options.flags.contains("cache-points")
+                    || ["id", "query", "dbf-fields"]
+                        .iter()
+                        .any(|name| options.values.contains_key(*name)))
+            {
+                return Err(AppError::ArgumentError(String::from(
+                    "`--occlusion` and `--vertical-angle` need point heights, which `--id`, `--query`, `--dbf-fields` and `--cache-points` do not keep",
+                )));
+            }
+            Command::Visible {
+                query: options.query_options()?,
+                point: options.take_or("point", 1)?,
+                id: options.take("id")?,
+                bearings: options.take("bearings")?,
+                origin: options.take("origin")?,
+                dem: options.take("dem")?,
+                path_loss: options.path_loss(&sightline)?,
+                sightline,
+                occlusion,
+                vertical_angle,
+                three_d,
+                hull: options.take_flag("hull"),
+                relative: options.take_flag("relative"),
+                sweep: options.take_flag("sweep"),
+            }
+        }
+        Some("cover") => Command::Cover {
+            query: options.query_options()?,
+            points: options
+                .take::<PointNumbers>("points")?
+                .ok_or_else(|| {
+                    AppError::ArgumentError(String::from("missing required option `--points`"))
+                })?
+                .0,
+            combination: options.take_or("combine", OriginCombination::Union)?,
+        },
+        Some("gaps") => Command::Gaps {
+            query: options.query_options()?,
+            point: options.take_or("point", 1)?,
+            origin: options.take("origin")?,
+        },
+        Some("coverage") => Command::Coverage {
+            query: options.query_options()?,
+            point: options.take_or("point", 1)?,
+            origin: options.take("origin")?,
+            buckets: options.take("buckets")?,
+            min_distance: options.take_or("min-distance", 0.0)?,
+            max_distance: options.take("max-distance")?,
+        },
+        Some("scan") => Command::Scan {
+            query: options.query_options()?,
+            point: options.take_or("point", 1)?,
+            origin: options.take("origin")?,
+            buckets: options.take_or("buckets", 8)?,
+        },
+        Some("histogram") => Command::Histogram {
+            query: options.query_options()?,
+            point: options.take_or("point", 1)?,
+            origin: options.take("origin")?,
+            bin_width: options.take_or("bin-width", 1.0)?,
+            output: options.take("output")?,
+        },
+        Some("geojson") => Command::GeoJson {
+            query: options.query_options()?,
+            point: options.take_or("point", 1)?,
+            origin: options.take("origin")?,
+            output_directory: options.take("output-dir")?.ok_or_else(|| {
+                AppError::ArgumentError(String::from("missing required option `--output-dir`"))
+            })?,
+        },
+        Some("kml") => Command::Kml {
+            query: options.query_options()?,
+            point: options.take_or("point", 1)?,
+            origin: options.take("origin")?,
+            output: options.take("output")?,
+        },
+        Some("scene") => {
+            options.check_space_options("scene")?;
+            Command::Scene {
+                query: options.query_options()?,
+                vertical_angle: options.vertical_angle()?,
+                format: options.take_or("format", SceneFormat::Glb)?,
+                output: options.take("output")?,
+            }
+        }
+        Some("bench-index") => {
+            let query = options.query_options()?;
+            let min_recall = options.take_or("min-recall", 1.0)?;
+            if !(0.0..=1.0).contains(&min_recall) {
+                return Err(AppError::ArgumentError(format!(
+                    "`--min-recall` must be from 0 to 1, not {min_recall}"
+                )));
+            }
+            Command::BenchIndex {
+                query,
+                queries: options.take_or("queries", 1000)?,
+                min_recall,
+                output: options.take("output")?,
+            }
+        }
+        Some("heatmap") => Command::Heatmap {
+            query: options.query_options()?,
+            cell_size: options.take_or("cell-size", 1)?,
+            format: options.take_or("format", HeatmapFormat::Csv)?,
+            output: options.take("output")?,
+        },
+        Some("matrix") => Command::Matrix {
+            query: options.query_options()?,
+            format: options.take_or("format", MatrixFormat::Csv)?,
+            output: options.take("output")?,
+            cache_directory: options.take("cache-dir")?,
+        },
+        Some("summary") => Command::Summary {
+            query: options.query_options()?,
+            format: options.take_or("format", SummaryFormat::Csv)?,
+            output: options.take("output")?,
+        },
+        Some("all-pairs") => Command::AllPairs {
+            file: options.take_or("file", PathBuf::from(DEFAULT_POINTS_FILE))?,
+            angle: options.take_or("angle", 45)?,
+            radius: options.take_or("radius", 20)?,
+            tile_size: options.take("tile-size")?,
+            tile_directory: options.take("tile-dir")?,
+            output: options.take("output")?,
+        },
+        Some("graph") => Command::Graph {
+            graph: options.take("graph")?.ok_or_else(|| {
+                AppError::ArgumentError(String::from("missing required option `--graph`"))
+            })?,
+            point: options.take_or("point", 1)?,
+            to: options.take("to")?,
+            hops: options.take("hops")?,
+        },
+        Some("reach") => Command::Reach {
+            query: options.query_options()?,
+            point: options.take_or("point", 1)?,
+            hops: options.take_or("hops", 2)?,
+        },
+        Some("audit") => Command::Audit {
+            query: options.query_options()?,
+        },
+        Some("optimise") => {
+            let k = options.take("k")?;
+            let weight_field = options.take("weight-field")?;
+            if k.is_some() && weight_field.is_some() {
+                return Err(AppError::ArgumentError(String::from(
+                    "`--weight-field` weighs headings, so cannot be combined with `--k`",
+                )));
+            }
+            Command::Optimise {
+                query: options.query_options()?,
+                point: options.take_or("point", 1)?,
+                k,
+                weight_field,
+            }
+        }
+        Some("contacts") => Command::Contacts {
+            file: options.take_or("file", PathBuf::from(DEFAULT_POINTS_FILE))?,
+            point: options.take_or("point", 1)?,
+            angle: options.take_or("angle", 45)?,
+            radius: options.take_or("radius", 20)?,
+        },
+        Some("simulate") => Command::Simulate {
+            file: options.take_or("file", PathBuf::from(DEFAULT_POINTS_FILE))?,
+            angle: options.take_or("angle", 45)?,
+            radius: options.take_or("radius", 20)?,
+            steps: options.take_or("steps", 10)?,
+            step_seconds: options.take_or("step-seconds", 1.0)?,
+            frames_directory: options.take("frames-dir")?,
+        },
+        #[cfg(feature = "animation")]
+        Some("animate") => Command::Animate {
+            file: options.take_or("file", PathBuf::from(DEFAULT_POINTS_FILE))?,
+            angle: options.take_or("angle", 45)?,
+            radius: options.take_or("radius", 20)?,
+            steps: options.take_or("steps", 10)?,
+            step_seconds: options.take_or("step-seconds", 1.0)?,
+            output: options.take("output")?.ok_or_else(|| {
+                AppError::ArgumentError(String::from("missing required option `--output`"))
+            })?,
+            size: options.take_or("size", 400)?,
+            frames_per_second: options.take_or("fps", 10)?,
+        },
+        Some("diff") => Command::Diff {
+            old: options.take_positional("old points file")?,
+            new: options.take_positional("new points file")?,
+        },
+        Some("combine") => Command::Combine {
+            left: options.take_positional("first results file")?,
+            right: options.take_positional("second results file")?,
+            operation: options.take_or("operation", SetOperation::Union)?,
+            output: options.take("output")?,
+        },
+        Some("compare") => Command::Compare {
+            old: options.take_positional("old points file")?,
+            new: options.take_positional("new points file")?,
+            angle: options.take_or("angle", 45)?,
+            radius: options.take_or("radius", 20)?,
+        },
+        Some("batch") => Command::Batch {
+            file: options.take_or("file", PathBuf::from(DEFAULT_POINTS_FILE))?,
+            queries: options.take("queries")?.ok_or_else(|| {
+                AppError::ArgumentError(String::from("missing required option `--queries`"))
+            })?,
+            output: options.take("output")?,
+            threads: options.take("threads")?,
+        },
+        Some("pipe") => Command::Pipe {
+            file: options.take_or("file", PathBuf::from(DEFAULT_POINTS_FILE))?,
+        },
+        #[cfg(feature = "stream")]
+        Some("monitor") => Command::Monitor {
+            query: options.query_options()?,
+            point: options.take_or("point", 1)?,
+            mqtt: options.take("mqtt")?,
+            topic: options.take_or("topic", String::from(DEFAULT_MQTT_TOPIC))?,
+        },
+        Some("serve") => Command::Serve {
+            file: options.take_or("file", PathBuf::from(DEFAULT_POINTS_FILE))?,
+            query: options.take("query")?,
+            redis: options.take("redis")?,
+            redis_key: options.take_or("redis-key", String::from(DEFAULT_REDIS_KEY))?,
+            bind: options.take_or("bind", String::from(DEFAULT_BIND_ADDRESS))?,
+            max_body_bytes: options.take_or("max-body-bytes", 1024 * 1024)?,
+            max_batch_queries: options.take_or("max-batch", 10_000)?,
+            requests_per_minute: options.take("rate-limit")?,
+            timeout_ms: options.take("timeout-ms")?,
+            max_connections: options.take_or("max-connections", 256)?,
+            connection_timeout_ms: options.take_or("connection-timeout-ms", 10_000)?,
+        },
+        Some(value) => {
+            return Err(AppError::ArgumentError(format!(
+                "unknown subcommand `{value}`"
+            )))
+        }
+    };
+    options.finish()?;
+    Ok(Arguments {
+        command,
+        verbose,
+        profile,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        parse_arguments, BearingBand, BoundingBox, Command, DuplicateStrategy, Facing,
+        HeatmapFormat, MatrixFormat, Origin, OriginCombination, PathLoss, QueryOptions,
+        SceneFormat, SetOperation, Sightline, SummaryFormat,
+    };
+    use std::path::{Path, PathBuf};
+
+    fn arguments(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn parse_arguments_gives_expected_defaults() {
+        // arrange
+        let line = "";
+
+        // act
+        let outcome = parse_arguments(arguments(line)).unwrap().command;
+
+        // assert
+        assert_eq!(
+            outcome,
+            Command::Visible {
+                query: QueryOptions {
+                    file: PathBuf::from("./points.json"),
+                    query: None,
+                    dbf_fields: None,
+                    bbox: None,
+                    angle: 45,
+                    radius: 20,
+                    progress: false,
+                    cache_points: false,
+                    dedup: None,
+                    face: None,
+                    downsample: None,
+                    downsample_map: None,
+                    max_age: None
+                },
+                point: 1,
+                id: None,
+                bearings: None,
+                origin: None,
+                dem: None,
+                path_loss: PathLoss::FreeSpace,
+                sightline: Sightline::default(),
+                occlusion: None,
+                vertical_angle: None,
+                three_d: false,
+                hull: false,
+                relative: false,
+                sweep: false
+            }
+        );
+
+        assert!(!parse_arguments(arguments(line)).unwrap().verbose);
+        assert!(!parse_arguments(arguments(line)).unwrap().profile);
+
+        // arrange
+        let line =
+            "--hull --point 3 --verbose --profile --cache-points --dedup last --face nearest --downsample 5 --downsample-map map.json --relative --sweep";
+
+        // act
+        let outcome = parse_arguments(arguments(line)).unwrap().command;
+
+        // assert
+        assert_eq!(
+            outcome,
+            Command::Visible {
+                query: QueryOptions {
+                    file: PathBuf::from("./points.json"),
+                    query: None,
+                    dbf_fields: None,
+                    bbox: None,
+                    angle: 45,
+                    radius: 20,
+                    progress: false,
+                    cache_points: true,
+                    dedup: Some(DuplicateStrategy::Last),
+                    face: Some(Facing::Nearest),
+                    downsample: Some(5),
+                    downsample_map: Some(PathBuf::from("map.json")),
+                    max_age: None
+                },
+                point: 3,
+                id: None,
+                bearings: None,
+                origin: None,
+                dem: None,
+                path_loss: PathLoss::FreeSpace,
+                sightline: Sightline::default(),
+                occlusion: None,
+                vertical_angle: None,
+                three_d: false,
+                hull: true,
+                relative: true,
+                sweep: true
+            }
+        );
+        assert!(parse_arguments(arguments(line)).unwrap().verbose);
+        assert!(parse_arguments(arguments(line)).unwrap().profile);
+        assert!(matches!(
+            parse_arguments(arguments("visible --id mast-7")).unwrap().command,
+            Command::Visible { id: Some(value), .. } if value == "mast-7"
+        ));
+        assert!(matches!(
+            parse_arguments(arguments("--bearings 300-30")).unwrap().command,
+            Command::Visible { bearings: Some(BearingBand { from, to }), .. } if (from, to) == (300.0, 30.0)
+        ));
+        assert!(matches!(
+            parse_arguments(arguments("--origin 10,-4.5,270")).unwrap().command,
+            Command::Visible { origin: Some(Origin { coordinates, heading }), .. } if (coordinates, heading) == ((10.0, -4.5), 270.0)
+        ));
+        assert!(matches!(
+            parse_arguments(arguments("--earth-curvature --observer-height 12.5")).unwrap().command,
+            Command::Visible { sightline: Sightline { observer_height, earth_curvature: true, refraction, .. }, .. } if (observer_height, refraction) == (12.5, 0.0)
+        ));
+        assert!(matches!(
+            parse_arguments(arguments("--earth-curvature --refraction 0.13")).unwrap().command,
+            Command::Visible { sightline: Sightline { refraction, .. }, .. } if refraction == 0.13
+        ));
+        assert!(matches!(
+            parse_arguments(arguments("--frequency 2400 --fresnel-clearance 0.6")).unwrap().command,
+            Command::Visible { sightline: Sightline { frequency: Some(frequency), fresnel_clearance, .. }, .. } if (frequency, fresnel_clearance) == (2400.0, 0.6)
+        ));
+        assert!(matches!(
+            parse_arguments(arguments("--frequency 868 --path-loss-exponent 3 --reference-distance 100")).unwrap().command,
+            Command::Visible { path_loss: PathLoss::LogDistance { exponent, reference_distance }, .. } if (exponent, reference_distance) == (3.0, 100.0)
+        ));
+        assert!(matches!(
+            parse_arguments(arguments("--occlusion 0.5")).unwrap().command,
+            Command::Visible { occlusion: Some(radius), .. } if radius == 0.5
+        ));
+        assert!(parse_arguments(arguments("--occlusion -1")).is_err());
+        assert!(parse_arguments(arguments("--downsample 0")).is_err());
+        assert!(parse_arguments(arguments("--downsample-map map.json")).is_err());
+        assert!(matches!(
+            parse_arguments(arguments("--vertical-angle 30")).unwrap().command,
+            Command::Visible { vertical_angle: Some(angle), .. } if angle == 30.0
+        ));
+        assert!(parse_arguments(arguments("--vertical-angle 120")).is_err());
+        assert!(parse_arguments(arguments("--vertical-angle 30 --cache-points")).is_err());
+        assert!(parse_arguments(arguments("--occlusion 0.5 --id gate")).is_err());
+        assert!(matches!(
+            parse_arguments(arguments("--3d --vertical-angle 15")).unwrap().command,
+            Command::Visible { three_d: true, vertical_angle: Some(angle), .. } if angle == 15.0
+        ));
+        assert!(parse_arguments(arguments("--3d --origin 0,0,90")).is_err());
+        assert!(parse_arguments(arguments("--path-loss-exponent 3")).is_err());
+        assert!(parse_arguments(arguments("--frequency 868 --reference-distance 100")).is_err());
+        assert!(parse_arguments(arguments("--fresnel-clearance 0.6")).is_err());
+        assert!(parse_arguments(arguments("--frequency 2400 --fresnel-clearance 1.5")).is_err());
+        assert!(parse_arguments(arguments("--frequency 0")).is_err());
+        assert!(parse_arguments(arguments("--refraction 0.13")).is_err());
+        assert!(parse_arguments(arguments("--earth-curvature --refraction 1")).is_err());
+        assert!(parse_arguments(arguments("--observer-height -2")).is_err());
+        assert!(matches!(
+            parse_arguments(arguments("--occlusion 0.5 --observer-height 4 --target-height 1.7")).unwrap().command,
+            Command::Visible { sightline: Sightline { observer_height, target_height, .. }, .. } if (observer_height, target_height) == (4.0, 1.7)
+        ));
+        assert!(parse_arguments(arguments("--target-height -1")).is_err());
+        assert!(matches!(
+            parse_arguments(arguments("--dem terrain.tif")).unwrap().command,
+            Command::Visible { dem: Some(path), .. } if path.as_path() == Path::new("terrain.tif")
+        ));
+        assert!(matches!(
+            parse_arguments(arguments("heatmap --max-age 300"))
+                .unwrap()
+                .command,
+            Command::Heatmap {
+                query: QueryOptions {
+                    max_age: Some(300),
+                    ..
+                },
+                ..
+            }
+        ));
+        assert!(matches!(
+            parse_arguments(arguments("--file parcels.shp --dbf-fields number=ID"))
+                .unwrap()
+                .command,
+            Command::Visible {
+                query: QueryOptions {
+                    dbf_fields: Some(ref fields),
+                    ..
+                },
+                ..
+            } if fields == "number=ID"
+        ));
+        assert!(parse_arguments(arguments("--dbf-fields number=ID --max-age 300")).is_err());
+        assert!(parse_arguments(arguments("--dbf-fields number=ID --cache-points")).is_err());
+        assert!(parse_arguments(arguments("--query SELECT --cache-points")).is_err());
+        assert!(matches!(
+            parse_arguments(arguments("--file roads.fgb --bbox 0,-10,50,40"))
+                .unwrap()
+                .command,
+            Command::Visible {
+                query: QueryOptions {
+                    bbox: Some(BoundingBox {
+                        min: (0, -10),
+                        max: (50, 40)
+                    }),
+                    ..
+                },
+                ..
+            }
+        ));
+        assert!(parse_arguments(arguments("--bbox 50,0,0,40")).is_err());
+    }
+
+    #[test]
+    fn parse_arguments_handles_subcommand_options() {
+        // arrange
+        let line =
+            "heatmap --file fixtures/valid_points.json --cell-size 5 --format pgm --progress";
+
+        // act
+        let outcome = parse_arguments(arguments(line)).unwrap().command;
+
+        // assert
+        assert_eq!(
+            outcome,
+            Command::Heatmap {
+                query: QueryOptions {
+                    file: PathBuf::from("fixtures/valid_points.json"),
+                    query: None,
+                    dbf_fields: None,
+                    bbox: None,
+                    angle: 45,
+                    radius: 20,
+                    progress: true,
+                    cache_points: false,
+                    dedup: None,
+                    face: None,
+                    downsample: None,
+                    downsample_map: None,
+                    max_age: None
+                },
+                cell_size: 5,
+                format: HeatmapFormat::Pgm,
+                output: None
+            }
+        );
+
+        // arrange
+        let line = "cover --points 3,9 --combine intersection --radius 30";
+
+        // act
+        let outcome = parse_arguments(arguments(line)).unwrap().command;
+
+        // assert
+        assert_eq!(
+            outcome,
+            Command::Cover {
+                query: QueryOptions {
+                    file: PathBuf::from("./points.json"),
+                    query: None,
+                    dbf_fields: None,
+                    bbox: None,
+                    angle: 45,
+                    radius: 30,
+                    progress: false,
+                    cache_points: false,
+                    dedup: None,
+                    face: None,
+                    downsample: None,
+                    downsample_map: None,
+                    max_age: None
+                },
+                points: vec![3, 9],
+                combination: OriginCombination::Intersection
+            }
+        );
+
+        assert!(matches!(
+            parse_arguments(arguments("gaps --origin 0,0,90 --angle 180"))
+                .unwrap()
+                .command,
+            Command::Gaps {
+                query: QueryOptions { angle: 180, .. },
+                point: 1,
+                origin: Some(Origin { heading, .. })
+            } if heading == 90.0
+        ));
+
+        assert!(matches!(
+            parse_arguments(arguments("coverage --buckets 12 --max-distance 7.5"))
+                .unwrap()
+                .command,
+            Command::Coverage {
+                buckets: Some(12),
+                max_distance: Some(value),
+                ..
+            } if value == 7.5
+        ));
+
+        assert!(matches!(
+            parse_arguments(arguments("scan --point 2"))
+                .unwrap()
+                .command,
+            Command::Scan {
+                point: 2,
+                origin: None,
+                buckets: 8,
+                ..
+            }
+        ));
+
+        assert!(matches!(
+            parse_arguments(arguments("histogram --bin-width 2.5 --output ranges.json"))
+                .unwrap()
+                .command,
+            Command::Histogram {
+                point: 1,
+                bin_width,
+                output: Some(_),
+                ..
+            } if bin_width == 2.5
+        ));
+
+        assert!(matches!(
+            parse_arguments(arguments("geojson --origin 1,2,90 --output-dir layers"))
+                .unwrap()
+                .command,
+            Command::GeoJson {
+                point: 1,
+                origin: Some(_),
+                ref output_directory,
+                ..
+            } if output_directory.as_path() == std::path::Path::new("layers")
+        ));
+        assert!(parse_arguments(arguments("geojson --point 2")).is_err());
+        assert!(matches!(
+            parse_arguments(arguments("kml --point 3 --output visible.kml"))
+                .unwrap()
+                .command,
+            Command::Kml {
+                point: 3,
+                origin: None,
+                output: Some(_),
+                ..
+            }
+        ));
+        assert!(matches!(
+            parse_arguments(arguments("scene --vertical-angle 20 --format obj"))
+                .unwrap()
+                .command,
+            Command::Scene {
+                vertical_angle: Some(angle),
+                format: SceneFormat::Obj,
+                output: None,
+                ..
+            } if angle == 20.0
+        ));
+        assert!(parse_arguments(arguments("scene --vertical-angle 120")).is_err());
+        assert!(matches!(
+            parse_arguments(arguments("bench-index --queries 200 --min-recall 0.95 --output report.json"))
+                .unwrap()
+                .command,
+            Command::BenchIndex {
+                queries: 200,
+                min_recall,
+                output: Some(_),
+                ..
+            } if min_recall == 0.95
+        ));
+        assert!(parse_arguments(arguments("bench-index --min-recall 2")).is_err());
+
+        assert!(matches!(
+            parse_arguments(arguments("summary --format json"))
+                .unwrap()
+                .command,
+            Command::Summary {
+                format: SummaryFormat::Json,
+                output: None,
+                ..
+            }
+        ));
+        assert!(parse_arguments(arguments("summary --format xml")).is_err());
+
+        // arrange
+        let line = "matrix --format bitset --output matrix.bin --angle 90 --cache-dir .cache";
+
+        // act
+        let outcome = parse_arguments(arguments(line)).unwrap().command;
+
+        // assert
+        assert_eq!(
+            outcome,
+            Command::Matrix {
+                query: QueryOptions {
+                    file: PathBuf::from("./points.json"),
+                    query: None,
+                    dbf_fields: None,
+                    bbox: None,
+                    angle: 90,
+                    radius: 20,
+                    progress: false,
+                    cache_points: false,
+                    dedup: None,
+                    face: None,
+                    downsample: None,
+                    downsample_map: None,
+                    max_age: None
+                },
+                format: MatrixFormat::Bitset,
+                output: Some(PathBuf::from("matrix.bin")),
+                cache_directory: Some(PathBuf::from(".cache"))
+            }
+        );
+
+        // arrange
+        let line = "all-pairs --file huge.ndjson --tile-size 50 --tile-dir tiles";
+
+        // act
+        let outcome = parse_arguments(arguments(line)).unwrap().command;
+
+        // assert
+        assert_eq!(
+            outcome,
+            Command::AllPairs {
+                file: PathBuf::from("huge.ndjson"),
+                angle: 45,
+                radius: 20,
+                tile_size: Some(50),
+                tile_directory: Some(PathBuf::from("tiles")),
+                output: None
+            }
+        );
+
+        // arrange
+        let line = "diff old.json new.json";
+
+        // act
+        let outcome = parse_arguments(arguments(line)).unwrap().command;
+
+        // assert
+        assert_eq!(
+            outcome,
+            Command::Diff {
+                old: PathBuf::from("old.json"),
+                new: PathBuf::from("new.json")
+            }
+        );
+
+        // arrange
+        let line = "combine monday.jsonl tuesday.jsonl --operation difference";
+
+        // act
+        let outcome = parse_arguments(arguments(line)).unwrap().command;
+
+        // assert
+        assert_eq!(
+            outcome,
+            Command::Combine {
+                left: PathBuf::from("monday.jsonl"),
+                right: PathBuf::from("tuesday.jsonl"),
+                operation: SetOperation::Difference,
+                output: None
+            }
+        );
+
+        // arrange
+        let line = "graph --graph saved.graph --point 4 --hops 3";
+
+        // act
+        let outcome = parse_arguments(arguments(line)).unwrap().command;
+
+        // assert
+        assert_eq!(
+            outcome,
+            Command::Graph {
+                graph: PathBuf::from("saved.graph"),
+                point: 4,
+                to: None,
+                hops: Some(3)
+            }
+        );
+        assert!(matches!(
+            parse_arguments(arguments("audit --radius 50"))
+                .unwrap()
+                .command,
+            Command::Audit {
+                query: QueryOptions { radius: 50, .. }
+            }
+        ));
+        assert!(matches!(
+            parse_arguments(arguments("optimise --point 3 --k 5"))
+                .unwrap()
+                .command,
+            Command::Optimise {
+                point: 3,
+                k: Some(5),
+                ..
+            }
+        ));
+        assert!(matches!(
+            parse_arguments(arguments("optimise --weight-field population"))
+                .unwrap()
+                .command,
+            Command::Optimise {
+                k: None,
+                weight_field: Some(ref field),
+                ..
+            } if field == "population"
+        ));
+        assert!(parse_arguments(arguments("optimise --k 5 --weight-field population")).is_err());
+        assert!(matches!(
+            parse_arguments(arguments("reach --point 4"))
+                .unwrap()
+                .command,
+            Command::Reach {
+                point: 4,
+                hops: 2,
+                ..
+            }
+        ));
+        assert_eq!(
+            parse_arguments(arguments(
+                "simulate --steps 30 --step-seconds 0.5 --frames-dir frames"
+            ))
+            .unwrap()
+            .command,
+            Command::Simulate {
+                file: PathBuf::from("./points.json"),
+                angle: 45,
+                radius: 20,
+                steps: 30,
+                step_seconds: 0.5,
+                frames_directory: Some(PathBuf::from("frames"))
+            }
+        );
+        assert_eq!(
+            parse_arguments(arguments("contacts --point 7 --angle 60"))
+                .unwrap()
+                .command,
+            Command::Contacts {
+                file: PathBuf::from("./points.json"),
+                point: 7,
+                angle: 60,
+                radius: 20
+            }
+        );
+
+        // arrange
+        let line = "compare old.json new.json --radius 30";
+
+        // act
+        let outcome = parse_arguments(arguments(line)).unwrap().command;
+
+        // assert
+        assert_eq!(
+            outcome,
+            Command::Compare {
+                old: PathBuf::from("old.json"),
+                new: PathBuf::from("new.json"),
+                angle: 45,
+                radius: 30
+            }
+        );
+
+        // arrange
+        let line = "batch --queries queries.csv --output results.jsonl --threads 8";
+
+        // act
+        let outcome = parse_arguments(arguments(line)).unwrap().command;
+
+        // assert
+        assert_eq!(
+            outcome,
+            Command::Batch {
+                file: PathBuf::from("./points.json"),
+                queries: PathBuf::from("queries.csv"),
+                output: Some(PathBuf::from("results.jsonl")),
+                threads: Some(8)
+            }
+        );
+
+        // arrange
+        let line = "serve --bind 0.0.0.0:9000 --max-batch 100 --rate-limit 60 --timeout-ms 500 --max-connections 32";
+
+        // act
+        let outcome = parse_arguments(arguments(line)).unwrap().command;
+
+        // assert
+        assert_eq!(
+            outcome,
+            Command::Serve {
+                file: PathBuf::from("./points.json"),
+                query: None,
+                redis: None,
+                redis_key: String::from("neighbours:points"),
+                bind: String::from("0.0.0.0:9000"),
+                max_body_bytes: 1024 * 1024,
+                max_batch_queries: 100,
+                requests_per_minute: Some(60),
+                timeout_ms: Some(500),
+                max_connections: 32,
+                connection_timeout_ms: 10_000
+            }
+        );
+
+        // arrange
+        let line = "serve --redis redis://localhost:6379/2 --redis-key survey:live";
+
+        // act
+        let outcome = parse_arguments(arguments(line)).unwrap().command;
+
+        // assert
+        match outcome {
+            Command::Serve {
+                redis, redis_key, ..
+            } => {
+                assert_eq!(redis.as_deref(), Some("redis://localhost:6379/2"));
+                assert_eq!(redis_key, "survey:live");
+            }
+            _ => panic!("expected serve command"),
+        }
+    }
+
+    #[cfg(feature = "animation")]
+    #[test]
+    fn parse_arguments_handles_animate() {
+        // arrange
+        let line = "animate --file fleet.json --steps 40 --output run.mp4 --fps 25";
+
+        // act
+        let outcome = parse_arguments(arguments(line)).unwrap().command;
+
+        // assert
+        assert_eq!(
+            outcome,
+            Command::Animate {
+                file: PathBuf::from("fleet.json"),
+                angle: 45,
+                radius: 20,
+                steps: 40,
+                step_seconds: 1.0,
+                output: PathBuf::from("run.mp4"),
+                size: 400,
+                frames_per_second: 25
+            }
+        );
+        assert!(parse_arguments(arguments("animate --steps 40")).is_err());
+    }
+
+    #[cfg(feature = "stream")]
+    #[test]
+    fn parse_arguments_handles_monitor() {
+        // arrange
+        let line = "monitor --file fleet.json --point 4 --radius 50";
+
+        // act
+        let outcome = parse_arguments(arguments(line)).unwrap().command;
+
+        // assert
+        assert_eq!(
+            outcome,
+            Command::Monitor {
+                query: QueryOptions {
+                    file: PathBuf::from("fleet.json"),
+                    query: None,
+                    dbf_fields: None,
+                    bbox: None,
+                    angle: 45,
+                    radius: 50,
+                    progress: false,
+                    cache_points: false,
+                    dedup: None,
+                    face: None,
+                    downsample: None,
+                    downsample_map: None,
+                    max_age: None
+                },
+                point: 4,
+                mqtt: None,
+                topic: String::from("neighbours/positions")
+            }
+        );
+
+        // arrange
+        let line = "monitor --mqtt mqtt://broker.local --topic trackers/+/position";
+
+        // act
+        let outcome = parse_arguments(arguments(line)).unwrap().command;
+
+        // assert
+        match outcome {
+            Command::Monitor { mqtt, topic, .. } => {
+                assert_eq!(mqtt.as_deref(), Some("mqtt://broker.local"));
+                assert_eq!(topic, "trackers/+/position");
+            }
+            _ => panic!("expected monitor command"),
+        }
+    }
+
+    #[test]
+    fn parse_arguments_handles_sqlite_query() {
+        // arrange
+        let line = vec![
+            String::from("--file"),
+            String::from("survey.sqlite"),
+            String::from("--query"),
+            String::from("SELECT point AS number, x, y, direction FROM survey"),
+        ];
+
+        // act
+        let outcome = parse_arguments(line).unwrap().command;
+
+        // assert
+        assert_eq!(
+            outcome,
+            Command::Visible {
+                query: QueryOptions {
+                    file: PathBuf::from("survey.sqlite"),
+                    query: Some(String::from(
+                        "SELECT point AS number, x, y, direction FROM survey"
+                    )),
+                    dbf_fields: None,
+                    bbox: None,
+                    angle: 45,
+                    radius: 20,
+                    progress: false,
+                    cache_points: false,
+                    dedup: None,
+                    face: None,
+                    downsample: None,
+                    downsample_map: None,
+                    max_age: None
+                },
+                point: 1,
+                id: None,
+                bearings: None,
+                origin: None,
+                dem: None,
+                path_loss: PathLoss::FreeSpace,
+                sightline: Sightline::default(),
+                occlusion: None,
+                vertical_angle: None,
+                three_d: false,
+                hull: false,
+                relative: false,
+                sweep: false
+            }
+        );
+    }
+
+    #[test]
+    fn parse_arguments_handles_invalid_input() {
+        // arrange
+        let lines = [
+            "visible --point",
+            "visible --point one",
+            "visible --cell-size 5",
+            "heatmap --hull",
+            "--max-age 60 --cache-points",
+            "--3d --dedup exact",
+            "scene --cache-points",
+            "--id gate --max-age 60",
+            "graph --point 2",
+            "cover --points 3,nine",
+            "diff old.json",
+            "visible extra.json",
+            "unknown",
+        ];
+
+        // act
+        let outcome: Vec<String> = lines
+            .iter()
+            .map(|line| parse_arguments(arguments(line)).unwrap_err().to_string())
+            .collect();
+
+        // assert
+        assert_eq!(
+            outcome,
+            vec![
+                "Invalid command line arguments: missing value for `--point`",
+                "Invalid command line arguments: invalid value for `--point`: invalid digit found in string",
+                "Invalid command line arguments: unknown option `--cell-size`",
+                "Invalid command line arguments: unknown option `--hull`",
+                "Invalid command line arguments: `--max-age` needs observation times, which `--query` and `--cache-points` do not keep",
+                "Invalid command line arguments: `--3d` reads each point's `z` and orientation from its record, so cannot be combined with `--query`, `--dbf-fields`, `--cache-points`, `--dedup`, `--downsample` or `--face`",
+                "Invalid command line arguments: `scene` reads each point's `z` and orientation from its record, so cannot be combined with `--query`, `--dbf-fields`, `--cache-points`, `--dedup`, `--downsample` or `--face`",
+                "Invalid command line arguments: `--id` reads points numbered by their place in the file, so cannot be combined with `--query`, `--dbf-fields`, `--cache-points`, `--max-age`, `--dedup` or `--downsample`",
+                "Invalid command line arguments: missing required option `--graph`",
+                "Invalid command line arguments: invalid value for `--points`: invalid point number `nine`",
+                "Invalid command line arguments: missing new points file argument",
+                "Invalid command line arguments: unexpected argument `extra.json`",
+                "Invalid command line arguments: unknown subcommand `unknown`",
+            ]
+        );
+    }
+}
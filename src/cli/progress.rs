@@ -0,0 +1,83 @@
+use std::io::{self, Write};
+
+/// Width of the bar, in characters, excluding the label and percentage
+const BAR_WIDTH: usize = 30;
+
+/// Progress bar drawn on standard error, redrawn only when the whole
+/// percentage changes.  A hidden bar draws nothing, so callers can pass one
+/// whether or not progress was requested.
+pub struct ProgressBar {
+    label: &'static str,
+    visible: bool,
+    percent: Option<usize>,
+}
+
+impl ProgressBar {
+    pub fn new(label: &'static str, visible: bool) -> Self {
+        ProgressBar {
+            label,
+            visible,
+            percent: None,
+        }
+    }
+
+    /// Text of the bar, `done` out of `total` complete
+    fn render(label: &str, done: usize, total: usize) -> String {
+        let fraction = match total {
+            0 => 1.0,
+            _ => done.min(total) as f64 / total as f64,
+        };
+        let filled = (fraction * BAR_WIDTH as f64).round() as usize;
+        format!(
+            "{label} [{}{}] {:>3}%",
+            "#".repeat(filled),
+            " ".repeat(BAR_WIDTH - filled),
+            (fraction * 100.0).floor() as usize
+        )
+    }
+
+    /// Redraw the bar with `done` out of `total` complete, finishing the line
+    /// once all work is done
+    pub fn update(&mut self, done: usize, total: usize) {
+        if !self.visible {
+            return;
+        }
+        let percent = match total {
+            0 => 100,
+            _ => done.min(total) * 100 / total,
+        };
+        if self.percent == Some(percent) {
+            return;
+        }
+        self.percent = Some(percent);
+        let mut stderr = io::stderr();
+        let ending = if percent == 100 { "\n" } else { "" };
+        // progress is best effort, so write errors are ignored
+        let _ = write!(
+            stderr,
+            "\r{}{ending}",
+            Self::render(self.label, done, total)
+        );
+        let _ = stderr.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ProgressBar;
+
+    #[test]
+    fn progress_bar_renders_expected_text() {
+        // arrange
+        let partial = (1, 3);
+        let empty = (0, 0);
+
+        // act
+        let partial = ProgressBar::render("Parsing", partial.0, partial.1);
+        let empty = ProgressBar::render("Parsing", empty.0, empty.1);
+
+        // assert
+        assert_eq!(partial, "Parsing [##########                    ]  33%");
+        assert_eq!(empty, "Parsing [##############################] 100%");
+    }
+}
@@ -0,0 +1,196 @@
+//! Minimal GIF89a encoder, writing a looping animation of [`Raster`]s with a
+//! four colour global palette and LZW compressed image data.
+
+use super::{Raster, PALETTE};
+
+/// Bits in each palette index, and so the smallest LZW code size
+const MIN_CODE_SIZE: u8 = 2;
+
+/// Largest LZW code, after which the code table is cleared
+const MAX_CODE: u16 = 4095;
+
+/// Packs variable width codes into bytes, least significant bit first
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    bits: u8,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        BitWriter {
+            bytes: vec![],
+            buffer: 0,
+            bits: 0,
+        }
+    }
+
+    fn write(&mut self, code: u16, size: u8) {
+        self.buffer |= u32::from(code) << self.bits;
+        self.bits += size;
+        while self.bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+/// LZW compress palette `indices`, as GIF image data expects
+fn lzw_encode(indices: &[u8]) -> Vec<u8> {
+    let clear_code: u16 = 1 << MIN_CODE_SIZE;
+    let end_code = clear_code + 1;
+    let mut writer = BitWriter::new();
+    let mut code_size = MIN_CODE_SIZE + 1;
+    let mut next_code = end_code + 1;
+    let mut table: std::collections::HashMap<(u16, u8), u16> = std::collections::HashMap::new();
+    writer.write(clear_code, code_size);
+    let (first, rest) = match indices.split_first() {
+        Some(value) => value,
+        None => {
+            writer.write(end_code, code_size);
+            return writer.finish();
+        }
+    };
+    let mut current = u16::from(*first);
+    for index in rest {
+        let key = (current, *index);
+        if let Some(code) = table.get(&key) {
+            current = *code;
+            continue;
+        }
+        writer.write(current, code_size);
+        if next_code > MAX_CODE {
+            writer.write(clear_code, code_size);
+            table.clear();
+            code_size = MIN_CODE_SIZE + 1;
+            next_code = end_code + 1;
+        } else {
+            if next_code >= 1 << code_size {
+                code_size += 1;
+            }
+            table.insert(key, next_code);
+            next_code += 1;
+        }
+        current = u16::from(*index);
+    }
+    writer.write(current, code_size);
+    writer.write(end_code, code_size);
+    writer.finish()
+}
+
+/// Encode `frames`, which should share one size, as a GIF animation looping
+/// forever, showing each frame for `delay` hundredths of a second
+pub fn encode_gif(frames: &[Raster], delay: u16) -> Vec<u8> {
+    let (width, height) = frames
+        .first()
+        .map_or((0, 0), |frame| (frame.width as u16, frame.height as u16));
+    let mut result = b"GIF89a".to_vec();
+    result.extend_from_slice(&width.to_le_bytes());
+    result.extend_from_slice(&height.to_le_bytes());
+    // global colour table of 2 ^ (1 + 1) colours, with 2 bit colour resolution
+    result.extend_from_slice(&[0x91, 0, 0]);
+    for colour in &PALETTE {
+        result.extend_from_slice(colour);
+    }
+    result.extend_from_slice(&[0x21, 0xff, 0x0b]);
+    result.extend_from_slice(b"NETSCAPE2.0");
+    result.extend_from_slice(&[0x03, 0x01, 0x00, 0x00, 0x00]);
+    for frame in frames {
+        result.extend_from_slice(&[0x21, 0xf9, 0x04, 0x00]);
+        result.extend_from_slice(&delay.to_le_bytes());
+        result.extend_from_slice(&[0x00, 0x00]);
+        result.extend_from_slice(&[0x2c, 0, 0, 0, 0]);
+        result.extend_from_slice(&(frame.width as u16).to_le_bytes());
+        result.extend_from_slice(&(frame.height as u16).to_le_bytes());
+        result.push(0x00);
+        result.push(MIN_CODE_SIZE);
+        for block in lzw_encode(&frame.pixels).chunks(255) {
+            result.push(block.len() as u8);
+            result.extend_from_slice(block);
+        }
+        result.push(0x00);
+    }
+    result.push(0x3b);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{lzw_encode, MIN_CODE_SIZE};
+
+    /// Reference LZW decoder, following the GIF specification
+    fn lzw_decode(bytes: &[u8]) -> Vec<u8> {
+        let clear_code: usize = 1 << MIN_CODE_SIZE;
+        let end_code = clear_code + 1;
+        let mut table: Vec<Vec<u8>> = vec![];
+        let mut code_size = 0;
+        let (mut buffer, mut bits, mut position) = (0_u32, 0, 0);
+        let mut previous: Option<usize> = None;
+        let mut result = vec![];
+        loop {
+            while bits < code_size.max(usize::from(MIN_CODE_SIZE) + 1) {
+                buffer |= u32::from(bytes[position]) << bits;
+                position += 1;
+                bits += 8;
+            }
+            let size = code_size.max(usize::from(MIN_CODE_SIZE) + 1);
+            let code = (buffer & ((1 << size) - 1)) as usize;
+            buffer >>= size;
+            bits -= size;
+            if code == clear_code {
+                table = (0..clear_code).map(|value| vec![value as u8]).collect();
+                table.push(vec![]);
+                table.push(vec![]);
+                code_size = usize::from(MIN_CODE_SIZE) + 1;
+                previous = None;
+                continue;
+            }
+            if code == end_code {
+                return result;
+            }
+            let entry = match (code < table.len(), previous) {
+                (true, _) => table[code].clone(),
+                (false, Some(last)) => {
+                    let mut value = table[last].clone();
+                    value.push(table[last][0]);
+                    value
+                }
+                (false, None) => panic!("invalid code"),
+            };
+            if let Some(last) = previous {
+                let mut value = table[last].clone();
+                value.push(entry[0]);
+                table.push(value);
+                if table.len() == 1 << code_size && code_size < 12 {
+                    code_size += 1;
+                }
+            }
+            result.extend_from_slice(&entry);
+            previous = Some(code);
+        }
+    }
+
+    #[test]
+    fn lzw_encode_round_trips() {
+        // arrange
+        let indices: Vec<u8> = (0..20_000_u32)
+            .map(|value| ((value * value / 7 + value / 3) % 4) as u8)
+            .collect();
+
+        // act
+        let outcome = lzw_decode(&lzw_encode(&indices));
+
+        // assert
+        assert_eq!(outcome, indices);
+        assert_eq!(lzw_decode(&lzw_encode(&[])), Vec::<u8>::new());
+        assert_eq!(lzw_decode(&lzw_encode(&[3; 500])), vec![3; 500]);
+    }
+}
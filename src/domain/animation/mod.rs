@@ -0,0 +1,226 @@
+//! Animations of simulation runs, enabled by the `animation` feature.
+//! [`Frame`]s are drawn as [`Raster`] images, showing points and the sightlines
+//! between them, then stitched into a GIF with the built in encoder, or an MP4
+//! by running `ffmpeg`.
+
+mod gif;
+
+pub use gif::encode_gif;
+
+use crate::{
+    domain::{geometry::BoundingBox, simulation::Frame},
+    utilities::{AppError, Span},
+};
+use std::{fs, path::Path, process};
+
+/// Colours of [`Raster`] palette indices, as RGB: background, sightline,
+/// point, and the outline of a point seeing nothing
+pub const PALETTE: [[u8; 3]; 4] = [
+    [255, 255, 255],
+    [150, 190, 230],
+    [20, 20, 20],
+    [200, 60, 60],
+];
+
+const BACKGROUND: u8 = 0;
+const SIGHTLINE: u8 = 1;
+const POINT: u8 = 2;
+const UNSIGHTED: u8 = 3;
+
+/// Image of palette indices, row by row from the top, running left to right
+/// within a row
+#[derive(Clone, Debug, PartialEq)]
+pub struct Raster {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>,
+}
+
+impl Raster {
+    fn new(width: usize, height: usize) -> Self {
+        Raster {
+            width,
+            height,
+            pixels: vec![BACKGROUND; width * height],
+        }
+    }
+
+    fn set(&mut self, (column, row): (i64, i64), colour: u8) {
+        if column >= 0 && row >= 0 && (column as usize) < self.width && (row as usize) < self.height
+        {
+            self.pixels[row as usize * self.width + column as usize] = colour;
+        }
+    }
+
+    /// Draw a straight line from `start` to `end`, with Bresenham’s algorithm
+    fn line(&mut self, start: (i64, i64), end: (i64, i64), colour: u8) {
+        let (mut column, mut row) = start;
+        let delta_column = (end.0 - column).abs();
+        let delta_row = -(end.1 - row).abs();
+        let step_column = if column < end.0 { 1 } else { -1 };
+        let step_row = if row < end.1 { 1 } else { -1 };
+        let mut error = delta_column + delta_row;
+        loop {
+            self.set((column, row), colour);
+            if (column, row) == end {
+                return;
+            }
+            let doubled = 2 * error;
+            if doubled >= delta_row {
+                error += delta_row;
+                column += step_column;
+            }
+            if doubled <= delta_column {
+                error += delta_column;
+                row += step_row;
+            }
+        }
+    }
+
+    /// Image as a binary colour (PPM) image, as `ffmpeg` and most image tools
+    /// read
+    pub fn to_ppm(&self) -> Vec<u8> {
+        let mut result = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        for pixel in &self.pixels {
+            result.extend_from_slice(&PALETTE[usize::from(*pixel)]);
+        }
+        result
+    }
+}
+
+/// Draw each of `frames` as a square image `size` pixels wide, showing points
+/// as dots, joined by sightlines to the points they see.  Every frame shares
+/// one view, fitting all points of every frame, so points appear to move.
+/// Points seeing nothing are drawn in red.
+pub fn render_frames(frames: &[Frame], size: usize) -> Vec<Raster> {
+    let _span = Span::enter("render_frames", || format!("{} frames", frames.len()));
+    let size = size.max(8);
+    let view = match BoundingBox::of_points(frames.iter().flat_map(|frame| &frame.points)) {
+        Some(value) => value,
+        None => return frames.iter().map(|_| Raster::new(size, size)).collect(),
+    };
+    let margin = 4.0;
+    let span = f64::from(
+        (view.max.0 - view.min.0)
+            .max(view.max.1 - view.min.1)
+            .max(1),
+    );
+    let scale = (size as f64 - 1.0 - 2.0 * margin) / span;
+    let pixel = |(x, y): (i32, i32)| {
+        (
+            (margin + f64::from(x - view.min.0) * scale).round() as i64,
+            (size as f64 - 1.0 - margin - f64::from(y - view.min.1) * scale).round() as i64,
+        )
+    };
+    frames
+        .iter()
+        .map(|frame| {
+            let mut raster = Raster::new(size, size);
+            let position = |number: u32| {
+                frame
+                    .points
+                    .binary_search_by_key(&number, |point| point.number)
+                    .ok()
+                    .map(|index| pixel(frame.points[index].coordinates))
+            };
+            for set in &frame.visible {
+                if let Some(start) = position(set.point_number) {
+                    for end in set.visible.iter().filter_map(|number| position(*number)) {
+                        raster.line(start, end, SIGHTLINE);
+                    }
+                }
+            }
+            for set in &frame.visible {
+                if let Some((column, row)) = position(set.point_number) {
+                    let colour = if set.visible.is_empty() {
+                        UNSIGHTED
+                    } else {
+                        POINT
+                    };
+                    for offset_column in -1..=1 {
+                        for offset_row in -1..=1 {
+                            raster.set((column + offset_column, row + offset_row), colour);
+                        }
+                    }
+                }
+            }
+            raster
+        })
+        .collect()
+}
+
+/// Encode `frames` as an MP4 video at `output`, shown at `frames_per_second`,
+/// by writing them as images to a temporary directory, then running `ffmpeg`,
+/// which must be on the `PATH`
+pub fn encode_mp4(
+    frames: &[Raster],
+    frames_per_second: u32,
+    output: &Path,
+) -> Result<(), AppError> {
+    let directory = std::env::temp_dir().join(format!("neighbours_frames_{}", std::process::id()));
+    let output_error = |path: &Path, error| AppError::OutputError {
+        path: path.display().to_string(),
+        source: error,
+    };
+    fs::create_dir_all(&directory).map_err(|error| output_error(&directory, error))?;
+    for (index, frame) in frames.iter().enumerate() {
+        let path = directory.join(format!("frame-{index:05}.ppm"));
+        fs::write(&path, frame.to_ppm()).map_err(|error| output_error(&path, error))?;
+    }
+    let status = process::Command::new("ffmpeg")
+        .args(["-loglevel", "error", "-y", "-framerate"])
+        .arg(frames_per_second.max(1).to_string())
+        .arg("-i")
+        .arg(directory.join("frame-%05d.ppm"))
+        .args([
+            "-pix_fmt",
+            "yuv420p",
+            "-vf",
+            "pad=ceil(iw/2)*2:ceil(ih/2)*2",
+        ])
+        .arg(output)
+        .status();
+    fs::remove_dir_all(&directory).ok();
+    match status {
+        Ok(value) if value.success() => Ok(()),
+        Ok(value) => Err(AppError::AnimationError(format!(
+            "`ffmpeg` failed, {value}"
+        ))),
+        Err(error) => Err(AppError::AnimationError(format!(
+            "could not run `ffmpeg`, {error}"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encode_gif, render_frames, POINT, SIGHTLINE, UNSIGHTED};
+    use crate::domain::{parse_moving_points, simulation_frame};
+
+    #[test]
+    fn render_frames_draws_points_and_sightlines() {
+        // arrange
+        let points = parse_moving_points(
+            r#"{"points": [
+                {"x": 0, "y": 0, "number": 1, "direction": "East"},
+                {"x": 10, "y": 0, "number": 2, "direction": "North"}
+            ]}"#,
+        )
+        .unwrap();
+        let frames = vec![simulation_frame(0, 1.0, 45, 20, &points)];
+
+        // act
+        let outcome = render_frames(&frames, 20);
+
+        // assert
+        let raster = &outcome[0];
+        let row = &raster.pixels[15 * 20..16 * 20];
+        assert_eq!(row[4], POINT);
+        assert_eq!(row[10], SIGHTLINE);
+        assert_eq!(row[15], UNSIGHTED);
+        assert_eq!(&raster.to_ppm()[..12], b"P6\n20 20\n255");
+        let gif = encode_gif(&outcome, 10);
+        assert_eq!(&gif[..10], b"GIF89a\x14\x00\x14\x00");
+        assert_eq!(gif.last(), Some(&0x3b));
+    }
+}
@@ -0,0 +1,198 @@
+use crate::{
+    domain::{metric::Metric, point::Point, spatial_index::SpatialIndex},
+    utilities::Span,
+};
+use std::cmp::Ordering;
+
+/// Node of a [`BallTree`], splitting the points below it by distance from
+/// its vantage point
+#[derive(Clone, Debug, PartialEq)]
+struct Node {
+    /// Index of the vantage point, in the tree's points
+    point: usize,
+
+    /// Median distance from the vantage point of the points below it: those
+    /// no further lie inside, and the rest outside
+    radius: f64,
+
+    inside: Option<usize>,
+    outside: Option<usize>,
+}
+
+/// Ball tree, built as a vantage point tree, answering range queries under
+/// any [`Metric`], such as [`Haversine`](crate::domain::Haversine) distances
+/// or a custom cost, where k-d trees and R-trees, which split along axes, do
+/// not apply.  Queries are exact, whatever the metric, provided it obeys the
+/// triangle inequality.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BallTree<M> {
+    points: Vec<Point>,
+    nodes: Vec<Node>,
+    root: Option<usize>,
+    metric: M,
+}
+
+impl<M: Metric> BallTree<M> {
+    /// Build the tree over `points`, which it keeps a copy of, measuring
+    /// distances with `metric`
+    pub fn new(points: &[Point], metric: M) -> Self {
+        let mut result = BallTree {
+            points: vec![],
+            nodes: vec![],
+            root: None,
+            metric,
+        };
+        result.rebuild(points);
+        result
+    }
+
+    /// Number of points in the tree
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    pub fn metric(&self) -> &M {
+        &self.metric
+    }
+
+    /// Build the subtree over the points at `indices`, taking the first as
+    /// its vantage point, and returning the index of its root node
+    fn build(&mut self, indices: &mut [usize]) -> Option<usize> {
+        let (vantage, rest) = indices.split_first_mut()?;
+        let vantage = *vantage;
+        let from = self.points[vantage].coordinates;
+        let mut radius = 0.0;
+        let mut split = 0;
+        if !rest.is_empty() {
+            let (points, metric) = (&self.points, &self.metric);
+            let distance = |index: &usize| metric.distance(from, points[*index].coordinates);
+            let middle = (rest.len() - 1) / 2;
+            rest.select_nth_unstable_by(middle, |a, b| {
+                distance(a)
+                    .partial_cmp(&distance(b))
+                    .unwrap_or(Ordering::Equal)
+            });
+            radius = distance(&rest[middle]);
+            split = middle + 1;
+        }
+        let node = self.nodes.len();
+        self.nodes.push(Node {
+            point: vantage,
+            radius,
+            inside: None,
+            outside: None,
+        });
+        let (inside, outside) = rest.split_at_mut(split);
+        let inside = self.build(inside);
+        let outside = self.build(outside);
+        self.nodes[node].inside = inside;
+        self.nodes[node].outside = outside;
+        Some(node)
+    }
+
+    /// Points closer than `radius` to `centre`, under the tree's metric, in
+    /// no particular order
+    pub fn within(&self, centre: (i32, i32), radius: f64) -> Vec<&Point> {
+        let mut result = vec![];
+        let mut pending: Vec<usize> = self.root.into_iter().collect();
+        while let Some(node) = pending.pop() {
+            let Node {
+                point,
+                radius: split,
+                inside,
+                outside,
+            } = self.nodes[node];
+            let distance = self.metric.distance(centre, self.points[point].coordinates);
+            if distance < radius {
+                result.push(&self.points[point]);
+            }
+
+            // by the triangle inequality, each side holds no point in range
+            // unless the range reaches across the split
+            if let Some(inside) = inside {
+                if distance - radius <= split {
+                    pending.push(inside);
+                }
+            }
+            if let Some(outside) = outside {
+                if distance + radius >= split {
+                    pending.push(outside);
+                }
+            }
+        }
+        result
+    }
+}
+
+impl<M: Metric> SpatialIndex for BallTree<M> {
+    fn range_query<'a>(
+        &'a self,
+        center: (i32, i32),
+        radius: f64,
+    ) -> Box<dyn Iterator<Item = &'a Point> + 'a> {
+        Box::new(self.within(center, radius).into_iter())
+    }
+
+    fn rebuild(&mut self, points: &[Point]) {
+        let _span = Span::enter("ball_tree", || format!("{} points", points.len()));
+        self.points = points.to_vec();
+        self.nodes = Vec::with_capacity(points.len());
+        let mut indices: Vec<usize> = (0..points.len()).collect();
+        self.root = self.build(&mut indices);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BallTree;
+    use crate::domain::{Direction, Haversine, Metric, Point};
+
+    #[test]
+    fn ball_tree_finds_points_in_range_under_any_metric() {
+        // arrange
+        let points: Vec<Point> = (0..300)
+            .map(|index| Point {
+                coordinates: (((index * 37) % 101) - 50, ((index * 59) % 103) - 51),
+                number: index as u32 + 1,
+                direction: Direction::North,
+            })
+            .collect();
+        let manhattan = |(x_1, y_1): (i32, i32), (x_2, y_2): (i32, i32)| {
+            f64::from((x_2 - x_1).abs() + (y_2 - y_1).abs())
+        };
+
+        // act
+        let tree = BallTree::new(&points, manhattan);
+
+        // assert
+        for (centre, radius) in [((0, 0), 12.0), ((40, -40), 25.0), ((7, 3), 0.5)] {
+            let mut outcome: Vec<u32> = tree
+                .within(centre, radius)
+                .into_iter()
+                .map(|point| point.number)
+                .collect();
+            outcome.sort_unstable();
+            let expected: Vec<u32> = points
+                .iter()
+                .filter(|point| manhattan.distance(centre, point.coordinates) < radius)
+                .map(|point| point.number)
+                .collect();
+            assert_eq!(outcome, expected);
+        }
+        let haversine = Haversine {
+            units_per_degree: 1_000.0,
+        };
+        assert!((haversine.distance((0, 0), (1_000, 0)) - 111_194.9).abs() < 0.1);
+        let tree = BallTree::new(&points, haversine);
+        let nearby = points
+            .iter()
+            .filter(|point| haversine.distance((0, 0), point.coordinates) < 3_000.0)
+            .count();
+        assert_eq!(tree.within((0, 0), 3_000.0).len(), nearby);
+        assert!(BallTree::new(&[], haversine).within((0, 0), 1.0).is_empty());
+    }
+}
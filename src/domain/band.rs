@@ -0,0 +1,148 @@
+use crate::{
+    domain::point::{angular_position, euclidean_distance, inside_sector, Point},
+    utilities::Span,
+};
+use std::str::FromStr;
+
+/// Range of bearings, in degrees clockwise from north, sweeping clockwise
+/// from `from` to `to`, whichever way points face.  `300` to `30` covers
+/// north-west round to north-east, through north, while `0` to `360` covers
+/// every bearing.  Both ends are inside the band.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BearingBand {
+    pub from: f64,
+    pub to: f64,
+}
+
+impl BearingBand {
+    /// Degrees swept clockwise from `from` to `to`, from `0` up to `360`
+    pub fn width(&self) -> f64 {
+        match (self.to - self.from).rem_euclid(360.0) {
+            val if val == 0.0 && self.to != self.from => 360.0,
+            val => val,
+        }
+    }
+
+    /// Centre bearing of the band, and half its width, in radians
+    fn sector(&self) -> (f64, f64) {
+        let half_width = self.width() / 2.0;
+        let centre = (self.from + half_width).rem_euclid(360.0);
+        (centre.to_radians(), half_width.to_radians())
+    }
+
+    /// Returns true if `bearing`, in degrees clockwise from north, lies in the
+    /// band
+    pub fn contains(&self, bearing: f64) -> bool {
+        let (centre, half_width) = self.sector();
+        inside_sector(bearing.rem_euclid(360.0).to_radians(), centre, half_width)
+    }
+}
+
+/// Parse a band written as `from-to`, for example `300-30`
+impl FromStr for BearingBand {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let parse_bearing = |bearing: &str| {
+            bearing
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|value| value.is_finite())
+        };
+        match value.split_once('-') {
+            Some((from, to)) => match (parse_bearing(from), parse_bearing(to)) {
+                (Some(from), Some(to)) => Ok(BearingBand { from, to }),
+                _ => Err(format!("invalid bearings in `{value}`")),
+            },
+            None => Err(format!(
+                "expected a bearing band written `from-to`, such as `300-30`, but got `{value}`"
+            )),
+        }
+    }
+}
+
+/// Return `neighbourhood` points closer than `arc_radius` units to the point
+/// numbered `point_number`, whose bearing from it lies in `band`, ignoring the
+/// way it faces, for questions like “what is to my north-west?”.  As for
+/// [`visible_points_from_neighbours`](crate::domain::visible_points_from_neighbours),
+/// points at the same coordinates are always included, the starting point
+/// never is, and points are returned in ascending number order.  An empty
+/// vector is returned if no point matching `point_number` is found.
+pub fn points_in_bearing_band<'a>(
+    point_number: u32,
+    band: &BearingBand,
+    arc_radius: u32,
+    neighbourhood: &'a [Point],
+) -> Vec<&'a Point> {
+    let _span = Span::enter("points_in_bearing_band", || {
+        format!("point {point_number} of {}", neighbourhood.len())
+    });
+    let point = match neighbourhood
+        .iter()
+        .find(|Point { number, .. }| *number == point_number)
+    {
+        Some(value) => value,
+        None => return vec![],
+    };
+    let (centre, half_width) = band.sector();
+    let mut result: Vec<&Point> = neighbourhood
+        .iter()
+        .filter(|neighbour| {
+            neighbour.number != point_number
+                && euclidean_distance(point.coordinates, neighbour.coordinates)
+                    < f64::from(arc_radius)
+                && (point.coordinates == neighbour.coordinates
+                    || inside_sector(
+                        angular_position(point.coordinates, neighbour.coordinates),
+                        centre,
+                        half_width,
+                    ))
+        })
+        .collect();
+    result.sort_by_key(|Point { number, .. }| *number);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{points_in_bearing_band, BearingBand};
+    use crate::domain::{test_support::point, Direction, Point};
+
+    #[test]
+    fn points_in_bearing_band_ignores_direction() {
+        // arrange
+        let neighbourhood = [
+            point(1, (0, 0), Direction::South),
+            point(5, (-3, 3), Direction::North),
+            point(2, (0, 4), Direction::East),
+            point(3, (4, 0), Direction::West),
+            point(4, (-4, -1), Direction::North),
+            point(6, (3, 6), Direction::North),
+        ];
+        let band: BearingBand = "300-30".parse().unwrap();
+
+        // act
+        let outcome: Vec<u32> = points_in_bearing_band(1, &band, 10, &neighbourhood)
+            .iter()
+            .map(|Point { number, .. }| *number)
+            .collect();
+
+        // assert
+        assert_eq!(outcome, vec![2, 5, 6]);
+        assert_eq!(band.width(), 90.0);
+        assert!(band.contains(-45.0) && band.contains(301.0) && !band.contains(90.0));
+        let full = BearingBand {
+            from: 0.0,
+            to: 360.0,
+        };
+        assert_eq!(
+            points_in_bearing_band(1, &full, 10, &neighbourhood).len(),
+            5
+        );
+        assert_eq!(points_in_bearing_band(1, &band, 5, &neighbourhood).len(), 2);
+        assert!(points_in_bearing_band(9, &band, 10, &neighbourhood).is_empty());
+        assert!("300".parse::<BearingBand>().is_err());
+        assert!("north-30".parse::<BearingBand>().is_err());
+    }
+}
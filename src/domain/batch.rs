@@ -0,0 +1,290 @@
+use crate::{
+    domain::point::{read_points_file, visible_points_from_neighbours, Point},
+    utilities::{AppError, Cancellation, Span},
+};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Visibility query, as found in a batch queries file
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Query {
+    pub point_number: u32,
+
+    /// Half arc central angle of the segment, in degrees
+    pub angle: u32,
+
+    pub radius: u32,
+}
+
+/// Result of running a [`Query`], with the numbers of visible points in
+/// neighbourhood order
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct QueryResult {
+    #[serde(flatten)]
+    pub query: Query,
+    pub visible: Vec<u32>,
+}
+
+impl QueryResult {
+    /// Result as a single line of JSON, without a trailing newline
+    pub fn to_json_line(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+/// Parse one CSV field of a query
+fn parse_field(field: Option<&str>, name: &str) -> Result<u32, String> {
+    let field = field.ok_or_else(|| format!("missing `{name}`"))?;
+    field
+        .trim()
+        .parse()
+        .map_err(|error| format!("invalid `{name}`: {error}"))
+}
+
+/// Parse a `point_number,angle,radius` CSV row
+fn parse_csv_row(row: &str) -> Result<Query, String> {
+    let mut fields = row.split(',');
+    let point_number = parse_field(fields.next(), "point_number")?;
+    let angle = parse_field(fields.next(), "angle")?;
+    let radius = parse_field(fields.next(), "radius")?;
+    match fields.next() {
+        Some(_) => Err(String::from("expected 3 fields")),
+        None => Ok(Query {
+            point_number,
+            angle,
+            radius,
+        }),
+    }
+}
+
+/// Parse a batch queries file.  Input starting with `[` is read as a JSON
+/// array of objects with `point_number`, `angle` and `radius` fields.  Any
+/// other input is read as CSV rows of `point_number,angle,radius`, skipping
+/// blank lines and an optional header row.
+pub fn parse_queries(input: &str) -> Result<Vec<Query>, AppError> {
+    if input.trim_start().starts_with('[') {
+        return serde_json::from_str(input).map_err(AppError::JSONParseError);
+    }
+
+    let mut result = vec![];
+    for (index, line) in input.lines().enumerate() {
+        if line.trim().is_empty() || (index == 0 && line.trim().starts_with("point_number")) {
+            continue;
+        }
+        let query = parse_csv_row(line)
+            .map_err(|error| AppError::QueryParseError(format!("line {}: {error}", index + 1)))?;
+        result.push(query);
+    }
+    Ok(result)
+}
+
+/// Parse a single query, given as a JSON object with `point_number`, `angle`
+/// and `radius` fields, or as a `point_number,angle,radius` CSV row
+pub fn parse_query_line(line: &str) -> Result<Query, AppError> {
+    if line.trim_start().starts_with('{') {
+        return serde_json::from_str(line).map_err(AppError::JSONParseError);
+    }
+    parse_csv_row(line).map_err(AppError::QueryParseError)
+}
+
+/// Answer the query on `line`, as for [`parse_query_line`], against
+/// `neighbourhood`, returning a line of JSON without a trailing newline.
+/// Invalid queries give an `{"error": …}` object, so a driving process can
+/// carry on sending queries.
+pub fn answer_query_line(line: &str, neighbourhood: &[Point]) -> String {
+    match parse_query_line(line) {
+        Ok(query) => run_queries(&[query], neighbourhood)
+            .iter()
+            .map(QueryResult::to_json_line)
+            .collect(),
+        Err(error) => serde_json::json!({ "error": error.to_string() }).to_string(),
+    }
+}
+
+/// Read and parse the batch queries file at `path`, as for [`parse_queries`]
+pub fn parse_queries_file<P: AsRef<Path>>(path: P) -> Result<Vec<Query>, AppError> {
+    let input = read_points_file(path.as_ref())?;
+    parse_queries(&input)
+}
+
+/// Run each of `queries` against the same `neighbourhood`, returning one
+/// result per query, in query order
+pub fn run_queries(queries: &[Query], neighbourhood: &[Point]) -> Vec<QueryResult> {
+    run_queries_cancellable(queries, neighbourhood, &Cancellation::new())
+}
+
+/// Run `queries`, as for [`run_queries`], checking `cancellation` before each
+/// query.  Results of queries answered before the token is cancelled, or its
+/// deadline passes, are returned, so fewer results than queries means the run
+/// was cut short.
+pub fn run_queries_cancellable(
+    queries: &[Query],
+    neighbourhood: &[Point],
+    cancellation: &Cancellation,
+) -> Vec<QueryResult> {
+    let _span = Span::enter("run_queries", || format!("{} queries", queries.len()));
+    queries
+        .iter()
+        .take_while(|_| !cancellation.is_cancelled())
+        .map(|query| QueryResult {
+            query: *query,
+            visible: visible_points_from_neighbours(
+                query.point_number,
+                query.angle,
+                query.radius,
+                neighbourhood,
+            )
+            .into_iter()
+            .map(|Point { number, .. }| *number)
+            .collect(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{answer_query_line, parse_queries, run_queries, run_queries_cancellable, Query};
+    use crate::{
+        domain::{Direction, Point},
+        utilities::Cancellation,
+    };
+
+    #[test]
+    fn parse_queries_reads_csv_and_json() {
+        // arrange
+        let csv = "point_number,angle,radius\n1,45,20\n\n2, 90, 10\n";
+        let json = r#"[{"point_number": 1, "angle": 45, "radius": 20}]"#;
+
+        // act
+        let csv_outcome = parse_queries(csv).unwrap();
+        let json_outcome = parse_queries(json).unwrap();
+
+        // assert
+        assert_eq!(
+            csv_outcome,
+            vec![
+                Query {
+                    point_number: 1,
+                    angle: 45,
+                    radius: 20
+                },
+                Query {
+                    point_number: 2,
+                    angle: 90,
+                    radius: 10
+                }
+            ]
+        );
+        assert_eq!(json_outcome, csv_outcome[..1]);
+    }
+
+    #[test]
+    fn parse_queries_handles_invalid_input() {
+        // arrange
+        let lines = ["1,45", "1,45,twenty", "1,45,20,5"];
+
+        // act
+        let outcome: Vec<String> = lines
+            .iter()
+            .map(|line| parse_queries(line).unwrap_err().to_string())
+            .collect();
+
+        // assert
+        assert_eq!(
+            outcome,
+            vec![
+                "Error parsing queries: line 1: missing `radius`",
+                "Error parsing queries: line 1: invalid `radius`: invalid digit found in string",
+                "Error parsing queries: line 1: expected 3 fields",
+            ]
+        );
+    }
+
+    #[test]
+    fn run_queries_gives_expected_result() {
+        // arrange
+        let points = vec![
+            Point {
+                coordinates: (0, 0),
+                number: 1,
+                direction: Direction::North,
+            },
+            Point {
+                coordinates: (0, 15),
+                number: 2,
+                direction: Direction::South,
+            },
+        ];
+        let queries = parse_queries("1,45,20\n1,45,10\n2,10,20\n").unwrap();
+
+        // act
+        let outcome = run_queries(&queries, &points);
+
+        // assert
+        let visible: Vec<Vec<u32>> = outcome.iter().map(|val| val.visible.clone()).collect();
+        assert_eq!(visible, vec![vec![2], vec![], vec![1]]);
+        assert_eq!(
+            outcome[0].to_json_line(),
+            r#"{"point_number":1,"angle":45,"radius":20,"visible":[2]}"#
+        );
+    }
+
+    #[test]
+    fn answer_query_line_gives_expected_result() {
+        // arrange
+        let points = vec![
+            Point {
+                coordinates: (0, 0),
+                number: 1,
+                direction: Direction::North,
+            },
+            Point {
+                coordinates: (0, 15),
+                number: 2,
+                direction: Direction::South,
+            },
+        ];
+        let lines = [
+            r#"{"point_number": 2, "angle": 10, "radius": 20}"#,
+            "1,45,10",
+            "1,45",
+        ];
+
+        // act
+        let outcome: Vec<String> = lines
+            .iter()
+            .map(|line| answer_query_line(line, &points))
+            .collect();
+
+        // assert
+        assert_eq!(
+            outcome,
+            vec![
+                r#"{"point_number":2,"angle":10,"radius":20,"visible":[1]}"#,
+                r#"{"point_number":1,"angle":45,"radius":10,"visible":[]}"#,
+                r#"{"error":"Error parsing queries: missing `radius`"}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn run_queries_cancellable_stops_when_cancelled() {
+        // arrange
+        let points = vec![Point {
+            coordinates: (0, 0),
+            number: 1,
+            direction: Direction::North,
+        }];
+        let queries = parse_queries("1,45,20\n1,90,20").unwrap();
+        let cancellation = Cancellation::new();
+
+        // act
+        let finished = run_queries_cancellable(&queries, &points, &cancellation);
+        cancellation.cancel();
+        let cancelled = run_queries_cancellable(&queries, &points, &cancellation);
+
+        // assert
+        assert_eq!(finished, run_queries(&queries, &points));
+        assert_eq!(cancelled.len(), 0);
+    }
+}
@@ -0,0 +1,203 @@
+use crate::{
+    domain::{
+        index_selection::{choose_index, Workload},
+        point::{euclidean_distance, Point},
+        spatial_index::IndexBackend,
+    },
+    utilities::Span,
+};
+use serde::Serialize;
+use std::{cmp::Ordering, collections::HashSet, time::Instant};
+
+/// Timings of one backend, from [`benchmark_indexes`]
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct BackendTiming {
+    pub backend: IndexBackend,
+
+    /// Seconds taken to build the index
+    pub build_seconds: f64,
+
+    /// Seconds taken to answer every query
+    pub query_seconds: f64,
+
+    /// Share of the points in range of each query which the backend found,
+    /// over all queries, from `0` to `1`
+    pub recall: f64,
+}
+
+impl BackendTiming {
+    /// Seconds taken to build the index and answer every query
+    pub fn total_seconds(&self) -> f64 {
+        self.build_seconds + self.query_seconds
+    }
+}
+
+/// Report from [`benchmark_indexes`], comparing index backends on one
+/// neighbourhood
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct IndexBenchmark {
+    /// Number of points indexed
+    pub points: usize,
+
+    /// Radius of each query
+    pub radius: u32,
+
+    /// Number of queries timed
+    pub queries: usize,
+
+    /// Timings of every backend, linear scan first
+    pub backends: Vec<BackendTiming>,
+
+    /// Backend building and answering the queries quickest, among those
+    /// reaching the recall asked for
+    pub recommended: IndexBackend,
+
+    /// Backend [`choose_index`] picks for the same radius and number of
+    /// queries, without timing any
+    pub automatic: IndexBackend,
+}
+
+impl IndexBenchmark {
+    /// Report as JSON, without a trailing newline
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+/// Time building each built-in index over `points`, and then answering
+/// `queries` range queries of `radius`, each centred on one of `points`,
+/// picked evenly through them.  Recall of each backend is checked against
+/// the linear scan, and the backend recommended is the quickest overall of
+/// those with a recall of at least `minimum_recall`, or the linear scan, if
+/// none is, or if no queries are timed, leaving nothing to compare.
+pub fn benchmark_indexes(
+    points: &[Point],
+    radius: u32,
+    queries: usize,
+    minimum_recall: f64,
+) -> IndexBenchmark {
+    let _span = Span::enter("benchmark_indexes", || {
+        format!("{queries} queries of {} points", points.len())
+    });
+    let centres: Vec<(i32, i32)> = if points.is_empty() {
+        vec![]
+    } else {
+        (0..queries)
+            .map(|query| points[query * points.len() / queries].coordinates)
+            .collect()
+    };
+    let range = f64::from(radius);
+    let expected: Vec<HashSet<u32>> = centres
+        .iter()
+        .map(|centre| {
+            points
+                .iter()
+                .filter(|point| euclidean_distance(*centre, point.coordinates) < range)
+                .map(|point| point.number)
+                .collect()
+        })
+        .collect();
+    let expected_total: usize = expected.iter().map(HashSet::len).sum();
+
+    let backends: Vec<BackendTiming> = IndexBackend::ALL
+        .iter()
+        .map(|backend| {
+            let start = Instant::now();
+            let index = backend.build(points);
+            let build_seconds = start.elapsed().as_secs_f64();
+            let start = Instant::now();
+            let found: Vec<Vec<u32>> = centres
+                .iter()
+                .map(|centre| {
+                    index
+                        .range_query(*centre, range)
+                        .map(|point| point.number)
+                        .collect()
+                })
+                .collect();
+            let query_seconds = start.elapsed().as_secs_f64();
+            let found_total: usize = found
+                .iter()
+                .zip(&expected)
+                .map(|(found, expected)| {
+                    found
+                        .iter()
+                        .collect::<HashSet<_>>()
+                        .into_iter()
+                        .filter(|number| expected.contains(number))
+                        .count()
+                })
+                .sum();
+            BackendTiming {
+                backend: *backend,
+                build_seconds,
+                query_seconds,
+                recall: if expected_total == 0 {
+                    1.0
+                } else {
+                    found_total as f64 / expected_total as f64
+                },
+            }
+        })
+        .collect();
+    let recommended = backends
+        .iter()
+        .filter(|timing| !centres.is_empty() && timing.recall >= minimum_recall)
+        .min_by(|a, b| {
+            a.total_seconds()
+                .partial_cmp(&b.total_seconds())
+                .unwrap_or(Ordering::Equal)
+        })
+        .map_or(IndexBackend::Linear, |timing| timing.backend);
+    IndexBenchmark {
+        points: points.len(),
+        radius,
+        queries: centres.len(),
+        backends,
+        recommended,
+        automatic: choose_index(points, &Workload { radius, queries }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::benchmark_indexes;
+    use crate::domain::{Direction, IndexBackend, Point};
+
+    #[test]
+    fn benchmark_indexes_times_every_backend() {
+        // arrange
+        let points: Vec<Point> = (0..300)
+            .map(|index| Point {
+                coordinates: ((index % 20) * 5, (index / 20) * 5),
+                number: index as u32 + 1,
+                direction: Direction::North,
+            })
+            .collect();
+
+        // act
+        let outcome = benchmark_indexes(&points, 12, 50, 1.0);
+
+        // assert
+        let backends: Vec<IndexBackend> = outcome
+            .backends
+            .iter()
+            .map(|timing| timing.backend)
+            .collect();
+        assert_eq!(backends, IndexBackend::ALL.to_vec());
+        assert_eq!(outcome.backends[0].recall, 1.0);
+        assert_eq!(outcome.backends[2].recall, 1.0);
+        assert_eq!(outcome.backends[3].recall, 1.0);
+        assert_eq!(outcome.automatic, IndexBackend::Linear);
+        assert_eq!((outcome.points, outcome.queries), (300, 50));
+        assert!(outcome
+            .backends
+            .iter()
+            .any(|timing| timing.backend == outcome.recommended && timing.recall >= 1.0));
+        assert!(outcome.to_json().contains(r#""backend":"ball-tree""#));
+        assert_eq!(
+            benchmark_indexes(&[], 12, 50, 1.0).recommended,
+            IndexBackend::Linear
+        );
+    }
+}
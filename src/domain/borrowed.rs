@@ -0,0 +1,94 @@
+use crate::{
+    domain::point::{Direction, Point},
+    utilities::{AppError, Span},
+};
+use serde::{Deserialize, Deserializer};
+use std::borrow::Cow;
+
+/// Point as found in a points JSON file, with an optional `label`, borrowed
+/// from the input wherever it holds no escapes, so millions of records need
+/// not each allocate a string
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct BorrowedPoint<'a> {
+    pub x: i32,
+    pub y: i32,
+    pub number: u32,
+    pub direction: Direction,
+
+    #[serde(borrow, default, deserialize_with = "borrow_label")]
+    pub label: Option<Cow<'a, str>>,
+}
+
+/// Deserialise an optional label, borrowing it where possible, since serde
+/// only borrows a `Cow` directly in a field, and not one inside an `Option`
+fn borrow_label<'de: 'a, 'a, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Cow<'a, str>>, D::Error> {
+    #[derive(Deserialize)]
+    struct Label<'a>(#[serde(borrow)] Cow<'a, str>);
+
+    Option::<Label>::deserialize(deserializer).map(|label| label.map(|Label(value)| value))
+}
+
+impl<'a> BorrowedPoint<'a> {
+    /// Position, number and direction, without the label
+    pub fn point(&self) -> Point {
+        Point {
+            coordinates: (self.x, self.y),
+            number: self.number,
+            direction: self.direction,
+        }
+    }
+}
+
+/// List of borrowed points as found in a points JSON file
+#[derive(Deserialize)]
+struct BorrowedPointList<'a> {
+    #[serde(borrow)]
+    points: Vec<BorrowedPoint<'a>>,
+}
+
+/// Parse a JSON string of points, as for [`parse_points`](crate::domain::parse_points),
+/// into points borrowing their labels from `json`, which must outlive them
+pub fn parse_borrowed_points(json: &str) -> Result<Vec<BorrowedPoint<'_>>, AppError> {
+    let _span = Span::enter("parse_borrowed_points", || format!("{} bytes", json.len()));
+    let BorrowedPointList { points } =
+        serde_json::from_str(json).map_err(AppError::JSONParseError)?;
+    Ok(points)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_borrowed_points;
+    use crate::domain::{parse_points, Direction, Point};
+    use std::borrow::Cow;
+
+    #[test]
+    fn parse_borrowed_points_borrows_labels() {
+        // arrange
+        let json = r#"{"points": [
+            {"x": 1, "y": 2, "number": 1, "direction": "East", "label": "buoy"},
+            {"x": 3, "y": 4, "number": 2, "direction": "West", "label": "say \"ahoy\""},
+            {"x": 5, "y": 6, "number": 3, "direction": "North"}
+        ]}"#;
+
+        // act
+        let outcome = parse_borrowed_points(json).unwrap();
+
+        // assert
+        assert!(matches!(outcome[0].label, Some(Cow::Borrowed("buoy"))));
+        assert!(matches!(&outcome[1].label, Some(Cow::Owned(value)) if value == "say \"ahoy\""));
+        assert_eq!(outcome[2].label, None);
+        assert_eq!(
+            outcome[1].point(),
+            Point {
+                coordinates: (3, 4),
+                number: 2,
+                direction: Direction::West
+            }
+        );
+        let points: Vec<Point> = outcome.iter().map(|point| point.point()).collect();
+        assert_eq!(points, parse_points(json).unwrap());
+        assert!(parse_borrowed_points("{}").is_err());
+    }
+}
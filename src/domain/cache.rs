@@ -0,0 +1,498 @@
+use crate::{
+    domain::{
+        graph::{visibility_graph, VisibilityGraph},
+        point::{parse_points_file, Direction, Point},
+    },
+    utilities::{log, AppError, LogLevel, Span},
+};
+use std::{
+    borrow::Cow,
+    convert::TryInto,
+    ffi::OsString,
+    fs::{self, File},
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+/// Leading bytes of the points cache format written by [`points_to_bytes`]
+pub const POINTS_CACHE_MAGIC: &[u8; 4] = b"NBPC";
+
+/// Version of the points cache format written by [`points_to_bytes`]
+pub const POINTS_CACHE_VERSION: u8 = 2;
+
+/// Bytes used by each point in the points cache format
+pub(crate) const CACHED_POINT_SIZE: usize = 29;
+
+/// Initial value of an FNV-1a hash
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// Bytes read at a time when fingerprinting a file
+const FINGERPRINT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Continue 64-bit FNV-1a `hash` over `bytes`, used to detect changes to a
+/// points file
+fn fingerprint_update(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// 64-bit FNV-1a hash of the contents of the file at `path`, read in chunks,
+/// so the whole file is never held in memory
+pub(crate) fn fingerprint_file(path: &Path) -> Result<u64, AppError> {
+    let invalid_file_error = |error| AppError::InvalidFileError {
+        expected_path: path.display().to_string(),
+        source: error,
+    };
+    let mut file = File::open(path).map_err(invalid_file_error)?;
+    let mut chunk = vec![0; FINGERPRINT_CHUNK_SIZE];
+    let mut hash = FNV_OFFSET_BASIS;
+    loop {
+        match file.read(&mut chunk).map_err(invalid_file_error)? {
+            0 => return Ok(hash),
+            length => hash = fingerprint_update(hash, &chunk[..length]),
+        }
+    }
+}
+
+/// Write `graph` to the file at `path`, in the format of
+/// [`VisibilityGraph::to_bytes`]
+pub fn save_visibility_graph<P: AsRef<Path>>(
+    path: P,
+    graph: &VisibilityGraph,
+) -> Result<(), AppError> {
+    let path_ref = path.as_ref();
+    fs::write(path_ref, graph.to_bytes()).map_err(|error| AppError::OutputError {
+        path: path_ref.display().to_string(),
+        source: error,
+    })
+}
+
+/// Read a graph saved with [`save_visibility_graph`] from the file at `path`
+pub fn load_visibility_graph<P: AsRef<Path>>(path: P) -> Result<VisibilityGraph, AppError> {
+    let path_ref = path.as_ref();
+    let bytes = fs::read(path_ref).map_err(|error| AppError::BinaryFileError {
+        path: path_ref.display().to_string(),
+        source: error,
+    })?;
+    VisibilityGraph::from_bytes(&bytes)
+}
+
+/// Path of the cached graph for the points file at `points_path`, whose
+/// contents have fingerprint `contents_fingerprint`.  The file name includes
+/// the fingerprint and the query parameters, so a changed points file, or
+/// different parameters, never reuse a stale graph.
+fn cache_path(
+    cache_directory: &Path,
+    points_path: &Path,
+    contents_fingerprint: u64,
+    half_arc_central_angle: u32,
+    arc_radius: u32,
+) -> PathBuf {
+    let file_name = points_path
+        .file_name()
+        .map(|value| value.to_string_lossy().into_owned())
+        .unwrap_or_else(|| String::from("points"));
+    cache_directory.join(format!(
+        "{}-{:016x}-a{}-r{}.graph",
+        file_name, contents_fingerprint, half_arc_central_angle, arc_radius
+    ))
+}
+
+/// Return the visibility graph of the points file at `points_path`,
+/// reusing a graph saved in `cache_directory` by an earlier call with the same
+/// points file contents, and parameters, where one exists.  Otherwise the graph
+/// is computed, as with [`visibility_graph`], then saved to `cache_directory`,
+/// which is created if missing.  Unreadable cache files are recomputed.
+pub fn cached_visibility_graph<P: AsRef<Path>, Q: AsRef<Path>>(
+    points_path: P,
+    half_arc_central_angle: u32,
+    arc_radius: u32,
+    cache_directory: Q,
+) -> Result<VisibilityGraph, AppError> {
+    let points_path = points_path.as_ref();
+    let fingerprint = fingerprint_file(points_path)?;
+    cached_graph(
+        cache_directory.as_ref(),
+        points_path,
+        fingerprint,
+        half_arc_central_angle,
+        arc_radius,
+        || parse_points_file(points_path).map(Cow::Owned),
+    )
+}
+
+/// Return the visibility graph of `points`, read from the points file at
+/// `points_path`, reusing a graph saved in `cache_directory`, as for
+/// [`cached_visibility_graph`].  The cache is keyed by the points themselves,
+/// rather than the file contents, so points left out or reworked after
+/// reading, for example by a bounding box or deduplication, never reuse a
+/// graph built from others.
+pub fn cached_points_visibility_graph<P: AsRef<Path>, Q: AsRef<Path>>(
+    points_path: P,
+    points: &[Point],
+    half_arc_central_angle: u32,
+    arc_radius: u32,
+    cache_directory: Q,
+) -> Result<VisibilityGraph, AppError> {
+    cached_graph(
+        cache_directory.as_ref(),
+        points_path.as_ref(),
+        fingerprint_update(FNV_OFFSET_BASIS, &points_to_bytes(0, points)),
+        half_arc_central_angle,
+        arc_radius,
+        || Ok(Cow::Borrowed(points)),
+    )
+}
+
+/// Load the graph cached for `fingerprint` and parameters, or compute it from
+/// the points given by `points`, and save it to `cache_directory`
+fn cached_graph<'a, F>(
+    cache_directory: &Path,
+    points_path: &Path,
+    fingerprint: u64,
+    half_arc_central_angle: u32,
+    arc_radius: u32,
+    points: F,
+) -> Result<VisibilityGraph, AppError>
+where
+    F: FnOnce() -> Result<Cow<'a, [Point]>, AppError>,
+{
+    let path = cache_path(
+        cache_directory,
+        points_path,
+        fingerprint,
+        half_arc_central_angle,
+        arc_radius,
+    );
+    if let Ok(value) = load_visibility_graph(&path) {
+        if value.half_arc_central_angle == half_arc_central_angle && value.arc_radius == arc_radius
+        {
+            log(
+                LogLevel::Info,
+                &format!("reusing cached graph `{}`", path.display()),
+            );
+            return Ok(value);
+        }
+    }
+    log(
+        LogLevel::Info,
+        &format!("cache miss, computing graph `{}`", path.display()),
+    );
+
+    let result = visibility_graph(half_arc_central_angle, arc_radius, &points()?);
+    fs::create_dir_all(cache_directory).map_err(|error| AppError::OutputError {
+        path: cache_directory.display().to_string(),
+        source: error,
+    })?;
+    save_visibility_graph(&path, &result)?;
+    Ok(result)
+}
+
+/// Serialise `points`, parsed from a source file with fingerprint
+/// `source_fingerprint`, for reuse by [`cached_points_file`].  The layout is
+/// [`POINTS_CACHE_MAGIC`], the [`POINTS_CACHE_VERSION`] byte, the source
+/// fingerprint as a little-endian `u64`, and point count as a little-endian
+/// `u32`.  Each point follows, as number (`u32`), x and y coordinates (`i32`),
+/// all little-endian, then a direction byte: `0` north, `1` east, `2` south,
+/// `3` west, `4` vector or `5` towards a point, and the vector's `dx` and `dy`
+/// (`f64`), or the number of the point faced in place of `dx`.
+pub fn points_to_bytes(source_fingerprint: u64, points: &[Point]) -> Vec<u8> {
+    let mut result = POINTS_CACHE_MAGIC.to_vec();
+    result.push(POINTS_CACHE_VERSION);
+    result.extend_from_slice(&source_fingerprint.to_le_bytes());
+    result.extend_from_slice(&(points.len() as u32).to_le_bytes());
+    for point in points {
+        push_point_bytes(&mut result, point);
+    }
+    result
+}
+
+/// Append the 29 byte record of `point`, as used by [`points_to_bytes`].  The
+/// components of a vector direction follow the direction byte, and are zero
+/// for compass points.
+pub(crate) fn push_point_bytes(
+    result: &mut Vec<u8>,
+    Point {
+        coordinates: (x, y),
+        number,
+        direction,
+    }: &Point,
+) {
+    result.extend_from_slice(&number.to_le_bytes());
+    result.extend_from_slice(&x.to_le_bytes());
+    result.extend_from_slice(&y.to_le_bytes());
+    let (tag, (dx, dy)) = match direction {
+        Direction::North => (0, (0.0, 0.0)),
+        Direction::East => (1, (0.0, 0.0)),
+        Direction::South => (2, (0.0, 0.0)),
+        Direction::West => (3, (0.0, 0.0)),
+        Direction::Vector { dx, dy } => (4, (*dx, *dy)),
+        Direction::Towards(number) => (5, (f64::from(*number), 0.0)),
+    };
+    result.push(tag);
+    result.extend_from_slice(&f64::to_le_bytes(dx));
+    result.extend_from_slice(&f64::to_le_bytes(dy));
+}
+
+/// Restore points from consecutive 29 byte records, as written by
+/// [`push_point_bytes`]
+pub(crate) fn points_from_records(body: &[u8]) -> Result<Vec<Point>, AppError> {
+    let format_error = |message: &str| AppError::PointsCacheError(message.to_string());
+    if body.len() % CACHED_POINT_SIZE != 0 {
+        return Err(format_error("truncated point record"));
+    }
+    body.chunks(CACHED_POINT_SIZE)
+        .map(|record| {
+            let u32_at = |position: usize| {
+                u32::from_le_bytes(
+                    record[position..position + 4]
+                        .try_into()
+                        .unwrap_or_default(),
+                )
+            };
+            let f64_at = |position: usize| {
+                f64::from_le_bytes(
+                    record[position..position + 8]
+                        .try_into()
+                        .unwrap_or_default(),
+                )
+            };
+            let direction = match record[12] {
+                0 => Direction::North,
+                1 => Direction::East,
+                2 => Direction::South,
+                3 => Direction::West,
+                4 => Direction::Vector {
+                    dx: f64_at(13),
+                    dy: f64_at(21),
+                },
+                5 => Direction::Towards(f64_at(13) as u32),
+                _ => return Err(format_error("invalid direction")),
+            };
+            Ok(Point {
+                coordinates: (u32_at(4) as i32, u32_at(8) as i32),
+                number: u32_at(0),
+                direction,
+            })
+        })
+        .collect()
+}
+
+/// Restore points serialised with [`points_to_bytes`], returning them with
+/// the fingerprint of the source file they were parsed from
+pub fn points_from_bytes(bytes: &[u8]) -> Result<(u64, Vec<Point>), AppError> {
+    let format_error = |message: &str| AppError::PointsCacheError(message.to_string());
+    if bytes.len() < 17 || &bytes[..4] != POINTS_CACHE_MAGIC {
+        return Err(format_error("missing points cache header"));
+    }
+    if bytes[4] != POINTS_CACHE_VERSION {
+        return Err(AppError::PointsCacheError(format!(
+            "unsupported version {}",
+            bytes[4]
+        )));
+    }
+    let u32_at = |position: usize| {
+        u32::from_le_bytes(bytes[position..position + 4].try_into().unwrap_or_default())
+    };
+    let fingerprint = u64::from_le_bytes(bytes[5..13].try_into().unwrap_or_default());
+    let count = u32_at(13) as usize;
+    let body = &bytes[17..];
+    if body.len() != count * CACHED_POINT_SIZE {
+        return Err(format_error("point count does not match data length"));
+    }
+    let points = points_from_records(body)?;
+    Ok((fingerprint, points))
+}
+
+/// Path of the points cache sidecar for `points_path`, which appends `.bin`,
+/// so `points.json` is cached in `points.json.bin`
+fn points_cache_path(points_path: &Path) -> PathBuf {
+    let mut result = OsString::from(points_path.as_os_str());
+    result.push(".bin");
+    PathBuf::from(result)
+}
+
+/// Parse the points file at `points_path`, as with [`parse_points_file`],
+/// reusing the binary sidecar written next to it, by an earlier call, when
+/// the file is unchanged, which is much quicker than parsing a large file.
+/// Otherwise the file is parsed and the sidecar rewritten.  Stale, or
+/// unreadable, sidecars are ignored, and failing to write one is logged as a
+/// warning, so a read-only directory never stops points being read.
+pub fn cached_points_file<P: AsRef<Path>>(points_path: P) -> Result<Vec<Point>, AppError> {
+    let points_path = points_path.as_ref();
+    let path = points_cache_path(points_path);
+    let fingerprint = fingerprint_file(points_path)?;
+    if let Ok(bytes) = fs::read(&path) {
+        let _span = Span::enter("read_points_cache", || path.display().to_string());
+        match points_from_bytes(&bytes) {
+            Ok((value, points)) if value == fingerprint => {
+                log(
+                    LogLevel::Info,
+                    &format!("reusing cached points `{}`", path.display()),
+                );
+                return Ok(points);
+            }
+            Ok(_) => log(
+                LogLevel::Info,
+                &format!("points file changed, replacing `{}`", path.display()),
+            ),
+            Err(error) => log(
+                LogLevel::Warn,
+                &format!("ignoring points cache `{}`: {error}", path.display()),
+            ),
+        }
+    }
+
+    let points = parse_points_file(points_path)?;
+    if let Err(error) = fs::write(&path, points_to_bytes(fingerprint, &points)) {
+        log(
+            LogLevel::Warn,
+            &format!("unable to write points cache `{}`: {error}", path.display()),
+        );
+    }
+    Ok(points)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        cached_points_file, cached_points_visibility_graph, cached_visibility_graph,
+        fingerprint_update, load_visibility_graph, points_from_bytes, points_to_bytes,
+        FNV_OFFSET_BASIS,
+    };
+    use crate::domain::{parse_points_file, visibility_graph, BoundingBox, Direction};
+    use std::{env, fs, path::Path};
+
+    #[test]
+    fn fingerprint_gives_expected_result() {
+        // arrange
+        let bytes = b"a";
+
+        // act
+        let outcome = fingerprint_update(FNV_OFFSET_BASIS, bytes);
+
+        // assert
+        assert_eq!(outcome, 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn cached_visibility_graph_reuses_saved_graph() {
+        // arrange
+        let points_file_path = Path::new("./fixtures/valid_points.json");
+        let cache_directory =
+            env::temp_dir().join(format!("neighbours-cache-test-{}", std::process::id()));
+        let points = parse_points_file(points_file_path).unwrap();
+        let expected = visibility_graph(45, 20, &points);
+
+        // act
+        let computed = cached_visibility_graph(points_file_path, 45, 20, &cache_directory).unwrap();
+        let cache_files: Vec<_> = fs::read_dir(&cache_directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        let reused = cached_visibility_graph(points_file_path, 45, 20, &cache_directory).unwrap();
+
+        // assert
+        assert_eq!(computed, expected);
+        assert_eq!(reused, expected);
+        assert_eq!(cache_files.len(), 1);
+        assert_eq!(load_visibility_graph(&cache_files[0]).unwrap(), expected);
+        fs::remove_dir_all(cache_directory).unwrap();
+    }
+
+    #[test]
+    fn cached_points_visibility_graph_keys_on_points_read() {
+        // arrange
+        let points_file_path = Path::new("./fixtures/valid_points.json");
+        let cache_directory = env::temp_dir().join(format!(
+            "neighbours-points-cache-test-{}",
+            std::process::id()
+        ));
+        let points = parse_points_file(points_file_path).unwrap();
+        let bounds = BoundingBox {
+            min: (20, 40),
+            max: (30, 50),
+        };
+        let mut bounded = points.clone();
+        bounded.retain(|point| bounds.contains(point.coordinates));
+
+        // act
+        let whole =
+            cached_points_visibility_graph(points_file_path, &points, 45, 20, &cache_directory)
+                .unwrap();
+        let within =
+            cached_points_visibility_graph(points_file_path, &bounded, 45, 20, &cache_directory)
+                .unwrap();
+        let reused =
+            cached_points_visibility_graph(points_file_path, &bounded, 45, 20, &cache_directory)
+                .unwrap();
+        let cache_files = fs::read_dir(&cache_directory).unwrap().count();
+
+        // assert
+        assert_eq!(whole, visibility_graph(45, 20, &points));
+        assert_eq!(within, visibility_graph(45, 20, &bounded));
+        assert_ne!(within, whole);
+        assert_eq!(reused, within);
+        assert_eq!(cache_files, 2);
+        fs::remove_dir_all(cache_directory).unwrap();
+    }
+
+    #[test]
+    fn points_to_bytes_round_trips() {
+        // arrange
+        let mut points = parse_points_file("./fixtures/valid_points.json").unwrap();
+        points[0].direction = Direction::Vector { dx: 0.6, dy: -0.8 };
+
+        // act
+        let bytes = points_to_bytes(42, &points);
+        let outcome = points_from_bytes(&bytes).unwrap();
+
+        // assert
+        assert_eq!(outcome, (42, points));
+        assert_eq!(
+            points_from_bytes(&bytes[..bytes.len() - 1])
+                .unwrap_err()
+                .to_string(),
+            "Error reading points cache: point count does not match data length"
+        );
+        let mut newer = bytes;
+        newer[4] = 99;
+        assert_eq!(
+            points_from_bytes(&newer).unwrap_err().to_string(),
+            "Error reading points cache: unsupported version 99"
+        );
+    }
+
+    #[test]
+    fn cached_points_file_reuses_sidecar() {
+        // arrange
+        let directory = env::temp_dir().join(format!(
+            "neighbours-points-cache-test-{}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&directory).unwrap();
+        let points_file_path = directory.join("points.json");
+        fs::copy("./fixtures/valid_points.json", &points_file_path).unwrap();
+        let expected = parse_points_file(&points_file_path).unwrap();
+
+        // act
+        let parsed = cached_points_file(&points_file_path).unwrap();
+        let sidecar = fs::read(directory.join("points.json.bin")).unwrap();
+        let reused = cached_points_file(&points_file_path).unwrap();
+        fs::write(
+            &points_file_path,
+            r#"{"points": [{"x": 1, "y": 2, "number": 3, "direction": "East"}]}"#,
+        )
+        .unwrap();
+        let changed = cached_points_file(&points_file_path).unwrap();
+
+        // assert
+        assert_eq!(parsed, expected);
+        assert_eq!(points_from_bytes(&sidecar).unwrap().1, expected);
+        assert_eq!(reused, expected);
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].number, 3);
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
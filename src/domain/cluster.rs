@@ -0,0 +1,287 @@
+use crate::domain::point::{euclidean_distance, euclidean_distance_f64, Point};
+use std::collections::VecDeque;
+
+/// Cluster assigned to a point by [`dbscan`]
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum ClusterLabel {
+    /// Point is not close enough to a dense region to join a cluster
+    Noise,
+
+    /// Point belongs to the cluster with this index, counting from zero
+    Cluster(usize),
+}
+
+/// Cluster `points` with DBSCAN, for example to group a query’s visible
+/// points, or a whole neighbourhood.  Points within `epsilon` units of each
+/// other are neighbours, and a point with at least `minimum_points` neighbours,
+/// counting itself, is a core point.  Clusters are built from connected core
+/// points along with their neighbours, and all other points are noise.
+///
+/// Points are returned in input order, each with its label.  Clusters are
+/// numbered in the order their first point appears.
+pub fn dbscan<'a, I: IntoIterator<Item = &'a Point>>(
+    points: I,
+    epsilon: f64,
+    minimum_points: usize,
+) -> Vec<(&'a Point, ClusterLabel)> {
+    let points: Vec<&Point> = points.into_iter().collect();
+    let region = |index: usize| -> Vec<usize> {
+        (0..points.len())
+            .filter(|other| {
+                euclidean_distance(points[index].coordinates, points[*other].coordinates) <= epsilon
+            })
+            .collect()
+    };
+
+    let mut labels: Vec<Option<ClusterLabel>> = vec![None; points.len()];
+    let mut cluster_count = 0;
+    for index in 0..points.len() {
+        if labels[index].is_some() {
+            continue;
+        }
+        let neighbours = region(index);
+        if neighbours.len() < minimum_points {
+            labels[index] = Some(ClusterLabel::Noise);
+            continue;
+        }
+
+        let label = ClusterLabel::Cluster(cluster_count);
+        cluster_count += 1;
+        labels[index] = Some(label);
+        let mut queue: VecDeque<usize> = neighbours.into_iter().collect();
+        while let Some(other) = queue.pop_front() {
+            match labels[other] {
+                // border point, previously marked as noise, joins the cluster
+                Some(ClusterLabel::Noise) => labels[other] = Some(label),
+                Some(ClusterLabel::Cluster(_)) => {}
+                None => {
+                    labels[other] = Some(label);
+                    let other_neighbours = region(other);
+                    if other_neighbours.len() >= minimum_points {
+                        queue.extend(other_neighbours);
+                    }
+                }
+            }
+        }
+    }
+
+    points
+        .into_iter()
+        .zip(labels)
+        .map(|(point, label)| (point, label.unwrap_or(ClusterLabel::Noise)))
+        .collect()
+}
+
+/// Result of [`kmeans`] clustering
+#[derive(Clone, Debug, PartialEq)]
+pub struct KMeans<'a> {
+    /// x,y coordinates of each cluster’s centroid
+    pub centroids: Vec<(f64, f64)>,
+
+    /// Points, in input order, each with the index of its cluster’s centroid
+    pub assignments: Vec<(&'a Point, usize)>,
+}
+
+/// Index of the centroid closest to `coordinates`, preferring the earliest
+/// centroid for ties
+fn closest_centroid(coordinates: (f64, f64), centroids: &[(f64, f64)]) -> usize {
+    centroids
+        .iter()
+        .enumerate()
+        .fold(
+            (0, f64::INFINITY),
+            |(best, best_distance), (index, centroid)| {
+                let distance = euclidean_distance_f64(coordinates, *centroid);
+                if distance < best_distance {
+                    (index, distance)
+                } else {
+                    (best, best_distance)
+                }
+            },
+        )
+        .0
+}
+
+/// Cluster `points` into at most `k` groups with k-means, for example to
+/// summarise a large visible set as a handful of representative positions.
+/// Lloyd’s algorithm runs until assignments stop changing, or for
+/// `maximum_iterations`.
+///
+/// Seeding is deterministic: the first centroid is the first point, and each
+/// further centroid is the point farthest from those already chosen.  `k` is
+/// reduced to the number of distinct coordinates if there are fewer.  An empty
+/// input, or a `k` of zero, gives no centroids, and every point is then
+/// assigned to centroid `0`.
+pub fn kmeans<'a, I: IntoIterator<Item = &'a Point>>(
+    points: I,
+    k: usize,
+    maximum_iterations: usize,
+) -> KMeans<'a> {
+    let points: Vec<&Point> = points.into_iter().collect();
+    let coordinates: Vec<(f64, f64)> = points
+        .iter()
+        .map(
+            |Point {
+                 coordinates: (x, y),
+                 ..
+             }| (f64::from(*x), f64::from(*y)),
+        )
+        .collect();
+
+    let mut centroids: Vec<(f64, f64)> = vec![];
+    if let Some(first) = coordinates.first().filter(|_| k > 0) {
+        centroids.push(*first);
+    }
+    while centroids.len() < k {
+        let (farthest, distance) =
+            coordinates
+                .iter()
+                .fold((None, 0.0), |(best, best_distance), val| {
+                    let distance =
+                        euclidean_distance_f64(*val, centroids[closest_centroid(*val, &centroids)]);
+                    if distance > best_distance {
+                        (Some(*val), distance)
+                    } else {
+                        (best, best_distance)
+                    }
+                });
+        match farthest {
+            Some(value) if distance > 0.0 => centroids.push(value),
+            _ => break,
+        }
+    }
+
+    let mut assignments: Vec<usize> = coordinates
+        .iter()
+        .map(|val| closest_centroid(*val, &centroids))
+        .collect();
+    for _ in 0..maximum_iterations {
+        // move each centroid to the mean of its points, leaving empty clusters
+        // in place
+        for (index, centroid) in centroids.iter_mut().enumerate() {
+            let (sum_x, sum_y, count) = coordinates
+                .iter()
+                .zip(&assignments)
+                .filter(|(_, assignment)| **assignment == index)
+                .fold((0.0, 0.0, 0), |(sum_x, sum_y, count), ((x, y), _)| {
+                    (sum_x + x, sum_y + y, count + 1)
+                });
+            if count > 0 {
+                *centroid = (sum_x / f64::from(count), sum_y / f64::from(count));
+            }
+        }
+
+        let updated: Vec<usize> = coordinates
+            .iter()
+            .map(|val| closest_centroid(*val, &centroids))
+            .collect();
+        if updated == assignments {
+            break;
+        }
+        assignments = updated;
+    }
+
+    KMeans {
+        centroids,
+        assignments: points.into_iter().zip(assignments).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{dbscan, kmeans, ClusterLabel};
+    use crate::domain::{Direction, Point};
+
+    fn points_at(coordinates: &[(i32, i32)]) -> Vec<Point> {
+        coordinates
+            .iter()
+            .enumerate()
+            .map(|(index, coordinates)| Point {
+                coordinates: *coordinates,
+                number: index as u32 + 1,
+                direction: Direction::North,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn dbscan_gives_expected_result() {
+        // arrange
+        let points = points_at(&[(0, 0), (1, 0), (0, 1), (20, 20), (21, 20), (10, 10), (2, 0)]);
+
+        // act
+        let outcome = dbscan(&points, 1.5, 3);
+
+        // assert
+        let labels: Vec<ClusterLabel> = outcome.iter().map(|(_, label)| *label).collect();
+        assert_eq!(
+            labels,
+            vec![
+                ClusterLabel::Cluster(0),
+                ClusterLabel::Cluster(0),
+                ClusterLabel::Cluster(0),
+                ClusterLabel::Noise,
+                ClusterLabel::Noise,
+                ClusterLabel::Noise,
+                ClusterLabel::Cluster(0),
+            ]
+        );
+
+        // act
+        let outcome = dbscan(&points, 1.5, 2);
+
+        // assert
+        let labels: Vec<ClusterLabel> = outcome.iter().map(|(_, label)| *label).collect();
+        assert_eq!(labels[3], ClusterLabel::Cluster(1));
+        assert_eq!(labels[4], ClusterLabel::Cluster(1));
+        assert_eq!(labels[5], ClusterLabel::Noise);
+    }
+
+    #[test]
+    fn dbscan_handles_empty_input() {
+        // arrange
+        let points: Vec<Point> = vec![];
+
+        // act
+        let outcome = dbscan(&points, 1.5, 3);
+
+        // assert
+        assert_eq!(outcome.len(), 0);
+    }
+
+    #[test]
+    fn kmeans_gives_expected_result() {
+        // arrange
+        let points = points_at(&[(0, 0), (2, 0), (20, 20), (22, 20), (0, 2), (20, 22)]);
+
+        // act
+        let outcome = kmeans(&points, 2, 10);
+
+        // assert
+        let assignments: Vec<usize> = outcome.assignments.iter().map(|(_, val)| *val).collect();
+        assert_eq!(assignments, vec![0, 0, 1, 1, 0, 1]);
+        let (x, y) = outcome.centroids[0];
+        assert!((x - 2.0 / 3.0).abs() < 1e-10 && (y - 2.0 / 3.0).abs() < 1e-10);
+        let (x, y) = outcome.centroids[1];
+        assert!((x - 62.0 / 3.0).abs() < 1e-10 && (y - 62.0 / 3.0).abs() < 1e-10);
+    }
+
+    #[test]
+    fn kmeans_handles_degenerate_input() {
+        // arrange
+        let points = points_at(&[(1, 1), (1, 1)]);
+
+        // act
+        let outcome = kmeans(&points, 3, 10);
+
+        // assert
+        assert_eq!(outcome.centroids, vec![(1.0, 1.0)]);
+
+        // act
+        let outcome = kmeans(&[], 3, 10);
+
+        // assert
+        assert_eq!(outcome.centroids.len(), 0);
+        assert_eq!(outcome.assignments.len(), 0);
+    }
+}
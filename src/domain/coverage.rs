@@ -0,0 +1,216 @@
+use crate::domain::point::{close_neighbours, Point};
+use std::collections::HashSet;
+
+/// Numbers of all `neighbourhood` points visible from at least one other
+/// point, with each point looking along its own direction.
+fn observed_point_numbers(
+    half_arc_central_angle: u32,
+    arc_radius: u32,
+    neighbourhood: &[Point],
+) -> HashSet<u32> {
+    neighbourhood
+        .iter()
+        .flat_map(|point| {
+            close_neighbours(point, half_arc_central_angle, arc_radius, neighbourhood)
+        })
+        .map(|Point { number, .. }| *number)
+        .collect()
+}
+
+/// Return a vector of all `neighbourhood` points which are not visible from
+/// any other point.  Each point sees a segment of radius `arc_radius` units,
+/// spanning left and right from its own direction by `half_arc_central_angle`.
+/// `half_arc_central_angle` should be in degrees, and can range from zero to
+/// `180` degrees.
+///
+/// Returned points keep their `neighbourhood` order.
+pub fn hidden_points(
+    half_arc_central_angle: u32,
+    arc_radius: u32,
+    neighbourhood: &[Point],
+) -> Vec<&Point> {
+    let observed = observed_point_numbers(half_arc_central_angle, arc_radius, neighbourhood);
+    neighbourhood
+        .iter()
+        .filter(|Point { number, .. }| !observed.contains(number))
+        .collect()
+}
+
+/// Return a vector of all `neighbourhood` points which see no other point.
+/// This is the converse of [`hidden_points`], and uses the same segment
+/// definition.
+///
+/// Returned points keep their `neighbourhood` order.
+pub fn blind_points(
+    half_arc_central_angle: u32,
+    arc_radius: u32,
+    neighbourhood: &[Point],
+) -> Vec<&Point> {
+    neighbourhood
+        .iter()
+        .filter(|point| {
+            close_neighbours(point, half_arc_central_angle, arc_radius, neighbourhood).is_empty()
+        })
+        .collect()
+}
+
+/// Return a small subset of `neighbourhood` points, such that every other
+/// point is visible from at least one member of the subset.  Each point sees a
+/// segment of radius `arc_radius` units, spanning left and right from its own
+/// direction by `half_arc_central_angle` degrees, as for [`hidden_points`].
+///
+/// Points are chosen greedily, each time picking the point which sees the
+/// most points not yet covered, so the subset is not guaranteed to be the
+/// smallest possible.  Every chosen point counts as covering itself, so
+/// hidden points are always chosen.  Returned points are in the order they
+/// were chosen, with ties broken by `neighbourhood` order.
+pub fn sentinel_points(
+    half_arc_central_angle: u32,
+    arc_radius: u32,
+    neighbourhood: &[Point],
+) -> Vec<&Point> {
+    // numbers of the points each candidate covers, including itself
+    let candidates: Vec<(&Point, HashSet<u32>)> = neighbourhood
+        .iter()
+        .map(|point| {
+            let mut covered: HashSet<u32> =
+                close_neighbours(point, half_arc_central_angle, arc_radius, neighbourhood)
+                    .iter()
+                    .map(|Point { number, .. }| *number)
+                    .collect();
+            covered.insert(point.number);
+            (point, covered)
+        })
+        .collect();
+    let mut uncovered: HashSet<u32> = neighbourhood
+        .iter()
+        .map(|Point { number, .. }| *number)
+        .collect();
+
+    let mut result: Vec<&Point> = vec![];
+    while !uncovered.is_empty() {
+        let best = candidates.iter().fold(
+            None,
+            |acc: Option<(&(&Point, HashSet<u32>), usize)>, candidate| {
+                let gain = candidate.1.intersection(&uncovered).count();
+                match acc {
+                    Some((_, best_gain)) if best_gain >= gain => acc,
+                    _ => Some((candidate, gain)),
+                }
+            },
+        );
+        match best {
+            Some(((point, covered), gain)) if gain > 0 => {
+                uncovered.retain(|number| !covered.contains(number));
+                result.push(point);
+            }
+            _ => break,
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{blind_points, hidden_points, sentinel_points};
+    use crate::domain::{Direction, Point};
+
+    fn neighbourhood() -> Vec<Point> {
+        vec![
+            Point {
+                coordinates: (0, 0),
+                number: 1,
+                direction: Direction::North,
+            },
+            Point {
+                coordinates: (0, 5),
+                number: 2,
+                direction: Direction::North,
+            },
+            Point {
+                coordinates: (5, 0),
+                number: 3,
+                direction: Direction::West,
+            },
+            Point {
+                coordinates: (0, -8),
+                number: 4,
+                direction: Direction::South,
+            },
+        ]
+    }
+
+    #[test]
+    fn hidden_points_gives_expected_result() {
+        // arrange
+        let points = neighbourhood();
+
+        // act
+        let outcome = hidden_points(45, 10, &points);
+
+        // assert
+        let numbers: Vec<u32> = outcome.iter().map(|Point { number, .. }| *number).collect();
+        assert_eq!(numbers, vec![3, 4]);
+
+        // act
+        let outcome = hidden_points(45, 3, &points);
+
+        // assert
+        assert_eq!(outcome.len(), 4);
+    }
+
+    #[test]
+    fn blind_points_gives_expected_result() {
+        // arrange
+        let points = neighbourhood();
+
+        // act
+        let outcome = blind_points(45, 10, &points);
+
+        // assert
+        let numbers: Vec<u32> = outcome.iter().map(|Point { number, .. }| *number).collect();
+        assert_eq!(numbers, vec![2, 4]);
+    }
+
+    #[test]
+    fn coverage_queries_handle_empty_input_universe() {
+        // arrange
+        let points: Vec<Point> = vec![];
+
+        // act
+        let hidden = hidden_points(45, 10, &points);
+        let blind = blind_points(45, 10, &points);
+
+        // assert
+        assert_eq!(hidden.len(), 0);
+        assert_eq!(blind.len(), 0);
+        assert_eq!(sentinel_points(45, 10, &points).len(), 0);
+    }
+
+    #[test]
+    fn sentinel_points_gives_expected_result() {
+        // arrange
+        let mut points = neighbourhood();
+        points.push(Point {
+            coordinates: (-3, 4),
+            number: 5,
+            direction: Direction::East,
+        });
+        points[0].direction = Direction::East;
+        points[1].coordinates = (-1, 3);
+
+        // act
+        let outcome = sentinel_points(180, 10, &points);
+
+        // assert
+        let numbers: Vec<u32> = outcome.iter().map(|Point { number, .. }| *number).collect();
+        assert_eq!(numbers, vec![1]);
+
+        // act
+        let outcome = sentinel_points(45, 10, &points);
+
+        // assert
+        let numbers: Vec<u32> = outcome.iter().map(|Point { number, .. }| *number).collect();
+        assert_eq!(numbers, vec![3, 4]);
+    }
+}
@@ -0,0 +1,98 @@
+use crate::domain::point::Point;
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+};
+
+/// How [`deduplicate_points`] treats repeated records
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DuplicateStrategy {
+    /// Drop records with the same number and coordinates as an earlier one,
+    /// keeping points which share a number but differ in position
+    Exact,
+
+    /// Keep only the first record with each number
+    First,
+
+    /// Keep only the last record with each number, for example the latest
+    /// reading in an export appended to over time, at the position of the
+    /// first
+    Last,
+}
+
+impl FromStr for DuplicateStrategy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "exact" => Ok(DuplicateStrategy::Exact),
+            "first" => Ok(DuplicateStrategy::First),
+            "last" => Ok(DuplicateStrategy::Last),
+            _ => Err(format!(
+                "unknown duplicate strategy `{value}`, expected `exact`, `first` or `last`"
+            )),
+        }
+    }
+}
+
+/// Remove repeated records from `points`, as chosen by `strategy`, keeping
+/// the order of those left.  Returns the number of points removed.
+pub fn deduplicate_points(points: &mut Vec<Point>, strategy: DuplicateStrategy) -> usize {
+    let before = points.len();
+    match strategy {
+        DuplicateStrategy::Exact => {
+            let mut seen = HashSet::new();
+            points.retain(|point| seen.insert((point.number, point.coordinates)));
+        }
+        DuplicateStrategy::First => {
+            let mut seen = HashSet::new();
+            points.retain(|point| seen.insert(point.number));
+        }
+        DuplicateStrategy::Last => {
+            let latest: HashMap<u32, Point> =
+                points.iter().map(|point| (point.number, *point)).collect();
+            let mut seen = HashSet::new();
+            points.retain(|point| seen.insert(point.number));
+            for point in points.iter_mut() {
+                if let Some(value) = latest.get(&point.number) {
+                    *point = *value;
+                }
+            }
+        }
+    }
+    before - points.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{deduplicate_points, DuplicateStrategy};
+    use crate::domain::{test_support::point, Direction};
+
+    #[test]
+    fn deduplicate_points_gives_expected_result() {
+        // arrange
+        let points = vec![
+            point(1, (0, 0), Direction::North),
+            point(2, (5, 5), Direction::East),
+            point(1, (0, 0), Direction::South),
+            point(1, (3, 4), Direction::West),
+            point(3, (9, 9), Direction::North),
+        ];
+
+        // act
+        let mut exact = points.clone();
+        let exact_removed = deduplicate_points(&mut exact, DuplicateStrategy::Exact);
+        let mut first = points.clone();
+        let first_removed = deduplicate_points(&mut first, DuplicateStrategy::First);
+        let mut last = points.clone();
+        let last_removed = deduplicate_points(&mut last, DuplicateStrategy::Last);
+
+        // assert
+        assert_eq!((exact_removed, first_removed, last_removed), (1, 2, 2));
+        assert_eq!(exact, vec![points[0], points[1], points[3], points[4]]);
+        assert_eq!(first, vec![points[0], points[1], points[4]]);
+        assert_eq!(last, vec![points[3], points[1], points[4]]);
+        assert_eq!("last".parse(), Ok(DuplicateStrategy::Last));
+        assert!("mean".parse::<DuplicateStrategy>().is_err());
+    }
+}
@@ -0,0 +1,280 @@
+use crate::{
+    domain::point::{euclidean_distance, visible_neighbour, Point},
+    utilities::Span,
+};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// Delaunay triangulation of a neighbourhood
+#[derive(Clone, Debug, PartialEq)]
+pub struct Triangulation<'a> {
+    /// Points used as triangle vertices, in neighbourhood order.  Only the
+    /// first of any points sharing coordinates is included.
+    pub vertices: Vec<&'a Point>,
+
+    /// Indices into `vertices` of each triangle’s corners, in counter-clockwise
+    /// order
+    pub triangles: Vec<[usize; 3]>,
+}
+
+impl<'a> Triangulation<'a> {
+    /// Unique triangle edges, as pairs of indices into `vertices`, with the
+    /// lower index first, and sorted
+    pub fn edges(&self) -> Vec<(usize, usize)> {
+        let edges: BTreeSet<(usize, usize)> = self
+            .triangles
+            .iter()
+            .flat_map(|[a, b, c]| [(*a, *b), (*b, *c), (*c, *a)])
+            .map(|(a, b)| (a.min(b), a.max(b)))
+            .collect();
+        edges.into_iter().collect()
+    }
+
+    /// Vertices sharing a triangle edge with the vertex at `index`, in
+    /// `vertices` order
+    pub fn neighbours(&self, index: usize) -> Vec<&'a Point> {
+        self.edges()
+            .into_iter()
+            .filter_map(|(a, b)| match index {
+                val if val == a => Some(b),
+                val if val == b => Some(a),
+                _ => None,
+            })
+            .map(|val| self.vertices[val])
+            .collect()
+    }
+}
+
+/// Twice the signed area of triangle `a`, `b`, `c`, positive when the corners
+/// run counter-clockwise
+fn orientation((x_a, y_a): (f64, f64), (x_b, y_b): (f64, f64), (x_c, y_c): (f64, f64)) -> f64 {
+    (x_b - x_a) * (y_c - y_a) - (y_b - y_a) * (x_c - x_a)
+}
+
+/// Returns true if `d` lies strictly inside the circumcircle of the
+/// counter-clockwise triangle `a`, `b`, `c`
+fn inside_circumcircle(
+    (x_a, y_a): (f64, f64),
+    (x_b, y_b): (f64, f64),
+    (x_c, y_c): (f64, f64),
+    (x_d, y_d): (f64, f64),
+) -> bool {
+    let (a_x, a_y) = (x_a - x_d, y_a - y_d);
+    let (b_x, b_y) = (x_b - x_d, y_b - y_d);
+    let (c_x, c_y) = (x_c - x_d, y_c - y_d);
+    let determinant = (a_x * a_x + a_y * a_y) * (b_x * c_y - c_x * b_y)
+        - (b_x * b_x + b_y * b_y) * (a_x * c_y - c_x * a_y)
+        + (c_x * c_x + c_y * c_y) * (a_x * b_y - b_x * a_y);
+    determinant > 0.0
+}
+
+/// Return the Delaunay triangulation of `neighbourhood`, computed with the
+/// Bowyer-Watson algorithm.  For fewer than three points, or if all points are
+/// collinear, the triangulation has no triangles.
+pub fn delaunay_triangulation(neighbourhood: &[Point]) -> Triangulation<'_> {
+    let _span = Span::enter("delaunay_triangulation", || {
+        format!("{} points", neighbourhood.len())
+    });
+    let mut seen: HashSet<(i32, i32)> = HashSet::new();
+    let vertices: Vec<&Point> = neighbourhood
+        .iter()
+        .filter(|Point { coordinates, .. }| seen.insert(*coordinates))
+        .collect();
+    if vertices.len() < 3 {
+        return Triangulation {
+            vertices,
+            triangles: vec![],
+        };
+    }
+
+    // work relative to the bounding box centre, inside a large super triangle
+    let (min_x, min_y, max_x, max_y) = vertices.iter().fold(
+        (i32::MAX, i32::MAX, i32::MIN, i32::MIN),
+        |(min_x, min_y, max_x, max_y), point| {
+            let (x, y) = point.coordinates;
+            (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y))
+        },
+    );
+    let centre_x = (f64::from(min_x) + f64::from(max_x)) / 2.0;
+    let centre_y = (f64::from(min_y) + f64::from(max_y)) / 2.0;
+    let size = (f64::from(max_x) - f64::from(min_x))
+        .max(f64::from(max_y) - f64::from(min_y))
+        .max(1.0);
+    let mut coordinates: Vec<(f64, f64)> = vertices
+        .iter()
+        .map(|point| {
+            let (x, y) = point.coordinates;
+            (f64::from(x) - centre_x, f64::from(y) - centre_y)
+        })
+        .collect();
+    let super_index = coordinates.len();
+    coordinates.extend([
+        (-100.0 * size, -100.0 * size),
+        (100.0 * size, -100.0 * size),
+        (0.0, 100.0 * size),
+    ]);
+
+    let mut triangles: Vec<[usize; 3]> = vec![[super_index, super_index + 1, super_index + 2]];
+    for index in 0..super_index {
+        let point = coordinates[index];
+        let (bad, good): (Vec<[usize; 3]>, Vec<[usize; 3]>) =
+            triangles.into_iter().partition(|[a, b, c]| {
+                inside_circumcircle(coordinates[*a], coordinates[*b], coordinates[*c], point)
+            });
+
+        // edges of the cavity left by removing bad triangles appear once
+        let mut edge_counts: HashMap<(usize, usize), usize> = HashMap::new();
+        for [a, b, c] in &bad {
+            for (start, end) in [(*a, *b), (*b, *c), (*c, *a)] {
+                *edge_counts
+                    .entry((start.min(end), start.max(end)))
+                    .or_insert(0) += 1;
+            }
+        }
+        triangles = good;
+        for [a, b, c] in &bad {
+            for (start, end) in [(*a, *b), (*b, *c), (*c, *a)] {
+                if edge_counts[&(start.min(end), start.max(end))] == 1 {
+                    triangles.push([start, end, index]);
+                }
+            }
+        }
+    }
+
+    let triangles = triangles
+        .into_iter()
+        .filter(|corners| corners.iter().all(|corner| *corner < super_index))
+        .filter(|[a, b, c]| orientation(coordinates[*a], coordinates[*b], coordinates[*c]) > 0.0)
+        .collect();
+    Triangulation {
+        vertices,
+        triangles,
+    }
+}
+
+/// Return a vector of `neighbourhood` points sharing a Delaunay triangulation
+/// edge with the point identified by `point_number`, which also lie inside its
+/// segment of radius `arc_radius` units, spanning left and right from its
+/// direction by `half_arc_central_angle`.  `half_arc_central_angle` should be
+/// in degrees, and can range from zero to `180` degrees.
+///
+/// An empty vector is returned if no point matching `point_number` is found
+/// in neighbourhood.
+pub fn delaunay_visible_points(
+    point_number: u32,
+    half_arc_central_angle: u32,
+    arc_radius: u32,
+    neighbourhood: &[Point],
+) -> Vec<&Point> {
+    let point = match neighbourhood
+        .iter()
+        .find(|Point { number, .. }| *number == point_number)
+    {
+        Some(value) => value,
+        None => return vec![],
+    };
+    let triangulation = delaunay_triangulation(neighbourhood);
+    let index = match triangulation
+        .vertices
+        .iter()
+        .position(|Point { coordinates, .. }| *coordinates == point.coordinates)
+    {
+        Some(value) => value,
+        None => return vec![],
+    };
+    triangulation
+        .neighbours(index)
+        .into_iter()
+        .filter(|val| {
+            euclidean_distance(point.coordinates, val.coordinates) < arc_radius as f64
+                && visible_neighbour(point, val, half_arc_central_angle)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{delaunay_triangulation, delaunay_visible_points};
+    use crate::domain::{Direction, Point};
+
+    fn points_at(coordinates: &[(i32, i32)]) -> Vec<Point> {
+        coordinates
+            .iter()
+            .enumerate()
+            .map(|(index, coordinates)| Point {
+                coordinates: *coordinates,
+                number: index as u32 + 1,
+                direction: Direction::North,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn delaunay_triangulation_gives_expected_result() {
+        // arrange
+        let points = points_at(&[(0, 0), (10, 0), (10, 10), (0, 10), (5, 4)]);
+
+        // act
+        let outcome = delaunay_triangulation(&points);
+
+        // assert
+        assert_eq!(outcome.vertices.len(), 5);
+        assert_eq!(outcome.triangles.len(), 4);
+        assert_eq!(
+            outcome.edges(),
+            vec![
+                (0, 1),
+                (0, 3),
+                (0, 4),
+                (1, 2),
+                (1, 4),
+                (2, 3),
+                (2, 4),
+                (3, 4)
+            ]
+        );
+        let numbers: Vec<u32> = outcome
+            .neighbours(4)
+            .iter()
+            .map(|Point { number, .. }| *number)
+            .collect();
+        assert_eq!(numbers, vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn delaunay_triangulation_handles_degenerate_input() {
+        // arrange
+        let points = points_at(&[(0, 0), (1, 1), (2, 2), (2, 2)]);
+
+        // act
+        let outcome = delaunay_triangulation(&points);
+
+        // assert
+        assert_eq!(outcome.vertices.len(), 3);
+        assert_eq!(outcome.triangles.len(), 0);
+
+        // act
+        let outcome = delaunay_triangulation(&[]);
+
+        // assert
+        assert_eq!(outcome.triangles.len(), 0);
+    }
+
+    #[test]
+    fn delaunay_visible_points_gives_expected_result() {
+        // arrange
+        let points = points_at(&[(0, 0), (-1, 5), (1, 5), (0, 10)]);
+
+        // act
+        let outcome = delaunay_visible_points(1, 45, 20, &points);
+
+        // assert
+        let numbers: Vec<u32> = outcome.iter().map(|Point { number, .. }| *number).collect();
+        assert_eq!(numbers, vec![2, 3]);
+
+        // act
+        let outcome = delaunay_visible_points(99, 45, 20, &points);
+
+        // assert
+        assert_eq!(outcome.len(), 0);
+    }
+}
@@ -0,0 +1,247 @@
+use crate::domain::point::{close_neighbours, euclidean_distance, Point};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// Point present in both neighbourhoods, but at different coordinates
+#[derive(Clone, Debug, PartialEq)]
+pub struct MovedPoint<'a> {
+    pub old: &'a Point,
+    pub new: &'a Point,
+
+    /// Distance between the old and new coordinates
+    pub distance: f64,
+}
+
+/// Changes between two versions of a neighbourhood, matching points by number
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NeighbourhoodDiff<'a> {
+    /// Points only in the new neighbourhood, in new neighbourhood order
+    pub added: Vec<&'a Point>,
+
+    /// Points only in the old neighbourhood, in old neighbourhood order
+    pub removed: Vec<&'a Point>,
+
+    /// Points whose coordinates changed, in new neighbourhood order
+    pub moved: Vec<MovedPoint<'a>>,
+}
+
+impl<'a> NeighbourhoodDiff<'a> {
+    /// Returns true if no points were added, removed or moved
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.moved.is_empty()
+    }
+}
+
+/// Compare `old` and `new` versions of a neighbourhood, for example to review
+/// a dataset update before re-running analyses.  Points are matched by number,
+/// and a point counts as moved only when its coordinates change.  If several
+/// points share a number, only the first is matched.
+pub fn diff_neighbourhoods<'a>(old: &'a [Point], new: &'a [Point]) -> NeighbourhoodDiff<'a> {
+    let mut old_by_number: HashMap<u32, &Point> = HashMap::new();
+    for point in old {
+        old_by_number.entry(point.number).or_insert(point);
+    }
+    let mut new_by_number: HashMap<u32, &Point> = HashMap::new();
+    for point in new {
+        new_by_number.entry(point.number).or_insert(point);
+    }
+
+    let removed = old
+        .iter()
+        .filter(|Point { number, .. }| !new_by_number.contains_key(number))
+        .collect();
+    let added = new
+        .iter()
+        .filter(|Point { number, .. }| !old_by_number.contains_key(number))
+        .collect();
+    let moved = new
+        .iter()
+        .filter(|point| std::ptr::eq(new_by_number[&point.number], *point))
+        .filter_map(|point| {
+            old_by_number
+                .get(&point.number)
+                .filter(|old_point| old_point.coordinates != point.coordinates)
+                .map(|old_point| MovedPoint {
+                    old: old_point,
+                    new: point,
+                    distance: euclidean_distance(old_point.coordinates, point.coordinates),
+                })
+        })
+        .collect();
+    NeighbourhoodDiff {
+        added,
+        removed,
+        moved,
+    }
+}
+
+/// Change in what one origin point sees between two versions of a
+/// neighbourhood
+#[derive(Clone, Debug, PartialEq)]
+pub struct VisibilityChange<'a> {
+    /// Number of the origin point
+    pub origin: u32,
+
+    /// New neighbourhood points visible now, but not before, in new
+    /// neighbourhood order
+    pub became_visible: Vec<&'a Point>,
+
+    /// Old neighbourhood points visible before, but not now, in old
+    /// neighbourhood order
+    pub stopped_being_visible: Vec<&'a Point>,
+}
+
+/// Numbers of the first point with each number in `neighbourhood`, mapped to
+/// the numbers of points it sees
+fn visible_numbers(
+    half_arc_central_angle: u32,
+    arc_radius: u32,
+    neighbourhood: &[Point],
+) -> HashMap<u32, HashSet<u32>> {
+    let mut result = HashMap::new();
+    for point in neighbourhood {
+        result.entry(point.number).or_insert_with(|| {
+            close_neighbours(point, half_arc_central_angle, arc_radius, neighbourhood)
+                .into_iter()
+                .map(|Point { number, .. }| *number)
+                .collect()
+        });
+    }
+    result
+}
+
+/// Compare what each point sees in `old` and `new` versions of a
+/// neighbourhood, using the same segment for both: radius `arc_radius` units,
+/// spanning left and right from each point’s direction by
+/// `half_arc_central_angle` degrees.  Points are matched by number, so an
+/// origin only in one version sees nothing in the other.
+///
+/// Only origins whose visible points changed are returned, ordered by number.
+pub fn compare_visibility<'a>(
+    half_arc_central_angle: u32,
+    arc_radius: u32,
+    old: &'a [Point],
+    new: &'a [Point],
+) -> Vec<VisibilityChange<'a>> {
+    let old_visible = visible_numbers(half_arc_central_angle, arc_radius, old);
+    let new_visible = visible_numbers(half_arc_central_angle, arc_radius, new);
+    let empty = HashSet::new();
+    let origins: BTreeSet<u32> = old_visible
+        .keys()
+        .chain(new_visible.keys())
+        .copied()
+        .collect();
+    origins
+        .into_iter()
+        .filter_map(|origin| {
+            let before = old_visible.get(&origin).unwrap_or(&empty);
+            let after = new_visible.get(&origin).unwrap_or(&empty);
+            let mut seen: HashSet<u32> = HashSet::new();
+            let became_visible: Vec<&Point> = new
+                .iter()
+                .filter(|Point { number, .. }| {
+                    after.contains(number) && !before.contains(number) && seen.insert(*number)
+                })
+                .collect();
+            seen.clear();
+            let stopped_being_visible: Vec<&Point> = old
+                .iter()
+                .filter(|Point { number, .. }| {
+                    before.contains(number) && !after.contains(number) && seen.insert(*number)
+                })
+                .collect();
+            match (became_visible.is_empty(), stopped_being_visible.is_empty()) {
+                (true, true) => None,
+                _ => Some(VisibilityChange {
+                    origin,
+                    became_visible,
+                    stopped_being_visible,
+                }),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compare_visibility, diff_neighbourhoods};
+    use crate::domain::{test_support::north_point, Direction, Point};
+
+    fn numbers(points: &[&Point]) -> Vec<u32> {
+        points.iter().map(|Point { number, .. }| *number).collect()
+    }
+
+    #[test]
+    fn diff_neighbourhoods_gives_expected_result() {
+        // arrange
+        let old = vec![
+            north_point(1, (0, 0)),
+            north_point(2, (5, 5)),
+            north_point(3, (1, 1)),
+        ];
+        let new = vec![
+            north_point(4, (9, 9)),
+            north_point(2, (8, 9)),
+            north_point(1, (0, 0)),
+        ];
+
+        // act
+        let outcome = diff_neighbourhoods(&old, &new);
+
+        // assert
+        assert_eq!(outcome.added, vec![&new[0]]);
+        assert_eq!(outcome.removed, vec![&old[2]]);
+        assert_eq!(outcome.moved.len(), 1);
+        assert_eq!(outcome.moved[0].old, &old[1]);
+        assert_eq!(outcome.moved[0].new, &new[1]);
+        assert!((outcome.moved[0].distance - 5.0).abs() < 1e-10);
+        assert!(!outcome.is_empty());
+    }
+
+    #[test]
+    fn diff_neighbourhoods_ignores_unchanged_points() {
+        // arrange
+        let old = vec![north_point(1, (0, 0)), north_point(2, (5, 5))];
+        let mut new = old.clone();
+        new[1].direction = Direction::West;
+
+        // act
+        let outcome = diff_neighbourhoods(&old, &new);
+
+        // assert
+        assert!(outcome.is_empty());
+    }
+
+    #[test]
+    fn compare_visibility_gives_expected_result() {
+        // arrange
+        let old = vec![
+            north_point(1, (0, 0)),
+            north_point(2, (0, 5)),
+            north_point(3, (6, 0)),
+        ];
+        let new = vec![
+            north_point(1, (0, 0)),
+            north_point(2, (10, 0)),
+            north_point(3, (1, 5)),
+            north_point(4, (0, -3)),
+        ];
+
+        // act
+        let outcome = compare_visibility(45, 20, &old, &new);
+
+        // assert
+        assert_eq!(outcome.len(), 2);
+        assert_eq!(outcome[0].origin, 1);
+        assert_eq!(numbers(&outcome[0].became_visible), vec![3]);
+        assert_eq!(numbers(&outcome[0].stopped_being_visible), vec![2]);
+        assert_eq!(outcome[1].origin, 4);
+        assert_eq!(numbers(&outcome[1].became_visible), vec![1, 3]);
+        assert_eq!(outcome[1].stopped_being_visible.len(), 0);
+
+        // act
+        let outcome = compare_visibility(45, 20, &old, &old);
+
+        // assert
+        assert_eq!(outcome.len(), 0);
+    }
+}
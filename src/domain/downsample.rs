@@ -0,0 +1,109 @@
+use crate::{domain::point::Point, utilities::Span};
+use std::{cmp::Ordering, collections::HashMap};
+
+/// Points thinned by [`downsample_points`], with the numbers of the points
+/// each stands for
+#[derive(Clone, Debug, PartialEq)]
+pub struct Downsampled {
+    /// One representative point per occupied cell, in the order their cells
+    /// were first reached in the input
+    pub points: Vec<Point>,
+
+    /// Numbers of the input points in each representative's cell, itself
+    /// included, in input order, keyed by the representative's number
+    pub members: HashMap<u32, Vec<u32>>,
+}
+
+/// Thin `points` to one per square cell, `cell_size` units across, so dense
+/// inputs, such as million-point scans, become tractable to query.  Each cell
+/// keeps the point nearest the mean position of the points in it, taking the
+/// lowest numbered of those equally near, with its own number and direction,
+/// so results refer to original points.  [`Downsampled::members`] maps each
+/// back to the points it stands for.
+pub fn downsample_points(points: &[Point], cell_size: u32) -> Downsampled {
+    let _span = Span::enter("downsample_points", || {
+        format!("{} points in cells of {cell_size}", points.len())
+    });
+    let cell_size = i64::from(cell_size.max(1));
+    let mut cells: HashMap<(i64, i64), Vec<&Point>> = HashMap::new();
+    let mut order = vec![];
+    for point in points {
+        let (x, y) = point.coordinates;
+        let cell = (
+            i64::from(x).div_euclid(cell_size),
+            i64::from(y).div_euclid(cell_size),
+        );
+        let members = cells.entry(cell).or_insert_with(|| {
+            order.push(cell);
+            vec![]
+        });
+        members.push(point);
+    }
+
+    let mut result = Downsampled {
+        points: Vec::with_capacity(order.len()),
+        members: HashMap::with_capacity(order.len()),
+    };
+    for cell in order {
+        let members = &cells[&cell];
+        let count = members.len() as f64;
+        let (sum_x, sum_y) = members.iter().fold((0.0, 0.0), |(x, y), point| {
+            (
+                x + f64::from(point.coordinates.0),
+                y + f64::from(point.coordinates.1),
+            )
+        });
+        let (mean_x, mean_y) = (sum_x / count, sum_y / count);
+        let distance = |point: &Point| {
+            (f64::from(point.coordinates.0) - mean_x).hypot(f64::from(point.coordinates.1) - mean_y)
+        };
+        let representative = members
+            .iter()
+            .copied()
+            .min_by(|a, b| {
+                distance(a)
+                    .partial_cmp(&distance(b))
+                    .unwrap_or(Ordering::Equal)
+                    .then(a.number.cmp(&b.number))
+            })
+            .copied();
+        if let Some(representative) = representative {
+            result.points.push(representative);
+            result
+                .members
+                .entry(representative.number)
+                .or_default()
+                .extend(members.iter().map(|point| point.number));
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::downsample_points;
+    use crate::domain::test_support::north_point;
+
+    #[test]
+    fn downsample_points_keeps_one_point_per_cell() {
+        // arrange
+        let points = vec![
+            north_point(1, (0, 0)),
+            north_point(2, (9, 9)),
+            north_point(3, (-1, 4)),
+            north_point(4, (4, 5)),
+            north_point(5, (5, 4)),
+            north_point(6, (12, 3)),
+        ];
+
+        // act
+        let outcome = downsample_points(&points, 10);
+
+        // assert
+        let numbers: Vec<u32> = outcome.points.iter().map(|point| point.number).collect();
+        assert_eq!(numbers, vec![4, 3, 6]);
+        assert_eq!(outcome.members[&4], vec![1, 2, 4, 5]);
+        assert_eq!(outcome.members[&3], vec![3]);
+        assert_eq!(downsample_points(&points, 1).points, points);
+    }
+}
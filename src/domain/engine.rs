@@ -0,0 +1,778 @@
+use crate::{
+    domain::{
+        ball_tree::BallTree,
+        batch::Query,
+        hnsw::{HnswIndex, HnswParameters},
+        index_selection::{choose_index, Workload},
+        metric::Euclidean,
+        point::{can_see, parse_points_file, resolve_direction, sees, Point},
+        spatial_index::{IndexBackend, SpatialIndex},
+    },
+    utilities::{AppError, Span},
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    path::Path,
+    sync::{Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+
+/// Point number, half arc central angle and radius of a cached query
+type QueryKey = (u32, u32, u32);
+
+/// Least recently used cache of query results
+#[derive(Debug)]
+struct QueryCache {
+    capacity: usize,
+
+    /// Cached results, each with the tick it was last used at
+    entries: HashMap<QueryKey, (Vec<Point>, u64)>,
+
+    /// Keys of cached results, ordered by tick last used
+    recency: BTreeMap<u64, QueryKey>,
+
+    tick: u64,
+    stats: CacheStats,
+}
+
+impl QueryCache {
+    fn new(capacity: usize) -> Self {
+        QueryCache {
+            capacity,
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            tick: 0,
+            stats: CacheStats::default(),
+        }
+    }
+
+    fn get(&mut self, key: &QueryKey) -> Option<Vec<Point>> {
+        self.tick += 1;
+        match self.entries.get_mut(key) {
+            Some((value, used)) => {
+                self.recency.remove(used);
+                self.recency.insert(self.tick, *key);
+                *used = self.tick;
+                self.stats.hits += 1;
+                Some(value.clone())
+            }
+            None => {
+                self.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    fn insert(&mut self, key: QueryKey, value: Vec<Point>) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(&key) {
+            let oldest = self.recency.keys().next().copied();
+            if let Some(used) = oldest {
+                if let Some(evicted) = self.recency.remove(&used) {
+                    self.entries.remove(&evicted);
+                }
+            }
+        }
+        self.tick += 1;
+        if let Some((_, used)) = self.entries.insert(key, (value, self.tick)) {
+            self.recency.remove(&used);
+        }
+        self.recency.insert(self.tick, key);
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+    }
+}
+
+/// Use of an engine’s query result cache, see
+/// [`VisibilityEngine::with_cache`]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct CacheStats {
+    /// Queries answered from the cache
+    pub hits: u64,
+
+    /// Queries computed, and then cached
+    pub misses: u64,
+}
+
+/// Points, with any index over them, kept together under one lock, so
+/// queries never see an index built for other points
+#[derive(Default)]
+struct Neighbourhood {
+    points: Vec<Point>,
+    index: Option<Box<dyn SpatialIndex + Send + Sync>>,
+
+    /// Built-in backend of the index, if it is one
+    backend: Option<IndexBackend>,
+
+    /// Expected use, from which the backend is picked again after updates
+    workload: Option<Workload>,
+
+    /// Points changed since the index was built, so the next query rebuilds
+    /// it first
+    stale: bool,
+}
+
+impl fmt::Debug for Neighbourhood {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("Neighbourhood")
+            .field("points", &self.points)
+            .field("indexed", &self.index.is_some())
+            .field("backend", &self.backend)
+            .field("workload", &self.workload)
+            .field("stale", &self.stale)
+            .finish()
+    }
+}
+
+impl Neighbourhood {
+    /// Points which may lie closer than `radius` to `centre`: those the index
+    /// finds, in no particular order, or every point, in neighbourhood order,
+    /// without an index
+    fn candidates(&self, centre: (i32, i32), radius: u32) -> Box<dyn Iterator<Item = &Point> + '_> {
+        match &self.index {
+            Some(index) => index.range_query(centre, f64::from(radius)),
+            None => Box::new(self.points.iter()),
+        }
+    }
+
+    /// Note the points changed, leaving any index, or backend picked for a
+    /// workload, to be rebuilt by the next query, so a burst of updates costs
+    /// one rebuild, rather than one each
+    fn changed(&mut self) {
+        self.stale = self.index.is_some() || self.workload.is_some();
+    }
+
+    /// Rebuild the index, if any, after the points change, first picking the
+    /// backend again, for an expected workload
+    fn reindex(&mut self) {
+        self.stale = false;
+        if let Some(workload) = &self.workload {
+            let backend = choose_index(&self.points, workload);
+            if self.backend != Some(backend) {
+                self.use_backend(backend);
+                return;
+            }
+        }
+        if let Some(index) = &mut self.index {
+            index.rebuild(&self.points);
+        }
+    }
+
+    /// Replace the index with one of the built-in `backend`, leaving a linear
+    /// scan without one, as checking every point needs no copy of them
+    fn use_backend(&mut self, backend: IndexBackend) {
+        self.index = match backend {
+            IndexBackend::Linear => None,
+            _ => Some(backend.build(&self.points)),
+        };
+        self.backend = Some(backend);
+        self.stale = false;
+    }
+}
+
+/// Neighbourhood shared between threads, for example by web server workers
+/// answering queries against one loaded dataset.  The engine is `Send` and
+/// `Sync`, so wrap it in an [`Arc`](std::sync::Arc) to share it.  Queries
+/// take a read lock, so any number run at once, and only updates take the
+/// write lock.
+#[derive(Debug, Default)]
+pub struct VisibilityEngine {
+    neighbourhood: RwLock<Neighbourhood>,
+    cache: Option<Mutex<QueryCache>>,
+}
+
+impl VisibilityEngine {
+    pub fn new(points: Vec<Point>) -> Self {
+        VisibilityEngine {
+            neighbourhood: RwLock::new(Neighbourhood {
+                points,
+                index: None,
+                backend: None,
+                workload: None,
+                stale: false,
+            }),
+            cache: None,
+        }
+    }
+
+    /// Keep results of up to `capacity` recent queries, answering repeats,
+    /// such as a dashboard refreshing every second, without redoing the
+    /// geometry.  The least recently used result is dropped when the cache is
+    /// full, and every update to the neighbourhood empties the cache.
+    pub fn with_cache(self, capacity: usize) -> Self {
+        VisibilityEngine {
+            cache: Some(Mutex::new(QueryCache::new(capacity))),
+            ..self
+        }
+    }
+
+    /// Answer queries from `index`, asking it for points near each query,
+    /// rather than checking every point.  The index is rebuilt over the
+    /// neighbourhood now, and by the first query after any updates to it.
+    pub fn with_index<I: SpatialIndex + Send + Sync + 'static>(self, index: I) -> Self {
+        {
+            let mut neighbourhood = self.write();
+            let mut index: Box<dyn SpatialIndex + Send + Sync> = Box::new(index);
+            index.rebuild(&neighbourhood.points);
+            neighbourhood.index = Some(index);
+            neighbourhood.backend = None;
+            neighbourhood.workload = None;
+            neighbourhood.stale = false;
+        }
+        self.invalidate();
+        self
+    }
+
+    /// Answer queries from an index of the built-in `backend`, overriding any
+    /// picked for a workload
+    pub fn with_backend(self, backend: IndexBackend) -> Self {
+        {
+            let mut neighbourhood = self.write();
+            neighbourhood.use_backend(backend);
+            neighbourhood.workload = None;
+        }
+        self.invalidate();
+        self
+    }
+
+    /// Answer queries from whichever exact backend suits `workload`, picked by
+    /// [`choose_index`] from the neighbourhood's size and extent, and picked
+    /// again after updates, so the engine keeps up as points come and go.
+    /// [`VisibilityEngine::with_backend`] overrides the choice.
+    ///
+    /// Picking takes a pass over every point, building a grid one more, and
+    /// building a ball tree `n log n` work, so neither runs on each update:
+    /// the first query after any number of updates picks and builds once,
+    /// holding the write lock while it does.  Updates interleaved one for one
+    /// with queries still pay that on every query, which `workload.queries`
+    /// should then reflect, steering the choice to a linear scan.
+    pub fn with_workload(self, workload: Workload) -> Self {
+        {
+            let mut neighbourhood = self.write();
+            let backend = choose_index(&neighbourhood.points, &workload);
+            neighbourhood.use_backend(backend);
+            neighbourhood.workload = Some(workload);
+        }
+        self.invalidate();
+        self
+    }
+
+    /// Built-in backend queries are answered from, if picked for a workload
+    /// or given to [`VisibilityEngine::with_backend`], or `None` for an index
+    /// of any other kind, or none
+    pub fn index_backend(&self) -> Option<IndexBackend> {
+        self.read_indexed().backend
+    }
+
+    /// Answer queries from an [`HnswIndex`] over the neighbourhood, rather
+    /// than scanning every point, for approximate, but fast, queries on huge
+    /// datasets.  `parameters.search_breadth` trades recall against speed.
+    /// The first query after updates to the neighbourhood rebuilds the index,
+    /// so this suits neighbourhoods which seldom change.
+    pub fn with_hnsw(self, parameters: HnswParameters) -> Self {
+        self.with_index(HnswIndex::new(&[], parameters))
+    }
+
+    /// Answer queries from a [`BallTree`] over the neighbourhood, giving the
+    /// same results as scanning every point, but checking only points near
+    /// each query.  The first query after updates to the neighbourhood
+    /// rebuilds the tree.
+    pub fn with_ball_tree(self) -> Self {
+        self.with_index(BallTree::new(&[], Euclidean))
+    }
+
+    /// Hits and misses of the query result cache, or `None` if the engine
+    /// has no cache
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.cache().map(|cache| cache.stats)
+    }
+
+    fn cache(&self) -> Option<MutexGuard<'_, QueryCache>> {
+        self.cache.as_ref().map(|cache| {
+            cache
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+        })
+    }
+
+    /// Empty the query result cache, called with the write lock held, so no
+    /// query still running against the old neighbourhood can cache its result
+    fn invalidate(&self) {
+        if let Some(mut cache) = self.cache() {
+            cache.clear();
+        }
+    }
+
+    /// Load the engine’s neighbourhood from a points file, as for
+    /// [`parse_points_file`]
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, AppError> {
+        parse_points_file(path).map(VisibilityEngine::new)
+    }
+
+    /// Load the engine’s neighbourhood from a points file on a background
+    /// thread, as for [`parse_points_file_async`](crate::domain::parse_points_file_async)
+    #[cfg(feature = "async")]
+    pub fn from_file_async<P: Into<std::path::PathBuf>>(
+        path: P,
+    ) -> crate::domain::Loading<Result<Self, AppError>> {
+        let path = path.into();
+        crate::domain::Loading::spawn(move || VisibilityEngine::from_file(path))
+    }
+
+    // a writer panicking part way through an update leaves a valid, if
+    // partly updated, neighbourhood, so poisoned locks are still used
+    fn read(&self) -> RwLockReadGuard<'_, Neighbourhood> {
+        self.neighbourhood
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, Neighbourhood> {
+        self.neighbourhood
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Read lock on the neighbourhood, with its index up to date, rebuilding
+    /// it first under the write lock if updates left it stale.  Checks again
+    /// after each rebuild, since another update may land before the read
+    /// lock is taken.
+    fn read_indexed(&self) -> RwLockReadGuard<'_, Neighbourhood> {
+        loop {
+            let neighbourhood = self.read();
+            if !neighbourhood.stale {
+                return neighbourhood;
+            }
+            drop(neighbourhood);
+            let mut neighbourhood = self.write();
+            if neighbourhood.stale {
+                neighbourhood.reindex();
+            }
+        }
+    }
+
+    /// Number of points in the neighbourhood
+    pub fn len(&self) -> usize {
+        self.read().points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.read().points.is_empty()
+    }
+
+    /// Copy of the current neighbourhood
+    pub fn points(&self) -> Vec<Point> {
+        self.read().points.clone()
+    }
+
+    /// Points visible from the point numbered `point_number`, as for
+    /// [`visible_points_from_neighbours`](crate::domain::visible_points_from_neighbours),
+    /// copied so the read lock is released before returning
+    pub fn visible_points(
+        &self,
+        point_number: u32,
+        half_arc_central_angle: u32,
+        arc_radius: u32,
+    ) -> Vec<Point> {
+        let neighbourhood = self.read_indexed();
+        let points = &neighbourhood.points;
+        let key = (point_number, half_arc_central_angle, arc_radius);
+        if let Some(value) = self.cache().and_then(|mut cache| cache.get(&key)) {
+            return value;
+        }
+        let _span = Span::enter("engine_visible_points", || {
+            format!("point {point_number} of {}", points.len())
+        });
+        let result: Vec<Point> = match points
+            .iter()
+            .find(|Point { number, .. }| *number == point_number)
+        {
+            Some(value) => {
+                let mut visible: Vec<Point> = neighbourhood
+                    .candidates(value.coordinates, arc_radius)
+                    .filter(|neighbour| sees(value, neighbour, half_arc_central_angle, arc_radius))
+                    .copied()
+                    .collect();
+                visible.sort_by_key(|Point { number, .. }| *number);
+                visible
+            }
+            None => vec![],
+        };
+        if let Some(mut cache) = self.cache() {
+            cache.insert(key, result.clone());
+        }
+        result
+    }
+
+    /// Returns true if the point numbered `from` sees the point numbered `to`,
+    /// as for [`can_see`], without finding every visible point
+    pub fn can_see(
+        &self,
+        from: u32,
+        to: u32,
+        half_arc_central_angle: u32,
+        arc_radius: u32,
+    ) -> Result<bool, AppError> {
+        can_see(
+            from,
+            to,
+            half_arc_central_angle,
+            arc_radius,
+            &self.read().points,
+        )
+    }
+
+    /// Write numbers of points visible for `query` into `visible`, as for
+    /// [`visible_points_into`](crate::domain::visible_points_into), clearing
+    /// it first, so callers reusing the
+    /// buffer, say once per frame, query without allocating.  The query result
+    /// cache is neither read nor updated, since both would copy the result.
+    pub fn visible_points_into(&self, query: &Query, visible: &mut Vec<u32>) {
+        let Query {
+            point_number,
+            angle,
+            radius,
+        } = *query;
+        visible.clear();
+        let neighbourhood = self.read_indexed();
+        if let Some(point) = neighbourhood
+            .points
+            .iter()
+            .find(|Point { number, .. }| *number == point_number)
+        {
+            visible.extend(
+                neighbourhood
+                    .candidates(point.coordinates, radius)
+                    .filter(|neighbour| sees(point, neighbour, angle, radius))
+                    .map(|Point { number, .. }| number),
+            );
+            visible.sort_unstable();
+        }
+    }
+
+    /// Run `query` against the current neighbourhood, holding the read lock,
+    /// for analyses beyond [`VisibilityEngine::visible_points`]
+    pub fn with_points<T, F: FnOnce(&[Point]) -> T>(&self, query: F) -> T {
+        query(&self.read().points)
+    }
+
+    /// Replace the whole neighbourhood, for example after reloading a file
+    pub fn replace(&self, points: Vec<Point>) {
+        let mut neighbourhood = self.write();
+        neighbourhood.points = points;
+        neighbourhood.changed();
+        self.invalidate();
+    }
+
+    /// Update the first point numbered as `point` is, or add `point` if no
+    /// point has its number.  A direction towards another point is resolved
+    /// against the current neighbourhood, failing if that point is missing or
+    /// at the same position.  Returns true if the neighbourhood changed.
+    pub fn upsert(&self, point: Point) -> Result<bool, AppError> {
+        let mut neighbourhood = self.write();
+        let point = resolve_direction(point, &neighbourhood.points)?;
+        match neighbourhood
+            .points
+            .iter_mut()
+            .find(|Point { number, .. }| *number == point.number)
+        {
+            Some(value) if *value == point => return Ok(false),
+            Some(value) => *value = point,
+            None => neighbourhood.points.push(point),
+        }
+        neighbourhood.changed();
+        self.invalidate();
+        Ok(true)
+    }
+
+    /// Remove all points numbered `point_number`, returning true if any were
+    /// removed
+    pub fn remove(&self, point_number: u32) -> bool {
+        let mut neighbourhood = self.write();
+        let before = neighbourhood.points.len();
+        neighbourhood
+            .points
+            .retain(|Point { number, .. }| *number != point_number);
+        let removed = neighbourhood.points.len() != before;
+        if removed {
+            neighbourhood.changed();
+            self.invalidate();
+        }
+        removed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CacheStats, VisibilityEngine};
+    use crate::domain::{
+        test_support::point, visible_points_from_neighbours, Direction, HnswParameters,
+        IndexBackend, LinearScan, Point, Query, SpatialIndex, Workload,
+    };
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        thread,
+    };
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn visibility_engine_answers_concurrent_queries() {
+        // arrange
+        assert_send_sync::<VisibilityEngine>();
+        let points = vec![
+            point(1, (0, 0), Direction::North),
+            point(2, (0, 5), Direction::South),
+            point(3, (3, 4), Direction::West),
+        ];
+        let engine = Arc::new(VisibilityEngine::new(points.clone()));
+
+        // act
+        let handles: Vec<_> = (1..=3)
+            .map(|number| {
+                let engine = Arc::clone(&engine);
+                thread::spawn(move || engine.visible_points(number, 45, 20))
+            })
+            .collect();
+        let outcome: Vec<Vec<Point>> = handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect();
+
+        // assert
+        for (index, visible) in outcome.iter().enumerate() {
+            let expected: Vec<Point> =
+                visible_points_from_neighbours(index as u32 + 1, 45, 20, &points)
+                    .into_iter()
+                    .copied()
+                    .collect();
+            assert_eq!(visible, &expected);
+        }
+        assert_eq!(engine.with_points(|points| points.len()), 3);
+        assert!(engine.can_see(1, 3, 45, 20).unwrap());
+        assert!(!engine.can_see(1, 3, 30, 20).unwrap());
+        assert!(engine.can_see(1, 9, 45, 20).is_err());
+    }
+
+    #[test]
+    fn visibility_engine_applies_updates() {
+        // arrange
+        let engine = VisibilityEngine::new(vec![point(1, (0, 0), Direction::North)]);
+
+        // act
+        let added = engine.upsert(point(2, (0, 5), Direction::South)).unwrap();
+        let unchanged = engine.upsert(point(2, (0, 5), Direction::South)).unwrap();
+        let visible = engine.visible_points(1, 45, 20);
+        let moved = engine.upsert(point(2, (0, -5), Direction::South)).unwrap();
+        let removed = engine.remove(1);
+        let missing = engine.remove(1);
+
+        // assert
+        assert_eq!(
+            (added, unchanged, moved, removed, missing),
+            (true, false, true, true, false)
+        );
+        assert_eq!(visible, vec![point(2, (0, 5), Direction::South)]);
+        assert_eq!(engine.points(), vec![point(2, (0, -5), Direction::South)]);
+        engine.replace(vec![]);
+        assert!(engine.is_empty());
+        assert_eq!(engine.len(), 0);
+    }
+
+    #[test]
+    fn visibility_engine_resolves_upserted_directions_towards_points() {
+        // arrange
+        let engine = VisibilityEngine::new(vec![point(1, (3, -4), Direction::North)]);
+
+        // act
+        let added = engine.upsert(point(2, (0, 0), Direction::Towards(1)));
+
+        // assert
+        assert!(added.unwrap());
+        assert_eq!(
+            engine.points()[1].direction,
+            Direction::Vector { dx: 3.0, dy: -4.0 }
+        );
+        assert!(engine
+            .upsert(point(3, (0, 0), Direction::Towards(9)))
+            .is_err());
+        assert!(engine
+            .upsert(point(3, (3, -4), Direction::Towards(1)))
+            .is_err());
+        assert_eq!(engine.len(), 2);
+    }
+
+    #[test]
+    fn visibility_engine_caches_recent_queries() {
+        // arrange
+        let engine = VisibilityEngine::new(vec![
+            point(1, (0, 0), Direction::North),
+            point(2, (0, 5), Direction::South),
+        ])
+        .with_cache(2);
+
+        // act
+        let first = engine.visible_points(1, 45, 20);
+        let repeated = engine.visible_points(1, 45, 20);
+        engine.visible_points(2, 45, 20);
+        engine.visible_points(2, 45, 10);
+        let evicted = engine.visible_points(1, 45, 20);
+        let stats = engine.cache_stats();
+        engine.upsert(point(2, (0, -5), Direction::South)).unwrap();
+        let updated = engine.visible_points(1, 45, 20);
+
+        // assert
+        assert_eq!(first, vec![point(2, (0, 5), Direction::South)]);
+        assert_eq!(repeated, first);
+        assert_eq!(evicted, first);
+        assert_eq!(stats, Some(CacheStats { hits: 1, misses: 4 }));
+        assert_eq!(updated, vec![]);
+        let mut visible = vec![];
+        engine.visible_points_into(
+            &Query {
+                point_number: 2,
+                angle: 180,
+                radius: 20,
+            },
+            &mut visible,
+        );
+        assert_eq!(visible, vec![1]);
+        assert_eq!(
+            engine.cache_stats(),
+            Some(CacheStats { hits: 1, misses: 5 })
+        );
+        assert_eq!(VisibilityEngine::default().cache_stats(), None);
+    }
+
+    #[test]
+    fn visibility_engine_answers_queries_from_hnsw_index() {
+        // arrange
+        let points: Vec<Point> = (0..225)
+            .map(|index| {
+                point(
+                    index as u32 + 1,
+                    ((index % 15) * 4, (index / 15) * 4),
+                    Direction::North,
+                )
+            })
+            .collect();
+        let engine = VisibilityEngine::new(points.clone()).with_hnsw(HnswParameters::default());
+        let exact = VisibilityEngine::new(points.clone()).with_ball_tree();
+
+        // act
+        let outcome = engine.visible_points(113, 60, 15);
+        engine.remove(143);
+        let updated = engine.visible_points(113, 60, 15);
+
+        // assert
+        let expected: Vec<Point> = visible_points_from_neighbours(113, 60, 15, &points)
+            .into_iter()
+            .copied()
+            .collect();
+        assert_eq!(outcome, expected);
+        assert!(outcome.iter().any(|point| point.number == 143));
+        assert_eq!(exact.visible_points(113, 60, 15), expected);
+        assert_eq!(updated.len(), outcome.len() - 1);
+        let mut visible = vec![];
+        engine.visible_points_into(
+            &Query {
+                point_number: 113,
+                angle: 60,
+                radius: 15,
+            },
+            &mut visible,
+        );
+        assert_eq!(visible.len(), updated.len());
+    }
+
+    #[test]
+    fn visibility_engine_rebuilds_index_once_per_burst_of_updates() {
+        // arrange
+        struct Counted(LinearScan, Arc<AtomicUsize>);
+
+        impl SpatialIndex for Counted {
+            fn range_query<'a>(
+                &'a self,
+                center: (i32, i32),
+                radius: f64,
+            ) -> Box<dyn Iterator<Item = &'a Point> + 'a> {
+                self.0.range_query(center, radius)
+            }
+
+            fn rebuild(&mut self, points: &[Point]) {
+                self.1.fetch_add(1, Ordering::SeqCst);
+                self.0.rebuild(points);
+            }
+        }
+
+        let rebuilds = Arc::new(AtomicUsize::new(0));
+        let engine = VisibilityEngine::new(vec![point(1, (0, 0), Direction::North)])
+            .with_index(Counted(LinearScan::default(), Arc::clone(&rebuilds)));
+
+        // act
+        for number in 2..12 {
+            engine
+                .upsert(point(number, (0, number as i32), Direction::South))
+                .unwrap();
+        }
+        engine.remove(11);
+        let visible = engine.visible_points(1, 45, 20);
+        engine.visible_points(1, 45, 5);
+
+        // assert
+        assert_eq!(rebuilds.load(Ordering::SeqCst), 2);
+        assert_eq!(visible.len(), 9);
+        engine.replace(vec![]);
+        assert!(engine.visible_points(1, 45, 20).is_empty());
+        assert_eq!(rebuilds.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn visibility_engine_picks_index_for_workload() {
+        // arrange
+        let points: Vec<Point> = (0..2_500)
+            .map(|index| {
+                point(
+                    index as u32 + 1,
+                    ((index % 50) * 6, (index / 50) * 6),
+                    Direction::East,
+                )
+            })
+            .collect();
+        let workload = Workload {
+            radius: 20,
+            queries: 1_000,
+        };
+
+        // act
+        let engine = VisibilityEngine::new(points.clone()).with_workload(workload);
+        let backend = engine.index_backend();
+        let visible = engine.visible_points(1_276, 45, 20);
+        engine.replace(points[..100].to_vec());
+
+        // assert
+        assert_eq!(backend, Some(IndexBackend::Grid));
+        let expected: Vec<Point> = visible_points_from_neighbours(1_276, 45, 20, &points)
+            .into_iter()
+            .copied()
+            .collect();
+        assert_eq!(visible, expected);
+        assert!(!visible.is_empty());
+        assert_eq!(engine.index_backend(), Some(IndexBackend::Linear));
+        let overridden = VisibilityEngine::new(points)
+            .with_workload(workload)
+            .with_backend(IndexBackend::BallTree);
+        assert_eq!(overridden.index_backend(), Some(IndexBackend::BallTree));
+        assert_eq!(overridden.visible_points(1_276, 45, 20), expected);
+        assert_eq!(VisibilityEngine::default().index_backend(), None);
+    }
+}
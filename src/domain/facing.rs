@@ -0,0 +1,181 @@
+use crate::{
+    domain::point::{Direction, Point},
+    utilities::Span,
+};
+use std::str::FromStr;
+
+/// How [`face_points`] derives each point's direction, for datasets with
+/// positions but no recorded orientations
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Facing {
+    /// Face the nearest other point, taking the lowest numbered of those
+    /// equally near
+    Nearest,
+
+    /// Face the centroid of all the points
+    Centroid,
+}
+
+impl FromStr for Facing {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "nearest" => Ok(Facing::Nearest),
+            "centroid" => Ok(Facing::Centroid),
+            _ => Err(format!(
+                "unknown facing `{value}`, expected `nearest` or `centroid`"
+            )),
+        }
+    }
+}
+
+/// Direction from `from` towards `to`, or `None` if they coincide
+fn direction_towards(from: (i32, i32), to: (f64, f64)) -> Option<Direction> {
+    let (dx, dy) = (to.0 - f64::from(from.0), to.1 - f64::from(from.1));
+    if dx == 0.0 && dy == 0.0 {
+        None
+    } else {
+        Some(Direction::Vector { dx, dy })
+    }
+}
+
+/// Position of the nearest of `points` to the point at `by_x[rank]`, other
+/// than any at the same position, where `by_x` lists indices of `points`
+/// sorted by x coordinate
+fn nearest_position(points: &[Point], by_x: &[usize], rank: usize) -> Option<(i32, i32)> {
+    let (x, y) = points[by_x[rank]].coordinates;
+    let distance_squared = |(other_x, other_y): (i32, i32)| {
+        let (dx, dy) = (
+            i64::from(other_x) - i64::from(x),
+            i64::from(other_y) - i64::from(y),
+        );
+        dx * dx + dy * dy
+    };
+    let mut best: Option<(i64, u32, (i32, i32))> = None;
+
+    let mut visit = |other: usize| {
+        let Point {
+            coordinates,
+            number,
+            ..
+        } = points[other];
+        let gap = i64::from(coordinates.0) - i64::from(x);
+        if best.map_or(false, |(distance, _, _)| gap * gap > distance) {
+            return false;
+        }
+        let distance = distance_squared(coordinates);
+        if distance > 0
+            && best.map_or(true, |(best_distance, best_number, _)| {
+                (distance, number) < (best_distance, best_number)
+            })
+        {
+            best = Some((distance, number, coordinates));
+        }
+        true
+    };
+
+    // scan outwards either side in x, stopping once the gap in x alone is
+    // further than the best found
+    for other in by_x[..rank].iter().rev() {
+        if !visit(*other) {
+            break;
+        }
+    }
+    for other in &by_x[rank + 1..] {
+        if !visit(*other) {
+            break;
+        }
+    }
+    best.map(|(_, _, coordinates)| coordinates)
+}
+
+/// Set the direction of each of `points` as chosen by `facing`.  Points with
+/// nothing to face, such as a lone point, or one at the centroid, keep their
+/// direction.
+pub fn face_points(points: &mut [Point], facing: Facing) {
+    let _span = Span::enter("face_points", || format!("{} points", points.len()));
+    match facing {
+        Facing::Nearest => {
+            let mut by_x: Vec<usize> = (0..points.len()).collect();
+            by_x.sort_by_key(|index| points[*index].coordinates.0);
+            let targets: Vec<Option<(i32, i32)>> = (0..by_x.len())
+                .map(|rank| nearest_position(points, &by_x, rank))
+                .collect();
+            for (rank, target) in targets.into_iter().enumerate() {
+                let point = &mut points[by_x[rank]];
+                if let Some(direction) = target.and_then(|(x, y)| {
+                    direction_towards(point.coordinates, (f64::from(x), f64::from(y)))
+                }) {
+                    point.direction = direction;
+                }
+            }
+        }
+        Facing::Centroid => {
+            if points.is_empty() {
+                return;
+            }
+            let count = points.len() as f64;
+            let (sum_x, sum_y) = points.iter().fold((0.0, 0.0), |(x, y), point| {
+                (
+                    x + f64::from(point.coordinates.0),
+                    y + f64::from(point.coordinates.1),
+                )
+            });
+            let centroid = (sum_x / count, sum_y / count);
+            for point in points.iter_mut() {
+                if let Some(direction) = direction_towards(point.coordinates, centroid) {
+                    point.direction = direction;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{face_points, Facing};
+    use crate::domain::{test_support::north_point, Direction};
+
+    #[test]
+    fn face_points_gives_expected_result() {
+        // arrange
+        let mut points = vec![
+            north_point(1, (0, 0)),
+            north_point(2, (10, 0)),
+            north_point(3, (0, 4)),
+            north_point(4, (0, -4)),
+            north_point(5, (30, 0)),
+        ];
+
+        // act
+        face_points(&mut points, Facing::Nearest);
+
+        // assert
+        let directions: Vec<Direction> = points.iter().map(|point| point.direction).collect();
+        assert_eq!(
+            directions,
+            vec![
+                Direction::Vector { dx: 0.0, dy: 4.0 },
+                Direction::Vector { dx: -10.0, dy: 0.0 },
+                Direction::Vector { dx: 0.0, dy: -4.0 },
+                Direction::Vector { dx: 0.0, dy: 4.0 },
+                Direction::Vector { dx: -20.0, dy: 0.0 },
+            ]
+        );
+        let mut square = vec![
+            north_point(1, (0, 0)),
+            north_point(2, (2, 0)),
+            north_point(3, (2, 2)),
+            north_point(4, (0, 2)),
+            north_point(5, (1, 1)),
+        ];
+        face_points(&mut square, Facing::Centroid);
+        assert_eq!(square[0].direction, Direction::Vector { dx: 1.0, dy: 1.0 });
+        assert_eq!(square[4].direction, Direction::North);
+        assert_eq!("centroid".parse(), Ok(Facing::Centroid));
+        let mut lone = vec![north_point(1, (0, 0))];
+        face_points(&mut lone, Facing::Nearest);
+        assert_eq!(lone[0].direction, Direction::North);
+    }
+}
@@ -0,0 +1,167 @@
+//! Fast path for parsing points, enabled by the `fast-json` feature.  Lines
+//! of large NDJSON inputs are scanned directly for the four point fields,
+//! skipping serde’s generic deserialisation.  Anything the scanner does not
+//! expect, such as escapes, fractions, unknown or repeated fields, is left to
+//! serde, so results and error messages match parsing without the feature.
+
+use crate::domain::point::{Direction, Point};
+
+/// Cursor over the bytes of one JSON object
+struct Scanner<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.bytes.get(self.position) {
+            self.position += 1;
+        }
+    }
+
+    /// Consume `byte`, after any whitespace, returning `None` if it is not
+    /// next
+    fn expect(&mut self, byte: u8) -> Option<()> {
+        self.skip_whitespace();
+        if self.bytes.get(self.position) == Some(&byte) {
+            self.position += 1;
+            Some(())
+        } else {
+            None
+        }
+    }
+
+    /// String without escapes, returning `None` for any other value
+    fn string(&mut self) -> Option<&'a [u8]> {
+        self.expect(b'"')?;
+        let start = self.position;
+        loop {
+            match self.bytes.get(self.position)? {
+                b'"' => break,
+                b'\\' => return None,
+                _ => self.position += 1,
+            }
+        }
+        self.position += 1;
+        Some(&self.bytes[start..self.position - 1])
+    }
+
+    /// Integer, without fraction or exponent, returning `None` for any other
+    /// value
+    fn integer(&mut self) -> Option<i64> {
+        self.skip_whitespace();
+        let negative = self.bytes.get(self.position) == Some(&b'-');
+        if negative {
+            self.position += 1;
+        }
+        let start = self.position;
+        let mut result: i64 = 0;
+        while let Some(digit @ b'0'..=b'9') = self.bytes.get(self.position) {
+            result = result
+                .checked_mul(10)?
+                .checked_add(i64::from(digit - b'0'))?;
+            self.position += 1;
+        }
+        let digits = self.position - start;
+        if digits == 0 || (digits > 1 && self.bytes[start] == b'0') {
+            return None;
+        }
+        if let Some(b'.' | b'e' | b'E') = self.bytes.get(self.position) {
+            return None;
+        }
+        Some(if negative { -result } else { result })
+    }
+}
+
+/// Set `field`, returning `None` if it was already set
+fn set<T>(field: &mut Option<T>, value: T) -> Option<()> {
+    match field.replace(value) {
+        Some(_) => None,
+        None => Some(()),
+    }
+}
+
+/// Parse a JSON object with exactly the fields `x`, `y`, `number` and
+/// `direction`, in any order, returning `None` if `line` holds anything else,
+/// to be parsed by serde instead
+pub(crate) fn parse_point_line(line: &str) -> Option<Point> {
+    let mut scanner = Scanner {
+        bytes: line.as_bytes(),
+        position: 0,
+    };
+    let (mut x, mut y, mut number, mut direction) = (None, None, None, None);
+    scanner.expect(b'{')?;
+    for index in 0..4 {
+        if index > 0 {
+            scanner.expect(b',')?;
+        }
+        let key = scanner.string()?;
+        scanner.expect(b':')?;
+        match key {
+            b"x" => set(&mut x, i32::try_from(scanner.integer()?).ok()?)?,
+            b"y" => set(&mut y, i32::try_from(scanner.integer()?).ok()?)?,
+            b"number" => set(&mut number, u32::try_from(scanner.integer()?).ok()?)?,
+            b"direction" => {
+                let value = match scanner.string()? {
+                    b"North" => Direction::North,
+                    b"East" => Direction::East,
+                    b"South" => Direction::South,
+                    b"West" => Direction::West,
+                    _ => return None,
+                };
+                set(&mut direction, value)?;
+            }
+            _ => return None,
+        }
+    }
+    scanner.expect(b'}')?;
+    scanner.skip_whitespace();
+    if scanner.position != scanner.bytes.len() {
+        return None;
+    }
+    Some(Point {
+        coordinates: (x?, y?),
+        number: number?,
+        direction: direction?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_point_line;
+    use crate::domain::point::{InputPoint, Point};
+
+    #[test]
+    fn parse_point_line_matches_serde() {
+        // arrange
+        let lines = [
+            r#"{"x": 28, "y": -42, "number": 1, "direction": "North"}"#,
+            "  {\"direction\":\"West\",\"number\":4294967295,\"y\":0,\"x\":-2147483648}\r\n",
+            r#"{"x": 1, "y": 2, "number": 3, "direction": "East", "colour": "red"}"#,
+            r#"{"x": 1.5, "y": 2, "number": 3, "direction": "East"}"#,
+            r#"{"x": 1, "x": 2, "number": 3, "direction": "East"}"#,
+            r#"{"x": 1, "y": 2, "number": -3, "direction": "East"}"#,
+            r#"{"x": 2147483648, "y": 2, "number": 3, "direction": "East"}"#,
+            r#"{"x": 01, "y": 2, "number": 3, "direction": "East"}"#,
+            r#"{"x": 1, "y": 2, "number": 3, "direction": "north"}"#,
+            r#"{"x": 1, "y": 2, "number": 3, "direction": "East"} trailing"#,
+            r#"{"x": 1, "y": 2, "number": 3}"#,
+        ];
+
+        // act
+        let outcome: Vec<Option<Point>> = lines.iter().map(|line| parse_point_line(line)).collect();
+
+        // assert
+        for (line, point) in lines.iter().zip(&outcome) {
+            if let Some(point) = point {
+                let expected: InputPoint = serde_json::from_str(line).unwrap();
+                assert_eq!(*point, Point::from(expected));
+            }
+        }
+        let parsed: Vec<bool> = outcome.iter().map(Option::is_some).collect();
+        assert_eq!(
+            parsed,
+            vec![true, true, false, false, false, false, false, false, false, false, false]
+        );
+    }
+}
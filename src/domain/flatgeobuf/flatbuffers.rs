@@ -0,0 +1,159 @@
+//! Bounds checked reads of FlatBuffers tables, vectors and strings, as
+//! FlatGeobuf headers and features are encoded
+
+use super::flatgeobuf_error;
+use crate::utilities::AppError;
+use std::convert::TryFrom;
+
+fn truncated() -> AppError {
+    flatgeobuf_error(String::from("truncated FlatBuffers data"))
+}
+
+fn read<const N: usize>(bytes: &[u8], position: usize) -> Result<[u8; N], AppError> {
+    let mut result = [0; N];
+    result.copy_from_slice(
+        bytes
+            .get(position..position.checked_add(N).ok_or_else(truncated)?)
+            .ok_or_else(truncated)?,
+    );
+    Ok(result)
+}
+
+pub(super) fn read_u16(bytes: &[u8], position: usize) -> Result<u16, AppError> {
+    read(bytes, position).map(u16::from_le_bytes)
+}
+
+pub(super) fn read_u32(bytes: &[u8], position: usize) -> Result<u32, AppError> {
+    read(bytes, position).map(u32::from_le_bytes)
+}
+
+pub(super) fn read_u64(bytes: &[u8], position: usize) -> Result<u64, AppError> {
+    read(bytes, position).map(u64::from_le_bytes)
+}
+
+pub(super) fn read_f64(bytes: &[u8], position: usize) -> Result<f64, AppError> {
+    read(bytes, position).map(f64::from_le_bytes)
+}
+
+/// Position `offset` bytes on from `position`, as an unsigned FlatBuffers
+/// offset is stored
+fn follow(bytes: &[u8], position: usize) -> Result<usize, AppError> {
+    let offset = usize::try_from(read_u32(bytes, position)?).map_err(|_| truncated())?;
+    position.checked_add(offset).ok_or_else(truncated)
+}
+
+/// Table within a FlatBuffers buffer
+#[derive(Copy, Clone)]
+pub(super) struct Table<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    vtable: usize,
+}
+
+impl<'a> Table<'a> {
+    /// Root table of the buffer `bytes`
+    pub(super) fn root(bytes: &'a [u8]) -> Result<Self, AppError> {
+        Table::at(bytes, follow(bytes, 0)?)
+    }
+
+    fn at(bytes: &'a [u8], position: usize) -> Result<Self, AppError> {
+        let offset = i64::from(read_u32(bytes, position)? as i32);
+        let vtable = usize::try_from(position as i64 - offset).map_err(|_| truncated())?;
+        Ok(Table {
+            bytes,
+            position,
+            vtable,
+        })
+    }
+
+    /// Position of field `field`, or `None` if it is absent
+    fn field(&self, field: usize) -> Result<Option<usize>, AppError> {
+        let vtable_size = usize::from(read_u16(self.bytes, self.vtable)?);
+        let entry = 4 + 2 * field;
+        if entry + 2 > vtable_size {
+            return Ok(None);
+        }
+        match read_u16(self.bytes, self.vtable + entry)? {
+            0 => Ok(None),
+            offset => Ok(Some(self.position + usize::from(offset))),
+        }
+    }
+
+    pub(super) fn u8(&self, field: usize, default: u8) -> Result<u8, AppError> {
+        match self.field(field)? {
+            Some(position) => self.bytes.get(position).copied().ok_or_else(truncated),
+            None => Ok(default),
+        }
+    }
+
+    pub(super) fn u16(&self, field: usize, default: u16) -> Result<u16, AppError> {
+        match self.field(field)? {
+            Some(position) => read_u16(self.bytes, position),
+            None => Ok(default),
+        }
+    }
+
+    pub(super) fn u64(&self, field: usize) -> Result<u64, AppError> {
+        match self.field(field)? {
+            Some(position) => read_u64(self.bytes, position),
+            None => Ok(0),
+        }
+    }
+
+    /// Table held by field `field`
+    pub(super) fn table(&self, field: usize) -> Result<Option<Table<'a>>, AppError> {
+        match self.field(field)? {
+            Some(position) => Table::at(self.bytes, follow(self.bytes, position)?).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Bytes of the vector held by field `field`, with elements `element_size`
+    /// bytes long, and its length
+    fn vector(
+        &self,
+        field: usize,
+        element_size: usize,
+    ) -> Result<Option<(usize, usize)>, AppError> {
+        let position = match self.field(field)? {
+            Some(value) => follow(self.bytes, value)?,
+            None => return Ok(None),
+        };
+        let length = usize::try_from(read_u32(self.bytes, position)?).map_err(|_| truncated())?;
+        let end = length
+            .checked_mul(element_size)
+            .and_then(|size| size.checked_add(position + 4))
+            .ok_or_else(truncated)?;
+        if end > self.bytes.len() {
+            return Err(truncated());
+        }
+        Ok(Some((position + 4, length)))
+    }
+
+    /// Bytes of the byte vector, or string, held by field `field`
+    pub(super) fn bytes(&self, field: usize) -> Result<Option<&'a [u8]>, AppError> {
+        Ok(self
+            .vector(field, 1)?
+            .map(|(start, length)| &self.bytes[start..start + length]))
+    }
+
+    /// Values of the `f64` vector held by field `field`
+    pub(super) fn f64s(&self, field: usize) -> Result<Vec<f64>, AppError> {
+        match self.vector(field, 8)? {
+            Some((start, length)) => (0..length)
+                .map(|index| read_f64(self.bytes, start + 8 * index))
+                .collect(),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Tables of the vector of tables held by field `field`
+    pub(super) fn tables(&self, field: usize) -> Result<Vec<Table<'a>>, AppError> {
+        match self.vector(field, 4)? {
+            Some((start, length)) => (0..length)
+                .map(|index| Table::at(self.bytes, follow(self.bytes, start + 4 * index)?))
+                .collect(),
+            None => Ok(vec![]),
+        }
+    }
+}
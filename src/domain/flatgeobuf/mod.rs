@@ -0,0 +1,475 @@
+//! Reader for points stored as FlatGeobuf, with Point geometries and
+//! `number` and `direction` columns.  Numbers may be any integer column type,
+//! and directions may be names, such as `North`, or headings in degrees,
+//! clockwise from north, taking the nearest compass direction.  Coordinates
+//! are rounded to whole units.  Given a bounding box, files with a spatial
+//! index only read the features the index places in the box.
+
+mod flatbuffers;
+
+use crate::{
+    domain::{
+        geometry::BoundingBox,
+        point::{heading_direction, Direction, Point},
+    },
+    utilities::{AppError, Span},
+};
+use flatbuffers::{read_f64, read_u16, read_u32, read_u64, Table};
+use std::{
+    convert::TryFrom,
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    path::Path,
+};
+
+/// Leading bytes of every FlatGeobuf file, either side of the major version
+const MAGIC: &[u8; 3] = b"fgb";
+
+/// Bytes in each node of the packed Hilbert R-tree index: a bounding box of
+/// four `f64` values, then a `u64` offset
+const NODE_ITEM_SIZE: usize = 40;
+
+/// Node size assumed when a header gives none
+const DEFAULT_NODE_SIZE: u16 = 16;
+
+const UNKNOWN_GEOMETRY: u8 = 0;
+const POINT_GEOMETRY: u8 = 1;
+
+pub(crate) fn flatgeobuf_error(message: String) -> AppError {
+    AppError::FlatGeobufError(message)
+}
+
+/// Bytes of a FlatGeobuf file, which may be held in memory or read from disk
+/// as needed
+trait Source {
+    fn len(&self) -> u64;
+
+    fn read_at(&mut self, offset: u64, length: usize) -> Result<Vec<u8>, AppError>;
+}
+
+impl Source for &[u8] {
+    fn len(&self) -> u64 {
+        <[u8]>::len(self) as u64
+    }
+
+    fn read_at(&mut self, offset: u64, length: usize) -> Result<Vec<u8>, AppError> {
+        usize::try_from(offset)
+            .ok()
+            .and_then(|start| self.get(start..start.checked_add(length)?))
+            .map(<[u8]>::to_vec)
+            .ok_or_else(|| flatgeobuf_error(String::from("truncated file")))
+    }
+}
+
+struct FileSource {
+    file: File,
+    length: u64,
+    path: String,
+}
+
+impl Source for FileSource {
+    fn len(&self) -> u64 {
+        self.length
+    }
+
+    fn read_at(&mut self, offset: u64, length: usize) -> Result<Vec<u8>, AppError> {
+        if offset.saturating_add(length as u64) > self.length {
+            return Err(flatgeobuf_error(String::from("truncated file")));
+        }
+        let mut result = vec![0; length];
+        self.file
+            .seek(SeekFrom::Start(offset))
+            .and_then(|_| self.file.read_exact(&mut result))
+            .map_err(|error| AppError::BinaryFileError {
+                path: self.path.clone(),
+                source: error,
+            })?;
+        Ok(result)
+    }
+}
+
+/// Header facts needed to read points
+struct Header {
+    /// Number of features, or `0` if unknown
+    features_count: u64,
+    index_node_size: u16,
+    geometry_type: u8,
+
+    /// Type of each column, by column index
+    column_types: Vec<u8>,
+    number_column: u16,
+    direction_column: u16,
+
+    /// Offset of the index, or of the features, if there is no index
+    end: u64,
+}
+
+fn parse_header<S: Source>(source: &mut S) -> Result<Header, AppError> {
+    let start = source.read_at(0, 12)?;
+    if &start[..3] != MAGIC || &start[4..7] != MAGIC {
+        return Err(flatgeobuf_error(String::from("not a FlatGeobuf file")));
+    }
+    let size = read_u32(&start, 8)?;
+    let bytes = source.read_at(12, size as usize)?;
+    let header = Table::root(&bytes)?;
+    let columns = header
+        .tables(7)?
+        .iter()
+        .map(|column| {
+            let name = column.bytes(0)?.unwrap_or_default();
+            Ok((
+                String::from_utf8_lossy(name).to_lowercase(),
+                column.u8(1, 0)?,
+            ))
+        })
+        .collect::<Result<Vec<(String, u8)>, AppError>>()?;
+    let column_index = |name: &str| {
+        columns
+            .iter()
+            .position(|(column, _)| column == name)
+            .and_then(|index| u16::try_from(index).ok())
+            .ok_or_else(|| flatgeobuf_error(format!("no `{name}` column")))
+    };
+    Ok(Header {
+        features_count: header.u64(8)?,
+        index_node_size: header.u16(9, DEFAULT_NODE_SIZE)?,
+        geometry_type: header.u8(2, UNKNOWN_GEOMETRY)?,
+        number_column: column_index("number")?,
+        direction_column: column_index("direction")?,
+        column_types: columns.into_iter().map(|(_, kind)| kind).collect(),
+        end: 12 + u64::from(size),
+    })
+}
+
+/// Number of nodes at each level of a packed R-tree indexing `items` with
+/// nodes of `node_size`, from the leaves up to the root
+fn level_sizes(items: u64, node_size: u64) -> Vec<u64> {
+    let mut result = vec![items];
+    let mut count = items;
+    while count > 1 {
+        count = count / node_size + u64::from(count % node_size != 0);
+        result.push(count);
+    }
+    result
+}
+
+/// Total number of nodes in an index with levels of `level_sizes`
+fn index_nodes(level_sizes: &[u64]) -> Result<u64, AppError> {
+    level_sizes
+        .iter()
+        .try_fold(0_u64, |total, size| total.checked_add(*size))
+        .filter(|total| total.checked_mul(NODE_ITEM_SIZE as u64).is_some())
+        .ok_or_else(|| flatgeobuf_error(String::from("index is too large")))
+}
+
+/// Offsets, relative to the start of the features, of features whose boxes
+/// the index places in `bounds`, in file order
+fn search_index<S: Source>(
+    source: &mut S,
+    header: &Header,
+    bounds: &BoundingBox,
+) -> Result<Vec<u64>, AppError> {
+    let node_size = u64::from(header.index_node_size.max(2));
+    let levels = level_sizes(header.features_count, node_size);
+    let total = index_nodes(&levels)?;
+    let mut level_ends = Vec::with_capacity(levels.len());
+    let mut end = total;
+    for size in &levels {
+        level_ends.push(end);
+        end -= size;
+    }
+    let (min_x, min_y) = (f64::from(bounds.min.0) - 0.5, f64::from(bounds.min.1) - 0.5);
+    let (max_x, max_y) = (f64::from(bounds.max.0) + 0.5, f64::from(bounds.max.1) + 0.5);
+    let mut result = vec![];
+    let mut queue = vec![(0_u64, levels.len() - 1)];
+    while let Some((node, level)) = queue.pop() {
+        let end = (node + node_size).min(level_ends[level]);
+        if end <= node {
+            continue;
+        }
+        let items = source.read_at(
+            header.end.saturating_add(node * NODE_ITEM_SIZE as u64),
+            (end - node) as usize * NODE_ITEM_SIZE,
+        )?;
+        for item in items.chunks(NODE_ITEM_SIZE) {
+            let intersects = read_f64(item, 0)? <= max_x
+                && read_f64(item, 8)? <= max_y
+                && read_f64(item, 16)? >= min_x
+                && read_f64(item, 24)? >= min_y;
+            if !intersects {
+                continue;
+            }
+            let offset = read_u64(item, 32)?;
+            if level == 0 {
+                result.push(offset);
+            } else {
+                if offset >= total {
+                    return Err(flatgeobuf_error(String::from("invalid index node")));
+                }
+                queue.push((offset, level - 1));
+            }
+        }
+    }
+    result.sort_unstable();
+    Ok(result)
+}
+
+/// Size, in bytes, of a property value of column type `kind`, after any
+/// length prefix
+fn property_size(kind: u8, properties: &[u8], position: usize) -> Result<(usize, usize), AppError> {
+    match kind {
+        0..=2 => Ok((0, 1)),
+        3 | 4 => Ok((0, 2)),
+        5 | 6 | 9 => Ok((0, 4)),
+        7 | 8 | 10 => Ok((0, 8)),
+        11..=14 => Ok((4, read_u32(properties, position)? as usize)),
+        _ => Err(flatgeobuf_error(format!("unknown column type {kind}"))),
+    }
+}
+
+/// Numeric value of a property of column type `kind`, held in `value`
+fn numeric(kind: u8, value: &[u8]) -> Result<Option<f64>, AppError> {
+    Ok(match kind {
+        0 => Some(f64::from(value[0] as i8)),
+        1 | 2 => Some(f64::from(value[0])),
+        3 => Some(f64::from(read_u16(value, 0)? as i16)),
+        4 => Some(f64::from(read_u16(value, 0)?)),
+        5 => Some(f64::from(read_u32(value, 0)? as i32)),
+        6 => Some(f64::from(read_u32(value, 0)?)),
+        7 => Some(read_u64(value, 0)? as i64 as f64),
+        8 => Some(read_u64(value, 0)? as f64),
+        9 => Some(f64::from(f32::from_bits(read_u32(value, 0)?))),
+        10 => Some(read_f64(value, 0)?),
+        _ => None,
+    })
+}
+
+fn direction(kind: u8, value: &[u8]) -> Result<Direction, AppError> {
+    if let Some(heading) = numeric(kind, value)?.filter(|value| value.is_finite()) {
+        return Ok(heading_direction(heading));
+    }
+    let name = String::from_utf8_lossy(value);
+    match name.trim() {
+        "North" => Ok(Direction::North),
+        "East" => Ok(Direction::East),
+        "South" => Ok(Direction::South),
+        "West" => Ok(Direction::West),
+        _ => Err(flatgeobuf_error(format!("unknown direction `{name}`"))),
+    }
+}
+
+/// Point held by the encoded feature `bytes`
+fn parse_feature(header: &Header, bytes: &[u8]) -> Result<Point, AppError> {
+    let feature = Table::root(bytes)?;
+    let geometry = feature
+        .table(0)?
+        .ok_or_else(|| flatgeobuf_error(String::from("feature has no geometry")))?;
+    let geometry_type = match header.geometry_type {
+        UNKNOWN_GEOMETRY => geometry.u8(6, UNKNOWN_GEOMETRY)?,
+        value => value,
+    };
+    if geometry_type != POINT_GEOMETRY {
+        return Err(flatgeobuf_error(format!(
+            "geometry type {geometry_type} is not supported, only points are"
+        )));
+    }
+    let (x, y) = match geometry.f64s(1)?[..] {
+        [x, y, ..] if x.is_finite() && y.is_finite() => (x, y),
+        _ => return Err(flatgeobuf_error(String::from("point has no coordinates"))),
+    };
+    let properties = feature.bytes(1)?.unwrap_or_default();
+    let (mut number, mut point_direction) = (None, None);
+    let mut position = 0;
+    while position < properties.len() {
+        let column = read_u16(properties, position)?;
+        let kind = *header
+            .column_types
+            .get(usize::from(column))
+            .ok_or_else(|| flatgeobuf_error(format!("unknown column {column}")))?;
+        let (prefix, size) = property_size(kind, properties, position + 2)?;
+        let start = position + 2 + prefix;
+        let value = properties
+            .get(start..start.saturating_add(size))
+            .ok_or_else(|| flatgeobuf_error(String::from("truncated properties")))?;
+        if column == header.number_column {
+            number = numeric(kind, value)?
+                .filter(|value| value.fract() == 0.0)
+                .and_then(|value| u32::try_from(value as i64).ok());
+        } else if column == header.direction_column {
+            point_direction = Some(direction(kind, value)?);
+        }
+        position = start + size;
+    }
+    match (number, point_direction) {
+        (Some(number), Some(direction)) => Ok(Point {
+            coordinates: (x.round() as i32, y.round() as i32),
+            number,
+            direction,
+        }),
+        (None, _) => Err(flatgeobuf_error(String::from(
+            "feature has no valid `number`",
+        ))),
+        (_, None) => Err(flatgeobuf_error(String::from("feature has no `direction`"))),
+    }
+}
+
+fn parse_points<S: Source>(
+    source: &mut S,
+    bounds: Option<&BoundingBox>,
+) -> Result<Vec<Point>, AppError> {
+    let header = parse_header(source)?;
+    let indexed = header.index_node_size > 0 && header.features_count > 0;
+    let index_size = if indexed {
+        let levels = level_sizes(
+            header.features_count,
+            u64::from(header.index_node_size.max(2)),
+        );
+        index_nodes(&levels)? * NODE_ITEM_SIZE as u64
+    } else {
+        0
+    };
+    let features_start = header.end.saturating_add(index_size);
+    let feature = |source: &mut S, offset: u64| -> Result<(Point, u64), AppError> {
+        let start = features_start.saturating_add(offset);
+        let size = read_u32(&source.read_at(start, 4)?, 0)?;
+        let bytes = source.read_at(start.saturating_add(4), size as usize)?;
+        Ok((
+            parse_feature(&header, &bytes)?,
+            offset.saturating_add(4 + u64::from(size)),
+        ))
+    };
+    let mut result = vec![];
+    match bounds {
+        Some(value) if indexed => {
+            for offset in search_index(source, &header, value)? {
+                result.push(feature(source, offset)?.0);
+            }
+        }
+        _ => {
+            let mut offset = 0;
+            while features_start.saturating_add(offset) < source.len() {
+                let (point, next) = feature(source, offset)?;
+                result.push(point);
+                offset = next;
+            }
+        }
+    }
+    if let Some(value) = bounds {
+        result.retain(|point| value.contains(point.coordinates));
+    }
+    Ok(result)
+}
+
+/// Parse points held in the FlatGeobuf `bytes`, keeping only those within
+/// `bounds`, if given, and using the spatial index, where there is one, to
+/// decode just the features in `bounds`
+pub fn parse_points_flatgeobuf(
+    bytes: &[u8],
+    bounds: Option<&BoundingBox>,
+) -> Result<Vec<Point>, AppError> {
+    let _span = Span::enter("parse_points_flatgeobuf", || {
+        format!("{} bytes", bytes.len())
+    });
+    parse_points(&mut &bytes[..], bounds)
+}
+
+/// Parse points from the FlatGeobuf file at `path`, as for
+/// [`parse_points_flatgeobuf`], but reading only the header, the index nodes
+/// searched, and the features found from disk, when `bounds` are given and the
+/// file has a spatial index
+pub(crate) fn parse_points_flatgeobuf_file(
+    path: &Path,
+    bounds: Option<&BoundingBox>,
+) -> Result<Vec<Point>, AppError> {
+    let _span = Span::enter("parse_points_flatgeobuf_file", || {
+        path.display().to_string()
+    });
+    let file_error = |error| AppError::BinaryFileError {
+        path: path.display().to_string(),
+        source: error,
+    };
+    let file = File::open(path).map_err(file_error)?;
+    let length = file.metadata().map_err(file_error)?.len();
+    parse_points(
+        &mut FileSource {
+            file,
+            length,
+            path: path.display().to_string(),
+        },
+        bounds,
+    )
+}
+
+/// Returns true if `path` has a `.fgb` extension, and so is read as
+/// FlatGeobuf
+pub(crate) fn is_flatgeobuf_path(path: &Path) -> bool {
+    path.extension().and_then(|value| value.to_str()) == Some("fgb")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{level_sizes, parse_points_flatgeobuf};
+    use crate::domain::{parse_points_file, parse_points_in, parse_records_in, BoundingBox, Point};
+    use std::{fs, path::Path};
+
+    #[test]
+    fn parse_points_flatgeobuf_gives_expected_result() {
+        // arrange
+        let bytes = fs::read("./fixtures/valid_points.fgb").unwrap();
+        let expected = parse_points_file("./fixtures/valid_points.json").unwrap();
+
+        // act
+        let outcome = parse_points_flatgeobuf(&bytes, None).unwrap();
+
+        // assert
+        assert_eq!(outcome, expected);
+        assert_eq!(
+            parse_points_file("./fixtures/valid_points.fgb").unwrap(),
+            expected
+        );
+        assert_eq!(level_sizes(20, 4), vec![20, 5, 2, 1]);
+        assert!(parse_points_flatgeobuf(&bytes[..40], None).is_err());
+        assert!(parse_points_flatgeobuf(b"not flatgeobuf", None).is_err());
+    }
+
+    #[test]
+    fn parse_points_flatgeobuf_searches_index_for_bounding_box() {
+        // arrange
+        let bytes = fs::read("./fixtures/valid_points.fgb").unwrap();
+        let bounds = BoundingBox {
+            min: (20, 40),
+            max: (30, 50),
+        };
+        let expected: Vec<_> = parse_points_file("./fixtures/valid_points.json")
+            .unwrap()
+            .into_iter()
+            .filter(|point| bounds.contains(point.coordinates))
+            .collect();
+
+        // act
+        let outcome = parse_points_flatgeobuf(&bytes, Some(&bounds)).unwrap();
+
+        // assert
+        assert_eq!(
+            outcome.iter().map(|point| point.number).collect::<Vec<_>>(),
+            vec![1, 2, 11]
+        );
+        assert_eq!(outcome, expected);
+        assert_eq!(
+            parse_points_in("./fixtures/valid_points.fgb", &bounds).unwrap(),
+            expected
+        );
+        assert_eq!(
+            parse_points_in("./fixtures/valid_points.json", &bounds).unwrap(),
+            expected
+        );
+        assert_eq!(
+            parse_records_in::<Point>(Path::new("./fixtures/valid_points.fgb"), &bounds).unwrap(),
+            expected
+        );
+        assert_eq!(
+            parse_records_in::<Point>(Path::new("./fixtures/valid_points.json"), &bounds).unwrap(),
+            expected
+        );
+    }
+}
@@ -0,0 +1,208 @@
+use crate::domain::{origin::Origin, point::Point, sector::sector_outline, sighting::Sighting};
+use serde_json::{json, Value};
+
+/// Colour of the origin marker
+const ORIGIN_COLOUR: &str = "#c83c3c";
+
+/// Outline and fill colours of the sector polygon
+const SECTOR_STROKE: &str = "#2878dc";
+const SECTOR_FILL: &str = "#96bee6";
+
+/// Colours of visible and invisible point markers
+const VISIBLE_COLOUR: &str = "#2878dc";
+const INVISIBLE_COLOUR: &str = "#5a5a5a";
+
+/// Query result as separate GeoJSON feature collections, one for each layer
+/// of a web map.  Features carry
+/// [simplestyle](https://github.com/mapbox/simplestyle-spec) properties, such
+/// as `marker-color` and `fill-opacity`, which Mapbox and geojson.io apply as
+/// they are, and Leaflet can apply with a short `style` function, along with a
+/// `layer` property naming their layer.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GeoJsonLayers {
+    /// Point feature for the origin, with its `heading`
+    pub origin: Value,
+
+    /// Polygon feature for the sector searched
+    pub sector: Value,
+
+    /// Point features for visible points, with their `distance`, `bearing`
+    /// and `relative_bearing` from the origin
+    pub visible: Value,
+
+    /// Point features for the other points, excluding the origin
+    pub invisible: Value,
+}
+
+impl GeoJsonLayers {
+    /// Layers, with their names, in order to add them to a map, so points are
+    /// drawn over the sector
+    pub fn layers(&self) -> [(&'static str, &Value); 4] {
+        [
+            ("sector", &self.sector),
+            ("invisible", &self.invisible),
+            ("visible", &self.visible),
+            ("origin", &self.origin),
+        ]
+    }
+}
+
+fn feature_collection(features: Vec<Value>) -> Value {
+    json!({ "type": "FeatureCollection", "features": features })
+}
+
+fn point_feature(point: &Point, layer: &str, colour: &str) -> Value {
+    json!({
+        "type": "Feature",
+        "geometry": {
+            "type": "Point",
+            "coordinates": [point.coordinates.0, point.coordinates.1]
+        },
+        "properties": {
+            "layer": layer,
+            "number": point.number,
+            "direction": point.direction,
+            "marker-color": colour
+        }
+    })
+}
+
+/// Build GeoJSON layers for a query from `origin`, with radius `arc_radius`,
+/// spanning left and right from its heading by `half_arc_central_angle`
+/// degrees, where `sightings` are the visible points of `neighbourhood`.
+/// `origin_point` is the number of the point queried from, if any, which is
+/// left out of the invisible layer.  Coordinates are written as they are, so
+/// should be longitude and latitude for a web map.
+pub fn geojson_layers(
+    origin: &Origin,
+    origin_point: Option<u32>,
+    half_arc_central_angle: u32,
+    arc_radius: u32,
+    neighbourhood: &[Point],
+    sightings: &[Sighting],
+) -> GeoJsonLayers {
+    let mut origin_properties = json!({
+        "layer": "origin",
+        "heading": origin.heading,
+        "marker-color": ORIGIN_COLOUR
+    });
+    if let Some(number) = origin_point {
+        origin_properties["number"] = json!(number);
+    }
+    let origin_feature = json!({
+        "type": "Feature",
+        "geometry": {
+            "type": "Point",
+            "coordinates": [origin.coordinates.0, origin.coordinates.1]
+        },
+        "properties": origin_properties
+    });
+    let ring: Vec<[f64; 2]> = sector_outline(origin, half_arc_central_angle, arc_radius)
+        .into_iter()
+        .map(|(x, y)| [x, y])
+        .collect();
+    let sector_feature = json!({
+        "type": "Feature",
+        "geometry": { "type": "Polygon", "coordinates": [ring] },
+        "properties": {
+            "layer": "sector",
+            "half_arc_central_angle": half_arc_central_angle,
+            "arc_radius": arc_radius,
+            "stroke": SECTOR_STROKE,
+            "fill": SECTOR_FILL,
+            "fill-opacity": 0.4
+        }
+    });
+    let visible = sightings
+        .iter()
+        .map(|sighting| {
+            let mut feature = point_feature(sighting.point, "visible", VISIBLE_COLOUR);
+            let properties = &mut feature["properties"];
+            properties["distance"] = json!(sighting.distance);
+            properties["bearing"] = json!(sighting.bearing);
+            properties["relative_bearing"] = json!(sighting.relative_bearing);
+            feature
+        })
+        .collect();
+    let invisible = neighbourhood
+        .iter()
+        .filter(|point| Some(point.number) != origin_point)
+        .filter(|point| {
+            !sightings
+                .iter()
+                .any(|sighting| sighting.point.number == point.number)
+        })
+        .map(|point| point_feature(point, "invisible", INVISIBLE_COLOUR))
+        .collect();
+    GeoJsonLayers {
+        origin: feature_collection(vec![origin_feature]),
+        sector: feature_collection(vec![sector_feature]),
+        visible: feature_collection(visible),
+        invisible: feature_collection(invisible),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::geojson_layers;
+    use crate::domain::{visible_sightings, Direction, Origin, Point};
+
+    #[test]
+    fn geojson_layers_splits_visible_and_invisible_points() {
+        // arrange
+        let neighbourhood = [
+            Point {
+                coordinates: (0, 0),
+                number: 1,
+                direction: Direction::North,
+            },
+            Point {
+                coordinates: (0, 5),
+                number: 2,
+                direction: Direction::East,
+            },
+            Point {
+                coordinates: (5, 0),
+                number: 3,
+                direction: Direction::South,
+            },
+        ];
+        let origin = Origin::from(&neighbourhood[0]);
+        let sightings = visible_sightings(1, 45, 10, &neighbourhood);
+
+        // act
+        let outcome = geojson_layers(&origin, Some(1), 45, 10, &neighbourhood, &sightings);
+
+        // assert
+        let numbers = |layer: &serde_json::Value| -> Vec<u64> {
+            layer["features"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|feature| feature["properties"]["number"].as_u64().unwrap())
+                .collect()
+        };
+        assert_eq!(numbers(&outcome.origin), vec![1]);
+        assert_eq!(numbers(&outcome.visible), vec![2]);
+        assert_eq!(numbers(&outcome.invisible), vec![3]);
+        assert_eq!(
+            outcome.visible["features"][0]["properties"]["distance"],
+            5.0
+        );
+        assert_eq!(
+            outcome.invisible["features"][0]["geometry"],
+            serde_json::json!({ "type": "Point", "coordinates": [5, 0] })
+        );
+        let ring = &outcome.sector["features"][0]["geometry"]["coordinates"][0];
+        assert_eq!(ring.as_array().unwrap().len(), 21);
+        assert_eq!(ring[0], ring[20]);
+        assert_eq!(
+            outcome
+                .layers()
+                .iter()
+                .map(|(name, _)| *name)
+                .collect::<Vec<_>>(),
+            vec!["sector", "invisible", "visible", "origin"]
+        );
+    }
+}
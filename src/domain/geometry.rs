@@ -0,0 +1,215 @@
+use crate::domain::point::Point;
+use std::str::FromStr;
+
+/// Axis-aligned rectangle, for example enclosing a neighbourhood
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BoundingBox {
+    /// x,y coordinates of the bottom left corner
+    pub min: (i32, i32),
+
+    /// x,y coordinates of the top right corner
+    pub max: (i32, i32),
+}
+
+impl BoundingBox {
+    /// Smallest bounding box containing all `points`, or `None` if there are
+    /// no points
+    pub fn of_points<'a, I: IntoIterator<Item = &'a Point>>(points: I) -> Option<Self> {
+        points
+            .into_iter()
+            .fold(None, |acc, Point { coordinates, .. }| {
+                let (x, y) = *coordinates;
+                match acc {
+                    Some(BoundingBox {
+                        min: (min_x, min_y),
+                        max: (max_x, max_y),
+                    }) => Some(BoundingBox {
+                        min: (min_x.min(x), min_y.min(y)),
+                        max: (max_x.max(x), max_y.max(y)),
+                    }),
+                    None => Some(BoundingBox {
+                        min: (x, y),
+                        max: (x, y),
+                    }),
+                }
+            })
+    }
+
+    /// Whether `coordinates` lie within the box, or on its edge
+    pub fn contains(&self, (x, y): (i32, i32)) -> bool {
+        self.min.0 <= x && x <= self.max.0 && self.min.1 <= y && y <= self.max.1
+    }
+}
+
+/// Parse a bounding box written as `min_x,min_y,max_x,max_y`, for example
+/// `0,-10,50,40`
+impl FromStr for BoundingBox {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<Option<i32>> = value
+            .split(',')
+            .map(|part| part.trim().parse::<i32>().ok())
+            .collect();
+        match parts[..] {
+            [Some(min_x), Some(min_y), Some(max_x), Some(max_y)]
+                if min_x <= max_x && min_y <= max_y =>
+            {
+                Ok(BoundingBox {
+                    min: (min_x, min_y),
+                    max: (max_x, max_y),
+                })
+            }
+            _ => Err(format!(
+                "expected a bounding box written `min_x,min_y,max_x,max_y`, such as `0,-10,50,40`, but got `{value}`"
+            )),
+        }
+    }
+}
+
+/// Convex hull of a set of points
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConvexHull {
+    /// x,y coordinates of hull vertices in counter-clockwise order, starting
+    /// from the bottom left vertex
+    pub vertices: Vec<(i32, i32)>,
+
+    /// Area enclosed by the hull, which is zero for fewer than three
+    /// non-collinear points
+    pub area: f64,
+}
+
+/// z component of the cross product of `a` to `b` and `a` to `c`, positive when
+/// `a`, `b`, `c` turn counter-clockwise
+fn cross((x_a, y_a): (i32, i32), (x_b, y_b): (i32, i32), (x_c, y_c): (i32, i32)) -> i64 {
+    let (x_a, y_a) = (i64::from(x_a), i64::from(y_a));
+    (i64::from(x_b) - x_a) * (i64::from(y_c) - y_a)
+        - (i64::from(y_b) - y_a) * (i64::from(x_c) - x_a)
+}
+
+/// Hull vertices turning counter-clockwise through `points`, which should be
+/// sorted
+fn half_hull<'a, I: Iterator<Item = &'a (i32, i32)>>(points: I) -> Vec<(i32, i32)> {
+    let mut result: Vec<(i32, i32)> = vec![];
+    for point in points {
+        while result.len() >= 2
+            && cross(result[result.len() - 2], result[result.len() - 1], *point) <= 0
+        {
+            result.pop();
+        }
+        result.push(*point);
+    }
+    result
+}
+
+/// Area of the simple polygon with `vertices`, listed in order
+pub fn polygon_area(vertices: &[(i32, i32)]) -> f64 {
+    let twice_area: i64 = vertices
+        .iter()
+        .zip(vertices.iter().cycle().skip(1))
+        .map(|((x_1, y_1), (x_2, y_2))| {
+            i64::from(*x_1) * i64::from(*y_2) - i64::from(*x_2) * i64::from(*y_1)
+        })
+        .sum();
+    twice_area.abs() as f64 / 2.0
+}
+
+/// Return the convex hull of `points`, for example the visible set from a
+/// query.  Collinear points along hull edges, and repeated coordinates, are
+/// not included as vertices.
+pub fn convex_hull<'a, I: IntoIterator<Item = &'a Point>>(points: I) -> ConvexHull {
+    let mut coordinates: Vec<(i32, i32)> = points
+        .into_iter()
+        .map(|Point { coordinates, .. }| *coordinates)
+        .collect();
+    coordinates.sort_unstable();
+    coordinates.dedup();
+    if coordinates.len() < 3 {
+        return ConvexHull {
+            vertices: coordinates,
+            area: 0.0,
+        };
+    }
+
+    // Andrew’s monotone chain, joining lower and upper hulls
+    let mut vertices = half_hull(coordinates.iter());
+    let mut upper = half_hull(coordinates.iter().rev());
+    vertices.pop();
+    upper.pop();
+    vertices.append(&mut upper);
+
+    let area = polygon_area(&vertices);
+    ConvexHull { vertices, area }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{convex_hull, polygon_area};
+    use crate::domain::{Direction, Point};
+
+    fn points_at(coordinates: &[(i32, i32)]) -> Vec<Point> {
+        coordinates
+            .iter()
+            .enumerate()
+            .map(|(index, coordinates)| Point {
+                coordinates: *coordinates,
+                number: index as u32 + 1,
+                direction: Direction::North,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn convex_hull_gives_expected_result() {
+        // arrange
+        let points = points_at(&[(0, 0), (4, 0), (2, 2), (4, 4), (0, 4), (2, 0), (1, 3)]);
+
+        // act
+        let outcome = convex_hull(&points);
+
+        // assert
+        assert_eq!(outcome.vertices, vec![(0, 0), (4, 0), (4, 4), (0, 4)]);
+        assert_eq!(outcome.area, 16.0);
+    }
+
+    #[test]
+    fn convex_hull_handles_degenerate_input() {
+        // arrange
+        let points = points_at(&[(1, 1), (1, 1), (3, 3)]);
+
+        // act
+        let outcome = convex_hull(&points);
+
+        // assert
+        assert_eq!(outcome.vertices, vec![(1, 1), (3, 3)]);
+        assert_eq!(outcome.area, 0.0);
+
+        // arrange
+        let points = points_at(&[(0, 0), (1, 1), (2, 2)]);
+
+        // act
+        let outcome = convex_hull(&points);
+
+        // assert
+        assert_eq!(outcome.vertices, vec![(0, 0), (2, 2)]);
+        assert_eq!(outcome.area, 0.0);
+
+        // act
+        let outcome = convex_hull(&[]);
+
+        // assert
+        assert_eq!(outcome.vertices.len(), 0);
+    }
+
+    #[test]
+    fn polygon_area_gives_expected_result() {
+        // arrange
+        let vertices = [(0, 0), (4, 0), (0, 3)];
+
+        // act
+        let outcome = polygon_area(&vertices);
+
+        // assert
+        assert_eq!(outcome, 6.0);
+    }
+}
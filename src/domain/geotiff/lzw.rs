@@ -0,0 +1,165 @@
+//! Decoder for TIFF LZW compressed strips and tiles, with codes packed most
+//! significant bit first, and code widths growing one code early, as libtiff
+//! writes them
+
+use super::geotiff_error;
+use crate::utilities::AppError;
+
+const CLEAR: usize = 256;
+const END_OF_INFORMATION: usize = 257;
+
+/// First code added to the table after a clear code
+const FIRST_CODE: usize = 258;
+
+const MAX_BITS: u32 = 12;
+
+/// Reads codes of varying width, most significant bit first
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn read(&mut self, bits: u32) -> Option<usize> {
+        let mut result = 0;
+        for _ in 0..bits {
+            let byte = self.bytes.get(self.position / 8)?;
+            let bit = (byte >> (7 - self.position % 8)) & 1;
+            result = (result << 1) | usize::from(bit);
+            self.position += 1;
+        }
+        Some(result)
+    }
+}
+
+/// Decode the LZW data `bytes`, stopping once `expected` bytes are decoded.
+/// Each table entry is held as the position and length of a run of bytes
+/// already decoded, since every entry extends the string decoded just before
+/// it by the byte decoded straight after.
+pub(super) fn decode(bytes: &[u8], expected: usize) -> Result<Vec<u8>, AppError> {
+    let invalid = || geotiff_error(String::from("invalid LZW data"));
+    let mut reader = BitReader { bytes, position: 0 };
+    let mut result = Vec::with_capacity(expected.min(bytes.len().saturating_mul(16)));
+    let mut table: Vec<(usize, usize)> = vec![];
+    let mut previous: Option<(usize, usize)> = None;
+    let mut bits = 9;
+    while result.len() < expected {
+        let code = match reader.read(bits) {
+            Some(value) => value,
+            None => break,
+        };
+        match code {
+            CLEAR => {
+                table.clear();
+                previous = None;
+                bits = 9;
+                continue;
+            }
+            END_OF_INFORMATION => break,
+            _ => {}
+        }
+        let start = result.len();
+        let length = if code < CLEAR {
+            result.push(code as u8);
+            1
+        } else {
+            let index = code - FIRST_CODE;
+            if let Some(&(position, length)) = table.get(index) {
+                result.extend_from_within(position..position + length);
+                length
+            } else if index == table.len() {
+                let (position, length) = previous.ok_or_else(invalid)?;
+                result.extend_from_within(position..position + length);
+                result.push(result[position]);
+                length + 1
+            } else {
+                return Err(invalid());
+            }
+        };
+        if let Some((position, previous_length)) = previous {
+            if FIRST_CODE + table.len() < 1 << MAX_BITS {
+                table.push((position, previous_length + 1));
+            }
+        }
+        previous = Some((start, length));
+        if bits < MAX_BITS && FIRST_CODE + table.len() + 1 >= 1 << bits {
+            bits += 1;
+        }
+    }
+    result.truncate(expected);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::decode;
+    use std::collections::HashMap;
+
+    /// LZW encoding of `bytes`, as libtiff writes it
+    fn encode(bytes: &[u8]) -> Vec<u8> {
+        let mut bits = Vec::new();
+        let mut width = 9;
+        let mut write = |code: usize, width: u32| {
+            for shift in (0..width).rev() {
+                bits.push((code >> shift) & 1 == 1);
+            }
+        };
+        write(256, width);
+        let mut table: HashMap<Vec<u8>, usize> = HashMap::new();
+        let mut next = 258;
+        let mut current: Vec<u8> = vec![];
+        let code = |table: &HashMap<Vec<u8>, usize>, string: &[u8]| {
+            if string.len() == 1 {
+                usize::from(string[0])
+            } else {
+                table[string]
+            }
+        };
+        for byte in bytes {
+            let mut candidate = current.clone();
+            candidate.push(*byte);
+            if current.is_empty() || table.contains_key(&candidate) {
+                current = candidate;
+                continue;
+            }
+            write(code(&table, &current), width);
+            table.insert(candidate, next);
+            next += 1;
+            if next > (1 << width) - 1 && width < 12 {
+                width += 1;
+            }
+            current = vec![*byte];
+        }
+        if !current.is_empty() {
+            write(code(&table, &current), width);
+            next += 1;
+            if next > (1 << width) - 1 && width < 12 {
+                width += 1;
+            }
+        }
+        write(257, width);
+        bits.chunks(8)
+            .map(|chunk| {
+                chunk.iter().enumerate().fold(0, |byte, (index, bit)| {
+                    byte | (u8::from(*bit) << (7 - index))
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn decode_gives_expected_result() {
+        // arrange
+        let bytes: Vec<u8> = (0..3000_u32)
+            .map(|index| ((index * index) % 251 / 3) as u8)
+            .collect();
+
+        // act
+        let outcome = decode(&encode(&bytes), bytes.len()).unwrap();
+
+        // assert
+        assert_eq!(outcome, bytes);
+        assert_eq!(decode(&encode(b"TOBEORNOTTOBE"), 6).unwrap(), b"TOBEOR");
+        assert!(decode(&[0x80, 0x4b, 0x00], 4).is_err());
+    }
+}
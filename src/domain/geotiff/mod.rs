@@ -0,0 +1,632 @@
+//! Reader for digital elevation models stored as GeoTIFF files, enabled by
+//! the `geotiff` feature.  The first band of the first image is read, from
+//! strips or tiles, uncompressed or LZW compressed, with or without a
+//! predictor, holding integer or floating point samples.  Georeferencing comes
+//! from the model pixel scale and tie point tags, or the model transformation
+//! tag, and `GDAL_NODATA` marks missing elevations.  Coordinates are in the
+//! units of the model, which should match those of the points, and elevations
+//! in the same units.
+
+mod lzw;
+mod tiff;
+
+use crate::{
+    domain::viewshed::{Profile, Sightline},
+    utilities::{AppError, Span},
+};
+use std::{convert::TryFrom, fs, path::Path};
+use tiff::Directory;
+
+const IMAGE_WIDTH: u16 = 256;
+const IMAGE_LENGTH: u16 = 257;
+const BITS_PER_SAMPLE: u16 = 258;
+const COMPRESSION: u16 = 259;
+const STRIP_OFFSETS: u16 = 273;
+const SAMPLES_PER_PIXEL: u16 = 277;
+const ROWS_PER_STRIP: u16 = 278;
+const STRIP_BYTE_COUNTS: u16 = 279;
+const PLANAR_CONFIGURATION: u16 = 284;
+const PREDICTOR: u16 = 317;
+const TILE_WIDTH: u16 = 322;
+const TILE_LENGTH: u16 = 323;
+const TILE_OFFSETS: u16 = 324;
+const TILE_BYTE_COUNTS: u16 = 325;
+const SAMPLE_FORMAT: u16 = 339;
+const MODEL_PIXEL_SCALE: u16 = 33550;
+const MODEL_TIEPOINT: u16 = 33922;
+const MODEL_TRANSFORMATION: u16 = 34264;
+const GEO_KEY_DIRECTORY: u16 = 34735;
+const GDAL_NODATA: u16 = 42113;
+
+/// Geo key giving whether pixels are areas, or points at their centres
+const RASTER_TYPE_GEO_KEY: u64 = 1025;
+const RASTER_PIXEL_IS_POINT: u64 = 2;
+
+/// Largest number of pixels read, so that a corrupt header cannot exhaust
+/// memory
+const MAX_PIXELS: usize = 1 << 28;
+
+pub(crate) fn geotiff_error(message: String) -> AppError {
+    AppError::GeoTIFFError(message)
+}
+
+/// Affine map from raster column and row to model x,y coordinates
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Transform([f64; 6]);
+
+impl Transform {
+    fn apply(&self, (column, row): (f64, f64)) -> (f64, f64) {
+        let [a, b, c, d, e, f] = self.0;
+        (a * column + b * row + c, d * column + e * row + f)
+    }
+
+    fn inverse(&self) -> Option<Transform> {
+        let [a, b, c, d, e, f] = self.0;
+        let determinant = a * e - b * d;
+        if determinant == 0.0 || !determinant.is_finite() {
+            return None;
+        }
+        Some(Transform([
+            e / determinant,
+            -b / determinant,
+            (b * f - c * e) / determinant,
+            -d / determinant,
+            a / determinant,
+            (c * d - a * f) / determinant,
+        ]))
+    }
+}
+
+/// Digital elevation model: a grid of elevations, sampled between cell
+/// centres by bilinear interpolation
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dem {
+    width: usize,
+    height: usize,
+
+    /// Elevations by row, and then column, from the top left, with `NaN` for
+    /// missing values
+    elevations: Vec<f32>,
+
+    /// Map from model coordinates to raster column and row, with cell centres
+    /// at whole numbers
+    to_raster: Transform,
+
+    /// Model distance between neighbouring cell centres, along the shorter of
+    /// the rows and columns
+    spacing: f64,
+}
+
+/// Sample format of a raster
+#[derive(Copy, Clone, Debug)]
+enum SampleFormat {
+    Unsigned,
+    Signed,
+    Float,
+}
+
+impl SampleFormat {
+    fn decode(self, value: &[u8], big_endian: bool) -> f32 {
+        let mut bytes = [0; 8];
+        let length = value.len();
+        if big_endian {
+            bytes[8 - length..].copy_from_slice(value);
+        } else {
+            bytes[..length].copy_from_slice(value);
+        }
+        let bits = if big_endian {
+            u64::from_be_bytes(bytes)
+        } else {
+            u64::from_le_bytes(bytes)
+        };
+        let shift = 64 - 8 * length as u32;
+        match (self, length) {
+            (SampleFormat::Float, 4) => f32::from_bits(bits as u32),
+            (SampleFormat::Float, _) => f64::from_bits(bits) as f32,
+            (SampleFormat::Signed, _) => ((bits << shift) as i64 >> shift) as f32,
+            (SampleFormat::Unsigned, _) => bits as f32,
+        }
+    }
+}
+
+/// Undo horizontal differencing of the `row` of `bytes_per_sample` byte
+/// integer samples, stored in the file byte order
+fn undo_horizontal_predictor(row: &mut [u8], bytes_per_sample: usize, big_endian: bool) {
+    let read = |value: &[u8]| {
+        value
+            .iter()
+            .enumerate()
+            .fold(0_u64, |result, (index, byte)| {
+                let shift = if big_endian {
+                    8 * (value.len() - 1 - index)
+                } else {
+                    8 * index
+                };
+                result | (u64::from(*byte) << shift)
+            })
+    };
+    let mut previous = 0_u64;
+    for sample in row.chunks_mut(bytes_per_sample) {
+        previous = previous.wrapping_add(read(sample));
+        for (index, byte) in sample.iter_mut().enumerate() {
+            let shift = if big_endian {
+                8 * (bytes_per_sample - 1 - index)
+            } else {
+                8 * index
+            };
+            *byte = (previous >> shift) as u8;
+        }
+    }
+}
+
+/// Undo the floating point predictor for the `row`, which differences bytes,
+/// after grouping them by significance, most significant first.  Samples are
+/// left big endian.
+fn undo_floating_point_predictor(row: &mut [u8], bytes_per_sample: usize) {
+    for index in 1..row.len() {
+        row[index] = row[index].wrapping_add(row[index - 1]);
+    }
+    let samples = row.len() / bytes_per_sample;
+    let shuffled = row.to_vec();
+    for sample in 0..samples {
+        for byte in 0..bytes_per_sample {
+            row[sample * bytes_per_sample + byte] = shuffled[byte * samples + sample];
+        }
+    }
+}
+
+/// Layout of the strips or tiles holding the raster
+struct Chunks {
+    width: usize,
+    height: usize,
+    offsets: Vec<u64>,
+    byte_counts: Vec<u64>,
+}
+
+fn chunks(directory: &Directory, width: usize, height: usize) -> Result<Chunks, AppError> {
+    let missing = |name: &str| geotiff_error(format!("missing `{name}` tag"));
+    let (chunk_width, chunk_height, offsets, byte_counts) =
+        match directory.integers(TILE_OFFSETS)? {
+            Some(offsets) => (
+                directory.integer(TILE_WIDTH, 0)?,
+                directory.integer(TILE_LENGTH, 0)?,
+                offsets,
+                directory
+                    .integers(TILE_BYTE_COUNTS)?
+                    .ok_or_else(|| missing("TileByteCounts"))?,
+            ),
+            None => (
+                width as u64,
+                directory
+                    .integer(ROWS_PER_STRIP, height as u64)?
+                    .min(height as u64),
+                directory
+                    .integers(STRIP_OFFSETS)?
+                    .ok_or_else(|| missing("StripOffsets"))?,
+                directory
+                    .integers(STRIP_BYTE_COUNTS)?
+                    .ok_or_else(|| missing("StripByteCounts"))?,
+            ),
+        };
+    let (chunk_width, chunk_height) =
+        match (usize::try_from(chunk_width), usize::try_from(chunk_height)) {
+            (Ok(columns), Ok(rows))
+                if columns > 0 && rows > 0 && columns.saturating_mul(rows) <= MAX_PIXELS =>
+            {
+                (columns, rows)
+            }
+            _ => return Err(geotiff_error(String::from("invalid strip or tile size"))),
+        };
+    let across = (width + chunk_width - 1) / chunk_width;
+    let down = (height + chunk_height - 1) / chunk_height;
+    if offsets.len() < across * down || byte_counts.len() < offsets.len() {
+        return Err(geotiff_error(String::from("too few strips or tiles")));
+    }
+    Ok(Chunks {
+        width: chunk_width,
+        height: chunk_height,
+        offsets,
+        byte_counts,
+    })
+}
+
+/// Map from raster column and row, with cell centres at whole numbers, to
+/// model coordinates
+fn transform(directory: &Directory) -> Result<Transform, AppError> {
+    let pixel_is_point = directory
+        .integers(GEO_KEY_DIRECTORY)?
+        .map_or(false, |keys| {
+            keys.get(4..).unwrap_or(&[]).chunks(4).any(|key| {
+                key.len() == 4
+                    && key[0] == RASTER_TYPE_GEO_KEY
+                    && key[1] == 0
+                    && key[3] == RASTER_PIXEL_IS_POINT
+            })
+        });
+    // cell centres are half a cell in from the corner the model points to
+    let centre = if pixel_is_point { 0.0 } else { 0.5 };
+    let [a, b, c, d, e, f] = match (
+        directory.numbers(MODEL_TRANSFORMATION)?,
+        directory.numbers(MODEL_PIXEL_SCALE)?,
+        directory.numbers(MODEL_TIEPOINT)?,
+    ) {
+        (Some(matrix), _, _) if matrix.len() >= 8 => [
+            matrix[0], matrix[1], matrix[3], matrix[4], matrix[5], matrix[7],
+        ],
+        (_, Some(scale), Some(tiepoint)) if scale.len() >= 2 && tiepoint.len() >= 6 => [
+            scale[0],
+            0.0,
+            tiepoint[3] - tiepoint[0] * scale[0],
+            0.0,
+            -scale[1],
+            tiepoint[4] + tiepoint[1] * scale[1],
+        ],
+        _ => {
+            return Err(geotiff_error(String::from(
+                "no georeferencing, expected model pixel scale and tie point, or model transformation tags",
+            )))
+        }
+    };
+    Ok(Transform([
+        a,
+        b,
+        c + centre * (a + b),
+        d,
+        e,
+        f + centre * (d + e),
+    ]))
+}
+
+impl Dem {
+    /// Read the elevation model held in the GeoTIFF `bytes`
+    pub fn from_geotiff(bytes: &[u8]) -> Result<Self, AppError> {
+        let _span = Span::enter("Dem::from_geotiff", || format!("{} bytes", bytes.len()));
+        let directory = Directory::parse(bytes)?;
+        let big_endian = directory.layout.big_endian;
+        let size = |tag| {
+            directory.integer(tag, 0).and_then(|value| {
+                usize::try_from(value)
+                    .map_err(|_| geotiff_error(String::from("image is too large")))
+            })
+        };
+        let (width, height) = (size(IMAGE_WIDTH)?, size(IMAGE_LENGTH)?);
+        if width == 0 || height == 0 || width.saturating_mul(height) > MAX_PIXELS {
+            return Err(geotiff_error(format!(
+                "unsupported image size {width} by {height}"
+            )));
+        }
+        let samples_per_pixel = size(SAMPLES_PER_PIXEL)?.max(1);
+        if samples_per_pixel > 1 && directory.integer(PLANAR_CONFIGURATION, 1)? != 1 {
+            return Err(geotiff_error(String::from(
+                "bands stored in separate planes are not supported",
+            )));
+        }
+        let bits = directory.integer(BITS_PER_SAMPLE, 1)?;
+        let format = match directory.integer(SAMPLE_FORMAT, 1)? {
+            1 => SampleFormat::Unsigned,
+            2 => SampleFormat::Signed,
+            3 if bits == 32 || bits == 64 => SampleFormat::Float,
+            value => {
+                return Err(geotiff_error(format!(
+                    "sample format {value} with {bits} bit samples is not supported"
+                )))
+            }
+        };
+        if ![8, 16, 32, 64].contains(&bits) {
+            return Err(geotiff_error(format!(
+                "{bits} bit samples are not supported"
+            )));
+        }
+        let sample_size = bits as usize / 8;
+        let pixel_size = sample_size * samples_per_pixel;
+        let compression = directory.integer(COMPRESSION, 1)?;
+        if compression != 1 && compression != 5 {
+            return Err(geotiff_error(format!(
+                "compression {compression} is not supported, only none and LZW are"
+            )));
+        }
+        let predictor = directory.integer(PREDICTOR, 1)?;
+        let no_data = directory
+            .text(GDAL_NODATA)
+            .and_then(|value| value.parse::<f32>().ok());
+        let to_model = transform(&directory)?;
+        let to_raster = to_model
+            .inverse()
+            .ok_or_else(|| geotiff_error(String::from("georeferencing is degenerate")))?;
+        let [a, b, _, d, e, _] = to_model.0;
+        let spacing = a.hypot(d).min(b.hypot(e));
+
+        let chunks = chunks(&directory, width, height)?;
+        let row_size = chunks.width * pixel_size;
+        let chunk_size = row_size * chunks.height;
+        let across = (width + chunks.width - 1) / chunks.width;
+        let mut elevations = vec![f32::NAN; width * height];
+        for (index, (offset, byte_count)) in
+            chunks.offsets.iter().zip(&chunks.byte_counts).enumerate()
+        {
+            let (top, left) = (
+                index / across * chunks.height,
+                index % across * chunks.width,
+            );
+            if top >= height {
+                break;
+            }
+            let data = usize::try_from(*offset)
+                .ok()
+                .zip(usize::try_from(*byte_count).ok())
+                .and_then(|(start, length)| bytes.get(start..start.checked_add(length)?))
+                .ok_or_else(|| geotiff_error(format!("truncated strip or tile {index}")))?;
+            let mut chunk = match compression {
+                5 => lzw::decode(data, chunk_size)?,
+                _ => data.to_vec(),
+            };
+            // the last strip may hold fewer rows
+            let rows = (chunk.len() / row_size).min(chunks.height);
+            let mut sample_big_endian = big_endian;
+            for row in chunk.chunks_mut(row_size).take(rows) {
+                match predictor {
+                    1 => {}
+                    2 => undo_horizontal_predictor(row, sample_size, big_endian),
+                    3 => {
+                        undo_floating_point_predictor(row, sample_size);
+                        sample_big_endian = true;
+                    }
+                    value => {
+                        return Err(geotiff_error(format!("predictor {value} is not supported")))
+                    }
+                }
+            }
+            for row in 0..rows.min(height - top) {
+                for column in 0..chunks.width.min(width - left) {
+                    let position = row * row_size + column * pixel_size;
+                    let value =
+                        format.decode(&chunk[position..position + sample_size], sample_big_endian);
+                    elevations[(top + row) * width + left + column] = if Some(value) == no_data {
+                        f32::NAN
+                    } else {
+                        value
+                    };
+                }
+            }
+        }
+        Ok(Dem {
+            width,
+            height,
+            elevations,
+            to_raster,
+            spacing,
+        })
+    }
+
+    /// Elevation at model coordinates `coordinates`, interpolated between the
+    /// four nearest cell centres, or `None` off the model or where any of
+    /// those cells has no elevation.  Within half a cell of the edge, the edge
+    /// cells are used.
+    pub fn elevation(&self, coordinates: (f64, f64)) -> Option<f64> {
+        let (column, row) = self.to_raster.apply(coordinates);
+        let in_range = |value: f64, size: usize| value >= -0.5 && value <= size as f64 - 0.5;
+        if !(in_range(column, self.width) && in_range(row, self.height)) {
+            return None;
+        }
+        let column = column.max(0.0).min((self.width - 1) as f64);
+        let row = row.max(0.0).min((self.height - 1) as f64);
+        let (left, top) = (column.floor() as usize, row.floor() as usize);
+        let (right, bottom) = (
+            (left + 1).min(self.width - 1),
+            (top + 1).min(self.height - 1),
+        );
+        let (across, down) = (column - left as f64, row - top as f64);
+        let cell =
+            |column: usize, row: usize| f64::from(self.elevations[row * self.width + column]);
+        // exact where neighbouring cells agree, and ignoring cells with no weight
+        let lerp = |from: f64, to: f64, fraction: f64| {
+            if fraction > 0.0 {
+                from + (to - from) * fraction
+            } else {
+                from
+            }
+        };
+        let value = lerp(
+            lerp(cell(left, top), cell(right, top), across),
+            lerp(cell(left, bottom), cell(right, bottom), across),
+            down,
+        );
+        if value.is_nan() {
+            None
+        } else {
+            Some(value)
+        }
+    }
+
+    /// Ground beneath the straight line from `from` to `to`, sampled every
+    /// half cell, leaving out stretches with no elevations, or `None` where
+    /// the ground at either end is unknown
+    pub fn profile(&self, from: (f64, f64), to: (f64, f64)) -> Option<Profile> {
+        let distance = (to.0 - from.0).hypot(to.1 - from.1);
+        let (start, end) = (self.elevation(from)?, self.elevation(to)?);
+        let steps = (2.0 * distance / self.spacing).ceil();
+        let steps = if steps.is_finite() { steps as usize } else { 0 };
+        let samples = (1..steps)
+            .filter_map(|step| {
+                let fraction = step as f64 / steps as f64;
+                let position = (
+                    from.0 + fraction * (to.0 - from.0),
+                    from.1 + fraction * (to.1 - from.1),
+                );
+                self.elevation(position)
+                    .map(|ground| (fraction * distance, ground))
+            })
+            .collect();
+        Some(Profile {
+            distance,
+            start,
+            end,
+            samples,
+        })
+    }
+
+    /// Returns true unless the terrain rises above the sightline from `from`
+    /// to `to`, judged as `sightline` gives, or by more than its Fresnel zone
+    /// allows.  Where the ground at either end is unknown, the sightline is
+    /// judged over a smooth Earth at sea level instead.
+    pub fn is_clear(&self, from: (f64, f64), to: (f64, f64), sightline: &Sightline) -> bool {
+        let profile = self
+            .profile(from, to)
+            .unwrap_or_else(|| Profile::smooth((to.0 - from.0).hypot(to.1 - from.1)));
+        sightline.is_clear(&sightline.clearance(&profile))
+    }
+}
+
+/// Read the GeoTIFF elevation model at `path`
+pub fn parse_dem_file<P: AsRef<Path>>(path: P) -> Result<Dem, AppError> {
+    let path = path.as_ref();
+    let bytes = fs::read(path).map_err(|error| AppError::BinaryFileError {
+        path: path.display().to_string(),
+        source: error,
+    })?;
+    Dem::from_geotiff(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_dem_file, Dem};
+    use crate::domain::Sightline;
+
+    /// Little endian GeoTIFF holding `rows` of signed 16 bit elevations,
+    /// uncompressed in a single strip, with cells `scale` units wide and the
+    /// top left corner at `corner`
+    fn geotiff(rows: &[&[i16]], scale: f64, corner: (f64, f64)) -> Vec<u8> {
+        let (width, height) = (rows[0].len() as u32, rows.len() as u32);
+        let strip: Vec<u8> = rows
+            .iter()
+            .flat_map(|row| row.iter().flat_map(|value| value.to_le_bytes()))
+            .collect();
+        let doubles = |values: &[f64]| -> Vec<u8> {
+            values
+                .iter()
+                .flat_map(|value| value.to_le_bytes())
+                .collect()
+        };
+        let scale = doubles(&[scale, scale, 0.0]);
+        let tiepoint = doubles(&[0.0, 0.0, 0.0, corner.0, corner.1, 0.0]);
+        let no_data = b"-32768\0".to_vec();
+        let entries: Vec<(u16, u16, u32, Vec<u8>)> = vec![
+            (256, 4, 1, width.to_le_bytes().to_vec()),
+            (257, 4, 1, height.to_le_bytes().to_vec()),
+            (258, 3, 1, 16_u16.to_le_bytes().to_vec()),
+            (259, 3, 1, 1_u16.to_le_bytes().to_vec()),
+            (273, 4, 1, vec![]),
+            (277, 3, 1, 1_u16.to_le_bytes().to_vec()),
+            (278, 4, 1, height.to_le_bytes().to_vec()),
+            (279, 4, 1, (strip.len() as u32).to_le_bytes().to_vec()),
+            (339, 3, 1, 2_u16.to_le_bytes().to_vec()),
+            (33550, 12, 3, scale),
+            (33922, 12, 6, tiepoint),
+            (42113, 2, no_data.len() as u32, no_data),
+        ];
+        let directory_size = 2 + 12 * entries.len() + 4;
+        let mut extra = vec![];
+        let extra_start = 8 + directory_size;
+        let mut directory = (entries.len() as u16).to_le_bytes().to_vec();
+        for (tag, kind, count, value) in &entries {
+            directory.extend_from_slice(&tag.to_le_bytes());
+            directory.extend_from_slice(&kind.to_le_bytes());
+            directory.extend_from_slice(&count.to_le_bytes());
+            if value.len() > 4 {
+                directory.extend_from_slice(&((extra_start + extra.len()) as u32).to_le_bytes());
+                extra.extend_from_slice(value);
+            } else if *tag == 273 {
+                directory.extend_from_slice(&[0; 4]);
+            } else {
+                let mut inline = value.clone();
+                inline.resize(4, 0);
+                directory.extend(inline);
+            }
+        }
+        directory.extend_from_slice(&[0; 4]);
+        let strip_offset = (extra_start + extra.len()) as u32;
+        let position = 2 + 4 * 12 + 8;
+        directory[position..position + 4].copy_from_slice(&strip_offset.to_le_bytes());
+        let mut result = b"II*\0".to_vec();
+        result.extend_from_slice(&8_u32.to_le_bytes());
+        result.extend(directory);
+        result.extend(extra);
+        result.extend(strip);
+        result
+    }
+
+    #[test]
+    fn dem_samples_elevations_bilinearly() {
+        // arrange
+        let bytes = geotiff(&[&[0, 10, 20], &[10, 20, -32768]], 2.0, (100.0, 50.0));
+
+        // act
+        let dem = Dem::from_geotiff(&bytes).unwrap();
+
+        // assert
+        assert_eq!(dem.elevation((101.0, 49.0)), Some(0.0));
+        assert_eq!(dem.elevation((102.0, 49.0)), Some(5.0));
+        assert_eq!(dem.elevation((102.0, 48.0)), Some(10.0));
+        assert_eq!(dem.elevation((100.0, 50.0)), Some(0.0));
+        assert_eq!(dem.elevation((105.0, 49.0)), Some(20.0));
+        assert_eq!(dem.elevation((105.0, 47.0)), None);
+        assert_eq!(dem.elevation((99.0, 49.0)), None);
+        assert!(Dem::from_geotiff(&bytes[..40]).is_err());
+    }
+
+    #[test]
+    fn dem_is_clear_gives_expected_result() {
+        // arrange
+        let ridge: &[i16] = &[0, 0, 0, 0, 0, 9, 0, 0, 0, 0];
+        let dem = Dem::from_geotiff(&geotiff(&[ridge, ridge], 1.0, (0.0, 2.0))).unwrap();
+        let eye = |height| Sightline {
+            observer_height: height,
+            ..Sightline::default()
+        };
+
+        // act
+        let outcome = dem.is_clear((0.5, 1.0), (9.5, 1.0), &eye(2.0));
+
+        // assert
+        assert!(!outcome);
+        assert!(dem.is_clear((0.5, 1.0), (9.5, 1.0), &eye(24.0)));
+        assert!(dem.is_clear((0.5, 1.0), (3.5, 1.0), &eye(0.0)));
+        assert!(dem.is_clear((0.5, 1.0), (20.0, 1.0), &eye(0.0)));
+        let radio = |frequency| Sightline {
+            frequency: Some(frequency),
+            fresnel_clearance: 0.6,
+            ..eye(24.0)
+        };
+        assert!(!dem.is_clear((0.5, 1.0), (9.5, 1.0), &radio(1.0)));
+        assert!(dem.is_clear((0.5, 1.0), (9.5, 1.0), &radio(1e6)));
+        assert_eq!(
+            dem.profile((0.5, 1.0), (3.5, 1.0)).unwrap().samples.len(),
+            5
+        );
+        assert_eq!(dem.profile((0.5, 1.0), (20.0, 1.0)), None);
+        let fixture = parse_dem_file("./fixtures/valid_dem.tif").unwrap();
+        assert_eq!(fixture.elevation((28.0, 42.0)), Some(10.0));
+        assert_eq!(fixture.elevation((27.5, 44.5)), Some(25.0));
+        assert!(!fixture.is_clear((28.0, 42.0), (27.0, 46.0), &eye(1.5)));
+    }
+
+    #[test]
+    fn dem_is_clear_allows_for_earth_curvature() {
+        // arrange
+        let plain: &[i16] = &[0; 30];
+        let dem = Dem::from_geotiff(&geotiff(&[plain], 1000.0, (0.0, 1000.0))).unwrap();
+        let sightline = Sightline {
+            observer_height: 10.0,
+            earth_curvature: true,
+            ..Sightline::default()
+        };
+
+        // act
+        let outcome = dem.is_clear((500.0, 500.0), (20_500.0, 500.0), &sightline);
+
+        // assert
+        assert!(!outcome);
+        assert!(dem.is_clear((500.0, 500.0), (5_500.0, 500.0), &sightline));
+        assert!(dem.is_clear((500.0, 500.0), (20_500.0, 500.0), &Sightline::default()));
+    }
+}
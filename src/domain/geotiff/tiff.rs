@@ -0,0 +1,190 @@
+//! Reader for the image file directory of a TIFF or BigTIFF file, giving the
+//! tag values of its first image
+
+use super::geotiff_error;
+use crate::utilities::AppError;
+use std::{collections::HashMap, convert::TryFrom};
+
+fn truncated() -> AppError {
+    geotiff_error(String::from("truncated TIFF data"))
+}
+
+/// Byte order of a TIFF file, and whether it is a BigTIFF, with 64 bit offsets
+#[derive(Copy, Clone, Debug)]
+pub(super) struct Layout {
+    pub(super) big_endian: bool,
+    big: bool,
+}
+
+impl Layout {
+    fn bytes<'a>(
+        &self,
+        bytes: &'a [u8],
+        position: usize,
+        length: usize,
+    ) -> Result<&'a [u8], AppError> {
+        bytes
+            .get(position..position.checked_add(length).ok_or_else(truncated)?)
+            .ok_or_else(truncated)
+    }
+
+    fn unsigned(&self, bytes: &[u8], position: usize, length: usize) -> Result<u64, AppError> {
+        let value = self.bytes(bytes, position, length)?;
+        let fold = |result: u64, byte: &u8| (result << 8) | u64::from(*byte);
+        Ok(if self.big_endian {
+            value.iter().fold(0, fold)
+        } else {
+            value.iter().rev().fold(0, fold)
+        })
+    }
+
+    fn offset(&self, bytes: &[u8], position: usize) -> Result<usize, AppError> {
+        let value = self.unsigned(bytes, position, if self.big { 8 } else { 4 })?;
+        usize::try_from(value).map_err(|_| truncated())
+    }
+}
+
+/// Values of a tag, as stored in the directory entry
+#[derive(Clone, Debug)]
+pub(super) struct Entry<'a> {
+    kind: u16,
+    count: usize,
+
+    /// Bytes holding the values, in the byte order of the file
+    data: &'a [u8],
+}
+
+/// Size, in bytes, of a value of TIFF field type `kind`
+fn type_size(kind: u16) -> Option<usize> {
+    match kind {
+        1 | 2 | 6 | 7 => Some(1),
+        3 | 8 => Some(2),
+        4 | 9 | 11 => Some(4),
+        5 | 10 | 12 | 16 | 17 | 18 => Some(8),
+        _ => None,
+    }
+}
+
+/// Tags of the first image of a TIFF file
+pub(super) struct Directory<'a> {
+    pub(super) layout: Layout,
+    entries: HashMap<u16, Entry<'a>>,
+}
+
+impl<'a> Directory<'a> {
+    /// Read the first image file directory of the TIFF file `bytes`
+    pub(super) fn parse(bytes: &'a [u8]) -> Result<Self, AppError> {
+        let big_endian = match bytes.get(..2) {
+            Some(b"II") => false,
+            Some(b"MM") => true,
+            _ => return Err(geotiff_error(String::from("not a TIFF file"))),
+        };
+        let mut layout = Layout {
+            big_endian,
+            big: false,
+        };
+        let (mut position, count_size, entry_size) = match layout.unsigned(bytes, 2, 2)? {
+            42 => (layout.offset(bytes, 4)?, 2, 12),
+            43 => {
+                layout.big = true;
+                (layout.offset(bytes, 8)?, 8, 20)
+            }
+            version => {
+                return Err(geotiff_error(format!(
+                    "TIFF version {version} is not supported"
+                )))
+            }
+        };
+        let count = usize::try_from(layout.unsigned(bytes, position, count_size)?)
+            .map_err(|_| truncated())?;
+        position += count_size;
+        let value_size = if layout.big { 8 } else { 4 };
+        let mut entries = HashMap::new();
+        for index in 0..count {
+            let start = index
+                .checked_mul(entry_size)
+                .and_then(|offset| offset.checked_add(position))
+                .ok_or_else(truncated)?;
+            let tag = layout.unsigned(bytes, start, 2)? as u16;
+            let kind = layout.unsigned(bytes, start + 2, 2)? as u16;
+            let count = usize::try_from(layout.unsigned(bytes, start + 4, value_size)?)
+                .map_err(|_| truncated())?;
+            let size = match type_size(kind) {
+                Some(value) => value.checked_mul(count).ok_or_else(truncated)?,
+                None => continue,
+            };
+            let value_position = start + 4 + value_size;
+            let data = if size <= value_size {
+                layout.bytes(bytes, value_position, size)?
+            } else {
+                layout.bytes(bytes, layout.offset(bytes, value_position)?, size)?
+            };
+            entries.insert(tag, Entry { kind, count, data });
+        }
+        Ok(Directory { layout, entries })
+    }
+
+    /// Values of tag `tag`, as numbers, or `None` if the tag is absent
+    pub(super) fn numbers(&self, tag: u16) -> Result<Option<Vec<f64>>, AppError> {
+        let Entry { kind, count, data } = match self.entries.get(&tag) {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+        let size = type_size(*kind).unwrap_or(1);
+        let layout = self.layout;
+        (0..*count)
+            .map(|index| {
+                let position = index * size;
+                let unsigned = layout.unsigned(data, position, size)?;
+                Ok(match kind {
+                    6 => f64::from(unsigned as u8 as i8),
+                    8 => f64::from(unsigned as u16 as i16),
+                    9 => f64::from(unsigned as u32 as i32),
+                    17 => unsigned as i64 as f64,
+                    11 => f64::from(f32::from_bits(unsigned as u32)),
+                    12 => f64::from_bits(unsigned),
+                    5 | 10 => {
+                        let numerator = layout.unsigned(data, position, 4)?;
+                        let denominator = layout.unsigned(data, position + 4, 4)?;
+                        if *kind == 5 {
+                            numerator as f64 / denominator as f64
+                        } else {
+                            f64::from(numerator as u32 as i32)
+                                / f64::from(denominator as u32 as i32)
+                        }
+                    }
+                    _ => unsigned as f64,
+                })
+            })
+            .collect::<Result<Vec<f64>, AppError>>()
+            .map(Some)
+    }
+
+    /// Values of tag `tag`, which should be whole numbers, such as offsets
+    pub(super) fn integers(&self, tag: u16) -> Result<Option<Vec<u64>>, AppError> {
+        Ok(self.numbers(tag)?.map(|values| {
+            values
+                .into_iter()
+                .map(|value| if value > 0.0 { value as u64 } else { 0 })
+                .collect()
+        }))
+    }
+
+    /// First value of tag `tag`, or `default` if the tag is absent
+    pub(super) fn integer(&self, tag: u16, default: u64) -> Result<u64, AppError> {
+        Ok(self
+            .integers(tag)?
+            .and_then(|values| values.first().copied())
+            .unwrap_or(default))
+    }
+
+    /// Text of the ASCII tag `tag`, without its trailing `NUL`
+    pub(super) fn text(&self, tag: u16) -> Option<String> {
+        self.entries.get(&tag).map(|Entry { data, .. }| {
+            String::from_utf8_lossy(data)
+                .trim_end_matches('\0')
+                .trim()
+                .to_string()
+        })
+    }
+}
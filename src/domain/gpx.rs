@@ -0,0 +1,270 @@
+//! Reader for GPX files, as recorded by handheld GPS units, taking waypoints,
+//! route points and track points as the neighbourhood
+
+use crate::{
+    domain::point::{angular_position_f64, heading_direction, read_points_file, Direction, Point},
+    utilities::{AppError, Span},
+};
+use std::path::Path;
+
+/// Mean radius of the Earth, in metres
+const EARTH_RADIUS: f64 = 6_371_008.8;
+
+fn gpx_error(message: String) -> AppError {
+    AppError::GPXParseError(message)
+}
+
+/// Position read from a `wpt`, `rtept` or `trkpt` element
+struct Fix {
+    latitude: f64,
+    longitude: f64,
+
+    /// Heading from a GPX 1.0 `course` element, in degrees clockwise from north
+    course: Option<f64>,
+
+    /// Track segment, route or waypoint the fix belongs to, so headings are
+    /// only derived between consecutive points of one sequence
+    sequence: usize,
+}
+
+/// Value of attribute `name` within the opening tag `tag`
+fn attribute(tag: &str, name: &str) -> Option<f64> {
+    let mut rest = tag;
+    while let Some(index) = rest.find(name) {
+        let preceding = rest[..index].chars().last();
+        let after = rest[index + name.len()..].trim_start();
+        rest = &rest[index + name.len()..];
+        if !preceding.map_or(false, char::is_whitespace) {
+            continue;
+        }
+        let value = match after.strip_prefix('=') {
+            Some(value) => value.trim_start(),
+            None => continue,
+        };
+        let quote = value.chars().next()?;
+        if quote != '"' && quote != '\'' {
+            return None;
+        }
+        let value = &value[1..];
+        return value[..value.find(quote)?].trim().parse().ok();
+    }
+    None
+}
+
+/// Name of the element opened or closed by `tag`, without any namespace
+/// prefix, and whether it is a closing tag
+fn element_name(tag: &str) -> (&str, bool) {
+    let (tag, closing) = match tag.strip_prefix('/') {
+        Some(value) => (value, true),
+        None => (tag, false),
+    };
+    let name = tag
+        .split(|character: char| character.is_whitespace() || character == '/')
+        .next()
+        .unwrap_or_default();
+    (name.rsplit(':').next().unwrap_or(name), closing)
+}
+
+fn parse_fixes(gpx: &str) -> Result<Vec<Fix>, AppError> {
+    let mut result: Vec<Fix> = vec![];
+    let mut sequence = 0;
+    let mut open_fix = false;
+    let mut rest = gpx;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        if let Some(comment) = rest.strip_prefix("!--") {
+            let end = comment
+                .find("-->")
+                .ok_or_else(|| gpx_error(String::from("unterminated comment")))?;
+            rest = &comment[end + 3..];
+            continue;
+        }
+        let end = rest
+            .find('>')
+            .ok_or_else(|| gpx_error(String::from("unterminated tag")))?;
+        let tag = &rest[..end];
+        rest = &rest[end + 1..];
+        let self_closing = tag.ends_with('/');
+        match element_name(tag) {
+            ("trkseg", false) | ("rte", false) => sequence += 1,
+            (name @ "wpt", false) | (name @ "rtept", false) | (name @ "trkpt", false) => {
+                if name == "wpt" {
+                    sequence += 1;
+                }
+                let coordinate = |name| {
+                    attribute(tag, name)
+                        .filter(|value| value.is_finite())
+                        .ok_or_else(|| {
+                            gpx_error(format!("point {} has no valid `{name}`", result.len() + 1))
+                        })
+                };
+                result.push(Fix {
+                    latitude: coordinate("lat")?,
+                    longitude: coordinate("lon")?,
+                    course: None,
+                    sequence,
+                });
+                open_fix = !self_closing;
+            }
+            ("wpt", true) | ("rtept", true) | ("trkpt", true) => open_fix = false,
+            ("course", false) if open_fix && !self_closing => {
+                let text = &rest[..rest.find('<').unwrap_or(rest.len())];
+                if let Some(fix) = result.last_mut() {
+                    fix.course = text
+                        .trim()
+                        .parse()
+                        .ok()
+                        .filter(|value: &f64| value.is_finite());
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(result)
+}
+
+/// Parse points from a GPX document, numbering them from `1` in document
+/// order.  Latitudes and longitudes are projected to x,y coordinates in
+/// whole metres east and north of the first point, which is accurate enough
+/// over the few kilometres a handheld recording covers, so query radii are in
+/// metres.  A point faces the compass direction nearest its GPX 1.0 `course`,
+/// if it has one, otherwise the bearing to the next point of its track
+/// segment or route, or from the previous point for the last.  Lone
+/// waypoints without a course face north.
+pub fn parse_points_gpx(gpx: &str) -> Result<Vec<Point>, AppError> {
+    let _span = Span::enter("parse_points_gpx", || format!("{} bytes", gpx.len()));
+    let fixes = parse_fixes(gpx)?;
+    let (latitude_0, longitude_0) = match fixes.first() {
+        Some(value) => (value.latitude, value.longitude),
+        None => return Ok(vec![]),
+    };
+    let scale = EARTH_RADIUS * std::f64::consts::PI / 180.0;
+    let positions: Vec<(f64, f64)> = fixes
+        .iter()
+        .map(|fix| {
+            (
+                (fix.longitude - longitude_0) * scale * latitude_0.to_radians().cos(),
+                (fix.latitude - latitude_0) * scale,
+            )
+        })
+        .collect();
+    let bearing = |from: usize, to: usize| angular_position_f64(positions[from], positions[to]);
+    fixes
+        .iter()
+        .enumerate()
+        .map(|(index, fix)| {
+            let same_sequence = |other: usize| {
+                fixes
+                    .get(other)
+                    .map_or(false, |value| value.sequence == fix.sequence)
+            };
+            let heading = match fix.course {
+                Some(value) => Some(value),
+                None if same_sequence(index + 1) => Some(bearing(index, index + 1).to_degrees()),
+                None if index > 0 && same_sequence(index - 1) => {
+                    Some(bearing(index - 1, index).to_degrees())
+                }
+                None => None,
+            };
+            let (x, y) = positions[index];
+            let number =
+                u32::try_from(index + 1).map_err(|_| gpx_error(String::from("too many points")))?;
+            Ok(Point {
+                coordinates: (x.round() as i32, y.round() as i32),
+                number,
+                direction: heading.map_or(Direction::North, heading_direction),
+            })
+        })
+        .collect()
+}
+
+/// Parse the GPX points file at `path`
+pub(crate) fn parse_points_gpx_file(path: &Path) -> Result<Vec<Point>, AppError> {
+    parse_points_gpx(&read_points_file(path)?)
+}
+
+/// Returns true if `path` has a `.gpx` extension, and so is read as GPX
+pub(crate) fn is_gpx_path(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|value| value.to_str()),
+        Some("gpx")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_points_gpx;
+    use crate::domain::{parse_points_file, Direction, Point};
+
+    #[test]
+    fn parse_points_gpx_derives_headings_from_track() {
+        // arrange
+        let gpx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gpx version="1.1" creator="handheld" xmlns="http://www.topografix.com/GPX/1/1">
+  <!-- <trkpt lat="0" lon="0"/> is not a point -->
+  <wpt lat="51.5" lon="-0.1"><name>Camp</name></wpt>
+  <trk><trkseg>
+    <trkpt lat='51.5' lon='-0.0995'><ele>12</ele></trkpt>
+    <trkpt lat="51.5009" lon="-0.0995"/>
+    <trkpt lat="51.5009" lon="-0.0990"></trkpt>
+  </trkseg></trk>
+  <rte><rtept lat="51.5" lon="-0.1"><course>185.5</course></rtept></rte>
+</gpx>"#;
+
+        // act
+        let outcome = parse_points_gpx(gpx).unwrap();
+
+        // assert
+        assert_eq!(
+            outcome,
+            vec![
+                Point {
+                    coordinates: (0, 0),
+                    number: 1,
+                    direction: Direction::North
+                },
+                Point {
+                    coordinates: (35, 0),
+                    number: 2,
+                    direction: Direction::North
+                },
+                Point {
+                    coordinates: (35, 100),
+                    number: 3,
+                    direction: Direction::East
+                },
+                Point {
+                    coordinates: (69, 100),
+                    number: 4,
+                    direction: Direction::East
+                },
+                Point {
+                    coordinates: (0, 0),
+                    number: 5,
+                    direction: Direction::South
+                },
+            ]
+        );
+        assert_eq!(
+            parse_points_gpx(r#"<gpx><wpt lon="1"/></gpx>"#)
+                .unwrap_err()
+                .to_string(),
+            "Error parsing GPX: point 1 has no valid `lat`"
+        );
+        assert_eq!(parse_points_gpx("<gpx></gpx>").unwrap(), vec![]);
+    }
+
+    #[test]
+    fn parse_points_file_reads_gpx_files() {
+        // arrange
+        let path = "./fixtures/valid_points.gpx";
+
+        // act
+        let outcome = parse_points_file(path).unwrap();
+
+        // assert
+        assert_eq!(outcome.len(), 3);
+        assert_eq!(outcome[0].coordinates, (0, 0));
+        assert_eq!(outcome[2].direction, Direction::West);
+    }
+}
@@ -0,0 +1,817 @@
+use crate::{
+    domain::point::{euclidean_distance, sees, Point},
+    utilities::{AppError, Cancellation, Span},
+};
+use std::collections::VecDeque;
+
+/// Leading bytes of the bitset format written by
+/// [`VisibilityGraph::to_bitset`]
+pub const BITSET_MAGIC: &[u8; 4] = b"NBVM";
+
+/// Version of the bitset format written by [`VisibilityGraph::to_bitset`]
+pub const BITSET_VERSION: u8 = 1;
+
+/// Leading bytes of the serialised graph format written by
+/// [`VisibilityGraph::to_bytes`]
+pub const GRAPH_MAGIC: &[u8; 4] = b"NBVG";
+
+/// Version of the serialised graph format written by
+/// [`VisibilityGraph::to_bytes`]
+pub const GRAPH_VERSION: u8 = 1;
+
+/// Reads little-endian values from serialised graph bytes
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, length: usize) -> Result<&'a [u8], AppError> {
+        if self.bytes.len() < length {
+            return Err(AppError::GraphFormatError(String::from(
+                "unexpected end of data",
+            )));
+        }
+        let (result, rest) = self.bytes.split_at(length);
+        self.bytes = rest;
+        Ok(result)
+    }
+
+    fn u32(&mut self) -> Result<u32, AppError> {
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(self.take(4)?);
+        Ok(u32::from_le_bytes(bytes))
+    }
+
+    fn f64(&mut self) -> Result<f64, AppError> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(self.take(8)?);
+        Ok(f64::from_le_bytes(bytes))
+    }
+}
+
+/// Directed graph of which neighbourhood points see which others
+#[derive(Clone, Debug, PartialEq)]
+pub struct VisibilityGraph {
+    /// Half arc central angle, in degrees, used to compute the graph
+    pub half_arc_central_angle: u32,
+
+    /// Segment radius used to compute the graph
+    pub arc_radius: u32,
+
+    /// Point numbers in neighbourhood order.  Graph nodes are indices into
+    /// this vector.
+    pub numbers: Vec<u32>,
+
+    /// For each node, the nodes it sees, in ascending order, along with their
+    /// distance
+    pub edges: Vec<Vec<(usize, f64)>>,
+}
+
+/// Edges from `point` to the `neighbourhood` points it sees, in ascending
+/// order of index, along with their distance
+fn edges_from(
+    point: &Point,
+    half_arc_central_angle: u32,
+    arc_radius: u32,
+    neighbourhood: &[Point],
+) -> Vec<(usize, f64)> {
+    neighbourhood
+        .iter()
+        .enumerate()
+        .filter(|(_, neighbour)| sees(point, neighbour, half_arc_central_angle, arc_radius))
+        .map(|(index, neighbour)| {
+            (
+                index,
+                euclidean_distance(point.coordinates, neighbour.coordinates),
+            )
+        })
+        .collect()
+}
+
+/// Nodes reached from `start` by following `next` at most `max_hops` times,
+/// with the fewest hops to each, leaving out `start`
+fn breadth_first<F: FnMut(usize) -> Vec<usize>>(
+    start: usize,
+    node_count: usize,
+    max_hops: usize,
+    mut next: F,
+) -> Vec<(usize, usize)> {
+    let mut visited = vec![false; node_count];
+    visited[start] = true;
+    let mut result = vec![];
+    let mut frontier = vec![start];
+    for hops in 1..=max_hops {
+        let mut following = vec![];
+        for node in frontier {
+            for value in next(node) {
+                if !visited[value] {
+                    visited[value] = true;
+                    result.push((value, hops));
+                    following.push(value);
+                }
+            }
+        }
+        if following.is_empty() {
+            break;
+        }
+        frontier = following;
+    }
+    result
+}
+
+/// Point numbers of `reached` nodes, with their hop counts, in ascending
+/// number order
+fn numbered(numbers: &[u32], reached: Vec<(usize, usize)>) -> Vec<(u32, usize)> {
+    let mut result: Vec<(u32, usize)> = reached
+        .into_iter()
+        .map(|(node, hops)| (numbers[node], hops))
+        .collect();
+    result.sort_unstable();
+    result
+}
+
+impl VisibilityGraph {
+    /// Update the graph after the point at `node` moved or turned, where
+    /// `neighbourhood` is the graph’s neighbourhood with that one point
+    /// changed.  Only the moved point’s own edges, and edges to it from
+    /// points within range, are recomputed, so a live graph can keep up with
+    /// one move in linear, rather than quadratic, time.
+    ///
+    /// Returns the nodes whose edges changed, in ascending order.  If
+    /// `neighbourhood` has a different number of points to the graph, or
+    /// `node` is out of range, the whole graph is rebuilt instead, and every
+    /// node is returned.
+    pub fn update_point(&mut self, neighbourhood: &[Point], node: usize) -> Vec<usize> {
+        let _span = Span::enter("update_visibility_graph", || {
+            format!("node {node} of {}", neighbourhood.len())
+        });
+        if neighbourhood.len() != self.numbers.len() || node >= neighbourhood.len() {
+            *self = visibility_graph(self.half_arc_central_angle, self.arc_radius, neighbourhood);
+            return (0..neighbourhood.len()).collect();
+        }
+
+        let moved = &neighbourhood[node];
+        self.numbers[node] = moved.number;
+        let mut result = vec![];
+        for (index, point) in neighbourhood.iter().enumerate() {
+            if index == node {
+                let edges = edges_from(
+                    moved,
+                    self.half_arc_central_angle,
+                    self.arc_radius,
+                    neighbourhood,
+                );
+                if edges != self.edges[node] {
+                    self.edges[node] = edges;
+                    result.push(index);
+                }
+                continue;
+            }
+            let row = &mut self.edges[index];
+            let position = row.binary_search_by(|(to, _)| to.cmp(&node));
+            let edge = match sees(point, moved, self.half_arc_central_angle, self.arc_radius) {
+                true => Some(euclidean_distance(point.coordinates, moved.coordinates)),
+                false => None,
+            };
+            let changed = match (position, edge) {
+                (Ok(found), Some(distance)) if row[found].1 == distance => false,
+                (Ok(found), Some(distance)) => {
+                    row[found].1 = distance;
+                    true
+                }
+                (Ok(found), None) => {
+                    row.remove(found);
+                    true
+                }
+                (Err(insert), Some(distance)) => {
+                    row.insert(insert, (node, distance));
+                    true
+                }
+                (Err(_), None) => false,
+            };
+            if changed {
+                result.push(index);
+            }
+        }
+        result
+    }
+
+    /// Node of the first point numbered `number`, if any
+    pub fn node(&self, number: u32) -> Option<usize> {
+        self.numbers.iter().position(|val| *val == number)
+    }
+
+    /// Numbers of the points seen by the point numbered `number`, in
+    /// ascending order, whatever the neighbourhood order.  An empty vector is
+    /// returned if no point matching `number` is in the graph.
+    pub fn visible_numbers(&self, number: u32) -> Vec<u32> {
+        match self.node(number) {
+            Some(value) => {
+                let mut result: Vec<u32> = self.edges[value]
+                    .iter()
+                    .map(|(to, _)| self.numbers[*to])
+                    .collect();
+                result.sort_unstable();
+                result
+            }
+            None => vec![],
+        }
+    }
+
+    /// Numbers of points along a path from the point numbered `from` to the
+    /// point numbered `to`, following visibility edges, with the fewest hops.
+    /// Both end points are included.  `None` is returned if either point is not
+    /// in the graph, or no path exists.
+    pub fn shortest_path(&self, from: u32, to: u32) -> Option<Vec<u32>> {
+        let start = self.node(from)?;
+        let end = self.node(to)?;
+        let mut previous: Vec<Option<usize>> = vec![None; self.numbers.len()];
+        let mut visited = vec![false; self.numbers.len()];
+        visited[start] = true;
+        let mut queue = VecDeque::from(vec![start]);
+        while let Some(node) = queue.pop_front() {
+            if node == end {
+                let mut result = vec![self.numbers[node]];
+                let mut current = node;
+                while let Some(value) = previous[current] {
+                    result.push(self.numbers[value]);
+                    current = value;
+                }
+                result.reverse();
+                return Some(result);
+            }
+            for (next, _) in &self.edges[node] {
+                if !visited[*next] {
+                    visited[*next] = true;
+                    previous[*next] = Some(node);
+                    queue.push_back(*next);
+                }
+            }
+        }
+        None
+    }
+
+    /// Numbers of points reachable from the point numbered `number` through
+    /// chains of at most `max_hops` visibility edges, each with the fewest
+    /// hops needed, for example to find who a message can reach via relays.
+    /// Results are in ascending number order, and never include the starting
+    /// point.  An empty vector is returned if no point matching `number` is in
+    /// the graph.
+    pub fn reachable_within(&self, number: u32, max_hops: usize) -> Vec<(u32, usize)> {
+        match self.node(number) {
+            Some(start) => {
+                let reached = breadth_first(start, self.numbers.len(), max_hops, |node| {
+                    self.edges[node].iter().map(|(next, _)| *next).collect()
+                });
+                numbered(&self.numbers, reached)
+            }
+            None => vec![],
+        }
+    }
+
+    /// Returns true if node `from` sees node `to`
+    pub fn sees(&self, from: usize, to: usize) -> bool {
+        self.edges[from]
+            .binary_search_by(|(node, _)| node.cmp(&to))
+            .is_ok()
+    }
+
+    /// Render the graph as an N×N CSV matrix, with a header row and column of
+    /// point numbers.  The cell in row `i` and column `j` is `1` when point `i`
+    /// sees point `j`, and `0` otherwise.
+    pub fn to_csv(&self) -> String {
+        self.render_csv(|from, to| match self.sees(from, to) {
+            true => String::from("1"),
+            false => String::from("0"),
+        })
+    }
+
+    /// Render the graph as an N×N CSV matrix, as for [`VisibilityGraph::to_csv`],
+    /// but with the distance between the points for visible pairs, and empty
+    /// cells otherwise.
+    pub fn to_distance_csv(&self) -> String {
+        self.render_csv(|from, to| {
+            match self.edges[from].binary_search_by(|(node, _)| node.cmp(&to)) {
+                Ok(index) => self.edges[from][index].1.to_string(),
+                Err(_) => String::new(),
+            }
+        })
+    }
+
+    fn render_csv<F: Fn(usize, usize) -> String>(&self, cell: F) -> String {
+        let header: Vec<String> = self.numbers.iter().map(u32::to_string).collect();
+        let mut result = format!(",{}\n", header.join(","));
+        for (from, number) in self.numbers.iter().enumerate() {
+            let cells: Vec<String> = (0..self.numbers.len()).map(|to| cell(from, to)).collect();
+            result.push_str(&format!("{},{}\n", number, cells.join(",")));
+        }
+        result
+    }
+
+    /// Encode the graph as a compact binary bitset, laid out as:
+    ///
+    /// - [`BITSET_MAGIC`], then the [`BITSET_VERSION`] byte;
+    /// - half arc central angle, arc radius and point count, N, each as a
+    ///   little-endian `u32`;
+    /// - N point numbers, each as a little-endian `u32`; and
+    /// - N rows of `ceil(N / 8)` bytes, where bit `j % 8` (least significant
+    ///   first) of byte `j / 8` in row `i` is set when point `i` sees point `j`.
+    ///
+    /// With NumPy, rows unpack using
+    /// `numpy.unpackbits(row_bytes, bitorder="little")[:n]`.
+    pub fn to_bitset(&self) -> Vec<u8> {
+        let count = self.numbers.len();
+        let row_length = (count + 7) / 8;
+        let mut result = Vec::with_capacity(17 + 4 * count + row_length * count);
+        result.extend_from_slice(BITSET_MAGIC);
+        result.push(BITSET_VERSION);
+        for value in [self.half_arc_central_angle, self.arc_radius, count as u32] {
+            result.extend_from_slice(&value.to_le_bytes());
+        }
+        for number in &self.numbers {
+            result.extend_from_slice(&number.to_le_bytes());
+        }
+        for edges in &self.edges {
+            let mut row = vec![0_u8; row_length];
+            for (to, _) in edges {
+                row[to / 8] |= 1 << (to % 8);
+            }
+            result.extend_from_slice(&row);
+        }
+        result
+    }
+
+    /// Serialise the graph, including distances, so it can be saved and later
+    /// restored with [`VisibilityGraph::from_bytes`].  Point coordinates are
+    /// not part of the graph, so saved graphs can be queried where the raw
+    /// points must not be shared.  The layout is
+    /// [`GRAPH_MAGIC`], the [`GRAPH_VERSION`] byte, then half arc central
+    /// angle, arc radius and point count as little-endian `u32`s.  Next come
+    /// the point numbers, then, for each node, its edge count followed by each
+    /// edge’s node index (`u32`) and distance (`f64`), all little-endian.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut result = GRAPH_MAGIC.to_vec();
+        result.push(GRAPH_VERSION);
+        for value in [
+            self.half_arc_central_angle,
+            self.arc_radius,
+            self.numbers.len() as u32,
+        ] {
+            result.extend_from_slice(&value.to_le_bytes());
+        }
+        for number in &self.numbers {
+            result.extend_from_slice(&number.to_le_bytes());
+        }
+        for edges in &self.edges {
+            result.extend_from_slice(&(edges.len() as u32).to_le_bytes());
+            for (to, distance) in edges {
+                result.extend_from_slice(&(*to as u32).to_le_bytes());
+                result.extend_from_slice(&distance.to_le_bytes());
+            }
+        }
+        result
+    }
+
+    /// Restore a graph serialised with [`VisibilityGraph::to_bytes`]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, AppError> {
+        let mut reader = Reader { bytes };
+        if reader.take(4)? != GRAPH_MAGIC {
+            return Err(AppError::GraphFormatError(String::from(
+                "missing graph header",
+            )));
+        }
+        let version = reader.take(1)?[0];
+        if version != GRAPH_VERSION {
+            return Err(AppError::GraphFormatError(format!(
+                "unsupported version {version}"
+            )));
+        }
+        let half_arc_central_angle = reader.u32()?;
+        let arc_radius = reader.u32()?;
+        let count = reader.u32()? as usize;
+        let numbers = (0..count)
+            .map(|_| reader.u32())
+            .collect::<Result<Vec<u32>, AppError>>()?;
+        let mut edges = Vec::with_capacity(count);
+        for _ in 0..count {
+            let edge_count = reader.u32()? as usize;
+            let mut node_edges: Vec<(usize, f64)> = Vec::with_capacity(edge_count.min(count));
+            for _ in 0..edge_count {
+                let to = reader.u32()? as usize;
+                if to >= count {
+                    return Err(AppError::GraphFormatError(format!(
+                        "edge to unknown node {to}"
+                    )));
+                }
+                node_edges.push((to, reader.f64()?));
+            }
+            edges.push(node_edges);
+        }
+        if !reader.bytes.is_empty() {
+            return Err(AppError::GraphFormatError(String::from(
+                "unexpected trailing data",
+            )));
+        }
+        Ok(VisibilityGraph {
+            half_arc_central_angle,
+            arc_radius,
+            numbers,
+            edges,
+        })
+    }
+}
+
+/// Compute the visibility graph of `neighbourhood`, where each point sees a
+/// segment of radius `arc_radius` units, spanning left and right from its own
+/// direction by `half_arc_central_angle`.  `half_arc_central_angle` should be
+/// in degrees, and can range from zero to `180` degrees.
+pub fn visibility_graph(
+    half_arc_central_angle: u32,
+    arc_radius: u32,
+    neighbourhood: &[Point],
+) -> VisibilityGraph {
+    visibility_graph_with_progress(half_arc_central_angle, arc_radius, neighbourhood, |_, _| {})
+}
+
+/// Build the visibility graph, as for [`visibility_graph`], calling
+/// `progress` with the number of points processed so far, and the total,
+/// after each point.
+pub fn visibility_graph_with_progress<F: FnMut(usize, usize)>(
+    half_arc_central_angle: u32,
+    arc_radius: u32,
+    neighbourhood: &[Point],
+    progress: F,
+) -> VisibilityGraph {
+    match visibility_graph_cancellable(
+        half_arc_central_angle,
+        arc_radius,
+        neighbourhood,
+        &Cancellation::new(),
+        progress,
+    ) {
+        Ok(value) => value,
+        Err(_) => unreachable!("Unexpected cancellation of visibility graph"),
+    }
+}
+
+/// Build the visibility graph, as for [`visibility_graph_with_progress`],
+/// checking `cancellation` before each point, so a server can abandon a
+/// runaway request.  An [`AppError::CancelledError`] is returned if the token
+/// is cancelled, or its deadline passes, before every point is processed.
+pub fn visibility_graph_cancellable<F: FnMut(usize, usize)>(
+    half_arc_central_angle: u32,
+    arc_radius: u32,
+    neighbourhood: &[Point],
+    cancellation: &Cancellation,
+    mut progress: F,
+) -> Result<VisibilityGraph, AppError> {
+    let _span = Span::enter("visibility_graph", || {
+        format!("{} points", neighbourhood.len())
+    });
+    let mut edges = Vec::with_capacity(neighbourhood.len());
+    for (done, point) in neighbourhood.iter().enumerate() {
+        if cancellation.is_cancelled() {
+            return Err(AppError::CancelledError {
+                completed: done,
+                total: neighbourhood.len(),
+            });
+        }
+        edges.push(edges_from(
+            point,
+            half_arc_central_angle,
+            arc_radius,
+            neighbourhood,
+        ));
+        progress(done + 1, neighbourhood.len());
+    }
+    Ok(VisibilityGraph {
+        half_arc_central_angle,
+        arc_radius,
+        numbers: neighbourhood
+            .iter()
+            .map(|Point { number, .. }| *number)
+            .collect(),
+        edges,
+    })
+}
+
+/// Numbers of `neighbourhood` points reachable from the point numbered
+/// `point_number` through chains of at most `max_hops` visibility edges, as
+/// for [`VisibilityGraph::reachable_within`], but finding edges only for the
+/// points reached, rather than building the whole graph first.
+pub fn reachable_points(
+    point_number: u32,
+    half_arc_central_angle: u32,
+    arc_radius: u32,
+    max_hops: usize,
+    neighbourhood: &[Point],
+) -> Vec<(u32, usize)> {
+    let _span = Span::enter("reachable_points", || {
+        format!(
+            "point {point_number} of {}, {max_hops} hops",
+            neighbourhood.len()
+        )
+    });
+    let start = match neighbourhood
+        .iter()
+        .position(|Point { number, .. }| *number == point_number)
+    {
+        Some(value) => value,
+        None => return vec![],
+    };
+    let reached = breadth_first(start, neighbourhood.len(), max_hops, |node| {
+        edges_from(
+            &neighbourhood[node],
+            half_arc_central_angle,
+            arc_radius,
+            neighbourhood,
+        )
+        .into_iter()
+        .map(|(next, _)| next)
+        .collect()
+    });
+    let numbers: Vec<u32> = neighbourhood
+        .iter()
+        .map(|Point { number, .. }| *number)
+        .collect();
+    numbered(&numbers, reached)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        reachable_points, visibility_graph, visibility_graph_cancellable,
+        visibility_graph_with_progress, VisibilityGraph,
+    };
+    use crate::{
+        domain::{parse_points_file, Direction, Point},
+        utilities::{AppError, Cancellation},
+    };
+    use std::time::Instant;
+
+    fn neighbourhood() -> Vec<Point> {
+        vec![
+            Point {
+                coordinates: (0, 0),
+                number: 1,
+                direction: Direction::North,
+            },
+            Point {
+                coordinates: (0, 5),
+                number: 2,
+                direction: Direction::South,
+            },
+            Point {
+                coordinates: (3, 4),
+                number: 3,
+                direction: Direction::East,
+            },
+        ]
+    }
+
+    #[test]
+    fn visibility_graph_gives_expected_result() {
+        // arrange
+        let points = neighbourhood();
+
+        // act
+        let outcome = visibility_graph(45, 10, &points);
+
+        // assert
+        assert_eq!(outcome.numbers, vec![1, 2, 3]);
+        assert_eq!(
+            outcome.edges,
+            vec![vec![(1, 5.0), (2, 5.0)], vec![(0, 5.0)], vec![]]
+        );
+        assert!(outcome.sees(0, 2));
+        assert!(!outcome.sees(2, 0));
+
+        // act
+        let mut updates = vec![];
+        let outcome_with_progress =
+            visibility_graph_with_progress(45, 10, &points, |done, total| {
+                updates.push((done, total))
+            });
+
+        // assert
+        assert_eq!(outcome_with_progress, outcome);
+        assert_eq!(updates, vec![(1, 3), (2, 3), (3, 3)]);
+    }
+
+    #[test]
+    fn visibility_graph_renders_csv() {
+        // arrange
+        let points = neighbourhood();
+        let graph = visibility_graph(45, 10, &points);
+
+        // act
+        let csv = graph.to_csv();
+        let distance_csv = graph.to_distance_csv();
+
+        // assert
+        assert_eq!(csv, ",1,2,3\n1,0,1,1\n2,1,0,0\n3,0,0,0\n");
+        assert_eq!(distance_csv, ",1,2,3\n1,,5,5\n2,5,,\n3,,,\n");
+    }
+
+    #[test]
+    fn visibility_graph_renders_bitset() {
+        // arrange
+        let points = neighbourhood();
+        let graph = visibility_graph(45, 10, &points);
+
+        // act
+        let outcome = graph.to_bitset();
+
+        // assert
+        let mut expected = b"NBVM\x01".to_vec();
+        for value in [45_u32, 10, 3, 1, 2, 3] {
+            expected.extend_from_slice(&value.to_le_bytes());
+        }
+        expected.extend_from_slice(&[0b110, 0b001, 0b000]);
+        assert_eq!(outcome, expected);
+    }
+
+    #[test]
+    fn visibility_graph_round_trips_through_bytes() {
+        // arrange
+        let points = neighbourhood();
+        let graph = visibility_graph(45, 10, &points);
+
+        // act
+        let outcome = VisibilityGraph::from_bytes(&graph.to_bytes()).unwrap();
+
+        // assert
+        assert_eq!(outcome, graph);
+    }
+
+    #[test]
+    fn visibility_graph_from_bytes_handles_invalid_input() {
+        // arrange
+        let points = neighbourhood();
+        let bytes = visibility_graph(45, 10, &points).to_bytes();
+
+        // act
+        let truncated = VisibilityGraph::from_bytes(&bytes[..bytes.len() - 1])
+            .unwrap_err()
+            .to_string();
+        let bitset = VisibilityGraph::from_bytes(b"NBVM\x01")
+            .unwrap_err()
+            .to_string();
+
+        // assert
+        assert_eq!(
+            truncated,
+            "Error decoding visibility graph: unexpected end of data"
+        );
+        assert_eq!(
+            bitset,
+            "Error decoding visibility graph: missing graph header"
+        );
+    }
+
+    #[test]
+    fn visibility_graph_answers_queries() {
+        // arrange
+        let points = neighbourhood();
+        let graph = visibility_graph(45, 10, &points);
+
+        // act
+        let visible = graph.visible_numbers(1);
+        let path = graph.shortest_path(2, 3);
+        let reverse_path = graph.shortest_path(3, 1);
+
+        // assert
+        assert_eq!(visible, vec![2, 3]);
+        assert_eq!(path, Some(vec![2, 1, 3]));
+        assert_eq!(reverse_path, None);
+        assert_eq!(graph.shortest_path(1, 1), Some(vec![1]));
+        assert_eq!(graph.visible_numbers(99), Vec::<u32>::new());
+        assert_eq!(graph.shortest_path(1, 99), None);
+    }
+
+    #[test]
+    fn visible_numbers_ascend_whatever_neighbourhood_order() {
+        // arrange
+        let points = vec![
+            Point {
+                coordinates: (0, 0),
+                number: 5,
+                direction: Direction::North,
+            },
+            Point {
+                coordinates: (0, 3),
+                number: 9,
+                direction: Direction::South,
+            },
+            Point {
+                coordinates: (1, 4),
+                number: 2,
+                direction: Direction::South,
+            },
+        ];
+        let graph = visibility_graph(45, 10, &points);
+
+        // act
+        let outcome = graph.visible_numbers(5);
+
+        // assert
+        assert_eq!(outcome, vec![2, 9]);
+    }
+
+    #[test]
+    fn reachable_points_follows_chains_of_edges() {
+        // arrange
+        let points = parse_points_file("./fixtures/valid_points.json").unwrap();
+        let graph = visibility_graph(90, 15, &points);
+
+        // act
+        let outcome: Vec<Vec<(u32, usize)>> = (0..4)
+            .map(|max_hops| reachable_points(1, 90, 15, max_hops, &points))
+            .collect();
+
+        // assert
+        assert!(outcome[0].is_empty());
+        let mut direct: Vec<(u32, usize)> = graph
+            .visible_numbers(1)
+            .into_iter()
+            .map(|number| (number, 1))
+            .collect();
+        direct.sort_unstable();
+        assert_eq!(outcome[1], direct);
+        for (max_hops, reached) in outcome.iter().enumerate() {
+            assert_eq!(*reached, graph.reachable_within(1, max_hops));
+            for (number, hops) in reached {
+                assert_eq!(
+                    graph.shortest_path(1, *number).map(|path| path.len() - 1),
+                    Some(*hops)
+                );
+            }
+        }
+        assert_eq!(
+            outcome[3],
+            vec![(2, 1), (4, 1), (8, 2), (11, 1), (18, 2), (19, 3)]
+        );
+        assert!(reachable_points(999, 90, 15, 3, &points).is_empty());
+    }
+
+    #[test]
+    fn visibility_graph_cancellable_stops_when_cancelled() {
+        // arrange
+        let points = neighbourhood();
+        let cancellation = Cancellation::new();
+
+        // act
+        let outcome = visibility_graph_cancellable(45, 20, &points, &cancellation, |done, _| {
+            if done == 1 {
+                cancellation.cancel();
+            }
+        });
+
+        // assert
+        match outcome {
+            Err(AppError::CancelledError { completed, total }) => {
+                assert_eq!((completed, total), (1, points.len()));
+            }
+            _ => panic!("expected cancelled error"),
+        }
+        let expired = Cancellation::with_deadline(Instant::now());
+        assert!(visibility_graph_cancellable(45, 20, &points, &expired, |_, _| {}).is_err());
+        assert_eq!(
+            visibility_graph_cancellable(45, 20, &points, &Cancellation::new(), |_, _| {}).unwrap(),
+            visibility_graph(45, 20, &points)
+        );
+    }
+
+    #[test]
+    fn visibility_graph_update_point_matches_rebuild() {
+        // arrange
+        let mut points: Vec<Point> = (0..30)
+            .map(|val| Point {
+                coordinates: (val * 7 % 23 - 11, val * 5 % 19 - 9),
+                number: val as u32 + 1,
+                direction: [
+                    Direction::North,
+                    Direction::East,
+                    Direction::South,
+                    Direction::West,
+                ][(val % 4) as usize],
+            })
+            .collect();
+        let mut graph = visibility_graph(45, 8, &points);
+
+        // act
+        let mut changed = vec![];
+        for (node, coordinates) in [(3, (0, 0)), (17, (4, -2)), (3, (20, 20))] {
+            points[node].coordinates = coordinates;
+            points[node].direction = Direction::West;
+            changed.push(graph.update_point(&points, node));
+        }
+
+        // assert
+        assert_eq!(graph, visibility_graph(45, 8, &points));
+        assert!(changed.iter().all(|nodes| !nodes.is_empty()));
+        assert_eq!(graph.update_point(&points, 3), Vec::<usize>::new());
+        assert_eq!(graph.update_point(&points[..2], 0), vec![0, 1]);
+        assert_eq!(graph, visibility_graph(45, 8, &points[..2]));
+    }
+}
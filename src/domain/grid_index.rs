@@ -0,0 +1,178 @@
+use crate::{
+    domain::{
+        geometry::BoundingBox,
+        point::{euclidean_distance, Point},
+        spatial_index::SpatialIndex,
+    },
+    utilities::Span,
+};
+use std::collections::HashMap;
+
+/// Points each cell of a [`GridIndex`] sized by [`GridIndex::with_density`]
+/// holds, on average, if points are spread evenly
+const POINTS_PER_CELL: f64 = 4.0;
+
+/// Cell size for `points`, spread over their bounding box, so each cell holds
+/// about [`POINTS_PER_CELL`] points, or `1` for no points
+pub(crate) fn density_cell_size(points: &[Point]) -> u32 {
+    match BoundingBox::of_points(points) {
+        Some(BoundingBox { min, max }) => {
+            let width = f64::from(max.0) - f64::from(min.0) + 1.0;
+            let height = f64::from(max.1) - f64::from(min.1) + 1.0;
+            let size = (width * height * POINTS_PER_CELL / points.len() as f64).sqrt();
+            size.ceil().clamp(1.0, f64::from(u32::MAX)) as u32
+        }
+        None => 1,
+    }
+}
+
+/// Uniform grid of square cells, each listing the points inside it, for
+/// range queries on points spread fairly evenly, which then check only the
+/// cells a query's range overlaps.  Building takes a single pass over the
+/// points.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GridIndex {
+    points: Vec<Point>,
+
+    /// Width of each cell, or `None` to size cells by the density of points
+    /// each time the grid is built
+    cell_size: Option<u32>,
+
+    /// Width of each cell in the current grid
+    current_cell_size: i64,
+
+    /// Indices of the points in each occupied cell
+    cells: HashMap<(i64, i64), Vec<usize>>,
+}
+
+impl GridIndex {
+    /// Build the grid over `points`, which it keeps a copy of, with cells
+    /// `cell_size` units across.  Cells about as wide as typical query radii
+    /// suit most uses.
+    pub fn new(points: &[Point], cell_size: u32) -> Self {
+        GridIndex::build(points, Some(cell_size))
+    }
+
+    /// Build the grid over `points`, sizing cells so each holds a few points,
+    /// if they are spread evenly, as they are again on every rebuild
+    pub fn with_density(points: &[Point]) -> Self {
+        GridIndex::build(points, None)
+    }
+
+    fn build(points: &[Point], cell_size: Option<u32>) -> Self {
+        let mut result = GridIndex {
+            points: vec![],
+            cell_size,
+            current_cell_size: 1,
+            cells: HashMap::new(),
+        };
+        result.rebuild(points);
+        result
+    }
+
+    /// Width of each cell
+    pub fn cell_size(&self) -> u32 {
+        self.current_cell_size as u32
+    }
+
+    fn cell(&self, (x, y): (i32, i32)) -> (i64, i64) {
+        (
+            i64::from(x).div_euclid(self.current_cell_size),
+            i64::from(y).div_euclid(self.current_cell_size),
+        )
+    }
+}
+
+impl SpatialIndex for GridIndex {
+    fn range_query<'a>(
+        &'a self,
+        center: (i32, i32),
+        radius: f64,
+    ) -> Box<dyn Iterator<Item = &'a Point> + 'a> {
+        if radius <= 0.0 || self.points.is_empty() {
+            return Box::new(std::iter::empty());
+        }
+        let reach = radius.ceil().min(f64::from(u32::MAX)) as i64;
+        let (x, y) = (i64::from(center.0), i64::from(center.1));
+        let size = self.current_cell_size;
+        let (min_x, max_x) = ((x - reach).div_euclid(size), (x + reach).div_euclid(size));
+        let (min_y, max_y) = ((y - reach).div_euclid(size), (y + reach).div_euclid(size));
+        let in_range =
+            move |point: &&'a Point| euclidean_distance(center, point.coordinates) < radius;
+
+        // ranges spanning more cells than are occupied check every point
+        let spanned = (max_x - min_x + 1).saturating_mul(max_y - min_y + 1);
+        if spanned > self.cells.len() as i64 {
+            return Box::new(self.points.iter().filter(in_range));
+        }
+        Box::new(
+            (min_x..=max_x)
+                .flat_map(move |cell_x| (min_y..=max_y).map(move |cell_y| (cell_x, cell_y)))
+                .filter_map(move |cell| self.cells.get(&cell))
+                .flatten()
+                .map(move |index| &self.points[*index])
+                .filter(in_range),
+        )
+    }
+
+    fn rebuild(&mut self, points: &[Point]) {
+        let _span = Span::enter("grid_index", || format!("{} points", points.len()));
+        self.points = points.to_vec();
+        self.current_cell_size = i64::from(
+            self.cell_size
+                .unwrap_or_else(|| density_cell_size(points))
+                .max(1),
+        );
+        self.cells.clear();
+        for (index, point) in points.iter().enumerate() {
+            let cell = self.cell(point.coordinates);
+            self.cells.entry(cell).or_default().push(index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GridIndex;
+    use crate::domain::{Direction, LinearScan, Point, SpatialIndex};
+
+    #[test]
+    fn grid_index_finds_points_in_range() {
+        // arrange
+        let points: Vec<Point> = (0..500)
+            .map(|index| Point {
+                coordinates: (((index * 37) % 211) - 105, ((index * 53) % 199) - 99),
+                number: index as u32 + 1,
+                direction: Direction::North,
+            })
+            .collect();
+        let linear = LinearScan::new(&points);
+
+        // act
+        let grids = [
+            GridIndex::new(&points, 10),
+            GridIndex::with_density(&points),
+        ];
+
+        // assert
+        assert_eq!(grids[1].cell_size(), 19);
+        for grid in &grids {
+            for (centre, radius) in [
+                ((0, 0), 15.0),
+                ((-100, 90), 30.5),
+                ((3, 4), 0.0),
+                ((0, 0), 500.0),
+            ] {
+                let numbers = |index: &dyn SpatialIndex| {
+                    let mut numbers: Vec<u32> = index
+                        .range_query(centre, radius)
+                        .map(|point| point.number)
+                        .collect();
+                    numbers.sort_unstable();
+                    numbers
+                };
+                assert_eq!(numbers(grid), numbers(&linear));
+            }
+        }
+    }
+}
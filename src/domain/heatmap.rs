@@ -0,0 +1,323 @@
+use crate::{
+    domain::{
+        geometry::BoundingBox,
+        point::{angular_position_f64, euclidean_distance_f64, inside_sector, Point},
+    },
+    utilities::{AppError, Cancellation, Span},
+};
+
+/// Most cells a heatmap may have, around 400 MB of counts, beyond which points
+/// are too far apart for the cell size
+pub const MAXIMUM_HEATMAP_CELLS: usize = 100_000_000;
+
+/// Counts of points able to see each cell of a grid laid over a neighbourhood
+#[derive(Clone, Debug, PartialEq)]
+pub struct Heatmap {
+    /// x,y coordinates of the bottom left corner of the grid
+    pub origin: (i32, i32),
+
+    /// Width and height of each square cell
+    pub cell_size: u32,
+
+    pub columns: usize,
+    pub rows: usize,
+
+    /// Number of points able to see the centre of each cell, row by row,
+    /// starting with the bottom row, and running left to right within a row
+    pub counts: Vec<u32>,
+}
+
+impl Heatmap {
+    /// x,y coordinates of the centre of the cell at `column` and `row`
+    pub fn cell_centre(&self, column: usize, row: usize) -> (f64, f64) {
+        let (origin_x, origin_y) = self.origin;
+        let cell_size = f64::from(self.cell_size);
+        (
+            f64::from(origin_x) + (column as f64 + 0.5) * cell_size,
+            f64::from(origin_y) + (row as f64 + 0.5) * cell_size,
+        )
+    }
+
+    /// Number of points able to see the cell at `column` and `row`
+    pub fn count(&self, column: usize, row: usize) -> u32 {
+        self.counts[row * self.columns + column]
+    }
+
+    /// Render the heatmap as CSV, with one `x,y,count` row for each cell
+    /// centre, after a header row.
+    pub fn to_csv(&self) -> String {
+        let mut result = String::from("x,y,count\n");
+        for row in 0..self.rows {
+            for column in 0..self.columns {
+                let (x, y) = self.cell_centre(column, row);
+                result.push_str(&format!("{},{},{}\n", x, y, self.count(column, row)));
+            }
+        }
+        result
+    }
+
+    /// Render the heatmap as a binary greyscale (PGM) image, with one pixel per
+    /// cell and north at the top.  White cells are seen by the most points and
+    /// black cells by none.
+    pub fn to_pgm(&self) -> Vec<u8> {
+        let maximum = self.counts.iter().copied().max().unwrap_or(0);
+        let mut result = format!("P5\n{} {}\n255\n", self.columns, self.rows).into_bytes();
+        for row in (0..self.rows).rev() {
+            for column in 0..self.columns {
+                let shade = match maximum {
+                    0 => 0,
+                    _ => (u64::from(self.count(column, row)) * 255 / u64::from(maximum)) as u8,
+                };
+                result.push(shade);
+            }
+        }
+        result
+    }
+}
+
+/// Sample a grid of square cells, each `cell_size` units wide, over the
+/// bounding box of `neighbourhood`, counting how many points could see an
+/// object placed at the centre of each cell.  Each point sees a segment of
+/// radius `arc_radius` units, spanning left and right from its own direction by
+/// `half_arc_central_angle`.  `half_arc_central_angle` should be in degrees,
+/// and can range from zero to `180` degrees.  A `cell_size` of zero is treated
+/// as one.
+///
+/// An empty neighbourhood gives a heatmap with no cells.  An
+/// [`AppError::HeatmapSizeError`] is returned if the grid would have more than
+/// [`MAXIMUM_HEATMAP_CELLS`] cells.
+pub fn visibility_heatmap(
+    half_arc_central_angle: u32,
+    arc_radius: u32,
+    cell_size: u32,
+    neighbourhood: &[Point],
+) -> Result<Heatmap, AppError> {
+    visibility_heatmap_with_progress(
+        half_arc_central_angle,
+        arc_radius,
+        cell_size,
+        neighbourhood,
+        |_, _| {},
+    )
+}
+
+/// Sample the heatmap, as for [`visibility_heatmap`], calling `progress` with
+/// the number of rows sampled so far, and the total, after each row.
+pub fn visibility_heatmap_with_progress<F: FnMut(usize, usize)>(
+    half_arc_central_angle: u32,
+    arc_radius: u32,
+    cell_size: u32,
+    neighbourhood: &[Point],
+    progress: F,
+) -> Result<Heatmap, AppError> {
+    visibility_heatmap_cancellable(
+        half_arc_central_angle,
+        arc_radius,
+        cell_size,
+        neighbourhood,
+        &Cancellation::new(),
+        progress,
+    )
+}
+
+/// Sample the heatmap, as for [`visibility_heatmap_with_progress`], checking
+/// `cancellation` before each row.  An [`AppError::CancelledError`] is
+/// returned if the token is cancelled, or its deadline passes, before every
+/// row is sampled.
+pub fn visibility_heatmap_cancellable<F: FnMut(usize, usize)>(
+    half_arc_central_angle: u32,
+    arc_radius: u32,
+    cell_size: u32,
+    neighbourhood: &[Point],
+    cancellation: &Cancellation,
+    mut progress: F,
+) -> Result<Heatmap, AppError> {
+    let _span = Span::enter("visibility_heatmap", || {
+        format!("{} points", neighbourhood.len())
+    });
+    let cell_size = cell_size.max(1);
+    let BoundingBox {
+        min: (min_x, min_y),
+        max: (max_x, max_y),
+    } = match BoundingBox::of_points(neighbourhood) {
+        Some(value) => value,
+        None => {
+            return Ok(Heatmap {
+                origin: (0, 0),
+                cell_size,
+                columns: 0,
+                rows: 0,
+                counts: vec![],
+            })
+        }
+    };
+
+    // every point lies inside a cell, including those on the maximum edges
+    let columns = ((i64::from(max_x) - i64::from(min_x)) / i64::from(cell_size) + 1) as usize;
+    let rows = ((i64::from(max_y) - i64::from(min_y)) / i64::from(cell_size) + 1) as usize;
+    let cells = match columns.checked_mul(rows) {
+        Some(value) if value <= MAXIMUM_HEATMAP_CELLS => value,
+        _ => return Err(AppError::HeatmapSizeError { columns, rows }),
+    };
+    let mut result = Heatmap {
+        origin: (min_x, min_y),
+        cell_size,
+        columns,
+        rows,
+        counts: vec![0; cells],
+    };
+
+    let half_arc_central_angle_radians = f64::from(half_arc_central_angle).to_radians();
+    for row in 0..rows {
+        if cancellation.is_cancelled() {
+            return Err(AppError::CancelledError {
+                completed: row,
+                total: rows,
+            });
+        }
+        for column in 0..columns {
+            let centre = result.cell_centre(column, row);
+            let count = neighbourhood
+                .iter()
+                .filter(
+                    |Point {
+                         coordinates: (x, y),
+                         direction,
+                         ..
+                     }| {
+                        let coordinates = (f64::from(*x), f64::from(*y));
+                        euclidean_distance_f64(coordinates, centre) < f64::from(arc_radius)
+                            && inside_sector(
+                                angular_position_f64(coordinates, centre),
+                                direction.bearing(),
+                                half_arc_central_angle_radians,
+                            )
+                    },
+                )
+                .count();
+            result.counts[row * columns + column] = count as u32;
+        }
+        progress(row + 1, rows);
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{visibility_heatmap, visibility_heatmap_cancellable};
+    use crate::{
+        domain::{Direction, Point},
+        utilities::{AppError, Cancellation},
+    };
+
+    fn neighbourhood() -> Vec<Point> {
+        vec![
+            Point {
+                coordinates: (0, 0),
+                number: 1,
+                direction: Direction::North,
+            },
+            Point {
+                coordinates: (4, 4),
+                number: 2,
+                direction: Direction::South,
+            },
+        ]
+    }
+
+    #[test]
+    fn visibility_heatmap_gives_expected_result() {
+        // arrange
+        let points = neighbourhood();
+
+        // act
+        let outcome = visibility_heatmap(60, 10, 3, &points).unwrap();
+
+        // assert
+        assert_eq!(outcome.columns, 2);
+        assert_eq!(outcome.rows, 2);
+        assert_eq!(outcome.cell_centre(1, 0), (4.5, 1.5));
+        assert_eq!(outcome.counts, vec![2, 1, 1, 1]);
+    }
+
+    #[test]
+    fn visibility_heatmap_cancellable_stops_when_cancelled() {
+        // arrange
+        let points = neighbourhood();
+        let cancellation = Cancellation::new();
+
+        // act
+        let outcome = visibility_heatmap_cancellable(60, 10, 3, &points, &cancellation, |_, _| {
+            cancellation.cancel()
+        });
+
+        // assert
+        match outcome {
+            Err(AppError::CancelledError { completed, total }) => {
+                assert_eq!((completed, total), (1, 2));
+            }
+            _ => panic!("expected cancelled error"),
+        }
+    }
+
+    #[test]
+    fn visibility_heatmap_rejects_grid_too_large() {
+        // arrange
+        let points = vec![
+            Point {
+                coordinates: (-2_000_000_000, -2_000_000_000),
+                number: 1,
+                direction: Direction::North,
+            },
+            Point {
+                coordinates: (2_000_000_000, 2_000_000_000),
+                number: 2,
+                direction: Direction::South,
+            },
+        ];
+
+        // act
+        let outcome = visibility_heatmap(60, 10, 1, &points);
+
+        // assert
+        match outcome {
+            Err(AppError::HeatmapSizeError { columns, rows }) => {
+                assert_eq!((columns, rows), (4_000_000_001, 4_000_000_001));
+            }
+            _ => panic!("expected heatmap size error"),
+        }
+        assert!(visibility_heatmap(60, 10, 40_000_000, &points).is_ok());
+    }
+
+    #[test]
+    fn heatmap_renders_csv_and_pgm() {
+        // arrange
+        let points = neighbourhood();
+        let heatmap = visibility_heatmap(60, 10, 3, &points).unwrap();
+
+        // act
+        let csv = heatmap.to_csv();
+        let pgm = heatmap.to_pgm();
+
+        // assert
+        assert_eq!(
+            csv,
+            "x,y,count\n1.5,1.5,2\n4.5,1.5,1\n1.5,4.5,1\n4.5,4.5,1\n"
+        );
+        assert_eq!(pgm, b"P5\n2 2\n255\n\x7f\x7f\xff\x7f".to_vec());
+    }
+
+    #[test]
+    fn visibility_heatmap_handles_empty_input_universe() {
+        // arrange
+        let points: Vec<Point> = vec![];
+
+        // act
+        let outcome = visibility_heatmap(60, 10, 3, &points).unwrap();
+
+        // assert
+        assert_eq!(outcome.columns, 0);
+        assert_eq!(outcome.counts.len(), 0);
+        assert_eq!(outcome.to_csv(), "x,y,count\n");
+    }
+}
@@ -0,0 +1,77 @@
+use crate::domain::sighting::Sighting;
+use serde::Serialize;
+
+/// Counts of visible points by distance, in bins of equal width starting from
+/// zero distance
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct DistanceHistogram {
+    /// Width of each bin
+    pub bin_width: f64,
+
+    /// Number of points in each bin, nearest bin first, up to the furthest
+    /// bin holding a point
+    pub counts: Vec<usize>,
+}
+
+impl DistanceHistogram {
+    /// Nearest and furthest distance of the bin at `index`, which holds points
+    /// at least the first distance away, and closer than the second
+    pub fn bin_range(&self, index: usize) -> (f64, f64) {
+        let from = index as f64 * self.bin_width;
+        (from, from + self.bin_width)
+    }
+
+    /// Histogram as JSON, without a trailing newline
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+/// Count `sightings` by distance, in bins `bin_width` units wide, for a quick
+/// summary of how visible points are spread by range.  A `bin_width` which is
+/// not a positive number is treated as one.
+pub fn distance_histogram(bin_width: f64, sightings: &[Sighting]) -> DistanceHistogram {
+    let bin_width = if bin_width > 0.0 && bin_width.is_finite() {
+        bin_width
+    } else {
+        1.0
+    };
+    let mut counts = vec![];
+    for Sighting { distance, .. } in sightings {
+        let index = (distance / bin_width) as usize;
+        if index >= counts.len() {
+            counts.resize(index + 1, 0);
+        }
+        counts[index] += 1;
+    }
+    DistanceHistogram { bin_width, counts }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::distance_histogram;
+    use crate::domain::{test_support::north_point, visible_sightings};
+
+    #[test]
+    fn distance_histogram_counts_by_range() {
+        // arrange
+        let neighbourhood = [
+            north_point(1, (0, 0)),
+            north_point(2, (0, 0)),
+            north_point(3, (0, 4)),
+            north_point(4, (3, 4)),
+            north_point(5, (0, 12)),
+        ];
+        let sightings = visible_sightings(1, 45, 20, &neighbourhood);
+
+        // act
+        let outcome = distance_histogram(5.0, &sightings);
+
+        // assert
+        assert_eq!(outcome.counts, vec![2, 1, 1]);
+        assert_eq!(outcome.bin_range(2), (10.0, 15.0));
+        assert_eq!(outcome.to_json(), r#"{"bin_width":5.0,"counts":[2,1,1]}"#);
+        assert_eq!(distance_histogram(0.0, &sightings).bin_width, 1.0);
+        assert!(distance_histogram(5.0, &[]).counts.is_empty());
+    }
+}
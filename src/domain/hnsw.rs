@@ -0,0 +1,382 @@
+use crate::{
+    domain::{point::Point, spatial_index::SpatialIndex},
+    utilities::Span,
+};
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashSet},
+};
+
+/// Settings for an [`HnswIndex`], trading build time, memory and recall
+/// against query speed
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct HnswParameters {
+    /// Links kept from each point to its neighbours, on each layer above the
+    /// lowest, which keeps twice as many
+    pub connections: usize,
+
+    /// Candidates considered when linking each point, while building
+    pub construction_breadth: usize,
+
+    /// Candidates considered by each query: the recall and speed knob, with
+    /// larger values missing fewer points, but taking longer
+    pub search_breadth: usize,
+}
+
+impl Default for HnswParameters {
+    fn default() -> Self {
+        HnswParameters {
+            connections: 16,
+            construction_breadth: 100,
+            search_breadth: 32,
+        }
+    }
+}
+
+/// Squared distance between two points, exact for any whole coordinates
+fn distance_squared((x_1, y_1): (i32, i32), (x_2, y_2): (i32, i32)) -> i64 {
+    let (dx, dy) = (
+        i64::from(x_2) - i64::from(x_1),
+        i64::from(y_2) - i64::from(y_1),
+    );
+    dx * dx + dy * dy
+}
+
+/// Points a search has reached
+enum Visited {
+    /// Mark for each point, reused by every search while building, with the
+    /// stamp of the last search to reach it
+    Marks { marks: Vec<u32>, stamp: u32 },
+
+    /// Points reached, for queries, whose cost should not grow with the size
+    /// of the whole index
+    Set(HashSet<usize>),
+}
+
+impl Visited {
+    /// Forget points reached by any earlier search
+    fn start(&mut self) {
+        match self {
+            Visited::Marks { marks, stamp } => {
+                *stamp = stamp.wrapping_add(1);
+                if *stamp == 0 {
+                    marks.iter_mut().for_each(|mark| *mark = 0);
+                    *stamp = 1;
+                }
+            }
+            Visited::Set(set) => set.clear(),
+        }
+    }
+
+    /// Record `index` as reached, returning true if it was not already
+    fn insert(&mut self, index: usize) -> bool {
+        match self {
+            Visited::Marks { marks, stamp } => {
+                let reached = marks[index] != *stamp;
+                marks[index] = *stamp;
+                reached
+            }
+            Visited::Set(set) => set.insert(index),
+        }
+    }
+}
+
+/// Hierarchical navigable small world graph over a neighbourhood, for
+/// approximate, but fast, queries on huge datasets.  Queries walk down
+/// through sparse upper layers, towards their centre, then search the dense
+/// lowest layer, so may miss a few points a linear scan would find.  Raise
+/// [`HnswParameters::search_breadth`] for fewer misses.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HnswIndex {
+    points: Vec<Point>,
+
+    /// Neighbours of each point, by layer, from the lowest up
+    links: Vec<Vec<Vec<usize>>>,
+
+    /// Point every search starts from, on the top layer
+    entry: Option<usize>,
+
+    parameters: HnswParameters,
+}
+
+impl HnswIndex {
+    /// Build the index over `points`, which it keeps a copy of
+    pub fn new(points: &[Point], parameters: HnswParameters) -> Self {
+        let _span = Span::enter("hnsw_index", || format!("{} points", points.len()));
+        let parameters = HnswParameters {
+            connections: parameters.connections.max(2),
+            construction_breadth: parameters.construction_breadth.max(1),
+            search_breadth: parameters.search_breadth.max(1),
+        };
+        let mut result = HnswIndex {
+            points: points.to_vec(),
+            links: Vec::with_capacity(points.len()),
+            entry: None,
+            parameters,
+        };
+
+        // layers are drawn from a fixed seed, so builds are repeatable
+        let mut state = 42_u64;
+        let level_scale = 1.0 / (parameters.connections as f64).ln();
+        let mut visited = Visited::Marks {
+            marks: vec![0; points.len()],
+            stamp: 0,
+        };
+        for index in 0..points.len() {
+            state = state
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            let uniform = ((state >> 11) as f64 + 1.0) / (1_u64 << 53) as f64;
+            let level = (-uniform.ln() * level_scale) as usize;
+            result.insert(index, level, &mut visited);
+        }
+        result
+    }
+
+    /// Number of points in the index
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    pub fn parameters(&self) -> HnswParameters {
+        self.parameters
+    }
+
+    /// Most links kept from a point on `layer`
+    fn capacity(&self, layer: usize) -> usize {
+        if layer == 0 {
+            2 * self.parameters.connections
+        } else {
+            self.parameters.connections
+        }
+    }
+
+    fn top_layer(&self) -> usize {
+        self.entry.map_or(0, |entry| self.links[entry].len() - 1)
+    }
+
+    /// Link the point at `index` into the graph, on layers up to `level`
+    fn insert(&mut self, index: usize, level: usize, visited: &mut Visited) {
+        self.links.push(vec![vec![]; level + 1]);
+        let entry = match self.entry {
+            Some(value) => value,
+            None => {
+                self.entry = Some(index);
+                return;
+            }
+        };
+        let centre = self.points[index].coordinates;
+        let top_layer = self.top_layer();
+        let mut nearest = vec![entry];
+        for layer in (level + 1..=top_layer).rev() {
+            nearest = self.search_layer(centre, &nearest, 1, layer, visited);
+        }
+        for layer in (0..=level.min(top_layer)).rev() {
+            let found = self.search_layer(
+                centre,
+                &nearest,
+                self.parameters.construction_breadth,
+                layer,
+                visited,
+            );
+            let capacity = self.capacity(layer);
+            let neighbours: Vec<usize> = found.iter().copied().take(capacity).collect();
+            for neighbour in &neighbours {
+                let links = &mut self.links[*neighbour][layer];
+                links.push(index);
+                if links.len() > capacity {
+                    // drop the furthest link, keeping the rest
+                    let from = self.points[*neighbour].coordinates;
+                    let points = &self.points;
+                    let furthest = (0..links.len()).max_by_key(|position| {
+                        let other = links[*position];
+                        (distance_squared(from, points[other].coordinates), other)
+                    });
+                    if let Some(position) = furthest {
+                        links.swap_remove(position);
+                    }
+                }
+            }
+            self.links[index][layer] = neighbours;
+            nearest = found;
+        }
+        if level > top_layer {
+            self.entry = Some(index);
+        }
+    }
+
+    /// Indices of up to `breadth` points nearest `centre` found on `layer`,
+    /// searching out from `entries`, nearest first
+    fn search_layer(
+        &self,
+        centre: (i32, i32),
+        entries: &[usize],
+        breadth: usize,
+        layer: usize,
+        visited: &mut Visited,
+    ) -> Vec<usize> {
+        let distance = |index: usize| {
+            (
+                distance_squared(centre, self.points[index].coordinates),
+                index,
+            )
+        };
+        visited.start();
+        for entry in entries {
+            visited.insert(*entry);
+        }
+        let mut candidates: BinaryHeap<Reverse<(i64, usize)>> = entries
+            .iter()
+            .map(|entry| Reverse(distance(*entry)))
+            .collect();
+        let mut found: BinaryHeap<(i64, usize)> =
+            entries.iter().map(|entry| distance(*entry)).collect();
+        while found.len() > breadth {
+            found.pop();
+        }
+        while let Some(Reverse(candidate)) = candidates.pop() {
+            if found.len() >= breadth
+                && found.peek().map_or(false, |furthest| candidate > *furthest)
+            {
+                break;
+            }
+            for neighbour in &self.links[candidate.1][layer] {
+                if !visited.insert(*neighbour) {
+                    continue;
+                }
+                let value = distance(*neighbour);
+                if found.len() < breadth || found.peek().map_or(true, |furthest| value < *furthest)
+                {
+                    candidates.push(Reverse(value));
+                    found.push(value);
+                    if found.len() > breadth {
+                        found.pop();
+                    }
+                }
+            }
+        }
+        found
+            .into_sorted_vec()
+            .into_iter()
+            .map(|(_, index)| index)
+            .collect()
+    }
+
+    /// Indices of up to `count` points nearest `centre`, nearest first
+    fn search(&self, centre: (i32, i32), count: usize) -> Vec<usize> {
+        let entry = match self.entry {
+            Some(value) => value,
+            None => return vec![],
+        };
+        let mut visited = Visited::Set(HashSet::new());
+        let mut nearest = vec![entry];
+        for layer in (1..=self.top_layer()).rev() {
+            nearest = self.search_layer(centre, &nearest, 1, layer, &mut visited);
+        }
+        let breadth = count.max(self.parameters.search_breadth);
+        let mut result = self.search_layer(centre, &nearest, breadth, 0, &mut visited);
+        result.truncate(count);
+        result
+    }
+
+    /// Approximately the `count` points nearest `centre`, nearest first, and
+    /// lowest numbered first among those equally near
+    pub fn nearest(&self, centre: (i32, i32), count: usize) -> Vec<&Point> {
+        let mut result: Vec<&Point> = self
+            .search(centre, count)
+            .into_iter()
+            .map(|index| &self.points[index])
+            .collect();
+        result.sort_by_key(|point| (distance_squared(centre, point.coordinates), point.number));
+        result
+    }
+
+    /// Points closer than `radius` to `centre`, approximately, in no
+    /// particular order.  The search widens while every point it finds is in
+    /// range, so dense ranges are not cut short at the search breadth.
+    pub fn within(&self, centre: (i32, i32), radius: f64) -> Vec<&Point> {
+        let in_range = |index: &usize| {
+            (distance_squared(centre, self.points[*index].coordinates) as f64).sqrt() < radius
+        };
+        let mut breadth = self.parameters.search_breadth;
+        loop {
+            let found = self.search(centre, breadth);
+            if found.len() < breadth || !found.iter().all(in_range) {
+                return found
+                    .into_iter()
+                    .filter(in_range)
+                    .map(|index| &self.points[index])
+                    .collect();
+            }
+            breadth *= 2;
+        }
+    }
+}
+
+impl SpatialIndex for HnswIndex {
+    fn range_query<'a>(
+        &'a self,
+        center: (i32, i32),
+        radius: f64,
+    ) -> Box<dyn Iterator<Item = &'a Point> + 'a> {
+        Box::new(self.within(center, radius).into_iter())
+    }
+
+    fn rebuild(&mut self, points: &[Point]) {
+        *self = HnswIndex::new(points, self.parameters);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HnswIndex, HnswParameters};
+    use crate::domain::{Direction, Point};
+
+    #[test]
+    fn hnsw_index_finds_points_in_range() {
+        // arrange
+        let points: Vec<Point> = (0..400)
+            .map(|index| Point {
+                coordinates: ((index % 20) * 3, (index / 20) * 3),
+                number: index as u32 + 1,
+                direction: Direction::North,
+            })
+            .collect();
+
+        // act
+        let index = HnswIndex::new(&points, HnswParameters::default());
+
+        // assert
+        let mut outcome: Vec<u32> = index
+            .within((30, 30), 7.0)
+            .into_iter()
+            .map(|point| point.number)
+            .collect();
+        outcome.sort_unstable();
+        let mut expected: Vec<u32> = points
+            .iter()
+            .filter(|point| {
+                let (dx, dy) = (point.coordinates.0 - 30, point.coordinates.1 - 30);
+                f64::from(dx * dx + dy * dy).sqrt() < 7.0
+            })
+            .map(|point| point.number)
+            .collect();
+        expected.sort_unstable();
+        assert_eq!(outcome, expected);
+        assert_eq!(outcome.len(), 21);
+        let nearest: Vec<u32> = index
+            .nearest((31, 30), 2)
+            .into_iter()
+            .map(|point| point.number)
+            .collect();
+        assert_eq!(nearest, vec![211, 212]);
+        assert_eq!(index.within((-500, -500), 5.0), Vec::<&Point>::new());
+        assert!(HnswIndex::new(&[], HnswParameters::default())
+            .within((0, 0), 10.0)
+            .is_empty());
+    }
+}
@@ -0,0 +1,182 @@
+use crate::{
+    domain::{
+        ndjson::is_ndjson_path,
+        point::{close_neighbours, read_points_file, Direction, Point},
+    },
+    utilities::{AppError, Span},
+};
+use serde::Deserialize;
+use std::{collections::HashMap, path::Path};
+
+/// Point keyed by a string identifier, as found in an input file
+#[derive(Debug, Deserialize)]
+struct InputIdentifiedPoint {
+    id: String,
+    x: i32,
+    y: i32,
+    direction: Direction,
+}
+
+#[derive(Debug, Deserialize)]
+struct IdentifiedPointList {
+    points: Vec<InputIdentifiedPoint>,
+}
+
+/// Neighbourhood of points identified by strings, such as asset tags or
+/// UUIDs, rather than numbers.  Each point is numbered by its position in the
+/// input, from `1`, so the numbered API works on [`points`](Self::points),
+/// while queries here take and return identifiers.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct IdentifiedNeighbourhood {
+    points: Vec<Point>,
+    ids: Vec<String>,
+    numbers: HashMap<String, u32>,
+}
+
+impl IdentifiedNeighbourhood {
+    fn from_input(input: Vec<InputIdentifiedPoint>) -> Result<Self, AppError> {
+        let mut result = IdentifiedNeighbourhood::default();
+        for (
+            index,
+            InputIdentifiedPoint {
+                id,
+                x,
+                y,
+                direction,
+            },
+        ) in input.into_iter().enumerate()
+        {
+            let number = index as u32 + 1;
+            if result.numbers.insert(id.clone(), number).is_some() {
+                return Err(AppError::DuplicateIdError(id));
+            }
+            result.points.push(Point {
+                coordinates: (x, y),
+                number,
+                direction,
+            });
+            result.ids.push(id);
+        }
+        Ok(result)
+    }
+
+    /// Parse a points JSON string, as for [`parse_points`](crate::domain::parse_points),
+    /// but with an `id` string for each point, in place of its `number`.
+    /// Returns an error if two points share an id.
+    pub fn parse(json: &str) -> Result<Self, AppError> {
+        let _span = Span::enter("parse_identified_points", || {
+            format!("{} bytes", json.len())
+        });
+        let IdentifiedPointList { points } =
+            serde_json::from_str(json).map_err(AppError::JSONParseError)?;
+        IdentifiedNeighbourhood::from_input(points)
+    }
+
+    /// Read and parse the points JSON file at `path`, as for
+    /// [`IdentifiedNeighbourhood::parse`], or an NDJSON file, with one point
+    /// object per line
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, AppError> {
+        let path = path.as_ref();
+        let input = read_points_file(path)?;
+        if !is_ndjson_path(path) {
+            return IdentifiedNeighbourhood::parse(&input);
+        }
+        let points = input
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| {
+                serde_json::from_str(line).map_err(|error| AppError::NDJSONParseError {
+                    line: index + 1,
+                    source: error,
+                })
+            })
+            .collect::<Result<Vec<InputIdentifiedPoint>, AppError>>()?;
+        IdentifiedNeighbourhood::from_input(points)
+    }
+
+    /// Points, numbered by their position in the input, from `1`
+    pub fn points(&self) -> &[Point] {
+        &self.points
+    }
+
+    /// Identifier of the point numbered `number`
+    pub fn id(&self, number: u32) -> Option<&str> {
+        let index = usize::try_from(number).ok()?.checked_sub(1)?;
+        self.ids.get(index).map(String::as_str)
+    }
+
+    /// Number given to the point identified by `id`
+    pub fn number(&self, id: &str) -> Option<u32> {
+        self.numbers.get(id).copied()
+    }
+
+    /// Identifiers of points visible from the point identified by `id`, as
+    /// for [`visible_points_from_neighbours`](crate::domain::visible_points_from_neighbours),
+    /// in input order.  An empty vector is returned if no point has `id`.
+    pub fn visible_ids(&self, id: &str, half_arc_central_angle: u32, arc_radius: u32) -> Vec<&str> {
+        let point = match self
+            .number(id)
+            .and_then(|number| self.points.get(number as usize - 1))
+        {
+            Some(value) => value,
+            None => return vec![],
+        };
+        close_neighbours(point, half_arc_central_angle, arc_radius, &self.points)
+            .iter()
+            .filter_map(|Point { number, .. }| self.id(*number))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IdentifiedNeighbourhood;
+    use crate::domain::{visible_points_from_neighbours, Direction, Point};
+
+    #[test]
+    fn identified_neighbourhood_answers_queries_by_id() {
+        // arrange
+        let json = r#"{"points": [
+            {"id": "camera-north-03", "x": 0, "y": 0, "direction": "North"},
+            {"id": "8c1c5e0a-5b8e-4a8f-9d3b-0f4a3c2b1a00", "x": 1, "y": 5, "direction": "South"},
+            {"id": "mast-7", "x": 0, "y": 50, "direction": "West"}
+        ]}"#;
+
+        // act
+        let neighbourhood = IdentifiedNeighbourhood::parse(json).unwrap();
+        let outcome = neighbourhood.visible_ids("camera-north-03", 45, 20);
+
+        // assert
+        assert_eq!(outcome, vec!["8c1c5e0a-5b8e-4a8f-9d3b-0f4a3c2b1a00"]);
+        assert_eq!(
+            neighbourhood.visible_ids("missing", 45, 20),
+            Vec::<&str>::new()
+        );
+        assert_eq!(neighbourhood.number("mast-7"), Some(3));
+        assert_eq!(neighbourhood.id(1), Some("camera-north-03"));
+        assert_eq!((neighbourhood.id(0), neighbourhood.id(4)), (None, None));
+        assert_eq!(
+            neighbourhood.points()[1],
+            Point {
+                coordinates: (1, 5),
+                number: 2,
+                direction: Direction::South
+            }
+        );
+        assert_eq!(
+            visible_points_from_neighbours(2, 45, 20, neighbourhood.points()).len(),
+            1
+        );
+        let duplicate = r#"{"points": [
+            {"id": "a", "x": 0, "y": 0, "direction": "North"},
+            {"id": "a", "x": 1, "y": 1, "direction": "North"}
+        ]}"#;
+        assert_eq!(
+            IdentifiedNeighbourhood::parse(duplicate)
+                .unwrap_err()
+                .to_string(),
+            "Duplicate point id `a`. Check each point has its own id."
+        );
+    }
+}
@@ -0,0 +1,126 @@
+use crate::domain::{
+    geometry::BoundingBox, grid_index::density_cell_size, point::Point, spatial_index::IndexBackend,
+};
+use std::collections::HashSet;
+
+/// Neighbourhoods this small are quickest to scan, whatever the queries
+const SMALL_NEIGHBOURHOOD: usize = 512;
+
+/// Share of the neighbourhood's extent a query range may cover before
+/// scanning every point beats narrowing them down
+const WIDE_RANGE: f64 = 0.25;
+
+/// Share of density sized grid cells holding points, above which points are
+/// spread evenly enough for a grid
+const EVEN_SPREAD: f64 = 0.5;
+
+/// Expected use of a [`VisibilityEngine`](crate::domain::VisibilityEngine),
+/// from which [`choose_index`] picks an index backend
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Workload {
+    /// Typical query radius
+    pub radius: u32,
+
+    /// Queries expected between updates to the neighbourhood, after which
+    /// the first query rebuilds any index
+    pub queries: usize,
+}
+
+/// Pick an exact index backend for `points` and `workload`, so callers need
+/// not weigh index trade-offs themselves:
+///
+/// - a linear scan for small neighbourhoods, for too few queries between
+///   updates to repay building an index, or for query ranges covering a good
+///   share of the neighbourhood's extent;
+/// - a grid, for points spread fairly evenly over their extent;
+/// - otherwise, a ball tree, which adapts to clustered points.
+///
+/// The approximate HNSW backend is never picked, since it may miss points.
+pub fn choose_index(points: &[Point], workload: &Workload) -> IndexBackend {
+    let count = points.len();
+    let BoundingBox { min, max } = match BoundingBox::of_points(points) {
+        Some(value) if count > SMALL_NEIGHBOURHOOD => value,
+        _ => return IndexBackend::Linear,
+    };
+
+    // building costs around log n scans' worth of work per point
+    if (workload.queries as f64) < 16.0 * (count as f64).log2() {
+        return IndexBackend::Linear;
+    }
+    let width = f64::from(max.0) - f64::from(min.0) + 1.0;
+    let height = f64::from(max.1) - f64::from(min.1) + 1.0;
+    let radius = f64::from(workload.radius);
+    if std::f64::consts::PI * radius * radius >= WIDE_RANGE * width * height {
+        return IndexBackend::Linear;
+    }
+
+    let cell_size = i64::from(density_cell_size(points));
+    let occupied: HashSet<(i64, i64)> = points
+        .iter()
+        .map(|Point { coordinates, .. }| {
+            (
+                (i64::from(coordinates.0) - i64::from(min.0)) / cell_size,
+                (i64::from(coordinates.1) - i64::from(min.1)) / cell_size,
+            )
+        })
+        .collect();
+    let columns = (width / cell_size as f64).ceil();
+    let rows = (height / cell_size as f64).ceil();
+    if occupied.len() as f64 >= EVEN_SPREAD * columns * rows {
+        IndexBackend::Grid
+    } else {
+        IndexBackend::BallTree
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{choose_index, Workload};
+    use crate::domain::{Direction, IndexBackend, Point};
+
+    fn points<F: Fn(usize) -> (i32, i32)>(count: usize, position: F) -> Vec<Point> {
+        (0..count)
+            .map(|index| Point {
+                coordinates: position(index),
+                number: index as u32 + 1,
+                direction: Direction::North,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn choose_index_picks_backend_for_workload() {
+        // arrange
+        let even = points(10_000, |index| {
+            ((index % 100) as i32 * 10, (index / 100) as i32 * 10)
+        });
+        let clustered = points(10_000, |index| {
+            let cluster = (index % 4) as i32 * 10_000;
+            (cluster + (index % 37) as i32, cluster + (index % 41) as i32)
+        });
+        let workload = Workload {
+            radius: 25,
+            queries: 10_000,
+        };
+
+        // act
+        let outcome = (
+            choose_index(&even, &workload),
+            choose_index(&clustered, &workload),
+        );
+
+        // assert
+        assert_eq!(outcome, (IndexBackend::Grid, IndexBackend::BallTree));
+        assert_eq!(choose_index(&even[..100], &workload), IndexBackend::Linear);
+        let few_queries = Workload {
+            queries: 20,
+            ..workload
+        };
+        assert_eq!(choose_index(&even, &few_queries), IndexBackend::Linear);
+        let wide = Workload {
+            radius: 400,
+            ..workload
+        };
+        assert_eq!(choose_index(&even, &wide), IndexBackend::Linear);
+    }
+}
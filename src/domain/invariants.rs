@@ -0,0 +1,172 @@
+//! Checks of invariants the visibility geometry keeps for any neighbourhood,
+//! used by the crate’s property tests, and exposed so callers can check them
+//! against their own data.  Each check returns a description of the first
+//! counterexample found.
+
+use crate::domain::point::{
+    angular_position, close_neighbours, euclidean_distance, Direction, Point,
+};
+use std::f64::consts::PI;
+
+/// Numbers of the points `point` sees
+fn visible_numbers(
+    point: &Point,
+    half_arc_central_angle: u32,
+    arc_radius: u32,
+    neighbourhood: &[Point],
+) -> Vec<u32> {
+    close_neighbours(point, half_arc_central_angle, arc_radius, neighbourhood)
+        .iter()
+        .map(|Point { number, .. }| *number)
+        .collect()
+}
+
+/// `point` and its direction rotated a quarter turn clockwise about the origin
+pub fn rotate_quarter_turn(point: &Point) -> Point {
+    let (x, y) = point.coordinates;
+    Point {
+        coordinates: (y, -x),
+        number: point.number,
+        direction: match point.direction {
+            Direction::North => Direction::East,
+            Direction::East => Direction::South,
+            Direction::South => Direction::West,
+            Direction::West => Direction::North,
+            Direction::Vector { dx, dy } => Direction::Vector { dx: dy, dy: -dx },
+            Direction::Towards(number) => Direction::Towards(number),
+        },
+    }
+}
+
+/// Check the bearing between every pair of distinct positions in
+/// `neighbourhood` lies in `0` up to, but not including, `2 PI` radians
+pub fn check_bearings(neighbourhood: &[Point]) -> Result<(), String> {
+    for point in neighbourhood {
+        for neighbour in neighbourhood {
+            if point.coordinates == neighbour.coordinates {
+                continue;
+            }
+            let bearing = angular_position(point.coordinates, neighbour.coordinates);
+            if !(0.0..2.0 * PI).contains(&bearing) {
+                return Err(format!(
+                    "bearing from {:?} to {:?} is {bearing}",
+                    point.coordinates, neighbour.coordinates
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Check that with a `180` degree half arc, each point sees exactly the other
+/// points closer than `arc_radius`, whatever its direction
+pub fn check_full_circle(arc_radius: u32, neighbourhood: &[Point]) -> Result<(), String> {
+    for point in neighbourhood {
+        let expected: Vec<u32> = neighbourhood
+            .iter()
+            .filter(|neighbour| {
+                neighbour.number != point.number
+                    && euclidean_distance(point.coordinates, neighbour.coordinates)
+                        < f64::from(arc_radius)
+            })
+            .map(|Point { number, .. }| *number)
+            .collect();
+        let visible = visible_numbers(point, 180, arc_radius, neighbourhood);
+        if visible != expected {
+            return Err(format!(
+                "point {} sees {visible:?} with a 180 degree half arc, but {expected:?} are in range",
+                point.number
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Check that rotating the whole neighbourhood, positions and directions, by
+/// quarter turns leaves every point’s visible set unchanged
+pub fn check_rotation(
+    half_arc_central_angle: u32,
+    arc_radius: u32,
+    neighbourhood: &[Point],
+) -> Result<(), String> {
+    let mut rotated = neighbourhood.to_vec();
+    for turns in 1..4 {
+        rotated = rotated.iter().map(rotate_quarter_turn).collect();
+        for (point, rotated_point) in neighbourhood.iter().zip(&rotated) {
+            let visible = visible_numbers(point, half_arc_central_angle, arc_radius, neighbourhood);
+            let rotated_visible =
+                visible_numbers(rotated_point, half_arc_central_angle, arc_radius, &rotated);
+            if visible != rotated_visible {
+                return Err(format!(
+                    "point {} sees {visible:?}, but {rotated_visible:?} after {turns} quarter turns",
+                    point.number
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_bearings, check_full_circle, check_rotation};
+    use crate::domain::{Direction, Point};
+
+    /// Generator of pseudo-random neighbourhoods, from a fixed seed so
+    /// failures reproduce
+    struct Neighbourhoods {
+        state: u64,
+    }
+
+    impl Neighbourhoods {
+        fn next(&mut self, bound: u64) -> u64 {
+            self.state = self
+                .state
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            (self.state >> 33) % bound
+        }
+
+        /// Up to 24 points, on a small grid, so points often share a row,
+        /// column or diagonal, or coincide, where sector boundaries lie
+        fn neighbourhood(&mut self) -> Vec<Point> {
+            let size = self.next(24) + 1;
+            (0..size)
+                .map(|number| Point {
+                    coordinates: (self.next(17) as i32 - 8, self.next(17) as i32 - 8),
+                    number: number as u32 + 1,
+                    direction: match self.next(4) {
+                        0 => Direction::North,
+                        1 => Direction::East,
+                        2 => Direction::South,
+                        _ => Direction::West,
+                    },
+                })
+                .collect()
+        }
+    }
+
+    #[test]
+    fn geometry_invariants_hold_for_generated_neighbourhoods() {
+        // arrange
+        let mut neighbourhoods = Neighbourhoods { state: 42 };
+
+        for _ in 0..500 {
+            let neighbourhood = neighbourhoods.neighbourhood();
+            let angle = [0, 1, 30, 45, 89, 90, 135, 179, 180][neighbourhoods.next(9) as usize];
+            let radius = neighbourhoods.next(30) as u32;
+
+            // act
+            let outcome = check_bearings(&neighbourhood)
+                .and_then(|()| check_full_circle(radius, &neighbourhood))
+                .and_then(|()| check_rotation(angle, radius, &neighbourhood));
+
+            // assert
+            assert_eq!(
+                outcome,
+                Ok(()),
+                "angle {angle}, radius {radius}, {neighbourhood:?}"
+            );
+        }
+    }
+}
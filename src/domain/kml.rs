@@ -0,0 +1,117 @@
+use crate::domain::{origin::Origin, sector::sector_outline, sighting::Sighting};
+
+/// Styles for the origin, visible points and sector, as KML colours, which
+/// run alpha, blue, green, red
+const STYLES: &str = r##"    <Style id="origin"><IconStyle><color>ff3c3cc8</color></IconStyle></Style>
+    <Style id="visible"><IconStyle><color>ffdc7828</color></IconStyle></Style>
+    <Style id="sector">
+      <LineStyle><color>ffdc7828</color><width>2</width></LineStyle>
+      <PolyStyle><color>66e6be96</color></PolyStyle>
+    </Style>
+"##;
+
+fn placemark(name: &str, style: &str, description: &str, (x, y): (f64, f64)) -> String {
+    format!(
+        "    <Placemark>\n      <name>{name}</name>\n      <description>{description}</description>\n      <styleUrl>#{style}</styleUrl>\n      <Point><coordinates>{x},{y}</coordinates></Point>\n    </Placemark>\n"
+    )
+}
+
+/// Write a query from `origin`, with radius `arc_radius`, spanning left and
+/// right from its heading by `half_arc_central_angle` degrees, as a KML
+/// document, for reviewing in Google Earth: a placemark for the origin, the
+/// sector wedge as a polygon, and a folder of placemarks for `sightings`.
+/// `origin_point` is the number of the point queried from, if any, to name
+/// the origin placemark.  Coordinates are written as they are, so should be
+/// longitude and latitude.
+pub fn kml_document(
+    origin: &Origin,
+    origin_point: Option<u32>,
+    half_arc_central_angle: u32,
+    arc_radius: u32,
+    sightings: &[Sighting],
+) -> String {
+    let origin_name = match origin_point {
+        Some(number) => format!("Point {number}"),
+        None => String::from("Origin"),
+    };
+    let mut result = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<kml xmlns=\"http://www.opengis.net/kml/2.2\">\n  <Document>\n    <name>neighbours</name>\n",
+    );
+    result.push_str(STYLES);
+    result.push_str(&placemark(
+        &origin_name,
+        "origin",
+        &format!("heading {:.1}°", origin.heading),
+        origin.coordinates,
+    ));
+    let ring: Vec<String> = sector_outline(origin, half_arc_central_angle, arc_radius)
+        .iter()
+        .map(|(x, y)| format!("{x},{y}"))
+        .collect();
+    result.push_str(&format!(
+        "    <Placemark>\n      <name>Sector</name>\n      <description>half angle {half_arc_central_angle}°, radius {arc_radius}</description>\n      <styleUrl>#sector</styleUrl>\n      <Polygon><outerBoundaryIs><LinearRing><coordinates>{}</coordinates></LinearRing></outerBoundaryIs></Polygon>\n    </Placemark>\n",
+        ring.join(" ")
+    ));
+    result.push_str("    <Folder>\n      <name>Visible points</name>\n");
+    for sighting in sightings {
+        result.push_str(&placemark(
+            &format!("Point {}", sighting.point.number),
+            "visible",
+            &format!(
+                "distance {:.2}, bearing {:.1}°",
+                sighting.distance, sighting.bearing
+            ),
+            (
+                f64::from(sighting.point.coordinates.0),
+                f64::from(sighting.point.coordinates.1),
+            ),
+        ));
+    }
+    result.push_str("    </Folder>\n  </Document>\n</kml>\n");
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::kml_document;
+    use crate::domain::{visible_sightings, Direction, Origin, Point};
+
+    #[test]
+    fn kml_document_has_origin_sector_and_visible_points() {
+        // arrange
+        let neighbourhood = [
+            Point {
+                coordinates: (0, 0),
+                number: 1,
+                direction: Direction::North,
+            },
+            Point {
+                coordinates: (0, 5),
+                number: 2,
+                direction: Direction::East,
+            },
+            Point {
+                coordinates: (5, 0),
+                number: 3,
+                direction: Direction::South,
+            },
+        ];
+        let origin = Origin::from(&neighbourhood[0]);
+        let sightings = visible_sightings(1, 45, 10, &neighbourhood);
+
+        // act
+        let outcome = kml_document(&origin, Some(1), 45, 10, &sightings);
+
+        // assert
+        assert!(outcome.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<kml "));
+        assert!(outcome.ends_with("</kml>\n"));
+        assert_eq!(outcome.matches("<Placemark>").count(), 3);
+        assert!(outcome.contains("<name>Point 1</name>"));
+        assert!(outcome.contains(
+            "<name>Point 2</name>\n      <description>distance 5.00, bearing 0.0°</description>"
+        ));
+        assert!(!outcome.contains("<name>Point 3</name>"));
+        assert!(outcome.contains("<LinearRing><coordinates>0,0 "));
+        assert!(outcome.contains(" 0,0</coordinates></LinearRing>"));
+    }
+}
@@ -0,0 +1,194 @@
+//! Reader for LAS lidar point clouds, enabled by the `las` feature.  Points
+//! come from the scaled x and y coordinates of each point record, in any
+//! point data record format from `0` to `10`, rounded to whole units.
+//! Numbers may come from a point attribute named in a mapping such as
+//! `number=point_source_id`, from `intensity`, `classification`, `user_data`
+//! or `point_source_id`, and are otherwise counted from `1`, in file order.
+//! Points face north, unless `direction` maps an attribute holding headings.
+//! Compressed LAZ files are not supported.
+
+use crate::{
+    domain::{
+        ply::{point_cloud_error, CloudFields},
+        point::Point,
+    },
+    utilities::{AppError, Span},
+};
+use std::{fs, path::Path};
+
+/// Length of the shortest LAS header, from version 1.0 to 1.2
+const MINIMUM_HEADER_LENGTH: usize = 227;
+
+fn read_u16(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        bytes[offset],
+        bytes[offset + 1],
+        bytes[offset + 2],
+        bytes[offset + 3],
+    ])
+}
+
+fn read_f64(bytes: &[u8], offset: usize) -> f64 {
+    let mut buffer = [0_u8; 8];
+    buffer.copy_from_slice(&bytes[offset..offset + 8]);
+    f64::from_le_bytes(buffer)
+}
+
+/// Value of the attribute `name` in the point `record`, of point data record
+/// `format`
+fn attribute(record: &[u8], format: u8, name: &str) -> Result<f64, AppError> {
+    // formats 6 to 10 widen the flags, moving later attributes along
+    let extended = format >= 6;
+    Ok(match name {
+        "intensity" => f64::from(read_u16(record, 12)),
+        "classification" => f64::from(record[if extended { 16 } else { 15 }]),
+        "user_data" => f64::from(record[17]),
+        "point_source_id" => f64::from(read_u16(record, if extended { 20 } else { 18 })),
+        _ => {
+            return Err(point_cloud_error(format!(
+                "unknown LAS attribute `{name}`, expected `intensity`, `classification`, `user_data` or `point_source_id`"
+            )))
+        }
+    })
+}
+
+/// Parse points from the LAS point cloud in `bytes`, taking numbers and
+/// directions from the point attributes named in `fields`, a mapping such as
+/// `number=point_source_id`, if given
+pub fn parse_points_las(bytes: &[u8], fields: Option<&str>) -> Result<Vec<Point>, AppError> {
+    let _span = Span::enter("parse_points_las", || format!("{} bytes", bytes.len()));
+    let fields = match fields {
+        Some(value) => CloudFields::parse(value)?,
+        None => CloudFields::default(),
+    };
+    if bytes.len() < MINIMUM_HEADER_LENGTH || &bytes[..4] != b"LASF" {
+        return Err(point_cloud_error(String::from(
+            "missing `LASF` signature, so not a LAS file",
+        )));
+    }
+    let (major, minor) = (bytes[24], bytes[25]);
+    let offset_to_points = read_u32(bytes, 96) as usize;
+    let format = bytes[104] & 0x3f;
+    if bytes[104] & 0x80 != 0 {
+        return Err(point_cloud_error(String::from(
+            "compressed LAZ point data is not supported",
+        )));
+    }
+    if format > 10 {
+        return Err(point_cloud_error(format!(
+            "unsupported point data record format {format}"
+        )));
+    }
+    let record_length = usize::from(read_u16(bytes, 105));
+    let minimum_record_length = if format >= 6 { 22 } else { 20 };
+    if record_length < minimum_record_length {
+        return Err(point_cloud_error(format!(
+            "point records of {record_length} bytes are too short for format {format}"
+        )));
+    }
+    let legacy_count = read_u32(bytes, 107) as usize;
+
+    // LAS 1.4 keeps a 64-bit count, when the legacy count is left as zero
+    let count = if legacy_count == 0 && (major, minor) >= (1, 4) && bytes.len() >= 255 {
+        let mut buffer = [0_u8; 8];
+        buffer.copy_from_slice(&bytes[247..255]);
+        u64::from_le_bytes(buffer) as usize
+    } else {
+        legacy_count
+    };
+    let (scale, offset) = (
+        (read_f64(bytes, 131), read_f64(bytes, 139)),
+        (read_f64(bytes, 155), read_f64(bytes, 163)),
+    );
+    let mut result = Vec::with_capacity(count.min(bytes.len() / record_length));
+    for index in 0..count {
+        let start = offset_to_points + index * record_length;
+        let record = bytes
+            .get(start..start + record_length)
+            .ok_or_else(|| point_cloud_error(format!("point {} is missing", index + 1)))?;
+        let coordinate = |position: usize, scale: f64, offset: f64| {
+            f64::from(read_u32(record, position) as i32) * scale + offset
+        };
+        let value = |name: &Option<String>| {
+            name.as_deref()
+                .map(|name| attribute(record, format, name))
+                .transpose()
+        };
+        result.push(fields.point(
+            index,
+            (
+                coordinate(0, scale.0, offset.0),
+                coordinate(4, scale.1, offset.1),
+            ),
+            value(&fields.number)?,
+            value(&fields.direction)?,
+        )?);
+    }
+    Ok(result)
+}
+
+/// Parse points from the LAS file at `path`, as for [`parse_points_las`]
+pub(crate) fn parse_points_las_file(
+    path: &Path,
+    fields: Option<&str>,
+) -> Result<Vec<Point>, AppError> {
+    let bytes = fs::read(path).map_err(|error| AppError::BinaryFileError {
+        path: path.display().to_string(),
+        source: error,
+    })?;
+    parse_points_las(&bytes, fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_points_las;
+
+    /// LAS 1.2 file, with format 1 point records holding `points`, as x, y and
+    /// point source id, scaled by 0.01 and offset by 1000 along x
+    fn las_file(points: &[(i32, i32, u16)]) -> Vec<u8> {
+        let mut result = vec![0_u8; 227];
+        result[..4].copy_from_slice(b"LASF");
+        result[24] = 1;
+        result[25] = 2;
+        result[94..96].copy_from_slice(&227_u16.to_le_bytes());
+        result[96..100].copy_from_slice(&227_u32.to_le_bytes());
+        result[104] = 1;
+        result[105..107].copy_from_slice(&28_u16.to_le_bytes());
+        result[107..111].copy_from_slice(&(points.len() as u32).to_le_bytes());
+        for (position, value) in [(131, 0.01), (139, 0.01), (147, 0.01), (155, 1000.0)] {
+            result[position..position + 8].copy_from_slice(&f64::to_le_bytes(value));
+        }
+        for (x, y, source) in points {
+            let mut record = vec![0_u8; 28];
+            record[..4].copy_from_slice(&x.to_le_bytes());
+            record[4..8].copy_from_slice(&y.to_le_bytes());
+            record[18..20].copy_from_slice(&source.to_le_bytes());
+            result.extend_from_slice(&record);
+        }
+        result
+    }
+
+    #[test]
+    fn parse_points_las_scales_and_numbers_points() {
+        // arrange
+        let bytes = las_file(&[(1250, -390, 41), (-100_000, 2000, 42)]);
+
+        // act
+        let outcome = parse_points_las(&bytes, Some("number=point_source_id")).unwrap();
+
+        // assert
+        let summary: Vec<(u32, (i32, i32))> = outcome
+            .iter()
+            .map(|point| (point.number, point.coordinates))
+            .collect();
+        assert_eq!(summary, vec![(41, (1013, -4)), (42, (0, 20))]);
+        assert_eq!(parse_points_las(&bytes, None).unwrap()[1].number, 2);
+        assert!(parse_points_las(&bytes, Some("number=colour")).is_err());
+        assert!(parse_points_las(&bytes[..bytes.len() - 1], None).is_err());
+        assert!(parse_points_las(b"ply\n", None).is_err());
+    }
+}
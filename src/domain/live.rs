@@ -0,0 +1,427 @@
+//! Live neighbourhoods, kept up to date from a stream of position updates,
+//! enabled by the `stream` feature.  Updates arrive through an
+//! [`UpdateSource`], such as a channel fed by a message queue consumer, or
+//! JSON lines piped from a tool like `kcat`.
+
+use crate::{
+    domain::{
+        observed::{observed_cutoff, ObservedPoint},
+        point::{heading_direction, Direction, Point},
+    },
+    utilities::{AppError, Span},
+};
+use serde::Deserialize;
+use std::{collections::HashMap, io::BufRead, sync::mpsc::Receiver};
+
+/// Change to one point of a live neighbourhood
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PointUpdate {
+    /// Point moved or turned, or joined the neighbourhood
+    Moved(Point),
+
+    /// Point moved or turned, or joined the neighbourhood, in a fix observed
+    /// at this time, in seconds since the Unix epoch
+    Observed(Point, u64),
+
+    /// Point, with this number, left the neighbourhood
+    Removed(u32),
+}
+
+/// Update message, as found in a JSON lines stream
+#[derive(Debug, Deserialize)]
+struct UpdateMessage {
+    number: u32,
+    x: Option<i32>,
+    y: Option<i32>,
+    direction: Option<Direction>,
+
+    /// Compass heading in degrees, clockwise from north, as reported by GPS
+    /// trackers, used when no direction is given
+    heading: Option<f64>,
+
+    /// Time of the fix, in seconds since the Unix epoch
+    observed_at: Option<u64>,
+
+    #[serde(default)]
+    removed: bool,
+}
+
+/// Parse a JSON update message.  Positions have the same fields as entries of
+/// a points JSON file’s `points` array, for example
+/// `{"number": 3, "x": 27, "y": 46, "direction": "East"}`, while
+/// `{"number": 3, "removed": true}` removes a point.  A tracker’s `heading`,
+/// in degrees clockwise from north, may be given instead of `direction`, and
+/// is rounded to the nearest compass direction.  A direction towards another
+/// point, as `{"towards": 12}`, is rejected, since the point faced may itself
+/// be moving.  An `observed_at` time, in seconds since the Unix epoch, makes
+/// the update [`PointUpdate::Observed`].
+pub fn parse_update(json: &str) -> Result<PointUpdate, serde_json::Error> {
+    let UpdateMessage {
+        number,
+        x,
+        y,
+        direction,
+        heading,
+        observed_at,
+        removed,
+    } = serde_json::from_str(json)?;
+    if removed {
+        return Ok(PointUpdate::Removed(number));
+    }
+    match (x, y, direction.or_else(|| heading.map(heading_direction))) {
+        (Some(_), Some(_), Some(Direction::Towards(target))) => Err(serde::de::Error::custom(
+            format!("position update faces point {target}, but should give a compass direction, a vector or a heading"),
+        )),
+        (Some(x), Some(y), Some(direction)) => {
+            let point = Point {
+                coordinates: (x, y),
+                number,
+                direction,
+            };
+            Ok(match observed_at {
+                Some(value) => PointUpdate::Observed(point, value),
+                None => PointUpdate::Moved(point),
+            })
+        }
+        _ => Err(serde::de::Error::custom(
+            "position update needs `x`, `y` and `direction` or `heading`",
+        )),
+    }
+}
+
+/// Source of position updates, such as a message queue consumer
+pub trait UpdateSource {
+    /// Block until the next update arrives, returning `None` once the source
+    /// is finished
+    fn next_update(&mut self) -> Option<Result<PointUpdate, AppError>>;
+}
+
+/// Updates sent over a channel, finishing when every sender is dropped
+impl UpdateSource for Receiver<PointUpdate> {
+    fn next_update(&mut self) -> Option<Result<PointUpdate, AppError>> {
+        self.recv().ok().map(Ok)
+    }
+}
+
+/// Updates read as JSON lines, see [`parse_update`], skipping blank lines
+pub struct JsonLinesSource<R> {
+    reader: R,
+    line_number: usize,
+}
+
+impl<R: BufRead> JsonLinesSource<R> {
+    pub fn new(reader: R) -> Self {
+        JsonLinesSource {
+            reader,
+            line_number: 0,
+        }
+    }
+}
+
+impl<R: BufRead> UpdateSource for JsonLinesSource<R> {
+    fn next_update(&mut self) -> Option<Result<PointUpdate, AppError>> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            self.line_number += 1;
+            match self.reader.read_line(&mut line) {
+                Ok(0) => return None,
+                Ok(_) if line.trim().is_empty() => continue,
+                Ok(_) => {
+                    return Some(
+                        parse_update(&line).map_err(|error| AppError::NDJSONParseError {
+                            line: self.line_number,
+                            source: error,
+                        }),
+                    )
+                }
+                Err(error) => {
+                    return Some(Err(AppError::InvalidFileError {
+                        expected_path: String::from("update stream"),
+                        source: error,
+                    }))
+                }
+            }
+        }
+    }
+}
+
+/// Neighbourhood held in memory, and changed by applying updates
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LiveNeighbourhood {
+    points: Vec<Point>,
+
+    /// Index in `points` of the point with each number
+    indices: HashMap<u32, usize>,
+
+    /// Time each point was last observed, for points whose updates gave one
+    observed_at: HashMap<u32, u64>,
+}
+
+impl From<ObservedPoint> for PointUpdate {
+    fn from(ObservedPoint { point, observed_at }: ObservedPoint) -> Self {
+        match observed_at {
+            Some(value) => PointUpdate::Observed(point, value),
+            None => PointUpdate::Moved(point),
+        }
+    }
+}
+
+impl LiveNeighbourhood {
+    /// Start from `points`.  Later points replace earlier ones with the same
+    /// number.
+    pub fn new(points: Vec<Point>) -> Self {
+        let mut result = LiveNeighbourhood::default();
+        for point in points {
+            result.apply(&PointUpdate::Moved(point));
+        }
+        result
+    }
+
+    /// Current points, in the order they joined the neighbourhood
+    pub fn points(&self) -> &[Point] {
+        &self.points
+    }
+
+    /// Current points observed no more than `max_age` seconds ago, or with no
+    /// observation time, in the order they joined the neighbourhood
+    pub fn fresh_points(&self, max_age: u64) -> Vec<Point> {
+        let cutoff = observed_cutoff(max_age);
+        self.points
+            .iter()
+            .filter(|point| {
+                self.observed_at
+                    .get(&point.number)
+                    .map_or(true, |value| *value >= cutoff)
+            })
+            .copied()
+            .collect()
+    }
+
+    /// Apply `update`, returning true if the neighbourhood, or a point’s
+    /// observation time, changed
+    pub fn apply(&mut self, update: &PointUpdate) -> bool {
+        match update {
+            PointUpdate::Moved(point) => {
+                let observed = self.observed_at.remove(&point.number).is_some();
+                self.move_point(point) || observed
+            }
+            PointUpdate::Observed(point, observed_at) => {
+                let observed =
+                    self.observed_at.insert(point.number, *observed_at) != Some(*observed_at);
+                self.move_point(point) || observed
+            }
+            PointUpdate::Removed(number) => {
+                self.observed_at.remove(number);
+                match self.indices.remove(number) {
+                    Some(removed) => {
+                        self.points.remove(removed);
+                        for index in self.indices.values_mut() {
+                            if *index > removed {
+                                *index -= 1;
+                            }
+                        }
+                        true
+                    }
+                    None => false,
+                }
+            }
+        }
+    }
+
+    /// Move `point`, or add it, returning true if the neighbourhood changed
+    fn move_point(&mut self, point: &Point) -> bool {
+        match self.indices.get(&point.number) {
+            Some(index) if self.points[*index] == *point => false,
+            Some(index) => {
+                self.points[*index] = *point;
+                true
+            }
+            None => {
+                self.indices.insert(point.number, self.points.len());
+                self.points.push(*point);
+                true
+            }
+        }
+    }
+}
+
+/// Apply every update from `source` to `neighbourhood`, until the source
+/// finishes, calling `on_change` after each update which changed it, for
+/// example to re-run a visibility query.  Returns the number of updates
+/// applied, or the first error from the source or `on_change`.
+pub fn monitor<S, F>(
+    neighbourhood: &mut LiveNeighbourhood,
+    source: &mut S,
+    mut on_change: F,
+) -> Result<usize, AppError>
+where
+    S: UpdateSource,
+    F: FnMut(&LiveNeighbourhood, &PointUpdate) -> Result<(), AppError>,
+{
+    let _span = Span::enter("monitor", String::new);
+    let mut applied = 0;
+    while let Some(update) = source.next_update() {
+        let update = update?;
+        applied += 1;
+        if neighbourhood.apply(&update) {
+            on_change(neighbourhood, &update)?;
+        }
+    }
+    Ok(applied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{monitor, parse_update, JsonLinesSource, LiveNeighbourhood, PointUpdate};
+    use crate::domain::{
+        test_support::point, unix_time_now, visible_points_from_neighbours, Direction,
+        ObservedPoint, Point,
+    };
+    use std::sync::mpsc;
+
+    #[test]
+    fn live_neighbourhood_applies_updates() {
+        // arrange
+        let mut neighbourhood = LiveNeighbourhood::new(vec![
+            point(1, (0, 0), Direction::North),
+            point(2, (0, 5), Direction::South),
+            point(3, (3, 4), Direction::East),
+        ]);
+
+        // act
+        let removed = neighbourhood.apply(&PointUpdate::Removed(2));
+        let moved = neighbourhood.apply(&PointUpdate::Moved(point(3, (1, 1), Direction::West)));
+        let unchanged = neighbourhood.apply(&PointUpdate::Moved(point(3, (1, 1), Direction::West)));
+        let missing = neighbourhood.apply(&PointUpdate::Removed(9));
+
+        // assert
+        assert_eq!(
+            (removed, moved, unchanged, missing),
+            (true, true, false, false)
+        );
+        assert_eq!(
+            neighbourhood.points(),
+            &[
+                point(1, (0, 0), Direction::North),
+                point(3, (1, 1), Direction::West)
+            ]
+        );
+    }
+
+    #[test]
+    fn live_neighbourhood_ignores_stale_points() {
+        // arrange
+        let now = unix_time_now();
+        let mut neighbourhood = LiveNeighbourhood::new(vec![point(1, (0, 0), Direction::North)]);
+        let stale = PointUpdate::Observed(point(2, (0, 5), Direction::South), now - 600);
+        let fresh = PointUpdate::Observed(point(3, (1, 4), Direction::East), now - 5);
+
+        // act
+        let changes = (
+            neighbourhood.apply(&stale),
+            neighbourhood.apply(&fresh),
+            neighbourhood.apply(&fresh),
+            neighbourhood.apply(&PointUpdate::Observed(
+                point(2, (0, 5), Direction::South),
+                now,
+            )),
+        );
+        let outcome = neighbourhood.fresh_points(60);
+
+        // assert
+        assert_eq!(changes, (true, true, false, true));
+        assert_eq!(outcome, neighbourhood.points());
+        neighbourhood.apply(&PointUpdate::Observed(
+            point(2, (0, 5), Direction::South),
+            now - 600,
+        ));
+        assert_eq!(
+            neighbourhood.fresh_points(60),
+            vec![
+                point(1, (0, 0), Direction::North),
+                point(3, (1, 4), Direction::East)
+            ]
+        );
+        let visible: Vec<u32> =
+            visible_points_from_neighbours(1, 45, 10, &neighbourhood.fresh_points(60))
+                .iter()
+                .map(|Point { number, .. }| *number)
+                .collect();
+        assert_eq!(visible, vec![3]);
+        assert_eq!(
+            PointUpdate::from(ObservedPoint {
+                point: point(4, (0, 0), Direction::West),
+                observed_at: None
+            }),
+            PointUpdate::Moved(point(4, (0, 0), Direction::West))
+        );
+    }
+
+    #[test]
+    fn monitor_reports_changes() {
+        // arrange
+        let mut neighbourhood = LiveNeighbourhood::new(vec![point(1, (0, 0), Direction::North)]);
+        let (sender, mut receiver) = mpsc::channel();
+        sender
+            .send(PointUpdate::Moved(point(2, (0, 5), Direction::South)))
+            .unwrap();
+        sender
+            .send(PointUpdate::Moved(point(2, (0, 5), Direction::South)))
+            .unwrap();
+        sender.send(PointUpdate::Removed(2)).unwrap();
+        drop(sender);
+
+        // act
+        let mut seen = vec![];
+        let applied = monitor(&mut neighbourhood, &mut receiver, |live, _| {
+            let visible = visible_points_from_neighbours(1, 45, 20, live.points());
+            seen.push(visible.len());
+            Ok(())
+        })
+        .unwrap();
+
+        // assert
+        assert_eq!(applied, 3);
+        assert_eq!(seen, vec![1, 0]);
+    }
+
+    #[test]
+    fn json_lines_source_parses_updates() {
+        // arrange
+        let input = "{\"number\": 2, \"x\": 0, \"y\": 5, \"direction\": \"South\"}\n\n{\"number\": 2, \"removed\": true}\n{\"number\": 3}\n";
+        let mut neighbourhood = LiveNeighbourhood::default();
+
+        // act
+        let outcome = monitor(
+            &mut neighbourhood,
+            &mut JsonLinesSource::new(input.as_bytes()),
+            |_, _| Ok(()),
+        )
+        .unwrap_err()
+        .to_string();
+
+        // assert
+        assert_eq!(
+            outcome,
+            "Error parsing NDJSON line 4: position update needs `x`, `y` and `direction` or `heading`"
+        );
+        assert_eq!(neighbourhood.points().len(), 0);
+        assert_eq!(
+            parse_update("{\"number\": 7, \"removed\": true}").unwrap(),
+            PointUpdate::Removed(7)
+        );
+        assert_eq!(
+            parse_update("{\"number\": 7, \"x\": 1, \"y\": 2, \"heading\": 350.5}").unwrap(),
+            PointUpdate::Moved(point(7, (1, 2), Direction::North))
+        );
+        assert_eq!(
+            parse_update("{\"number\": 7, \"x\": 1, \"y\": 2, \"direction\": \"East\", \"observed_at\": 1700000000}").unwrap(),
+            PointUpdate::Observed(point(7, (1, 2), Direction::East), 1_700_000_000)
+        );
+        assert!(parse_update(
+            "{\"number\": 7, \"x\": 1, \"y\": 2, \"direction\": {\"towards\": 3}}"
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,144 @@
+//! Loading without blocking async runtimes, enabled by the `async` feature.
+//! Work runs on a background thread, and the returned future completes when
+//! it finishes, so it suits any executor, including tokio, without tying the
+//! crate to one.
+
+use crate::{
+    domain::point::{parse_points_file, Point},
+    utilities::AppError,
+};
+use std::{
+    future::Future,
+    panic::{self, AssertUnwindSafe},
+    path::PathBuf,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+    thread,
+};
+
+/// Result of a background task, and the waker of the task awaiting it
+struct Shared<T> {
+    result: Option<thread::Result<T>>,
+    waker: Option<Waker>,
+}
+
+/// Future of work running on a background thread, see [`Loading::spawn`]
+pub struct Loading<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+}
+
+impl<T: Send + 'static> Loading<T> {
+    /// Run `work` on a new thread, returning a future of its result.  If
+    /// `work` panics, the panic resumes in the task awaiting the future.
+    pub fn spawn<F: FnOnce() -> T + Send + 'static>(work: F) -> Self {
+        let shared = Arc::new(Mutex::new(Shared {
+            result: None,
+            waker: None,
+        }));
+        let task_shared = Arc::clone(&shared);
+        thread::spawn(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(work));
+            let mut shared = task_shared
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            shared.result = Some(result);
+            if let Some(waker) = shared.waker.take() {
+                waker.wake();
+            }
+        });
+        Loading { shared }
+    }
+}
+
+impl<T> Future for Loading<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<T> {
+        let mut shared = self
+            .shared
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        match shared.result.take() {
+            Some(Ok(value)) => Poll::Ready(value),
+            Some(Err(payload)) => {
+                drop(shared);
+                panic::resume_unwind(payload)
+            }
+            None => {
+                shared.waker = Some(context.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Parse a points file, as for [`parse_points_file`], on a background thread,
+/// so a server’s runtime threads carry on serving while large inputs load
+pub fn parse_points_file_async<P: Into<PathBuf>>(path: P) -> Loading<Result<Vec<Point>, AppError>> {
+    let path = path.into();
+    Loading::spawn(move || parse_points_file(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_points_file_async, Loading};
+    use crate::{
+        domain::{Direction, Point, VisibilityEngine},
+        utilities::AppError,
+    };
+    use std::{
+        future::Future,
+        sync::Arc,
+        task::{Context, Poll, Wake},
+        thread::{self, Thread},
+    };
+
+    /// Waker unparking the thread which polled
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    /// Minimal executor, polling `future` on this thread until it completes
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let waker = Arc::new(ThreadWaker(thread::current())).into();
+        let mut context = Context::from_waker(&waker);
+        let mut future = Box::pin(future);
+        loop {
+            match future.as_mut().poll(&mut context) {
+                Poll::Ready(value) => return value,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    #[test]
+    fn parse_points_file_async_gives_expected_result() -> Result<(), AppError> {
+        // arrange
+        let path = "./fixtures/valid_points.json";
+
+        // act
+        let outcome = block_on(parse_points_file_async(path))?;
+
+        // assert
+        assert_eq!(
+            outcome[0],
+            Point {
+                coordinates: (28, 42),
+                number: 1,
+                direction: Direction::North
+            }
+        );
+        assert!(block_on(parse_points_file_async("./fixtures/missing.json")).is_err());
+        let engine = block_on(VisibilityEngine::from_file_async(path))?;
+        assert_eq!(engine.len(), outcome.len());
+        let panicked = std::panic::catch_unwind(|| block_on(Loading::spawn(|| panic!("oops"))));
+        assert!(panicked.is_err());
+        assert_eq!(block_on(Loading::spawn(|| 42)), 42);
+        Ok(())
+    }
+}
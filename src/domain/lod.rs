@@ -0,0 +1,299 @@
+use crate::{
+    domain::{
+        geometry::BoundingBox,
+        point::{angular_position_f64, euclidean_distance_f64, inside_sector, sees, Point},
+    },
+    utilities::Span,
+};
+
+/// Group of nearby points, standing in for them in approximate queries
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SuperPoint {
+    /// Mean x,y coordinates of the points in the group
+    pub centroid: (f64, f64),
+
+    /// Number of points in the group
+    pub count: usize,
+
+    /// Smallest box enclosing the group
+    pub bounds: BoundingBox,
+}
+
+/// Result of an approximate query, see [`LevelOfDetail::visible_points`]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ApproximateVisibility<'a> {
+    /// Nearby points, tested individually, in ascending number order
+    pub points: Vec<&'a Point>,
+
+    /// Distant groups, tested by their centroid
+    pub aggregates: Vec<SuperPoint>,
+}
+
+impl<'a> ApproximateVisibility<'a> {
+    /// Estimated number of visible points, counting every point in each
+    /// visible group
+    pub fn estimated_count(&self) -> usize {
+        self.points.len()
+            + self
+                .aggregates
+                .iter()
+                .map(|SuperPoint { count, .. }| count)
+                .sum::<usize>()
+    }
+}
+
+/// Node of the hierarchy, covering the points inside its quadrant
+#[derive(Clone, Debug)]
+struct Node {
+    group: SuperPoint,
+
+    /// Child node indices, empty for leaves
+    children: Vec<usize>,
+
+    /// Indices of points held by a leaf
+    points: Vec<usize>,
+}
+
+/// Quadtree hierarchy over a neighbourhood, giving approximate queries for
+/// very large neighbourhoods, where distant regions are represented by
+/// aggregate "super points", rather than testing every point in them
+#[derive(Clone, Debug)]
+pub struct LevelOfDetail {
+    points: Vec<Point>,
+    nodes: Vec<Node>,
+}
+
+/// Shortest distance from `(x, y)` to a point inside `bounds`
+fn distance_to_bounds((x, y): (f64, f64), bounds: &BoundingBox) -> f64 {
+    let clamp = |value: f64, min: i32, max: i32| value.max(f64::from(min)).min(f64::from(max));
+    euclidean_distance_f64(
+        (x, y),
+        (
+            clamp(x, bounds.min.0, bounds.max.0),
+            clamp(y, bounds.min.1, bounds.max.1),
+        ),
+    )
+}
+
+impl LevelOfDetail {
+    /// Build the hierarchy, splitting each region into quadrants until it
+    /// holds at most `leaf_size` points, or they all share coordinates
+    pub fn new(points: Vec<Point>, leaf_size: usize) -> Self {
+        let _span = Span::enter("level_of_detail", || format!("{} points", points.len()));
+        let mut result = LevelOfDetail {
+            points,
+            nodes: vec![],
+        };
+        if !result.points.is_empty() {
+            let indices = (0..result.points.len()).collect();
+            result.build(indices, leaf_size.max(1));
+        }
+        result
+    }
+
+    /// Add a node for the points at `indices`, and its descendants, returning
+    /// its index
+    fn build(&mut self, indices: Vec<usize>, leaf_size: usize) -> usize {
+        let bounds = match BoundingBox::of_points(indices.iter().map(|index| &self.points[*index]))
+        {
+            Some(value) => value,
+            None => unreachable!("Unexpected empty level of detail node"),
+        };
+        let (sum_x, sum_y) = indices.iter().fold((0.0, 0.0), |(sum_x, sum_y), index| {
+            let (x, y) = self.points[*index].coordinates;
+            (sum_x + f64::from(x), sum_y + f64::from(y))
+        });
+        let count = indices.len();
+        let node = self.nodes.len();
+        self.nodes.push(Node {
+            group: SuperPoint {
+                centroid: (sum_x / count as f64, sum_y / count as f64),
+                count,
+                bounds,
+            },
+            children: vec![],
+            points: vec![],
+        });
+        if count <= leaf_size || bounds.min == bounds.max {
+            self.nodes[node].points = indices;
+            return node;
+        }
+
+        let middle = |min: i32, max: i32| ((i64::from(min) + i64::from(max)) / 2) as i32;
+        let (middle_x, middle_y) = (
+            middle(bounds.min.0, bounds.max.0),
+            middle(bounds.min.1, bounds.max.1),
+        );
+        let mut quadrants: [Vec<usize>; 4] = Default::default();
+        for index in indices {
+            let (x, y) = self.points[index].coordinates;
+            quadrants[usize::from(x > middle_x) + 2 * usize::from(y > middle_y)].push(index);
+        }
+        for quadrant in quadrants {
+            if !quadrant.is_empty() {
+                let child = self.build(quadrant, leaf_size);
+                self.nodes[node].children.push(child);
+            }
+        }
+        node
+    }
+
+    /// Points and groups visible from the point numbered `point_number`,
+    /// within a segment of radius `arc_radius`, spanning left and right from
+    /// its direction by `half_arc_central_angle` degrees.
+    ///
+    /// `detail` trades accuracy for speed: a group is reported as a single
+    /// [`SuperPoint`], without testing its points, when its size is less than
+    /// `detail` times its distance from the observer, and its bounding box
+    /// lies wholly inside, or wholly outside, the segment.  A `detail` of zero
+    /// tests every point individually, while values around `0.5` test far
+    /// fewer, only telling which distant points are visible to the
+    /// resolution of their group.  Estimated counts can miss points of a
+    /// narrow segment passing through a group without covering any of its
+    /// corners, and, for segments wider than `90` degrees either side, can
+    /// include a group’s points outside the segment.  An empty result is returned if no point matching
+    /// `point_number` is found.
+    pub fn visible_points(
+        &self,
+        point_number: u32,
+        half_arc_central_angle: u32,
+        arc_radius: u32,
+        detail: f64,
+    ) -> ApproximateVisibility<'_> {
+        let mut result = ApproximateVisibility::default();
+        let observer = match self
+            .points
+            .iter()
+            .find(|Point { number, .. }| *number == point_number)
+        {
+            Some(value) => value,
+            None => return result,
+        };
+        let _span = Span::enter("approximate_visible_points", || {
+            format!("point {point_number} of {}", self.points.len())
+        });
+        let origin = (
+            f64::from(observer.coordinates.0),
+            f64::from(observer.coordinates.1),
+        );
+        let radius = f64::from(arc_radius);
+        let half_arc_central_angle_radians = f64::from(half_arc_central_angle).to_radians();
+        let mut stack = match self.nodes.is_empty() {
+            true => vec![],
+            false => vec![0],
+        };
+        while let Some(node) = stack.pop() {
+            let Node {
+                group,
+                children,
+                points,
+            } = &self.nodes[node];
+            if distance_to_bounds(origin, &group.bounds) >= radius {
+                continue;
+            }
+            let (min_x, min_y) = group.bounds.min;
+            let (max_x, max_y) = group.bounds.max;
+            let size = f64::from(max_x - min_x).max(f64::from(max_y - min_y));
+            let distance = euclidean_distance_f64(origin, group.centroid);
+            let contains_observer = (min_x..=max_x).contains(&observer.coordinates.0)
+                && (min_y..=max_y).contains(&observer.coordinates.1);
+            let corners = [
+                (min_x, min_y),
+                (min_x, max_y),
+                (max_x, min_y),
+                (max_x, max_y),
+            ];
+            let farthest = corners
+                .iter()
+                .map(|(x, y)| euclidean_distance_f64(origin, (f64::from(*x), f64::from(*y))))
+                .fold(0.0, f64::max);
+            let corners_inside = corners
+                .iter()
+                .filter(|(x, y)| {
+                    inside_sector(
+                        angular_position_f64(origin, (f64::from(*x), f64::from(*y))),
+                        observer.direction.bearing(),
+                        half_arc_central_angle_radians,
+                    )
+                })
+                .count();
+            // groups straddling the segment’s arc or edges are split, so only
+            // groups wholly inside or outside the segment are aggregated
+            if !children.is_empty()
+                && !contains_observer
+                && size < detail * distance
+                && farthest < radius
+                && (corners_inside == 0 || corners_inside == corners.len())
+            {
+                if corners_inside > 0 {
+                    result.aggregates.push(*group);
+                }
+                continue;
+            }
+            stack.extend(children);
+            result.points.extend(
+                points
+                    .iter()
+                    .map(|index| &self.points[*index])
+                    .filter(|point| sees(observer, point, half_arc_central_angle, arc_radius)),
+            );
+        }
+        result.points.sort_by_key(|Point { number, .. }| *number);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LevelOfDetail;
+    use crate::domain::{visible_points_from_neighbours, Direction, Point};
+
+    fn grid(size: i32) -> Vec<Point> {
+        (0..size * size)
+            .map(|val| Point {
+                coordinates: (val % size, val / size),
+                number: val as u32 + 1,
+                direction: Direction::East,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn level_of_detail_is_exact_without_aggregation() {
+        // arrange
+        let points = grid(20);
+        let hierarchy = LevelOfDetail::new(points.clone(), 4);
+
+        // act
+        let outcome = hierarchy.visible_points(1, 45, 15, 0.0);
+
+        // assert
+        let numbers: Vec<u32> = outcome.points.iter().map(|point| point.number).collect();
+        let expected: Vec<u32> = visible_points_from_neighbours(1, 45, 15, &points)
+            .iter()
+            .map(|point| point.number)
+            .collect();
+        assert_eq!(numbers, expected);
+        assert_eq!(outcome.aggregates.len(), 0);
+        assert_eq!(
+            hierarchy.visible_points(999, 45, 15, 0.0).estimated_count(),
+            0
+        );
+    }
+
+    #[test]
+    fn level_of_detail_aggregates_distant_regions() {
+        // arrange
+        let points = grid(40);
+        let hierarchy = LevelOfDetail::new(points.clone(), 4);
+        let exact = visible_points_from_neighbours(1, 45, 60, &points).len();
+
+        // act
+        let outcome = hierarchy.visible_points(1, 45, 60, 0.5);
+
+        // assert
+        assert!(!outcome.aggregates.is_empty());
+        assert!(outcome.points.len() < exact);
+        assert_eq!(outcome.estimated_count(), exact);
+    }
+}
@@ -0,0 +1,48 @@
+use crate::domain::{point::euclidean_distance, viewshed::EARTH_RADIUS};
+
+/// Distance between point coordinates, for indexes such as [`BallTree`]
+/// which work with any metric.  Distances must never be negative, must be
+/// symmetric, and must obey the triangle inequality, or queries may miss
+/// points.  Closures taking two coordinate pairs are metrics too.
+///
+/// [`BallTree`]: crate::domain::BallTree
+pub trait Metric {
+    fn distance(&self, a: (i32, i32), b: (i32, i32)) -> f64;
+}
+
+impl<F: Fn((i32, i32), (i32, i32)) -> f64> Metric for F {
+    fn distance(&self, a: (i32, i32), b: (i32, i32)) -> f64 {
+        self(a, b)
+    }
+}
+
+/// Straight line distance, as used by visibility queries
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Euclidean;
+
+impl Metric for Euclidean {
+    fn distance(&self, a: (i32, i32), b: (i32, i32)) -> f64 {
+        euclidean_distance(a, b)
+    }
+}
+
+/// Great circle distance, in metres, for coordinates holding longitude, as x,
+/// and latitude, as y, each in degrees multiplied by `units_per_degree`, so
+/// `1_000_000.0` for microdegrees
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Haversine {
+    pub units_per_degree: f64,
+}
+
+impl Metric for Haversine {
+    fn distance(&self, (x_1, y_1): (i32, i32), (x_2, y_2): (i32, i32)) -> f64 {
+        let radians = |value: i32| (f64::from(value) / self.units_per_degree).to_radians();
+        let (longitude_1, latitude_1, longitude_2, latitude_2) =
+            (radians(x_1), radians(y_1), radians(x_2), radians(y_2));
+        let a = ((latitude_2 - latitude_1) / 2.0).sin().powi(2)
+            + latitude_1.cos()
+                * latitude_2.cos()
+                * ((longitude_2 - longitude_1) / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS * a.sqrt().min(1.0).asin()
+    }
+}
@@ -1,3 +1,194 @@
+#[cfg(feature = "animation")]
+mod animation;
+mod ball_tree;
+mod band;
+mod batch;
+mod benchmark;
+mod borrowed;
+mod cache;
+mod cluster;
+mod coverage;
+mod dedup;
+mod delaunay;
+mod diff;
+mod downsample;
+mod engine;
+mod facing;
+#[cfg(feature = "fast-json")]
+mod fast_json;
+mod flatgeobuf;
+mod geojson;
+mod geometry;
+#[cfg(feature = "geotiff")]
+mod geotiff;
+mod gpx;
+mod graph;
+mod grid_index;
+mod heatmap;
+mod histogram;
+mod hnsw;
+mod ids;
+mod index_selection;
+pub mod invariants;
+mod kml;
+#[cfg(feature = "las")]
+mod las;
+#[cfg(feature = "stream")]
+mod live;
+#[cfg(feature = "async")]
+mod loading;
+mod lod;
+mod metric;
+mod motion;
+#[cfg(feature = "mqtt")]
+mod mqtt;
+mod multi;
+mod ndjson;
+mod observed;
+mod occlusion;
+mod optimise;
+mod origin;
+#[cfg(feature = "parquet")]
+mod parquet;
+mod partition;
+mod path_loss;
+mod ply;
 mod point;
+mod positions;
+#[cfg(feature = "postgres")]
+mod postgres;
+mod protobuf;
+mod scene;
+mod sector;
+mod sensor;
+mod sets;
+#[cfg(feature = "shapefile")]
+mod shapefile;
+mod sighting;
+mod simulation;
+mod space;
+mod spatial_index;
+mod sqlite;
+mod summary;
+#[cfg(test)]
+mod test_support;
+mod vertical;
+mod viewshed;
+mod voronoi;
 
-pub use point::visible_points;
+#[cfg(feature = "animation")]
+pub use animation::{encode_gif, encode_mp4, render_frames, Raster, PALETTE};
+pub use ball_tree::BallTree;
+pub use band::{points_in_bearing_band, BearingBand};
+pub use batch::{
+    answer_query_line, parse_queries, parse_queries_file, parse_query_line, run_queries,
+    run_queries_cancellable, Query, QueryResult,
+};
+pub use benchmark::{benchmark_indexes, BackendTiming, IndexBenchmark};
+pub use borrowed::{parse_borrowed_points, BorrowedPoint};
+pub use cache::{
+    cached_points_file, cached_points_visibility_graph, cached_visibility_graph,
+    load_visibility_graph, points_from_bytes, points_to_bytes, save_visibility_graph,
+    POINTS_CACHE_MAGIC, POINTS_CACHE_VERSION,
+};
+pub use cluster::{dbscan, kmeans, ClusterLabel, KMeans};
+pub use coverage::{blind_points, hidden_points, sentinel_points};
+pub use dedup::{deduplicate_points, DuplicateStrategy};
+pub use delaunay::{delaunay_triangulation, delaunay_visible_points, Triangulation};
+pub use diff::{
+    compare_visibility, diff_neighbourhoods, MovedPoint, NeighbourhoodDiff, VisibilityChange,
+};
+pub use downsample::{downsample_points, Downsampled};
+pub use engine::{CacheStats, VisibilityEngine};
+pub use facing::{face_points, Facing};
+pub use flatgeobuf::parse_points_flatgeobuf;
+pub use geojson::{geojson_layers, GeoJsonLayers};
+pub use geometry::{convex_hull, polygon_area, BoundingBox, ConvexHull};
+#[cfg(feature = "geotiff")]
+pub use geotiff::{parse_dem_file, Dem};
+pub use gpx::parse_points_gpx;
+pub use graph::{
+    reachable_points, visibility_graph, visibility_graph_cancellable,
+    visibility_graph_with_progress, VisibilityGraph, BITSET_MAGIC, BITSET_VERSION, GRAPH_MAGIC,
+    GRAPH_VERSION,
+};
+pub use grid_index::GridIndex;
+pub use heatmap::{
+    visibility_heatmap, visibility_heatmap_cancellable, visibility_heatmap_with_progress, Heatmap,
+    MAXIMUM_HEATMAP_CELLS,
+};
+pub use histogram::{distance_histogram, DistanceHistogram};
+pub use hnsw::{HnswIndex, HnswParameters};
+pub use ids::IdentifiedNeighbourhood;
+pub use index_selection::{choose_index, Workload};
+pub use kml::kml_document;
+#[cfg(feature = "las")]
+pub use las::parse_points_las;
+#[cfg(feature = "stream")]
+pub use live::{
+    monitor, parse_update, JsonLinesSource, LiveNeighbourhood, PointUpdate, UpdateSource,
+};
+#[cfg(feature = "async")]
+pub use loading::{parse_points_file_async, Loading};
+pub use lod::{ApproximateVisibility, LevelOfDetail, SuperPoint};
+pub use metric::{Euclidean, Haversine, Metric};
+pub use motion::{contacts, parse_moving_points, Contact, MovingPoint};
+#[cfg(feature = "mqtt")]
+pub use mqtt::MqttSource;
+pub use multi::{visible_from_points, MultiOriginVisibility, OriginCombination};
+pub use ndjson::{parse_points_file_parallel, parse_points_ndjson, parse_points_ndjson_parallel};
+pub use observed::{
+    fresh_points, observed_cutoff, parse_observed_points, unix_time_now, ObservedPoint,
+};
+pub use occlusion::{parse_solid_points, point_heights, unoccluded_points, SolidPoint};
+pub use optimise::{
+    minimum_radius_for_neighbours, optimal_heading, optimal_heading_by_weight, point_weights,
+    OptimalHeading,
+};
+pub use origin::{visible_points_from_origin, Origin};
+#[cfg(feature = "parquet")]
+pub use parquet::parse_points_parquet;
+pub use partition::Partition;
+pub use path_loss::{free_space_path_loss, PathLoss};
+pub use ply::parse_points_ply;
+pub use point::{
+    can_see, parse_points, parse_points_file, parse_points_file_with_progress, parse_points_in,
+    parse_points_with_fields, parse_points_with_query, parse_records_file,
+    parse_records_file_with_progress, parse_records_in, resolve_direction, resolve_directions,
+    visible_neighbours, visible_points, visible_points_from_neighbours, visible_points_into,
+    Direction, InputPoint, Point,
+};
+pub use positions::{
+    heading_from_vector, heading_vector, visible_positions, visible_positions_f32,
+    visible_positions_f32_into, visible_positions_facing, visible_positions_i32,
+};
+#[cfg(feature = "postgres")]
+pub use postgres::{
+    load_points_postgres, load_points_postgres_async, PendingPoints, DEFAULT_POSTGRES_QUERY,
+};
+pub use protobuf::parse_points_protobuf;
+pub use scene::{scene_glb, scene_obj};
+pub use sector::{angular_gaps, nearest_in_buckets, sector_coverage, sector_outline};
+pub use sensor::{
+    find_sensor_point, observing_sensors, parse_sensor_points, visible_points_from_sensor,
+    SensorPoint,
+};
+pub use sets::{
+    combine_visible_sets, parse_visible_sets, parse_visible_sets_file, SetOperation, VisibleSet,
+};
+#[cfg(feature = "shapefile")]
+pub use shapefile::{parse_points_shapefile, DEFAULT_DBF_FIELDS};
+pub use sighting::{relative_bearing, sort_by_sweep, visible_sightings, Sighting};
+pub use simulation::{simulation_frame, Frame};
+pub use space::{
+    parse_space_points, visible_space_points, Orientation, SensorFrame, SpacePoint, Vector,
+    ViewCone,
+};
+pub use spatial_index::{IndexBackend, LinearScan, SpatialIndex};
+pub use sqlite::{parse_points_sqlite, parse_points_sqlite_file, DEFAULT_SQLITE_QUERY};
+pub use summary::{summary_csv, visibility_summary, PointSummary};
+pub use vertical::{elevation_angle, vertically_visible_points};
+pub use viewshed::{
+    clear_sightlines, Clearance, Profile, Sightline, EARTH_RADIUS, STANDARD_REFRACTION,
+};
+pub use voronoi::{voronoi_cells, VoronoiCell};
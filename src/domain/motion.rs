@@ -0,0 +1,191 @@
+use crate::{
+    domain::point::{parse_input_points, sees, InputPoint, Point},
+    utilities::{AppError, Span},
+};
+
+/// Point with its velocity, as x and y components in units per second.  Points
+/// whose record gave no velocity are taken to be stationary.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MovingPoint {
+    pub point: Point,
+    pub velocity: (f64, f64),
+}
+
+impl From<InputPoint> for MovingPoint {
+    fn from(input: InputPoint) -> Self {
+        let velocity = (input.vx.unwrap_or(0.0), input.vy.unwrap_or(0.0));
+        MovingPoint {
+            point: Point::from(input),
+            velocity,
+        }
+    }
+}
+
+impl MovingPoint {
+    /// Where the point will be after `seconds`, keeping its velocity, with
+    /// coordinates rounded to the nearest whole unit
+    pub fn at(&self, seconds: f64) -> Point {
+        let (x, y) = self.point.coordinates;
+        Point {
+            coordinates: (
+                (f64::from(x) + self.velocity.0 * seconds).round() as i32,
+                (f64::from(y) + self.velocity.1 * seconds).round() as i32,
+            ),
+            ..self.point
+        }
+    }
+}
+
+/// How a visible neighbour is moving relative to the point seeing it
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Contact {
+    /// Number of the neighbour
+    pub number: u32,
+
+    /// Current distance to the neighbour
+    pub distance: f64,
+
+    /// Rate the distance is shrinking, in units per second, negative while
+    /// the points move apart
+    pub closing_speed: f64,
+
+    /// Seconds until the points are closest, or `None` if they are not
+    /// getting closer
+    pub time_to_closest: Option<f64>,
+
+    /// Distance between the points when they are closest, assuming both keep
+    /// their current velocities
+    pub closest_distance: f64,
+}
+
+/// Parse a points JSON string, as for [`parse_points`](crate::domain::parse_points),
+/// keeping each point’s optional `vx` and `vy` velocity components
+pub fn parse_moving_points(json: &str) -> Result<Vec<MovingPoint>, AppError> {
+    let _span = Span::enter("parse_moving_points", || format!("{} bytes", json.len()));
+    let points = parse_input_points(json)?;
+    Ok(points.into_iter().map(MovingPoint::from).collect())
+}
+
+/// Closing speed and closest approach of `neighbour`, seen from `origin`
+fn contact(origin: &MovingPoint, neighbour: &MovingPoint) -> Contact {
+    let offset = (
+        f64::from(neighbour.point.coordinates.0 - origin.point.coordinates.0),
+        f64::from(neighbour.point.coordinates.1 - origin.point.coordinates.1),
+    );
+    let velocity = (
+        neighbour.velocity.0 - origin.velocity.0,
+        neighbour.velocity.1 - origin.velocity.1,
+    );
+    let distance = offset.0.hypot(offset.1);
+    let approach = 0.0 - (offset.0 * velocity.0 + offset.1 * velocity.1);
+    let speed_squared = velocity.0 * velocity.0 + velocity.1 * velocity.1;
+    let time_to_closest = if approach > 0.0 && speed_squared > 0.0 {
+        Some(approach / speed_squared)
+    } else {
+        None
+    };
+    let closest_distance = match time_to_closest {
+        Some(time) => (offset.0 + velocity.0 * time).hypot(offset.1 + velocity.1 * time),
+        None => distance,
+    };
+    Contact {
+        number: neighbour.point.number,
+        distance,
+        closing_speed: if distance > 0.0 {
+            approach / distance
+        } else {
+            0.0
+        },
+        time_to_closest,
+        closest_distance,
+    }
+}
+
+/// Find points of `neighbourhood` visible from the point numbered
+/// `point_number`, as for [`visible_points_from_neighbours`](crate::domain::visible_points_from_neighbours),
+/// with the speed each is closing at, and when and how near its closest
+/// approach will be, assuming velocities stay constant.  Contacts are
+/// returned in ascending number order, and none are returned if no point
+/// matches `point_number`.
+pub fn contacts(
+    point_number: u32,
+    half_arc_central_angle: u32,
+    arc_radius: u32,
+    neighbourhood: &[MovingPoint],
+) -> Vec<Contact> {
+    let _span = Span::enter("contacts", || {
+        format!("point {point_number} of {}", neighbourhood.len())
+    });
+    let origin = match neighbourhood
+        .iter()
+        .find(|MovingPoint { point, .. }| point.number == point_number)
+    {
+        Some(value) => value,
+        None => return vec![],
+    };
+    let mut result: Vec<Contact> = neighbourhood
+        .iter()
+        .filter(|neighbour| {
+            sees(
+                &origin.point,
+                &neighbour.point,
+                half_arc_central_angle,
+                arc_radius,
+            )
+        })
+        .map(|neighbour| contact(origin, neighbour))
+        .collect();
+    result.sort_by_key(|Contact { number, .. }| *number);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{contacts, parse_moving_points, Contact};
+
+    #[test]
+    fn contacts_gives_closest_approach_of_visible_neighbours() {
+        // arrange
+        let json = r#"{"points": [
+            {"x": 0, "y": 0, "number": 1, "direction": "North", "vy": 1},
+            {"x": 0, "y": 10, "number": 2, "direction": "South", "vy": -1},
+            {"x": 3, "y": 4, "number": 3, "direction": "West"},
+            {"x": -3, "y": 4, "number": 4, "direction": "East", "vy": 2},
+            {"x": 0, "y": -5, "number": 5, "direction": "North", "vy": 3}
+        ]}"#;
+        let points = parse_moving_points(json).unwrap();
+
+        // act
+        let outcome = contacts(1, 45, 20, &points);
+
+        // assert
+        assert_eq!(points[2].velocity, (0.0, 0.0));
+        assert_eq!(
+            outcome,
+            vec![
+                Contact {
+                    number: 2,
+                    distance: 10.0,
+                    closing_speed: 2.0,
+                    time_to_closest: Some(5.0),
+                    closest_distance: 0.0
+                },
+                Contact {
+                    number: 3,
+                    distance: 5.0,
+                    closing_speed: 0.8,
+                    time_to_closest: Some(4.0),
+                    closest_distance: 3.0
+                },
+                Contact {
+                    number: 4,
+                    distance: 5.0,
+                    closing_speed: -0.8,
+                    time_to_closest: None,
+                    closest_distance: 5.0
+                },
+            ]
+        );
+        assert!(contacts(9, 45, 20, &points).is_empty());
+    }
+}
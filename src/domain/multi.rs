@@ -0,0 +1,167 @@
+use crate::{
+    domain::point::{sees, Point},
+    utilities::Span,
+};
+use std::str::FromStr;
+
+/// How [`visible_from_points`] results for several origins are combined
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum OriginCombination {
+    /// Points visible from any origin
+    Union,
+
+    /// Points visible from every origin
+    Intersection,
+
+    /// Points visible from each origin, listed separately
+    Each,
+}
+
+impl FromStr for OriginCombination {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "union" => Ok(OriginCombination::Union),
+            "intersection" => Ok(OriginCombination::Intersection),
+            "each" => Ok(OriginCombination::Each),
+            _ => Err(format!(
+                "unknown combination `{value}`, expected `union`, `intersection` or `each`"
+            )),
+        }
+    }
+}
+
+/// Points visible from each of several origin points
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MultiOriginVisibility {
+    /// Numbers of the origin points, in the order requested
+    pub origins: Vec<u32>,
+
+    /// Numbers of points visible from each origin, in ascending order, and
+    /// empty for an origin matching no point
+    pub visible: Vec<Vec<u32>>,
+}
+
+impl MultiOriginVisibility {
+    /// Numbers of points visible from any origin, in ascending order
+    pub fn union(&self) -> Vec<u32> {
+        let mut result: Vec<u32> = self.visible.iter().flatten().copied().collect();
+        result.sort_unstable();
+        result.dedup();
+        result
+    }
+
+    /// Numbers of points visible from every origin, in ascending order.  No
+    /// origin sees itself, so origins are never included.
+    pub fn intersection(&self) -> Vec<u32> {
+        let (first, rest) = match self.visible.split_first() {
+            Some(value) => value,
+            None => return vec![],
+        };
+        first
+            .iter()
+            .filter(|number| {
+                rest.iter()
+                    .all(|visible| visible.binary_search(number).is_ok())
+            })
+            .copied()
+            .collect()
+    }
+}
+
+/// Find points visible from each point numbered in `origins`, as for
+/// [`visible_points_from_neighbours`](crate::domain::visible_points_from_neighbours),
+/// in one pass over `neighbourhood`, so the results can be combined, for
+/// example to find targets covered by both of two cameras.
+pub fn visible_from_points(
+    origins: &[u32],
+    half_arc_central_angle: u32,
+    arc_radius: u32,
+    neighbourhood: &[Point],
+) -> MultiOriginVisibility {
+    let _span = Span::enter("visible_from_points", || {
+        format!("{} origins of {}", origins.len(), neighbourhood.len())
+    });
+    let origin_points: Vec<Option<&Point>> = origins
+        .iter()
+        .map(|origin| {
+            neighbourhood
+                .iter()
+                .find(|Point { number, .. }| number == origin)
+        })
+        .collect();
+    let mut visible = vec![vec![]; origins.len()];
+    for neighbour in neighbourhood {
+        for (origin, visible) in origin_points.iter().zip(visible.iter_mut()) {
+            if let Some(point) = origin {
+                if sees(point, neighbour, half_arc_central_angle, arc_radius) {
+                    visible.push(neighbour.number);
+                }
+            }
+        }
+    }
+    for numbers in &mut visible {
+        numbers.sort_unstable();
+    }
+    MultiOriginVisibility {
+        origins: origins.to_vec(),
+        visible,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{visible_from_points, OriginCombination};
+    use crate::domain::{
+        parse_points_file, test_support::north_point, visible_points_from_neighbours, Point,
+    };
+
+    #[test]
+    fn visible_from_points_combines_origins() {
+        // arrange
+        let points = parse_points_file("./fixtures/valid_points.json").unwrap();
+        let origins = [20, 7, 999];
+
+        // act
+        let outcome = visible_from_points(&origins, 180, 10, &points);
+
+        // assert
+        let expected: Vec<Vec<u32>> = origins
+            .iter()
+            .map(|origin| {
+                visible_points_from_neighbours(*origin, 180, 10, &points)
+                    .iter()
+                    .map(|Point { number, .. }| *number)
+                    .collect()
+            })
+            .collect();
+        assert_eq!(outcome.visible, expected);
+        assert_eq!(outcome.origins, origins);
+        assert!(outcome.intersection().is_empty());
+        let both = visible_from_points(&origins[..2], 180, 10, &points);
+        for number in both.intersection() {
+            assert!(both.visible.iter().all(|visible| visible.contains(&number)));
+        }
+        let union = both.union();
+        assert!(union.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(
+            union.len(),
+            both.visible[0].len() + both.visible[1].len() - both.intersection().len()
+        );
+        let cameras = [
+            north_point(1, (0, 0)),
+            north_point(2, (4, 0)),
+            north_point(3, (2, 5)),
+            north_point(4, (-3, 5)),
+        ];
+        let covered = visible_from_points(&[1, 2], 45, 10, &cameras);
+        assert_eq!(covered.visible, vec![vec![3, 4], vec![3]]);
+        assert_eq!(
+            (covered.intersection(), covered.union()),
+            (vec![3], vec![3, 4])
+        );
+        assert_eq!("each".parse(), Ok(OriginCombination::Each));
+        assert!("both".parse::<OriginCombination>().is_err());
+    }
+}
@@ -0,0 +1,355 @@
+use crate::{
+    domain::point::{parse_points_file, read_points_file, resolve_directions, InputPoint, Point},
+    utilities::{AppError, Span},
+};
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, Read},
+    panic,
+    path::Path,
+    sync::Arc,
+    thread,
+};
+
+/// Capacity of the buffer used when streaming an NDJSON file
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/// Returns true if `path` has an `.ndjson` or `.jsonl` extension, and so is
+/// read as newline delimited JSON
+pub(crate) fn is_ndjson_path(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|value| value.to_str()),
+        Some("ndjson") | Some("jsonl")
+    )
+}
+
+/// Reader passing reads through to `inner`, calling `progress` with the bytes
+/// read so far, and `total`, after each read
+struct ProgressReader<R, F> {
+    inner: R,
+    read: usize,
+    total: usize,
+    progress: F,
+}
+
+impl<R: Read, F: FnMut(usize, usize)> Read for ProgressReader<R, F> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let length = self.inner.read(buf)?;
+        if length > 0 {
+            self.read += length;
+            (self.progress)(self.read, self.total.max(self.read));
+        }
+        Ok(length)
+    }
+}
+
+/// Parse newline delimited JSON points from `reader`, one object per line, with
+/// the same fields as entries of a points JSON file’s `points` array.  Lines
+/// are parsed as they are read, so the input is never held in memory all at
+/// once.  Blank lines are skipped.  Directions towards other points are
+/// resolved once every line is read, see [`resolve_directions`].
+///
+/// [`resolve_directions`]: crate::domain::resolve_directions
+pub fn parse_points_ndjson<R: BufRead>(reader: R) -> Result<Vec<Point>, AppError> {
+    let _span = Span::enter("parse_points_ndjson", String::new);
+    let mut result = collect_points_ndjson(reader)?;
+    resolve_directions(&mut result)?;
+    Ok(result)
+}
+
+/// Parse newline delimited JSON points from `reader`, as for
+/// [`parse_points_ndjson`], leaving directions towards other points
+/// unresolved, since they may lie in another chunk
+fn collect_points_ndjson<R: BufRead>(reader: R) -> Result<Vec<Point>, AppError> {
+    let mut result = vec![];
+    for_each_point_ndjson(reader, |point| {
+        result.push(point);
+        Ok(())
+    })?;
+    Ok(result)
+}
+
+/// Parse newline delimited JSON points from `reader`, as for
+/// [`parse_points_ndjson`], passing each to `visit` as it is read, instead of
+/// collecting them.  Stops at the first error, from parsing or from `visit`.
+pub(crate) fn for_each_point_ndjson<R, F>(reader: R, mut visit: F) -> Result<(), AppError>
+where
+    R: BufRead,
+    F: FnMut(Point) -> Result<(), AppError>,
+{
+    let mut reader = reader;
+    let mut line = String::new();
+    let mut line_number = 0;
+    loop {
+        line.clear();
+        line_number += 1;
+        let length = reader
+            .read_line(&mut line)
+            .map_err(|error| AppError::InvalidFileError {
+                expected_path: String::from("NDJSON input"),
+                source: error,
+            })?;
+        if length == 0 {
+            return Ok(());
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        #[cfg(feature = "fast-json")]
+        if let Some(point) = crate::domain::fast_json::parse_point_line(&line) {
+            visit(point)?;
+            continue;
+        }
+        let point: InputPoint =
+            serde_json::from_str(&line).map_err(|error| AppError::NDJSONParseError {
+                line: line_number,
+                source: error,
+            })?;
+        visit(point.into())?;
+    }
+}
+
+/// Parse newline delimited JSON points, as for [`parse_points_ndjson`], on
+/// `threads` threads.  `input` is split into chunks at line breaks, each
+/// parsed on its own thread, and the results are joined in input order, so
+/// points come back in the same order as from [`parse_points_ndjson`].  If
+/// more than one chunk has an error, the error from the earliest line is
+/// returned.
+pub fn parse_points_ndjson_parallel(input: &str, threads: usize) -> Result<Vec<Point>, AppError> {
+    parse_shared_ndjson(Arc::new(input.to_string()), threads)
+}
+
+/// Parse `input` as for [`parse_points_ndjson_parallel`], sharing it between
+/// threads without copying
+fn parse_shared_ndjson(input: Arc<String>, threads: usize) -> Result<Vec<Point>, AppError> {
+    let threads = threads.max(1);
+    let _span = Span::enter("parse_points_ndjson_parallel", || {
+        format!("{} bytes on {threads} threads", input.len())
+    });
+
+    // line breaks before each chunk, and its byte range, ending each chunk
+    // after a line break, so no line is split
+    let mut chunks = vec![];
+    let mut start = 0;
+    let mut lines_before = 0;
+    let chunk_size = input.len() / threads + 1;
+    while start < input.len() {
+        let end = match input.as_bytes()[(start + chunk_size).min(input.len())..]
+            .iter()
+            .position(|byte| *byte == b'\n')
+        {
+            Some(offset) => (start + chunk_size + offset + 1).min(input.len()),
+            None => input.len(),
+        };
+        chunks.push((lines_before, start..end));
+        lines_before += input.as_bytes()[start..end]
+            .iter()
+            .filter(|byte| **byte == b'\n')
+            .count();
+        start = end;
+    }
+
+    let handles: Vec<_> = chunks
+        .into_iter()
+        .map(|(lines_before, range)| {
+            let input = Arc::clone(&input);
+            thread::spawn(
+                move || match collect_points_ndjson(input[range].as_bytes()) {
+                    Err(AppError::NDJSONParseError { line, source }) => {
+                        Err(AppError::NDJSONParseError {
+                            line: line + lines_before,
+                            source,
+                        })
+                    }
+                    result => result,
+                },
+            )
+        })
+        .collect();
+    let parsed: Vec<Result<Vec<Point>, AppError>> = handles
+        .into_iter()
+        .map(|handle| match handle.join() {
+            Ok(value) => value,
+            Err(payload) => panic::resume_unwind(payload),
+        })
+        .collect();
+    let mut result = Vec::with_capacity(
+        parsed
+            .iter()
+            .map(|chunk| chunk.as_ref().map_or(0, Vec::len))
+            .sum(),
+    );
+    for chunk in parsed {
+        result.extend(chunk?);
+    }
+    resolve_directions(&mut result)?;
+    Ok(result)
+}
+
+/// Read the points file at `path`, parsing NDJSON files in parallel, as for
+/// [`parse_points_ndjson_parallel`].  The whole file is read into memory
+/// first, unlike [`parse_points_file`], which streams NDJSON files, so this
+/// suits batch jobs where parsing time, rather than memory, is the limit.
+/// Other formats are parsed as for [`parse_points_file`].
+pub fn parse_points_file_parallel<P: AsRef<Path>>(
+    path: P,
+    threads: usize,
+) -> Result<Vec<Point>, AppError> {
+    let path = path.as_ref();
+    if !is_ndjson_path(path) {
+        return parse_points_file(path);
+    }
+    parse_shared_ndjson(Arc::new(read_points_file(path)?), threads)
+}
+
+/// Pass each point of the points file at `path` to `visit`.  NDJSON files are
+/// streamed, one line at a time, while other formats are parsed in full first,
+/// as for [`parse_points_file`].
+pub(crate) fn for_each_point_in_file<F>(path: &Path, mut visit: F) -> Result<(), AppError>
+where
+    F: FnMut(Point) -> Result<(), AppError>,
+{
+    if !is_ndjson_path(path) {
+        return parse_points_file(path)?.into_iter().try_for_each(visit);
+    }
+    let invalid_file_error = |error| AppError::InvalidFileError {
+        expected_path: path.display().to_string(),
+        source: error,
+    };
+    let file = File::open(path).map_err(invalid_file_error)?;
+    match for_each_point_ndjson(BufReader::with_capacity(BUFFER_SIZE, file), &mut visit) {
+        Err(AppError::InvalidFileError { source, .. }) => Err(invalid_file_error(source)),
+        result => result,
+    }
+}
+
+/// Stream and parse the NDJSON points file at `path`, as for
+/// [`parse_points_ndjson`], calling `progress` with the bytes read so far, and
+/// the file size
+pub(crate) fn parse_points_ndjson_file<F: FnMut(usize, usize)>(
+    path: &Path,
+    progress: F,
+) -> Result<Vec<Point>, AppError> {
+    let invalid_file_error = |error| AppError::InvalidFileError {
+        expected_path: path.display().to_string(),
+        source: error,
+    };
+    let file = File::open(path).map_err(invalid_file_error)?;
+    let total = file
+        .metadata()
+        .map(|value| value.len() as usize)
+        .unwrap_or(0);
+    let reader = ProgressReader {
+        inner: file,
+        read: 0,
+        total,
+        progress,
+    };
+    match parse_points_ndjson(BufReader::with_capacity(BUFFER_SIZE, reader)) {
+        // report read errors against the file path, rather than the stream
+        Err(AppError::InvalidFileError { source, .. }) => Err(invalid_file_error(source)),
+        result => result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_ndjson_path, parse_points_ndjson, parse_points_ndjson_parallel};
+    use crate::domain::{parse_points_file, Direction, Point};
+    use std::path::Path;
+
+    #[test]
+    fn parse_points_ndjson_gives_expected_result() {
+        // arrange
+        let input = "{\"x\": 1, \"y\": 2, \"number\": 1, \"direction\": \"East\"}\n\n{\"x\": -3, \"y\": 4, \"number\": 2, \"direction\": \"South\"}";
+
+        // act
+        let outcome = parse_points_ndjson(input.as_bytes()).unwrap();
+
+        // assert
+        assert_eq!(
+            outcome,
+            vec![
+                Point {
+                    coordinates: (1, 2),
+                    number: 1,
+                    direction: Direction::East
+                },
+                Point {
+                    coordinates: (-3, 4),
+                    number: 2,
+                    direction: Direction::South
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_points_ndjson_handles_invalid_input() {
+        // arrange
+        let input = "{\"x\": 1, \"y\": 2, \"number\": 1, \"direction\": \"East\"}\n{\"x\": 1}\n";
+
+        // act
+        let outcome = parse_points_ndjson(input.as_bytes())
+            .unwrap_err()
+            .to_string();
+
+        // assert
+        assert_eq!(
+            outcome,
+            "Error parsing NDJSON line 2: missing field `y` at line 1 column 8"
+        );
+    }
+
+    #[test]
+    fn parse_points_file_reads_ndjson_files() {
+        // arrange
+        let path = Path::new("./fixtures/valid_points.ndjson");
+
+        // act
+        let outcome = parse_points_file(path).unwrap();
+
+        // assert
+        assert!(is_ndjson_path(path));
+        assert_eq!(
+            outcome,
+            parse_points_file("./fixtures/valid_points.json").unwrap()
+        );
+    }
+
+    #[test]
+    fn parse_points_ndjson_parallel_matches_serial_parsing() {
+        // arrange
+        let input: String = (0..50)
+            .map(|val| {
+                format!(
+                    "{{\"x\": {val}, \"y\": {}, \"number\": {}, \"direction\": \"West\"}}\n",
+                    -val,
+                    val + 1
+                )
+            })
+            .collect();
+        let invalid = input.replacen("\"y\": -40,", "", 1);
+
+        // act
+        let outcome: Vec<_> = [0, 1, 3, 7, 100]
+            .iter()
+            .map(|threads| parse_points_ndjson_parallel(&input, *threads).unwrap())
+            .collect();
+
+        // assert
+        let expected = parse_points_ndjson(input.as_bytes()).unwrap();
+        for points in outcome {
+            assert_eq!(points, expected);
+        }
+        assert_eq!(
+            parse_points_ndjson_parallel(&invalid, 4)
+                .unwrap_err()
+                .to_string(),
+            parse_points_ndjson(invalid.as_bytes())
+                .unwrap_err()
+                .to_string()
+        );
+        assert_eq!(parse_points_ndjson_parallel("", 4).unwrap(), vec![]);
+    }
+}
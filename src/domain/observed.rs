@@ -0,0 +1,106 @@
+use crate::{
+    domain::point::{parse_input_points, InputPoint, Point},
+    utilities::{AppError, Span},
+};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Point with the time it was observed, as seconds since the Unix epoch, if
+/// its record gave an `observed_at` time
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ObservedPoint {
+    pub point: Point,
+    pub observed_at: Option<u64>,
+}
+
+impl From<InputPoint> for ObservedPoint {
+    fn from(input: InputPoint) -> Self {
+        let observed_at = input.observed_at;
+        ObservedPoint {
+            point: Point::from(input),
+            observed_at,
+        }
+    }
+}
+
+impl ObservedPoint {
+    /// True unless the point was observed before `cutoff`, in seconds since
+    /// the Unix epoch.  Points with no observation time are never stale.
+    pub fn is_fresh(&self, cutoff: u64) -> bool {
+        self.observed_at.map_or(true, |value| value >= cutoff)
+    }
+}
+
+/// Current time, in seconds since the Unix epoch
+pub fn unix_time_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |value| value.as_secs())
+}
+
+/// Earliest observation time, in seconds since the Unix epoch, of points no
+/// older than `max_age` seconds now
+pub fn observed_cutoff(max_age: u64) -> u64 {
+    unix_time_now().saturating_sub(max_age)
+}
+
+/// Parse a points JSON string, as for [`parse_points`](crate::domain::parse_points),
+/// keeping each point’s optional `observed_at` time, in seconds since the
+/// Unix epoch
+pub fn parse_observed_points(json: &str) -> Result<Vec<ObservedPoint>, AppError> {
+    let _span = Span::enter("parse_observed_points", || format!("{} bytes", json.len()));
+    let points = parse_input_points(json)?;
+    Ok(points.into_iter().map(ObservedPoint::from).collect())
+}
+
+/// Points of `observed` no older than `max_age` seconds, in their original
+/// order, so stale fixes do not count as visible contacts
+pub fn fresh_points(observed: &[ObservedPoint], max_age: u64) -> Vec<Point> {
+    let cutoff = observed_cutoff(max_age);
+    observed
+        .iter()
+        .filter(|value| value.is_fresh(cutoff))
+        .map(|ObservedPoint { point, .. }| *point)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fresh_points, parse_observed_points, unix_time_now, ObservedPoint};
+    use crate::domain::{parse_points, Direction, Point};
+
+    #[test]
+    fn fresh_points_drops_stale_observations() {
+        // arrange
+        let now = unix_time_now();
+        let json = format!(
+            r#"{{"points": [
+                {{"x": 0, "y": 0, "number": 1, "direction": "North", "observed_at": {}}},
+                {{"x": 0, "y": 5, "number": 2, "direction": "South", "observed_at": {}}},
+                {{"x": 3, "y": 4, "number": 3, "direction": "East"}}
+            ]}}"#,
+            now - 10,
+            now - 3_600
+        );
+
+        // act
+        let observed = parse_observed_points(&json).unwrap();
+        let outcome = fresh_points(&observed, 60);
+
+        // assert
+        assert_eq!(
+            observed[0],
+            ObservedPoint {
+                point: Point {
+                    coordinates: (0, 0),
+                    number: 1,
+                    direction: Direction::North
+                },
+                observed_at: Some(now - 10)
+            }
+        );
+        assert_eq!(observed[2].observed_at, None);
+        let numbers: Vec<u32> = outcome.iter().map(|Point { number, .. }| *number).collect();
+        assert_eq!(numbers, vec![1, 3]);
+        assert_eq!(fresh_points(&observed, 7_200), parse_points(&json).unwrap());
+    }
+}
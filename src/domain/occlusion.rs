@@ -0,0 +1,187 @@
+use crate::{
+    domain::{
+        origin::Origin,
+        point::{
+            is_json_points_path, parse_input_points, read_input_points_file, InputPoint, Point,
+        },
+        viewshed::Sightline,
+    },
+    utilities::{AppError, Span},
+};
+use std::{collections::HashMap, path::Path};
+
+/// Point with the height of the thing it stands for, such as a bollard or a
+/// mast.  Points whose record gave no height are taken to stand at ground
+/// level.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SolidPoint {
+    pub point: Point,
+    pub height: f64,
+}
+
+impl From<InputPoint> for SolidPoint {
+    fn from(input: InputPoint) -> Self {
+        let height = input.height.unwrap_or(0.0);
+        SolidPoint {
+            point: Point::from(input),
+            height,
+        }
+    }
+}
+
+/// Parse a points JSON string, as for [`parse_points`](crate::domain::parse_points),
+/// keeping each point’s optional `height`
+pub fn parse_solid_points(json: &str) -> Result<Vec<SolidPoint>, AppError> {
+    let _span = Span::enter("parse_solid_points", || format!("{} bytes", json.len()));
+    let points = parse_input_points(json)?;
+    Ok(points.into_iter().map(SolidPoint::from).collect())
+}
+
+/// Heights of the points in the file at `path`, keyed by number, leaving out
+/// points with no height.  The map is empty unless the file is a JSON or
+/// NDJSON file, since other formats do not carry heights.
+pub fn point_heights<P: AsRef<Path>>(path: P) -> Result<HashMap<u32, f64>, AppError> {
+    let path = path.as_ref();
+    if !is_json_points_path(path) {
+        return Ok(HashMap::new());
+    }
+    Ok(read_input_points_file(path)?
+        .into_iter()
+        .filter_map(|InputPoint { number, height, .. }| Some((number, height?)))
+        .collect())
+}
+
+/// Returns true if a blocker at `blocker`, standing `blocker_height` tall,
+/// with a footprint of radius `radius`, stands in the way of the sightline
+/// from `from`, `eye_height` above the ground, to the top of `to`,
+/// `target_height` tall.  Blockers level with, or beyond, either end never
+/// block.
+fn blocks(
+    (from, eye_height): ((f64, f64), f64),
+    (to, target_height): ((f64, f64), f64),
+    (blocker, blocker_height): ((f64, f64), f64),
+    radius: f64,
+) -> bool {
+    let along = (to.0 - from.0, to.1 - from.1);
+    let length_squared = along.0 * along.0 + along.1 * along.1;
+    if length_squared == 0.0 {
+        return false;
+    }
+    let offset = (blocker.0 - from.0, blocker.1 - from.1);
+    let fraction = (offset.0 * along.0 + offset.1 * along.1) / length_squared;
+    if !(fraction > 0.0 && fraction < 1.0) {
+        return false;
+    }
+    let miss = (offset.0 - fraction * along.0).hypot(offset.1 - fraction * along.1);
+    miss <= radius && blocker_height >= eye_height + fraction * (target_height - eye_height)
+}
+
+/// Points of `visible` whose sightlines from `viewpoint`, standing
+/// `viewpoint_height` tall, are not blocked by other points of
+/// `neighbourhood`, in the same order.  Each point stands as tall as
+/// `heights` gives, or at ground level, with a footprint of radius `radius`,
+/// and a sightline runs to the top of its target, so a short bollard does not
+/// hide a tall mast behind it.  The observer’s eye and the part of each target
+/// to be seen are raised further by the heights `sightline` gives.
+pub fn unoccluded_points<'a>(
+    viewpoint: &Origin,
+    viewpoint_height: f64,
+    visible: Vec<&'a Point>,
+    neighbourhood: &[Point],
+    heights: &HashMap<u32, f64>,
+    radius: f64,
+    sightline: &Sightline,
+) -> Vec<&'a Point> {
+    let _span = Span::enter("unoccluded_points", || {
+        format!("{} of {}", visible.len(), neighbourhood.len())
+    });
+    let position = |point: &Point| {
+        (
+            f64::from(point.coordinates.0),
+            f64::from(point.coordinates.1),
+        )
+    };
+    let height = |point: &Point| heights.get(&point.number).copied().unwrap_or(0.0);
+    let eye_height = viewpoint_height + sightline.observer_height;
+    visible
+        .into_iter()
+        .filter(|target| {
+            !neighbourhood.iter().any(|blocker| {
+                blocker.number != target.number
+                    && blocks(
+                        (viewpoint.coordinates, eye_height),
+                        (position(target), height(target) + sightline.target_height),
+                        (position(blocker), height(blocker)),
+                        radius,
+                    )
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_solid_points, unoccluded_points};
+    use crate::domain::{parse_points, Origin, Point, Sightline};
+    use std::collections::HashMap;
+
+    #[test]
+    fn unoccluded_points_compares_sightline_with_blocker_height() {
+        // arrange
+        let json = r#"{"points": [
+            {"x": 0, "y": 0, "number": 1, "direction": "North", "height": 2},
+            {"x": 0, "y": 10, "number": 2, "direction": "South", "height": 1},
+            {"x": 0, "y": 20, "number": 3, "direction": "South", "height": 30},
+            {"x": 0, "y": 30, "number": 4, "direction": "South"},
+            {"x": 5, "y": 10, "number": 5, "direction": "West", "height": 40}
+        ]}"#;
+        let solids = parse_solid_points(json).unwrap();
+        let points = parse_points(json).unwrap();
+        let heights: HashMap<u32, f64> = solids
+            .iter()
+            .map(|solid| (solid.point.number, solid.height))
+            .collect();
+        let viewpoint = Origin::from(&points[0]);
+        let numbers =
+            |visible: &[&Point]| -> Vec<u32> { visible.iter().map(|point| point.number).collect() };
+
+        // act
+        let outcome = unoccluded_points(
+            &viewpoint,
+            2.0,
+            points[1..].iter().collect(),
+            &points,
+            &heights,
+            0.5,
+            &Sightline::default(),
+        );
+
+        // assert
+        assert_eq!(numbers(&outcome), vec![2, 3, 5]);
+        assert_eq!(solids[3].height, 0.0);
+        let flat = unoccluded_points(
+            &viewpoint,
+            0.0,
+            points[1..].iter().collect(),
+            &points,
+            &HashMap::new(),
+            0.5,
+            &Sightline::default(),
+        );
+        assert_eq!(numbers(&flat), vec![2, 5]);
+        let person = Sightline {
+            target_height: 1.7,
+            ..Sightline::default()
+        };
+        let raised = unoccluded_points(
+            &viewpoint,
+            0.0,
+            points[1..].iter().collect(),
+            &points,
+            &HashMap::new(),
+            0.5,
+            &person,
+        );
+        assert_eq!(numbers(&raised), vec![2, 3, 4, 5]);
+    }
+}
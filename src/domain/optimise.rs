@@ -0,0 +1,469 @@
+use crate::{
+    domain::{
+        ndjson::is_ndjson_path,
+        point::{
+            angular_position, euclidean_distance, inside_sector, is_json_points_path,
+            read_points_file, visible_neighbour, Point,
+        },
+    },
+    utilities::AppError,
+};
+use serde::Deserialize;
+use serde_json::Value;
+use std::{cmp::Ordering, collections::HashMap, f64::consts::PI, path::Path};
+
+/// Heading found to maximise the neighbours visible from a point
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct OptimalHeading {
+    /// Bearing in degrees, measured clockwise from north, from `0` up to `360`
+    pub bearing: f64,
+
+    /// Number, or total weight, of neighbours visible facing `bearing`
+    pub score: f64,
+}
+
+/// Point number and every other field of a point, as found in an input file
+#[derive(Debug, Deserialize)]
+struct WeightedRecord {
+    number: u32,
+
+    #[serde(flatten)]
+    fields: HashMap<String, Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WeightedRecordList {
+    points: Vec<WeightedRecord>,
+}
+
+/// Wrap an angle in radians into the range `0` up to `2 PI`
+fn normalise(angle: f64) -> f64 {
+    let result = angle.rem_euclid(2.0 * PI);
+    if result >= 2.0 * PI {
+        0.0
+    } else {
+        result
+    }
+}
+
+/// Return the heading which maximises the number of `neighbourhood` points
+/// visible from the point identified by `point_number`, within a segment of
+/// radius `arc_radius`, spanning left and right from the heading by
+/// `half_arc_central_angle`.  `half_arc_central_angle` should be in degrees,
+/// and can range from zero to `180` degrees.
+///
+/// `None` is returned if no point matching `point_number` is found in
+/// neighbourhood.
+pub fn optimal_heading(
+    point_number: u32,
+    half_arc_central_angle: u32,
+    arc_radius: u32,
+    neighbourhood: &[Point],
+) -> Option<OptimalHeading> {
+    optimal_heading_by_weight(
+        point_number,
+        half_arc_central_angle,
+        arc_radius,
+        neighbourhood,
+        |_| 1.0,
+    )
+}
+
+/// Return the heading which maximises the total `weight` of `neighbourhood`
+/// points visible from the point identified by `point_number`.  Otherwise
+/// behaves as [`optimal_heading`].
+///
+/// Candidate headings are those where a neighbour enters or leaves the
+/// segment, plus the midpoints between them, so the sweep is exact.  Where
+/// several headings score equally, the first found sweeping clockwise from
+/// north is returned.  If no neighbour is in range, or the segment spans the
+/// full circle, the point’s current direction is returned.
+pub fn optimal_heading_by_weight<F>(
+    point_number: u32,
+    half_arc_central_angle: u32,
+    arc_radius: u32,
+    neighbourhood: &[Point],
+    weight: F,
+) -> Option<OptimalHeading>
+where
+    F: Fn(&Point) -> f64,
+{
+    let point = neighbourhood
+        .iter()
+        .find(|Point { number, .. }| *number == point_number)?;
+    let Point {
+        coordinates: point_coordinates,
+        direction,
+        ..
+    } = point;
+
+    let neighbours: Vec<&Point> = neighbourhood
+        .iter()
+        .filter(
+            |Point {
+                 number,
+                 coordinates,
+                 ..
+             }| {
+                *number != point_number
+                    && euclidean_distance(*point_coordinates, *coordinates) < arc_radius as f64
+            },
+        )
+        .collect();
+
+    // co-located neighbours have no bearing, and are visible whichever way
+    // the point faces, as for `visible_neighbour`
+    let co_located: f64 = neighbours
+        .iter()
+        .filter(|val| val.coordinates == *point_coordinates)
+        .map(|val| weight(val))
+        .sum();
+
+    // bearing and weight of each other neighbour within range
+    let in_range: Vec<(f64, f64)> = neighbours
+        .iter()
+        .filter(|val| val.coordinates != *point_coordinates)
+        .map(|val| {
+            (
+                angular_position(*point_coordinates, val.coordinates),
+                weight(val),
+            )
+        })
+        .collect();
+
+    let half_arc_central_angle_radians = (half_arc_central_angle as f64).to_radians();
+    let score_heading = |heading: f64| -> f64 {
+        co_located
+            + in_range
+                .iter()
+                .filter(|(bearing, _)| {
+                    inside_sector(*bearing, heading, half_arc_central_angle_radians)
+                })
+                .map(|(_, weight)| weight)
+                .sum::<f64>()
+    };
+
+    let current_heading = direction.bearing();
+    if in_range.is_empty() || half_arc_central_angle_radians >= PI {
+        return Some(OptimalHeading {
+            bearing: current_heading.to_degrees(),
+            score: score_heading(current_heading),
+        });
+    }
+
+    // headings at which a neighbour enters or leaves the segment
+    let mut events: Vec<f64> = in_range
+        .iter()
+        .flat_map(|(bearing, _)| {
+            [
+                normalise(bearing - half_arc_central_angle_radians),
+                normalise(bearing + half_arc_central_angle_radians),
+            ]
+        })
+        .collect();
+    events.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+    events.dedup();
+
+    // prefer midpoints, which are clear of segment edges, over the events
+    let midpoints = events.iter().enumerate().map(|(index, start)| {
+        let end = match events.get(index + 1) {
+            Some(value) => *value,
+            None => events[0] + 2.0 * PI,
+        };
+        normalise((start + end) / 2.0)
+    });
+    let candidates: Vec<f64> = midpoints.chain(events.iter().copied()).collect();
+
+    let (bearing, score) = candidates.iter().fold(
+        (current_heading, f64::NEG_INFINITY),
+        |(best_heading, best_score), heading| {
+            let score = score_heading(*heading);
+            if score > best_score {
+                (*heading, score)
+            } else {
+                (best_heading, best_score)
+            }
+        },
+    );
+    Some(OptimalHeading {
+        bearing: bearing.to_degrees(),
+        score,
+    })
+}
+
+/// Read the numeric `field` of each point in the points JSON or NDJSON file
+/// at `path`, keyed by point number, as weights for
+/// [`optimal_heading_by_weight`].  Points without the field are left out, so
+/// may be given a default weight.  Returns an error for other formats, which
+/// carry no such fields, or if the field of any point is not a number.
+pub fn point_weights<P: AsRef<Path>>(path: P, field: &str) -> Result<HashMap<u32, f64>, AppError> {
+    let path = path.as_ref();
+    if !is_json_points_path(path) {
+        return Err(AppError::ArgumentError(format!(
+            "weights from field `{field}` need a JSON or NDJSON points file"
+        )));
+    }
+    let input = read_points_file(path)?;
+    let records: Vec<WeightedRecord> = if is_ndjson_path(path) {
+        input
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| {
+                serde_json::from_str(line).map_err(|error| AppError::NDJSONParseError {
+                    line: index + 1,
+                    source: error,
+                })
+            })
+            .collect::<Result<_, _>>()?
+    } else {
+        let WeightedRecordList { points } =
+            serde_json::from_str(&input).map_err(AppError::JSONParseError)?;
+        points
+    };
+    records
+        .into_iter()
+        .filter_map(|WeightedRecord { number, fields }| {
+            let value = fields.get(field)?;
+            Some(
+                value
+                    .as_f64()
+                    .map(|weight| (number, weight))
+                    .ok_or_else(|| {
+                        AppError::ArgumentError(format!(
+                            "field `{field}` of point {number} is not a number"
+                        ))
+                    }),
+            )
+        })
+        .collect()
+}
+
+/// Return the smallest radius at which the point identified by
+/// `point_number` sees at least `k` `neighbourhood` points, within a segment
+/// spanning left and right from its direction by `half_arc_central_angle`.
+/// `half_arc_central_angle` should be in degrees, and can range from zero to
+/// `180` degrees.  The radius is in whole units, as accepted by
+/// [`crate::domain::visible_points_from_neighbours`].
+///
+/// `None` is returned if no point matching `point_number` is found in
+/// neighbourhood, or if fewer than `k` neighbours lie inside the segment at
+/// any radius.
+pub fn minimum_radius_for_neighbours(
+    point_number: u32,
+    half_arc_central_angle: u32,
+    k: usize,
+    neighbourhood: &[Point],
+) -> Option<u32> {
+    let point = neighbourhood
+        .iter()
+        .find(|Point { number, .. }| *number == point_number)?;
+    if k == 0 {
+        return Some(0);
+    }
+
+    let mut distances: Vec<f64> = neighbourhood
+        .iter()
+        .filter(|val| {
+            val.number != point_number && visible_neighbour(point, val, half_arc_central_angle)
+        })
+        .map(|Point { coordinates, .. }| euclidean_distance(point.coordinates, *coordinates))
+        .collect();
+    distances.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+
+    // neighbours are visible when strictly closer than the radius
+    distances
+        .get(k - 1)
+        .map(|distance| (distance.floor() as u32).saturating_add(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        minimum_radius_for_neighbours, optimal_heading, optimal_heading_by_weight, point_weights,
+        OptimalHeading,
+    };
+    use crate::domain::visible_points_from_neighbours;
+    use crate::domain::{Direction, Point};
+    use std::{collections::HashMap, env, fs};
+
+    fn neighbourhood() -> Vec<Point> {
+        vec![
+            Point {
+                coordinates: (0, 0),
+                number: 1,
+                direction: Direction::North,
+            },
+            Point {
+                coordinates: (5, 0),
+                number: 2,
+                direction: Direction::North,
+            },
+            Point {
+                coordinates: (6, -1),
+                number: 3,
+                direction: Direction::North,
+            },
+            Point {
+                coordinates: (-5, 0),
+                number: 4,
+                direction: Direction::North,
+            },
+        ]
+    }
+
+    #[test]
+    fn optimal_heading_gives_expected_result() {
+        // arrange
+        let points = neighbourhood();
+
+        // act
+        let outcome = optimal_heading(1, 30, 10, &points).unwrap();
+
+        // assert
+        assert_eq!(outcome.score, 2.0);
+        assert!(outcome.bearing > 60.0 && outcome.bearing < 120.0);
+
+        // act
+        let outcome = optimal_heading(1, 30, 3, &points).unwrap();
+
+        // assert
+        assert_eq!(
+            outcome,
+            OptimalHeading {
+                bearing: 0.0,
+                score: 0.0
+            }
+        );
+    }
+
+    #[test]
+    fn optimal_heading_by_weight_gives_expected_result() {
+        // arrange
+        let points = neighbourhood();
+
+        // act
+        let outcome =
+            optimal_heading_by_weight(
+                1,
+                30,
+                10,
+                &points,
+                |Point { number, .. }| {
+                    if *number == 4 {
+                        5.0
+                    } else {
+                        1.0
+                    }
+                },
+            )
+            .unwrap();
+
+        // assert
+        assert_eq!(outcome.score, 5.0);
+        assert!(outcome.bearing > 240.0 && outcome.bearing < 300.0);
+    }
+
+    #[test]
+    fn optimal_heading_counts_co_located_neighbours() {
+        // arrange
+        let mut points = neighbourhood();
+        points.push(Point {
+            coordinates: (0, 0),
+            number: 5,
+            direction: Direction::North,
+        });
+
+        // act
+        let outcome = optimal_heading(1, 30, 10, &points).unwrap();
+
+        // assert
+        assert_eq!(outcome.score, 3.0);
+        assert!(outcome.bearing > 60.0 && outcome.bearing < 120.0);
+        let facing = Point {
+            direction: Direction::Vector {
+                dx: outcome.bearing.to_radians().sin(),
+                dy: outcome.bearing.to_radians().cos(),
+            },
+            ..points[0]
+        };
+        points[0] = facing;
+        assert_eq!(visible_points_from_neighbours(1, 30, 10, &points).len(), 3);
+    }
+
+    #[test]
+    fn optimal_heading_handles_missing_point() {
+        // arrange
+        let points = neighbourhood();
+
+        // act
+        let outcome = optimal_heading(99, 30, 10, &points);
+
+        // assert
+        assert_eq!(outcome, None);
+    }
+
+    #[test]
+    fn minimum_radius_for_neighbours_gives_expected_result() {
+        // arrange
+        let mut points = neighbourhood();
+        points[0].direction = Direction::East;
+
+        // act
+        let outcome = minimum_radius_for_neighbours(1, 45, 2, &points);
+
+        // assert
+        assert_eq!(outcome, Some(7));
+        assert_eq!(visible_points_from_neighbours(1, 45, 7, &points).len(), 2);
+        assert_eq!(visible_points_from_neighbours(1, 45, 6, &points).len(), 1);
+
+        // act
+        let outcome = minimum_radius_for_neighbours(1, 45, 1, &points);
+
+        // assert
+        assert_eq!(outcome, Some(6));
+
+        // act
+        let outcome = minimum_radius_for_neighbours(1, 45, 3, &points);
+
+        // assert
+        assert_eq!(outcome, None);
+
+        // act
+        let outcome = minimum_radius_for_neighbours(99, 45, 1, &points);
+
+        // assert
+        assert_eq!(outcome, None);
+    }
+
+    #[test]
+    fn point_weights_reads_numeric_field() {
+        // arrange
+        let path = env::temp_dir().join(format!(
+            "neighbours-weights-test-{}.json",
+            std::process::id()
+        ));
+        fs::write(
+            &path,
+            r#"{"points": [
+                {"x": 0, "y": 0, "number": 1, "direction": "North", "population": 120},
+                {"x": 0, "y": 5, "number": 2, "direction": "South", "population": 2.5},
+                {"x": 3, "y": 4, "number": 3, "direction": "East"}
+            ]}"#,
+        )
+        .unwrap();
+
+        // act
+        let outcome = point_weights(&path, "population").unwrap();
+        let invalid = point_weights(&path, "direction");
+
+        // assert
+        assert_eq!(outcome, HashMap::from([(1, 120.0), (2, 2.5)]));
+        assert_eq!(
+            invalid.unwrap_err().to_string(),
+            "Invalid command line arguments: field `direction` of point 1 is not a number"
+        );
+        assert!(point_weights("./fixtures/valid_points.sqlite", "population").is_err());
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,147 @@
+use crate::{
+    domain::{point::Point, positions::sees_position},
+    utilities::Span,
+};
+use std::str::FromStr;
+
+/// Position and heading to query from, which need not be a point of the
+/// neighbourhood, for example a sensor placement under consideration
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Origin {
+    /// x,y coordinates of the origin
+    pub coordinates: (f64, f64),
+
+    /// Heading in degrees, clockwise from north
+    pub heading: f64,
+}
+
+/// Position and heading of a point, to query from as if it were elsewhere
+impl<Id> From<&Point<Id>> for Origin {
+    fn from(point: &Point<Id>) -> Self {
+        Origin {
+            coordinates: (
+                f64::from(point.coordinates.0),
+                f64::from(point.coordinates.1),
+            ),
+            heading: point.direction.bearing().to_degrees(),
+        }
+    }
+}
+
+/// Parse an origin written as `x,y,heading`, for example `10,-4.5,270`
+impl FromStr for Origin {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<Option<f64>> = value
+            .split(',')
+            .map(|part| {
+                part.trim()
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|val| val.is_finite())
+            })
+            .collect();
+        match parts[..] {
+            [Some(x), Some(y), Some(heading)] => Ok(Origin {
+                coordinates: (x, y),
+                heading,
+            }),
+            _ => Err(format!(
+                "expected an origin written `x,y,heading`, such as `10,-4.5,270`, but got `{value}`"
+            )),
+        }
+    }
+}
+
+/// Return `neighbourhood` points within a segment whose centre is at
+/// `origin`, and has radius of `arc_radius` units and spans left and right
+/// from the origin’s heading by `half_arc_central_angle`, in degrees.  As the
+/// origin is not one of the points, every point is a candidate, including
+/// any at the origin’s coordinates.  Points are returned in ascending number
+/// order, as for [`visible_points_from_neighbours`](crate::domain::visible_points_from_neighbours).
+pub fn visible_points_from_origin<'a>(
+    origin: &Origin,
+    half_arc_central_angle: u32,
+    arc_radius: u32,
+    neighbourhood: &'a [Point],
+) -> Vec<&'a Point> {
+    let _span = Span::enter("visible_points_from_origin", || {
+        format!("{:?} of {}", origin.coordinates, neighbourhood.len())
+    });
+    let mut result: Vec<&Point> = neighbourhood
+        .iter()
+        .filter(|Point { coordinates, .. }| {
+            sees_position(
+                origin.coordinates,
+                origin.heading,
+                f64::from(half_arc_central_angle),
+                f64::from(arc_radius),
+                (f64::from(coordinates.0), f64::from(coordinates.1)),
+            )
+        })
+        .collect();
+    result.sort_by_key(|Point { number, .. }| *number);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{visible_points_from_origin, Origin};
+    use crate::domain::{parse_points_file, visible_points_from_neighbours, Direction, Point};
+
+    #[test]
+    fn visible_points_from_origin_matches_numbered_point() {
+        // arrange
+        let points = parse_points_file("./fixtures/valid_points.json").unwrap();
+        let point = points.iter().find(|point| point.number == 20).unwrap();
+        let origin = Origin {
+            coordinates: (
+                f64::from(point.coordinates.0),
+                f64::from(point.coordinates.1),
+            ),
+            heading: match point.direction {
+                Direction::North => 0.0,
+                Direction::East => 90.0,
+                Direction::South => 180.0,
+                Direction::West => 270.0,
+                Direction::Vector { .. } | Direction::Towards(_) => {
+                    point.direction.bearing().to_degrees()
+                }
+            },
+        };
+        let others: Vec<Point> = points
+            .iter()
+            .filter(|value| value.number != 20)
+            .copied()
+            .collect();
+
+        // act
+        let outcome = visible_points_from_origin(&origin, 180, 10, &others);
+
+        // assert
+        assert!(!outcome.is_empty());
+        assert_eq!(
+            outcome,
+            visible_points_from_neighbours(20, 180, 10, &points)
+        );
+        let hypothetical: Origin = "0.5, 0.5, 45".parse().unwrap();
+        let nearby = [
+            Point {
+                coordinates: (3, 3),
+                number: 2,
+                direction: Direction::South,
+            },
+            Point {
+                coordinates: (0, 3),
+                number: 1,
+                direction: Direction::West,
+            },
+        ];
+        assert_eq!(
+            visible_points_from_origin(&hypothetical, 30, 5, &nearby),
+            vec![&nearby[0]]
+        );
+        assert!("1,2".parse::<Origin>().is_err());
+    }
+}
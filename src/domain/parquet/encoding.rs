@@ -0,0 +1,212 @@
+//! Decoders for Parquet value and level encodings
+
+/// Physical type of a column, as stored
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) enum PhysicalType {
+    Int32,
+    Int64,
+    ByteArray,
+}
+
+/// Decoded column values
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Values {
+    Integers(Vec<i64>),
+    Strings(Vec<String>),
+}
+
+impl Values {
+    pub(crate) fn empty(physical_type: PhysicalType) -> Self {
+        match physical_type {
+            PhysicalType::Int32 | PhysicalType::Int64 => Values::Integers(vec![]),
+            PhysicalType::ByteArray => Values::Strings(vec![]),
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            Values::Integers(value) => value.len(),
+            Values::Strings(value) => value.len(),
+        }
+    }
+
+    /// Append the values at `indices` of `dictionary`
+    pub(crate) fn extend_from_dictionary(
+        &mut self,
+        dictionary: &Values,
+        indices: &[u32],
+    ) -> Result<(), String> {
+        let out_of_range = || String::from("dictionary index out of range");
+        match (self, dictionary) {
+            (Values::Integers(values), Values::Integers(dictionary)) => {
+                for index in indices {
+                    values.push(*dictionary.get(*index as usize).ok_or_else(out_of_range)?);
+                }
+            }
+            (Values::Strings(values), Values::Strings(dictionary)) => {
+                for index in indices {
+                    values.push(
+                        dictionary
+                            .get(*index as usize)
+                            .ok_or_else(out_of_range)?
+                            .clone(),
+                    );
+                }
+            }
+            _ => return Err(String::from("dictionary type mismatch")),
+        }
+        Ok(())
+    }
+
+    pub(crate) fn extend(&mut self, other: Values) -> Result<(), String> {
+        match (self, other) {
+            (Values::Integers(values), Values::Integers(other)) => values.extend(other),
+            (Values::Strings(values), Values::Strings(other)) => values.extend(other),
+            _ => return Err(String::from("column type mismatch")),
+        }
+        Ok(())
+    }
+}
+
+/// Decode `count` PLAIN encoded values of `physical_type` from `bytes`
+pub(crate) fn decode_plain(
+    bytes: &[u8],
+    physical_type: PhysicalType,
+    count: usize,
+) -> Result<Values, String> {
+    let truncated = || String::from("truncated PLAIN values");
+    match physical_type {
+        PhysicalType::Int32 | PhysicalType::Int64 => {
+            let width = match physical_type {
+                PhysicalType::Int32 => 4,
+                _ => 8,
+            };
+            let values = bytes
+                .get(..count.checked_mul(width).ok_or_else(truncated)?)
+                .ok_or_else(truncated)?
+                .chunks_exact(width)
+                .map(|chunk| match width {
+                    4 => i64::from(i32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]])),
+                    _ => {
+                        let mut value = [0; 8];
+                        value.copy_from_slice(chunk);
+                        i64::from_le_bytes(value)
+                    }
+                })
+                .collect();
+            Ok(Values::Integers(values))
+        }
+        PhysicalType::ByteArray => {
+            let mut position = 0;
+            let mut values = Vec::with_capacity(count.min(bytes.len() / 4));
+            for _ in 0..count {
+                let length = bytes.get(position..position + 4).ok_or_else(truncated)?;
+                let length =
+                    u32::from_le_bytes([length[0], length[1], length[2], length[3]]) as usize;
+                position += 4;
+                let value = bytes
+                    .get(position..position.saturating_add(length))
+                    .ok_or_else(truncated)?;
+                values.push(String::from_utf8_lossy(value).into_owned());
+                position += length;
+            }
+            Ok(Values::Strings(values))
+        }
+    }
+}
+
+/// Decode `count` values of `bit_width` bits from the RLE/bit-packed hybrid
+/// encoded `bytes`, as used for levels and dictionary indices
+pub(crate) fn decode_rle_hybrid(
+    bytes: &[u8],
+    bit_width: u8,
+    count: usize,
+) -> Result<Vec<u32>, String> {
+    if bit_width > 32 {
+        return Err(String::from("invalid bit width"));
+    }
+    let truncated = || String::from("truncated RLE data");
+    let byte_width = (usize::from(bit_width) + 7) / 8;
+    let mut position = 0;
+    let mut result = Vec::with_capacity(count.min(bytes.len() * 8));
+    while result.len() < count {
+        let mut header = 0_usize;
+        for shift in (0..35).step_by(7) {
+            let byte = *bytes.get(position).ok_or_else(truncated)?;
+            position += 1;
+            header |= usize::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+        if header & 1 == 0 {
+            // run of one repeated value
+            let value = bytes
+                .get(position..position + byte_width)
+                .ok_or_else(truncated)?
+                .iter()
+                .rev()
+                .fold(0_u32, |acc, byte| (acc << 8) | u32::from(*byte));
+            position += byte_width;
+            let run = (header >> 1).min(count - result.len());
+            result.extend(std::iter::repeat(value).take(run));
+        } else {
+            // groups of eight bit-packed values, least significant bit first
+            let values = (header >> 1) * 8;
+            let length = (header >> 1) * usize::from(bit_width);
+            let packed = bytes
+                .get(position..position + length)
+                .ok_or_else(truncated)?;
+            position += length;
+            for index in 0..values.min(count - result.len()) {
+                let mut value = 0_u32;
+                for bit in 0..usize::from(bit_width) {
+                    let offset = index * usize::from(bit_width) + bit;
+                    if packed[offset / 8] & (1 << (offset % 8)) != 0 {
+                        value |= 1 << bit;
+                    }
+                }
+                result.push(value);
+            }
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_plain, decode_rle_hybrid, PhysicalType, Values};
+
+    #[test]
+    fn decode_plain_gives_expected_result() {
+        // arrange
+        let integers = [0xfe, 0xff, 0xff, 0xff, 0x07, 0x00, 0x00, 0x00];
+        let strings = [0x02, 0x00, 0x00, 0x00, b'h', b'i', 0x00, 0x00, 0x00, 0x00];
+
+        // act
+        let integers = decode_plain(&integers, PhysicalType::Int32, 2).unwrap();
+        let strings = decode_plain(&strings, PhysicalType::ByteArray, 2).unwrap();
+        let truncated = decode_plain(&[0x01], PhysicalType::Int64, 1).unwrap_err();
+
+        // assert
+        assert_eq!(integers, Values::Integers(vec![-2, 7]));
+        assert_eq!(
+            strings,
+            Values::Strings(vec![String::from("hi"), String::new()])
+        );
+        assert_eq!(truncated, "truncated PLAIN values");
+    }
+
+    #[test]
+    fn decode_rle_hybrid_gives_expected_result() {
+        // arrange
+        // run of three 2s, then one group of bit-packed 3-bit values 0 to 7
+        let bytes = [0x06, 0x02, 0x03, 0x88, 0xc6, 0xfa];
+
+        // act
+        let outcome = decode_rle_hybrid(&bytes, 3, 11).unwrap();
+
+        // assert
+        assert_eq!(outcome, vec![2, 2, 2, 0, 1, 2, 3, 4, 5, 6, 7]);
+    }
+}
@@ -0,0 +1,416 @@
+//! Reader for points stored in Parquet files, enabled by the `parquet`
+//! feature.  Flat schemas with `number`, `x`, `y` and `direction` columns are
+//! supported, with integer coordinates and numbers, and `direction` names as
+//! strings.  Pages may be PLAIN or dictionary encoded, version 1 or 2, and
+//! uncompressed or Snappy compressed.  Null values are rejected.
+
+mod encoding;
+mod snappy;
+mod thrift;
+
+use crate::{
+    domain::point::{Direction, Point},
+    utilities::{AppError, Span},
+};
+use encoding::{decode_plain, decode_rle_hybrid, PhysicalType, Values};
+use std::{convert::TryFrom, fs, path::Path};
+use thrift::{Decoder, Struct};
+
+/// Leading and trailing bytes of every Parquet file
+const MAGIC: &[u8; 4] = b"PAR1";
+
+/// Columns read, in the order they are combined into points
+const COLUMNS: [&str; 4] = ["number", "x", "y", "direction"];
+
+fn parquet_error(message: String) -> AppError {
+    AppError::ParquetError(message)
+}
+
+/// Schema facts about one of [`COLUMNS`]
+#[derive(Copy, Clone, Debug)]
+struct Column {
+    /// Index among the schema’s leaf columns, and so among row group chunks
+    index: usize,
+    physical_type: PhysicalType,
+
+    /// Values may be null, so pages carry definition levels
+    optional: bool,
+}
+
+/// Find the leaf column called `name` in `schema`
+fn find_column(schema: &[Struct], name: &str) -> Result<Column, AppError> {
+    // the first element is the root, and the rest are leaves of a flat schema
+    let leaves = schema.get(1..).unwrap_or(&[]);
+    let index = leaves
+        .iter()
+        .position(|element| element.string(4).as_deref() == Some(name))
+        .ok_or_else(|| parquet_error(format!("missing column `{name}`")))?;
+    let element = &leaves[index];
+    if element.int(5).unwrap_or(0) > 0 {
+        return Err(parquet_error(format!("column `{name}` is nested")));
+    }
+    let physical_type = match (name, element.int(1)) {
+        ("direction", Some(6)) => PhysicalType::ByteArray,
+        ("direction", _) => {
+            return Err(parquet_error(String::from(
+                "column `direction` should hold strings",
+            )))
+        }
+        (_, Some(1)) => PhysicalType::Int32,
+        (_, Some(2)) => PhysicalType::Int64,
+        _ => {
+            return Err(parquet_error(format!(
+                "column `{name}` should hold integers"
+            )))
+        }
+    };
+    let optional = match element.int(3).unwrap_or(0) {
+        0 => false,
+        1 => true,
+        _ => return Err(parquet_error(format!("column `{name}` is repeated"))),
+    };
+    Ok(Column {
+        index,
+        physical_type,
+        optional,
+    })
+}
+
+/// Decompress page `data` compressed with `codec`
+fn decompress(data: &[u8], codec: i64) -> Result<Vec<u8>, AppError> {
+    match codec {
+        0 => Ok(data.to_vec()),
+        1 => snappy::decompress(data).map_err(parquet_error),
+        _ => Err(parquet_error(format!(
+            "unsupported compression codec {codec}, expected uncompressed or Snappy"
+        ))),
+    }
+}
+
+/// Decode `count` values in `encoding` from `bytes`
+fn decode_values(
+    bytes: &[u8],
+    encoding: i64,
+    column: Column,
+    dictionary: Option<&Values>,
+    count: usize,
+) -> Result<Values, AppError> {
+    match encoding {
+        0 => decode_plain(bytes, column.physical_type, count).map_err(parquet_error),
+        2 | 8 => {
+            let dictionary =
+                dictionary.ok_or_else(|| parquet_error(String::from("missing dictionary page")))?;
+            let (bit_width, indices) = bytes
+                .split_first()
+                .ok_or_else(|| parquet_error(String::from("empty dictionary encoded page")))?;
+            let indices = decode_rle_hybrid(indices, *bit_width, count).map_err(parquet_error)?;
+            let mut result = Values::empty(column.physical_type);
+            result
+                .extend_from_dictionary(dictionary, &indices)
+                .map_err(parquet_error)?;
+            Ok(result)
+        }
+        _ => Err(parquet_error(format!(
+            "unsupported encoding {encoding}, expected PLAIN or dictionary"
+        ))),
+    }
+}
+
+/// Error unless all `count` definition levels, in RLE/bit-packed hybrid
+/// `levels`, mark non-null values
+fn check_no_nulls(levels: &[u8], count: usize, name: &str) -> Result<(), AppError> {
+    let levels = decode_rle_hybrid(levels, 1, count).map_err(parquet_error)?;
+    match levels.iter().all(|level| *level == 1) {
+        true => Ok(()),
+        false => Err(parquet_error(format!("null value in column `{name}`"))),
+    }
+}
+
+/// Read every value of a column chunk, described by `chunk`, from `file`
+fn read_column_chunk(
+    file: &[u8],
+    chunk: &Struct,
+    column: Column,
+    name: &str,
+) -> Result<Values, AppError> {
+    let metadata = chunk
+        .structure(3)
+        .ok_or_else(|| parquet_error(format!("missing metadata for column `{name}`")))?;
+    let codec = metadata.int(4).unwrap_or(0);
+    let value_count = metadata.int(5).unwrap_or(0).max(0) as usize;
+    let start = match (metadata.int(11), metadata.int(9)) {
+        (Some(dictionary), Some(data)) if dictionary > 0 => dictionary.min(data),
+        (_, Some(data)) => data,
+        _ => {
+            return Err(parquet_error(format!(
+                "missing data page offset for column `{name}`"
+            )))
+        }
+    };
+    let mut position = usize::try_from(start)
+        .map_err(|_| parquet_error(format!("invalid page offset for column `{name}`")))?;
+
+    let mut dictionary: Option<Values> = None;
+    let mut result = Values::empty(column.physical_type);
+    while result.len() < value_count {
+        let mut decoder = Decoder::new(file.get(position..).unwrap_or(&[]));
+        let header = decoder.read_struct().map_err(parquet_error)?;
+        position += decoder.position;
+        let compressed_size = header.int(3).unwrap_or(0).max(0) as usize;
+        let data = file
+            .get(position..position + compressed_size)
+            .ok_or_else(|| parquet_error(format!("truncated page in column `{name}`")))?;
+        position += compressed_size;
+
+        match header.int(1) {
+            // data page, version 1
+            Some(0) => {
+                let page = header
+                    .structure(5)
+                    .ok_or_else(|| parquet_error(String::from("missing data page header")))?;
+                let count = page.int(1).unwrap_or(0).max(0) as usize;
+                let data = decompress(data, codec)?;
+                let mut values = &data[..];
+                if column.optional {
+                    let length = values
+                        .get(..4)
+                        .map(|val| u32::from_le_bytes([val[0], val[1], val[2], val[3]]) as usize)
+                        .ok_or_else(|| parquet_error(String::from("truncated levels")))?;
+                    let levels = values
+                        .get(4..4 + length)
+                        .ok_or_else(|| parquet_error(String::from("truncated levels")))?;
+                    check_no_nulls(levels, count, name)?;
+                    values = &values[4 + length..];
+                }
+                let encoding = page.int(2).unwrap_or(0);
+                result
+                    .extend(decode_values(
+                        values,
+                        encoding,
+                        column,
+                        dictionary.as_ref(),
+                        count,
+                    )?)
+                    .map_err(parquet_error)?;
+            }
+            // dictionary page
+            Some(2) => {
+                let page = header
+                    .structure(7)
+                    .ok_or_else(|| parquet_error(String::from("missing dictionary page header")))?;
+                let count = page.int(1).unwrap_or(0).max(0) as usize;
+                let data = decompress(data, codec)?;
+                dictionary =
+                    Some(decode_plain(&data, column.physical_type, count).map_err(parquet_error)?);
+            }
+            // data page, version 2, with levels before the compressed values
+            Some(3) => {
+                let page = header
+                    .structure(8)
+                    .ok_or_else(|| parquet_error(String::from("missing data page header")))?;
+                let count = page.int(1).unwrap_or(0).max(0) as usize;
+                if page.int(2).unwrap_or(0) > 0 {
+                    return Err(parquet_error(format!("null value in column `{name}`")));
+                }
+                let levels_length =
+                    (page.int(5).unwrap_or(0) + page.int(6).unwrap_or(0)).max(0) as usize;
+                let values = data
+                    .get(levels_length..)
+                    .ok_or_else(|| parquet_error(String::from("truncated levels")))?;
+                let values = match page.bool(7).unwrap_or(true) {
+                    true => decompress(values, codec)?,
+                    false => values.to_vec(),
+                };
+                let encoding = page.int(4).unwrap_or(0);
+                result
+                    .extend(decode_values(
+                        &values,
+                        encoding,
+                        column,
+                        dictionary.as_ref(),
+                        count,
+                    )?)
+                    .map_err(parquet_error)?;
+            }
+            // index pages, and unknown page types, carry no values
+            _ => {}
+        }
+    }
+    Ok(result)
+}
+
+/// Convert column `values` to integers of type `T`
+fn integers<T: TryFrom<i64>>(values: Values, name: &str) -> Result<Vec<T>, AppError> {
+    match values {
+        Values::Integers(values) => values
+            .into_iter()
+            .map(|value| {
+                T::try_from(value)
+                    .map_err(|_| parquet_error(format!("value {value} out of range for `{name}`")))
+            })
+            .collect(),
+        Values::Strings(_) => Err(parquet_error(format!(
+            "column `{name}` should hold integers"
+        ))),
+    }
+}
+
+fn directions(values: Values) -> Result<Vec<Direction>, AppError> {
+    match values {
+        Values::Strings(values) => values
+            .iter()
+            .map(|value| match value.as_str() {
+                "North" => Ok(Direction::North),
+                "East" => Ok(Direction::East),
+                "South" => Ok(Direction::South),
+                "West" => Ok(Direction::West),
+                _ => Err(parquet_error(format!("unknown direction `{value}`"))),
+            })
+            .collect(),
+        Values::Integers(_) => Err(parquet_error(String::from(
+            "column `direction` should hold strings",
+        ))),
+    }
+}
+
+/// Parse the bytes of a Parquet file into a [`Vec`] of [`Point`]s, reading
+/// the `number`, `x`, `y` and `direction` columns of every row group.  Points
+/// are returned in row order.
+pub fn parse_points_parquet(bytes: &[u8]) -> Result<Vec<Point>, AppError> {
+    let _span = Span::enter("parse_points_parquet", || format!("{} bytes", bytes.len()));
+    let length = bytes.len();
+    if length < 12 || &bytes[..4] != MAGIC || &bytes[length - 4..] != MAGIC {
+        return Err(parquet_error(String::from("missing Parquet header")));
+    }
+    let footer_length = u32::from_le_bytes([
+        bytes[length - 8],
+        bytes[length - 7],
+        bytes[length - 6],
+        bytes[length - 5],
+    ]) as usize;
+    let footer = length
+        .checked_sub(8 + footer_length)
+        .filter(|start| *start >= 4)
+        .map(|start| &bytes[start..length - 8])
+        .ok_or_else(|| parquet_error(String::from("invalid footer length")))?;
+    let metadata = Decoder::new(footer).read_struct().map_err(parquet_error)?;
+    let schema: Vec<Struct> = metadata
+        .list(2)
+        .iter()
+        .filter_map(|value| value.as_struct().cloned())
+        .collect();
+    let columns = COLUMNS
+        .iter()
+        .map(|name| find_column(&schema, name))
+        .collect::<Result<Vec<Column>, AppError>>()?;
+
+    let mut result = vec![];
+    for row_group in metadata
+        .list(4)
+        .iter()
+        .filter_map(|value| value.as_struct())
+    {
+        let chunks = row_group.list(1);
+        let mut values = columns
+            .iter()
+            .zip(COLUMNS.iter())
+            .map(|(column, name)| {
+                let chunk = chunks
+                    .get(column.index)
+                    .and_then(|value| value.as_struct())
+                    .ok_or_else(|| parquet_error(format!("missing chunk for column `{name}`")))?;
+                read_column_chunk(bytes, chunk, *column, name)
+            })
+            .collect::<Result<Vec<Values>, AppError>>()?
+            .into_iter();
+        let (number, x, y, direction) =
+            match (values.next(), values.next(), values.next(), values.next()) {
+                (Some(number), Some(x), Some(y), Some(direction)) => (number, x, y, direction),
+                _ => return Err(parquet_error(String::from("missing column values"))),
+            };
+        let number: Vec<u32> = integers(number, "number")?;
+        let x: Vec<i32> = integers(x, "x")?;
+        let y: Vec<i32> = integers(y, "y")?;
+        let direction = directions(direction)?;
+        if [x.len(), y.len(), direction.len()]
+            .iter()
+            .any(|length| *length != number.len())
+        {
+            return Err(parquet_error(String::from(
+                "columns have different numbers of values",
+            )));
+        }
+        result.extend(
+            number
+                .into_iter()
+                .zip(x.into_iter().zip(y))
+                .zip(direction)
+                .map(|((number, coordinates), direction)| Point {
+                    coordinates,
+                    number,
+                    direction,
+                }),
+        );
+    }
+    Ok(result)
+}
+
+/// Read and parse the Parquet file at `path`, as for [`parse_points_parquet`]
+pub(crate) fn parse_points_parquet_file(path: &Path) -> Result<Vec<Point>, AppError> {
+    let bytes = fs::read(path).map_err(|error| AppError::BinaryFileError {
+        path: path.display().to_string(),
+        source: error,
+    })?;
+    parse_points_parquet(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_points_parquet;
+    use crate::domain::parse_points_file;
+    use std::fs;
+
+    #[test]
+    fn parse_points_parquet_gives_expected_result() {
+        // arrange
+        // two row groups, mixing PLAIN and dictionary encoding, data page
+        // versions 1 and 2, and uncompressed and Snappy compressed pages
+        let bytes = fs::read("./fixtures/valid_points.parquet").unwrap();
+
+        // act
+        let outcome = parse_points_parquet(&bytes).unwrap();
+
+        // assert
+        assert_eq!(
+            outcome,
+            parse_points_file("./fixtures/valid_points.json").unwrap()
+        );
+        assert_eq!(
+            parse_points_file("./fixtures/valid_points.parquet").unwrap(),
+            outcome
+        );
+    }
+
+    #[test]
+    fn parse_points_parquet_handles_invalid_input() {
+        // arrange
+        let bytes = fs::read("./fixtures/valid_points.parquet").unwrap();
+        let truncated = [&bytes[..4], &bytes[bytes.len() - 20..]].concat();
+
+        // act
+        let not_parquet = parse_points_parquet(b"{\"points\": []}")
+            .unwrap_err()
+            .to_string();
+        let truncated = parse_points_parquet(&truncated).unwrap_err().to_string();
+
+        // assert
+        assert_eq!(
+            not_parquet,
+            "Error reading Parquet input: missing Parquet header"
+        );
+        assert_eq!(
+            truncated,
+            "Error reading Parquet input: invalid footer length"
+        );
+    }
+}
@@ -0,0 +1,117 @@
+//! Decompressor for raw Snappy blocks, the most common Parquet page codec
+
+/// Read a little endian unsigned integer of `length` bytes at `position`
+fn read_le(input: &[u8], position: usize, length: usize) -> Result<usize, String> {
+    let bytes = input
+        .get(position..position + length)
+        .ok_or_else(|| String::from("truncated Snappy data"))?;
+    Ok(bytes
+        .iter()
+        .rev()
+        .fold(0, |acc, byte| (acc << 8) | usize::from(*byte)))
+}
+
+/// Decompress the raw Snappy block `input`
+pub(crate) fn decompress(input: &[u8]) -> Result<Vec<u8>, String> {
+    // preamble is the uncompressed length, as a varint
+    let mut position = 0;
+    let mut expected_length = 0_usize;
+    for shift in (0..35).step_by(7) {
+        let byte = *input
+            .get(position)
+            .ok_or_else(|| String::from("truncated Snappy data"))?;
+        position += 1;
+        expected_length |= usize::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            break;
+        }
+    }
+
+    let mut output: Vec<u8> = Vec::with_capacity(expected_length);
+    while position < input.len() {
+        let tag = input[position];
+        position += 1;
+        let (length, offset) = match tag & 0x03 {
+            0 => {
+                let length = match usize::from(tag >> 2) {
+                    value @ 0..=59 => value + 1,
+                    value => {
+                        let bytes = value - 59;
+                        let length = read_le(input, position, bytes)? + 1;
+                        position += bytes;
+                        length
+                    }
+                };
+                let literal = input
+                    .get(position..position + length)
+                    .ok_or_else(|| String::from("truncated Snappy data"))?;
+                output.extend_from_slice(literal);
+                position += length;
+                continue;
+            }
+            1 => {
+                let length = 4 + usize::from((tag >> 2) & 0x07);
+                let offset = (usize::from(tag >> 5) << 8) | read_le(input, position, 1)?;
+                position += 1;
+                (length, offset)
+            }
+            2 => {
+                let offset = read_le(input, position, 2)?;
+                position += 2;
+                (usize::from(tag >> 2) + 1, offset)
+            }
+            _ => {
+                let offset = read_le(input, position, 4)?;
+                position += 4;
+                (usize::from(tag >> 2) + 1, offset)
+            }
+        };
+        if offset == 0 || offset > output.len() {
+            return Err(String::from("invalid Snappy copy offset"));
+        }
+        // copies may overlap the bytes they produce, so go byte by byte
+        let start = output.len() - offset;
+        for index in 0..length {
+            let byte = output[start + index];
+            output.push(byte);
+        }
+        if output.len() > expected_length {
+            return Err(String::from("Snappy data longer than expected"));
+        }
+    }
+    match output.len() == expected_length {
+        true => Ok(output),
+        false => Err(String::from("Snappy data length mismatch")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::decompress;
+
+    #[test]
+    fn decompress_gives_expected_result() {
+        // arrange
+        // 11 bytes: literal "abc", 1-byte-offset copy of 4 from 3 back, then
+        // 2-byte-offset copy of 4 from 7 back
+        let input = [11, 0x08, b'a', b'b', b'c', 0x01, 0x03, 0x0e, 0x07, 0x00];
+
+        // act
+        let outcome = decompress(&input).unwrap();
+
+        // assert
+        assert_eq!(outcome, b"abcabcaabca");
+    }
+
+    #[test]
+    fn decompress_handles_invalid_input() {
+        // arrange
+        let input = [4, 0x01, 0x05];
+
+        // act
+        let outcome = decompress(&input).unwrap_err();
+
+        // assert
+        assert_eq!(outcome, "invalid Snappy copy offset");
+    }
+}
@@ -0,0 +1,242 @@
+//! Decoder for the Thrift compact protocol, used by Parquet file and page
+//! metadata
+
+/// Decoded Thrift value.  Integers of every width are widened to `i64`.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Value {
+    Bool(bool),
+    Int(i64),
+    Double(f64),
+    Binary(Vec<u8>),
+    List(Vec<Value>),
+    Struct(Struct),
+}
+
+/// Decoded Thrift struct, as its fields’ ids and values
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Struct {
+    fields: Vec<(i16, Value)>,
+}
+
+impl Struct {
+    fn field(&self, id: i16) -> Option<&Value> {
+        self.fields
+            .iter()
+            .find(|(field_id, _)| *field_id == id)
+            .map(|(_, value)| value)
+    }
+
+    pub(crate) fn int(&self, id: i16) -> Option<i64> {
+        match self.field(id) {
+            Some(Value::Int(value)) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn bool(&self, id: i16) -> Option<bool> {
+        match self.field(id) {
+            Some(Value::Bool(value)) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn string(&self, id: i16) -> Option<String> {
+        match self.field(id) {
+            Some(Value::Binary(value)) => Some(String::from_utf8_lossy(value).into_owned()),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn list(&self, id: i16) -> &[Value] {
+        match self.field(id) {
+            Some(Value::List(value)) => value,
+            _ => &[],
+        }
+    }
+
+    pub(crate) fn structure(&self, id: i16) -> Option<&Struct> {
+        match self.field(id) {
+            Some(Value::Struct(value)) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl Value {
+    pub(crate) fn as_struct(&self) -> Option<&Struct> {
+        match self {
+            Value::Struct(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+/// Compact protocol reader over `bytes`, tracking the current position
+pub(crate) struct Decoder<'a> {
+    bytes: &'a [u8],
+    pub(crate) position: usize,
+}
+
+/// Deepest nesting of structs and lists accepted, guarding against stack
+/// overflow on corrupt input
+const MAXIMUM_DEPTH: usize = 64;
+
+impl<'a> Decoder<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Decoder { bytes, position: 0 }
+    }
+
+    fn byte(&mut self) -> Result<u8, String> {
+        let result = *self
+            .bytes
+            .get(self.position)
+            .ok_or_else(|| String::from("unexpected end of metadata"))?;
+        self.position += 1;
+        Ok(result)
+    }
+
+    fn take(&mut self, length: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .position
+            .checked_add(length)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| String::from("unexpected end of metadata"))?;
+        let result = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(result)
+    }
+
+    fn varint(&mut self) -> Result<u64, String> {
+        let mut result = 0_u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            result |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(result);
+            }
+        }
+        Err(String::from("varint too long"))
+    }
+
+    fn zigzag(&mut self) -> Result<i64, String> {
+        let value = self.varint()?;
+        Ok((value >> 1) as i64 ^ -((value & 1) as i64))
+    }
+
+    /// Read a value of compact type `element_type`.  `in_list` selects the
+    /// list encoding of booleans, which are stored as a byte each.
+    fn value(&mut self, element_type: u8, in_list: bool, depth: usize) -> Result<Value, String> {
+        if depth > MAXIMUM_DEPTH {
+            return Err(String::from("metadata nested too deeply"));
+        }
+        match element_type {
+            1 if in_list => Ok(Value::Bool(self.byte()? == 1)),
+            1 => Ok(Value::Bool(true)),
+            2 => Ok(Value::Bool(false)),
+            3 => Ok(Value::Int(i64::from(self.byte()? as i8))),
+            4..=6 => Ok(Value::Int(self.zigzag()?)),
+            7 => {
+                let mut bytes = [0; 8];
+                bytes.copy_from_slice(self.take(8)?);
+                Ok(Value::Double(f64::from_le_bytes(bytes)))
+            }
+            8 => {
+                let length = self.varint()? as usize;
+                Ok(Value::Binary(self.take(length)?.to_vec()))
+            }
+            9 | 10 => {
+                let header = self.byte()?;
+                let size = match header >> 4 {
+                    15 => self.varint()? as usize,
+                    value => usize::from(value),
+                };
+                let element_type = header & 0x0f;
+                // every element takes at least one byte, except true booleans
+                if size > self.bytes.len() - self.position && element_type != 1 {
+                    return Err(String::from("list longer than metadata"));
+                }
+                (0..size)
+                    .map(|_| self.value(element_type, true, depth + 1))
+                    .collect::<Result<Vec<Value>, String>>()
+                    .map(Value::List)
+            }
+            11 => {
+                let size = self.varint()? as usize;
+                if size > 0 {
+                    let types = self.byte()?;
+                    for _ in 0..size {
+                        self.value(types >> 4, true, depth + 1)?;
+                        self.value(types & 0x0f, true, depth + 1)?;
+                    }
+                }
+                // maps are not used by the fields read, so are discarded
+                Ok(Value::List(vec![]))
+            }
+            12 => self.structure(depth + 1).map(Value::Struct),
+            _ => Err(format!("unknown metadata field type {element_type}")),
+        }
+    }
+
+    fn structure(&mut self, depth: usize) -> Result<Struct, String> {
+        let mut result = Struct::default();
+        let mut field_id: i16 = 0;
+        loop {
+            let header = self.byte()?;
+            if header == 0 {
+                return Ok(result);
+            }
+            field_id = match header >> 4 {
+                0 => self.zigzag()? as i16,
+                delta => field_id.wrapping_add(i16::from(delta)),
+            };
+            let value = self.value(header & 0x0f, false, depth)?;
+            result.fields.push((field_id, value));
+        }
+    }
+
+    /// Read a struct starting at the current position
+    pub(crate) fn read_struct(&mut self) -> Result<Struct, String> {
+        self.structure(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Decoder, Value};
+
+    #[test]
+    fn decoder_reads_compact_struct() {
+        // arrange
+        // field 1: i32 -2, field 2: string "ab", field 4 (delta 2): list of two
+        // i64, field 20 (long form): true, field 21: struct with field 1 false
+        let bytes = [
+            0x15, 0x03, 0x18, 0x02, b'a', b'b', 0x29, 0x26, 0x02, 0x04, 0x01, 0x28, 0x1c, 0x12,
+            0x00, 0x00,
+        ];
+
+        // act
+        let outcome = Decoder::new(&bytes).read_struct().unwrap();
+
+        // assert
+        assert_eq!(outcome.int(1), Some(-2));
+        assert_eq!(outcome.string(2).as_deref(), Some("ab"));
+        assert_eq!(outcome.list(4), &[Value::Int(1), Value::Int(2)]);
+        assert_eq!(outcome.bool(20), Some(true));
+        assert_eq!(
+            outcome.structure(21).and_then(|val| val.bool(1)),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn decoder_handles_truncated_input() {
+        // arrange
+        let bytes = [0x18, 0x05, b'a'];
+
+        // act
+        let outcome = Decoder::new(&bytes).read_struct().unwrap_err();
+
+        // assert
+        assert_eq!(outcome, "unexpected end of metadata");
+    }
+}
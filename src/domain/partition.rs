@@ -0,0 +1,257 @@
+use crate::{
+    domain::{
+        cache::{points_from_records, push_point_bytes, CACHED_POINT_SIZE},
+        ndjson::for_each_point_in_file,
+        point::{close_neighbours, Point},
+    },
+    utilities::{AppError, Span},
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+/// Bytes of tile records held in memory while partitioning, before they are
+/// appended to tile files
+const TILE_BUFFER_BUDGET: usize = 16 * 1024 * 1024;
+
+/// Column and row of a tile
+type Tile = (i32, i32);
+
+/// Neighbourhood split into square tiles, each saved to its own file, so
+/// all-pairs visibility runs on datasets larger than memory.  Only a tile and
+/// its eight neighbours are loaded at once, see
+/// [`Partition::for_each_visible`].
+#[derive(Clone, Debug)]
+pub struct Partition {
+    directory: PathBuf,
+    tile_size: u32,
+
+    /// Number of points in each tile, for tiles holding any
+    tiles: BTreeMap<Tile, usize>,
+}
+
+impl Partition {
+    /// First pass: read the points file at `points_path`, writing each point
+    /// to the file of the `tile_size` square tile holding it, inside
+    /// `directory`, which is created if missing.  NDJSON files are streamed,
+    /// so they need never fit in memory, while other formats are parsed in
+    /// full first.  Tile files are named `tile_{column}_{row}.bin`, and hold
+    /// points in the binary layout of the points cache, without its header.
+    pub fn create<P: AsRef<Path>, Q: Into<PathBuf>>(
+        points_path: P,
+        tile_size: u32,
+        directory: Q,
+    ) -> Result<Self, AppError> {
+        if tile_size == 0 {
+            return Err(AppError::ArgumentError(String::from(
+                "tile size must be greater than zero",
+            )));
+        }
+        let mut result = Partition {
+            directory: directory.into(),
+            tile_size,
+            tiles: BTreeMap::new(),
+        };
+        let _span = Span::enter("partition_points", || {
+            result.directory.display().to_string()
+        });
+        fs::create_dir_all(&result.directory).map_err(|error| result.output_error(error))?;
+
+        let mut buffers: HashMap<Tile, Vec<u8>> = HashMap::new();
+        let mut buffered = 0;
+        for_each_point_in_file(points_path.as_ref(), |point| {
+            let tile = result.tile_of(&point);
+            push_point_bytes(buffers.entry(tile).or_default(), &point);
+            buffered += CACHED_POINT_SIZE;
+            if buffered >= TILE_BUFFER_BUDGET {
+                result.flush(&mut buffers)?;
+                buffered = 0;
+            }
+            Ok(())
+        })?;
+        result.flush(&mut buffers)?;
+        Ok(result)
+    }
+
+    /// Append buffered records to their tile files, truncating files left by
+    /// earlier runs on first write, and empty the buffers
+    fn flush(&mut self, buffers: &mut HashMap<Tile, Vec<u8>>) -> Result<(), AppError> {
+        for (tile, bytes) in buffers.drain() {
+            let path = self.tile_path(tile);
+            let count = self.tiles.entry(tile).or_insert(0);
+            OpenOptions::new()
+                .create(true)
+                .write(true)
+                .append(*count > 0)
+                .truncate(*count == 0)
+                .open(&path)
+                .and_then(|mut file| file.write_all(&bytes))
+                .map_err(|error| AppError::OutputError {
+                    path: path.display().to_string(),
+                    source: error,
+                })?;
+            *count += bytes.len() / CACHED_POINT_SIZE;
+        }
+        Ok(())
+    }
+
+    fn output_error(&self, error: std::io::Error) -> AppError {
+        AppError::OutputError {
+            path: self.directory.display().to_string(),
+            source: error,
+        }
+    }
+
+    fn tile_of(&self, point: &Point) -> Tile {
+        let (x, y) = point.coordinates;
+        let size = i64::from(self.tile_size);
+        (
+            i64::from(x).div_euclid(size) as i32,
+            i64::from(y).div_euclid(size) as i32,
+        )
+    }
+
+    fn tile_path(&self, (column, row): Tile) -> PathBuf {
+        self.directory.join(format!("tile_{column}_{row}.bin"))
+    }
+
+    /// Points of `tile`, which is empty unless it was written
+    fn read_tile(&self, tile: Tile) -> Result<Vec<Point>, AppError> {
+        if !self.tiles.contains_key(&tile) {
+            return Ok(vec![]);
+        }
+        let path = self.tile_path(tile);
+        let bytes = fs::read(&path).map_err(|error| AppError::InvalidFileError {
+            expected_path: path.display().to_string(),
+            source: error,
+        })?;
+        points_from_records(&bytes)
+    }
+
+    /// Number of points partitioned
+    pub fn len(&self) -> usize {
+        self.tiles.values().sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+
+    /// Number of tiles holding at least one point
+    pub fn tile_count(&self) -> usize {
+        self.tiles.len()
+    }
+
+    /// Second pass: for every point, call `visit` with the point and the
+    /// points it sees, within a segment of radius `arc_radius`, spanning left
+    /// and right from its direction by `half_arc_central_angle` degrees, as
+    /// for [`visible_points_from_neighbours`](crate::domain::visible_points_from_neighbours).
+    /// Results stream as each tile is processed, tile by tile, in column then
+    /// row order, and in file order within a tile.  Visible points are in
+    /// ascending number order.
+    ///
+    /// Returns an error if `arc_radius` is greater than the tile size, since
+    /// visible points could then lie beyond the neighbouring tiles, or if
+    /// `visit` returns one.
+    pub fn for_each_visible<F>(
+        &self,
+        half_arc_central_angle: u32,
+        arc_radius: u32,
+        mut visit: F,
+    ) -> Result<(), AppError>
+    where
+        F: FnMut(&Point, Vec<&Point>) -> Result<(), AppError>,
+    {
+        if arc_radius > self.tile_size {
+            return Err(AppError::ArgumentError(format!(
+                "radius {arc_radius} is greater than tile size {}",
+                self.tile_size
+            )));
+        }
+        let _span = Span::enter("partition_visible_points", || {
+            format!("{} points in {} tiles", self.len(), self.tiles.len())
+        });
+        for &(column, row) in self.tiles.keys() {
+            let tile = self.read_tile((column, row))?;
+            let mut neighbourhood = vec![];
+            for neighbour_column in column.saturating_sub(1)..=column.saturating_add(1) {
+                for neighbour_row in row.saturating_sub(1)..=row.saturating_add(1) {
+                    neighbourhood.extend(self.read_tile((neighbour_column, neighbour_row))?);
+                }
+            }
+            for point in &tile {
+                visit(
+                    point,
+                    close_neighbours(point, half_arc_central_angle, arc_radius, &neighbourhood),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Delete the tile files, leaving the directory in place
+    pub fn remove_files(self) -> Result<(), AppError> {
+        for tile in self.tiles.keys() {
+            let path = self.tile_path(*tile);
+            fs::remove_file(&path).map_err(|error| AppError::OutputError {
+                path: path.display().to_string(),
+                source: error,
+            })?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Partition;
+    use crate::{
+        domain::{parse_points_file, visible_points_from_neighbours},
+        utilities::AppError,
+    };
+    use std::env;
+
+    #[test]
+    fn partition_matches_in_memory_visibility() -> Result<(), AppError> {
+        // arrange
+        let path = "./fixtures/valid_points.ndjson";
+        let points = parse_points_file(path)?;
+        let directory = env::temp_dir().join(format!("neighbours_tiles_{}", std::process::id()));
+
+        // act
+        let partition = Partition::create(path, 20, &directory)?;
+        let mut outcome = vec![];
+        partition.for_each_visible(45, 20, |point, visible| {
+            outcome.push((point.number, visible.len()));
+            Ok(())
+        })?;
+
+        // assert
+        assert_eq!(partition.len(), points.len());
+        assert!(partition.tile_count() > 1);
+        outcome.sort_unstable();
+        let mut expected: Vec<(u32, usize)> = points
+            .iter()
+            .map(|point| {
+                (
+                    point.number,
+                    visible_points_from_neighbours(point.number, 45, 20, &points).len(),
+                )
+            })
+            .collect();
+        expected.sort_unstable();
+        assert_eq!(outcome, expected);
+        assert!(partition.for_each_visible(45, 21, |_, _| Ok(())).is_err());
+        assert!(Partition::create(path, 0, &directory).is_err());
+        partition.remove_files()?;
+        assert_eq!(
+            std::fs::read_dir(&directory).map(Iterator::count).ok(),
+            Some(0)
+        );
+        std::fs::remove_dir(&directory).ok();
+        Ok(())
+    }
+}
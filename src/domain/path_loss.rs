@@ -0,0 +1,75 @@
+use crate::domain::viewshed::SPEED_OF_LIGHT;
+use std::f64::consts::PI;
+
+/// How radio signals weaken along a link, for screening link budgets
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PathLoss {
+    /// Loss from the signal spreading out through free space
+    FreeSpace,
+
+    /// Free space loss out to `reference_distance`, growing with the
+    /// `exponent` power of distance beyond, for cluttered surroundings, such
+    /// as `3` in towns
+    LogDistance {
+        exponent: f64,
+        reference_distance: f64,
+    },
+}
+
+impl Default for PathLoss {
+    fn default() -> Self {
+        PathLoss::FreeSpace
+    }
+}
+
+impl PathLoss {
+    /// Loss, in dB, over a link `distance` metres long, at `frequency` MHz,
+    /// or `None` for a link of no length
+    pub fn loss(&self, distance: f64, frequency: f64) -> Option<f64> {
+        if distance <= 0.0 {
+            return None;
+        }
+        Some(match *self {
+            PathLoss::LogDistance {
+                exponent,
+                reference_distance,
+            } if distance > reference_distance => {
+                free_space_path_loss(reference_distance, frequency)
+                    + 10.0 * exponent * (distance / reference_distance).log10()
+            }
+            _ => free_space_path_loss(distance, frequency),
+        })
+    }
+}
+
+/// Free space path loss, in dB, over `distance` metres, at `frequency` MHz
+pub fn free_space_path_loss(distance: f64, frequency: f64) -> f64 {
+    20.0 * (4.0 * PI * distance * frequency / SPEED_OF_LIGHT).log10()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{free_space_path_loss, PathLoss};
+
+    #[test]
+    fn path_loss_gives_expected_result() {
+        // arrange
+        let log_distance = PathLoss::LogDistance {
+            exponent: 3.0,
+            reference_distance: 100.0,
+        };
+
+        // act
+        let outcome = PathLoss::FreeSpace.loss(1_000.0, 2_400.0).unwrap();
+
+        // assert
+        assert!((outcome - 100.05).abs() < 0.01);
+        assert_eq!(outcome, free_space_path_loss(1_000.0, 2_400.0));
+        assert!((log_distance.loss(1_000.0, 2_400.0).unwrap() - 110.05).abs() < 0.01);
+        assert_eq!(
+            log_distance.loss(50.0, 2_400.0),
+            PathLoss::FreeSpace.loss(50.0, 2_400.0)
+        );
+        assert_eq!(PathLoss::default().loss(0.0, 2_400.0), None);
+    }
+}
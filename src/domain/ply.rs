@@ -0,0 +1,452 @@
+//! Reader for PLY point clouds, such as lidar scans.  Points come from the
+//! `x` and `y` properties of each vertex, in ASCII or binary PLY, with
+//! coordinates rounded to whole units.  Numbers and directions come from the
+//! properties named in a mapping such as `number=id,direction=heading`, with
+//! directions as headings in degrees, clockwise from north.  Without a number
+//! property, vertices are numbered from `1`, in file order, and without a
+//! direction property, they face north.
+
+use crate::{
+    domain::point::{Direction, Point},
+    utilities::{AppError, Span},
+};
+use std::{convert::TryFrom, fs, path::Path};
+
+/// Returns true if `path` has a `.ply` extension
+pub(crate) fn is_ply_path(path: &Path) -> bool {
+    path.extension().and_then(|value| value.to_str()) == Some("ply")
+}
+
+pub(crate) fn point_cloud_error(message: String) -> AppError {
+    AppError::PointCloudError(message)
+}
+
+/// Point cloud attributes holding point numbers and directions, if any
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct CloudFields {
+    pub(crate) number: Option<String>,
+    pub(crate) direction: Option<String>,
+}
+
+impl CloudFields {
+    /// Parse a mapping such as `number=id,direction=heading`, where either
+    /// entry may be left out
+    pub(crate) fn parse(value: &str) -> Result<Self, AppError> {
+        let mut result = CloudFields::default();
+        for entry in value.split(',').filter(|entry| !entry.trim().is_empty()) {
+            match entry.split_once('=').map(|(key, field)| (key.trim(), field.trim())) {
+                Some(("number", field)) if !field.is_empty() => {
+                    result.number = Some(field.to_string())
+                }
+                Some(("direction", field)) if !field.is_empty() => {
+                    result.direction = Some(field.to_string())
+                }
+                _ => {
+                    return Err(point_cloud_error(format!(
+                        "expected an attribute mapping such as `number=id,direction=heading`, but got `{value}`"
+                    )))
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    /// Point numbered `number`, or else `index + 1`, at `x` and `y`, facing
+    /// along `heading`, in degrees, if given, or else north
+    pub(crate) fn point(
+        &self,
+        index: usize,
+        (x, y): (f64, f64),
+        number: Option<f64>,
+        heading: Option<f64>,
+    ) -> Result<Point, AppError> {
+        let coordinate = |value: f64| {
+            let rounded = value.round();
+            if rounded >= f64::from(i32::MIN) && rounded <= f64::from(i32::MAX) {
+                Ok(rounded as i32)
+            } else {
+                Err(point_cloud_error(format!(
+                    "point {} has coordinate {value}, out of range",
+                    index + 1
+                )))
+            }
+        };
+        let number = match number {
+            Some(value) if value.fract() == 0.0 && value >= 0.0 && value <= f64::from(u32::MAX) => {
+                value as u32
+            }
+            Some(value) => {
+                return Err(point_cloud_error(format!(
+                    "point {} has number {value}, which is not a whole number from 0 to {}",
+                    index + 1,
+                    u32::MAX
+                )))
+            }
+            None => u32::try_from(index + 1)
+                .map_err(|_| point_cloud_error(String::from("too many points to number")))?,
+        };
+        let direction = match heading {
+            Some(value) if value.is_finite() => {
+                let (dx, dy) = value.to_radians().sin_cos();
+                Direction::Vector { dx, dy }
+            }
+            Some(value) => {
+                return Err(point_cloud_error(format!(
+                    "point {} has heading {value}",
+                    index + 1
+                )))
+            }
+            None => Direction::North,
+        };
+        Ok(Point {
+            coordinates: (coordinate(x)?, coordinate(y)?),
+            number,
+            direction,
+        })
+    }
+}
+
+/// Encoding of the body of a PLY file
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+/// Scalar property types, with their sizes in bytes
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Scalar {
+    Int8,
+    UInt8,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Float32,
+    Float64,
+}
+
+impl Scalar {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "char" | "int8" => Some(Scalar::Int8),
+            "uchar" | "uint8" => Some(Scalar::UInt8),
+            "short" | "int16" => Some(Scalar::Int16),
+            "ushort" | "uint16" => Some(Scalar::UInt16),
+            "int" | "int32" => Some(Scalar::Int32),
+            "uint" | "uint32" => Some(Scalar::UInt32),
+            "float" | "float32" => Some(Scalar::Float32),
+            "double" | "float64" => Some(Scalar::Float64),
+            _ => None,
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Scalar::Int8 | Scalar::UInt8 => 1,
+            Scalar::Int16 | Scalar::UInt16 => 2,
+            Scalar::Int32 | Scalar::UInt32 | Scalar::Float32 => 4,
+            Scalar::Float64 => 8,
+        }
+    }
+
+    /// Value of this type at the start of `bytes`, which holds at least
+    /// [`size`](Scalar::size) bytes
+    fn read(self, bytes: &[u8], format: Format) -> f64 {
+        macro_rules! number {
+            ($type:ty, $size:expr) => {{
+                let mut buffer = [0_u8; $size];
+                buffer.copy_from_slice(&bytes[..$size]);
+                if format == Format::BinaryBigEndian {
+                    f64::from(<$type>::from_be_bytes(buffer))
+                } else {
+                    f64::from(<$type>::from_le_bytes(buffer))
+                }
+            }};
+        }
+        match self {
+            Scalar::Int8 => number!(i8, 1),
+            Scalar::UInt8 => number!(u8, 1),
+            Scalar::Int16 => number!(i16, 2),
+            Scalar::UInt16 => number!(u16, 2),
+            Scalar::Int32 => number!(i32, 4),
+            Scalar::UInt32 => number!(u32, 4),
+            Scalar::Float32 => number!(f32, 4),
+            Scalar::Float64 => {
+                let mut buffer = [0_u8; 8];
+                buffer.copy_from_slice(&bytes[..8]);
+                if format == Format::BinaryBigEndian {
+                    f64::from_be_bytes(buffer)
+                } else {
+                    f64::from_le_bytes(buffer)
+                }
+            }
+        }
+    }
+}
+
+/// What a PLY header says of the vertices which follow it
+struct Header {
+    format: Format,
+    vertices: usize,
+
+    /// Name and type of each vertex property, in order
+    properties: Vec<(String, Scalar)>,
+
+    /// Offset of the body, following the header
+    body: usize,
+}
+
+/// Parse the PLY header at the start of `bytes`
+fn parse_header(bytes: &[u8]) -> Result<Header, AppError> {
+    const END: &[u8] = b"end_header";
+    let end = bytes
+        .windows(END.len())
+        .position(|window| window == END)
+        .ok_or_else(|| point_cloud_error(String::from("missing `end_header` line")))?;
+    let body = match bytes[end + END.len()..]
+        .iter()
+        .position(|byte| *byte == b'\n')
+    {
+        Some(offset) => end + END.len() + offset + 1,
+        None => bytes.len(),
+    };
+    let header = std::str::from_utf8(&bytes[..end])
+        .map_err(|_| point_cloud_error(String::from("header is not valid UTF-8")))?;
+    let mut lines = header.lines().map(str::trim);
+    if lines.next() != Some("ply") {
+        return Err(point_cloud_error(String::from(
+            "missing `ply` line, so not a PLY file",
+        )));
+    }
+    let mut format = None;
+    let mut vertices: Option<usize> = None;
+    let mut properties = vec![];
+    let mut element: Option<&str> = None;
+    for line in lines {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            [] | ["comment", ..] | ["obj_info", ..] => {}
+            ["format", encoding, "1.0"] => {
+                format = Some(match *encoding {
+                    "ascii" => Format::Ascii,
+                    "binary_little_endian" => Format::BinaryLittleEndian,
+                    "binary_big_endian" => Format::BinaryBigEndian,
+                    _ => {
+                        return Err(point_cloud_error(format!(
+                            "unsupported format `{encoding}`"
+                        )))
+                    }
+                })
+            }
+            ["element", name, count] => {
+                if element.is_none() && *name != "vertex" {
+                    return Err(point_cloud_error(format!(
+                        "expected the `vertex` element first, but found `{name}`"
+                    )));
+                }
+                if *name == "vertex" {
+                    vertices = Some(count.parse().map_err(|_| {
+                        point_cloud_error(format!("invalid vertex count `{count}`"))
+                    })?);
+                }
+                element = Some(name);
+            }
+            ["property", kind, name] if element == Some("vertex") => {
+                let scalar = Scalar::parse(kind).ok_or_else(|| {
+                    point_cloud_error(format!("unsupported property type `{kind}`"))
+                })?;
+                properties.push((name.to_string(), scalar));
+            }
+            ["property", ..] if element.is_some() => {
+                if element == Some("vertex") {
+                    return Err(point_cloud_error(format!(
+                        "unsupported vertex property `{line}`"
+                    )));
+                }
+            }
+            _ => {
+                return Err(point_cloud_error(format!(
+                    "unexpected header line `{line}`"
+                )))
+            }
+        }
+    }
+    let format = format.ok_or_else(|| point_cloud_error(String::from("missing `format` line")))?;
+    let vertices =
+        vertices.ok_or_else(|| point_cloud_error(String::from("missing `vertex` element")))?;
+    Ok(Header {
+        format,
+        vertices,
+        properties,
+        body,
+    })
+}
+
+/// Parse points from the vertices of the PLY point cloud in `bytes`, taking
+/// numbers and directions from the properties named in `fields`, a mapping
+/// such as `number=id,direction=heading`, if given
+pub fn parse_points_ply(bytes: &[u8], fields: Option<&str>) -> Result<Vec<Point>, AppError> {
+    let _span = Span::enter("parse_points_ply", || format!("{} bytes", bytes.len()));
+    let fields = match fields {
+        Some(value) => CloudFields::parse(value)?,
+        None => CloudFields::default(),
+    };
+    let Header {
+        format,
+        vertices,
+        properties,
+        body,
+    } = parse_header(bytes)?;
+    let column = |name: &str| {
+        properties
+            .iter()
+            .position(|(property, _)| property == name)
+            .ok_or_else(|| point_cloud_error(format!("missing vertex property `{name}`")))
+    };
+    let (x, y) = (column("x")?, column("y")?);
+    let number = fields.number.as_deref().map(column).transpose()?;
+    let direction = fields.direction.as_deref().map(column).transpose()?;
+
+    let stride: usize = properties.iter().map(|(_, scalar)| scalar.size()).sum();
+
+    // headers may claim more vertices than the body holds, so reserve only as
+    // many as fit, with each ASCII value taking a digit and a separator
+    let least_vertex_size = match format {
+        Format::Ascii => 2 * properties.len(),
+        _ => stride,
+    };
+    let mut values = vec![0.0; properties.len()];
+    let mut result = Vec::with_capacity(vertices.min((bytes.len() - body) / least_vertex_size));
+    let mut ascii_lines = match format {
+        Format::Ascii => Some(
+            std::str::from_utf8(&bytes[body..])
+                .map_err(|_| point_cloud_error(String::from("body is not valid UTF-8")))?
+                .lines()
+                .filter(|line| !line.trim().is_empty()),
+        ),
+        _ => None,
+    };
+    for index in 0..vertices {
+        let truncated = || point_cloud_error(format!("vertex {} is missing", index + 1));
+        match ascii_lines.as_mut() {
+            Some(lines) => {
+                let words: Vec<&str> = lines
+                    .next()
+                    .ok_or_else(truncated)?
+                    .split_whitespace()
+                    .collect();
+                if words.len() < properties.len() {
+                    return Err(truncated());
+                }
+                for (value, word) in values.iter_mut().zip(words) {
+                    *value = word.parse().map_err(|_| {
+                        point_cloud_error(format!(
+                            "vertex {} has invalid value `{word}`",
+                            index + 1
+                        ))
+                    })?;
+                }
+            }
+            None => {
+                let start = body + index * stride;
+                let record = bytes.get(start..start + stride).ok_or_else(truncated)?;
+                let mut offset = 0;
+                for (value, (_, scalar)) in values.iter_mut().zip(&properties) {
+                    *value = scalar.read(&record[offset..], format);
+                    offset += scalar.size();
+                }
+            }
+        }
+        result.push(fields.point(
+            index,
+            (values[x], values[y]),
+            number.map(|column| values[column]),
+            direction.map(|column| values[column]),
+        )?);
+    }
+    Ok(result)
+}
+
+/// Parse points from the PLY file at `path`, as for [`parse_points_ply`]
+pub(crate) fn parse_points_ply_file(
+    path: &Path,
+    fields: Option<&str>,
+) -> Result<Vec<Point>, AppError> {
+    let bytes = fs::read(path).map_err(|error| AppError::BinaryFileError {
+        path: path.display().to_string(),
+        source: error,
+    })?;
+    parse_points_ply(&bytes, fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_points_ply;
+    use crate::domain::{Direction, Point};
+
+    #[test]
+    fn parse_points_ply_reads_ascii_and_binary_clouds() {
+        // arrange
+        let ascii = b"ply\nformat ascii 1.0\ncomment lidar\nelement vertex 2\nproperty float x\nproperty float y\nproperty float z\nproperty uint id\nproperty float heading\nelement face 0\nproperty list uchar int vertex_indices\nend_header\n1.4 -2.6 0.5 7 90\n10 20 1 8 0\n";
+        let mut binary = b"ply\nformat binary_little_endian 1.0\nelement vertex 2\nproperty double x\nproperty double y\nproperty uchar intensity\nend_header\n".to_vec();
+        for (x, y, intensity) in [(3.0_f64, 4.0_f64, 200_u8), (-5.5, 6.0, 10)] {
+            binary.extend_from_slice(&x.to_le_bytes());
+            binary.extend_from_slice(&y.to_le_bytes());
+            binary.push(intensity);
+        }
+
+        // act
+        let outcome = parse_points_ply(ascii, Some("number=id,direction=heading")).unwrap();
+
+        // assert
+        assert_eq!(outcome[0].coordinates, (1, -3));
+        assert_eq!(outcome[0].number, 7);
+        assert!((outcome[0].direction.bearing().to_degrees() - 90.0).abs() < 1e-9);
+        assert_eq!(outcome[1].number, 8);
+        assert_eq!(
+            parse_points_ply(&binary, None).unwrap(),
+            vec![
+                Point {
+                    coordinates: (3, 4),
+                    number: 1,
+                    direction: Direction::North,
+                },
+                Point {
+                    coordinates: (-6, 6),
+                    number: 2,
+                    direction: Direction::North,
+                },
+            ]
+        );
+        assert_eq!(
+            parse_points_ply(&binary, Some("number=id"))
+                .unwrap_err()
+                .to_string(),
+            "Error reading point cloud: missing vertex property `id`"
+        );
+        assert!(parse_points_ply(&binary[..binary.len() - 1], None).is_err());
+    }
+
+    #[test]
+    fn parse_points_ply_rejects_more_vertices_than_body_holds() {
+        // arrange
+        let ascii = b"ply\nformat ascii 1.0\nelement vertex 100000000000000\nproperty float x\nproperty float y\nend_header\n1 2\n";
+        let mut binary = b"ply\nformat binary_little_endian 1.0\nelement vertex 100000000000000\nproperty float x\nproperty float y\nend_header\n".to_vec();
+        binary.extend_from_slice(&[0; 8]);
+
+        // act
+        let outcome = parse_points_ply(ascii, None);
+
+        // assert
+        assert_eq!(
+            outcome.unwrap_err().to_string(),
+            "Error reading point cloud: vertex 2 is missing"
+        );
+        assert_eq!(
+            parse_points_ply(&binary, None).unwrap_err().to_string(),
+            "Error reading point cloud: vertex 2 is missing"
+        );
+    }
+}
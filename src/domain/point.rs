@@ -1,26 +1,214 @@
-use crate::utilities::AppError;
-use serde::Deserialize;
+use crate::{
+    domain::{
+        flatgeobuf::{is_flatgeobuf_path, parse_points_flatgeobuf_file},
+        geometry::BoundingBox,
+        gpx::{is_gpx_path, parse_points_gpx_file},
+        ndjson::{is_ndjson_path, parse_points_ndjson_file},
+        ply::{is_ply_path, parse_points_ply_file},
+        protobuf::{is_protobuf_path, parse_points_protobuf_file},
+        sqlite::{is_sqlite_path, parse_points_sqlite_file, DEFAULT_SQLITE_QUERY},
+    },
+    utilities::{AppError, Span},
+};
+use serde::{Deserialize, Serialize};
 use std::{
+    collections::HashMap,
     f64::consts::{FRAC_PI_2, PI},
-    fs::read_to_string,
+    fs::{metadata, read_to_string, File},
+    io::{self, Read},
     path::Path,
 };
 
-/// Represents direction faced by a point
-#[derive(Copy, Clone, Debug, Deserialize, PartialEq)]
+/// Bytes read at a time from JSON points files
+const READ_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Represents direction faced by a point, either one of the compass points, or
+/// a vector, such as a normalised velocity, read from input files as
+/// `{"dx": 0.6, "dy": 0.8}`, which keeps the precision a compass point loses,
+/// or towards another point, read as `{"towards": 12}`
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(try_from = "DirectionInput", into = "DirectionInput")]
 pub enum Direction {
     North,
     East,
     South,
     West,
+
+    /// Direction along `dx` east and `dy` north, which need not be a unit
+    /// vector, though not both zero
+    Vector {
+        dx: f64,
+        dy: f64,
+    },
+
+    /// Direction towards the point with this number, until resolved to a
+    /// vector by [`resolve_directions`], which parsing a points file does, or
+    /// by [`resolve_direction`], against a neighbourhood.  Points passed on
+    /// one at a time as an NDJSON file streams are left unresolved, and face
+    /// north, while live updates and stored positions facing another point
+    /// are rejected.
+    Towards(u32),
+}
+
+impl Direction {
+    /// Bearing the direction faces in radians, measured clockwise, with `0`
+    /// indicating north.
+    pub fn bearing(&self) -> f64 {
+        match self {
+            Direction::North => 0.0,
+            Direction::East => FRAC_PI_2,
+            Direction::South => PI,
+            Direction::West => 3.0 * FRAC_PI_2,
+            Direction::Vector { dx, dy } => dx.atan2(*dy).rem_euclid(2.0 * PI),
+            Direction::Towards(_) => 0.0,
+        }
+    }
+
+    /// Returns false for a direction still towards another point, which has
+    /// no bearing of its own until resolved
+    pub fn is_resolved(&self) -> bool {
+        !matches!(self, Direction::Towards(_))
+    }
+}
+
+/// Direction as written in input files, either named, as a vector or as a
+/// point to face
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum DirectionInput {
+    Named(String),
+    Vector { dx: f64, dy: f64 },
+    Towards { towards: u32 },
+}
+
+impl TryFrom<DirectionInput> for Direction {
+    type Error = String;
+
+    fn try_from(input: DirectionInput) -> Result<Self, Self::Error> {
+        match input {
+            DirectionInput::Named(name) => match name.as_str() {
+                "North" => Ok(Direction::North),
+                "East" => Ok(Direction::East),
+                "South" => Ok(Direction::South),
+                "West" => Ok(Direction::West),
+                _ => Err(format!(
+                    "unknown direction `{name}`, expected `North`, `East`, `South`, `West`, a vector or a point to face"
+                )),
+            },
+            DirectionInput::Vector { dx, dy } => {
+                if !(dx.is_finite() && dy.is_finite()) || (dx == 0.0 && dy == 0.0) {
+                    Err(format!(
+                        "direction vector ({dx}, {dy}) should be finite and not zero"
+                    ))
+                } else {
+                    Ok(Direction::Vector { dx, dy })
+                }
+            }
+            DirectionInput::Towards { towards } => Ok(Direction::Towards(towards)),
+        }
+    }
+}
+
+impl From<Direction> for DirectionInput {
+    fn from(direction: Direction) -> Self {
+        match direction {
+            Direction::North => DirectionInput::Named("North".to_string()),
+            Direction::East => DirectionInput::Named("East".to_string()),
+            Direction::South => DirectionInput::Named("South".to_string()),
+            Direction::West => DirectionInput::Named("West".to_string()),
+            Direction::Vector { dx, dy } => DirectionInput::Vector { dx, dy },
+            Direction::Towards(towards) => DirectionInput::Towards { towards },
+        }
+    }
 }
 
-/// Represents a point as used internally
+/// `direction` of the point numbered `number`, at `coordinates`, with a
+/// direction towards another point replaced by the vector to that point's
+/// position, as `position` gives it
+fn resolved_direction<F: Fn(u32) -> Option<(i32, i32)>>(
+    number: u32,
+    coordinates: (i32, i32),
+    direction: Direction,
+    position: F,
+) -> Result<Direction, AppError> {
+    let target = match direction {
+        Direction::Towards(target) => target,
+        other => return Ok(other),
+    };
+    let (x, y) = position(target).ok_or_else(|| {
+        AppError::FacingError(format!(
+            "point {number} faces point {target}, which is not in the points input"
+        ))
+    })?;
+    if (x, y) == coordinates {
+        return Err(AppError::FacingError(format!(
+            "point {number} faces point {target}, at the same position"
+        )));
+    }
+    Ok(Direction::Vector {
+        dx: f64::from(x) - f64::from(coordinates.0),
+        dy: f64::from(y) - f64::from(coordinates.1),
+    })
+}
+
+/// Replace each direction of `points` given as towards another point, such as
+/// a camera aimed at a gate, with the vector from the point to that point.
+/// Fails if the point faced is missing, or at the same position.
+pub fn resolve_directions(points: &mut [Point]) -> Result<(), AppError> {
+    let positions: HashMap<u32, (i32, i32)> = points
+        .iter()
+        .map(|point| (point.number, point.coordinates))
+        .collect();
+    for point in points.iter_mut() {
+        point.direction =
+            resolved_direction(point.number, point.coordinates, point.direction, |target| {
+                positions.get(&target).copied()
+            })?;
+    }
+    Ok(())
+}
+
+/// `point`, with a direction towards another point replaced by the vector to
+/// the first point of `neighbourhood` with that number, for a point joining
+/// or moving within an existing neighbourhood.  Fails if the point faced is
+/// missing, or at the same position.
+pub fn resolve_direction(point: Point, neighbourhood: &[Point]) -> Result<Point, AppError> {
+    let direction =
+        resolved_direction(point.number, point.coordinates, point.direction, |target| {
+            neighbourhood
+                .iter()
+                .find(|Point { number, .. }| *number == target)
+                .map(|Point { coordinates, .. }| *coordinates)
+        })?;
+    Ok(Point { direction, ..point })
+}
+
+/// Replace directions of `points` given as towards another point, as for
+/// [`resolve_directions`], keeping every other field of each record
+fn resolve_input_directions(points: &mut [InputPoint]) -> Result<(), AppError> {
+    let positions: HashMap<u32, (i32, i32)> = points
+        .iter()
+        .map(|point| (point.number, (point.x, point.y)))
+        .collect();
+    for point in points.iter_mut() {
+        point.direction = resolved_direction(
+            point.number,
+            (point.x, point.y),
+            point.direction,
+            |target| positions.get(&target).copied(),
+        )?;
+    }
+    Ok(())
+}
+
+/// Represents a point as used internally.  Points are identified by a `u32`
+/// number, as in input files, unless another identifier type is chosen, such
+/// as an entity handle or UUID, for use with [`visible_neighbours`].
 #[derive(Copy, Clone, Debug, PartialEq)]
-pub struct Point {
+pub struct Point<Id = u32> {
     /// x,y coordinates of the point
     pub coordinates: (i32, i32),
-    pub number: u32,
+    pub number: Id,
     pub direction: Direction,
 }
 
@@ -31,6 +219,106 @@ pub struct InputPoint {
     pub y: i32,
     pub number: u32,
     pub direction: Direction,
+
+    /// Time the point was observed, in seconds since the Unix epoch, used to
+    /// ignore stale points, see [`ObservedPoint`](crate::domain::ObservedPoint)
+    #[serde(default)]
+    pub observed_at: Option<u64>,
+
+    /// Velocity along x, in units per second, used to estimate time to
+    /// contact, see [`MovingPoint`](crate::domain::MovingPoint)
+    #[serde(default)]
+    pub vx: Option<f64>,
+
+    /// Velocity along y, in units per second
+    #[serde(default)]
+    pub vy: Option<f64>,
+
+    /// Full width of the point’s own field of view, in degrees, used in place
+    /// of the global angle when the point looks out, see
+    /// [`SensorPoint`](crate::domain::SensorPoint)
+    #[serde(default)]
+    pub fov_degrees: Option<f64>,
+
+    /// Point’s own viewing range, used in place of the global radius
+    #[serde(default)]
+    pub range: Option<f64>,
+
+    /// Height of the thing the point stands for, such as a bollard or a mast,
+    /// used in occlusion mode, see [`SolidPoint`](crate::domain::SolidPoint)
+    #[serde(default)]
+    pub height: Option<f64>,
+
+    /// Height of the point above the plan, in 3D mode, see
+    /// [`SpacePoint`](crate::domain::SpacePoint)
+    #[serde(default)]
+    pub z: Option<f64>,
+
+    /// Heading, in degrees clockwise from north, of a sensor in 3D mode, in
+    /// place of `direction`, see [`Orientation`](crate::domain::Orientation)
+    #[serde(default)]
+    pub yaw: Option<f64>,
+
+    /// Angle, in degrees, a sensor in 3D mode is tilted up, or down if
+    /// negative
+    #[serde(default)]
+    pub pitch: Option<f64>,
+
+    /// Angle, in degrees, a sensor in 3D mode is rolled, clockwise as seen
+    /// from behind
+    #[serde(default)]
+    pub roll: Option<f64>,
+
+    /// Orientation of a sensor in 3D mode, as a unit quaternion `[x, y, z, w]`,
+    /// in place of `yaw`, `pitch` and `roll`
+    #[serde(default)]
+    pub quaternion: Option<[f64; 4]>,
+}
+
+impl From<InputPoint> for Point {
+    fn from(
+        InputPoint {
+            x,
+            y,
+            number,
+            direction,
+            ..
+        }: InputPoint,
+    ) -> Self {
+        Point {
+            coordinates: (x, y),
+            number,
+            direction,
+        }
+    }
+}
+
+impl From<Point> for InputPoint {
+    fn from(
+        Point {
+            coordinates: (x, y),
+            number,
+            direction,
+        }: Point,
+    ) -> Self {
+        InputPoint {
+            x,
+            y,
+            number,
+            direction,
+            observed_at: None,
+            vx: None,
+            vy: None,
+            fov_degrees: None,
+            range: None,
+            height: None,
+            z: None,
+            yaw: None,
+            pitch: None,
+            roll: None,
+            quaternion: None,
+        }
+    }
 }
 
 /// List of points as found in a points JSON file
@@ -39,51 +327,485 @@ pub struct PointList {
     pub points: Vec<InputPoint>,
 }
 
-/// Helper function for parsing a JSON file of points into a [`Vec`] of
+/// Helper function for parsing a JSON string of points into a [`Vec`] of
 /// [`Point`]s
-pub fn parse_points_file<P: AsRef<Path>>(path: P) -> Result<Vec<Point>, AppError> {
-    let path_ref = path.as_ref();
-    let json = match read_to_string(path_ref) {
-        Ok(value) => value,
+pub fn parse_points(json: &str) -> Result<Vec<Point>, AppError> {
+    let _span = Span::enter("parse_points", || format!("{} bytes", json.len()));
+    let result: Vec<Point> = parse_input_points(json)?
+        .into_iter()
+        .map(Point::from)
+        .collect();
+    Ok(result)
+}
+
+/// Parse a points JSON string, keeping every field of each record, with
+/// directions towards other points resolved, as for [`resolve_directions`]
+pub(crate) fn parse_input_points(json: &str) -> Result<Vec<InputPoint>, AppError> {
+    let PointList { mut points } = serde_json::from_str(json).map_err(AppError::JSONParseError)?;
+    resolve_input_directions(&mut points)?;
+    Ok(points)
+}
+
+/// File read through, counting bytes read, and reporting them to `progress`,
+/// if given, after each read
+struct ProgressReader<'a, R> {
+    inner: R,
+    read: usize,
+    total: usize,
+    progress: Option<&'a mut dyn FnMut(usize, usize)>,
+}
+
+impl<R: Read> Read for ProgressReader<'_, R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let length = self.inner.read(buffer)?;
+        if length > 0 {
+            self.read += length;
+            if let Some(report) = self.progress.as_mut() {
+                report(self.read, self.total.max(self.read));
+            }
+        }
+        Ok(length)
+    }
+}
+
+/// Parse the points JSON file at `path`, keeping every field of each record,
+/// as for [`parse_input_points`].  The file is parsed as it is read, in
+/// chunks, rather than read into memory whole first, and `progress`, if
+/// given, is called with the number of bytes read so far, and the file size,
+/// after each chunk.
+fn read_input_points_json(
+    path: &Path,
+    progress: Option<&mut dyn FnMut(usize, usize)>,
+) -> Result<Vec<InputPoint>, AppError> {
+    let _span = Span::enter("read_points_json", || path.display().to_string());
+    let invalid_file_error = |error| AppError::InvalidFileError {
+        expected_path: path.display().to_string(),
+        source: error,
+    };
+    let file = File::open(path).map_err(invalid_file_error)?;
+    let total = file
+        .metadata()
+        .map(|value| value.len() as usize)
+        .unwrap_or(0);
+    let reader = io::BufReader::with_capacity(
+        READ_CHUNK_SIZE,
+        ProgressReader {
+            inner: file,
+            read: 0,
+            total,
+            progress,
+        },
+    );
+    let PointList { mut points } =
+        serde_json::from_reader(reader).map_err(|error| match error.io_error_kind() {
+            Some(kind) => invalid_file_error(io::Error::new(kind, error)),
+            None => AppError::JSONParseError(error),
+        })?;
+    resolve_input_directions(&mut points)?;
+    Ok(points)
+}
+
+/// Read the points JSON file at `path`, streaming it as for
+/// [`read_input_points_json`], or an NDJSON file, with one point object per
+/// line, keeping every field of each record
+pub(crate) fn read_input_points_file(path: &Path) -> Result<Vec<InputPoint>, AppError> {
+    if !is_ndjson_path(path) {
+        return read_input_points_json(path, None);
+    }
+    let input = read_points_file(path)?;
+    let mut points = parse_input_points_ndjson(&input)?;
+    resolve_input_directions(&mut points)?;
+    Ok(points)
+}
+
+/// Parse the points file at `path` into records built from every field of
+/// each point, such as [`ObservedPoint`](crate::domain::ObservedPoint) or
+/// [`MovingPoint`](crate::domain::MovingPoint).  Every format
+/// [`parse_points_file`] reads is accepted, but only JSON and NDJSON files
+/// carry fields beyond position, number and direction, so records from other
+/// formats have none of them, as if left out of a JSON file.
+pub fn parse_records_file<T: From<InputPoint>>(path: &Path) -> Result<Vec<T>, AppError> {
+    read_records_file(path, None)
+}
+
+/// Parse the points file at `path` into records, as for
+/// [`parse_records_file`], calling `progress` with the number of bytes read
+/// so far, and the file size, as for [`parse_points_file_with_progress`]
+pub fn parse_records_file_with_progress<T, F>(
+    path: &Path,
+    mut progress: F,
+) -> Result<Vec<T>, AppError>
+where
+    T: From<InputPoint>,
+    F: FnMut(usize, usize),
+{
+    read_records_file(path, Some(&mut progress))
+}
+
+/// Parse records within `bounds` from the file at `path`, as for
+/// [`parse_records_file`].  FlatGeobuf files with a spatial index are
+/// searched, as for [`parse_points_in`].
+pub fn parse_records_in<T: From<InputPoint>>(
+    path: &Path,
+    bounds: &BoundingBox,
+) -> Result<Vec<T>, AppError> {
+    if is_flatgeobuf_path(path) {
+        return Ok(parse_points_flatgeobuf_file(path, Some(bounds))?
+            .into_iter()
+            .map(|point| T::from(InputPoint::from(point)))
+            .collect());
+    }
+    let mut points: Vec<InputPoint> = read_records_file(path, None)?;
+    points.retain(|InputPoint { x, y, .. }| bounds.contains((*x, *y)));
+    Ok(points.into_iter().map(T::from).collect())
+}
+
+/// Parse the points file at `path` into records, reporting progress to
+/// `progress`, if given, as for [`parse_points_file_reporting`]
+fn read_records_file<T: From<InputPoint>>(
+    path: &Path,
+    progress: Option<&mut dyn FnMut(usize, usize)>,
+) -> Result<Vec<T>, AppError> {
+    let points = match PointsFormat::of(path) {
+        PointsFormat::Json => read_input_points_json(path, progress)?,
+        PointsFormat::Ndjson => read_input_points_file(path)?,
+        _ => parse_points_file_reporting(path, progress)?
+            .into_iter()
+            .map(InputPoint::from)
+            .collect(),
+    };
+    Ok(points.into_iter().map(T::from).collect())
+}
+
+/// Parse each non-blank line of `input` as a point object
+fn parse_input_points_ndjson(input: &str) -> Result<Vec<InputPoint>, AppError> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line).map_err(|error| AppError::NDJSONParseError {
+                line: index + 1,
+                source: error,
+            })
+        })
+        .collect()
+}
+
+/// Returns true if `path` names a JSON or NDJSON points file, whose records
+/// may carry optional fields beyond those of [`Point`]
+pub(crate) fn is_json_points_path(path: &Path) -> bool {
+    is_ndjson_path(path) || path.extension().and_then(|value| value.to_str()) == Some("json")
+}
+
+/// Helper function for reading a file to a string, mapping errors to
+/// [`AppError::InvalidFileError`]
+pub(crate) fn read_points_file(path: &Path) -> Result<String, AppError> {
+    let _span = Span::enter("read_points_file", || path.display().to_string());
+    match read_to_string(path) {
+        Ok(value) => Ok(value),
         Err(error) => {
-            let expected_path = path_ref.display().to_string();
-            return Err(AppError::InvalidFileError {
+            let expected_path = path.display().to_string();
+            Err(AppError::InvalidFileError {
                 expected_path,
                 source: error,
-            });
+            })
         }
-    };
-    let PointList { points } = serde_json::from_str(&json).map_err(AppError::JSONParseError)?;
-    let result: Vec<Point> = points
-        .into_iter()
-        .map(
-            |InputPoint {
-                 x,
-                 y,
-                 number,
-                 direction,
-             }| Point {
-                coordinates: (x, y),
-                number,
-                direction,
-            },
-        )
-        .collect();
+    }
+}
+
+/// Parse the Parquet file at `path`, when the `parquet` feature is enabled
+#[cfg(feature = "parquet")]
+fn parse_parquet_file(path: &Path) -> Result<Vec<Point>, AppError> {
+    crate::domain::parquet::parse_points_parquet_file(path)
+}
+
+#[cfg(not(feature = "parquet"))]
+fn parse_parquet_file(_path: &Path) -> Result<Vec<Point>, AppError> {
+    Err(AppError::ParquetError(String::from(
+        "Parquet input needs the `parquet` feature",
+    )))
+}
+
+/// Returns true if `path` has a `.parquet` extension
+fn is_parquet_path(path: &Path) -> bool {
+    path.extension().and_then(|value| value.to_str()) == Some("parquet")
+}
+
+/// Parse the point shapefile at `path`, with DBF field mapping `fields`, when
+/// the `shapefile` feature is enabled
+#[cfg(feature = "shapefile")]
+fn parse_shapefile(path: &Path, fields: Option<&str>) -> Result<Vec<Point>, AppError> {
+    use crate::domain::shapefile::{parse_points_shapefile_file, DEFAULT_DBF_FIELDS};
+    parse_points_shapefile_file(path, fields.unwrap_or(DEFAULT_DBF_FIELDS))
+}
+
+#[cfg(not(feature = "shapefile"))]
+fn parse_shapefile(_path: &Path, _fields: Option<&str>) -> Result<Vec<Point>, AppError> {
+    Err(AppError::ShapefileError(String::from(
+        "shapefile input needs the `shapefile` feature",
+    )))
+}
+
+/// Parse the LAS point cloud at `path`, with attribute mapping `fields`, when
+/// the `las` feature is enabled
+#[cfg(feature = "las")]
+fn parse_las(path: &Path, fields: Option<&str>) -> Result<Vec<Point>, AppError> {
+    crate::domain::las::parse_points_las_file(path, fields)
+}
+
+#[cfg(not(feature = "las"))]
+fn parse_las(_path: &Path, _fields: Option<&str>) -> Result<Vec<Point>, AppError> {
+    Err(AppError::PointCloudError(String::from(
+        "LAS input needs the `las` feature",
+    )))
+}
+
+/// Returns true if `path` has a `.las` extension
+fn is_las_path(path: &Path) -> bool {
+    path.extension().and_then(|value| value.to_str()) == Some("las")
+}
+
+/// Returns true if `path` has a `.shp` extension
+fn is_shapefile_path(path: &Path) -> bool {
+    path.extension().and_then(|value| value.to_str()) == Some("shp")
+}
+
+/// Fetch points from the PostgreSQL server at connection URL `url`, with
+/// `query`, or else the default query, when the `postgres` feature is enabled
+#[cfg(feature = "postgres")]
+fn load_postgres(url: &str, query: Option<&str>) -> Result<Vec<Point>, AppError> {
+    use crate::domain::postgres::{load_points_postgres, DEFAULT_POSTGRES_QUERY};
+    load_points_postgres(url, query.unwrap_or(DEFAULT_POSTGRES_QUERY))
+}
+
+#[cfg(not(feature = "postgres"))]
+fn load_postgres(_url: &str, _query: Option<&str>) -> Result<Vec<Point>, AppError> {
+    Err(AppError::PostgresError(String::from(
+        "PostgreSQL input needs the `postgres` feature",
+    )))
+}
+
+/// Connection URL held in `path`, if it is a `postgres://` or
+/// `postgresql://` URL rather than a file
+fn postgres_url(path: &Path) -> Option<&str> {
+    path.to_str()
+        .filter(|value| value.starts_with("postgres://") || value.starts_with("postgresql://"))
+}
+
+/// Helper function for parsing a JSON file of points into a [`Vec`] of
+/// [`Point`]s, parsed as the file is read, so the whole file is never held in
+/// memory alongside the parsed points.  Files with an `.ndjson` or `.jsonl`
+/// extension are streamed as newline delimited JSON instead, see
+/// [`parse_points_ndjson`], in chunks.  Files with a
+/// `.parquet` extension are read as Parquet, with the `parquet` feature, and
+/// files with a `.sqlite`, `.sqlite3` or `.db` extension as SQLite databases,
+/// taking points from the `points` table, see [`parse_points_sqlite`].  Files
+/// with a `.pb` or `.binpb` extension are read as protobuf, see
+/// [`parse_points_protobuf`], and files with a `.gpx` extension as GPX, see
+/// [`parse_points_gpx`].  With the `shapefile` feature, files with a `.shp`
+/// extension are read as point shapefiles, with numbers and directions from
+/// the `number` and `direction` fields of the `.dbf` file alongside, see
+/// [`parse_points_with_fields`].  Files with a `.fgb` extension are read as
+/// FlatGeobuf, see [`parse_points_flatgeobuf`].  Files with a `.ply`
+/// extension are read as PLY point clouds, see [`parse_points_ply`], and, with
+/// the `las` feature, files with a `.las` extension as LAS point clouds.  With
+/// the `postgres` feature, `path` may
+/// instead be a `postgresql://` connection URL, and points are taken from the
+/// `points` table of that database.
+///
+/// [`parse_points_ndjson`]: crate::domain::parse_points_ndjson
+/// [`parse_points_sqlite`]: crate::domain::parse_points_sqlite
+/// [`parse_points_protobuf`]: crate::domain::parse_points_protobuf
+/// [`parse_points_gpx`]: crate::domain::parse_points_gpx
+/// [`parse_points_flatgeobuf`]: crate::domain::parse_points_flatgeobuf
+/// [`parse_points_ply`]: crate::domain::parse_points_ply
+pub fn parse_points_file<P: AsRef<Path>>(path: P) -> Result<Vec<Point>, AppError> {
+    parse_points_file_reporting(path.as_ref(), None)
+}
+
+/// Parse a file of points, as for [`parse_points_file`], calling `progress`
+/// with the number of bytes read so far, and the file size.  JSON and NDJSON
+/// files are read in chunks, with a call after each chunk, while other
+/// formats report once, when parsed.
+pub fn parse_points_file_with_progress<P, F>(
+    path: P,
+    mut progress: F,
+) -> Result<Vec<Point>, AppError>
+where
+    P: AsRef<Path>,
+    F: FnMut(usize, usize),
+{
+    parse_points_file_reporting(path.as_ref(), Some(&mut progress))
+}
+
+/// Formats [`parse_points_file`] reads, picked from the path
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum PointsFormat<'a> {
+    /// PostgreSQL database, at this connection URL
+    Postgres(&'a str),
+    Ndjson,
+    Parquet,
+    Sqlite,
+    Protobuf,
+    Gpx,
+    Shapefile,
+    FlatGeobuf,
+    Ply,
+    Las,
+    Json,
+}
+
+impl<'a> PointsFormat<'a> {
+    fn of(path: &'a Path) -> Self {
+        if let Some(url) = postgres_url(path) {
+            PointsFormat::Postgres(url)
+        } else if is_ndjson_path(path) {
+            PointsFormat::Ndjson
+        } else if is_parquet_path(path) {
+            PointsFormat::Parquet
+        } else if is_sqlite_path(path) {
+            PointsFormat::Sqlite
+        } else if is_protobuf_path(path) {
+            PointsFormat::Protobuf
+        } else if is_gpx_path(path) {
+            PointsFormat::Gpx
+        } else if is_shapefile_path(path) {
+            PointsFormat::Shapefile
+        } else if is_flatgeobuf_path(path) {
+            PointsFormat::FlatGeobuf
+        } else if is_ply_path(path) {
+            PointsFormat::Ply
+        } else if is_las_path(path) {
+            PointsFormat::Las
+        } else {
+            PointsFormat::Json
+        }
+    }
+}
+
+/// Parse the points file at `path`, picking the format from its extension,
+/// and reporting progress to `progress`, if given, as for
+/// [`parse_points_file_with_progress`]
+fn parse_points_file_reporting(
+    path: &Path,
+    mut progress: Option<&mut dyn FnMut(usize, usize)>,
+) -> Result<Vec<Point>, AppError> {
+    let result = match PointsFormat::of(path) {
+        PointsFormat::Postgres(url) => return load_postgres(url, None),
+        PointsFormat::Ndjson => {
+            return parse_points_ndjson_file(path, |done, total| {
+                if let Some(report) = progress.as_mut() {
+                    report(done, total);
+                }
+            })
+        }
+        PointsFormat::Json => {
+            return Ok(read_input_points_json(path, progress)?
+                .into_iter()
+                .map(Point::from)
+                .collect())
+        }
+        PointsFormat::Parquet => parse_parquet_file(path),
+        PointsFormat::Sqlite => parse_points_sqlite_file(path, DEFAULT_SQLITE_QUERY),
+        PointsFormat::Protobuf => parse_points_protobuf_file(path),
+        PointsFormat::Gpx => parse_points_gpx_file(path),
+        PointsFormat::Shapefile => parse_shapefile(path, None),
+        PointsFormat::FlatGeobuf => parse_points_flatgeobuf_file(path, None),
+        PointsFormat::Ply => parse_points_ply_file(path, None),
+        PointsFormat::Las => parse_las(path, None),
+    }?;
+    if let Some(report) = progress {
+        let size = metadata(path).map_or(0, |value| value.len() as usize);
+        report(size, size);
+    }
+    Ok(result)
+}
+
+/// Parse points returned by SQL `query`, from the SQLite database file at
+/// `path`, or, with the `postgres` feature, from the PostgreSQL database at
+/// `path`, when it is a `postgresql://` connection URL
+pub fn parse_points_with_query<P: AsRef<Path>>(
+    path: P,
+    query: &str,
+) -> Result<Vec<Point>, AppError> {
+    let path = path.as_ref();
+    match postgres_url(path) {
+        Some(url) => load_postgres(url, Some(query)),
+        None => parse_points_sqlite_file(path, query),
+    }
+}
+
+/// Parse points within `bounds` from the file at `path`, as for
+/// [`parse_points_file`].  FlatGeobuf files with a spatial index are searched,
+/// reading only the features in `bounds` from disk, which avoids loading a
+/// whole national dataset for a local query.  Other files are read in full,
+/// then points outside `bounds` dropped.
+pub fn parse_points_in<P: AsRef<Path>>(
+    path: P,
+    bounds: &BoundingBox,
+) -> Result<Vec<Point>, AppError> {
+    let path = path.as_ref();
+    if is_flatgeobuf_path(path) {
+        return parse_points_flatgeobuf_file(path, Some(bounds));
+    }
+    let mut result = parse_points_file(path)?;
+    result.retain(|point| bounds.contains(point.coordinates));
     Ok(result)
 }
 
+/// Parse points from the point shapefile at `path`, with the `shapefile`
+/// feature, taking numbers and directions from the `.dbf` fields named in
+/// `fields`, a mapping such as `number=ID,direction=HEADING`.  For PLY and LAS
+/// point clouds, `fields` instead names the point attributes holding numbers
+/// and directions.
+pub fn parse_points_with_fields<P: AsRef<Path>>(
+    path: P,
+    fields: &str,
+) -> Result<Vec<Point>, AppError> {
+    let path = path.as_ref();
+    if is_ply_path(path) {
+        return parse_points_ply_file(path, Some(fields));
+    }
+    if is_las_path(path) {
+        return parse_las(path, Some(fields));
+    }
+    parse_shapefile(path, Some(fields))
+}
+
 /// Distance between two points
-fn euclidean_distance((x_1, y_1): (i32, i32), (x_2, y_2): (i32, i32)) -> f64 {
-    let horizontal_distance: f64 = (x_2 - x_1).into();
-    let vertical_distance: f64 = (y_2 - y_1).into();
+pub(crate) fn euclidean_distance((x_1, y_1): (i32, i32), (x_2, y_2): (i32, i32)) -> f64 {
+    euclidean_distance_f64((x_1.into(), y_1.into()), (x_2.into(), y_2.into()))
+}
+
+/// Distance between two points with fractional coordinates
+pub(crate) fn euclidean_distance_f64((x_1, y_1): (f64, f64), (x_2, y_2): (f64, f64)) -> f64 {
+    let horizontal_distance = x_2 - x_1;
+    let vertical_distance = y_2 - y_1;
     ((horizontal_distance * horizontal_distance) + (vertical_distance * vertical_distance)).sqrt()
 }
 
 /// Angular position (or bearing) in radians, measured clockwise, between `0`
 /// and `2 PI`, with `0` indicating point 2 is directly above the first.
-fn angular_position((x_1, y_1): (i32, i32), (x_2, y_2): (i32, i32)) -> f64 {
-    let horizontal_distance: f64 = (x_2 - x_1).into();
-    let vertical_distance: f64 = (y_2 - y_1).into();
+pub(crate) fn angular_position((x_1, y_1): (i32, i32), (x_2, y_2): (i32, i32)) -> f64 {
+    angular_position_f64((x_1.into(), y_1.into()), (x_2.into(), y_2.into()))
+}
+
+/// Compass direction nearest `heading`, in degrees clockwise from north
+pub(crate) fn heading_direction(heading: f64) -> Direction {
+    match (heading.rem_euclid(360.0) / 90.0).round() as u32 {
+        1 => Direction::East,
+        2 => Direction::South,
+        3 => Direction::West,
+        _ => Direction::North,
+    }
+}
+
+/// Angular position (or bearing) between two points with fractional
+/// coordinates, as for [`angular_position`].
+pub(crate) fn angular_position_f64((x_1, y_1): (f64, f64), (x_2, y_2): (f64, f64)) -> f64 {
+    let horizontal_distance = x_2 - x_1;
+    let vertical_distance = y_2 - y_1;
 
     if vertical_distance.abs() < 1e-10 {
         match horizontal_distance {
@@ -115,7 +837,9 @@ fn inside_left_segment(bearing: f64, center: f64, half_arc_central_angle_radians
             ((val + 2.0 * PI)..=(2.0 * PI)).contains(&bearing) || (0.0..=center).contains(&bearing)
         }
         val if val >= 0.0 => (val..=center).contains(&bearing),
-        _ => unreachable!("Unexpected error checking bearing is inside left segment"),
+
+        // NaN, from a NaN or infinite heading or angle, bounds no segment
+        _ => false,
     }
 }
 
@@ -130,77 +854,90 @@ fn inside_right_segment(bearing: f64, center: f64, half_arc_central_angle_radian
         val if val >= 2.0 * PI => {
             (center..=(2.0 * PI)).contains(&bearing) || (0.0..=(val - 2.0 * PI)).contains(&bearing)
         }
-        _ => unreachable!("Unexpected error checking bearing is inside right segment"),
+
+        // NaN, from a NaN or infinite heading or angle, bounds no segment
+        _ => false,
     }
 }
 
+/// Returns true if `bearing` is inside the segment sweeping left and right from
+/// `center` by `half_arc_central_angle`.  All angles are in radians, and
+/// `half_arc_central_angle` should be between zero and `PI`.
+pub(crate) fn inside_sector(
+    bearing: f64,
+    center: f64,
+    half_arc_central_angle_radians: f64,
+) -> bool {
+    // left segment sweeps left from center through an angle of
+    // `half_arc_central_angle`
+    // right segment sweeps right from center through an angle of
+    // `half_arc_central_angle`
+    inside_left_segment(bearing, center, half_arc_central_angle_radians)
+        || inside_right_segment(bearing, center, half_arc_central_angle_radians)
+}
+
 /// Helper function to determine if the second point is visible from the first,
 /// taking into account the direction of the first point.  Returns true if the
 /// second point is within a segment of large radius, sweeping left and right
 /// from the first point’s direction by `half_arc_central_angle`.
 /// `half_arc_central_angle` should be in degrees and lie in the range zero to
-/// `180` degrees.
-fn visible_neighbour(
+/// `180` degrees.  A neighbour at the same coordinates has no bearing, and is
+/// always visible, whichever way the first point faces.
+pub(crate) fn visible_neighbour<Id>(
     Point {
         coordinates: point_coordinates,
         direction,
         ..
-    }: &Point,
+    }: &Point<Id>,
     Point {
         coordinates: neighbour_coordinates,
         ..
-    }: &Point,
+    }: &Point<Id>,
     half_arc_central_angle: u32,
 ) -> bool {
+    if point_coordinates == neighbour_coordinates {
+        return true;
+    }
     let bearing = angular_position(*point_coordinates, *neighbour_coordinates);
     let half_arc_central_angle_radians = (half_arc_central_angle as f64).to_radians();
 
-    // direction point is facing
-    let center: f64 = match direction {
-        Direction::North => 0.0,
-        Direction::East => FRAC_PI_2,
-        Direction::South => PI,
-        Direction::West => 3.0 * FRAC_PI_2,
-    };
+    inside_sector(bearing, direction.bearing(), half_arc_central_angle_radians)
+}
 
-    // left segment sweeps left from center through an angle of
-    // `half_arc_central_angle`
-    // right segment sweeps right from center through an angle of
-    // `half_arc_central_angle`
-    inside_left_segment(bearing, center, half_arc_central_angle_radians)
-        || inside_right_segment(bearing, center, half_arc_central_angle_radians)
+/// Returns true if `neighbour` is a different point to `point`, lies closer
+/// than `radius` units, and is inside the segment spanning left and right from
+/// `point`’s direction by `half_arc_central_angle`.  `half_arc_central_angle`
+/// should be in degrees, and can range from zero to `180` degrees.
+pub(crate) fn sees<Id: PartialEq>(
+    point: &Point<Id>,
+    neighbour: &Point<Id>,
+    half_arc_central_angle: u32,
+    radius: u32,
+) -> bool {
+    point.number != neighbour.number
+        && euclidean_distance(point.coordinates, neighbour.coordinates) < radius as f64
+        && visible_neighbour(point, neighbour, half_arc_central_angle)
 }
 
 /// Return a vector of all `neighbourhood` points within a segment whose centre
 /// is at `point`, and has radius of `radius` units and spans left and right
 /// front `point`’s direction by `half_arc_central_angle`.
 /// `half_arc_central_angle` should be in degrees, and can range from zero to
-/// `180` degrees.  `point` is never included in the returned vector.
-fn close_neighbours<'a>(
+/// `180` degrees.  `point` is never included in the returned vector.  Points
+/// are returned in ascending number order, and points sharing a number in
+/// neighbourhood order, so results are the same whatever order the
+/// neighbourhood was read in.
+pub(crate) fn close_neighbours<'a>(
     point: &'a Point,
     half_arc_central_angle: u32,
     radius: u32,
     neighbourhood: &'a [Point],
 ) -> Vec<&'a Point> {
-    let Point {
-        number: point_number,
-        coordinates: point_coordinates,
-        ..
-    } = point;
-    let result: Vec<&Point> = neighbourhood.iter().fold(vec![], |mut acc, val| {
-        let Point {
-            number: neighbour_number,
-            coordinates: neighbour_coordinates,
-            ..
-        } = val;
-        if point_number != neighbour_number {
-            let distance = euclidean_distance(*point_coordinates, *neighbour_coordinates);
-            if distance < radius as f64 && visible_neighbour(point, val, half_arc_central_angle) {
-                acc.push(val);
-            }
-        }
-        acc
-    });
+    let mut result: Vec<&Point> = neighbourhood
+        .iter()
+        .filter(|val| sees(point, val, half_arc_central_angle, radius))
+        .collect();
+    result.sort_by_key(|Point { number, .. }| *number);
     result
 }
 
@@ -213,13 +950,17 @@ fn close_neighbours<'a>(
 /// An empty vector is returned if no point matching `point_number` is found
 /// in neighbourhood. The starting point is never included in the returned
 /// vector.  No checks are performed to ensure neighbourhood points have
-/// unique numbers.
+/// unique numbers.  Points are returned in ascending number order, whatever
+/// the neighbourhood order, so output is stable across runs and inputs.
 pub fn visible_points_from_neighbours(
     point_number: u32,
     half_arc_central_angle: u32,
     arc_radius: u32,
     neighbourhood: &[Point],
 ) -> Vec<&Point> {
+    let _span = Span::enter("visible_points", || {
+        format!("point {point_number} of {}", neighbourhood.len())
+    });
     match neighbourhood
         .iter()
         .find(|Point { number, .. }| *number == point_number)
@@ -229,6 +970,78 @@ pub fn visible_points_from_neighbours(
     }
 }
 
+/// Returns true if the point numbered `from` sees the point numbered `to`,
+/// with the same rules as [`visible_points_from_neighbours`], checking just
+/// that pair, rather than finding every visible point.  Returns an error if
+/// no point has either number.
+pub fn can_see(
+    from: u32,
+    to: u32,
+    half_arc_central_angle: u32,
+    arc_radius: u32,
+    neighbourhood: &[Point],
+) -> Result<bool, AppError> {
+    let find = |point_number: u32| {
+        neighbourhood
+            .iter()
+            .find(|Point { number, .. }| *number == point_number)
+            .ok_or(AppError::PointNotFoundError(point_number))
+    };
+    let (point, neighbour) = (find(from)?, find(to)?);
+    Ok(sees(point, neighbour, half_arc_central_angle, arc_radius))
+}
+
+/// Return `neighbourhood` points visible from the point identified by `id`,
+/// as for [`visible_points_from_neighbours`], for points keyed by any
+/// identifier type.  Points are returned in neighbourhood order, since
+/// identifiers need not be ordered.  An empty vector is returned if no point
+/// has `id`.
+pub fn visible_neighbours<'a, Id: Eq>(
+    id: &Id,
+    half_arc_central_angle: u32,
+    arc_radius: u32,
+    neighbourhood: &'a [Point<Id>],
+) -> Vec<&'a Point<Id>> {
+    match neighbourhood
+        .iter()
+        .find(|Point { number, .. }| number == id)
+    {
+        Some(point) => neighbourhood
+            .iter()
+            .filter(|val| sees(point, val, half_arc_central_angle, arc_radius))
+            .collect(),
+        None => vec![],
+    }
+}
+
+/// Write numbers of `neighbourhood` points visible from the point numbered
+/// `point_number` into `visible`, in ascending order, as for
+/// [`visible_points_from_neighbours`], clearing it first.  Reusing `visible`
+/// between queries, for example once per frame in a game, avoids allocating,
+/// once it has grown large enough.  `visible` is left empty if no point
+/// matching `point_number` is found.
+pub fn visible_points_into(
+    point_number: u32,
+    half_arc_central_angle: u32,
+    arc_radius: u32,
+    neighbourhood: &[Point],
+    visible: &mut Vec<u32>,
+) {
+    visible.clear();
+    if let Some(point) = neighbourhood
+        .iter()
+        .find(|Point { number, .. }| *number == point_number)
+    {
+        visible.extend(
+            neighbourhood
+                .iter()
+                .filter(|val| sees(point, val, half_arc_central_angle, arc_radius))
+                .map(|Point { number, .. }| number),
+        );
+        visible.sort_unstable();
+    }
+}
+
 /// Return a vector of all neighbourhood points within a segment whose centre
 /// is at the starting point, identified by `point_number`, and has radius of
 /// `radius` units and spans left and right front `point`’s direction by
@@ -257,15 +1070,79 @@ pub fn visible_points(
 #[cfg(test)]
 mod tests {
     use super::{
-        angular_position, euclidean_distance, parse_points_file, visible_points,
-        visible_points_from_neighbours, Direction, Point,
+        angular_position, can_see, euclidean_distance, parse_points, parse_points_file,
+        parse_points_file_with_progress, parse_records_file, resolve_directions,
+        visible_neighbours, visible_points, visible_points_from_neighbours, visible_points_into,
+        Direction, Point,
     };
-    use crate::utilities::AppError;
+    use crate::{domain::ObservedPoint, utilities::AppError};
     use std::{
         f64::consts::{FRAC_PI_2, FRAC_PI_4, SQRT_2},
         path::Path,
     };
 
+    #[test]
+    fn parse_points_accepts_vector_directions() {
+        // arrange
+        let json = r#"{"points": [
+            {"x": 0, "y": 0, "number": 1, "direction": {"dx": 0.6, "dy": 0.8}},
+            {"x": 0, "y": 0, "number": 2, "direction": {"dx": -1, "dy": 0}},
+            {"x": 0, "y": 0, "number": 3, "direction": "South"}
+        ]}"#;
+
+        // act
+        let outcome = parse_points(json).unwrap();
+
+        // assert
+        assert_eq!(outcome[0].direction, Direction::Vector { dx: 0.6, dy: 0.8 });
+        assert!((outcome[0].direction.bearing() - 0.6_f64.atan2(0.8)).abs() < 1e-10);
+        assert!((outcome[1].direction.bearing() - 3.0 * FRAC_PI_2).abs() < 1e-10);
+        assert_eq!(outcome[2].direction, Direction::South);
+        assert_eq!(
+            serde_json::to_string(&outcome[0].direction).unwrap(),
+            r#"{"dx":0.6,"dy":0.8}"#
+        );
+        assert!(parse_points(
+            r#"{"points": [{"x": 0, "y": 0, "number": 1, "direction": {"dx": 0, "dy": 0}}]}"#
+        )
+        .is_err());
+        assert!(
+            parse_points(r#"{"points": [{"x": 0, "y": 0, "number": 1, "direction": "Up"}]}"#)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn parse_points_resolves_directions_towards_points() {
+        // arrange
+        let json = r#"{"points": [
+            {"x": 0, "y": 0, "number": 1, "direction": {"towards": 2}},
+            {"x": 3, "y": -4, "number": 2, "direction": "North"}
+        ]}"#;
+
+        // act
+        let outcome = parse_points(json).unwrap();
+
+        // assert
+        assert_eq!(
+            outcome[0].direction,
+            Direction::Vector { dx: 3.0, dy: -4.0 }
+        );
+        let mut unresolved = vec![Point {
+            coordinates: (0, 0),
+            number: 1,
+            direction: Direction::Towards(9),
+        }];
+        assert_eq!(
+            resolve_directions(&mut unresolved).unwrap_err().to_string(),
+            "Error resolving point direction: point 1 faces point 9, which is not in the points input"
+        );
+        assert!(parse_points(
+            r#"{"points": [{"x": 0, "y": 0, "number": 1, "direction": {"towards": 1}}]}"#
+        )
+        .is_err());
+    }
+
     #[test]
     fn angular_position_gives_expected_result() {
         // arrange
@@ -401,15 +1278,65 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn parse_points_file_with_progress_reports_bytes_read() -> Result<(), AppError> {
+        // arrange
+        let points_file_path = Path::new("./fixtures/valid_points.json");
+        let mut updates = vec![];
+
+        // act
+        let points = parse_points_file_with_progress(points_file_path, |done, total| {
+            updates.push((done, total))
+        })?;
+
+        // assert
+        assert_eq!(points, parse_points_file(points_file_path)?);
+        let file_size = std::fs::metadata(points_file_path).unwrap().len() as usize;
+        assert_eq!(updates.last(), Some(&(file_size, file_size)));
+
+        // act
+        let gpx_file_path = Path::new("./fixtures/valid_points.gpx");
+        let mut gpx_updates = vec![];
+        let gpx_points = parse_points_file_with_progress(gpx_file_path, |done, total| {
+            gpx_updates.push((done, total))
+        })?;
+
+        // assert
+        assert_eq!(gpx_points, parse_points_file(gpx_file_path)?);
+        let gpx_file_size = std::fs::metadata(gpx_file_path).unwrap().len() as usize;
+        assert_eq!(gpx_updates, vec![(gpx_file_size, gpx_file_size)]);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_records_file_reads_every_format() -> Result<(), AppError> {
+        // arrange
+        let json_file_path = Path::new("./fixtures/valid_points.json");
+        let gpx_file_path = Path::new("./fixtures/valid_points.gpx");
+
+        // act
+        let json_records: Vec<ObservedPoint> = parse_records_file(json_file_path)?;
+        let gpx_records: Vec<ObservedPoint> = parse_records_file(gpx_file_path)?;
+
+        // assert
+        let points = |records: &[ObservedPoint]| -> Vec<Point> {
+            records.iter().map(|record| record.point).collect()
+        };
+        assert_eq!(points(&json_records), parse_points_file(json_file_path)?);
+        assert_eq!(points(&gpx_records), parse_points_file(gpx_file_path)?);
+        assert!(gpx_records
+            .iter()
+            .all(|record| record.observed_at.is_none()));
+        Ok(())
+    }
+
     #[test]
     fn handles_invalid_points_file() {
         // arrange
         let points_file_path = Path::new("./fixtures/invalid.json");
 
         // act
-        let outcome = parse_points_file(&points_file_path)
-            .unwrap_err()
-            .to_string();
+        let outcome = parse_points_file(points_file_path).unwrap_err().to_string();
 
         // assert
         assert_eq!(outcome, "Error parsing JSON. Check the input JSON is valid and has expected structure: EOF while parsing a value at line 9 column 0");
@@ -421,9 +1348,7 @@ mod tests {
         let points_file_path = Path::new("./fixtures/does-not-exist.json");
 
         // act
-        let outcome = parse_points_file(&points_file_path)
-            .unwrap_err()
-            .to_string();
+        let outcome = parse_points_file(points_file_path).unwrap_err().to_string();
 
         // assert
         assert_eq!(
@@ -575,4 +1500,133 @@ mod tests {
         // assert
         assert_eq!(outcome.len(), 0);
     }
+
+    #[test]
+    fn visible_points_into_reuses_buffer() {
+        // arrange
+        let points = parse_points_file("./fixtures/valid_points.json").unwrap();
+        let mut visible = Vec::with_capacity(points.len());
+        visible.push(999);
+
+        // act
+        visible_points_into(20, 180, 10, &points, &mut visible);
+
+        // assert
+        let expected: Vec<u32> = visible_points_from_neighbours(20, 180, 10, &points)
+            .iter()
+            .map(|Point { number, .. }| *number)
+            .collect();
+        assert_eq!(visible, expected);
+        assert_eq!(visible.capacity(), points.len());
+
+        // act
+        visible_points_into(999, 180, 10, &points, &mut visible);
+
+        // assert
+        assert!(visible.is_empty());
+    }
+
+    #[test]
+    fn can_see_matches_visible_points() {
+        // arrange
+        let points = parse_points_file("./fixtures/valid_points.json").unwrap();
+        let visible: Vec<u32> = visible_points_from_neighbours(1, 90, 15, &points)
+            .iter()
+            .map(|Point { number, .. }| *number)
+            .collect();
+
+        // act
+        let outcome: Vec<u32> = points
+            .iter()
+            .map(|Point { number, .. }| *number)
+            .filter(|number| can_see(1, *number, 90, 15, &points).unwrap())
+            .collect();
+
+        // assert
+        let mut outcome = outcome;
+        outcome.sort_unstable();
+        assert_eq!(outcome, visible);
+        assert!(!can_see(1, 1, 90, 15, &points).unwrap());
+        assert_eq!(
+            can_see(1, 999, 90, 15, &points).unwrap_err().to_string(),
+            "No point numbered `999`. Check the point number is in the points input."
+        );
+    }
+
+    #[test]
+    fn visible_neighbours_accepts_other_identifier_types() {
+        // arrange
+        #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+        struct Entity(u64);
+
+        let points = parse_points_file("./fixtures/valid_points.json").unwrap();
+        let entities: Vec<Point<Entity>> = points
+            .iter()
+            .map(|point| Point {
+                coordinates: point.coordinates,
+                number: Entity(u64::from(point.number) << 32),
+                direction: point.direction,
+            })
+            .collect();
+
+        // act
+        let mut outcome: Vec<u32> = visible_neighbours(&Entity(20 << 32), 180, 10, &entities)
+            .iter()
+            .map(|Point { number, .. }| (number.0 >> 32) as u32)
+            .collect();
+        outcome.sort_unstable();
+
+        // assert
+        let expected: Vec<u32> = visible_points_from_neighbours(20, 180, 10, &points)
+            .iter()
+            .map(|Point { number, .. }| *number)
+            .collect();
+        assert!(!expected.is_empty());
+        assert_eq!(outcome, expected);
+        assert!(visible_neighbours(&Entity(0), 180, 10, &entities).is_empty());
+        let named = [
+            Point {
+                coordinates: (0, 0),
+                number: "mast",
+                direction: Direction::East,
+            },
+            Point {
+                coordinates: (3, 1),
+                number: "buoy",
+                direction: Direction::West,
+            },
+        ];
+        assert_eq!(visible_neighbours(&"mast", 45, 5, &named), vec![&named[1]]);
+    }
+
+    #[test]
+    fn visible_points_from_neighbours_orders_by_number() {
+        // arrange
+        let points = parse_points_file("./fixtures/valid_points.json").unwrap();
+        let mut reordered = points.clone();
+        reordered.reverse();
+        reordered.swap(0, 7);
+
+        // act
+        let outcome: Vec<Vec<u32>> = points
+            .iter()
+            .map(|point| {
+                visible_points_from_neighbours(point.number, 90, 30, &reordered)
+                    .iter()
+                    .map(|Point { number, .. }| *number)
+                    .collect()
+            })
+            .collect();
+
+        // assert
+        for (point, visible) in points.iter().zip(&outcome) {
+            let mut sorted = visible.clone();
+            sorted.sort_unstable();
+            assert_eq!(visible, &sorted);
+            let mut into = vec![];
+            visible_points_into(point.number, 90, 30, &points, &mut into);
+            assert_eq!(visible, &into);
+        }
+        assert!(outcome.iter().any(|visible| visible.len() > 1));
+    }
 }
@@ -0,0 +1,447 @@
+use crate::domain::point::{angular_position_f64, euclidean_distance_f64, inside_sector};
+use std::f64::consts::PI;
+
+/// Returns true if `target` lies closer than `arc_radius` units to `observer`,
+/// inside the segment spanning left and right from `heading` by
+/// `half_arc_central_angle`.  Angles are in degrees, with `heading` measured
+/// clockwise from north, and it may lie outside `0` to `360` degrees.  A
+/// `target` at the observer’s position counts as inside the segment.  Nothing
+/// is seen facing a NaN or infinite `heading`, or with a NaN
+/// `half_arc_central_angle`.
+pub(crate) fn sees_position(
+    observer: (f64, f64),
+    heading: f64,
+    half_arc_central_angle: f64,
+    arc_radius: f64,
+    target: (f64, f64),
+) -> bool {
+    if !heading.is_finite() || half_arc_central_angle.is_nan() {
+        return false;
+    }
+    let mut centre = heading.to_radians().rem_euclid(2.0 * PI);
+    if centre >= 2.0 * PI {
+        centre = 0.0;
+    }
+    euclidean_distance_f64(observer, target) < arc_radius
+        && (observer == target
+            || inside_sector(
+                angular_position_f64(observer, target),
+                centre,
+                half_arc_central_angle.to_radians(),
+            ))
+}
+
+/// Return indices of `positions` visible from the position at `index`,
+/// facing `headings[index]`, within a segment of radius `arc_radius` units,
+/// spanning left and right from the heading by `half_arc_central_angle`.
+/// Angles are in degrees, and headings are measured clockwise from north, so
+/// `90` faces along the positive x axis.
+///
+/// Positions can be any type converting to x,y coordinates, including tuples
+/// and `geo::Point<f64>` or `geo::Coord<f64>` from the geo crate, so existing
+/// data need not be copied into [`Point`](crate::domain::Point) values first.
+/// Indices are returned in ascending order, and never include `index`, while
+/// other positions coinciding with the observer’s count as visible.  An
+/// empty vector is returned if `index` is out of range of either slice, or if
+/// the heading is NaN or infinite, or `half_arc_central_angle` NaN.
+pub fn visible_positions<P: Copy + Into<(f64, f64)>>(
+    index: usize,
+    half_arc_central_angle: f64,
+    arc_radius: f64,
+    positions: &[P],
+    headings: &[f64],
+) -> Vec<usize> {
+    let (observer, heading) = match (positions.get(index), headings.get(index)) {
+        (Some(position), Some(heading)) => ((*position).into(), *heading),
+        _ => return vec![],
+    };
+    positions
+        .iter()
+        .enumerate()
+        .filter(|(other, position)| {
+            *other != index
+                && sees_position(
+                    observer,
+                    heading,
+                    half_arc_central_angle,
+                    arc_radius,
+                    (**position).into(),
+                )
+        })
+        .map(|(other, _)| other)
+        .collect()
+}
+
+/// Heading in degrees, clockwise from north, from `0` up to `360`, of a
+/// direction vector, such as a unit vector from a robot’s pose.  The vector
+/// need not be normalised, and a zero vector gives a heading of `0`.
+pub fn heading_from_vector([x, y]: [f64; 2]) -> f64 {
+    let result = x.atan2(y).to_degrees().rem_euclid(360.0);
+    if result >= 360.0 {
+        0.0
+    } else {
+        result
+    }
+}
+
+/// Unit vector pointing along `heading`, in degrees clockwise from north, so
+/// `0` gives `[0.0, 1.0]` and `90` gives `[1.0, 0.0]`
+pub fn heading_vector(heading: f64) -> [f64; 2] {
+    let (sin, cos) = heading.to_radians().sin_cos();
+    [sin, cos]
+}
+
+/// Return indices of `positions` visible from the position at `index`,
+/// facing along the direction vector `facings[index]`, as for
+/// [`visible_positions`].  Positions and facings can be any type converting
+/// to a two element array, including `nalgebra::Point2<f64>` and
+/// `nalgebra::Vector2<f64>`, so poses need no conversion to angles first.
+pub fn visible_positions_facing<P, V>(
+    index: usize,
+    half_arc_central_angle: f64,
+    arc_radius: f64,
+    positions: &[P],
+    facings: &[V],
+) -> Vec<usize>
+where
+    P: Copy + Into<[f64; 2]>,
+    V: Copy + Into<[f64; 2]>,
+{
+    let heading = match facings.get(index) {
+        Some(value) => heading_from_vector((*value).into()),
+        None => return vec![],
+    };
+    let coordinates: Vec<(f64, f64)> = positions
+        .iter()
+        .map(|position| {
+            let [x, y] = (*position).into();
+            (x, y)
+        })
+        .collect();
+    let mut headings = vec![0.0; coordinates.len()];
+    if let Some(value) = headings.get_mut(index) {
+        *value = heading;
+    }
+    visible_positions(
+        index,
+        half_arc_central_angle,
+        arc_radius,
+        &coordinates,
+        &headings,
+    )
+}
+
+/// Return indices of `positions` visible from the position at `index`, as for
+/// [`visible_positions`], for whole number positions, such as
+/// `glam::IVec2`, which converts to `(i32, i32)`.  Every `i32` converts to
+/// `f64` exactly, so results match [`visible_positions`] on the same positions.
+pub fn visible_positions_i32<P: Copy + Into<(i32, i32)>>(
+    index: usize,
+    half_arc_central_angle: f64,
+    arc_radius: f64,
+    positions: &[P],
+    headings: &[f64],
+) -> Vec<usize> {
+    let coordinates: Vec<(f64, f64)> = positions
+        .iter()
+        .map(|position| {
+            let (x, y) = (*position).into();
+            (f64::from(x), f64::from(y))
+        })
+        .collect();
+    visible_positions(
+        index,
+        half_arc_central_angle,
+        arc_radius,
+        &coordinates,
+        headings,
+    )
+}
+
+/// Return indices of `positions` visible from the position at `index`, as for
+/// [`visible_positions`], but working in `f32` throughout, for positions such
+/// as `glam::Vec2`, which converts to `(f32, f32)`.
+///
+/// The sector test compares dot products against the cosine of the half arc,
+/// instead of finding each bearing, so it is faster for per frame use in
+/// games.  Results can differ from [`visible_positions`] for positions within
+/// rounding error of the sector boundary.
+pub fn visible_positions_f32<P: Copy + Into<(f32, f32)>>(
+    index: usize,
+    half_arc_central_angle: f32,
+    arc_radius: f32,
+    positions: &[P],
+    headings: &[f32],
+) -> Vec<usize> {
+    let mut result = vec![];
+    visible_positions_f32_into(
+        index,
+        half_arc_central_angle,
+        arc_radius,
+        positions,
+        headings,
+        &mut result,
+    );
+    result
+}
+
+/// Write indices of `positions` visible from the position at `index` into
+/// `visible`, as for [`visible_positions_f32`], clearing it first.  Positions
+/// and headings are parallel slices, as kept by entity component systems, and
+/// reusing `visible` between calls avoids allocating each frame.
+pub fn visible_positions_f32_into<P: Copy + Into<(f32, f32)>>(
+    index: usize,
+    half_arc_central_angle: f32,
+    arc_radius: f32,
+    positions: &[P],
+    headings: &[f32],
+    visible: &mut Vec<usize>,
+) {
+    visible.clear();
+    let ((x, y), heading) = match (positions.get(index), headings.get(index)) {
+        (Some(position), Some(heading)) => ((*position).into(), *heading),
+        _ => return,
+    };
+    let (facing_x, facing_y) = heading.to_radians().sin_cos();
+    let cos_half_arc = half_arc_central_angle.to_radians().cos();
+    let radius_squared = arc_radius * arc_radius;
+    visible.extend(
+        positions
+            .iter()
+            .enumerate()
+            .filter(|(other, position)| {
+                let (other_x, other_y) = (**position).into();
+                let (offset_x, offset_y) = (other_x - x, other_y - y);
+                let distance_squared = offset_x * offset_x + offset_y * offset_y;
+                *other != index
+                    && distance_squared < radius_squared
+                    && (half_arc_central_angle >= 180.0
+                        || offset_x * facing_x + offset_y * facing_y
+                            >= distance_squared.sqrt() * cos_half_arc)
+            })
+            .map(|(other, _)| other),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        heading_from_vector, heading_vector, visible_positions, visible_positions_f32,
+        visible_positions_f32_into, visible_positions_facing, visible_positions_i32,
+    };
+    use crate::domain::{visible_points_from_neighbours, Direction, Point};
+
+    /// Stand in for a position type from another crate, such as `geo::Point`
+    #[derive(Copy, Clone)]
+    struct Position {
+        x: f64,
+        y: f64,
+    }
+
+    impl From<Position> for (f64, f64) {
+        fn from(Position { x, y }: Position) -> Self {
+            (x, y)
+        }
+    }
+
+    #[test]
+    fn visible_positions_gives_expected_result() {
+        // arrange
+        let positions = [(0.0, 0.0), (0.5, 4.0), (3.0, 0.2), (-3.0, 0.2), (0.0, 30.0)];
+        let headings = [0.0, 180.0, 270.0, 90.0, 0.0];
+
+        // act
+        let outcome = visible_positions(0, 45.0, 20.0, &positions, &headings);
+
+        // assert
+        assert_eq!(outcome, vec![1]);
+
+        // act
+        let outcome = visible_positions(0, 95.0, 20.0, &positions, &headings);
+
+        // assert
+        assert_eq!(outcome, vec![1, 2, 3]);
+
+        // act
+        let outcome = visible_positions(2, 45.0, 20.0, &positions, &headings);
+        let wrapped = visible_positions(2, 45.0, 20.0, &positions, &[0.0, 0.0, -90.0]);
+
+        // assert
+        assert_eq!(outcome, vec![0, 3]);
+        assert_eq!(wrapped, outcome);
+        assert_eq!(
+            visible_positions(9, 45.0, 20.0, &positions, &headings),
+            Vec::<usize>::new()
+        );
+    }
+
+    #[test]
+    fn visible_positions_sees_nothing_with_invalid_angles() {
+        // arrange
+        let positions = [(0.0, 0.0), (0.0, 0.0), (0.5, 4.0), (3.0, 0.2)];
+
+        // act
+        let outcome: Vec<Vec<usize>> = [
+            (f64::NAN, 45.0),
+            (f64::INFINITY, 45.0),
+            (f64::NEG_INFINITY, 45.0),
+            (0.0, f64::NAN),
+        ]
+        .iter()
+        .map(|(heading, angle)| {
+            visible_positions(0, *angle, 20.0, &positions, &[*heading, 0.0, 0.0, 0.0])
+        })
+        .collect();
+
+        // assert
+        assert!(outcome.iter().all(Vec::is_empty));
+        let facing_nan = [[f64::NAN, 1.0], [0.0, 0.0], [0.0, 0.0], [0.0, 0.0]];
+        let positions_array: Vec<[f64; 2]> = positions.iter().map(|(x, y)| [*x, *y]).collect();
+        assert!(visible_positions_facing(0, 45.0, 20.0, &positions_array, &facing_nan).is_empty());
+        let nan_vector = Point {
+            coordinates: (0, 0),
+            number: 1,
+            direction: Direction::Vector {
+                dx: f64::NAN,
+                dy: 1.0,
+            },
+        };
+        let neighbours = [
+            nan_vector,
+            Point {
+                coordinates: (0, 5),
+                number: 2,
+                direction: Direction::North,
+            },
+        ];
+        assert!(visible_points_from_neighbours(1, 45, 20, &neighbours).is_empty());
+    }
+
+    #[test]
+    fn visible_positions_accepts_foreign_types() {
+        // arrange
+        let positions = [
+            Position { x: 10.0, y: 10.0 },
+            Position { x: 17.5, y: 10.0 },
+            Position { x: 10.0, y: 17.5 },
+        ];
+
+        // act
+        let outcome = visible_positions(0, 10.0, 8.0, &positions, &[90.0, 0.0, 0.0]);
+
+        // assert
+        assert_eq!(outcome, vec![1]);
+    }
+
+    #[test]
+    fn visible_positions_facing_gives_expected_result() {
+        // arrange
+        let positions = [[0.0, 0.0], [4.0, 4.5], [-4.0, 4.5]];
+        let facings = [[0.6, 0.8], [0.0, 0.0], [0.0, 0.0]];
+
+        // act
+        let outcome = visible_positions_facing(0, 20.0, 10.0, &positions, &facings);
+
+        // assert
+        assert_eq!(outcome, vec![1]);
+        assert!((heading_from_vector([-1.0, 0.0]) - 270.0).abs() < 1e-10);
+        assert!((heading_from_vector([0.0, -2.0]) - 180.0).abs() < 1e-10);
+        assert_eq!(heading_from_vector([0.0, 0.0]), 0.0);
+        let [x, y] = heading_vector(heading_from_vector([0.6, 0.8]));
+        assert!((x - 0.6).abs() < 1e-10 && (y - 0.8).abs() < 1e-10);
+    }
+
+    #[test]
+    fn visible_positions_i32_accepts_whole_number_positions() {
+        // arrange
+        #[derive(Copy, Clone)]
+        struct GridPosition {
+            x: i32,
+            y: i32,
+        }
+
+        impl From<GridPosition> for (i32, i32) {
+            fn from(GridPosition { x, y }: GridPosition) -> Self {
+                (x, y)
+            }
+        }
+
+        let positions = [
+            GridPosition { x: 0, y: 0 },
+            GridPosition { x: 0, y: 4 },
+            GridPosition { x: 3, y: 0 },
+            GridPosition { x: 0, y: -4 },
+        ];
+        let headings = [0.0, 180.0, 270.0, 0.0];
+
+        // act
+        let outcome = visible_positions_i32(0, 45.0, 10.0, &positions, &headings);
+
+        // assert
+        assert_eq!(outcome, vec![1]);
+        assert_eq!(
+            visible_positions_i32(3, 30.0, 10.0, &positions, &headings),
+            vec![0, 1]
+        );
+    }
+
+    #[test]
+    fn visible_positions_f32_matches_visible_positions() {
+        // arrange
+        let positions: Vec<(f64, f64)> = (0..40)
+            .map(|val| {
+                (
+                    f64::from(val * 7 % 23) - 11.5,
+                    f64::from(val * 5 % 19) - 9.5,
+                )
+            })
+            .collect();
+        let headings: Vec<f64> = (0..40).map(|val| f64::from(val * 37 % 360)).collect();
+        let positions_f32: Vec<(f32, f32)> = positions
+            .iter()
+            .map(|(x, y)| (*x as f32, *y as f32))
+            .collect();
+        let headings_f32: Vec<f32> = headings.iter().map(|val| *val as f32).collect();
+
+        // act
+        let outcome: Vec<Vec<usize>> = (0..40)
+            .map(|index| visible_positions_f32(index, 40.0, 12.0, &positions_f32, &headings_f32))
+            .collect();
+
+        // assert
+        let expected: Vec<Vec<usize>> = (0..40)
+            .map(|index| visible_positions(index, 40.0, 12.0, &positions, &headings))
+            .collect();
+        assert_eq!(outcome, expected);
+        assert_eq!(
+            visible_positions_f32(0, 180.0, 12.0, &positions_f32, &headings_f32).len(),
+            visible_positions(0, 180.0, 12.0, &positions, &headings).len()
+        );
+    }
+
+    #[test]
+    fn visible_positions_f32_into_reuses_buffer() {
+        // arrange
+        let positions = [(0.0_f32, 0.0_f32), (0.0, 5.0), (6.0, 0.0), (0.0, -5.0)];
+        let headings = [0.0_f32, 180.0, 270.0, 0.0];
+        let mut visible = vec![7, 8, 9];
+
+        // act
+        visible_positions_f32_into(0, 45.0, 10.0, &positions, &headings, &mut visible);
+
+        // assert
+        assert_eq!(visible, vec![1]);
+
+        // act
+        visible_positions_f32_into(3, 45.0, 20.0, &positions, &headings, &mut visible);
+
+        // assert
+        assert_eq!(visible, vec![0, 1]);
+
+        // act
+        visible_positions_f32_into(4, 45.0, 20.0, &positions, &headings, &mut visible);
+
+        // assert
+        assert!(visible.is_empty());
+    }
+}
@@ -0,0 +1,379 @@
+//! Password authentication for PostgreSQL: MD5, and SCRAM-SHA-256, which is
+//! the server default since PostgreSQL 14
+
+const MD5_SHIFTS: [u32; 64] = [
+    7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 5, 9, 14, 20, 5, 9, 14, 20, 5, 9,
+    14, 20, 5, 9, 14, 20, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 6, 10, 15,
+    21, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21,
+];
+
+const SHA256_ROUND_CONSTANTS: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Pad `message` to a whole number of 64 byte blocks, ending with its length
+/// in bits, little endian for MD5 and big endian for SHA-256
+fn pad(message: &[u8], big_endian: bool) -> Vec<u8> {
+    let bit_length = (message.len() as u64).wrapping_mul(8);
+    let mut result = message.to_vec();
+    result.push(0x80);
+    while result.len() % 64 != 56 {
+        result.push(0);
+    }
+    match big_endian {
+        true => result.extend_from_slice(&bit_length.to_be_bytes()),
+        false => result.extend_from_slice(&bit_length.to_le_bytes()),
+    }
+    result
+}
+
+pub(crate) fn md5(message: &[u8]) -> [u8; 16] {
+    let constants: Vec<u32> = (0..64)
+        .map(|index| ((index as f64 + 1.0).sin().abs() * 4_294_967_296.0) as u32)
+        .collect();
+    let mut state: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+    for block in pad(message, false).chunks(64) {
+        let words: Vec<u32> = block
+            .chunks(4)
+            .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+            .collect();
+        let [mut a, mut b, mut c, mut d] = state;
+        for index in 0..64 {
+            let (f, word) = match index / 16 {
+                0 => ((b & c) | (!b & d), index),
+                1 => ((d & b) | (!d & c), (5 * index + 1) % 16),
+                2 => (b ^ c ^ d, (3 * index + 5) % 16),
+                _ => (c ^ (b | !d), (7 * index) % 16),
+            };
+            let rotated = a
+                .wrapping_add(f)
+                .wrapping_add(constants[index])
+                .wrapping_add(words[word])
+                .rotate_left(MD5_SHIFTS[index]);
+            a = d;
+            d = c;
+            c = b;
+            b = b.wrapping_add(rotated);
+        }
+        for (value, update) in state.iter_mut().zip([a, b, c, d]) {
+            *value = value.wrapping_add(update);
+        }
+    }
+    let mut result = [0; 16];
+    for (chunk, value) in result.chunks_mut(4).zip(state) {
+        chunk.copy_from_slice(&value.to_le_bytes());
+    }
+    result
+}
+
+pub(crate) fn sha256(message: &[u8]) -> [u8; 32] {
+    let mut state: [u32; 8] = [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+        0x5be0cd19,
+    ];
+    for block in pad(message, true).chunks(64) {
+        let mut schedule = [0_u32; 64];
+        for (index, word) in block.chunks(4).enumerate() {
+            schedule[index] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for index in 16..64 {
+            let previous = schedule[index - 15];
+            let s0 = previous.rotate_right(7) ^ previous.rotate_right(18) ^ (previous >> 3);
+            let previous = schedule[index - 2];
+            let s1 = previous.rotate_right(17) ^ previous.rotate_right(19) ^ (previous >> 10);
+            schedule[index] = schedule[index - 16]
+                .wrapping_add(s0)
+                .wrapping_add(schedule[index - 7])
+                .wrapping_add(s1);
+        }
+        let mut working = state;
+        for index in 0..64 {
+            let [a, b, c, d, e, f, g, h] = working;
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let choice = (e & f) ^ (!e & g);
+            let temporary1 = h
+                .wrapping_add(s1)
+                .wrapping_add(choice)
+                .wrapping_add(SHA256_ROUND_CONSTANTS[index])
+                .wrapping_add(schedule[index]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let majority = (a & b) ^ (a & c) ^ (b & c);
+            let temporary2 = s0.wrapping_add(majority);
+            working = [
+                temporary1.wrapping_add(temporary2),
+                a,
+                b,
+                c,
+                d.wrapping_add(temporary1),
+                e,
+                f,
+                g,
+            ];
+        }
+        for (value, update) in state.iter_mut().zip(working) {
+            *value = value.wrapping_add(update);
+        }
+    }
+    let mut result = [0; 32];
+    for (chunk, value) in result.chunks_mut(4).zip(state) {
+        chunk.copy_from_slice(&value.to_be_bytes());
+    }
+    result
+}
+
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut block = [0_u8; 64];
+    if key.len() > 64 {
+        block[..32].copy_from_slice(&sha256(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let inner: Vec<u8> = block
+        .iter()
+        .map(|byte| byte ^ 0x36)
+        .chain(message.iter().copied())
+        .collect();
+    let outer: Vec<u8> = block
+        .iter()
+        .map(|byte| byte ^ 0x5c)
+        .chain(sha256(&inner).iter().copied())
+        .collect();
+    sha256(&outer)
+}
+
+/// PBKDF2 with HMAC-SHA-256, giving a single 32 byte block
+fn pbkdf2_sha256(password: &[u8], salt: &[u8], iterations: u32) -> [u8; 32] {
+    let mut block = hmac_sha256(password, &[salt, &1_u32.to_be_bytes()].concat());
+    let mut result = block;
+    for _ in 1..iterations {
+        block = hmac_sha256(password, &block);
+        for (value, update) in result.iter_mut().zip(block.iter()) {
+            *value ^= update;
+        }
+    }
+    result
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+pub(crate) fn base64_encode(bytes: &[u8]) -> String {
+    let mut result = String::new();
+    for chunk in bytes.chunks(3) {
+        let value = chunk.iter().enumerate().fold(0_u32, |acc, (index, byte)| {
+            acc | u32::from(*byte) << (16 - 8 * index)
+        });
+        for index in 0..4 {
+            match index <= chunk.len() {
+                true => result.push(char::from(
+                    BASE64_ALPHABET[(value >> (18 - 6 * index) & 0x3f) as usize],
+                )),
+                false => result.push('='),
+            }
+        }
+    }
+    result
+}
+
+pub(crate) fn base64_decode(text: &str) -> Result<Vec<u8>, String> {
+    let mut result = vec![];
+    let mut value = 0_u32;
+    let mut bits = 0;
+    for character in text.trim_end_matches('=').bytes() {
+        let digit = BASE64_ALPHABET
+            .iter()
+            .position(|val| *val == character)
+            .ok_or_else(|| String::from("invalid base64 in SCRAM message"))?;
+        value = (value << 6) | digit as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            result.push((value >> bits) as u8);
+        }
+    }
+    Ok(result)
+}
+
+/// Response to an MD5 password request, with the server’s `salt`
+pub(crate) fn md5_password(user: &str, password: &str, salt: &[u8]) -> String {
+    let inner = hex(&md5(format!("{password}{user}").as_bytes()));
+    let outer = md5(&[inner.as_bytes(), salt].concat());
+    format!("md5{}", hex(&outer))
+}
+
+/// Most PBKDF2 iterations accepted from a server, well above PostgreSQL's
+/// default of 4096, so a hostile server cannot keep the client hashing
+/// indefinitely
+const MAXIMUM_SCRAM_ITERATIONS: u32 = 100_000;
+
+/// Client side of a SCRAM-SHA-256 exchange, without channel binding
+pub(crate) struct Scram {
+    client_first_bare: String,
+    nonce: String,
+    server_signature: Option<[u8; 32]>,
+}
+
+impl Scram {
+    /// Start an exchange with client `nonce`, returning the state and the
+    /// client-first message
+    pub(crate) fn new(nonce: &str) -> (Self, String) {
+        // PostgreSQL ignores the SCRAM user name, taking the startup user
+        let client_first_bare = format!("n=,r={nonce}");
+        let message = format!("n,,{client_first_bare}");
+        (
+            Scram {
+                client_first_bare,
+                nonce: nonce.to_string(),
+                server_signature: None,
+            },
+            message,
+        )
+    }
+
+    /// Answer the server-first message with the client-final message
+    pub(crate) fn client_final(
+        &mut self,
+        password: &str,
+        server_first: &str,
+    ) -> Result<String, String> {
+        let attribute = |name: char| {
+            server_first
+                .split(',')
+                .find_map(|value| value.strip_prefix(&format!("{name}=")))
+                .ok_or_else(|| format!("SCRAM server message has no `{name}` attribute"))
+        };
+        let nonce = attribute('r')?;
+        if !nonce.starts_with(&self.nonce) {
+            return Err(String::from(
+                "SCRAM server nonce does not extend the client nonce",
+            ));
+        }
+        let salt = base64_decode(attribute('s')?)?;
+        let iterations: u32 = attribute('i')?
+            .parse()
+            .ok()
+            .filter(|value| (1..=MAXIMUM_SCRAM_ITERATIONS).contains(value))
+            .ok_or_else(|| String::from("invalid SCRAM iteration count"))?;
+
+        let salted_password = pbkdf2_sha256(password.as_bytes(), &salt, iterations);
+        let client_key = hmac_sha256(&salted_password, b"Client Key");
+        let stored_key = sha256(&client_key);
+        let client_final_without_proof = format!("c=biws,r={nonce}");
+        let auth_message = format!(
+            "{},{server_first},{client_final_without_proof}",
+            self.client_first_bare
+        );
+        let client_signature = hmac_sha256(&stored_key, auth_message.as_bytes());
+        let proof: Vec<u8> = client_key
+            .iter()
+            .zip(client_signature.iter())
+            .map(|(key, signature)| key ^ signature)
+            .collect();
+        let server_key = hmac_sha256(&salted_password, b"Server Key");
+        self.server_signature = Some(hmac_sha256(&server_key, auth_message.as_bytes()));
+        Ok(format!(
+            "{client_final_without_proof},p={}",
+            base64_encode(&proof)
+        ))
+    }
+
+    /// Check the server-final message proves the server knows the password
+    pub(crate) fn verify(&self, server_final: &str) -> Result<(), String> {
+        let signature = server_final
+            .strip_prefix("v=")
+            .ok_or_else(|| format!("SCRAM authentication failed: {server_final}"))
+            .and_then(base64_decode)?;
+        match self.server_signature {
+            Some(value) if value[..] == signature[..] => Ok(()),
+            _ => Err(String::from("SCRAM server signature does not match")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{base64_decode, base64_encode, hex, md5, md5_password, sha256, Scram};
+
+    #[test]
+    fn digests_give_expected_result() {
+        // arrange
+        let message = b"The quick brown fox jumps over the lazy dog";
+
+        // act
+        let outcome = (hex(&md5(message)), hex(&sha256(message)), hex(&sha256(b"")));
+
+        // assert
+        assert_eq!(outcome.0, "9e107d9d372bb6826bd81d3542a419d6");
+        assert_eq!(
+            outcome.1,
+            "d7a8fbb307d7809469ca9abcb0082e4f8d5651e46d3cdb762d02d0bf37c9e592"
+        );
+        assert_eq!(
+            outcome.2,
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            md5_password("postgres", "secret", &[1, 2, 3, 4]),
+            "md5bb41a296aab6baccb36ff243a562abff"
+        );
+    }
+
+    #[test]
+    fn base64_round_trips() {
+        // arrange
+        let bytes = b"neighbours!";
+
+        // act
+        let outcome = base64_encode(bytes);
+
+        // assert
+        assert_eq!(outcome, "bmVpZ2hib3VycyE=");
+        assert_eq!(base64_decode(&outcome).unwrap(), bytes.to_vec());
+    }
+
+    #[test]
+    fn scram_gives_expected_result() {
+        // arrange
+        // RFC 7677 example, with the user name left empty as PostgreSQL sends
+        let (mut scram, client_first) = Scram::new("rOprNGfwEbeRWgbNEkqO");
+        let server_first =
+            "r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,s=W22ZaJ0SNY7soEsUEjb6gQ==,i=4096";
+
+        // act
+        let outcome = scram.client_final("pencil", server_first).unwrap();
+
+        // assert
+        assert_eq!(client_first, "n,,n=,r=rOprNGfwEbeRWgbNEkqO");
+        assert_eq!(
+            outcome,
+            "c=biws,r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,\
+             p=qvT2SWdEH5Q06albL+hjSYuUhCG7VndFyzIb7CK4n9k="
+        );
+        assert!(scram
+            .verify("v=3HO6Qt1M4MKJrmlKaoOqLAI0/0TV0HZe7J9H3MBtSOg=")
+            .is_ok());
+        assert!(scram.verify("v=AAAA").is_err());
+        assert!(scram.verify("e=invalid-proof").is_err());
+
+        // act
+        let (mut scram, _) = Scram::new("rOprNGfwEbeRWgbNEkqO");
+        let outcome = scram.client_final(
+            "pencil",
+            "r=rOprNGfwEbeRWgbNEkqO%hvYD,s=W22ZaJ0SNY7soEsUEjb6gQ==,i=4000000000",
+        );
+
+        // assert
+        assert_eq!(outcome, Err(String::from("invalid SCRAM iteration count")));
+    }
+}
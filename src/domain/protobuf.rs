@@ -0,0 +1,224 @@
+//! Reader for points lists encoded as protobuf, with the `PointList` message
+//! of the schema published in `proto/points.proto`
+
+use crate::{
+    domain::point::{Direction, Point},
+    utilities::{AppError, Span},
+};
+use std::{fs, path::Path};
+
+fn protobuf_error(message: String) -> AppError {
+    AppError::ProtobufParseError(message)
+}
+
+/// Field value, by wire type, skipping fixed width values, which the schema
+/// does not use
+enum Value<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    Fixed,
+}
+
+/// Reads fields from an encoded message
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn varint(&mut self) -> Result<u64, AppError> {
+        let mut result = 0_u64;
+        for (index, byte) in self.bytes.iter().enumerate().take(10) {
+            result |= u64::from(byte & 0x7f) << (7 * index);
+            if byte & 0x80 == 0 {
+                self.bytes = &self.bytes[index + 1..];
+                return Ok(result);
+            }
+        }
+        Err(protobuf_error(String::from("truncated varint")))
+    }
+
+    fn take(&mut self, length: usize) -> Result<&'a [u8], AppError> {
+        if self.bytes.len() < length {
+            return Err(protobuf_error(String::from("truncated field")));
+        }
+        let (result, rest) = self.bytes.split_at(length);
+        self.bytes = rest;
+        Ok(result)
+    }
+
+    /// Next field number and value, or `None` at the end of the message
+    fn field(&mut self) -> Result<Option<(u64, Value<'a>)>, AppError> {
+        if self.bytes.is_empty() {
+            return Ok(None);
+        }
+        let key = self.varint()?;
+        let value = match key & 7 {
+            0 => Value::Varint(self.varint()?),
+            1 => {
+                self.take(8)?;
+                Value::Fixed
+            }
+            2 => {
+                let length = self.varint()? as usize;
+                Value::Bytes(self.take(length)?)
+            }
+            5 => {
+                self.take(4)?;
+                Value::Fixed
+            }
+            wire_type => return Err(protobuf_error(format!("unsupported wire type {wire_type}"))),
+        };
+        Ok(Some((key >> 3, value)))
+    }
+}
+
+/// Decode one `Point` message, where missing fields take proto3 defaults
+fn parse_point(bytes: &[u8]) -> Result<Point, AppError> {
+    let mut reader = Reader { bytes };
+    let mut result = Point {
+        coordinates: (0, 0),
+        number: 0,
+        direction: Direction::North,
+    };
+    while let Some((field, value)) = reader.field()? {
+        let value = match value {
+            Value::Varint(value) => value,
+            // unknown fields are skipped, for compatibility with newer schemas
+            _ if field > 4 => continue,
+            _ => {
+                return Err(protobuf_error(format!(
+                    "unexpected wire type for field {field}"
+                )))
+            }
+        };
+        match field {
+            // int32 values are sign extended to 64 bits on the wire
+            1 => result.coordinates.0 = value as i32,
+            2 => result.coordinates.1 = value as i32,
+            3 => {
+                result.number = u32::try_from(value)
+                    .map_err(|_| protobuf_error(format!("number {value} out of range")))?
+            }
+            4 => {
+                result.direction = match value {
+                    0 => Direction::North,
+                    1 => Direction::East,
+                    2 => Direction::South,
+                    3 => Direction::West,
+                    _ => return Err(protobuf_error(format!("unknown direction {value}"))),
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(result)
+}
+
+/// Parse points from `bytes`, holding a protobuf encoded `PointList` message,
+/// as defined in `proto/points.proto`
+pub fn parse_points_protobuf(bytes: &[u8]) -> Result<Vec<Point>, AppError> {
+    let _span = Span::enter("parse_points_protobuf", || format!("{} bytes", bytes.len()));
+    let mut reader = Reader { bytes };
+    let mut result = vec![];
+    while let Some((field, value)) = reader.field()? {
+        match (field, value) {
+            (1, Value::Bytes(value)) => result.push(parse_point(value)?),
+            (1, _) => {
+                return Err(protobuf_error(String::from(
+                    "unexpected wire type for field 1",
+                )))
+            }
+            _ => {}
+        }
+    }
+    Ok(result)
+}
+
+/// Parse the protobuf points file at `path`
+pub(crate) fn parse_points_protobuf_file(path: &Path) -> Result<Vec<Point>, AppError> {
+    let bytes = fs::read(path).map_err(|error| AppError::BinaryFileError {
+        path: path.display().to_string(),
+        source: error,
+    })?;
+    parse_points_protobuf(&bytes)
+}
+
+/// Returns true if `path` has a `.pb` or `.binpb` extension, and so is read
+/// as protobuf
+pub(crate) fn is_protobuf_path(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|value| value.to_str()),
+        Some("pb") | Some("binpb")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_points_protobuf;
+    use crate::domain::{parse_points_file, Direction, Point};
+    use std::fs;
+
+    #[test]
+    fn parse_points_protobuf_gives_expected_result() {
+        // arrange
+        let bytes = fs::read("./fixtures/valid_points.pb").unwrap();
+
+        // act
+        let outcome = parse_points_protobuf(&bytes).unwrap();
+
+        // assert
+        assert_eq!(
+            outcome,
+            parse_points_file("./fixtures/valid_points.json").unwrap()
+        );
+        assert_eq!(
+            parse_points_file("./fixtures/valid_points.pb").unwrap(),
+            outcome
+        );
+    }
+
+    #[test]
+    fn parse_points_protobuf_handles_negative_coordinates_and_unknown_fields() {
+        // arrange
+        // x = -2, as a ten byte varint, number = 7, direction = west, then an
+        // unknown string field 9
+        let point = [
+            0x08, 0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01, 0x18, 0x07, 0x20,
+            0x03, 0x4a, 0x01, b'z',
+        ];
+        let bytes = [&[0x0a, point.len() as u8][..], &point].concat();
+
+        // act
+        let outcome = parse_points_protobuf(&bytes).unwrap();
+
+        // assert
+        assert_eq!(
+            outcome,
+            vec![Point {
+                coordinates: (-2, 0),
+                number: 7,
+                direction: Direction::West
+            }]
+        );
+    }
+
+    #[test]
+    fn parse_points_protobuf_handles_invalid_input() {
+        // arrange
+        let truncated = [0x0a, 0x05, 0x08, 0x01];
+        let bad_direction = [0x0a, 0x02, 0x20, 0x09];
+
+        // act
+        let truncated = parse_points_protobuf(&truncated).unwrap_err().to_string();
+        let bad_direction = parse_points_protobuf(&bad_direction)
+            .unwrap_err()
+            .to_string();
+
+        // assert
+        assert_eq!(truncated, "Error parsing protobuf points: truncated field");
+        assert_eq!(
+            bad_direction,
+            "Error parsing protobuf points: unknown direction 9"
+        );
+    }
+}
@@ -0,0 +1,317 @@
+use crate::{
+    domain::space::{SpacePoint, Vector, ViewCone},
+    utilities::Span,
+};
+use serde_json::{json, Value};
+
+/// Segments across, and up, the far surface of each view cone mesh
+const CONE_STEPS: (u32, u32) = (16, 8);
+
+/// Colours of the points and view cones, as linear RGBA, with the cones
+/// translucent, so points inside them show through
+const POINT_COLOUR: [f64; 4] = [0.78, 0.24, 0.24, 1.0];
+const CONE_COLOUR: [f64; 4] = [0.16, 0.47, 0.86, 0.3];
+
+/// Triangle mesh, with positions in the points' own coordinates, z up
+struct Mesh {
+    positions: Vec<Vector>,
+    triangles: Vec<[u32; 3]>,
+}
+
+/// Mesh of the part of space `point` sees within `cone`: a fan of triangles
+/// from the point out to the edge of its view, closed by the curved far
+/// surface at the cone's radius
+fn view_cone_mesh(point: &SpacePoint, cone: &ViewCone) -> Mesh {
+    let frame = point.frame();
+    let (across_steps, up_steps) = CONE_STEPS;
+    let (from, radius) = (point.coordinates, cone.radius);
+    let far = |across_step: u32, up_step: u32| {
+        let across = (-cone.horizontal
+            + 2.0 * cone.horizontal * f64::from(across_step) / f64::from(across_steps))
+        .to_radians();
+        let up = (-cone.vertical + 2.0 * cone.vertical * f64::from(up_step) / f64::from(up_steps))
+            .to_radians();
+        let (forward, right, upward) = (up.cos() * across.cos(), up.cos() * across.sin(), up.sin());
+        (
+            from.0
+                + radius
+                    * (forward * frame.forward.0 + right * frame.right.0 + upward * frame.up.0),
+            from.1
+                + radius
+                    * (forward * frame.forward.1 + right * frame.right.1 + upward * frame.up.1),
+            from.2
+                + radius
+                    * (forward * frame.forward.2 + right * frame.right.2 + upward * frame.up.2),
+        )
+    };
+
+    // the point itself, then the far surface, row by row from the bottom
+    let mut positions = vec![from];
+    for up_step in 0..=up_steps {
+        for across_step in 0..=across_steps {
+            positions.push(far(across_step, up_step));
+        }
+    }
+    let index = |across_step: u32, up_step: u32| 1 + up_step * (across_steps + 1) + across_step;
+    let mut triangles = vec![];
+    for up_step in 0..up_steps {
+        for across_step in 0..across_steps {
+            let (a, b) = (index(across_step, up_step), index(across_step + 1, up_step));
+            let (c, d) = (
+                index(across_step + 1, up_step + 1),
+                index(across_step, up_step + 1),
+            );
+            triangles.push([a, b, c]);
+            triangles.push([a, c, d]);
+        }
+    }
+
+    // sides, from the point round the edge of the far surface
+    let mut edge = vec![];
+    edge.extend((0..across_steps).map(|step| index(step, 0)));
+    edge.extend((0..up_steps).map(|step| index(across_steps, step)));
+    edge.extend((1..=across_steps).rev().map(|step| index(step, up_steps)));
+    edge.extend((1..=up_steps).rev().map(|step| index(0, step)));
+    for (position, start) in edge.iter().enumerate() {
+        triangles.push([0, edge[(position + 1) % edge.len()], *start]);
+    }
+    Mesh {
+        positions,
+        triangles,
+    }
+}
+
+/// `position` in glTF axes, which have y up and z towards the viewer, so
+/// north runs along negative z
+fn gltf_position((x, y, z): Vector) -> [f32; 3] {
+    // adding zero turns negative zero, written as `-0`, into zero
+    [x as f32, z as f32, (-y + 0.0) as f32]
+}
+
+/// Append `positions`, in glTF axes, to the `binary` buffer, with a buffer view
+/// and an accessor, returning the accessor's index
+fn push_positions(
+    binary: &mut Vec<u8>,
+    buffer_views: &mut Vec<Value>,
+    accessors: &mut Vec<Value>,
+    positions: &[Vector],
+) -> usize {
+    let start = binary.len();
+    let (mut min, mut max) = ([f32::MAX; 3], [f32::MIN; 3]);
+    for position in positions.iter().copied().map(gltf_position) {
+        for axis in 0..3 {
+            min[axis] = min[axis].min(position[axis]);
+            max[axis] = max[axis].max(position[axis]);
+            binary.extend_from_slice(&position[axis].to_le_bytes());
+        }
+    }
+    buffer_views.push(json!({
+        "buffer": 0,
+        "byteOffset": start,
+        "byteLength": binary.len() - start,
+        "target": 34962,
+    }));
+    accessors.push(json!({
+        "bufferView": buffer_views.len() - 1,
+        "componentType": 5126,
+        "count": positions.len(),
+        "type": "VEC3",
+        "min": min,
+        "max": max,
+    }));
+    accessors.len() - 1
+}
+
+/// Write `points` and the view cone of each, with the extent given by
+/// `cone`, as a binary glTF (GLB) scene, for reviewing coverage in Blender or
+/// a game engine.  Each point's cone is a node of its own, named for the
+/// point, and the points themselves are drawn as a single node of points.
+pub fn scene_glb(points: &[SpacePoint], cone: &ViewCone) -> Vec<u8> {
+    let _span = Span::enter("scene_glb", || format!("{} points", points.len()));
+    let mut binary: Vec<u8> = vec![];
+    let mut buffer_views = vec![];
+    let mut accessors = vec![];
+
+    let mut meshes = vec![];
+    let mut nodes = vec![];
+    if !points.is_empty() {
+        let positions: Vec<Vector> = points.iter().map(|point| point.coordinates).collect();
+        let accessor = push_positions(&mut binary, &mut buffer_views, &mut accessors, &positions);
+        meshes.push(json!({
+            "name": "Points",
+            "primitives": [{ "attributes": { "POSITION": accessor }, "material": 0, "mode": 0 }],
+        }));
+        nodes.push(json!({ "name": "Points", "mesh": 0 }));
+    }
+    let mut index_accessors = vec![];
+    for point in points {
+        let mesh = view_cone_mesh(point, cone);
+        let accessor = push_positions(
+            &mut binary,
+            &mut buffer_views,
+            &mut accessors,
+            &mesh.positions,
+        );
+        index_accessors.push((accessor, mesh.triangles));
+        meshes.push(json!({ "name": format!("Point {} view", point.number) }));
+        nodes.push(json!({
+            "name": format!("Point {} view", point.number),
+            "mesh": meshes.len() - 1,
+        }));
+    }
+
+    // triangle indices follow every position, each view aligned to four bytes
+    let first_cone_mesh = meshes.len() - index_accessors.len();
+    for (offset, (position_accessor, triangles)) in index_accessors.into_iter().enumerate() {
+        let start = binary.len();
+        for index in triangles.iter().flatten() {
+            binary.extend_from_slice(&index.to_le_bytes());
+        }
+        buffer_views.push(json!({
+            "buffer": 0,
+            "byteOffset": start,
+            "byteLength": binary.len() - start,
+            "target": 34963,
+        }));
+        accessors.push(json!({
+            "bufferView": buffer_views.len() - 1,
+            "componentType": 5125,
+            "count": 3 * triangles.len(),
+            "type": "SCALAR",
+        }));
+        meshes[first_cone_mesh + offset]["primitives"] = json!([{
+            "attributes": { "POSITION": position_accessor },
+            "indices": accessors.len() - 1,
+            "material": 1,
+        }]);
+    }
+    let document = json!({
+        "asset": { "version": "2.0", "generator": "neighbours" },
+        "scene": 0,
+        "scenes": [{ "nodes": (0..nodes.len()).collect::<Vec<usize>>() }],
+        "nodes": nodes,
+        "meshes": meshes,
+        "materials": [
+            {
+                "name": "Points",
+                "pbrMetallicRoughness": { "baseColorFactor": POINT_COLOUR, "metallicFactor": 0.0 },
+            },
+            {
+                "name": "View",
+                "pbrMetallicRoughness": { "baseColorFactor": CONE_COLOUR, "metallicFactor": 0.0 },
+                "alphaMode": "BLEND",
+                "doubleSided": true,
+            },
+        ],
+        "accessors": accessors,
+        "bufferViews": buffer_views,
+        "buffers": [{ "byteLength": binary.len() }],
+    });
+    glb_container(document.to_string().into_bytes(), binary)
+}
+
+/// Pack the glTF JSON `document` and its `binary` buffer into a GLB file,
+/// padding each chunk to four bytes
+fn glb_container(mut document: Vec<u8>, mut binary: Vec<u8>) -> Vec<u8> {
+    while document.len() % 4 != 0 {
+        document.push(b' ');
+    }
+    while binary.len() % 4 != 0 {
+        binary.push(0);
+    }
+    let total = 12 + 8 + document.len() + 8 + binary.len();
+    let mut result = Vec::with_capacity(total);
+    result.extend_from_slice(b"glTF");
+    result.extend_from_slice(&2_u32.to_le_bytes());
+    result.extend_from_slice(&(total as u32).to_le_bytes());
+    result.extend_from_slice(&(document.len() as u32).to_le_bytes());
+    result.extend_from_slice(b"JSON");
+    result.extend_from_slice(&document);
+    result.extend_from_slice(&(binary.len() as u32).to_le_bytes());
+    result.extend_from_slice(b"BIN\0");
+    result.extend_from_slice(&binary);
+    result
+}
+
+/// Write `points` and the view cone of each, as for [`scene_glb`], as a
+/// Wavefront OBJ scene, with an object for the points and one for each view.
+/// Positions use y up, as OBJ importers expect.
+pub fn scene_obj(points: &[SpacePoint], cone: &ViewCone) -> String {
+    let _span = Span::enter("scene_obj", || format!("{} points", points.len()));
+    let vertex = |position: Vector| {
+        let [x, y, z] = gltf_position(position);
+        format!("v {x} {y} {z}\n")
+    };
+    let mut result = String::from("# neighbours scene\n");
+    if !points.is_empty() {
+        result.push_str("o Points\n");
+        for point in points {
+            result.push_str(&vertex(point.coordinates));
+        }
+        let indices: Vec<String> = (1..=points.len()).map(|index| index.to_string()).collect();
+        result.push_str(&format!("p {}\n", indices.join(" ")));
+    }
+    let mut vertices = points.len();
+    for point in points {
+        let mesh = view_cone_mesh(point, cone);
+        result.push_str(&format!("o Point_{}_view\n", point.number));
+        for position in &mesh.positions {
+            result.push_str(&vertex(*position));
+        }
+        for [a, b, c] in mesh.triangles {
+            let corner = |index: u32| vertices + index as usize + 1;
+            result.push_str(&format!("f {} {} {}\n", corner(a), corner(b), corner(c)));
+        }
+        vertices += mesh.positions.len();
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{scene_glb, scene_obj, view_cone_mesh, CONE_STEPS};
+    use crate::domain::{parse_space_points, ViewCone};
+
+    #[test]
+    fn scene_glb_writes_points_and_view_cones() {
+        // arrange
+        let points = parse_space_points(
+            r#"{"points": [
+                {"x": 0, "y": 0, "z": 2, "number": 1, "direction": "North"},
+                {"x": 10, "y": 0, "number": 2, "direction": "West"}
+            ]}"#,
+        )
+        .unwrap();
+        let cone = ViewCone {
+            horizontal: 45.0,
+            vertical: 30.0,
+            radius: 20.0,
+        };
+
+        // act
+        let outcome = scene_glb(&points, &cone);
+
+        // assert
+        assert_eq!(&outcome[..4], b"glTF");
+        let length = u32::from_le_bytes([outcome[8], outcome[9], outcome[10], outcome[11]]);
+        assert_eq!(length as usize, outcome.len());
+        let json_length =
+            u32::from_le_bytes([outcome[12], outcome[13], outcome[14], outcome[15]]) as usize;
+        let document: serde_json::Value =
+            serde_json::from_slice(&outcome[20..20 + json_length]).unwrap();
+        assert_eq!(document["nodes"][1]["name"], "Point 1 view");
+        assert_eq!(document["meshes"].as_array().unwrap().len(), 3);
+        assert_eq!(document["accessors"][0]["count"], 2);
+
+        let mesh = view_cone_mesh(&points[0], &cone);
+        let (across, up) = CONE_STEPS;
+        assert_eq!(mesh.positions.len() as u32, 1 + (across + 1) * (up + 1));
+        assert_eq!(mesh.positions[0], (0.0, 0.0, 2.0));
+        let middle = mesh.positions[(1 + (up / 2) * (across + 1) + across / 2) as usize];
+        assert!(middle.0.abs() < 1e-9 && (middle.1 - 20.0).abs() < 1e-9);
+
+        let obj = scene_obj(&points, &cone);
+        assert!(obj.contains("o Point_2_view\n"));
+        assert!(obj.starts_with("# neighbours scene\no Points\nv 0 2 0\n"));
+    }
+}
@@ -0,0 +1,288 @@
+use crate::domain::{band::BearingBand, origin::Origin, sighting::Sighting};
+use std::{cmp::Ordering, ops::Range};
+
+/// Band of bearings from `start` to `end` degrees, relative to `heading`
+fn relative_band(heading: f64, start: f64, end: f64) -> BearingBand {
+    if end - start >= 360.0 {
+        return BearingBand {
+            from: 0.0,
+            to: 360.0,
+        };
+    }
+    BearingBand {
+        from: (heading + start).rem_euclid(360.0),
+        to: (heading + end).rem_euclid(360.0),
+    }
+}
+
+/// Find bearings within the sector of `origin`, spanning left and right from
+/// its heading by `half_arc_central_angle` degrees, with none of `sightings`
+/// in them, so another sensor can be pointed at the widest.  Gaps are
+/// returned widest first, and from left to right where widths are equal.
+/// With a `half_arc_central_angle` of `180`, gaps wrap round behind the
+/// origin.  Sightings at the origin’s coordinates have no bearing, and leave
+/// no mark.
+pub fn angular_gaps(
+    origin: &Origin,
+    half_arc_central_angle: u32,
+    sightings: &[Sighting],
+) -> Vec<BearingBand> {
+    let half_arc = f64::from(half_arc_central_angle.min(180));
+    let mut bearings: Vec<f64> = sightings
+        .iter()
+        .filter(|Sighting { distance, .. }| *distance > 0.0)
+        .map(
+            |Sighting {
+                 relative_bearing, ..
+             }| *relative_bearing,
+        )
+        .collect();
+    bearings.sort_by(|first, second| first.partial_cmp(second).unwrap_or(Ordering::Equal));
+    let mut edges = if half_arc >= 180.0 {
+        match bearings.first() {
+            Some(first) => {
+                let wrapped = first + 360.0;
+                bearings.push(wrapped);
+                bearings
+            }
+            None => vec![-180.0, 180.0],
+        }
+    } else {
+        let mut edges = Vec::with_capacity(bearings.len() + 2);
+        edges.push(-half_arc);
+        edges.extend(bearings);
+        edges.push(half_arc);
+        edges
+    };
+    edges.dedup();
+    let mut result: Vec<BearingBand> = edges
+        .windows(2)
+        .map(|pair| relative_band(origin.heading, pair[0], pair[1]))
+        .collect();
+    result.sort_by(|first, second| {
+        second
+            .width()
+            .partial_cmp(&first.width())
+            .unwrap_or(Ordering::Equal)
+    });
+    result
+}
+
+/// Index of the bucket, of `buckets` equal buckets sweeping left to right
+/// across a sector spanning `half_arc` degrees either side of the heading,
+/// holding `relative_bearing`, or `None` outside the sector
+fn bucket_index(relative_bearing: f64, half_arc: f64, buckets: usize) -> Option<usize> {
+    if buckets == 0 || half_arc <= 0.0 || relative_bearing.abs() > half_arc {
+        return None;
+    }
+    let position = (relative_bearing + half_arc) / (2.0 * half_arc);
+    Some(((position * buckets as f64) as usize).min(buckets - 1))
+}
+
+/// Fraction, from `0` to `1`, of the sector spanning left and right from the
+/// heading by `half_arc_central_angle` degrees with at least one of
+/// `sightings` at a distance within `distances`, dividing the sector into
+/// `buckets` equal buckets of bearing, for a single coverage figure per
+/// query.  Sightings at the origin’s coordinates have no bearing, and are not
+/// counted.
+pub fn sector_coverage(
+    half_arc_central_angle: u32,
+    buckets: usize,
+    distances: Range<f64>,
+    sightings: &[Sighting],
+) -> f64 {
+    if buckets == 0 {
+        return 0.0;
+    }
+    let half_arc = f64::from(half_arc_central_angle.min(180));
+    let mut covered = vec![false; buckets];
+    for Sighting {
+        distance,
+        relative_bearing,
+        ..
+    } in sightings
+    {
+        if *distance > 0.0 && distances.contains(distance) {
+            if let Some(index) = bucket_index(*relative_bearing, half_arc, buckets) {
+                covered[index] = true;
+            }
+        }
+    }
+    covered.iter().filter(|value| **value).count() as f64 / buckets as f64
+}
+
+/// Closest of `sightings` in each of `buckets` equal buckets of bearing,
+/// sweeping from the left of the sector, spanning left and right from the
+/// heading by `half_arc_central_angle` degrees, to the right, as a simple
+/// range sensor would report.  Buckets with nothing visible are `None`, and
+/// where sightings are the same distance away, the first is kept.  Sightings
+/// at the origin’s coordinates have no bearing, and are left out.
+pub fn nearest_in_buckets<'a>(
+    half_arc_central_angle: u32,
+    buckets: usize,
+    sightings: &[Sighting<'a>],
+) -> Vec<Option<Sighting<'a>>> {
+    let half_arc = f64::from(half_arc_central_angle.min(180));
+    let mut result: Vec<Option<Sighting>> = vec![None; buckets];
+    for sighting in sightings.iter().filter(|value| value.distance > 0.0) {
+        if let Some(index) = bucket_index(sighting.relative_bearing, half_arc, buckets) {
+            let nearest = &mut result[index];
+            if nearest.map_or(true, |value| sighting.distance < value.distance) {
+                *nearest = Some(*sighting);
+            }
+        }
+    }
+    result
+}
+
+/// Largest angle, in degrees, between neighbouring vertices of the arc in a
+/// [`sector_outline`]
+const OUTLINE_STEP: f64 = 5.0;
+
+/// Outline of the sector of `origin`, with radius `arc_radius`, spanning left
+/// and right from its heading by `half_arc_central_angle` degrees, as a
+/// closed ring of x,y coordinates, running anticlockwise, for drawing or
+/// writing as a polygon.  The arc is approximated by straight edges no more
+/// than 5° apart.  A `half_arc_central_angle` of `180` gives a circle, without
+/// the origin as a vertex.
+pub fn sector_outline(
+    origin: &Origin,
+    half_arc_central_angle: u32,
+    arc_radius: u32,
+) -> Vec<(f64, f64)> {
+    let half_arc = f64::from(half_arc_central_angle.min(180));
+    let radius = f64::from(arc_radius);
+    let (x, y) = origin.coordinates;
+    let steps = ((2.0 * half_arc / OUTLINE_STEP).ceil() as usize).max(1);
+    let arc = (0..=steps).map(|step| {
+        let bearing =
+            (origin.heading + half_arc - 2.0 * half_arc * step as f64 / steps as f64).to_radians();
+        (x + radius * bearing.sin(), y + radius * bearing.cos())
+    });
+    if half_arc >= 180.0 {
+        return arc.collect();
+    }
+    let mut result = vec![origin.coordinates];
+    result.extend(arc);
+    result.push(origin.coordinates);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{angular_gaps, nearest_in_buckets, sector_coverage, sector_outline};
+    use crate::domain::{test_support::north_point, visible_sightings, BearingBand, Origin};
+
+    fn rounded(gaps: &[BearingBand]) -> Vec<(f64, f64)> {
+        gaps.iter()
+            .map(|BearingBand { from, to }| (from.round(), to.round()))
+            .collect()
+    }
+
+    #[test]
+    fn angular_gaps_gives_widest_gaps_first() {
+        // arrange
+        let neighbourhood = [
+            north_point(1, (0, 0)),
+            north_point(2, (-3, 3)),
+            north_point(3, (0, 4)),
+            north_point(4, (3, 3)),
+            north_point(5, (0, 0)),
+        ];
+        let origin = Origin::from(&neighbourhood[0]);
+
+        // act
+        let outcome = angular_gaps(&origin, 60, &visible_sightings(1, 60, 10, &neighbourhood));
+
+        // assert
+        assert_eq!(
+            rounded(&outcome),
+            vec![(315.0, 0.0), (0.0, 45.0), (300.0, 315.0), (45.0, 60.0)]
+        );
+        let all_round = angular_gaps(&origin, 180, &visible_sightings(1, 180, 10, &neighbourhood));
+        assert_eq!(
+            rounded(&all_round),
+            vec![(45.0, 315.0), (315.0, 0.0), (0.0, 45.0)]
+        );
+        assert_eq!(all_round[0].width().round(), 270.0);
+        let empty = angular_gaps(&origin, 180, &[]);
+        assert_eq!(rounded(&empty), vec![(0.0, 360.0)]);
+        assert_eq!(empty[0].width(), 360.0);
+    }
+
+    #[test]
+    fn sector_coverage_counts_buckets_in_distance_band() {
+        // arrange
+        let neighbourhood = [
+            north_point(1, (0, 0)),
+            north_point(2, (-3, 3)),
+            north_point(3, (0, 5)),
+            north_point(4, (3, 4)),
+            north_point(5, (-5, 12)),
+            north_point(6, (0, 0)),
+        ];
+        let sightings = visible_sightings(1, 45, 20, &neighbourhood);
+
+        // act
+        let outcome = [0.0..10.0, 0.0..20.0, 10.0..20.0]
+            .iter()
+            .map(|distances| sector_coverage(45, 9, distances.clone(), &sightings))
+            .collect::<Vec<f64>>();
+
+        // assert
+        assert_eq!(outcome, vec![3.0 / 9.0, 4.0 / 9.0, 1.0 / 9.0]);
+        assert_eq!(sector_coverage(45, 0, 0.0..20.0, &sightings), 0.0);
+        assert_eq!(sector_coverage(45, 1, 0.0..20.0, &sightings), 1.0);
+    }
+
+    #[test]
+    fn nearest_in_buckets_keeps_closest_sighting() {
+        // arrange
+        let neighbourhood = [
+            north_point(1, (0, 0)),
+            north_point(2, (-6, 6)),
+            north_point(3, (-2, 2)),
+            north_point(4, (0, 5)),
+            north_point(5, (3, 4)),
+            north_point(6, (0, 0)),
+        ];
+        let sightings = visible_sightings(1, 45, 20, &neighbourhood);
+
+        // act
+        let outcome: Vec<Option<u32>> = nearest_in_buckets(45, 4, &sightings)
+            .iter()
+            .map(|nearest| nearest.map(|value| value.point.number))
+            .collect();
+
+        // assert
+        assert_eq!(outcome, vec![Some(3), None, Some(4), Some(5)]);
+        assert!(nearest_in_buckets(45, 0, &sightings).is_empty());
+    }
+
+    #[test]
+    fn sector_outline_runs_anticlockwise_round_sector() {
+        // arrange
+        let origin = Origin {
+            coordinates: (1.0, 2.0),
+            heading: 90.0,
+        };
+
+        // act
+        let outcome: Vec<(f64, f64)> = sector_outline(&origin, 45, 10)
+            .iter()
+            .map(|(x, y)| ((x * 100.0).round() / 100.0, (y * 100.0).round() / 100.0))
+            .collect();
+
+        // assert
+        assert_eq!(outcome.len(), 21);
+        assert_eq!(outcome[0], (1.0, 2.0));
+        assert_eq!(outcome[1], (8.07, -5.07));
+        assert_eq!(outcome[10], (11.0, 2.0));
+        assert_eq!(outcome[19], (8.07, 9.07));
+        assert_eq!(outcome[20], (1.0, 2.0));
+        let circle = sector_outline(&origin, 180, 10);
+        assert_eq!(circle.len(), 73);
+        assert!((circle[0].0 - circle[72].0).abs() < 1e-9);
+        assert!((circle[0].1 - circle[72].1).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,158 @@
+use crate::{
+    domain::{
+        point::{is_json_points_path, parse_input_points, parse_records_file, InputPoint, Point},
+        positions::sees_position,
+    },
+    utilities::{AppError, Span},
+};
+use std::path::Path;
+
+/// Point with its own field of view and range, if its record gave a
+/// `fov_degrees` or `range`, for fleets of sensors which do not all see
+/// alike.  Either missing falls back to the global angle or radius.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SensorPoint {
+    pub point: Point,
+
+    /// Full width of the field of view, in degrees, so half spans either side
+    /// of the point’s direction
+    pub fov_degrees: Option<f64>,
+
+    pub range: Option<f64>,
+}
+
+impl From<InputPoint> for SensorPoint {
+    fn from(input: InputPoint) -> Self {
+        let (fov_degrees, range) = (input.fov_degrees, input.range);
+        SensorPoint {
+            point: Point::from(input),
+            fov_degrees,
+            range,
+        }
+    }
+}
+
+impl SensorPoint {
+    /// Half arc central angle, in degrees, and radius of the segment the
+    /// point sees, falling back to `half_arc_central_angle` and `arc_radius`
+    pub fn view(&self, half_arc_central_angle: u32, arc_radius: u32) -> (f64, f64) {
+        (
+            self.fov_degrees
+                .map_or(f64::from(half_arc_central_angle), |value| value / 2.0)
+                .clamp(0.0, 180.0),
+            self.range.unwrap_or_else(|| f64::from(arc_radius)),
+        )
+    }
+
+    /// Returns true if the point sees `neighbour`, with the same rules as
+    /// [`visible_points_from_neighbours`](crate::domain::visible_points_from_neighbours),
+    /// but for its own field of view and range
+    pub fn sees(&self, neighbour: &Point, half_arc_central_angle: u32, arc_radius: u32) -> bool {
+        let (half_angle, radius) = self.view(half_arc_central_angle, arc_radius);
+        let position = |(x, y): (i32, i32)| (f64::from(x), f64::from(y));
+        self.point.number != neighbour.number
+            && sees_position(
+                position(self.point.coordinates),
+                self.point.direction.bearing().to_degrees(),
+                half_angle,
+                radius,
+                position(neighbour.coordinates),
+            )
+    }
+}
+
+/// Parse a points JSON string, as for [`parse_points`](crate::domain::parse_points),
+/// keeping each point’s optional `fov_degrees` and `range`
+pub fn parse_sensor_points(json: &str) -> Result<Vec<SensorPoint>, AppError> {
+    let _span = Span::enter("parse_sensor_points", || format!("{} bytes", json.len()));
+    let points = parse_input_points(json)?;
+    Ok(points.into_iter().map(SensorPoint::from).collect())
+}
+
+/// Field of view and range of the point numbered `point_number` in the file
+/// at `path`, or `None` if no such point is found, or the file is not a JSON
+/// or NDJSON file, since other formats do not carry them
+pub fn find_sensor_point<P: AsRef<Path>>(
+    path: P,
+    point_number: u32,
+) -> Result<Option<SensorPoint>, AppError> {
+    let path = path.as_ref();
+    if !is_json_points_path(path) {
+        return Ok(None);
+    }
+    Ok(parse_records_file::<SensorPoint>(path)?
+        .into_iter()
+        .find(|sensor| sensor.point.number == point_number))
+}
+
+/// Return `neighbourhood` points seen by `sensor`, as for
+/// [`visible_points_from_neighbours`](crate::domain::visible_points_from_neighbours),
+/// with its own field of view and range, where it has them.  Points are
+/// returned in ascending number order.
+pub fn visible_points_from_sensor<'a>(
+    sensor: &SensorPoint,
+    half_arc_central_angle: u32,
+    arc_radius: u32,
+    neighbourhood: &'a [Point],
+) -> Vec<&'a Point> {
+    let _span = Span::enter("visible_points_from_sensor", || {
+        format!("point {} of {}", sensor.point.number, neighbourhood.len())
+    });
+    let mut result: Vec<&Point> = neighbourhood
+        .iter()
+        .filter(|neighbour| sensor.sees(neighbour, half_arc_central_angle, arc_radius))
+        .collect();
+    result.sort_by_key(|Point { number, .. }| *number);
+    result
+}
+
+/// Return the `sensors` which see `target`, each with its own field of view
+/// and range, where it has them, in their original order
+pub fn observing_sensors<'a>(
+    target: &Point,
+    half_arc_central_angle: u32,
+    arc_radius: u32,
+    sensors: &'a [SensorPoint],
+) -> Vec<&'a SensorPoint> {
+    sensors
+        .iter()
+        .filter(|sensor| sensor.sees(target, half_arc_central_angle, arc_radius))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{observing_sensors, parse_sensor_points, visible_points_from_sensor};
+    use crate::domain::{parse_points, visible_points_from_neighbours, Point};
+
+    #[test]
+    fn visible_points_from_sensor_uses_own_view() {
+        // arrange
+        let json = r#"{"points": [
+            {"x": 0, "y": 0, "number": 1, "direction": "North", "fov_degrees": 180, "range": 50},
+            {"x": 0, "y": 0, "number": 2, "direction": "North"},
+            {"x": 0, "y": 30, "number": 3, "direction": "South", "range": 10},
+            {"x": 20, "y": 20, "number": 4, "direction": "West", "range": 25}
+        ]}"#;
+        let sensors = parse_sensor_points(json).unwrap();
+        let points = parse_points(json).unwrap();
+        let numbers =
+            |visible: &[&Point]| -> Vec<u32> { visible.iter().map(|point| point.number).collect() };
+
+        // act
+        let outcome = visible_points_from_sensor(&sensors[0], 30, 20, &points);
+
+        // assert
+        assert_eq!(numbers(&outcome), vec![2, 3, 4]);
+        assert_eq!(
+            visible_points_from_sensor(&sensors[1], 30, 20, &points),
+            visible_points_from_neighbours(2, 30, 20, &points)
+        );
+        assert_eq!(sensors[2].view(30, 20), (30.0, 10.0));
+        let observers: Vec<u32> = observing_sensors(&points[2], 30, 20, &sensors)
+            .iter()
+            .map(|sensor| sensor.point.number)
+            .collect();
+        assert_eq!(observers, vec![1, 4]);
+    }
+}
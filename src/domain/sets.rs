@@ -0,0 +1,194 @@
+use crate::{
+    domain::point::read_points_file,
+    utilities::{AppError, Span},
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::Path, str::FromStr};
+
+/// Operation combining two collections of [`VisibleSet`]s
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SetOperation {
+    /// Points visible in either collection
+    Union,
+
+    /// Points visible in both collections
+    Intersection,
+
+    /// Points visible in the first collection, but not the second
+    Difference,
+}
+
+impl FromStr for SetOperation {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "union" => Ok(SetOperation::Union),
+            "intersection" => Ok(SetOperation::Intersection),
+            "difference" => Ok(SetOperation::Difference),
+            _ => Err(format!(
+                "unknown set operation `{value}`, expected `union`, `intersection` or `difference`"
+            )),
+        }
+    }
+}
+
+/// Numbers of points visible from one point, as found in a line of saved
+/// query results, such as written by the `batch` and `all-pairs` commands.
+/// Other fields of the line, such as `angle` and `radius`, are ignored.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct VisibleSet {
+    pub point_number: u32,
+    pub visible: Vec<u32>,
+}
+
+impl VisibleSet {
+    /// Result as a single line of JSON, without a trailing newline
+    pub fn to_json_line(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+/// Parse saved query results, one JSON object per line, skipping blank lines
+pub fn parse_visible_sets(input: &str) -> Result<Vec<VisibleSet>, AppError> {
+    let _span = Span::enter("parse_visible_sets", || format!("{} bytes", input.len()));
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line).map_err(|error| AppError::NDJSONParseError {
+                line: index + 1,
+                source: error,
+            })
+        })
+        .collect()
+}
+
+/// Read and parse the saved query results file at `path`, as for
+/// [`parse_visible_sets`]
+pub fn parse_visible_sets_file<P: AsRef<Path>>(path: P) -> Result<Vec<VisibleSet>, AppError> {
+    parse_visible_sets(&read_points_file(path.as_ref())?)
+}
+
+/// Visible points of each point number, with sets for the same number joined
+fn by_point_number(sets: &[VisibleSet]) -> BTreeMap<u32, Vec<u32>> {
+    let mut result: BTreeMap<u32, Vec<u32>> = BTreeMap::new();
+    for VisibleSet {
+        point_number,
+        visible,
+    } in sets
+    {
+        result
+            .entry(*point_number)
+            .or_default()
+            .extend_from_slice(visible);
+    }
+    for visible in result.values_mut() {
+        visible.sort_unstable();
+        visible.dedup();
+    }
+    result
+}
+
+/// Combine `left` and `right` by `operation`, keyed by point number, so
+/// coverage from different queries or runs can be compared.  Sets sharing a
+/// point number within either collection are joined first.  The union has
+/// every point number found in either collection, the intersection those
+/// found in both, and the difference those found in `left`.  Results are in
+/// ascending point number order, each with visible numbers in ascending
+/// order.
+pub fn combine_visible_sets(
+    left: &[VisibleSet],
+    right: &[VisibleSet],
+    operation: SetOperation,
+) -> Vec<VisibleSet> {
+    let mut left = by_point_number(left);
+    let right = by_point_number(right);
+    match operation {
+        SetOperation::Union => {
+            for (point_number, visible) in right {
+                let entry = left.entry(point_number).or_default();
+                entry.extend(visible);
+                entry.sort_unstable();
+                entry.dedup();
+            }
+        }
+        SetOperation::Intersection => {
+            left.retain(|point_number, _| right.contains_key(point_number));
+            for (point_number, visible) in left.iter_mut() {
+                let other = &right[point_number];
+                visible.retain(|number| other.binary_search(number).is_ok());
+            }
+        }
+        SetOperation::Difference => {
+            for (point_number, visible) in left.iter_mut() {
+                if let Some(other) = right.get(point_number) {
+                    visible.retain(|number| other.binary_search(number).is_err());
+                }
+            }
+        }
+    }
+    left.into_iter()
+        .map(|(point_number, visible)| VisibleSet {
+            point_number,
+            visible,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{combine_visible_sets, parse_visible_sets, SetOperation, VisibleSet};
+
+    fn set(point_number: u32, visible: &[u32]) -> VisibleSet {
+        VisibleSet {
+            point_number,
+            visible: visible.to_vec(),
+        }
+    }
+
+    #[test]
+    fn combine_visible_sets_gives_expected_result() {
+        // arrange
+        let left = parse_visible_sets(
+            r#"{"point_number":1,"angle":45,"radius":20,"visible":[4,2]}
+
+{"point_number":3,"angle":45,"radius":20,"visible":[1]}
+{"point_number":1,"angle":90,"radius":20,"visible":[5]}"#,
+        )
+        .unwrap();
+        let right = vec![set(1, &[2, 3, 5]), set(7, &[1])];
+
+        // act
+        let outcome: Vec<Vec<VisibleSet>> = [
+            SetOperation::Union,
+            SetOperation::Intersection,
+            SetOperation::Difference,
+        ]
+        .iter()
+        .map(|operation| combine_visible_sets(&left, &right, *operation))
+        .collect();
+
+        // assert
+        assert_eq!(
+            outcome,
+            vec![
+                vec![set(1, &[2, 3, 4, 5]), set(3, &[1]), set(7, &[1])],
+                vec![set(1, &[2, 5])],
+                vec![set(1, &[4]), set(3, &[1])],
+            ]
+        );
+        assert_eq!(
+            set(1, &[2]).to_json_line(),
+            r#"{"point_number":1,"visible":[2]}"#
+        );
+        assert_eq!("difference".parse(), Ok(SetOperation::Difference));
+        assert_eq!(
+            parse_visible_sets("{\"point_number\": 1}")
+                .unwrap_err()
+                .to_string(),
+            "Error parsing NDJSON line 1: missing field `visible` at line 1 column 19"
+        );
+    }
+}
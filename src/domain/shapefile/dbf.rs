@@ -0,0 +1,122 @@
+//! Reader for dBASE (DBF) tables, holding the attributes of shapefile records
+
+use super::shapefile_error;
+use crate::utilities::AppError;
+
+/// Byte ending the field descriptors of the header
+const HEADER_TERMINATOR: u8 = 0x0d;
+
+/// Deletion flag of a record removed from the table
+const DELETED: u8 = b'*';
+
+/// Column of the table
+#[derive(Clone, Debug, PartialEq)]
+pub(super) struct Field {
+    pub(super) name: String,
+
+    /// dBASE field type, such as `C` for character, or `N` for numeric
+    pub(super) kind: u8,
+    offset: usize,
+    length: usize,
+}
+
+/// Table read from a `.dbf` file
+pub(super) struct Table<'a> {
+    pub(super) fields: Vec<Field>,
+    records: Vec<&'a [u8]>,
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> usize {
+    usize::from(u16::from_le_bytes([bytes[offset], bytes[offset + 1]]))
+}
+
+impl<'a> Table<'a> {
+    pub(super) fn parse(bytes: &'a [u8]) -> Result<Self, AppError> {
+        if bytes.len() < 32 {
+            return Err(shapefile_error(String::from("truncated DBF header")));
+        }
+        let count = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]) as usize;
+        let header_length = read_u16(bytes, 8);
+        let record_length = read_u16(bytes, 10);
+        if header_length < 32 || header_length > bytes.len() || record_length == 0 {
+            return Err(shapefile_error(String::from("invalid DBF header")));
+        }
+        let mut fields = vec![];
+        let mut offset = 1;
+        for descriptor in bytes[32..header_length].chunks(32) {
+            if descriptor[0] == HEADER_TERMINATOR {
+                break;
+            }
+            if descriptor.len() < 32 {
+                return Err(shapefile_error(String::from(
+                    "truncated DBF field descriptor",
+                )));
+            }
+            let name_length = descriptor[..11]
+                .iter()
+                .position(|byte| *byte == 0)
+                .unwrap_or(11);
+            let length = usize::from(descriptor[16]);
+            fields.push(Field {
+                name: String::from_utf8_lossy(&descriptor[..name_length]).into_owned(),
+                kind: descriptor[11],
+                offset,
+                length,
+            });
+            offset += length;
+        }
+        if offset > record_length {
+            return Err(shapefile_error(String::from(
+                "DBF fields are longer than the record",
+            )));
+        }
+        let records = bytes[header_length..]
+            .chunks(record_length)
+            .take(count)
+            .collect::<Vec<_>>();
+        if records.len() < count
+            || records
+                .last()
+                .map_or(false, |value| value.len() < record_length)
+        {
+            return Err(shapefile_error(String::from("truncated DBF records")));
+        }
+        Ok(Table { fields, records })
+    }
+
+    /// Index of the field named `name`, ignoring case, as DBF field names are
+    /// often upper case
+    pub(super) fn field_index(&self, name: &str) -> Result<usize, AppError> {
+        self.fields
+            .iter()
+            .position(|field| field.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| {
+                let names: Vec<&str> = self
+                    .fields
+                    .iter()
+                    .map(|field| field.name.as_str())
+                    .collect();
+                shapefile_error(format!(
+                    "no DBF field `{name}`, fields are {}",
+                    names.join(", ")
+                ))
+            })
+    }
+
+    pub(super) fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    /// Whether record `record` was deleted
+    pub(super) fn is_deleted(&self, record: usize) -> bool {
+        self.records[record][0] == DELETED
+    }
+
+    /// Value of field `field` in record `record`, without padding
+    pub(super) fn value(&self, record: usize, field: usize) -> String {
+        let Field { offset, length, .. } = self.fields[field];
+        String::from_utf8_lossy(&self.records[record][offset..offset + length])
+            .trim()
+            .to_string()
+    }
+}
@@ -0,0 +1,361 @@
+//! Reader for point shapefiles, enabled by the `shapefile` feature.  Points
+//! come from the `.shp` file, with Point, PointZ or PointM shapes, and numbers
+//! and directions from the matching record of the `.dbf` attribute table.
+//! Fields are mapped with a list such as `number=ID,direction=HEADING`, and
+//! default to `number` and `direction`.  Direction fields may hold direction
+//! names, such as `North`, or headings in degrees, clockwise from north, taking
+//! the nearest compass direction.  Coordinates are rounded to whole units.
+
+mod dbf;
+
+use crate::{
+    domain::point::{heading_direction, Direction, Point},
+    utilities::{AppError, Span},
+};
+use dbf::Table;
+use std::{convert::TryFrom, fs, path::Path};
+
+/// Field mapping used when none is given
+pub const DEFAULT_DBF_FIELDS: &str = "number=number,direction=direction";
+
+/// File code at the start of every `.shp` file
+const FILE_CODE: i32 = 9994;
+
+/// Length of the `.shp` file header, in bytes
+const HEADER_LENGTH: usize = 100;
+
+const NULL_SHAPE: i32 = 0;
+const POINT_SHAPES: [i32; 3] = [1, 11, 21];
+
+pub(crate) fn shapefile_error(message: String) -> AppError {
+    AppError::ShapefileError(message)
+}
+
+/// DBF fields holding point numbers and directions
+#[derive(Clone, Debug, PartialEq)]
+struct DbfFields {
+    number: String,
+    direction: String,
+}
+
+impl DbfFields {
+    /// Parse a mapping such as `number=ID,direction=HEADING`, where either
+    /// entry may be left out
+    fn parse(value: &str) -> Result<Self, AppError> {
+        let mut result = DbfFields {
+            number: String::from("number"),
+            direction: String::from("direction"),
+        };
+        for entry in value.split(',').filter(|entry| !entry.trim().is_empty()) {
+            match entry.split_once('=').map(|(key, field)| (key.trim(), field.trim())) {
+                Some(("number", field)) if !field.is_empty() => result.number = field.to_string(),
+                Some(("direction", field)) if !field.is_empty() => {
+                    result.direction = field.to_string()
+                }
+                _ => {
+                    return Err(shapefile_error(format!(
+                        "expected a field mapping such as `number=ID,direction=HEADING`, but got `{value}`"
+                    )))
+                }
+            }
+        }
+        Ok(result)
+    }
+}
+
+fn read_i32_be(bytes: &[u8], offset: usize) -> i32 {
+    i32::from_be_bytes([
+        bytes[offset],
+        bytes[offset + 1],
+        bytes[offset + 2],
+        bytes[offset + 3],
+    ])
+}
+
+fn read_i32_le(bytes: &[u8], offset: usize) -> i32 {
+    i32::from_le_bytes([
+        bytes[offset],
+        bytes[offset + 1],
+        bytes[offset + 2],
+        bytes[offset + 3],
+    ])
+}
+
+fn read_f64_le(bytes: &[u8], offset: usize) -> f64 {
+    let mut value = [0; 8];
+    value.copy_from_slice(&bytes[offset..offset + 8]);
+    f64::from_le_bytes(value)
+}
+
+/// Coordinates of each record of the `.shp` file `bytes`, in record order,
+/// with `None` for null shapes
+fn parse_shapes(bytes: &[u8]) -> Result<Vec<Option<(f64, f64)>>, AppError> {
+    if bytes.len() < HEADER_LENGTH || read_i32_be(bytes, 0) != FILE_CODE {
+        return Err(shapefile_error(String::from("not a `.shp` file")));
+    }
+    let shape_type = read_i32_le(bytes, 32);
+    if shape_type != NULL_SHAPE && !POINT_SHAPES.contains(&shape_type) {
+        return Err(shapefile_error(format!(
+            "shape type {shape_type} is not supported, only points are"
+        )));
+    }
+    let mut result = vec![];
+    let mut offset = HEADER_LENGTH;
+    while offset + 8 <= bytes.len() {
+        let length = usize::try_from(read_i32_be(bytes, offset + 4))
+            .map_err(|_| shapefile_error(String::from("negative record length")))?
+            * 2;
+        let content = bytes
+            .get(offset + 8..offset + 8 + length)
+            .filter(|value| value.len() >= 4)
+            .ok_or_else(|| shapefile_error(format!("truncated record {}", result.len() + 1)))?;
+        match read_i32_le(content, 0) {
+            NULL_SHAPE => result.push(None),
+            kind if POINT_SHAPES.contains(&kind) && content.len() >= 20 => {
+                result.push(Some((read_f64_le(content, 4), read_f64_le(content, 12))))
+            }
+            kind => {
+                return Err(shapefile_error(format!(
+                    "record {} has unsupported shape type {kind}",
+                    result.len() + 1
+                )))
+            }
+        }
+        offset += 8 + length;
+    }
+    Ok(result)
+}
+
+fn number(value: &str, record: usize) -> Result<u32, AppError> {
+    value
+        .parse::<f64>()
+        .ok()
+        .filter(|number| number.fract() == 0.0 && *number >= 0.0 && *number <= f64::from(u32::MAX))
+        .map(|number| number as u32)
+        .ok_or_else(|| shapefile_error(format!("record {record} has invalid number `{value}`")))
+}
+
+fn direction(value: &str, record: usize) -> Result<Direction, AppError> {
+    match value.to_ascii_lowercase().as_str() {
+        "north" | "n" => Ok(Direction::North),
+        "east" | "e" => Ok(Direction::East),
+        "south" | "s" => Ok(Direction::South),
+        "west" | "w" => Ok(Direction::West),
+        _ => value
+            .parse::<f64>()
+            .ok()
+            .filter(|heading| heading.is_finite())
+            .map(heading_direction)
+            .ok_or_else(|| {
+                shapefile_error(format!("record {record} has unknown direction `{value}`"))
+            }),
+    }
+}
+
+/// Parse points from the contents of a `.shp` file, `shp`, and its `.dbf`
+/// attribute table, `dbf`, taking numbers and directions from the DBF fields
+/// named in `fields`, a mapping such as `number=ID,direction=HEADING`.
+/// Records with null shapes, or deleted from the table, are skipped.
+pub fn parse_points_shapefile(
+    shp: &[u8],
+    dbf: &[u8],
+    fields: &str,
+) -> Result<Vec<Point>, AppError> {
+    let _span = Span::enter("parse_points_shapefile", || {
+        format!("{} bytes", shp.len() + dbf.len())
+    });
+    let fields = DbfFields::parse(fields)?;
+    let shapes = parse_shapes(shp)?;
+    let table = Table::parse(dbf)?;
+    if table.len() != shapes.len() {
+        return Err(shapefile_error(format!(
+            "`.shp` file has {} records, but `.dbf` file has {}",
+            shapes.len(),
+            table.len()
+        )));
+    }
+    let number_field = table.field_index(&fields.number)?;
+    let direction_field = table.field_index(&fields.direction)?;
+    shapes
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| !table.is_deleted(*index))
+        .filter_map(|(index, shape)| shape.map(|value| (index, value)))
+        .map(|(index, (x, y))| {
+            let record = index + 1;
+            if !(x.is_finite() && y.is_finite()) {
+                return Err(shapefile_error(format!(
+                    "record {record} has no coordinates"
+                )));
+            }
+            Ok(Point {
+                coordinates: (x.round() as i32, y.round() as i32),
+                number: number(&table.value(index, number_field), record)?,
+                direction: direction(&table.value(index, direction_field), record)?,
+            })
+        })
+        .collect()
+}
+
+/// Parse the point shapefile at `path`, with its attribute table alongside,
+/// sharing its name, with a `.dbf` extension
+pub(crate) fn parse_points_shapefile_file(
+    path: &Path,
+    fields: &str,
+) -> Result<Vec<Point>, AppError> {
+    let read = |path: &Path| {
+        fs::read(path).map_err(|error| AppError::BinaryFileError {
+            path: path.display().to_string(),
+            source: error,
+        })
+    };
+    let shp = read(path)?;
+    let dbf = read(&path.with_extension("dbf"))?;
+    parse_points_shapefile(&shp, &dbf, fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_points_shapefile, DEFAULT_DBF_FIELDS};
+    use crate::domain::{parse_points_file, parse_points_with_fields, Direction, Point};
+
+    /// DBF table with `fields`, given as name, type and length, holding
+    /// `records`, each a deletion marker followed by field values
+    fn dbf(fields: &[(&str, u8, u8)], records: &[&[&str]]) -> Vec<u8> {
+        let record_length: usize = 1 + fields
+            .iter()
+            .map(|(_, _, length)| usize::from(*length))
+            .sum::<usize>();
+        let header_length = 32 + 32 * fields.len() + 1;
+        let mut result = vec![3, 124, 1, 1];
+        result.extend_from_slice(&(records.len() as u32).to_le_bytes());
+        result.extend_from_slice(&(header_length as u16).to_le_bytes());
+        result.extend_from_slice(&(record_length as u16).to_le_bytes());
+        result.resize(32, 0);
+        for (name, kind, length) in fields {
+            let mut descriptor = name.as_bytes().to_vec();
+            descriptor.resize(11, 0);
+            descriptor.push(*kind);
+            descriptor.resize(16, 0);
+            descriptor.push(*length);
+            descriptor.resize(32, 0);
+            result.extend(descriptor);
+        }
+        result.push(0x0d);
+        for record in records {
+            result.push(if record[0] == "deleted" { b'*' } else { b' ' });
+            for ((_, _, length), value) in fields.iter().zip(&record[1..]) {
+                result.extend(format!("{value:>width$}", width = usize::from(*length)).bytes());
+            }
+        }
+        result
+    }
+
+    /// `.shp` file with a Point shape for each of `coordinates`, or a null
+    /// shape for `None`
+    fn shp(coordinates: &[Option<(f64, f64)>]) -> Vec<u8> {
+        let mut records = vec![];
+        for (index, value) in coordinates.iter().enumerate() {
+            let content = match value {
+                Some((x, y)) => {
+                    [&1_i32.to_le_bytes()[..], &x.to_le_bytes(), &y.to_le_bytes()].concat()
+                }
+                None => 0_i32.to_le_bytes().to_vec(),
+            };
+            records.extend_from_slice(&(index as i32 + 1).to_be_bytes());
+            records.extend_from_slice(&(content.len() as i32 / 2).to_be_bytes());
+            records.extend(content);
+        }
+        let mut result = 9994_i32.to_be_bytes().to_vec();
+        result.resize(24, 0);
+        result.extend_from_slice(&((100 + records.len()) as i32 / 2).to_be_bytes());
+        result.extend_from_slice(&1000_i32.to_le_bytes());
+        result.extend_from_slice(&1_i32.to_le_bytes());
+        result.resize(100, 0);
+        result.extend(records);
+        result
+    }
+
+    #[test]
+    fn parse_points_shapefile_gives_expected_result() {
+        // arrange
+        let path = "./fixtures/valid_points.shp";
+
+        // act
+        let outcome = parse_points_file(path).unwrap();
+
+        // assert
+        assert_eq!(
+            outcome,
+            parse_points_file("./fixtures/valid_points.json").unwrap()
+        );
+        assert_eq!(
+            parse_points_with_fields(path, "direction=Direction").unwrap(),
+            outcome
+        );
+        assert_eq!(
+            parse_points_with_fields(path, "number=ID")
+                .unwrap_err()
+                .to_string(),
+            "Error reading shapefile: no DBF field `ID`, fields are NUMBER, DIRECTION"
+        );
+    }
+
+    #[test]
+    fn parse_points_shapefile_maps_fields_and_skips_null_shapes() {
+        // arrange
+        let shp = shp(&[Some((1.4, -2.6)), None, Some((3.0, 4.0)), Some((5.0, 6.0))]);
+        let dbf = dbf(
+            &[("ID", b'N', 4), ("HEADING", b'N', 6)],
+            &[
+                &["", "7", "87.5"],
+                &["", "8", "0"],
+                &["deleted", "9", "0"],
+                &["", "10", "200"],
+            ],
+        );
+
+        // act
+        let outcome = parse_points_shapefile(&shp, &dbf, "number=id,direction=heading").unwrap();
+
+        // assert
+        assert_eq!(
+            outcome,
+            vec![
+                Point {
+                    coordinates: (1, -3),
+                    number: 7,
+                    direction: Direction::East
+                },
+                Point {
+                    coordinates: (5, 6),
+                    number: 10,
+                    direction: Direction::South
+                },
+            ]
+        );
+        assert!(parse_points_shapefile(&shp, &dbf, DEFAULT_DBF_FIELDS).is_err());
+        assert!(parse_points_shapefile(&shp, &dbf, "colour=red").is_err());
+        assert!(parse_points_shapefile(&shp[..110], &dbf, "number=id,direction=heading").is_err());
+    }
+
+    #[test]
+    fn parse_points_shapefile_rejects_short_dbf_header_length() {
+        // arrange
+        let shp = shp(&[Some((1.0, 2.0))]);
+        let mut dbf = dbf(
+            &[("NUMBER", b'N', 4), ("DIRECTION", b'C', 5)],
+            &[&["", "1", "N"]],
+        );
+        dbf[8..10].copy_from_slice(&16_u16.to_le_bytes());
+
+        // act
+        let outcome = parse_points_shapefile(&shp, &dbf, DEFAULT_DBF_FIELDS);
+
+        // assert
+        assert_eq!(
+            outcome.unwrap_err().to_string(),
+            "Error reading shapefile: invalid DBF header"
+        );
+    }
+}
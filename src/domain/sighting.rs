@@ -0,0 +1,213 @@
+use crate::{
+    domain::{
+        origin::Origin,
+        point::{
+            angular_position_f64, euclidean_distance_f64, visible_points_from_neighbours, Point,
+        },
+    },
+    utilities::Span,
+};
+use std::cmp::Ordering;
+
+/// Visible point, with where it lies from the origin seeing it
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Sighting<'a> {
+    pub point: &'a Point,
+
+    /// Distance from the origin
+    pub distance: f64,
+
+    /// Bearing from the origin, in degrees clockwise from north, from `0` up
+    /// to `360`
+    pub bearing: f64,
+
+    /// Bearing relative to the origin’s heading, in degrees from `-180` up to
+    /// `180`, negative to the left and positive to the right, so `-30` is
+    /// 30° left of straight ahead
+    pub relative_bearing: f64,
+}
+
+impl<'a> Sighting<'a> {
+    /// Where `point` lies from `origin`.  A point at the origin’s coordinates
+    /// has no bearing of its own, and is taken to lie straight ahead.
+    pub fn new(origin: &Origin, point: &'a Point) -> Self {
+        let coordinates = (
+            f64::from(point.coordinates.0),
+            f64::from(point.coordinates.1),
+        );
+        let distance = euclidean_distance_f64(origin.coordinates, coordinates);
+        let bearing = if distance > 0.0 {
+            angular_position_f64(origin.coordinates, coordinates).to_degrees()
+        } else {
+            origin.heading
+        }
+        .rem_euclid(360.0);
+        Sighting {
+            point,
+            distance,
+            bearing,
+            relative_bearing: relative_bearing(bearing, origin.heading),
+        }
+    }
+}
+
+/// Angle in degrees turning from `heading` to `bearing`, both clockwise from
+/// north, from `-180` up to `180`, negative for a turn to the left
+pub fn relative_bearing(bearing: f64, heading: f64) -> f64 {
+    let turn = (bearing - heading).rem_euclid(360.0);
+    if turn > 180.0 {
+        turn - 360.0
+    } else {
+        turn
+    }
+}
+
+/// Order `sightings` by relative bearing, sweeping from the left edge of the
+/// sector round to the right, as a radar display draws them, with nearer
+/// points first where bearings are the same
+pub fn sort_by_sweep(sightings: &mut [Sighting]) {
+    sightings.sort_by(|first, second| {
+        first
+            .relative_bearing
+            .partial_cmp(&second.relative_bearing)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| {
+                first
+                    .distance
+                    .partial_cmp(&second.distance)
+                    .unwrap_or(Ordering::Equal)
+            })
+    });
+}
+
+/// Find points visible from the point numbered `point_number`, as for
+/// [`visible_points_from_neighbours`], with the distance, absolute bearing and
+/// bearing relative to the direction faced, of each.  Sightings are in
+/// ascending number order.
+pub fn visible_sightings<'a>(
+    point_number: u32,
+    half_arc_central_angle: u32,
+    arc_radius: u32,
+    neighbourhood: &'a [Point],
+) -> Vec<Sighting<'a>> {
+    let _span = Span::enter("visible_sightings", || {
+        format!("point {point_number} of {}", neighbourhood.len())
+    });
+    let origin = match neighbourhood
+        .iter()
+        .find(|Point { number, .. }| *number == point_number)
+    {
+        Some(value) => Origin::from(value),
+        None => return vec![],
+    };
+    visible_points_from_neighbours(
+        point_number,
+        half_arc_central_angle,
+        arc_radius,
+        neighbourhood,
+    )
+    .into_iter()
+    .map(|point| Sighting::new(&origin, point))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{relative_bearing, sort_by_sweep, visible_sightings, Sighting};
+    use crate::domain::{test_support::point, Direction, Origin};
+
+    #[test]
+    fn visible_sightings_gives_relative_bearings() {
+        // arrange
+        let neighbourhood = [
+            point(1, (0, 0), Direction::East),
+            point(2, (4, 4), Direction::North),
+            point(3, (4, -4), Direction::North),
+            point(4, (0, 0), Direction::South),
+            point(5, (-4, 0), Direction::North),
+        ];
+
+        // act
+        let outcome: Vec<(u32, f64, f64)> = visible_sightings(1, 60, 10, &neighbourhood)
+            .iter()
+            .map(|sighting| {
+                (
+                    sighting.point.number,
+                    sighting.bearing.round(),
+                    sighting.relative_bearing.round(),
+                )
+            })
+            .collect();
+
+        // assert
+        assert_eq!(
+            outcome,
+            vec![(2, 45.0, -45.0), (3, 135.0, 45.0), (4, 90.0, 0.0)]
+        );
+        let origin: Origin = "0,0,350".parse().unwrap();
+        let sighting = Sighting::new(&origin, &neighbourhood[1]);
+        assert_eq!(sighting.relative_bearing.round(), 55.0);
+        assert!((sighting.distance - 32.0_f64.sqrt()).abs() < 1e-10);
+        assert_eq!(relative_bearing(10.0, 340.0), 30.0);
+        assert_eq!(relative_bearing(0.0, 180.0), 180.0);
+        assert!(visible_sightings(9, 60, 10, &neighbourhood).is_empty());
+    }
+
+    #[test]
+    fn relative_bearing_gives_signed_turn() {
+        // arrange
+        let turns = [
+            (300.0, 0.0),
+            (60.0, 0.0),
+            (350.0, 10.0),
+            (10.0, 350.0),
+            (180.0, 0.0),
+            (181.0, 0.0),
+            (179.0, 0.0),
+            (0.0, 180.0),
+            (90.0, -270.0),
+        ];
+        let origin: Origin = "3,4,270".parse().unwrap();
+        let co_located = point(2, (3, 4), Direction::North);
+
+        // act
+        let outcome: Vec<f64> = turns
+            .iter()
+            .map(|(bearing, heading)| relative_bearing(*bearing, *heading))
+            .collect();
+        let sighting = Sighting::new(&origin, &co_located);
+
+        // assert
+        assert_eq!(
+            outcome,
+            vec![-60.0, 60.0, -20.0, 20.0, 180.0, -179.0, 179.0, 180.0, 0.0]
+        );
+        assert_eq!(sighting.distance, 0.0);
+        assert_eq!(sighting.bearing, 270.0);
+        assert_eq!(sighting.relative_bearing, 0.0);
+    }
+
+    #[test]
+    fn sort_by_sweep_orders_left_to_right() {
+        // arrange
+        let neighbourhood = [
+            point(1, (0, 0), Direction::North),
+            point(2, (6, 6), Direction::North),
+            point(3, (-2, 2), Direction::North),
+            point(4, (0, 5), Direction::North),
+            point(5, (2, 2), Direction::North),
+            point(6, (-1, 8), Direction::North),
+        ];
+        let mut sightings = visible_sightings(1, 60, 10, &neighbourhood);
+
+        // act
+        sort_by_sweep(&mut sightings);
+
+        // assert
+        let outcome: Vec<u32> = sightings
+            .iter()
+            .map(|Sighting { point, .. }| point.number)
+            .collect();
+        assert_eq!(outcome, vec![3, 6, 4, 5, 2]);
+    }
+}
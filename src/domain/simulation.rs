@@ -0,0 +1,124 @@
+use crate::{
+    domain::{graph::visibility_graph, motion::MovingPoint, point::Point, sets::VisibleSet},
+    utilities::Span,
+};
+use serde::Serialize;
+
+/// Visibility at one step of a simulation, for assembling animations or
+/// analysing visibility as a time series
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Frame {
+    /// Number of steps since the start, from `0`
+    pub step: usize,
+
+    /// Seconds since the start
+    pub time: f64,
+
+    /// Points, at their positions for the step, in ascending number order
+    #[serde(skip)]
+    pub points: Vec<Point>,
+
+    /// Points visible from each point, both in ascending number order
+    pub visible: Vec<VisibleSet>,
+}
+
+impl Frame {
+    /// Frame as a single line of JSON, without a trailing newline
+    pub fn to_json_line(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+/// Move `neighbourhood` points on by `step` steps of `step_seconds` each, and
+/// find which points each sees, as for
+/// [`visible_points_from_neighbours`](crate::domain::visible_points_from_neighbours),
+/// at their new positions
+pub fn simulation_frame(
+    step: usize,
+    step_seconds: f64,
+    half_arc_central_angle: u32,
+    arc_radius: u32,
+    neighbourhood: &[MovingPoint],
+) -> Frame {
+    let time = step as f64 * step_seconds;
+    let _span = Span::enter("simulation_frame", || {
+        format!("{time} s, {} points", neighbourhood.len())
+    });
+    let mut points: Vec<Point> = neighbourhood.iter().map(|value| value.at(time)).collect();
+    points.sort_by_key(|Point { number, .. }| *number);
+    let graph = visibility_graph(half_arc_central_angle, arc_radius, &points);
+    let visible = graph
+        .edges
+        .iter()
+        .zip(&graph.numbers)
+        .map(|(edges, point_number)| {
+            let mut visible: Vec<u32> = edges.iter().map(|(to, _)| graph.numbers[*to]).collect();
+            visible.sort_unstable();
+            VisibleSet {
+                point_number: *point_number,
+                visible,
+            }
+        })
+        .collect();
+    Frame {
+        step,
+        time,
+        points,
+        visible,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::simulation_frame;
+    use crate::domain::{parse_moving_points, Point, VisibleSet};
+
+    #[test]
+    fn simulation_frame_moves_points_before_querying() {
+        // arrange
+        let points = parse_moving_points(
+            r#"{"points": [
+                {"x": 0, "y": 30, "number": 2, "direction": "South", "vy": -5},
+                {"x": 0, "y": 0, "number": 1, "direction": "North"}
+            ]}"#,
+        )
+        .unwrap();
+
+        // act
+        let outcome: Vec<Vec<VisibleSet>> = (0..3)
+            .map(|step| simulation_frame(step, 2.0, 45, 15, &points).visible)
+            .collect();
+
+        // assert
+        let both = |first: &[u32], second: &[u32]| {
+            vec![
+                VisibleSet {
+                    point_number: 1,
+                    visible: first.to_vec(),
+                },
+                VisibleSet {
+                    point_number: 2,
+                    visible: second.to_vec(),
+                },
+            ]
+        };
+        assert_eq!(
+            outcome,
+            vec![both(&[], &[]), both(&[], &[]), both(&[2], &[1])]
+        );
+        let frame = simulation_frame(1, 2.5, 45, 15, &points);
+        assert_eq!(frame.time, 2.5);
+        assert_eq!(
+            frame
+                .points
+                .iter()
+                .map(|Point { coordinates, .. }| *coordinates)
+                .collect::<Vec<_>>(),
+            vec![(0, 0), (0, 18)]
+        );
+        assert_eq!(
+            frame.to_json_line(),
+            r#"{"step":1,"time":2.5,"visible":[{"point_number":1,"visible":[]},{"point_number":2,"visible":[]}]}"#
+        );
+    }
+}
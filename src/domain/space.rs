@@ -0,0 +1,378 @@
+use crate::{
+    domain::point::{parse_input_points, Direction, InputPoint},
+    utilities::{AppError, Span},
+};
+
+/// x,y,z components of a position or direction in space, with z up
+pub type Vector = (f64, f64, f64);
+
+fn dot(a: Vector, b: Vector) -> f64 {
+    a.0 * b.0 + a.1 * b.1 + a.2 * b.2
+}
+
+/// Directions straight ahead of, to the right of, and above a sensor, as unit
+/// vectors
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SensorFrame {
+    pub forward: Vector,
+    pub right: Vector,
+    pub up: Vector,
+}
+
+impl SensorFrame {
+    /// Frame of a sensor looking horizontally, along `heading`, in degrees
+    /// clockwise from north, with north along y
+    pub fn level(heading: f64) -> Self {
+        let (sin, cos) = heading.to_radians().sin_cos();
+        SensorFrame {
+            forward: (sin, cos, 0.0),
+            right: (cos, -sin, 0.0),
+            up: (0.0, 0.0, 1.0),
+        }
+    }
+
+    /// Angles, in degrees, of `offset` to the right of straight ahead, and
+    /// above it, as the sensor sees it
+    pub fn angles(&self, offset: Vector) -> (f64, f64) {
+        let (forward, right, up) = (
+            dot(offset, self.forward),
+            dot(offset, self.right),
+            dot(offset, self.up),
+        );
+        (
+            right.atan2(forward).to_degrees(),
+            up.atan2(forward.hypot(right)).to_degrees(),
+        )
+    }
+}
+
+/// Orientation of a sensor in 3D, as Euler angles, in degrees, turning it
+/// first to `yaw`, clockwise from north, then tilting it up by `pitch`, and
+/// last rolling it clockwise, as seen from behind, by `roll`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Orientation {
+    pub yaw: f64,
+    pub pitch: f64,
+    pub roll: f64,
+}
+
+impl Orientation {
+    /// Orientation of a sensor looking horizontally along `direction`
+    pub fn level(direction: Direction) -> Self {
+        Orientation {
+            yaw: direction.bearing().to_degrees(),
+            pitch: 0.0,
+            roll: 0.0,
+        }
+    }
+
+    /// Frame of a sensor with this orientation
+    pub fn frame(&self) -> SensorFrame {
+        let SensorFrame { forward, right, up } = SensorFrame::level(self.yaw);
+        let (forward, up) = turn(forward, up, self.pitch);
+        let (up, right) = turn(up, right, self.roll);
+        SensorFrame { forward, right, up }
+    }
+
+    /// Orientation giving `frame`, with no roll when the sensor looks
+    /// straight up or down, and yaw alone sets which way is right
+    pub fn from_frame(frame: &SensorFrame) -> Self {
+        let SensorFrame { forward, right, up } = *frame;
+        let pitch = forward.2.clamp(-1.0, 1.0).asin().to_degrees();
+        let (yaw, roll) = if forward.0.hypot(forward.1) < 1e-9 {
+            ((-right.1).atan2(right.0).to_degrees(), 0.0)
+        } else {
+            (
+                forward.0.atan2(forward.1).to_degrees(),
+                (-right.2).atan2(up.2).to_degrees(),
+            )
+        };
+        Orientation {
+            yaw: yaw.rem_euclid(360.0),
+            pitch,
+            roll,
+        }
+    }
+
+    /// Orientation given by the quaternion `[x, y, z, w]`, as flight
+    /// controllers log it, rotating the sensor’s forward, right and down axes
+    /// onto north, east and down.  Returns `None` for a quaternion with no
+    /// length.  The quaternion need not be normalised.
+    pub fn from_quaternion([x, y, z, w]: [f64; 4]) -> Option<Self> {
+        let length = (x * x + y * y + z * z + w * w).sqrt();
+        if !(length.is_finite() && length > 0.0) {
+            return None;
+        }
+        let (x, y, z, w) = (x / length, y / length, z / length, w / length);
+        let rotate = |v: Vector| {
+            let cross = |a: Vector, b: Vector| {
+                (
+                    a.1 * b.2 - a.2 * b.1,
+                    a.2 * b.0 - a.0 * b.2,
+                    a.0 * b.1 - a.1 * b.0,
+                )
+            };
+            let t = cross((x, y, z), v);
+            let t = (2.0 * t.0, 2.0 * t.1, 2.0 * t.2);
+            let u = cross((x, y, z), t);
+            let (north, east, down) = (
+                v.0 + w * t.0 + u.0,
+                v.1 + w * t.1 + u.1,
+                v.2 + w * t.2 + u.2,
+            );
+            (east, north, -down)
+        };
+        let down = rotate((0.0, 0.0, 1.0));
+        Some(Orientation::from_frame(&SensorFrame {
+            forward: rotate((1.0, 0.0, 0.0)),
+            right: rotate((0.0, 1.0, 0.0)),
+            up: (-down.0, -down.1, -down.2),
+        }))
+    }
+}
+
+/// Turn the perpendicular unit vectors `a` and `b` through `angle` degrees,
+/// in their plane, from `a` towards `b`
+fn turn(a: Vector, b: Vector, angle: f64) -> (Vector, Vector) {
+    let (sin, cos) = angle.to_radians().sin_cos();
+    (
+        (
+            a.0 * cos + b.0 * sin,
+            a.1 * cos + b.1 * sin,
+            a.2 * cos + b.2 * sin,
+        ),
+        (
+            b.0 * cos - a.0 * sin,
+            b.1 * cos - a.1 * sin,
+            b.2 * cos - a.2 * sin,
+        ),
+    )
+}
+
+/// Represents a point in 3D mode, with a height, `z`, above the plan
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SpacePoint {
+    /// x,y,z coordinates of the point
+    pub coordinates: Vector,
+    pub number: u32,
+    pub orientation: Orientation,
+}
+
+impl From<InputPoint> for SpacePoint {
+    fn from(input: InputPoint) -> Self {
+        let level = Orientation::level(input.direction);
+        let quaternion = input.quaternion.and_then(Orientation::from_quaternion);
+        SpacePoint {
+            coordinates: (
+                f64::from(input.x),
+                f64::from(input.y),
+                input.z.unwrap_or(0.0),
+            ),
+            number: input.number,
+            orientation: quaternion.unwrap_or(Orientation {
+                yaw: input.yaw.unwrap_or(level.yaw),
+                pitch: input.pitch.unwrap_or(0.0),
+                roll: input.roll.unwrap_or(0.0),
+            }),
+        }
+    }
+}
+
+impl SpacePoint {
+    /// Frame the point looks out along
+    pub fn frame(&self) -> SensorFrame {
+        self.orientation.frame()
+    }
+}
+
+/// Extent of a sensor’s view in 3D mode, as half angles, in degrees, either
+/// side of straight ahead, across and up or down, and a range
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ViewCone {
+    pub horizontal: f64,
+
+    /// Half angle of the vertical field of view, so `90` gives a full vertical
+    /// fan, and a ceiling camera’s narrow field may be modelled
+    pub vertical: f64,
+
+    pub radius: f64,
+}
+
+impl ViewCone {
+    /// Returns true if a sensor at `from`, looking out along `frame`, sees a
+    /// point at `to`.  A point at the sensor’s own position is always seen.
+    pub fn contains(&self, from: Vector, frame: &SensorFrame, to: Vector) -> bool {
+        let offset = (to.0 - from.0, to.1 - from.1, to.2 - from.2);
+        let distance = dot(offset, offset).sqrt();
+        if distance >= self.radius {
+            return false;
+        }
+        if distance == 0.0 {
+            return true;
+        }
+        let (across, up) = frame.angles(offset);
+        across.abs() <= self.horizontal && up.abs() <= self.vertical
+    }
+}
+
+/// Parse a points JSON string, as for [`parse_points`](crate::domain::parse_points),
+/// as 3D points, with each point’s optional `z`, or `0`
+pub fn parse_space_points(json: &str) -> Result<Vec<SpacePoint>, AppError> {
+    let _span = Span::enter("parse_space_points", || format!("{} bytes", json.len()));
+    let points = parse_input_points(json)?;
+    Ok(points.into_iter().map(SpacePoint::from).collect())
+}
+
+/// Return `neighbourhood` points within `cone` of the point numbered
+/// `point_number`, looking out along its own frame, in 3D.  An empty vector
+/// is returned if no point has that number, and the point itself is never
+/// included.  Points are returned in ascending number order.
+pub fn visible_space_points<'a>(
+    point_number: u32,
+    cone: &ViewCone,
+    neighbourhood: &'a [SpacePoint],
+) -> Vec<&'a SpacePoint> {
+    let _span = Span::enter("visible_space_points", || {
+        format!("point {point_number} of {}", neighbourhood.len())
+    });
+    let sensor = match neighbourhood
+        .iter()
+        .find(|SpacePoint { number, .. }| *number == point_number)
+    {
+        Some(value) => value,
+        None => return vec![],
+    };
+    let frame = sensor.frame();
+    let mut result: Vec<&SpacePoint> = neighbourhood
+        .iter()
+        .filter(|neighbour| {
+            neighbour.number != point_number
+                && cone.contains(sensor.coordinates, &frame, neighbour.coordinates)
+        })
+        .collect();
+    result.sort_by_key(|SpacePoint { number, .. }| *number);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_space_points, visible_space_points, Orientation, Vector, ViewCone};
+
+    #[test]
+    fn visible_space_points_allows_for_vertical_field() {
+        // arrange
+        let json = r#"{"points": [
+            {"x": 0, "y": 0, "z": 3, "number": 1, "direction": "East"},
+            {"x": 10, "y": 0, "z": 3, "number": 2, "direction": "North"},
+            {"x": 10, "y": 0, "z": 0, "number": 3, "direction": "North"},
+            {"x": 4, "y": 3, "number": 4, "direction": "North"},
+            {"x": 0, "y": 0, "number": 5, "direction": "North"},
+            {"x": -10, "y": 0, "z": 3, "number": 6, "direction": "North"}
+        ]}"#;
+        let points = parse_space_points(json).unwrap();
+        let cone = ViewCone {
+            horizontal: 45.0,
+            vertical: 10.0,
+            radius: 20.0,
+        };
+        let numbers = |cone: &ViewCone| -> Vec<u32> {
+            visible_space_points(1, cone, &points)
+                .iter()
+                .map(|point| point.number)
+                .collect()
+        };
+
+        // act
+        let outcome = numbers(&cone);
+
+        // assert
+        assert_eq!(outcome, vec![2]);
+        let fan = ViewCone {
+            vertical: 90.0,
+            ..cone
+        };
+        assert_eq!(numbers(&fan), vec![2, 3, 4, 5]);
+        assert!(visible_space_points(7, &cone, &points).is_empty());
+    }
+
+    #[test]
+    fn orientation_frame_gives_expected_result() {
+        // arrange
+        let drone = Orientation {
+            yaw: 90.0,
+            pitch: -30.0,
+            roll: 0.0,
+        };
+        let close = |a: Vector, b: Vector| {
+            (a.0 - b.0).abs() < 1e-9 && (a.1 - b.1).abs() < 1e-9 && (a.2 - b.2).abs() < 1e-9
+        };
+
+        // act
+        let outcome = drone.frame();
+
+        // assert
+        assert!(close(outcome.forward, (0.75_f64.sqrt(), 0.0, -0.5)));
+        assert!(close(outcome.right, (0.0, -1.0, 0.0)));
+        assert!(close(outcome.up, (0.5, 0.0, 0.75_f64.sqrt())));
+        let rolled = Orientation {
+            yaw: 0.0,
+            pitch: 0.0,
+            roll: 90.0,
+        }
+        .frame();
+        assert!(close(rolled.right, (0.0, 0.0, -1.0)));
+        assert!(close(rolled.up, (1.0, 0.0, 0.0)));
+        let points = parse_space_points(
+            r#"{"points": [
+                {"x": 0, "y": 0, "z": 30, "number": 1, "direction": "North", "yaw": 90, "pitch": -45},
+                {"x": 30, "y": 0, "number": 2, "direction": "North"},
+                {"x": 30, "y": 0, "z": 30, "number": 3, "direction": "North"}
+            ]}"#,
+        )
+        .unwrap();
+        let cone = ViewCone {
+            horizontal: 20.0,
+            vertical: 20.0,
+            radius: 50.0,
+        };
+        let seen: Vec<u32> = visible_space_points(1, &cone, &points)
+            .iter()
+            .map(|point| point.number)
+            .collect();
+        assert_eq!(seen, vec![2]);
+    }
+
+    #[test]
+    fn orientation_from_quaternion_gives_expected_result() {
+        // arrange
+        let half = |angle: f64| (angle.to_radians() / 2.0).sin_cos();
+        let ((yaw_sin, yaw_cos), (pitch_sin, pitch_cos)) = (half(90.0), half(-30.0));
+        let quaternion = [
+            -yaw_sin * pitch_sin,
+            yaw_cos * pitch_sin,
+            yaw_sin * pitch_cos,
+            yaw_cos * pitch_cos,
+        ];
+
+        // act
+        let outcome = Orientation::from_quaternion(quaternion).unwrap();
+
+        // assert
+        assert!((outcome.yaw - 90.0).abs() < 1e-9);
+        assert!((outcome.pitch + 30.0).abs() < 1e-9);
+        assert!(outcome.roll.abs() < 1e-9);
+        let straight_down = Orientation::from_quaternion([0.0, -half(90.0).0, 0.0, half(90.0).1])
+            .unwrap()
+            .frame();
+        assert!((straight_down.forward.2 + 1.0).abs() < 1e-9);
+        assert!((straight_down.right.0 - 1.0).abs() < 1e-9);
+        assert_eq!(Orientation::from_quaternion([0.0; 4]), None);
+        let points = parse_space_points(
+            r#"{"points": [
+                {"x": 0, "y": 0, "number": 1, "direction": "North", "quaternion": [0, 0, 0.7071068, 0.7071068]}
+            ]}"#,
+        )
+        .unwrap();
+        assert!((points[0].orientation.yaw - 90.0).abs() < 1e-4);
+    }
+}
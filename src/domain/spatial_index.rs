@@ -0,0 +1,200 @@
+use crate::domain::{
+    ball_tree::BallTree,
+    grid_index::GridIndex,
+    hnsw::{HnswIndex, HnswParameters},
+    metric::Euclidean,
+    point::{euclidean_distance, Point},
+};
+use serde::Serialize;
+use std::{fmt, str::FromStr};
+
+/// Index over a neighbourhood, which a [`VisibilityEngine`] asks for points
+/// near each query, rather than checking every point.  Implement it to plug in
+/// an index of your own, for example a game engine's broadphase, and pass it
+/// to [`VisibilityEngine::with_index`].  [`LinearScan`], [`GridIndex`],
+/// [`HnswIndex`] and [`BallTree`] are built in.
+///
+/// [`VisibilityEngine`]: crate::domain::VisibilityEngine
+/// [`VisibilityEngine::with_index`]: crate::domain::VisibilityEngine::with_index
+pub trait SpatialIndex {
+    /// Points closer than `radius` to `center`, in any order.  Extra points
+    /// further away are allowed, since the engine checks each point it is
+    /// given, but points missed are missing from results.
+    fn range_query<'a>(
+        &'a self,
+        center: (i32, i32),
+        radius: f64,
+    ) -> Box<dyn Iterator<Item = &'a Point> + 'a>;
+
+    /// Replace the indexed points with `points`, which the engine calls when
+    /// given the index, and before the first query after updates to its
+    /// neighbourhood
+    fn rebuild(&mut self, points: &[Point]);
+}
+
+/// Index which checks every point, the quickest choice for small
+/// neighbourhoods, and a baseline for comparing others against
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LinearScan {
+    points: Vec<Point>,
+}
+
+impl LinearScan {
+    pub fn new(points: &[Point]) -> Self {
+        LinearScan {
+            points: points.to_vec(),
+        }
+    }
+}
+
+impl SpatialIndex for LinearScan {
+    fn range_query<'a>(
+        &'a self,
+        center: (i32, i32),
+        radius: f64,
+    ) -> Box<dyn Iterator<Item = &'a Point> + 'a> {
+        Box::new(
+            self.points
+                .iter()
+                .filter(move |point| euclidean_distance(center, point.coordinates) < radius),
+        )
+    }
+
+    fn rebuild(&mut self, points: &[Point]) {
+        self.points = points.to_vec();
+    }
+}
+
+/// Built-in [`SpatialIndex`] backends, by name
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IndexBackend {
+    /// [`LinearScan`]
+    Linear,
+
+    /// [`HnswIndex`], with default parameters
+    Hnsw,
+
+    /// [`BallTree`], with the [`Euclidean`] metric
+    BallTree,
+
+    /// [`GridIndex`], with cells sized by the density of points
+    Grid,
+}
+
+impl IndexBackend {
+    /// Every built-in backend, linear scan first
+    pub const ALL: [IndexBackend; 4] = [
+        IndexBackend::Linear,
+        IndexBackend::Hnsw,
+        IndexBackend::BallTree,
+        IndexBackend::Grid,
+    ];
+
+    /// Build this kind of index over `points`
+    pub fn build(self, points: &[Point]) -> Box<dyn SpatialIndex + Send + Sync> {
+        match self {
+            IndexBackend::Linear => Box::new(LinearScan::new(points)),
+            IndexBackend::Hnsw => Box::new(HnswIndex::new(points, HnswParameters::default())),
+            IndexBackend::BallTree => Box::new(BallTree::new(points, Euclidean)),
+            IndexBackend::Grid => Box::new(GridIndex::with_density(points)),
+        }
+    }
+
+    /// Returns true if the backend may miss points in range
+    pub fn is_approximate(self) -> bool {
+        self == IndexBackend::Hnsw
+    }
+}
+
+impl fmt::Display for IndexBackend {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str(match self {
+            IndexBackend::Linear => "linear",
+            IndexBackend::Hnsw => "hnsw",
+            IndexBackend::BallTree => "ball-tree",
+            IndexBackend::Grid => "grid",
+        })
+    }
+}
+
+impl FromStr for IndexBackend {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        IndexBackend::ALL
+            .iter()
+            .copied()
+            .find(|backend| backend.to_string() == value)
+            .ok_or_else(|| {
+                format!("unknown index `{value}`, expected `linear`, `hnsw`, `ball-tree` or `grid`")
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LinearScan, SpatialIndex};
+    use crate::domain::{
+        BallTree, Direction, Euclidean, HnswIndex, HnswParameters, Point, VisibilityEngine,
+    };
+
+    /// Index answering every query with every point, as a coarse broadphase
+    /// might
+    struct Everything(Vec<Point>);
+
+    impl SpatialIndex for Everything {
+        fn range_query<'a>(
+            &'a self,
+            _center: (i32, i32),
+            _radius: f64,
+        ) -> Box<dyn Iterator<Item = &'a Point> + 'a> {
+            Box::new(self.0.iter())
+        }
+
+        fn rebuild(&mut self, points: &[Point]) {
+            self.0 = points.to_vec();
+        }
+    }
+
+    #[test]
+    fn spatial_indexes_agree_on_range_queries() {
+        // arrange
+        let points: Vec<Point> = (0..200)
+            .map(|index| Point {
+                coordinates: (((index * 37) % 61) - 30, ((index * 23) % 53) - 26),
+                number: index as u32 + 1,
+                direction: Direction::East,
+            })
+            .collect();
+        let indexes: Vec<Box<dyn SpatialIndex>> = vec![
+            Box::new(LinearScan::new(&points)),
+            Box::new(HnswIndex::new(&points, HnswParameters::default())),
+            Box::new(BallTree::new(&points, Euclidean)),
+        ];
+
+        // act
+        let outcome: Vec<Vec<u32>> = indexes
+            .iter()
+            .map(|index| {
+                let mut numbers: Vec<u32> = index
+                    .range_query((5, -3), 9.5)
+                    .map(|point| point.number)
+                    .collect();
+                numbers.sort_unstable();
+                numbers
+            })
+            .collect();
+
+        // assert
+        assert!(!outcome[0].is_empty());
+        assert_eq!(outcome[1], outcome[0]);
+        assert_eq!(outcome[2], outcome[0]);
+        let engine = VisibilityEngine::new(vec![]).with_index(Everything(vec![]));
+        engine.replace(points.clone());
+        assert_eq!(
+            engine.visible_points(7, 60, 12),
+            VisibilityEngine::new(points).visible_points(7, 60, 12)
+        );
+    }
+}
@@ -0,0 +1,249 @@
+//! Reader for table b-trees in the SQLite database file format
+
+/// Value stored in a record column
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum SqlValue {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(String),
+    Blob(Vec<u8>),
+}
+
+/// Database file, held in memory, with its page geometry
+pub(crate) struct Database<'a> {
+    bytes: &'a [u8],
+    page_size: usize,
+    usable_size: usize,
+}
+
+/// Deepest table b-tree accepted, guarding against loops in corrupt files
+const MAXIMUM_DEPTH: usize = 32;
+
+/// Read a SQLite varint at `position`, returning the value and its length
+fn varint(bytes: &[u8], position: usize) -> Result<(u64, usize), String> {
+    let mut result = 0_u64;
+    for index in 0..9 {
+        let byte = *bytes
+            .get(position + index)
+            .ok_or_else(|| String::from("truncated varint"))?;
+        if index == 8 {
+            return Ok(((result << 8) | u64::from(byte), 9));
+        }
+        result = (result << 7) | u64::from(byte & 0x7f);
+        if byte & 0x80 == 0 {
+            return Ok((result, index + 1));
+        }
+    }
+    Err(String::from("truncated varint"))
+}
+
+/// Read a big endian unsigned integer of `length` bytes at `position`
+fn read_be(bytes: &[u8], position: usize, length: usize) -> Result<u64, String> {
+    let slice = bytes
+        .get(position..position + length)
+        .ok_or_else(|| String::from("truncated database page"))?;
+    Ok(slice
+        .iter()
+        .fold(0_u64, |acc, byte| (acc << 8) | u64::from(*byte)))
+}
+
+/// Decode a record, in the SQLite record format, into its column values
+pub(crate) fn decode_record(payload: &[u8]) -> Result<Vec<SqlValue>, String> {
+    let (header_length, mut header_position) = varint(payload, 0)?;
+    let header_length = header_length as usize;
+    let mut body_position = header_length;
+    let mut result = vec![];
+    while header_position < header_length {
+        let (serial_type, length) = varint(payload, header_position)?;
+        header_position += length;
+        let (value, length) = match serial_type {
+            0 => (SqlValue::Null, 0),
+            1..=6 => {
+                let length = [1, 2, 3, 4, 6, 8][serial_type as usize - 1];
+                let raw = read_be(payload, body_position, length)?;
+                // sign extend from the stored width
+                let shift = 64 - 8 * length as u32;
+                (SqlValue::Integer(((raw << shift) as i64) >> shift), length)
+            }
+            7 => {
+                let raw = read_be(payload, body_position, 8)?;
+                (SqlValue::Real(f64::from_bits(raw)), 8)
+            }
+            8 => (SqlValue::Integer(0), 0),
+            9 => (SqlValue::Integer(1), 0),
+            value if value >= 12 => {
+                let length = ((value - 12) / 2) as usize;
+                let bytes = payload
+                    .get(body_position..body_position + length)
+                    .ok_or_else(|| String::from("truncated record"))?;
+                match value % 2 {
+                    0 => (SqlValue::Blob(bytes.to_vec()), length),
+                    _ => (
+                        SqlValue::Text(String::from_utf8_lossy(bytes).into_owned()),
+                        length,
+                    ),
+                }
+            }
+            _ => return Err(format!("invalid serial type {serial_type}")),
+        };
+        body_position += length;
+        result.push(value);
+    }
+    Ok(result)
+}
+
+impl<'a> Database<'a> {
+    /// Check the header of database file `bytes`, and read its page geometry
+    pub(crate) fn new(bytes: &'a [u8]) -> Result<Self, String> {
+        if bytes.len() < 100 || &bytes[..16] != b"SQLite format 3\0" {
+            return Err(String::from("missing SQLite header"));
+        }
+        let page_size = match read_be(bytes, 16, 2)? {
+            1 => 65_536,
+            value => value as usize,
+        };
+        if page_size < 512 || !page_size.is_power_of_two() {
+            return Err(format!("invalid page size {page_size}"));
+        }
+        if read_be(bytes, 56, 4)? > 1 {
+            return Err(String::from("only UTF-8 databases are supported"));
+        }
+        let reserved = usize::from(bytes[20]);
+        Ok(Database {
+            bytes,
+            page_size,
+            usable_size: page_size - reserved,
+        })
+    }
+
+    fn page(&self, number: u64) -> Result<&'a [u8], String> {
+        let start = (number as usize)
+            .checked_sub(1)
+            .map(|index| index * self.page_size)
+            .ok_or_else(|| String::from("invalid page number 0"))?;
+        self.bytes
+            .get(start..start + self.page_size)
+            .ok_or_else(|| format!("page {number} is past the end of the file"))
+    }
+
+    /// Payload of the leaf cell at `position` in `page`, following overflow
+    /// pages where the payload does not fit
+    fn payload(&self, page: &[u8], position: usize) -> Result<(i64, Vec<u8>), String> {
+        let (payload_length, length) = varint(page, position)?;
+        let (rowid, rowid_length) = varint(page, position + length)?;
+        let start = position + length + rowid_length;
+        let payload_length = payload_length as usize;
+        let usable = self.usable_size;
+        let maximum_local = usable - 35;
+        if payload_length <= maximum_local {
+            let payload = page
+                .get(start..start + payload_length)
+                .ok_or_else(|| String::from("truncated cell"))?;
+            return Ok((rowid as i64, payload.to_vec()));
+        }
+
+        let minimum_local = (usable - 12) * 32 / 255 - 23;
+        let local = match minimum_local + (payload_length - minimum_local) % (usable - 4) {
+            value if value <= maximum_local => value,
+            _ => minimum_local,
+        };
+        let mut payload = page
+            .get(start..start + local)
+            .ok_or_else(|| String::from("truncated cell"))?
+            .to_vec();
+        let mut overflow = read_be(page, start + local, 4)?;
+        while payload.len() < payload_length {
+            if overflow == 0 || payload.len() > self.bytes.len() {
+                return Err(String::from("truncated overflow chain"));
+            }
+            let page = self.page(overflow)?;
+            overflow = read_be(page, 0, 4)?;
+            let take = (payload_length - payload.len()).min(usable - 4);
+            payload.extend_from_slice(&page[4..4 + take]);
+        }
+        Ok((rowid as i64, payload))
+    }
+
+    /// Visit every row of the table b-tree rooted at page `root`, in rowid
+    /// order, with its rowid and record payload
+    pub(crate) fn scan_table<F>(&self, root: u64, visit: &mut F) -> Result<(), String>
+    where
+        F: FnMut(i64, &[u8]) -> Result<(), String>,
+    {
+        self.scan_page(root, visit, 0)
+    }
+
+    fn scan_page<F>(&self, number: u64, visit: &mut F, depth: usize) -> Result<(), String>
+    where
+        F: FnMut(i64, &[u8]) -> Result<(), String>,
+    {
+        if depth > MAXIMUM_DEPTH {
+            return Err(String::from("table b-tree too deep"));
+        }
+        let page = self.page(number)?;
+        // the first page starts with the 100 byte database header
+        let header = if number == 1 { 100 } else { 0 };
+        let cell_count = read_be(page, header + 3, 2)? as usize;
+        match page.get(header) {
+            Some(0x0d) => {
+                for index in 0..cell_count {
+                    let pointer = read_be(page, header + 8 + 2 * index, 2)? as usize;
+                    let (rowid, payload) = self.payload(page, pointer)?;
+                    visit(rowid, &payload)?;
+                }
+                Ok(())
+            }
+            Some(0x05) => {
+                for index in 0..cell_count {
+                    let pointer = read_be(page, header + 12 + 2 * index, 2)? as usize;
+                    self.scan_page(read_be(page, pointer, 4)?, visit, depth + 1)?;
+                }
+                self.scan_page(read_be(page, header + 8, 4)?, visit, depth + 1)
+            }
+            _ => Err(format!("page {number} is not a table b-tree page")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_record, varint, SqlValue};
+
+    #[test]
+    fn decode_record_gives_expected_result() {
+        // arrange
+        // header of 6 bytes: NULL, 8-bit integer, 16-bit integer, zero, text
+        // of length 2
+        let payload = [
+            0x06, 0x00, 0x01, 0x02, 0x08, 0x11, 0xff, 0x01, 0x00, b'h', b'i',
+        ];
+
+        // act
+        let outcome = decode_record(&payload).unwrap();
+
+        // assert
+        assert_eq!(
+            outcome,
+            vec![
+                SqlValue::Null,
+                SqlValue::Integer(-1),
+                SqlValue::Integer(256),
+                SqlValue::Integer(0),
+                SqlValue::Text(String::from("hi"))
+            ]
+        );
+    }
+
+    #[test]
+    fn varint_gives_expected_result() {
+        // arrange
+        let bytes = [0x81, 0x00, 0x7f];
+
+        // act
+        let outcome = (varint(&bytes, 0).unwrap(), varint(&bytes, 2).unwrap());
+
+        // assert
+        assert_eq!(outcome, ((128, 2), (127, 1)));
+    }
+}
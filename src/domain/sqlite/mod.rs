@@ -0,0 +1,491 @@
+//! Reader for points stored in SQLite database files.  Queries are limited to
+//! selecting columns of a single table, optionally renamed with `AS`, for
+//! example `SELECT point AS number, easting AS x, northing AS y, heading AS
+//! direction FROM survey`.  The result must have `number`, `x`, `y` and
+//! `direction` columns, with integer coordinates and numbers, and `direction`
+//! names as text.
+
+mod btree;
+
+use crate::{
+    domain::point::{Direction, Point},
+    utilities::{AppError, Span},
+};
+use btree::{decode_record, Database, SqlValue};
+use std::{convert::TryFrom, fs, path::Path};
+
+/// Query used for SQLite files when none is given
+pub const DEFAULT_SQLITE_QUERY: &str = "SELECT number, x, y, direction FROM points";
+
+/// Columns read, in the order they are combined into points
+const COLUMNS: [&str; 4] = ["number", "x", "y", "direction"];
+
+fn sqlite_error(message: String) -> AppError {
+    AppError::SQLiteError(message)
+}
+
+/// Column of a `SELECT` statement, naming a table column, and the name it
+/// takes in the result
+#[derive(Clone, Debug, PartialEq)]
+struct ResultColumn {
+    source: String,
+    name: String,
+}
+
+/// Parsed `SELECT` statement, where `None` columns means `*`
+#[derive(Clone, Debug, PartialEq)]
+struct Select {
+    columns: Option<Vec<ResultColumn>>,
+    table: String,
+}
+
+/// Split `sql` into words, quoted identifiers or strings, and single
+/// punctuation characters, removing quotes.  Quoted tokens are flagged, so
+/// they are never taken for keywords.
+fn tokenise(sql: &str) -> Result<Vec<(String, bool)>, AppError> {
+    let mut result = vec![];
+    let mut characters = sql.chars().peekable();
+    while let Some(character) = characters.next() {
+        match character {
+            value if value.is_whitespace() => {}
+            '"' | '`' | '[' | '\'' => {
+                let close = match character {
+                    '[' => ']',
+                    value => value,
+                };
+                let mut token = String::new();
+                loop {
+                    match characters.next() {
+                        // doubled quotes stand for the quote character
+                        Some(value) if value == close && characters.peek() == Some(&close) => {
+                            characters.next();
+                            token.push(value);
+                        }
+                        Some(value) if value == close => break,
+                        Some(value) => token.push(value),
+                        None => return Err(sqlite_error(String::from("unterminated quote"))),
+                    }
+                }
+                result.push((token, true));
+            }
+            value if value.is_alphanumeric() || value == '_' => {
+                let mut token = value.to_string();
+                while let Some(value) = characters
+                    .peek()
+                    .copied()
+                    .filter(|value| value.is_alphanumeric() || *value == '_' || *value == '$')
+                {
+                    token.push(value);
+                    characters.next();
+                }
+                result.push((token, false));
+            }
+            value => result.push((value.to_string(), false)),
+        }
+    }
+    Ok(result)
+}
+
+fn is_keyword(token: &(String, bool), keyword: &str) -> bool {
+    !token.1 && token.0.eq_ignore_ascii_case(keyword)
+}
+
+/// Parse `sql`, which should have the form `SELECT columns FROM table`, where
+/// columns are `*`, or a comma separated list of column names, each optionally
+/// followed by `AS` and the name to use in the result
+fn parse_select(sql: &str) -> Result<Select, AppError> {
+    let mut tokens = tokenise(sql)?;
+    if tokens.last().map(|token| token.0.as_str()) == Some(";") {
+        tokens.pop();
+    }
+    let unsupported = || {
+        sqlite_error(format!(
+            "unsupported query `{sql}`, expected `SELECT columns FROM table`"
+        ))
+    };
+    if tokens.len() < 4 || !is_keyword(&tokens[0], "SELECT") {
+        return Err(unsupported());
+    }
+    let from = tokens
+        .iter()
+        .position(|token| is_keyword(token, "FROM"))
+        .ok_or_else(unsupported)?;
+    let table = match &tokens[from + 1..] {
+        [(name, _)] => name.clone(),
+        _ => return Err(unsupported()),
+    };
+
+    let columns = match &tokens[1..from] {
+        [(star, false)] if star == "*" => None,
+        list => {
+            let mut columns = vec![];
+            for column in list.split(|token| token == &(String::from(","), false)) {
+                let column = match column {
+                    [(source, _)] => ResultColumn {
+                        source: source.clone(),
+                        name: source.clone(),
+                    },
+                    [(source, _), as_keyword, (name, _)] if is_keyword(as_keyword, "AS") => {
+                        ResultColumn {
+                            source: source.clone(),
+                            name: name.clone(),
+                        }
+                    }
+                    [(source, _), (name, _)] => ResultColumn {
+                        source: source.clone(),
+                        name: name.clone(),
+                    },
+                    _ => return Err(unsupported()),
+                };
+                columns.push(column);
+            }
+            Some(columns)
+        }
+    };
+    Ok(Select { columns, table })
+}
+
+/// Column names from `CREATE TABLE` statement `sql`, with the index of any
+/// `INTEGER PRIMARY KEY` column, which is stored as the rowid
+fn table_columns(sql: &str) -> Result<(Vec<String>, Option<usize>), AppError> {
+    let tokens = tokenise(sql)?;
+    let open = tokens
+        .iter()
+        .position(|token| token == &(String::from("("), false))
+        .ok_or_else(|| sqlite_error(String::from("unsupported table definition")))?;
+
+    // split column definitions on commas outside of parentheses
+    let mut definitions: Vec<&[(String, bool)]> = vec![];
+    let mut depth = 0;
+    let mut start = open + 1;
+    let mut close = None;
+    for (index, token) in tokens.iter().enumerate().skip(open + 1) {
+        match (token.0.as_str(), token.1) {
+            ("(", false) => depth += 1,
+            (")", false) if depth == 0 => {
+                definitions.push(&tokens[start..index]);
+                close = Some(index);
+                break;
+            }
+            (")", false) => depth -= 1,
+            (",", false) if depth == 0 => {
+                definitions.push(&tokens[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    let close = close.ok_or_else(|| sqlite_error(String::from("unsupported table definition")))?;
+    if tokens[close + 1..]
+        .iter()
+        .any(|token| is_keyword(token, "WITHOUT"))
+    {
+        return Err(sqlite_error(String::from(
+            "WITHOUT ROWID tables are not supported",
+        )));
+    }
+
+    let mut columns = vec![];
+    let mut integer_primary_key = None;
+    for definition in definitions {
+        let first = match definition.first() {
+            Some(value) => value,
+            None => continue,
+        };
+        let constraint = ["CONSTRAINT", "PRIMARY", "UNIQUE", "CHECK", "FOREIGN"]
+            .iter()
+            .any(|keyword| is_keyword(first, keyword));
+        if constraint {
+            continue;
+        }
+        let is_integer = definition
+            .get(1)
+            .map_or(false, |token| is_keyword(token, "INTEGER"));
+        let primary_key = definition
+            .windows(2)
+            .any(|pair| is_keyword(&pair[0], "PRIMARY") && is_keyword(&pair[1], "KEY"));
+        let descending = definition.iter().any(|token| is_keyword(token, "DESC"));
+        if is_integer && primary_key && !descending {
+            integer_primary_key = Some(columns.len());
+        }
+        columns.push(first.0.clone());
+    }
+    Ok((columns, integer_primary_key))
+}
+
+/// Find the root page and definition of table `name` in the schema table
+fn find_table(database: &Database, name: &str) -> Result<(u64, String), AppError> {
+    let mut result = None;
+    database
+        .scan_table(1, &mut |_, payload| {
+            let record = decode_record(payload)?;
+            if let [SqlValue::Text(kind), SqlValue::Text(table), _, SqlValue::Integer(root), SqlValue::Text(sql)] =
+                record.as_slice()
+            {
+                if result.is_none() && kind == "table" && table.eq_ignore_ascii_case(name) {
+                    result = Some((*root as u64, sql.clone()));
+                }
+            }
+            Ok(())
+        })
+        .map_err(sqlite_error)?;
+    result.ok_or_else(|| sqlite_error(format!("no such table `{name}`")))
+}
+
+fn integer<T: TryFrom<i64>>(value: &SqlValue, name: &str) -> Result<T, AppError> {
+    match value {
+        SqlValue::Integer(value) => T::try_from(*value)
+            .map_err(|_| sqlite_error(format!("value {value} out of range for `{name}`"))),
+        SqlValue::Null => Err(sqlite_error(format!("null value for `{name}`"))),
+        _ => Err(sqlite_error(format!(
+            "column `{name}` should hold integers"
+        ))),
+    }
+}
+
+fn direction(value: &SqlValue) -> Result<Direction, AppError> {
+    match value {
+        SqlValue::Text(value) => match value.as_str() {
+            "North" => Ok(Direction::North),
+            "East" => Ok(Direction::East),
+            "South" => Ok(Direction::South),
+            "West" => Ok(Direction::West),
+            _ => Err(sqlite_error(format!("unknown direction `{value}`"))),
+        },
+        SqlValue::Null => Err(sqlite_error(String::from("null value for `direction`"))),
+        _ => Err(sqlite_error(String::from(
+            "column `direction` should hold text",
+        ))),
+    }
+}
+
+/// Parse points returned by `query` from the SQLite database held in `bytes`.
+/// See the module documentation for the queries supported.
+pub fn parse_points_sqlite(bytes: &[u8], query: &str) -> Result<Vec<Point>, AppError> {
+    let _span = Span::enter("parse_points_sqlite", || format!("{} bytes", bytes.len()));
+    let select = parse_select(query)?;
+    let database = Database::new(bytes).map_err(sqlite_error)?;
+    let (root, sql) = find_table(&database, &select.table)?;
+    let (table_columns, integer_primary_key) = table_columns(&sql)?;
+    let result_columns = select.columns.unwrap_or_else(|| {
+        table_columns
+            .iter()
+            .map(|name| ResultColumn {
+                source: name.clone(),
+                name: name.clone(),
+            })
+            .collect()
+    });
+
+    // index, in the table, of the column giving each of `COLUMNS`
+    let mut indices = [0; 4];
+    for (index, name) in indices.iter_mut().zip(COLUMNS.iter()) {
+        let column = result_columns
+            .iter()
+            .find(|column| column.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| sqlite_error(format!("query has no `{name}` column")))?;
+        *index = table_columns
+            .iter()
+            .position(|value| value.eq_ignore_ascii_case(&column.source))
+            .ok_or_else(|| {
+                sqlite_error(format!(
+                    "no such column `{}` in table `{}`",
+                    column.source, select.table
+                ))
+            })?;
+    }
+
+    let mut result = vec![];
+    let mut error = None;
+    database
+        .scan_table(root, &mut |rowid, payload| {
+            let record = decode_record(payload)?;
+            let value = |index: usize| match record.get(index) {
+                // records may omit trailing columns added after they were
+                // written, and the integer primary key is held as the rowid
+                _ if Some(index) == integer_primary_key => SqlValue::Integer(rowid),
+                Some(value) => value.clone(),
+                None => SqlValue::Null,
+            };
+            let point = integer(&value(indices[0]), COLUMNS[0]).and_then(|number| {
+                Ok(Point {
+                    coordinates: (
+                        integer(&value(indices[1]), COLUMNS[1])?,
+                        integer(&value(indices[2]), COLUMNS[2])?,
+                    ),
+                    number,
+                    direction: direction(&value(indices[3]))?,
+                })
+            });
+            match point {
+                Ok(value) => {
+                    result.push(value);
+                    Ok(())
+                }
+                Err(value) => {
+                    error = Some(value);
+                    Err(String::new())
+                }
+            }
+        })
+        .map_err(|message| error.take().unwrap_or_else(|| sqlite_error(message)))?;
+    Ok(result)
+}
+
+/// Parse points returned by `query` from the SQLite database file at `path`
+pub fn parse_points_sqlite_file<P: AsRef<Path>>(
+    path: P,
+    query: &str,
+) -> Result<Vec<Point>, AppError> {
+    let path = path.as_ref();
+    let bytes = fs::read(path).map_err(|error| AppError::BinaryFileError {
+        path: path.display().to_string(),
+        source: error,
+    })?;
+    parse_points_sqlite(&bytes, query)
+}
+
+/// Returns true if `path` has a `.sqlite`, `.sqlite3` or `.db` extension
+pub(crate) fn is_sqlite_path(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|value| value.to_str()),
+        Some("sqlite" | "sqlite3" | "db")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        parse_points_sqlite, parse_select, table_columns, ResultColumn, DEFAULT_SQLITE_QUERY,
+    };
+    use crate::domain::{parse_points_file, Direction, Point};
+    use std::fs;
+
+    #[test]
+    fn parse_select_gives_expected_result() {
+        // arrange
+        let sql = "select point AS number, \"x\", [y] FROM `survey`;";
+
+        // act
+        let outcome = parse_select(sql).unwrap();
+
+        // assert
+        assert_eq!(outcome.table, "survey");
+        assert_eq!(
+            outcome.columns,
+            Some(vec![
+                ResultColumn {
+                    source: String::from("point"),
+                    name: String::from("number")
+                },
+                ResultColumn {
+                    source: String::from("x"),
+                    name: String::from("x")
+                },
+                ResultColumn {
+                    source: String::from("y"),
+                    name: String::from("y")
+                },
+            ])
+        );
+        assert_eq!(parse_select("SELECT * FROM points").unwrap().columns, None);
+        assert!(parse_select("SELECT * FROM points WHERE x > 0").is_err());
+        assert!(parse_select("DELETE FROM points").is_err());
+    }
+
+    #[test]
+    fn table_columns_gives_expected_result() {
+        // arrange
+        let sql = "CREATE TABLE points (id INTEGER PRIMARY KEY, number INTEGER NOT NULL, \
+                   x NUMERIC(10, 2), \"direction\" TEXT, UNIQUE (number))";
+
+        // act
+        let outcome = table_columns(sql).unwrap();
+
+        // assert
+        assert_eq!(
+            outcome,
+            (
+                vec![
+                    String::from("id"),
+                    String::from("number"),
+                    String::from("x"),
+                    String::from("direction")
+                ],
+                Some(0)
+            )
+        );
+    }
+
+    #[test]
+    fn parse_points_sqlite_gives_expected_result() {
+        // arrange
+        let bytes = fs::read("./fixtures/valid_points.sqlite").unwrap();
+
+        // act
+        let outcome = parse_points_sqlite(&bytes, DEFAULT_SQLITE_QUERY).unwrap();
+
+        // assert
+        assert_eq!(
+            outcome,
+            parse_points_file("./fixtures/valid_points.json").unwrap()
+        );
+        assert_eq!(
+            parse_points_file("./fixtures/valid_points.sqlite").unwrap(),
+            outcome
+        );
+
+        // act
+        let outcome = parse_points_sqlite(
+            &bytes,
+            "SELECT point AS number, easting AS x, northing AS y, heading AS direction FROM survey",
+        )
+        .unwrap();
+
+        // assert
+        assert_eq!(
+            outcome,
+            vec![Point {
+                coordinates: (-1000, 70000),
+                number: 7,
+                direction: Direction::West
+            }]
+        );
+    }
+
+    #[test]
+    fn parse_points_sqlite_handles_invalid_input() {
+        // arrange
+        let bytes = fs::read("./fixtures/valid_points.sqlite").unwrap();
+
+        // act
+        let not_sqlite = parse_points_sqlite(b"{\"points\": []}", DEFAULT_SQLITE_QUERY)
+            .unwrap_err()
+            .to_string();
+        let missing_table = parse_points_sqlite(&bytes, "SELECT * FROM other")
+            .unwrap_err()
+            .to_string();
+        let missing_column = parse_points_sqlite(&bytes, "SELECT number, x, y FROM points")
+            .unwrap_err()
+            .to_string();
+        let wrong_type =
+            parse_points_sqlite(&bytes, "SELECT number, x, y, note AS direction FROM points")
+                .unwrap_err()
+                .to_string();
+
+        // assert
+        assert_eq!(
+            not_sqlite,
+            "Error reading SQLite input: missing SQLite header"
+        );
+        assert_eq!(
+            missing_table,
+            "Error reading SQLite input: no such table `other`"
+        );
+        assert_eq!(
+            missing_column,
+            "Error reading SQLite input: query has no `direction` column"
+        );
+        assert!(wrong_type.starts_with("Error reading SQLite input: "));
+    }
+}
@@ -0,0 +1,138 @@
+use crate::domain::graph::VisibilityGraph;
+use serde::Serialize;
+
+/// Visibility figures for one point of a neighbourhood
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct PointSummary {
+    pub point_number: u32,
+
+    /// Number of points this point sees
+    pub visible_count: usize,
+
+    /// Mean distance to the points this point sees, or `None` if it sees none
+    pub mean_distance: Option<f64>,
+
+    /// Number of points this point sees, which also see it
+    pub reciprocal_count: usize,
+}
+
+impl PointSummary {
+    /// Summary as a single line of JSON, without a trailing newline
+    pub fn to_json_line(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+/// Summarise every point of `graph`: how many points each sees, how far
+/// away they are on average, and how many see it back, so all points are
+/// covered from one graph, rather than a query per point.  Summaries are in
+/// ascending point number order.
+pub fn visibility_summary(graph: &VisibilityGraph) -> Vec<PointSummary> {
+    let mut result: Vec<PointSummary> = graph
+        .edges
+        .iter()
+        .enumerate()
+        .map(|(node, edges)| {
+            let total: f64 = edges.iter().map(|(_, distance)| distance).sum();
+            PointSummary {
+                point_number: graph.numbers[node],
+                visible_count: edges.len(),
+                mean_distance: match edges.len() {
+                    0 => None,
+                    count => Some(total / count as f64),
+                },
+                reciprocal_count: edges
+                    .iter()
+                    .filter(|(other, _)| graph.sees(*other, node))
+                    .count(),
+            }
+        })
+        .collect();
+    result.sort_by_key(|PointSummary { point_number, .. }| *point_number);
+    result
+}
+
+/// Render `summaries` as CSV, with one
+/// `point_number,visible_count,mean_distance,reciprocal_count` row for each
+/// point, after a header row.  Points seeing nothing have an empty
+/// `mean_distance` cell.
+pub fn summary_csv(summaries: &[PointSummary]) -> String {
+    let mut result = String::from("point_number,visible_count,mean_distance,reciprocal_count\n");
+    for summary in summaries {
+        result.push_str(&format!(
+            "{},{},{},{}\n",
+            summary.point_number,
+            summary.visible_count,
+            summary
+                .mean_distance
+                .map_or_else(String::new, |value| value.to_string()),
+            summary.reciprocal_count
+        ));
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{summary_csv, visibility_summary, PointSummary};
+    use crate::domain::{visibility_graph, Direction, Point};
+
+    #[test]
+    fn visibility_summary_counts_reciprocal_pairs() {
+        // arrange
+        let neighbourhood = [
+            Point {
+                coordinates: (0, 4),
+                number: 3,
+                direction: Direction::South,
+            },
+            Point {
+                coordinates: (0, 0),
+                number: 1,
+                direction: Direction::North,
+            },
+            Point {
+                coordinates: (3, 0),
+                number: 2,
+                direction: Direction::East,
+            },
+        ];
+        let graph = visibility_graph(45, 10, &neighbourhood);
+
+        // act
+        let outcome = visibility_summary(&graph);
+
+        // assert
+        assert_eq!(
+            outcome,
+            vec![
+                PointSummary {
+                    point_number: 1,
+                    visible_count: 1,
+                    mean_distance: Some(4.0),
+                    reciprocal_count: 1
+                },
+                PointSummary {
+                    point_number: 2,
+                    visible_count: 0,
+                    mean_distance: None,
+                    reciprocal_count: 0
+                },
+                PointSummary {
+                    point_number: 3,
+                    visible_count: 2,
+                    mean_distance: Some(4.5),
+                    reciprocal_count: 1
+                },
+            ]
+        );
+        assert_eq!(
+            summary_csv(&outcome[..2]),
+            "point_number,visible_count,mean_distance,reciprocal_count\n1,1,4,1\n2,0,,0\n"
+        );
+        assert_eq!(
+            outcome[1].to_json_line(),
+            r#"{"point_number":2,"visible_count":0,"mean_distance":null,"reciprocal_count":0}"#
+        );
+    }
+}
@@ -0,0 +1,17 @@
+//! Fixtures shared by unit tests across the domain modules
+
+use crate::domain::point::{Direction, Point};
+
+/// Point numbered `number`, at `coordinates`, facing `direction`
+pub(crate) fn point(number: u32, coordinates: (i32, i32), direction: Direction) -> Point {
+    Point {
+        coordinates,
+        number,
+        direction,
+    }
+}
+
+/// Point numbered `number`, at `coordinates`, facing north
+pub(crate) fn north_point(number: u32, coordinates: (i32, i32)) -> Point {
+    point(number, coordinates, Direction::North)
+}
@@ -0,0 +1,109 @@
+use crate::{
+    domain::{
+        origin::Origin,
+        point::{euclidean_distance_f64, Point},
+        viewshed::Sightline,
+    },
+    utilities::Span,
+};
+use std::collections::HashMap;
+
+/// Angle, in degrees, of a point `rise` above an observer, and `distance` away
+/// across the plan, above the observer’s horizontal, or negative below it
+pub fn elevation_angle(rise: f64, distance: f64) -> f64 {
+    rise.atan2(distance).to_degrees()
+}
+
+/// Points of `visible` lying within `half_vertical_angle` degrees, above or
+/// below, of the horizontal through the eye of the observer at `viewpoint`,
+/// in the same order, for points on a 2D plan which carry heights, such as
+/// sensors across the floors of a building.  The observer stands
+/// `viewpoint_height` tall, and each point as tall as `heights` gives, or at
+/// ground level, both raised by the heights `sightline` gives.  Distances are
+/// measured across the plan, so this is much cheaper than full 3D.
+pub fn vertically_visible_points<'a>(
+    viewpoint: &Origin,
+    viewpoint_height: f64,
+    visible: Vec<&'a Point>,
+    heights: &HashMap<u32, f64>,
+    half_vertical_angle: f64,
+    sightline: &Sightline,
+) -> Vec<&'a Point> {
+    let _span = Span::enter("vertically_visible_points", || {
+        format!("{} points", visible.len())
+    });
+    let eye_height = viewpoint_height + sightline.observer_height;
+    visible
+        .into_iter()
+        .filter(|point| {
+            let height = heights.get(&point.number).copied().unwrap_or(0.0);
+            let distance = euclidean_distance_f64(
+                viewpoint.coordinates,
+                (
+                    f64::from(point.coordinates.0),
+                    f64::from(point.coordinates.1),
+                ),
+            );
+            let rise = height + sightline.target_height - eye_height;
+            elevation_angle(rise, distance).abs() <= half_vertical_angle
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{elevation_angle, vertically_visible_points};
+    use crate::domain::{Direction, Origin, Point, Sightline};
+    use std::collections::HashMap;
+
+    #[test]
+    fn vertically_visible_points_leaves_out_points_too_steep() {
+        // arrange
+        let points: Vec<Point> = (1..=4)
+            .map(|number| Point {
+                coordinates: (0, 10),
+                number,
+                direction: Direction::South,
+            })
+            .collect();
+        let heights: HashMap<u32, f64> = vec![(1, 0.0), (2, 5.0), (3, 15.0), (4, -9.0)]
+            .into_iter()
+            .collect();
+        let viewpoint = Origin {
+            coordinates: (0.0, 0.0),
+            heading: 0.0,
+        };
+
+        // act
+        let outcome = vertically_visible_points(
+            &viewpoint,
+            0.0,
+            points.iter().collect(),
+            &heights,
+            45.0,
+            &Sightline::default(),
+        );
+
+        // assert
+        let numbers: Vec<u32> = outcome.iter().map(|point| point.number).collect();
+        assert_eq!(numbers, vec![1, 2, 4]);
+        assert_eq!(elevation_angle(10.0, 10.0).round(), 45.0);
+        assert_eq!(elevation_angle(0.0, 0.0), 0.0);
+        let raised = Sightline {
+            observer_height: 8.0,
+            ..Sightline::default()
+        };
+        let from_above: Vec<u32> = vertically_visible_points(
+            &viewpoint,
+            0.0,
+            points.iter().collect(),
+            &heights,
+            45.0,
+            &raised,
+        )
+        .iter()
+        .map(|point| point.number)
+        .collect();
+        assert_eq!(from_above, vec![1, 2, 3]);
+    }
+}
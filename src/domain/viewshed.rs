@@ -0,0 +1,416 @@
+use crate::{
+    domain::{
+        origin::Origin,
+        point::{euclidean_distance_f64, Point},
+    },
+    utilities::{AppError, Span},
+};
+use std::path::Path;
+
+/// x,y coordinates of either end of a sightline
+type Position = (f64, f64);
+
+/// Mean radius of the Earth, in metres
+pub const EARTH_RADIUS: f64 = 6_371_000.0;
+
+/// Refraction coefficient of a standard atmosphere, as surveying and radio
+/// planning tools use by default
+pub const STANDARD_REFRACTION: f64 = 0.13;
+
+/// Speed of light, in metres per microsecond, so dividing by a frequency in
+/// MHz gives a wavelength in metres
+pub(crate) const SPEED_OF_LIGHT: f64 = 299.792_458;
+
+/// Number of steps along a sightline over a smooth Earth, at which the ground
+/// is sampled
+const SMOOTH_STEPS: usize = 256;
+
+/// How sightlines from a viewpoint are judged, for terrain aware and long
+/// range visibility
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Sightline {
+    /// Height of the observer’s eye above the ground, or above sea level away
+    /// from any elevation model, and above the top of the viewing point in
+    /// occlusion mode, such as `4` for a camera on a pole
+    pub observer_height: f64,
+
+    /// Height above the ground of the part of each target to be seen, such as
+    /// `1.7` for a person, added to the target’s own height in occlusion mode
+    pub target_height: f64,
+
+    /// Let the Earth’s surface fall away below the observer’s horizontal, so
+    /// distant points may lie below the horizon.  Coordinates and heights are
+    /// then in metres, as for GPX input, or projected coordinates.
+    pub earth_curvature: bool,
+
+    /// Refraction coefficient, the ratio of the Earth’s radius to that of
+    /// sightlines bending back towards the ground, such as
+    /// [`STANDARD_REFRACTION`], so sightlines see a little beyond the
+    /// geometric horizon.  Used with `earth_curvature`.
+    pub refraction: f64,
+
+    /// Radio frequency, in MHz, for judging clearance of the first Fresnel
+    /// zone, and not just the direct ray
+    pub frequency: Option<f64>,
+
+    /// Fraction of the first Fresnel zone radius, such as `0.6`, which must
+    /// stay clear of the ground all along a sightline.  Used with `frequency`.
+    pub fresnel_clearance: f64,
+}
+
+impl Default for Sightline {
+    fn default() -> Self {
+        Sightline {
+            observer_height: 0.0,
+            target_height: 0.0,
+            earth_curvature: false,
+            refraction: 0.0,
+            frequency: None,
+            fresnel_clearance: 0.0,
+        }
+    }
+}
+
+/// Ground beneath a sightline, from the observer to the target
+#[derive(Clone, Debug, PartialEq)]
+pub struct Profile {
+    /// Length of the sightline, along the ground
+    pub distance: f64,
+
+    /// Elevation of the ground beneath the observer
+    pub start: f64,
+
+    /// Elevation of the ground beneath the target
+    pub end: f64,
+
+    /// Distance from the observer and elevation of the ground, at points in
+    /// between
+    pub samples: Vec<(f64, f64)>,
+}
+
+impl Profile {
+    /// Ground of a smooth Earth, at sea level, beneath a sightline `distance`
+    /// long
+    pub fn smooth(distance: f64) -> Self {
+        let samples = if distance > 0.0 {
+            (1..SMOOTH_STEPS)
+                .map(|step| (distance * step as f64 / SMOOTH_STEPS as f64, 0.0))
+                .collect()
+        } else {
+            vec![]
+        };
+        Profile {
+            distance,
+            start: 0.0,
+            end: 0.0,
+            samples,
+        }
+    }
+}
+
+/// How far a sightline stays clear of the ground beneath it
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Clearance {
+    /// Least height of the sightline above the ground, negative where the
+    /// ground blocks it, or infinite with no ground in between
+    pub height: f64,
+
+    /// Least height of the sightline above the ground, as a fraction of the
+    /// first Fresnel zone radius there, when a frequency is given
+    pub fresnel: Option<f64>,
+}
+
+impl Sightline {
+    /// Radius of an Earth over which sightlines run straight, so refraction
+    /// may be allowed for as if it flattened the Earth
+    pub fn effective_radius(&self) -> f64 {
+        EARTH_RADIUS / (1.0 - self.refraction)
+    }
+
+    /// Depth of the Earth’s surface below the observer’s horizontal, at
+    /// `distance` along the ground, or `0` ignoring curvature
+    pub fn curvature_drop(&self, distance: f64) -> f64 {
+        if self.earth_curvature {
+            distance * distance / (2.0 * self.effective_radius())
+        } else {
+            0.0
+        }
+    }
+
+    /// Returns true unless curvature puts a target `target_height` above sea
+    /// level, `distance` away, below the horizon of an observer at
+    /// `observer_height` above sea level, over a smooth Earth
+    pub fn above_horizon(&self, distance: f64, target_height: f64) -> bool {
+        if !self.earth_curvature {
+            return true;
+        }
+        let radius = self.effective_radius();
+        let horizon = |height: f64| (2.0 * radius * height.max(0.0)).sqrt();
+        distance <= horizon(self.observer_height) + horizon(target_height)
+    }
+
+    /// Radius of the first Fresnel zone, `along` the ground from the observer,
+    /// on a sightline `distance` long, when a frequency is given
+    pub fn fresnel_radius(&self, along: f64, distance: f64) -> Option<f64> {
+        let wavelength = SPEED_OF_LIGHT / self.frequency?;
+        Some((wavelength * along * (distance - along) / distance).sqrt())
+    }
+
+    /// Clearance of the sightline from the observer’s eye, `observer_height`
+    /// above the start of `profile`, to the target, `target_height` above its
+    /// end.  With Earth curvature, the ground falls away below the observer’s
+    /// horizontal with distance.
+    pub fn clearance(&self, profile: &Profile) -> Clearance {
+        let eye = profile.start + self.observer_height;
+        let target = profile.end + self.target_height - self.curvature_drop(profile.distance);
+        let mut height = f64::INFINITY;
+        let mut fresnel = self.frequency.map(|_| f64::INFINITY);
+        for (along, ground) in &profile.samples {
+            let margin = eye + (target - eye) * along / profile.distance
+                - (ground - self.curvature_drop(*along));
+            height = height.min(margin);
+            if let (Some(least), Some(radius)) =
+                (&mut fresnel, self.fresnel_radius(*along, profile.distance))
+            {
+                if radius > 0.0 {
+                    *least = least.min(margin / radius);
+                }
+            }
+        }
+        Clearance { height, fresnel }
+    }
+
+    /// Returns true if `clearance` leaves the direct ray clear of the ground,
+    /// and, with a frequency, the required fraction of the first Fresnel zone
+    pub fn is_clear(&self, clearance: &Clearance) -> bool {
+        clearance.height >= 0.0
+            && clearance
+                .fresnel
+                .map_or(true, |fraction| fraction >= self.fresnel_clearance)
+    }
+}
+
+/// Profile of the ground beneath the sightline between two positions, from
+/// the GeoTIFF elevation model at `path`, or over a smooth Earth, off the model
+#[cfg(feature = "geotiff")]
+fn terrain_profile(path: &Path) -> Result<impl Fn(Position, Position) -> Profile, AppError> {
+    let dem = crate::domain::geotiff::parse_dem_file(path)?;
+    Ok(move |from, to| {
+        dem.profile(from, to)
+            .unwrap_or_else(|| Profile::smooth(euclidean_distance_f64(from, to)))
+    })
+}
+
+#[cfg(not(feature = "geotiff"))]
+fn terrain_profile(_path: &Path) -> Result<fn(Position, Position) -> Profile, AppError> {
+    Err(AppError::GeoTIFFError(String::from(
+        "terrain elevations need the `geotiff` feature",
+    )))
+}
+
+/// Points of `visible` whose sightlines from `viewpoint` are clear, in the
+/// same order, each with the clearance of its sightline.  With `dem`, a
+/// GeoTIFF elevation model, read when the `geotiff` feature is enabled,
+/// sightlines must clear the terrain, and otherwise a smooth Earth, at sea
+/// level.
+pub fn clear_sightlines<'a>(
+    viewpoint: &Origin,
+    visible: Vec<&'a Point>,
+    sightline: &Sightline,
+    dem: Option<&Path>,
+) -> Result<Vec<(&'a Point, Clearance)>, AppError> {
+    let _span = Span::enter("clear_sightlines", || format!("{} points", visible.len()));
+    let terrain = match dem {
+        Some(path) => Some(terrain_profile(path)?),
+        None => None,
+    };
+    Ok(visible
+        .into_iter()
+        .filter_map(|point| {
+            let coordinates = (
+                f64::from(point.coordinates.0),
+                f64::from(point.coordinates.1),
+            );
+            let profile = match &terrain {
+                Some(profile) => profile(viewpoint.coordinates, coordinates),
+                None => Profile::smooth(euclidean_distance_f64(viewpoint.coordinates, coordinates)),
+            };
+            let clearance = sightline.clearance(&profile);
+            if sightline.is_clear(&clearance) {
+                Some((point, clearance))
+            } else {
+                None
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        clear_sightlines, Clearance, Profile, Sightline, EARTH_RADIUS, STANDARD_REFRACTION,
+    };
+    use crate::domain::{Direction, Origin, Point};
+
+    fn numbers(sightlines: &[(&Point, Clearance)]) -> Vec<u32> {
+        sightlines.iter().map(|(point, _)| point.number).collect()
+    }
+
+    #[test]
+    fn clear_sightlines_leaves_out_points_below_horizon() {
+        // arrange
+        let points: Vec<Point> = [0, 11_000, 12_000]
+            .iter()
+            .enumerate()
+            .map(|(index, y)| Point {
+                coordinates: (0, *y),
+                number: index as u32 + 1,
+                direction: Direction::North,
+            })
+            .collect();
+        let origin = Origin {
+            coordinates: (0.0, 0.0),
+            heading: 0.0,
+        };
+        let sightline = Sightline {
+            observer_height: 10.0,
+            earth_curvature: true,
+            ..Sightline::default()
+        };
+
+        // act
+        let outcome = clear_sightlines(&origin, points.iter().collect(), &sightline, None).unwrap();
+
+        // assert
+        assert_eq!(numbers(&outcome), vec![1, 2]);
+        assert_eq!(outcome[0].1.height, f64::INFINITY);
+        let refracted = Sightline {
+            refraction: STANDARD_REFRACTION,
+            ..sightline
+        };
+        assert_eq!(
+            numbers(&clear_sightlines(&origin, points.iter().collect(), &refracted, None).unwrap()),
+            vec![1, 2, 3]
+        );
+        assert_eq!(refracted.curvature_drop(10_000.0).round(), 7.0);
+        assert_eq!(sightline.curvature_drop(10_000.0).round(), 8.0);
+        assert_eq!(Sightline::default().curvature_drop(10_000.0), 0.0);
+        assert!(Sightline::default().above_horizon(1e6, 0.0));
+    }
+
+    #[test]
+    fn effective_radius_allows_for_refraction() {
+        // arrange
+        let geometric = Sightline {
+            observer_height: 10.0,
+            earth_curvature: true,
+            ..Sightline::default()
+        };
+        let refracted = Sightline {
+            refraction: STANDARD_REFRACTION,
+            ..geometric
+        };
+
+        // act
+        let outcome = refracted.effective_radius();
+
+        // assert
+        assert!((outcome - EARTH_RADIUS / 0.87).abs() < 1e-6);
+        assert_eq!(geometric.effective_radius(), EARTH_RADIUS);
+        assert!(!geometric.above_horizon(11_700.0, 0.0));
+        assert!(refracted.above_horizon(11_700.0, 0.0));
+    }
+
+    #[test]
+    fn clearance_raises_observer_and_target() {
+        // arrange
+        let profile = Profile {
+            distance: 1000.0,
+            start: 0.0,
+            end: 0.0,
+            samples: vec![(500.0, 5.0)],
+        };
+        let raised = Sightline {
+            observer_height: 8.0,
+            target_height: 4.0,
+            ..Sightline::default()
+        };
+
+        // act
+        let outcome = raised.clearance(&profile);
+
+        // assert
+        assert_eq!(outcome.height, 1.0);
+        assert!(raised.is_clear(&outcome));
+        let ground = Sightline::default().clearance(&profile);
+        assert_eq!(ground.height, -5.0);
+        assert!(!Sightline::default().is_clear(&ground));
+    }
+
+    #[test]
+    fn clearance_allows_for_fresnel_zone() {
+        // arrange
+        let profile = Profile {
+            distance: 1000.0,
+            start: 0.0,
+            end: 20.0,
+            samples: vec![(250.0, 2.0), (500.0, 4.0), (750.0, 10.0)],
+        };
+        let sightline = Sightline {
+            observer_height: 20.0,
+            frequency: Some(2400.0),
+            fresnel_clearance: 0.6,
+            ..Sightline::default()
+        };
+
+        // act
+        let outcome = sightline.clearance(&profile);
+
+        // assert
+        let radius = sightline.fresnel_radius(750.0, 1000.0).unwrap();
+        assert!((radius - 4.84).abs() < 0.01);
+        assert_eq!(outcome.height, 10.0);
+        assert_eq!(outcome.fresnel, Some(10.0 / radius));
+        assert!(sightline.is_clear(&outcome));
+        let strict = Sightline {
+            fresnel_clearance: 2.5,
+            ..sightline
+        };
+        assert!(!strict.is_clear(&outcome));
+        let raised = Sightline {
+            target_height: 10.0,
+            ..sightline
+        };
+        assert_eq!(raised.clearance(&profile).height, 17.5);
+        assert_eq!(Sightline::default().clearance(&profile).fresnel, None);
+        assert_eq!(Sightline::default().fresnel_radius(500.0, 1000.0), None);
+    }
+
+    #[cfg(feature = "geotiff")]
+    #[test]
+    fn clear_sightlines_leaves_out_points_hidden_by_terrain() {
+        use crate::domain::{parse_points_file, visible_points_from_origin};
+        use std::path::Path;
+
+        // arrange
+        let points = parse_points_file("./fixtures/valid_points.json").unwrap();
+        let origin = Origin {
+            coordinates: (28.0, 42.0),
+            heading: 0.0,
+        };
+        let visible = visible_points_from_origin(&origin, 90, 50, &points);
+
+        // act
+        let outcome = clear_sightlines(
+            &origin,
+            visible.clone(),
+            &Sightline::default(),
+            Some(Path::new("./fixtures/valid_dem.tif")),
+        )
+        .unwrap();
+
+        // assert
+        assert_eq!(visible.len(), 6);
+        assert_eq!(numbers(&outcome), vec![1, 16]);
+    }
+}
@@ -0,0 +1,226 @@
+use crate::domain::{delaunay::delaunay_triangulation, geometry::BoundingBox, point::Point};
+
+/// Distances smaller than this are treated as zero when deciding which
+/// neighbours contribute an edge to a cell
+const EPSILON: f64 = 1e-9;
+
+/// Voronoi cell of a point: the region closer to it than to any other point
+#[derive(Clone, Debug, PartialEq)]
+pub struct VoronoiCell<'a> {
+    pub point: &'a Point,
+
+    /// x,y coordinates of the cell’s vertices in counter-clockwise order.
+    /// Empty if the cell lies entirely outside the clipping box.
+    pub vertices: Vec<(f64, f64)>,
+
+    pub area: f64,
+
+    /// Points whose cells share an edge with this cell, in neighbourhood order
+    pub neighbours: Vec<&'a Point>,
+}
+
+/// Area of the simple polygon with `vertices`, listed in order
+fn area(vertices: &[(f64, f64)]) -> f64 {
+    let twice_area: f64 = vertices
+        .iter()
+        .zip(vertices.iter().cycle().skip(1))
+        .map(|((x_1, y_1), (x_2, y_2))| x_1 * y_2 - x_2 * y_1)
+        .sum();
+    twice_area.abs() / 2.0
+}
+
+/// Signed distance (scaled) of `coordinates` from the perpendicular bisector of
+/// `site` and `other`, negative on the `site` side
+fn bisector_offset((x, y): (f64, f64), (x_s, y_s): (f64, f64), (x_o, y_o): (f64, f64)) -> f64 {
+    let (x_m, y_m) = ((x_s + x_o) / 2.0, (y_s + y_o) / 2.0);
+    (x - x_m) * (x_o - x_s) + (y - y_m) * (y_o - y_s)
+}
+
+/// Clip convex `polygon` to the half-plane closer to `site` than to `other`
+/// (Sutherland-Hodgman)
+fn clip(polygon: &[(f64, f64)], site: (f64, f64), other: (f64, f64)) -> Vec<(f64, f64)> {
+    let mut result = vec![];
+    for (index, current) in polygon.iter().enumerate() {
+        let next = polygon[(index + 1) % polygon.len()];
+        let current_offset = bisector_offset(*current, site, other);
+        let next_offset = bisector_offset(next, site, other);
+        if current_offset <= 0.0 {
+            result.push(*current);
+        }
+        if (current_offset < 0.0 && next_offset > 0.0)
+            || (current_offset > 0.0 && next_offset < 0.0)
+        {
+            let t = current_offset / (current_offset - next_offset);
+            result.push((
+                current.0 + t * (next.0 - current.0),
+                current.1 + t * (next.1 - current.1),
+            ));
+        }
+    }
+    result
+}
+
+/// Return the Voronoi cell of each `neighbourhood` point, clipped to
+/// `clip_box`.  Without a clipping box, cells are clipped to the
+/// neighbourhood’s bounding box, padded on each side by its larger dimension,
+/// so cells of points on the convex hull, which would otherwise be unbounded,
+/// have finite area.
+///
+/// Cells are returned in neighbourhood order.  Only the first of any points
+/// sharing coordinates gets a cell.
+pub fn voronoi_cells(
+    neighbourhood: &[Point],
+    clip_box: Option<BoundingBox>,
+) -> Vec<VoronoiCell<'_>> {
+    let (clip_box, padding) = match (clip_box, BoundingBox::of_points(neighbourhood)) {
+        (_, None) => return vec![],
+        (Some(value), Some(_)) => (value, 0),
+        (None, Some(value)) => {
+            let BoundingBox {
+                min: (min_x, min_y),
+                max: (max_x, max_y),
+            } = value;
+            let padding = (i64::from(max_x) - i64::from(min_x))
+                .max(i64::from(max_y) - i64::from(min_y))
+                .max(1);
+            (value, padding)
+        }
+    };
+    let BoundingBox {
+        min: (min_x, min_y),
+        max: (max_x, max_y),
+    } = clip_box;
+    let (min_x, min_y) = (
+        (i64::from(min_x) - padding) as f64,
+        (i64::from(min_y) - padding) as f64,
+    );
+    let (max_x, max_y) = (
+        (i64::from(max_x) + padding) as f64,
+        (i64::from(max_y) + padding) as f64,
+    );
+    let bounds = vec![
+        (min_x, min_y),
+        (max_x, min_y),
+        (max_x, max_y),
+        (min_x, max_y),
+    ];
+
+    let triangulation = delaunay_triangulation(neighbourhood);
+    let coordinates = |point: &Point| {
+        let (x, y) = point.coordinates;
+        (f64::from(x), f64::from(y))
+    };
+    (0..triangulation.vertices.len())
+        .map(|index| {
+            let point = triangulation.vertices[index];
+            let site = coordinates(point);
+
+            // Delaunay neighbours determine the cell, unless all points are
+            // collinear, and there are no triangles
+            let others: Vec<&Point> = match triangulation.triangles.len() {
+                0 => triangulation
+                    .vertices
+                    .iter()
+                    .filter(|val| val.coordinates != point.coordinates)
+                    .copied()
+                    .collect(),
+                _ => triangulation.neighbours(index),
+            };
+            let vertices = others.iter().fold(bounds.clone(), |acc, other| {
+                clip(&acc, site, coordinates(other))
+            });
+
+            // neighbours contribute an edge when two cell vertices lie on their
+            // bisector
+            let neighbours: Vec<&Point> = others
+                .into_iter()
+                .filter(|other| {
+                    let other_site = coordinates(other);
+                    let scale = (other_site.0 - site.0).hypot(other_site.1 - site.1);
+                    vertices
+                        .iter()
+                        .filter(|vertex| {
+                            (bisector_offset(**vertex, site, other_site) / scale).abs() < EPSILON
+                        })
+                        .count()
+                        >= 2
+                })
+                .collect();
+            VoronoiCell {
+                point,
+                area: area(&vertices),
+                vertices,
+                neighbours,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::voronoi_cells;
+    use crate::domain::{BoundingBox, Direction, Point};
+
+    fn points_at(coordinates: &[(i32, i32)]) -> Vec<Point> {
+        coordinates
+            .iter()
+            .enumerate()
+            .map(|(index, coordinates)| Point {
+                coordinates: *coordinates,
+                number: index as u32 + 1,
+                direction: Direction::North,
+            })
+            .collect()
+    }
+
+    fn neighbour_numbers(neighbours: &[&Point]) -> Vec<u32> {
+        neighbours
+            .iter()
+            .map(|Point { number, .. }| *number)
+            .collect()
+    }
+
+    #[test]
+    fn voronoi_cells_gives_expected_result() {
+        // arrange
+        let points = points_at(&[(0, 0), (10, 0), (10, 10), (0, 10), (5, 5)]);
+        let clip_box = BoundingBox {
+            min: (0, 0),
+            max: (10, 10),
+        };
+
+        // act
+        let outcome = voronoi_cells(&points, Some(clip_box));
+
+        // assert
+        assert_eq!(outcome.len(), 5);
+        assert!((outcome[4].area - 50.0).abs() < 1e-10);
+        assert!((outcome[0].area - 12.5).abs() < 1e-10);
+        let total_area: f64 = outcome.iter().map(|cell| cell.area).sum();
+        assert!((total_area - 100.0).abs() < 1e-10);
+        assert_eq!(neighbour_numbers(&outcome[4].neighbours), vec![1, 2, 3, 4]);
+        assert_eq!(neighbour_numbers(&outcome[0].neighbours), vec![5]);
+    }
+
+    #[test]
+    fn voronoi_cells_handles_unclipped_and_collinear_input() {
+        // arrange
+        let points = points_at(&[(0, 0), (2, 0), (4, 0)]);
+
+        // act
+        let outcome = voronoi_cells(&points, None);
+
+        // assert
+        // bounds are padded by 4 on every side, giving a 12 by 8 box
+        assert!((outcome[0].area - 5.0 * 8.0).abs() < 1e-10);
+        assert!((outcome[1].area - 2.0 * 8.0).abs() < 1e-10);
+        assert_eq!(neighbour_numbers(&outcome[1].neighbours), vec![1, 3]);
+        assert_eq!(neighbour_numbers(&outcome[0].neighbours), vec![2]);
+
+        // act
+        let outcome = voronoi_cells(&[], None);
+
+        // assert
+        assert_eq!(outcome.len(), 0);
+    }
+}
@@ -1,23 +1,1216 @@
-mod domain;
-mod utilities;
+mod cli;
+mod server;
 
-use crate::domain::visible_points;
+use crate::cli::{
+    parse_arguments, Arguments, Command, HeatmapFormat, MatrixFormat, ProgressBar, QueryOptions,
+    SceneFormat, SummaryFormat,
+};
+use crate::server::{serve, Limits, RedisStore, Server};
+use neighbours::{
+    domain::{
+        angular_gaps, answer_query_line, benchmark_indexes, blind_points, cached_points_file,
+        cached_points_visibility_graph, clear_sightlines, combine_visible_sets, compare_visibility,
+        contacts, convex_hull, deduplicate_points, diff_neighbourhoods, distance_histogram,
+        downsample_points, face_points, geojson_layers, hidden_points, kml_document,
+        load_visibility_graph, minimum_radius_for_neighbours, nearest_in_buckets, observed_cutoff,
+        optimal_heading, optimal_heading_by_weight, parse_points_file, parse_points_file_parallel,
+        parse_points_with_fields, parse_points_with_query, parse_queries_file, parse_records_file,
+        parse_records_file_with_progress, parse_records_in, parse_visible_sets_file, point_weights,
+        points_in_bearing_band, reachable_points, run_queries, scene_glb, scene_obj,
+        sector_coverage, sentinel_points, simulation_frame, sort_by_sweep, summary_csv,
+        unoccluded_points, vertically_visible_points, visibility_graph_with_progress,
+        visibility_heatmap_with_progress, visibility_summary, visible_from_points,
+        visible_points_from_neighbours, visible_points_from_origin, visible_points_from_sensor,
+        visible_space_points, IdentifiedNeighbourhood, InputPoint, MovingPoint, OptimalHeading,
+        Origin, OriginCombination, Partition, Point, Query, QueryResult, SensorPoint, Sighting,
+        SpacePoint, ViewCone,
+    },
+    utilities::{
+        format_profile, set_log_level, start_profiling, take_profile, AppError, LogLevel, Span,
+    },
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    io::{self, BufRead, Write},
+    net::TcpListener,
+    path::PathBuf,
+    time::Duration,
+};
 
-/// Prints visible points taking point neighbourhood from `./points.json` input
-/// file, which must exist.
+/// Read the point records named in `query`, from the points file, with a
+/// progress bar if requested, or from the SQL query given, against a SQLite
+/// or PostgreSQL database.  With `--dbf-fields`, numbers and directions come
+/// from the fields named, and with `--cache-points`, a binary copy of the
+/// points saved by an earlier run is reused while the file is unchanged.
+/// These sources are exclusive, as checked when parsing arguments.  Points
+/// observed longer ago than `--max-age`, then points outside `--bbox`, are
+/// then left out.
+fn parse_query_records(query: &QueryOptions) -> Result<Vec<InputPoint>, AppError> {
+    let from_points = |points: Vec<Point>| points.into_iter().map(InputPoint::from).collect();
+    let mut records: Vec<InputPoint> = if let Some(sql) = &query.query {
+        from_points(parse_points_with_query(&query.file, sql)?)
+    } else if let Some(fields) = &query.dbf_fields {
+        from_points(parse_points_with_fields(&query.file, fields)?)
+    } else if query.cache_points {
+        from_points(cached_points_file(&query.file)?)
+    } else if let Some(bounds) = &query.bbox {
+        parse_records_in(&query.file, bounds)?
+    } else {
+        let mut progress_bar = ProgressBar::new("Reading points", query.progress);
+        parse_records_file_with_progress(&query.file, |done, total| {
+            progress_bar.update(done, total)
+        })?
+    };
+    if let Some(max_age) = query.max_age {
+        let cutoff = observed_cutoff(max_age);
+        records.retain(|InputPoint { observed_at, .. }| {
+            observed_at.map_or(true, |value| value >= cutoff)
+        });
+    }
+    if let Some(bounds) = &query.bbox {
+        records.retain(|InputPoint { x, y, .. }| bounds.contains((*x, *y)));
+    }
+    Ok(records)
+}
+
+/// Refine `points` read for `query`.  With `--dedup`, repeated records are
+/// removed, reporting how many on standard error.  With `--downsample`, dense
+/// points are then thinned to one per cell, optionally saving which points
+/// each kept point stands for.  With `--face`, each point's direction is then
+/// derived from where the others lie.
+fn refine_points(query: &QueryOptions, mut points: Vec<Point>) -> Result<Vec<Point>, AppError> {
+    if let Some(strategy) = query.dedup {
+        match deduplicate_points(&mut points, strategy) {
+            1 => eprintln!("Removed 1 duplicate point."),
+            removed => eprintln!("Removed {removed} duplicate points."),
+        }
+    }
+    if let Some(cell_size) = query.downsample {
+        let count = points.len();
+        let downsampled = downsample_points(&points, cell_size);
+        eprintln!(
+            "Downsampled {count} points to {}.",
+            downsampled.points.len()
+        );
+        if let Some(path) = &query.downsample_map {
+            let members: BTreeMap<u32, Vec<u32>> = downsampled.members.into_iter().collect();
+            let json = serde_json::to_string(&members).map_err(AppError::JSONParseError)?;
+            fs::write(path, json).map_err(|error| AppError::OutputError {
+                path: path.display().to_string(),
+                source: error,
+            })?;
+        }
+        points = downsampled.points;
+    }
+    if let Some(facing) = query.face {
+        face_points(&mut points, facing);
+    }
+    Ok(points)
+}
+
+/// Read the points named in `query`, as for [`parse_query_records`], then
+/// refine them, as for [`refine_points`]
+fn parse_query_points(query: &QueryOptions) -> Result<Vec<Point>, AppError> {
+    let records = parse_query_records(query)?;
+    refine_points(query, records.into_iter().map(Point::from).collect())
+}
+
+/// Write `bytes` to the `output` file, or to standard output when no file is
+/// given.
+fn write_output(output: &Option<PathBuf>, bytes: &[u8]) -> Result<(), AppError> {
+    let _span = Span::enter("write_output", || format!("{} bytes", bytes.len()));
+    match output {
+        Some(path) => fs::write(path, bytes).map_err(|error| AppError::OutputError {
+            path: path.display().to_string(),
+            source: error,
+        }),
+        None => io::stdout()
+            .write_all(bytes)
+            .map_err(|error| AppError::OutputError {
+                path: String::from("stdout"),
+                source: error,
+            }),
+    }
+}
+
+/// Print points reached through chains of visibility edges, with the hops
+/// needed to reach each
+fn print_reachable(reachable: &[(u32, usize)]) {
+    match reachable.len() {
+        1 => println!("There is 1 reachable point."),
+        _ => println!("There are {} reachable points.", reachable.len()),
+    }
+    for (number, hops) in reachable {
+        match hops {
+            1 => println!("{number}: 1 hop"),
+            _ => println!("{number}: {hops} hops"),
+        }
+    }
+}
+
+/// Points visible from the point numbered `point`, or from `origin` if given,
+/// with where each lies from the viewpoint
+fn viewpoint_sightings<'a>(
+    points: &'a [Point],
+    point: u32,
+    origin: Option<Origin>,
+    angle: u32,
+    radius: u32,
+) -> Result<(Origin, Vec<Sighting<'a>>), AppError> {
+    let (viewpoint, visible_points) = match origin {
+        Some(value) => (
+            value,
+            visible_points_from_origin(&value, angle, radius, points),
+        ),
+        None => {
+            let viewpoint = points
+                .iter()
+                .find(|Point { number, .. }| *number == point)
+                .map(Origin::from)
+                .ok_or(AppError::PointNotFoundError(point))?;
+            (
+                viewpoint,
+                visible_points_from_neighbours(point, angle, radius, points),
+            )
+        }
+    };
+    let sightings = visible_points
+        .into_iter()
+        .map(|value| Sighting::new(&viewpoint, value))
+        .collect();
+    Ok((viewpoint, sightings))
+}
+
+fn run(command: Command) -> Result<(), AppError> {
+    match command {
+        Command::Visible {
+            query,
+            point,
+            id,
+            bearings,
+            origin,
+            dem,
+            path_loss,
+            sightline,
+            occlusion,
+            vertical_angle,
+            three_d,
+            hull,
+            relative,
+            sweep,
+        } => {
+            if three_d {
+                let points: Vec<SpacePoint> = parse_query_records(&query)?
+                    .into_iter()
+                    .map(SpacePoint::from)
+                    .collect();
+                let cone = ViewCone {
+                    horizontal: f64::from(query.angle),
+                    vertical: vertical_angle.unwrap_or(90.0),
+                    radius: f64::from(query.radius),
+                };
+                let visible = visible_space_points(point, &cone, &points);
+                match visible.len() {
+                    1 => println!("There is 1 visible point."),
+                    _ => println!("There are {} visible points.", visible.len()),
+                }
+                println!("{visible:?}");
+                return Ok(());
+            }
+            let identified = match &id {
+                Some(value) => {
+                    let neighbourhood = IdentifiedNeighbourhood::from_file(&query.file)?;
+                    let number = neighbourhood.number(value).ok_or_else(|| {
+                        AppError::ArgumentError(format!("no point has id `{value}`"))
+                    })?;
+                    Some((neighbourhood, number))
+                }
+                None => None,
+            };
+            // heights and the sensor's view come from the same records as the
+            // points, so the file is read once
+            let (points, heights, view) = match &identified {
+                Some((neighbourhood, _)) => {
+                    let mut points = neighbourhood.points().to_vec();
+                    if let Some(bounds) = &query.bbox {
+                        points.retain(|value| bounds.contains(value.coordinates));
+                    }
+                    (refine_points(&query, points)?, HashMap::new(), None)
+                }
+                None => {
+                    let records = parse_query_records(&query)?;
+                    let heights: HashMap<u32, f64> = records
+                        .iter()
+                        .filter_map(|InputPoint { number, height, .. }| Some((*number, (*height)?)))
+                        .collect();
+                    let view = records
+                        .iter()
+                        .find(|InputPoint { number, .. }| *number == point)
+                        .map(|value| (value.fov_degrees, value.range));
+                    let points =
+                        refine_points(&query, records.into_iter().map(Point::from).collect())?;
+                    (points, heights, view)
+                }
+            };
+            let point = identified.as_ref().map_or(point, |(_, number)| *number);
+            let sensor = match (&origin, &bearings, view) {
+                (None, None, Some((fov_degrees, range))) => points
+                    .iter()
+                    .find(|Point { number, .. }| *number == point)
+                    .map(|value| SensorPoint {
+                        point: *value,
+                        fov_degrees,
+                        range,
+                    }),
+                _ => None,
+            };
+            let mut visible_points = match (&origin, &bearings) {
+                (Some(_), Some(_)) => {
+                    return Err(AppError::ArgumentError(String::from(
+                        "`--origin` and `--bearings` cannot be used together",
+                    )))
+                }
+                (Some(origin), None) => {
+                    visible_points_from_origin(origin, query.angle, query.radius, &points)
+                }
+                (None, Some(band)) => points_in_bearing_band(point, band, query.radius, &points),
+                (None, None) => match sensor {
+                    Some(value) => {
+                        visible_points_from_sensor(&value, query.angle, query.radius, &points)
+                    }
+                    None => {
+                        visible_points_from_neighbours(point, query.angle, query.radius, &points)
+                    }
+                },
+            };
+
+            let viewpoint = origin.or_else(|| {
+                points
+                    .iter()
+                    .find(|Point { number, .. }| *number == point)
+                    .map(Origin::from)
+            });
+            let viewpoint_height = match origin {
+                Some(_) => 0.0,
+                None => heights.get(&point).copied().unwrap_or(0.0),
+            };
+            if let (Some(angle), Some(value)) = (vertical_angle, &viewpoint) {
+                visible_points = vertically_visible_points(
+                    value,
+                    viewpoint_height,
+                    visible_points,
+                    &heights,
+                    angle,
+                    &sightline,
+                );
+            }
+            if let (Some(radius), Some(value)) = (occlusion, &viewpoint) {
+                visible_points = unoccluded_points(
+                    value,
+                    viewpoint_height,
+                    visible_points,
+                    &points,
+                    &heights,
+                    radius,
+                    &sightline,
+                );
+            }
+            let mut clearances = vec![];
+            if let Some(value) = &viewpoint {
+                if dem.is_some() || sightline.earth_curvature || sightline.frequency.is_some() {
+                    clearances =
+                        clear_sightlines(value, visible_points, &sightline, dem.as_deref())?;
+                    visible_points = clearances.iter().map(|(point, _)| *point).collect();
+                }
+            }
+            match visible_points.len() {
+                1 => println!("There is 1 visible point."),
+                _ => println!("There are {} visible points.", visible_points.len()),
+            }
+            let mut sightings: Vec<Sighting> = match viewpoint {
+                Some(value) => visible_points
+                    .iter()
+                    .map(|point| Sighting::new(&value, point))
+                    .collect(),
+                None => vec![],
+            };
+            if sweep {
+                sort_by_sweep(&mut sightings);
+            }
+            match &identified {
+                _ if relative => {
+                    for sighting in &sightings {
+                        let number = sighting.point.number;
+                        let name = match &identified {
+                            Some((neighbourhood, _)) => {
+                                neighbourhood.id(number).unwrap_or_default().to_string()
+                            }
+                            None => number.to_string(),
+                        };
+                        println!(
+                            "{name}: bearing {:.1}, relative {:+.1}, distance {:.2}",
+                            sighting.bearing, sighting.relative_bearing, sighting.distance
+                        );
+                    }
+                }
+                Some((neighbourhood, _)) => {
+                    let ids: Vec<&str> = sightings
+                        .iter()
+                        .filter_map(|Sighting { point, .. }| neighbourhood.id(point.number))
+                        .collect();
+                    println!("{:?}", ids);
+                }
+                None => {
+                    let ordered: Vec<&Point> = sightings
+                        .iter()
+                        .map(|Sighting { point, .. }| *point)
+                        .collect();
+                    println!("{:?}", ordered);
+                }
+            }
+            if let (Some(frequency), Some(value)) = (sightline.frequency, &viewpoint) {
+                for (point, clearance) in &clearances {
+                    let distance = Sighting::new(value, point).distance;
+                    let loss = match path_loss.loss(distance, frequency) {
+                        Some(loss) => format!("path loss {loss:.1} dB"),
+                        None => String::from("no path loss"),
+                    };
+                    match clearance.fresnel {
+                        Some(fraction) if fraction.is_finite() => println!(
+                            "{}: {loss}, clearance {:.0}% of the first Fresnel zone",
+                            point.number,
+                            100.0 * fraction
+                        ),
+                        _ => println!("{}: {loss}, no ground beneath the sightline", point.number),
+                    }
+                }
+            }
+            if hull {
+                let convex_hull = convex_hull(visible_points);
+                println!(
+                    "Convex hull: {:?}, with area {}.",
+                    convex_hull.vertices, convex_hull.area
+                );
+            }
+        }
+        Command::Cover {
+            query,
+            points: origins,
+            combination,
+        } => {
+            let points = parse_query_points(&query)?;
+            let visibility = visible_from_points(&origins, query.angle, query.radius, &points);
+            let combined = match combination {
+                OriginCombination::Union => visibility.union(),
+                OriginCombination::Intersection => visibility.intersection(),
+                OriginCombination::Each => {
+                    for (origin, visible) in visibility.origins.iter().zip(&visibility.visible) {
+                        println!("Point {origin}: {visible:?}");
+                    }
+                    return Ok(());
+                }
+            };
+            match combined.len() {
+                1 => println!("There is 1 visible point."),
+                _ => println!("There are {} visible points.", combined.len()),
+            }
+            println!("{combined:?}");
+        }
+        Command::Gaps {
+            query,
+            point,
+            origin,
+        } => {
+            let points = parse_query_points(&query)?;
+            let (viewpoint, sightings) =
+                viewpoint_sightings(&points, point, origin, query.angle, query.radius)?;
+            for gap in angular_gaps(&viewpoint, query.angle, &sightings) {
+                println!(
+                    "Gap of {:.1}° from {:.1}° to {:.1}°",
+                    gap.width(),
+                    gap.from,
+                    gap.to
+                );
+            }
+        }
+        Command::Coverage {
+            query,
+            point,
+            origin,
+            buckets,
+            min_distance,
+            max_distance,
+        } => {
+            let points = parse_query_points(&query)?;
+            let (_, sightings) =
+                viewpoint_sightings(&points, point, origin, query.angle, query.radius)?;
+            let buckets = buckets.unwrap_or(2 * query.angle.clamp(1, 180) as usize);
+            let distances = min_distance..max_distance.unwrap_or_else(|| f64::from(query.radius));
+            let coverage = sector_coverage(query.angle, buckets, distances, &sightings);
+            println!("Coverage: {:.1}% of {buckets} buckets.", 100.0 * coverage);
+        }
+        Command::Scan {
+            query,
+            point,
+            origin,
+            buckets,
+        } => {
+            let points = parse_query_points(&query)?;
+            let (_, sightings) =
+                viewpoint_sightings(&points, point, origin, query.angle, query.radius)?;
+            let width = 2.0 * f64::from(query.angle.min(180)) / buckets as f64;
+            for (index, nearest) in nearest_in_buckets(query.angle, buckets, &sightings)
+                .iter()
+                .enumerate()
+            {
+                let left = index as f64 * width - f64::from(query.angle.min(180));
+                let bucket = format!("{left:+.1}° to {:+.1}°", left + width);
+                match nearest {
+                    Some(sighting) => println!(
+                        "{bucket}: point {} at {:.2}",
+                        sighting.point.number, sighting.distance
+                    ),
+                    None => println!("{bucket}: nothing visible"),
+                }
+            }
+        }
+        Command::Histogram {
+            query,
+            point,
+            origin,
+            bin_width,
+            output,
+        } => {
+            let points = parse_query_points(&query)?;
+            let (_, sightings) =
+                viewpoint_sightings(&points, point, origin, query.angle, query.radius)?;
+            let json = distance_histogram(bin_width, &sightings).to_json();
+            write_output(&output, format!("{json}\n").as_bytes())?;
+        }
+        Command::GeoJson {
+            query,
+            point,
+            origin,
+            output_directory,
+        } => {
+            let points = parse_query_points(&query)?;
+            let origin_point = if origin.is_none() { Some(point) } else { None };
+            let (viewpoint, sightings) =
+                viewpoint_sightings(&points, point, origin, query.angle, query.radius)?;
+            let layers = geojson_layers(
+                &viewpoint,
+                origin_point,
+                query.angle,
+                query.radius,
+                &points,
+                &sightings,
+            );
+            fs::create_dir_all(&output_directory).map_err(|error| AppError::OutputError {
+                path: output_directory.display().to_string(),
+                source: error,
+            })?;
+            for (name, layer) in layers.layers() {
+                let path = output_directory.join(format!("{name}.geojson"));
+                fs::write(&path, format!("{layer}\n")).map_err(|error| AppError::OutputError {
+                    path: path.display().to_string(),
+                    source: error,
+                })?;
+            }
+        }
+        Command::Kml {
+            query,
+            point,
+            origin,
+            output,
+        } => {
+            let points = parse_query_points(&query)?;
+            let origin_point = if origin.is_none() { Some(point) } else { None };
+            let (viewpoint, sightings) =
+                viewpoint_sightings(&points, point, origin, query.angle, query.radius)?;
+            let document = kml_document(
+                &viewpoint,
+                origin_point,
+                query.angle,
+                query.radius,
+                &sightings,
+            );
+            write_output(&output, document.as_bytes())?;
+        }
+        Command::Scene {
+            query,
+            vertical_angle,
+            format,
+            output,
+        } => {
+            let points: Vec<SpacePoint> = parse_query_records(&query)?
+                .into_iter()
+                .map(SpacePoint::from)
+                .collect();
+            let cone = ViewCone {
+                horizontal: f64::from(query.angle),
+                vertical: vertical_angle.unwrap_or(90.0),
+                radius: f64::from(query.radius),
+            };
+            let scene = match format {
+                SceneFormat::Glb => scene_glb(&points, &cone),
+                SceneFormat::Obj => scene_obj(&points, &cone).into_bytes(),
+            };
+            write_output(&output, &scene)?;
+        }
+        Command::BenchIndex {
+            query,
+            queries,
+            min_recall,
+            output,
+        } => {
+            let points = parse_query_points(&query)?;
+            let report = benchmark_indexes(&points, query.radius, queries, min_recall);
+            for timing in &report.backends {
+                eprintln!(
+                    "{}: built in {:.3} s, queries took {:.3} s, recall {:.3}",
+                    timing.backend, timing.build_seconds, timing.query_seconds, timing.recall
+                );
+            }
+            eprintln!("Recommended index: {}", report.recommended);
+            eprintln!("Automatic choice: {}", report.automatic);
+            write_output(&output, format!("{}\n", report.to_json()).as_bytes())?;
+        }
+        Command::Heatmap {
+            query,
+            cell_size,
+            format,
+            output,
+        } => {
+            let points = parse_query_points(&query)?;
+            let mut progress_bar = ProgressBar::new("Sampling heatmap", query.progress);
+            let heatmap = visibility_heatmap_with_progress(
+                query.angle,
+                query.radius,
+                cell_size,
+                &points,
+                |done, total| progress_bar.update(done, total),
+            )?;
+            let bytes = match format {
+                HeatmapFormat::Csv => heatmap.to_csv().into_bytes(),
+                HeatmapFormat::Pgm => heatmap.to_pgm(),
+            };
+            write_output(&output, &bytes)?;
+        }
+        Command::Matrix {
+            query,
+            format,
+            output,
+            cache_directory,
+        } => {
+            let graph = match cache_directory {
+                Some(value) => {
+                    let points = parse_query_points(&query)?;
+                    cached_points_visibility_graph(
+                        &query.file,
+                        &points,
+                        query.angle,
+                        query.radius,
+                        value,
+                    )?
+                }
+                None => {
+                    let points = parse_query_points(&query)?;
+                    let mut progress_bar = ProgressBar::new("Building graph", query.progress);
+                    visibility_graph_with_progress(
+                        query.angle,
+                        query.radius,
+                        &points,
+                        |done, total| progress_bar.update(done, total),
+                    )
+                }
+            };
+            let bytes = match format {
+                MatrixFormat::Bitset => graph.to_bitset(),
+                MatrixFormat::Csv => graph.to_csv().into_bytes(),
+                MatrixFormat::DistanceCsv => graph.to_distance_csv().into_bytes(),
+            };
+            write_output(&output, &bytes)?;
+        }
+        Command::Summary {
+            query,
+            format,
+            output,
+        } => {
+            let points = parse_query_points(&query)?;
+            let mut progress_bar = ProgressBar::new("Building graph", query.progress);
+            let graph = visibility_graph_with_progress(
+                query.angle,
+                query.radius,
+                &points,
+                |done, total| progress_bar.update(done, total),
+            );
+            let summaries = visibility_summary(&graph);
+            let text = match format {
+                SummaryFormat::Csv => summary_csv(&summaries),
+                SummaryFormat::Json => summaries
+                    .iter()
+                    .map(|summary| format!("{}\n", summary.to_json_line()))
+                    .collect(),
+            };
+            write_output(&output, text.as_bytes())?;
+        }
+        Command::AllPairs {
+            file,
+            angle,
+            radius,
+            tile_size,
+            tile_directory,
+            output,
+        } => {
+            let directory = tile_directory.clone().unwrap_or_else(|| {
+                std::env::temp_dir().join(format!("neighbours_tiles_{}", std::process::id()))
+            });
+            let partition = Partition::create(&file, tile_size.unwrap_or(radius), &directory)?;
+            let (path, writer): (String, Box<dyn Write>) = match &output {
+                Some(value) => (
+                    value.display().to_string(),
+                    Box::new(
+                        fs::File::create(value).map_err(|error| AppError::OutputError {
+                            path: value.display().to_string(),
+                            source: error,
+                        })?,
+                    ),
+                ),
+                None => (String::from("stdout"), Box::new(io::stdout())),
+            };
+            let mut writer = io::BufWriter::new(writer);
+            let output_error = |error| AppError::OutputError {
+                path: path.clone(),
+                source: error,
+            };
+            let result = partition
+                .for_each_visible(angle, radius, |point, visible| {
+                    let line = QueryResult {
+                        query: Query {
+                            point_number: point.number,
+                            angle,
+                            radius,
+                        },
+                        visible: visible.iter().map(|value| value.number).collect(),
+                    }
+                    .to_json_line();
+                    writeln!(writer, "{line}").map_err(output_error)
+                })
+                .and_then(|()| writer.flush().map_err(output_error));
+            partition.remove_files()?;
+            if tile_directory.is_none() {
+                fs::remove_dir(&directory).ok();
+            }
+            result?;
+        }
+        Command::Graph {
+            graph,
+            point,
+            to,
+            hops,
+        } => {
+            let graph = load_visibility_graph(graph)?;
+            match (to, hops) {
+                (Some(to), _) => match graph.shortest_path(point, to) {
+                    Some(path) => println!("Path from {point} to {to}: {path:?}"),
+                    None => println!("There is no path from {point} to {to}."),
+                },
+                (None, Some(hops)) => print_reachable(&graph.reachable_within(point, hops)),
+                (None, None) => {
+                    let visible_numbers = graph.visible_numbers(point);
+                    match visible_numbers.len() {
+                        1 => println!("There is 1 visible point."),
+                        _ => println!("There are {} visible points.", visible_numbers.len()),
+                    }
+                    println!("{visible_numbers:?}");
+                }
+            }
+        }
+        Command::Reach { query, point, hops } => {
+            let points = parse_query_points(&query)?;
+            print_reachable(&reachable_points(
+                point,
+                query.angle,
+                query.radius,
+                hops,
+                &points,
+            ));
+        }
+        Command::Audit { query } => {
+            let points = parse_query_points(&query)?;
+            let numbers = |points: Vec<&Point>| -> Vec<u32> {
+                points.iter().map(|value| value.number).collect()
+            };
+            let hidden = numbers(hidden_points(query.angle, query.radius, &points));
+            match hidden.len() {
+                1 => println!("There is 1 hidden point, seen by no other point."),
+                count => println!("There are {count} hidden points, seen by no other point."),
+            }
+            println!("{hidden:?}");
+            let blind = numbers(blind_points(query.angle, query.radius, &points));
+            match blind.len() {
+                1 => println!("There is 1 blind point, seeing no other point."),
+                count => println!("There are {count} blind points, seeing no other point."),
+            }
+            println!("{blind:?}");
+            let sentinels = numbers(sentinel_points(query.angle, query.radius, &points));
+            match sentinels.len() {
+                1 => println!("1 sentinel point sees every other point."),
+                count => println!("{count} sentinel points between them see every other point."),
+            }
+            println!("{sentinels:?}");
+        }
+        Command::Optimise {
+            query,
+            point,
+            k,
+            weight_field,
+        } => {
+            let points = parse_query_points(&query)?;
+            let count = |value: usize| match value {
+                1 => String::from("1 point"),
+                _ => format!("{value} points"),
+            };
+            match k {
+                Some(k) => match minimum_radius_for_neighbours(point, query.angle, k, &points) {
+                    Some(radius) => println!(
+                        "Point {point} sees {} within a radius of {radius}.",
+                        count(k)
+                    ),
+                    None if points.iter().any(|value| value.number == point) => {
+                        println!("Point {point} never sees {}, at any radius.", count(k))
+                    }
+                    None => return Err(AppError::PointNotFoundError(point)),
+                },
+                None => match weight_field {
+                    Some(field) => {
+                        let weights = point_weights(&query.file, &field)?;
+                        let OptimalHeading { bearing, score } = optimal_heading_by_weight(
+                            point,
+                            query.angle,
+                            query.radius,
+                            &points,
+                            |value| weights.get(&value.number).copied().unwrap_or(0.0),
+                        )
+                        .ok_or(AppError::PointNotFoundError(point))?;
+                        println!("Facing {bearing:.1}°, point {point} sees a total `{field}` of {score}.");
+                    }
+                    None => {
+                        let OptimalHeading { bearing, score } =
+                            optimal_heading(point, query.angle, query.radius, &points)
+                                .ok_or(AppError::PointNotFoundError(point))?;
+                        println!(
+                            "Facing {bearing:.1}°, point {point} sees {}.",
+                            count(score as usize)
+                        );
+                    }
+                },
+            }
+        }
+        Command::Contacts {
+            file,
+            point,
+            angle,
+            radius,
+        } => {
+            let contacts = contacts(
+                point,
+                angle,
+                radius,
+                &parse_records_file::<MovingPoint>(&file)?,
+            );
+            match contacts.len() {
+                1 => println!("There is 1 visible point."),
+                _ => println!("There are {} visible points.", contacts.len()),
+            }
+            for contact in contacts {
+                let approach = match contact.time_to_closest {
+                    Some(time) => format!("closest {:.2} in {time:.2} s", contact.closest_distance),
+                    None => String::from("not closing"),
+                };
+                println!(
+                    "{}: distance {:.2}, closing speed {:.2}, {approach}",
+                    contact.number, contact.distance, contact.closing_speed
+                );
+            }
+        }
+        Command::Simulate {
+            file,
+            angle,
+            radius,
+            steps,
+            step_seconds,
+            frames_directory,
+        } => {
+            let points = parse_records_file::<MovingPoint>(&file)?;
+            if let Some(directory) = &frames_directory {
+                fs::create_dir_all(directory).map_err(|error| AppError::OutputError {
+                    path: directory.display().to_string(),
+                    source: error,
+                })?;
+            }
+            let mut lines = String::new();
+            for step in 0..steps {
+                let line =
+                    simulation_frame(step, step_seconds, angle, radius, &points).to_json_line();
+                match &frames_directory {
+                    Some(directory) => write_output(
+                        &Some(directory.join(format!("frame-{step:05}.json"))),
+                        format!("{line}\n").as_bytes(),
+                    )?,
+                    None => {
+                        lines.push_str(&line);
+                        lines.push('\n');
+                    }
+                }
+            }
+            if frames_directory.is_none() {
+                write_output(&None, lines.as_bytes())?;
+            }
+        }
+        #[cfg(feature = "animation")]
+        Command::Animate {
+            file,
+            angle,
+            radius,
+            steps,
+            step_seconds,
+            output,
+            size,
+            frames_per_second,
+        } => {
+            use neighbours::domain::{encode_gif, encode_mp4, render_frames};
+
+            let points = parse_records_file::<MovingPoint>(&file)?;
+            let frames: Vec<_> = (0..steps)
+                .map(|step| simulation_frame(step, step_seconds, angle, radius, &points))
+                .collect();
+            let rasters = render_frames(&frames, size);
+            if output.extension().and_then(|value| value.to_str()) == Some("mp4") {
+                encode_mp4(&rasters, frames_per_second, &output)?;
+            } else {
+                let delay = (100 / frames_per_second.max(1)) as u16;
+                write_output(&Some(output), &encode_gif(&rasters, delay))?;
+            }
+        }
+        Command::Diff { old, new } => {
+            let old_points = parse_points_file(&old)?;
+            let new_points = parse_points_file(&new)?;
+            let diff = diff_neighbourhoods(&old_points, &new_points);
+            if diff.is_empty() {
+                println!("No points were added, removed or moved.");
+            }
+            for point in diff.added {
+                println!("Added: {point:?}");
+            }
+            for point in diff.removed {
+                println!("Removed: {point:?}");
+            }
+            for moved in diff.moved {
+                println!(
+                    "Moved: point {} from {:?} to {:?}, distance {:.2}",
+                    moved.new.number, moved.old.coordinates, moved.new.coordinates, moved.distance
+                );
+            }
+        }
+        Command::Combine {
+            left,
+            right,
+            operation,
+            output,
+        } => {
+            let sets = combine_visible_sets(
+                &parse_visible_sets_file(&left)?,
+                &parse_visible_sets_file(&right)?,
+                operation,
+            );
+            let lines: String = sets.iter().map(|set| set.to_json_line() + "\n").collect();
+            write_output(&output, lines.as_bytes())?;
+        }
+        Command::Compare {
+            old,
+            new,
+            angle,
+            radius,
+        } => {
+            let old_points = parse_points_file(&old)?;
+            let new_points = parse_points_file(&new)?;
+            let changes = compare_visibility(angle, radius, &old_points, &new_points);
+            if changes.is_empty() {
+                println!("No point's visible neighbours changed.");
+            }
+            for change in changes {
+                let became_visible: Vec<u32> = change
+                    .became_visible
+                    .iter()
+                    .map(|point| point.number)
+                    .collect();
+                let stopped_being_visible: Vec<u32> = change
+                    .stopped_being_visible
+                    .iter()
+                    .map(|point| point.number)
+                    .collect();
+                println!(
+                    "Point {}: became visible {became_visible:?}, stopped being visible {stopped_being_visible:?}",
+                    change.origin
+                );
+            }
+        }
+        Command::Batch {
+            file,
+            queries,
+            output,
+            threads,
+        } => {
+            let points = match threads {
+                Some(value) => parse_points_file_parallel(&file, value)?,
+                None => parse_points_file(&file)?,
+            };
+            let queries = parse_queries_file(&queries)?;
+            let lines: String = run_queries(&queries, &points)
+                .iter()
+                .map(|result| result.to_json_line() + "\n")
+                .collect();
+            write_output(&output, lines.as_bytes())?;
+        }
+        Command::Pipe { file } => {
+            let points = parse_points_file(&file)?;
+            let stdout = io::stdout();
+            let mut stdout = stdout.lock();
+            for line in io::stdin().lock().lines() {
+                let line = line.map_err(|error| AppError::InvalidFileError {
+                    expected_path: String::from("stdin"),
+                    source: error,
+                })?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                // flush each result, so a driving process can read it at once
+                writeln!(stdout, "{}", answer_query_line(&line, &points))
+                    .and_then(|()| stdout.flush())
+                    .map_err(|error| AppError::OutputError {
+                        path: String::from("stdout"),
+                        source: error,
+                    })?;
+            }
+        }
+        #[cfg(feature = "stream")]
+        Command::Monitor {
+            query,
+            point,
+            mqtt,
+            topic,
+        } => {
+            use neighbours::domain::{
+                monitor, JsonLinesSource, LiveNeighbourhood, ObservedPoint, PointUpdate,
+            };
+
+            let records = parse_query_records(&query)?;
+            let observed: HashMap<u32, u64> = records
+                .iter()
+                .filter_map(
+                    |InputPoint {
+                         number,
+                         observed_at,
+                         ..
+                     }| Some((*number, (*observed_at)?)),
+                )
+                .collect();
+            let points = refine_points(&query, records.into_iter().map(Point::from).collect())?;
+            let mut neighbourhood = LiveNeighbourhood::default();
+            for point in points {
+                // keep observation times, so points can go stale while monitoring
+                let observed_at = query.max_age.and(observed.get(&point.number).copied());
+                neighbourhood.apply(&PointUpdate::from(ObservedPoint { point, observed_at }));
+            }
+            let stdout = io::stdout();
+            let mut stdout = stdout.lock();
+            let mut previous = None;
+            let mut report = |live: &LiveNeighbourhood| -> Result<(), AppError> {
+                let points = match query.max_age {
+                    Some(max_age) => live.fresh_points(max_age),
+                    None => live.points().to_vec(),
+                };
+                let visible: Vec<u32> =
+                    visible_points_from_neighbours(point, query.angle, query.radius, &points)
+                        .iter()
+                        .map(|value| value.number)
+                        .collect();
+                if previous.as_ref() == Some(&visible) {
+                    return Ok(());
+                }
+                let line = QueryResult {
+                    query: Query {
+                        point_number: point,
+                        angle: query.angle,
+                        radius: query.radius,
+                    },
+                    visible: visible.clone(),
+                }
+                .to_json_line();
+                previous = Some(visible);
+                // flush each change, so a consumer sees it at once
+                writeln!(stdout, "{line}")
+                    .and_then(|()| stdout.flush())
+                    .map_err(|error| AppError::OutputError {
+                        path: String::from("stdout"),
+                        source: error,
+                    })
+            };
+            report(&neighbourhood)?;
+            match mqtt {
+                #[cfg(feature = "mqtt")]
+                Some(url) => {
+                    let mut source = neighbours::domain::MqttSource::subscribe(&url, &topic)?;
+                    eprintln!("Subscribed to `{topic}`");
+                    monitor(&mut neighbourhood, &mut source, |live, _| report(live))?;
+                }
+                #[cfg(not(feature = "mqtt"))]
+                Some(_) => {
+                    return Err(AppError::MQTTError(format!(
+                        "subscribing to `{topic}` needs the `mqtt` feature"
+                    )))
+                }
+                None => {
+                    let stdin = io::stdin();
+                    monitor(
+                        &mut neighbourhood,
+                        &mut JsonLinesSource::new(stdin.lock()),
+                        |live, _| report(live),
+                    )?;
+                }
+            }
+        }
+        Command::Serve {
+            file,
+            query,
+            redis,
+            redis_key,
+            bind,
+            max_body_bytes,
+            max_batch_queries,
+            requests_per_minute,
+            timeout_ms,
+            max_connections,
+            connection_timeout_ms,
+        } => {
+            let store = redis
+                .map(|url| RedisStore::new(&url, &redis_key))
+                .transpose()?;
+            let points = match (&store, query) {
+                // positions come from the store instead
+                (Some(_), _) => vec![],
+                (None, Some(value)) => parse_points_with_query(&file, &value)?,
+                (None, None) => parse_points_file(&file)?,
+            };
+            let listener = TcpListener::bind(&bind).map_err(|error| AppError::BindError {
+                address: bind.clone(),
+                source: error,
+            })?;
+            eprintln!("Listening on http://{bind}");
+            let limits = Limits {
+                max_body_bytes,
+                max_batch_queries,
+                requests_per_minute,
+                request_timeout: timeout_ms.map(Duration::from_millis),
+                connection_timeout: Duration::from_millis(connection_timeout_ms),
+                max_connections,
+                ..Limits::default()
+            };
+            let server = Server::new(points, limits);
+            let server = match store {
+                Some(value) => server.with_store(value),
+                None => server,
+            };
+            serve(listener, server)?;
+        }
+    }
+    Ok(())
+}
+
+/// Set the log level from `--verbose`, or else the `RUST_LOG` environment
+/// variable, then run the command, printing a timing report if `--profile` was
+/// given
+fn run_arguments(
+    Arguments {
+        command,
+        verbose,
+        profile,
+    }: Arguments,
+) -> Result<(), AppError> {
+    let level = match (verbose, std::env::var("RUST_LOG")) {
+        (true, _) => LogLevel::Debug,
+
+        // the filter may be meant for other tools, so a bad level warns
+        (false, Ok(value)) => value.parse().unwrap_or_else(|error| {
+            eprintln!("[Warn] ignoring `RUST_LOG`: {error}");
+            LogLevel::Off
+        }),
+        (false, Err(_)) => LogLevel::Off,
+    };
+    set_log_level(level);
+    if !profile {
+        return run(command);
+    }
+
+    start_profiling();
+    let result = {
+        let _span = Span::enter("run", String::new);
+        run(command)
+    };
+    eprint!("{}", format_profile(&take_profile()));
+    result
+}
+
+/// Runs the command given on the command line.  With no arguments, prints
+/// points visible from point `1`, taking point neighbourhood from
+/// `./points.json` input file, which must exist.
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let visible_points = match visible_points(1, 45, 20) {
-        Ok(value) => value,
+    match parse_arguments(std::env::args().skip(1)).and_then(run_arguments) {
+        Ok(()) => Ok(()),
         Err(error) => {
             eprintln!("{error}");
-            return Err(error.into());
+            Err(error.into())
         }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_query_points;
+    use crate::cli::{parse_arguments, Command};
+    use neighbours::domain::{
+        deduplicate_points, face_points, parse_points_file, DuplicateStrategy, Facing,
     };
 
-    match visible_points.len() {
-        1 => println!("There is 1 visible point."),
-        _ => println!("There are {} visible points.", visible_points.len()),
+    #[test]
+    fn parse_query_points_applies_every_option_given() {
+        // arrange
+        let line = "--file ./fixtures/valid_points.json --max-age 300 --bbox 0,0,30,50 --dedup last --face nearest";
+        let query = match parse_arguments(line.split_whitespace().map(String::from))
+            .unwrap()
+            .command
+        {
+            Command::Visible { query, .. } => query,
+            _ => unreachable!(),
+        };
+        let mut expected = parse_points_file("./fixtures/valid_points.json").unwrap();
+        expected.retain(|point| point.coordinates.0 <= 30);
+        deduplicate_points(&mut expected, DuplicateStrategy::Last);
+        face_points(&mut expected, Facing::Nearest);
+
+        // act
+        let outcome = parse_query_points(&query).unwrap();
+
+        // assert
+        assert!(!outcome.is_empty());
+        assert!(
+            outcome.len()
+                < parse_points_file("./fixtures/valid_points.json")
+                    .unwrap()
+                    .len()
+        );
+        assert_eq!(outcome, expected);
     }
-    println!("{:?}", visible_points);
-    Ok(())
 }
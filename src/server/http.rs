@@ -0,0 +1,336 @@
+use super::limits::Limits;
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, Read, Write},
+    net::TcpStream,
+    time::Instant,
+};
+
+/// HTTP request, with query string parameters split out of the path
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub query: HashMap<String, String>,
+    pub body: String,
+}
+
+/// HTTP response, always sent with `Connection: close`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+impl Response {
+    pub fn new(status: u16, content_type: &'static str, body: String) -> Self {
+        Response {
+            status,
+            content_type,
+            body,
+        }
+    }
+
+    /// Plain text error response, with `message` as the body
+    pub fn error(status: u16, message: &str) -> Self {
+        Response::new(status, "text/plain; charset=utf-8", format!("{message}\n"))
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            204 => "No Content",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            408 => "Request Timeout",
+            413 => "Payload Too Large",
+            429 => "Too Many Requests",
+            431 => "Request Header Fields Too Large",
+            503 => "Service Unavailable",
+            _ => "Internal Server Error",
+        }
+    }
+
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write!(
+            writer,
+            "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.status,
+            self.reason(),
+            self.content_type,
+            self.body.len(),
+            self.body
+        )?;
+        writer.flush()
+    }
+}
+
+/// Split `name=value` pairs of a query string
+fn parse_query_string(query: &str) -> HashMap<String, String> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.split_once('=') {
+            Some((name, value)) => (name.to_string(), value.to_string()),
+            None => (pair.to_string(), String::new()),
+        })
+        .collect()
+}
+
+/// Connection read through, failing reads once `deadline` has passed, so a
+/// client trickling bytes cannot hold its connection open indefinitely
+pub struct DeadlineReader<'a> {
+    stream: &'a TcpStream,
+    deadline: Instant,
+}
+
+impl<'a> DeadlineReader<'a> {
+    pub fn new(stream: &'a TcpStream, deadline: Instant) -> Self {
+        DeadlineReader { stream, deadline }
+    }
+}
+
+impl Read for DeadlineReader<'_> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.deadline.saturating_duration_since(Instant::now());
+        if remaining.as_nanos() == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "timed out reading request",
+            ));
+        }
+        self.stream.set_read_timeout(Some(remaining))?;
+        let mut stream = self.stream;
+        stream.read(buffer)
+    }
+}
+
+/// Response for an error reading a request: `408` for a client too slow to
+/// send it, and otherwise `400`
+fn read_error(error: &io::Error) -> Response {
+    match error.kind() {
+        io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => {
+            Response::error(408, "timed out reading request")
+        }
+        _ => Response::error(400, &format!("error reading request: {error}")),
+    }
+}
+
+/// Read one line from `reader` into `line`, reading no more than `limit`
+/// bytes.  Returns the number of bytes read, or `None` if the line is longer.
+fn read_line_within<R: BufRead>(
+    reader: &mut R,
+    limit: usize,
+    line: &mut String,
+) -> Result<Option<usize>, Response> {
+    let length = reader
+        .by_ref()
+        .take(limit as u64 + 1)
+        .read_line(line)
+        .map_err(|error| read_error(&error))?;
+    if length > limit {
+        Ok(None)
+    } else {
+        Ok(Some(length))
+    }
+}
+
+/// Read a request from `reader`.  Only the request line, `Content-Length`
+/// header and body are used.  Errors give the response to send: `413` for a
+/// body longer than [`Limits::max_body_bytes`], `431` for headers beyond
+/// [`Limits::max_header_bytes`] or [`Limits::max_headers`], `408` for reads
+/// timing out, and otherwise `400`.
+pub fn read_request<R: BufRead>(reader: &mut R, limits: &Limits) -> Result<Request, Response> {
+    let bad_request = |message: String| Response::error(400, &message);
+    let headers_too_large = || Response::error(431, "request headers too large");
+
+    // the request line and headers share one budget
+    let mut remaining = limits.max_header_bytes;
+    let mut line = String::new();
+    remaining -= read_line_within(reader, remaining, &mut line)?
+        .ok_or_else(|| bad_request(String::from("request line too long")))?;
+    let mut parts = line.split_whitespace();
+    let (method, target) = match (parts.next(), parts.next()) {
+        (Some(method), Some(target)) => (method.to_string(), target),
+        _ => return Err(bad_request(String::from("malformed request line"))),
+    };
+    let (path, query) = match target.split_once('?') {
+        Some((path, query)) => (path.to_string(), parse_query_string(query)),
+        None => (target.to_string(), HashMap::new()),
+    };
+
+    let mut content_length = 0;
+    let mut headers = 0;
+    loop {
+        let mut header = String::new();
+        let length =
+            read_line_within(reader, remaining, &mut header)?.ok_or_else(headers_too_large)?;
+        remaining -= length;
+        let header = header.trim_end();
+        if length == 0 || header.is_empty() {
+            break;
+        }
+        headers += 1;
+        if headers > limits.max_headers {
+            return Err(Response::error(
+                431,
+                &format!("request has more than {} headers", limits.max_headers),
+            ));
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| bad_request(String::from("invalid `Content-Length` header")))?;
+            }
+        }
+    }
+
+    if content_length > limits.max_body_bytes {
+        return Err(Response::error(
+            413,
+            &format!("request body exceeds {} bytes", limits.max_body_bytes),
+        ));
+    }
+    let mut body = vec![0; content_length];
+    reader
+        .read_exact(&mut body)
+        .map_err(|error| match error.kind() {
+            io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => read_error(&error),
+            _ => bad_request(format!("error reading request body: {error}")),
+        })?;
+    let body = String::from_utf8(body)
+        .map_err(|_| bad_request(String::from("request body is not UTF-8")))?;
+    Ok(Request {
+        method,
+        path,
+        query,
+        body,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_request, DeadlineReader, Response};
+    use crate::server::Limits;
+    use std::{
+        io::{BufReader, Cursor, Write},
+        net::{TcpListener, TcpStream},
+        time::{Duration, Instant},
+    };
+
+    #[test]
+    fn read_request_gives_expected_result() {
+        // arrange
+        let input =
+            "POST /batch?angle=45&flag HTTP/1.1\r\nHost: x\r\ncontent-length: 7\r\n\r\n1,45,20";
+
+        let limits = Limits {
+            max_body_bytes: 1024,
+            ..Limits::default()
+        };
+
+        // act
+        let outcome = read_request(&mut Cursor::new(input), &limits).unwrap();
+
+        // assert
+        assert_eq!(outcome.method, "POST");
+        assert_eq!(outcome.path, "/batch");
+        assert_eq!(outcome.query["angle"], "45");
+        assert_eq!(outcome.query["flag"], "");
+        assert_eq!(outcome.body, "1,45,20");
+
+        // act
+        let malformed = read_request(&mut Cursor::new("\r\n"), &limits).unwrap_err();
+        let too_large = read_request(
+            &mut Cursor::new(input),
+            &Limits {
+                max_body_bytes: 6,
+                ..limits
+            },
+        )
+        .unwrap_err();
+
+        // assert
+        assert_eq!(malformed, Response::error(400, "malformed request line"));
+        assert_eq!(
+            too_large,
+            Response::error(413, "request body exceeds 6 bytes")
+        );
+    }
+
+    #[test]
+    fn read_request_limits_headers() {
+        // arrange
+        let limits = Limits {
+            max_header_bytes: 64,
+            max_headers: 2,
+            ..Limits::default()
+        };
+        let long_line = format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(64));
+        let long_header = format!("GET / HTTP/1.1\r\nX-Padding: {}\r\n\r\n", "a".repeat(64));
+        let unterminated = format!("GET / HTTP/1.1\r\nX-Padding: {}", "a".repeat(10_000));
+        let many_headers = "GET / HTTP/1.1\r\nA: 1\r\nB: 2\r\nC: 3\r\n\r\n";
+
+        // act
+        let outcome: Vec<Response> = [
+            long_line.as_str(),
+            long_header.as_str(),
+            unterminated.as_str(),
+            many_headers,
+        ]
+        .iter()
+        .map(|input| read_request(&mut Cursor::new(input.as_bytes()), &limits).unwrap_err())
+        .collect();
+
+        // assert
+        assert_eq!(
+            outcome,
+            vec![
+                Response::error(400, "request line too long"),
+                Response::error(431, "request headers too large"),
+                Response::error(431, "request headers too large"),
+                Response::error(431, "request has more than 2 headers"),
+            ]
+        );
+    }
+
+    #[test]
+    fn deadline_reader_times_out_slow_clients() {
+        // arrange
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        (&client).write_all(b"GET /visible HTTP/1.1\r\n").unwrap();
+        let deadline = Instant::now() + Duration::from_millis(50);
+
+        // act
+        let outcome = read_request(
+            &mut BufReader::new(DeadlineReader::new(&stream, deadline)),
+            &Limits::default(),
+        )
+        .unwrap_err();
+
+        // assert
+        assert_eq!(outcome, Response::error(408, "timed out reading request"));
+    }
+
+    #[test]
+    fn response_writes_expected_bytes() {
+        // arrange
+        let response = Response::error(404, "not found");
+        let mut bytes = vec![];
+
+        // act
+        response.write_to(&mut bytes).unwrap();
+
+        // assert
+        assert_eq!(
+            String::from_utf8(bytes).unwrap(),
+            "HTTP/1.1 404 Not Found\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: 10\r\nConnection: close\r\n\r\nnot found\n"
+        );
+    }
+}
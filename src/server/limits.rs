@@ -0,0 +1,202 @@
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+/// Client buckets kept before full buckets, which behave like new ones, are
+/// discarded
+const MAXIMUM_TRACKED_CLIENTS: usize = 10_000;
+
+/// Limits protecting services sharing a host with the server from a
+/// misbehaving client
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Limits {
+    /// Largest request body accepted, in bytes
+    pub max_body_bytes: usize,
+
+    /// Largest request line and headers accepted, in bytes, together
+    pub max_header_bytes: usize,
+
+    /// Most headers accepted in one request
+    pub max_headers: usize,
+
+    /// Most queries accepted in one `/batch` request
+    pub max_batch_queries: usize,
+
+    /// Requests allowed per minute from each client IP address, including a
+    /// burst of up to this many at once, or unlimited if `None`
+    pub requests_per_minute: Option<u32>,
+
+    /// Longest time spent answering one request's queries, or unlimited if
+    /// `None`
+    pub request_timeout: Option<Duration>,
+
+    /// Longest time spent reading a request from a client, or writing the
+    /// response back
+    pub connection_timeout: Duration,
+
+    /// Most connections handled at once, each on its own thread, with a
+    /// `503` response to any more
+    pub max_connections: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_body_bytes: 1024 * 1024,
+            max_header_bytes: 16 * 1024,
+            max_headers: 100,
+            max_batch_queries: 10_000,
+            requests_per_minute: None,
+            request_timeout: None,
+            connection_timeout: Duration::from_secs(10),
+            max_connections: 256,
+        }
+    }
+}
+
+/// Tokens left for one client, as of `updated`
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token bucket rate limiter, keyed by client IP address
+pub struct RateLimiter {
+    requests_per_minute: u32,
+    buckets: Mutex<HashMap<IpAddr, Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(requests_per_minute: u32) -> Self {
+        RateLimiter {
+            requests_per_minute,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns true, and uses up a token, if `client` may make a request at
+    /// `now`
+    pub fn allow(&self, client: IpAddr, now: Instant) -> bool {
+        let capacity = f64::from(self.requests_per_minute);
+        let refill = |bucket: &Bucket| {
+            let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+            (bucket.tokens + elapsed * capacity / 60.0).min(capacity)
+        };
+        let mut buckets = match self.buckets.lock() {
+            Ok(value) => value,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        if buckets.len() >= MAXIMUM_TRACKED_CLIENTS {
+            buckets.retain(|_, bucket| refill(bucket) < capacity);
+        }
+        let bucket = buckets.entry(client).or_insert(Bucket {
+            tokens: capacity,
+            updated: now,
+        });
+        bucket.tokens = refill(bucket);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Count of open connections, capped at a maximum
+pub struct ConnectionSlots {
+    open: Arc<AtomicUsize>,
+    maximum: usize,
+}
+
+/// Place held by one open connection, given up when dropped
+pub struct ConnectionSlot {
+    open: Arc<AtomicUsize>,
+}
+
+impl ConnectionSlots {
+    pub fn new(maximum: usize) -> Self {
+        ConnectionSlots {
+            open: Arc::new(AtomicUsize::new(0)),
+            maximum,
+        }
+    }
+
+    /// Take a place for a new connection, or `None` if all are taken
+    pub fn acquire(&self) -> Option<ConnectionSlot> {
+        self.open
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |open| {
+                if open < self.maximum {
+                    Some(open + 1)
+                } else {
+                    None
+                }
+            })
+            .ok()
+            .map(|_| ConnectionSlot {
+                open: Arc::clone(&self.open),
+            })
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.open.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConnectionSlots, RateLimiter};
+    use std::{
+        net::{IpAddr, Ipv4Addr},
+        time::{Duration, Instant},
+    };
+
+    #[test]
+    fn rate_limiter_limits_each_client() {
+        // arrange
+        let limiter = RateLimiter::new(2);
+        let client = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        let other_client = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+        let start = Instant::now();
+
+        // act
+        let burst: Vec<bool> = (0..3).map(|_| limiter.allow(client, start)).collect();
+        let other = limiter.allow(other_client, start);
+        let after_refill = limiter.allow(client, start + Duration::from_secs(30));
+        let again = limiter.allow(client, start + Duration::from_secs(30));
+
+        // assert
+        assert_eq!(burst, vec![true, true, false]);
+        assert!(other);
+        assert!(after_refill);
+        assert!(!again);
+    }
+
+    #[test]
+    fn connection_slots_cap_open_connections() {
+        // arrange
+        let slots = ConnectionSlots::new(2);
+
+        // act
+        let first = slots.acquire();
+        let second = slots.acquire();
+        let third = slots.acquire();
+        drop(first);
+        let after_close = slots.acquire();
+
+        // assert
+        assert!(second.is_some());
+        assert!(third.is_none());
+        assert!(after_close.is_some());
+        assert!(slots.acquire().is_none());
+    }
+}
@@ -0,0 +1,141 @@
+use std::{
+    fmt::Write,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+/// Upper bounds, in seconds, of query latency histogram buckets
+const LATENCY_BUCKETS: [f64; 8] = [0.0001, 0.0005, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5];
+
+/// Counters exported at `/metrics`, in the Prometheus text format.  All
+/// counters are atomic, so one instance is shared by every connection.
+pub struct Metrics {
+    neighbourhood_points: AtomicU64,
+    queries: AtomicU64,
+    errors: AtomicU64,
+    rate_limited: AtomicU64,
+
+    /// Query counts falling in each of [`LATENCY_BUCKETS`], not cumulative
+    latency_buckets: [AtomicU64; LATENCY_BUCKETS.len()],
+    latency_count: AtomicU64,
+    latency_sum_nanoseconds: AtomicU64,
+}
+
+impl Metrics {
+    pub fn new(neighbourhood_points: usize) -> Self {
+        Metrics {
+            neighbourhood_points: AtomicU64::new(neighbourhood_points as u64),
+            queries: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+            rate_limited: AtomicU64::new(0),
+            latency_buckets: Default::default(),
+            latency_count: AtomicU64::new(0),
+            latency_sum_nanoseconds: AtomicU64::new(0),
+        }
+    }
+
+    /// Update the neighbourhood size, after reading the latest positions from
+    /// a live store
+    pub fn set_neighbourhood_points(&self, neighbourhood_points: usize) {
+        self.neighbourhood_points
+            .store(neighbourhood_points as u64, Ordering::Relaxed);
+    }
+
+    /// Record `queries` answered by one request, which took `latency`
+    pub fn record_queries(&self, queries: usize, latency: Duration) {
+        self.queries.fetch_add(queries as u64, Ordering::Relaxed);
+        let seconds = latency.as_secs_f64();
+        if let Some(index) = LATENCY_BUCKETS.iter().position(|bound| seconds <= *bound) {
+            self.latency_buckets[index].fetch_add(1, Ordering::Relaxed);
+        }
+        self.latency_count.fetch_add(1, Ordering::Relaxed);
+        self.latency_sum_nanoseconds
+            .fetch_add(latency.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    /// Record a request which failed, for example with an invalid query
+    pub fn record_error(&self) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record a request rejected by the rate limiter
+    pub fn record_rate_limited(&self) {
+        self.rate_limited.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Render all metrics in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let mut result = String::new();
+        let _ = writeln!(
+            result,
+            "# HELP neighbours_neighbourhood_points Points in the loaded neighbourhood.\n# TYPE neighbours_neighbourhood_points gauge\nneighbours_neighbourhood_points {}",
+            self.neighbourhood_points.load(Ordering::Relaxed)
+        );
+        let _ = writeln!(
+            result,
+            "# HELP neighbours_queries_total Visibility queries answered.\n# TYPE neighbours_queries_total counter\nneighbours_queries_total {}",
+            self.queries.load(Ordering::Relaxed)
+        );
+        let _ = writeln!(
+            result,
+            "# HELP neighbours_errors_total Requests which failed.\n# TYPE neighbours_errors_total counter\nneighbours_errors_total {}",
+            self.errors.load(Ordering::Relaxed)
+        );
+        let _ = writeln!(
+            result,
+            "# HELP neighbours_rate_limited_total Requests rejected by the rate limiter.\n# TYPE neighbours_rate_limited_total counter\nneighbours_rate_limited_total {}",
+            self.rate_limited.load(Ordering::Relaxed)
+        );
+        let _ = writeln!(
+            result,
+            "# HELP neighbours_request_duration_seconds Time taken to answer query requests.\n# TYPE neighbours_request_duration_seconds histogram"
+        );
+        let mut cumulative = 0;
+        for (bound, count) in LATENCY_BUCKETS.iter().zip(&self.latency_buckets) {
+            cumulative += count.load(Ordering::Relaxed);
+            let _ = writeln!(
+                result,
+                "neighbours_request_duration_seconds_bucket{{le=\"{bound}\"}} {cumulative}"
+            );
+        }
+        let count = self.latency_count.load(Ordering::Relaxed);
+        let sum = self.latency_sum_nanoseconds.load(Ordering::Relaxed) as f64 / 1e9;
+        let _ = writeln!(
+            result,
+            "neighbours_request_duration_seconds_bucket{{le=\"+Inf\"}} {count}\nneighbours_request_duration_seconds_sum {sum}\nneighbours_request_duration_seconds_count {count}"
+        );
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Metrics;
+    use std::time::Duration;
+
+    #[test]
+    fn metrics_render_expected_text() {
+        // arrange
+        let metrics = Metrics::new(20);
+
+        // act
+        metrics.record_queries(1, Duration::from_micros(200));
+        metrics.record_queries(3, Duration::from_millis(2));
+        metrics.record_queries(1, Duration::from_secs(1));
+        metrics.record_error();
+        metrics.record_rate_limited();
+        let outcome = metrics.render();
+
+        // assert
+        assert!(outcome.contains("\nneighbours_neighbourhood_points 20\n"));
+        assert!(outcome.contains("\nneighbours_queries_total 5\n"));
+        assert!(outcome.contains("\nneighbours_errors_total 1\n"));
+        assert!(outcome.contains("\nneighbours_rate_limited_total 1\n"));
+        assert!(outcome.contains("\nneighbours_request_duration_seconds_bucket{le=\"0.0001\"} 0\n"));
+        assert!(outcome.contains("\nneighbours_request_duration_seconds_bucket{le=\"0.0005\"} 1\n"));
+        assert!(outcome.contains("\nneighbours_request_duration_seconds_bucket{le=\"0.5\"} 2\n"));
+        assert!(outcome.contains("\nneighbours_request_duration_seconds_bucket{le=\"+Inf\"} 3\n"));
+        assert!(outcome.contains("\nneighbours_request_duration_seconds_sum 1.0022\n"));
+        assert!(outcome.ends_with("neighbours_request_duration_seconds_count 3\n"));
+    }
+}
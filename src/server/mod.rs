@@ -0,0 +1,386 @@
+mod http;
+mod limits;
+mod metrics;
+mod redis;
+
+pub use http::{read_request, DeadlineReader, Request, Response};
+pub use limits::{ConnectionSlots, Limits, RateLimiter};
+pub use metrics::Metrics;
+pub use redis::{parse_position, RedisStore};
+
+use neighbours::{
+    domain::{parse_queries, run_queries_cancellable, Point, Query},
+    utilities::{log, AppError, Cancellation, LogLevel},
+};
+use std::{
+    borrow::Cow,
+    io::BufReader,
+    net::{IpAddr, TcpListener, TcpStream},
+    str::FromStr,
+    sync::Arc,
+    thread,
+    time::Instant,
+};
+
+/// Content type of responses with one JSON result per line
+const JSON_LINES: &str = "application/x-ndjson";
+
+/// Visibility service answering queries against one loaded neighbourhood:
+///
+/// - `GET /visible?point=1&angle=45&radius=20` answers a single query,
+/// - `POST /batch` answers queries in the body, given as for a batch queries
+///   file, and
+/// - `GET /metrics` exports Prometheus metrics.
+///
+/// Query results are JSON lines, as in pipe mode.  Request sizes, batch sizes,
+/// request rates and time spent answering are capped by [`Limits`], with a
+/// `503` response for requests running out of time.
+///
+/// With a [`RedisStore`], every query reads the latest positions from Redis,
+/// instead of the loaded neighbourhood, and `PUT /points/{number}`, with a
+/// JSON position body, such as `{"x":27,"y":46,"direction":"East"}`, updates
+/// one point.
+pub struct Server {
+    points: Vec<Point>,
+    store: Option<RedisStore>,
+    metrics: Metrics,
+    limits: Limits,
+    rate_limiter: Option<RateLimiter>,
+}
+
+/// Parse query string parameter `name`, or return `default` if missing
+fn parameter<T: FromStr>(request: &Request, name: &str, default: T) -> Result<T, String> {
+    match request.query.get(name) {
+        Some(value) => value
+            .parse()
+            .map_err(|_| format!("invalid value for `{name}`: `{value}`")),
+        None => Ok(default),
+    }
+}
+
+impl Server {
+    pub fn new(points: Vec<Point>, limits: Limits) -> Self {
+        Server {
+            metrics: Metrics::new(points.len()),
+            points,
+            store: None,
+            limits,
+            rate_limiter: limits.requests_per_minute.map(RateLimiter::new),
+        }
+    }
+
+    /// Answer queries against positions in `store`, rather than the points
+    /// the server was created with
+    pub fn with_store(self, store: RedisStore) -> Self {
+        Server {
+            store: Some(store),
+            ..self
+        }
+    }
+
+    /// Latest neighbourhood: from the live store, if there is one
+    fn neighbourhood(&self) -> Result<Cow<'_, [Point]>, AppError> {
+        match &self.store {
+            Some(store) => {
+                let points = store.load()?;
+                self.metrics.set_neighbourhood_points(points.len());
+                Ok(Cow::Owned(points))
+            }
+            None => Ok(Cow::Borrowed(&self.points)),
+        }
+    }
+
+    /// Write the position in the body of `request` to the live store
+    fn update(&self, store: &RedisStore, request: &Request) -> Response {
+        let number = match request.path["/points/".len()..].parse() {
+            Ok(value) => value,
+            Err(_) => {
+                self.metrics.record_error();
+                return Response::error(400, "invalid point number");
+            }
+        };
+        let result = parse_position(number, &request.body)
+            .map_err(|error| Response::error(400, &error.to_string()))
+            .and_then(|point| {
+                store
+                    .store(&point)
+                    .map_err(|error| Response::error(503, &error.to_string()))
+            });
+        match result {
+            Ok(()) => Response::new(204, "text/plain; charset=utf-8", String::new()),
+            Err(response) => {
+                self.metrics.record_error();
+                response
+            }
+        }
+    }
+
+    /// Queries requested by `request`, or a message for a `400` response
+    fn queries(&self, request: &Request) -> Result<Vec<Query>, String> {
+        match request.path.as_str() {
+            "/visible" => Ok(vec![Query {
+                point_number: parameter(request, "point", 1)?,
+                angle: parameter(request, "angle", 45)?,
+                radius: parameter(request, "radius", 20)?,
+            }]),
+            _ => {
+                let queries = parse_queries(&request.body).map_err(|error| error.to_string())?;
+                match queries.len() {
+                    length if length > self.limits.max_batch_queries => Err(format!(
+                        "batch of {length} queries exceeds limit of {}",
+                        self.limits.max_batch_queries
+                    )),
+                    _ => Ok(queries),
+                }
+            }
+        }
+    }
+
+    /// Answer the queries in `request`, recording metrics
+    fn answer(&self, request: &Request) -> Response {
+        let start = Instant::now();
+        let points = match self.neighbourhood() {
+            Ok(value) => value,
+            Err(error) => {
+                self.metrics.record_error();
+                return Response::error(503, &error.to_string());
+            }
+        };
+        match self.queries(request) {
+            Ok(queries) => {
+                let cancellation = match self.limits.request_timeout {
+                    Some(timeout) => Cancellation::with_deadline(start + timeout),
+                    None => Cancellation::new(),
+                };
+                let results = run_queries_cancellable(&queries, &points, &cancellation);
+                if results.len() < queries.len() {
+                    self.metrics.record_error();
+                    return Response::error(
+                        503,
+                        &format!(
+                            "request timed out after answering {} of {} queries",
+                            results.len(),
+                            queries.len()
+                        ),
+                    );
+                }
+                let body: String = results
+                    .iter()
+                    .map(|result| result.to_json_line() + "\n")
+                    .collect();
+                self.metrics.record_queries(queries.len(), start.elapsed());
+                Response::new(200, JSON_LINES, body)
+            }
+            Err(message) => {
+                self.metrics.record_error();
+                Response::error(400, &message)
+            }
+        }
+    }
+
+    /// Route `request` to its endpoint
+    pub fn handle(&self, request: &Request) -> Response {
+        match (request.method.as_str(), request.path.as_str()) {
+            ("GET", "/visible") | ("POST", "/batch") => self.answer(request),
+            ("GET", "/metrics") => {
+                Response::new(200, "text/plain; version=0.0.4", self.metrics.render())
+            }
+            (method, path) if path.starts_with("/points/") => match (method, &self.store) {
+                ("PUT", Some(store)) => self.update(store, request),
+                (_, Some(_)) => Response::error(405, "method not allowed"),
+                (_, None) => Response::error(404, "not found"),
+            },
+            (_, "/visible") | (_, "/batch") | (_, "/metrics") => {
+                Response::error(405, "method not allowed")
+            }
+            _ => Response::error(404, "not found"),
+        }
+    }
+
+    /// Returns a `429` response if `client` has used up its requests
+    fn rate_limit(&self, client: IpAddr) -> Option<Response> {
+        match &self.rate_limiter {
+            Some(limiter) if !limiter.allow(client, Instant::now()) => {
+                self.metrics.record_rate_limited();
+                Some(Response::error(429, "too many requests"))
+            }
+            _ => None,
+        }
+    }
+
+    fn handle_connection(&self, stream: TcpStream) {
+        let limited = stream
+            .peer_addr()
+            .ok()
+            .and_then(|address| self.rate_limit(address.ip()));
+        let response = match limited {
+            Some(value) => value,
+            None => {
+                let deadline = Instant::now() + self.limits.connection_timeout;
+                let mut reader = BufReader::new(DeadlineReader::new(&stream, deadline));
+                match read_request(&mut reader, &self.limits) {
+                    Ok(request) => self.handle(&request),
+                    Err(response) => {
+                        self.metrics.record_error();
+                        response
+                    }
+                }
+            }
+        };
+        self.respond(&stream, &response);
+    }
+
+    /// Write `response` to `stream`, giving up on clients too slow to read it
+    fn respond(&self, stream: &TcpStream, response: &Response) {
+        let mut stream = stream;
+        let result = stream
+            .set_write_timeout(Some(self.limits.connection_timeout))
+            .and_then(|_| response.write_to(&mut stream));
+        if let Err(error) = result {
+            log(LogLevel::Warn, &format!("error writing response: {error}"));
+        }
+    }
+}
+
+/// Answer requests arriving on `listener`, each connection on its own thread,
+/// until the listener fails.  Connections beyond [`Limits::max_connections`]
+/// get a `503` response straight away.
+pub fn serve(listener: TcpListener, server: Server) -> Result<(), AppError> {
+    let server = Arc::new(server);
+    let slots = ConnectionSlots::new(server.limits.max_connections);
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => match slots.acquire() {
+                Some(slot) => {
+                    let server = Arc::clone(&server);
+                    thread::spawn(move || {
+                        server.handle_connection(stream);
+                        drop(slot);
+                    });
+                }
+                None => {
+                    server.metrics.record_error();
+                    server.respond(&stream, &Response::error(503, "too many connections"));
+                }
+            },
+            Err(error) => log(
+                LogLevel::Warn,
+                &format!("error accepting connection: {error}"),
+            ),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Limits, Request, Server};
+    use neighbours::domain::{Direction, Point};
+    use std::{
+        collections::HashMap,
+        net::{IpAddr, Ipv4Addr},
+        time::Duration,
+    };
+
+    fn server(limits: Limits) -> Server {
+        Server::new(
+            vec![
+                Point {
+                    coordinates: (0, 0),
+                    number: 1,
+                    direction: Direction::North,
+                },
+                Point {
+                    coordinates: (0, 15),
+                    number: 2,
+                    direction: Direction::South,
+                },
+            ],
+            limits,
+        )
+    }
+
+    fn request(method: &str, path: &str, query: &[(&str, &str)], body: &str) -> Request {
+        Request {
+            method: method.to_string(),
+            path: path.to_string(),
+            query: query
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect::<HashMap<String, String>>(),
+            body: body.to_string(),
+        }
+    }
+
+    #[test]
+    fn server_handles_requests() {
+        // arrange
+        let server = server(Limits::default());
+
+        // act
+        let visible = server.handle(&request("GET", "/visible", &[("radius", "10")], ""));
+        let batch = server.handle(&request("POST", "/batch", &[], "1,45,20\n2,10,20"));
+        let invalid = server.handle(&request("GET", "/visible", &[("point", "one")], ""));
+        let missing = server.handle(&request("GET", "/other", &[], ""));
+        let no_store = server.handle(&request("PUT", "/points/1", &[], "{}"));
+        let metrics = server.handle(&request("GET", "/metrics", &[], ""));
+
+        // assert
+        assert_eq!(
+            visible.body,
+            "{\"point_number\":1,\"angle\":45,\"radius\":10,\"visible\":[]}\n"
+        );
+        assert_eq!(batch.body.lines().count(), 2);
+        assert_eq!(invalid.status, 400);
+        assert_eq!(invalid.body, "invalid value for `point`: `one`\n");
+        assert_eq!(missing.status, 404);
+        assert_eq!(no_store.status, 404);
+        assert!(metrics.body.contains("\nneighbours_queries_total 3\n"));
+        assert!(metrics.body.contains("\nneighbours_errors_total 1\n"));
+        assert!(metrics
+            .body
+            .contains("\nneighbours_neighbourhood_points 2\n"));
+    }
+
+    #[test]
+    fn server_enforces_limits() {
+        // arrange
+        let server = server(Limits {
+            max_body_bytes: 1024,
+            max_batch_queries: 1,
+            requests_per_minute: Some(1),
+            ..Limits::default()
+        });
+        let client = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+        // act
+        let batch = server.handle(&request("POST", "/batch", &[], "1,45,20\n2,10,20"));
+        let first = server.rate_limit(client);
+        let second = server.rate_limit(client);
+
+        // assert
+        assert_eq!(batch.status, 400);
+        assert_eq!(batch.body, "batch of 2 queries exceeds limit of 1\n");
+        assert_eq!(first, None);
+        assert_eq!(second.map(|response| response.status), Some(429));
+    }
+
+    #[test]
+    fn server_times_out_requests() {
+        // arrange
+        let server = server(Limits {
+            request_timeout: Some(Duration::from_secs(0)),
+            ..Limits::default()
+        });
+
+        // act
+        let timed_out = server.handle(&request("POST", "/batch", &[], "1,45,20\n2,10,20"));
+
+        // assert
+        assert_eq!(timed_out.status, 503);
+        assert_eq!(
+            timed_out.body,
+            "request timed out after answering 0 of 2 queries\n"
+        );
+    }
+}
@@ -0,0 +1,418 @@
+use neighbours::{
+    domain::{Direction, Point},
+    utilities::AppError,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpStream,
+    sync::Mutex,
+    time::Duration,
+};
+
+/// Longest wait for Redis to reply, before the request fails
+const REDIS_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Longest reply line accepted, in bytes
+const MAXIMUM_LINE_BYTES: u64 = 64 * 1024;
+
+/// Longest bulk string accepted, in bytes, as for Redis' own default
+/// `proto-max-bulk-len`
+const MAXIMUM_BULK_BYTES: i64 = 512 * 1024 * 1024;
+
+/// Position of one point, as stored in the Redis hash, keyed by point number
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+struct StoredPosition {
+    x: i32,
+    y: i32,
+    direction: Direction,
+}
+
+/// Reply to a Redis command, in the RESP protocol
+#[derive(Clone, Debug, PartialEq)]
+enum Reply {
+    Status(String),
+    Integer(i64),
+    Bulk(Option<Vec<u8>>),
+    Array(Vec<Reply>),
+}
+
+fn redis_error(message: String) -> AppError {
+    AppError::RedisError(message)
+}
+
+/// Read one RESP reply from `reader`
+fn read_reply<R: BufRead>(reader: &mut R) -> Result<Reply, String> {
+    let mut line = String::new();
+    reader
+        .by_ref()
+        .take(MAXIMUM_LINE_BYTES)
+        .read_line(&mut line)
+        .map_err(|error| format!("error reading reply: {error}"))?;
+    if line.len() as u64 >= MAXIMUM_LINE_BYTES {
+        return Err(format!("reply line exceeds {MAXIMUM_LINE_BYTES} bytes"));
+    }
+    let line = line.trim_end_matches("\r\n");
+    let (kind, value) = match line.chars().next() {
+        Some(kind) => (kind, &line[kind.len_utf8()..]),
+        None => return Err(String::from("connection closed")),
+    };
+    let length =
+        || -> Result<i64, String> { value.parse().map_err(|_| format!("invalid reply `{line}`")) };
+    match kind {
+        '+' => Ok(Reply::Status(value.to_string())),
+        '-' => Err(value.to_string()),
+        ':' => Ok(Reply::Integer(length()?)),
+        '$' => match length()? {
+            length if length < 0 => Ok(Reply::Bulk(None)),
+            length if length > MAXIMUM_BULK_BYTES => Err(format!(
+                "bulk reply of {length} bytes exceeds {MAXIMUM_BULK_BYTES} bytes"
+            )),
+            length => {
+                // grow the buffer as bytes arrive, rather than trusting the
+                // length up front
+                let mut bytes = vec![];
+                reader
+                    .by_ref()
+                    .take(length as u64 + 2)
+                    .read_to_end(&mut bytes)
+                    .map_err(|error| format!("error reading reply: {error}"))?;
+                if bytes.len() as i64 != length + 2 {
+                    return Err(String::from("connection closed"));
+                }
+                bytes.truncate(length as usize);
+                Ok(Reply::Bulk(Some(bytes)))
+            }
+        },
+        '*' => match length()? {
+            length if length < 0 => Ok(Reply::Array(vec![])),
+            length => (0..length)
+                .map(|_| read_reply(reader))
+                .collect::<Result<Vec<Reply>, String>>()
+                .map(Reply::Array),
+        },
+        _ => Err(format!("invalid reply `{line}`")),
+    }
+}
+
+/// Encode `arguments` as a RESP command
+fn encode_command(arguments: &[&[u8]]) -> Vec<u8> {
+    let mut result = format!("*{}\r\n", arguments.len()).into_bytes();
+    for argument in arguments {
+        result.extend_from_slice(format!("${}\r\n", argument.len()).as_bytes());
+        result.extend_from_slice(argument);
+        result.extend_from_slice(b"\r\n");
+    }
+    result
+}
+
+/// Live neighbourhood held in a Redis hash, so several producers can update
+/// positions, and every query sees the latest.  Hash fields are point
+/// numbers, and values JSON positions, such as
+/// `{"x":27,"y":46,"direction":"East"}`.
+pub struct RedisStore {
+    address: String,
+    password: Option<String>,
+    database: u32,
+    key: String,
+
+    /// Open connection, reused between commands, and replaced after errors
+    connection: Mutex<Option<BufReader<TcpStream>>>,
+}
+
+impl RedisStore {
+    /// Store using hash `key`, on the server at `url`, of the form
+    /// `redis://:password@host:port/database`, where all parts but the host
+    /// are optional
+    pub fn new(url: &str, key: &str) -> Result<Self, AppError> {
+        let rest = url
+            .strip_prefix("redis://")
+            .ok_or_else(|| redis_error(format!("URL `{url}` should start with `redis://`")))?;
+        let (authority, database) = match rest.split_once('/') {
+            Some((authority, database)) => (authority, database),
+            None => (rest, ""),
+        };
+        let (password, host) = match authority.rsplit_once('@') {
+            // user names are ignored, as servers without ACLs do not use them
+            Some((credentials, host)) => (
+                Some(match credentials.split_once(':') {
+                    Some((_, password)) => password.to_string(),
+                    None => credentials.to_string(),
+                }),
+                host,
+            ),
+            None => (None, authority),
+        };
+        let address = match host.contains(':') {
+            true => host.to_string(),
+            false => format!("{host}:6379"),
+        };
+        let database = match database {
+            "" => 0,
+            value => value
+                .parse()
+                .map_err(|_| redis_error(format!("invalid database `{value}` in `{url}`")))?,
+        };
+        Ok(RedisStore {
+            address,
+            password,
+            database,
+            key: key.to_string(),
+            connection: Mutex::new(None),
+        })
+    }
+
+    fn connect(&self) -> Result<BufReader<TcpStream>, String> {
+        let stream = TcpStream::connect(&self.address)
+            .map_err(|error| format!("error connecting to `{}`: {error}", self.address))?;
+        stream
+            .set_read_timeout(Some(REDIS_TIMEOUT))
+            .map_err(|error| error.to_string())?;
+        let mut connection = BufReader::new(stream);
+        if let Some(password) = &self.password {
+            Self::send(&mut connection, &[b"AUTH", password.as_bytes()])?;
+        }
+        if self.database != 0 {
+            Self::send(
+                &mut connection,
+                &[b"SELECT", self.database.to_string().as_bytes()],
+            )?;
+        }
+        Ok(connection)
+    }
+
+    fn send(connection: &mut BufReader<TcpStream>, arguments: &[&[u8]]) -> Result<Reply, String> {
+        connection
+            .get_mut()
+            .write_all(&encode_command(arguments))
+            .map_err(|error| format!("error sending command: {error}"))?;
+        read_reply(connection)
+    }
+
+    /// Run a command, connecting first if needed, and dropping the connection
+    /// after a failure, so the next command reconnects
+    fn command(&self, arguments: &[&[u8]]) -> Result<Reply, AppError> {
+        let mut connection = self
+            .connection
+            .lock()
+            .map_err(|_| redis_error(String::from("connection lock poisoned")))?;
+        if connection.is_none() {
+            *connection = Some(self.connect().map_err(redis_error)?);
+        }
+        let result = match connection.as_mut() {
+            Some(value) => Self::send(value, arguments),
+            None => Err(String::from("not connected")),
+        };
+        if result.is_err() {
+            *connection = None;
+        }
+        result.map_err(redis_error)
+    }
+
+    /// Read every point from the hash
+    pub fn load(&self) -> Result<Vec<Point>, AppError> {
+        let entries = match self.command(&[b"HGETALL", self.key.as_bytes()])? {
+            Reply::Array(value) => value,
+            reply => return Err(redis_error(format!("unexpected reply {reply:?}"))),
+        };
+        let mut result: Vec<Point> = entries
+            .chunks(2)
+            .map(|entry| match entry {
+                [Reply::Bulk(Some(field)), Reply::Bulk(Some(value))] => {
+                    let field = String::from_utf8_lossy(field);
+                    let number = field
+                        .parse()
+                        .map_err(|_| redis_error(format!("invalid point number `{field}`")))?;
+                    let StoredPosition { x, y, direction } = serde_json::from_slice(value)
+                        .map_err(|error| {
+                            redis_error(format!("invalid position for point {number}: {error}"))
+                        })?;
+                    if !direction.is_resolved() {
+                        return Err(redis_error(format!(
+                            "invalid position for point {number}: direction faces another point"
+                        )));
+                    }
+                    Ok(Point {
+                        coordinates: (x, y),
+                        number,
+                        direction,
+                    })
+                }
+                _ => Err(redis_error(String::from("unexpected reply to HGETALL"))),
+            })
+            .collect::<Result<Vec<Point>, AppError>>()?;
+        // hash order is arbitrary, so order by number, for stable results
+        result.sort_by_key(|point| point.number);
+        Ok(result)
+    }
+
+    /// Write the position of `point` to the hash, replacing any earlier one
+    pub fn store(&self, point: &Point) -> Result<(), AppError> {
+        let Point {
+            coordinates: (x, y),
+            number,
+            direction,
+        } = *point;
+        let value = serde_json::to_vec(&StoredPosition { x, y, direction })
+            .map_err(AppError::JSONParseError)?;
+        self.command(&[
+            b"HSET",
+            self.key.as_bytes(),
+            number.to_string().as_bytes(),
+            &value,
+        ])?;
+        Ok(())
+    }
+}
+
+/// Parse a position, given as JSON `body`, for point `number`.  A direction
+/// towards another point is rejected, since it could only be resolved against
+/// positions which may change before the next query.
+pub fn parse_position(number: u32, body: &str) -> Result<Point, AppError> {
+    let StoredPosition { x, y, direction } =
+        serde_json::from_str(body).map_err(AppError::JSONParseError)?;
+    if let Direction::Towards(target) = direction {
+        return Err(AppError::FacingError(format!(
+            "point {number} faces point {target}, but a position should give a compass direction or a vector"
+        )));
+    }
+    Ok(Point {
+        coordinates: (x, y),
+        number,
+        direction,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encode_command, parse_position, read_reply, RedisStore, Reply};
+    use neighbours::domain::{Direction, Point};
+    use std::{
+        io::{BufRead, BufReader, Cursor, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    #[test]
+    fn read_reply_gives_expected_result() {
+        // arrange
+        let mut reader = Cursor::new(b"*3\r\n$1\r\n7\r\n$-1\r\n:2\r\n-ERR wrong type\r\n".to_vec());
+
+        // act
+        let outcome = read_reply(&mut reader).unwrap();
+        let error = read_reply(&mut reader).unwrap_err();
+
+        // assert
+        assert_eq!(
+            outcome,
+            Reply::Array(vec![
+                Reply::Bulk(Some(b"7".to_vec())),
+                Reply::Bulk(None),
+                Reply::Integer(2)
+            ])
+        );
+        assert_eq!(error, "ERR wrong type");
+        let malformed: Vec<String> = [
+            "\u{e9}1\r\n".as_bytes(),
+            b"$100000000\r\n7\r\n",
+            b"$1000000000000\r\n",
+        ]
+        .iter()
+        .map(|input| read_reply(&mut Cursor::new(input.to_vec())).unwrap_err())
+        .collect();
+        assert_eq!(
+            malformed,
+            vec![
+                String::from("invalid reply `\u{e9}1`"),
+                String::from("connection closed"),
+                String::from("bulk reply of 1000000000000 bytes exceeds 536870912 bytes"),
+            ]
+        );
+        assert_eq!(
+            encode_command(&[b"HGET", b"key", b"7"]),
+            b"*3\r\n$4\r\nHGET\r\n$3\r\nkey\r\n$1\r\n7\r\n".to_vec()
+        );
+    }
+
+    #[test]
+    fn parse_position_rejects_directions_towards_points() {
+        // arrange
+        let body = "{\"x\":1,\"y\":2,\"direction\":{\"towards\":3}}";
+
+        // act
+        let outcome = parse_position(7, body);
+
+        // assert
+        assert_eq!(
+            outcome.unwrap_err().to_string(),
+            "Error resolving point direction: point 7 faces point 3, but a position should give a compass direction or a vector"
+        );
+        assert_eq!(
+            parse_position(7, "{\"x\":1,\"y\":2,\"direction\":{\"dx\":1,\"dy\":0}}").unwrap(),
+            Point {
+                coordinates: (1, 2),
+                number: 7,
+                direction: Direction::Vector { dx: 1.0, dy: 0.0 },
+            }
+        );
+    }
+
+    #[test]
+    fn redis_store_reads_and_writes_positions() {
+        // arrange
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut writer = stream;
+            let mut commands = vec![];
+            for reply in [
+                &b"+OK\r\n"[..],
+                b"*4\r\n$1\r\n2\r\n$33\r\n{\"x\":0,\"y\":5,\"direction\":\"South\"}\r\n$1\r\n1\r\n$33\r\n{\"x\":0,\"y\":0,\"direction\":\"North\"}\r\n",
+                b":1\r\n",
+            ] {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let count: usize = line[1..].trim().parse().unwrap();
+                let mut command = vec![];
+                for _ in 0..count * 2 {
+                    line.clear();
+                    reader.read_line(&mut line).unwrap();
+                    command.push(line.trim().to_string());
+                }
+                commands.push(command.into_iter().skip(1).step_by(2).collect::<Vec<_>>());
+                writer.write_all(reply).unwrap();
+            }
+            commands
+        });
+        let store = RedisStore::new(&format!("redis://:secret@{address}"), "live").unwrap();
+        let moved = Point {
+            coordinates: (-3, 4),
+            number: 3,
+            direction: Direction::West,
+        };
+
+        // act
+        let loaded = store.load().unwrap();
+        store.store(&moved).unwrap();
+
+        // assert
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded[0].number, 1);
+        assert_eq!(loaded[1].coordinates, (0, 5));
+        assert_eq!(
+            server.join().unwrap(),
+            vec![
+                vec!["AUTH", "secret"],
+                vec!["HGETALL", "live"],
+                vec![
+                    "HSET",
+                    "live",
+                    "3",
+                    "{\"x\":-3,\"y\":4,\"direction\":\"West\"}"
+                ]
+            ]
+        );
+    }
+}
@@ -0,0 +1,74 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+/// Cooperative cancellation for long computations, such as visibility graphs
+/// and heatmaps.  Clones share the same flag, so one thread can cancel work
+/// running on another.  Work also stops once the optional deadline passes.
+/// Computations check the token between steps, so they stop soon after
+/// cancelling, rather than at once.
+#[derive(Clone, Debug, Default)]
+pub struct Cancellation {
+    cancelled: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+}
+
+impl Cancellation {
+    /// Token which is only cancelled by calling [`Cancellation::cancel`]
+    pub fn new() -> Self {
+        Cancellation::default()
+    }
+
+    /// Token which is also cancelled once `deadline` passes
+    pub fn with_deadline(deadline: Instant) -> Self {
+        Cancellation {
+            cancelled: Arc::default(),
+            deadline: Some(deadline),
+        }
+    }
+
+    /// Token which is also cancelled once `timeout` has passed from now
+    pub fn with_timeout(timeout: Duration) -> Self {
+        Cancellation::with_deadline(Instant::now() + timeout)
+    }
+
+    /// Ask work using this token, or any of its clones, to stop
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns true if the token was cancelled, or its deadline has passed
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+            || self
+                .deadline
+                .map_or(false, |deadline| Instant::now() >= deadline)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Cancellation;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn cancellation_is_shared_between_clones() {
+        // arrange
+        let token = Cancellation::new();
+        let clone = token.clone();
+
+        // act
+        let before = clone.is_cancelled();
+        token.cancel();
+
+        // assert
+        assert!(!before);
+        assert!(clone.is_cancelled());
+        assert!(Cancellation::with_deadline(Instant::now()).is_cancelled());
+        assert!(!Cancellation::with_timeout(Duration::from_secs(60)).is_cancelled());
+    }
+}
@@ -2,6 +2,48 @@ use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum AppError {
+    #[error("Error encoding animation: {0}")]
+    AnimationError(String),
+
+    #[error("Invalid command line arguments: {0}")]
+    ArgumentError(String),
+
+    #[error("Error listening on `{address}`. Check the address is valid and not in use.")]
+    BindError {
+        address: String,
+        source: std::io::Error,
+    },
+
+    #[error("Error reading binary file: `{path}`. Check it exists and is readable.")]
+    BinaryFileError {
+        path: String,
+        source: std::io::Error,
+    },
+
+    #[error("Cancelled, or past the deadline, after {completed} of {total} steps")]
+    CancelledError { completed: usize, total: usize },
+
+    #[error("Duplicate point id `{0}`. Check each point has its own id.")]
+    DuplicateIdError(String),
+
+    #[error("Error resolving point direction: {0}")]
+    FacingError(String),
+
+    #[error("Error reading FlatGeobuf input: {0}")]
+    FlatGeobufError(String),
+
+    #[error("Error reading GeoTIFF elevations: {0}")]
+    GeoTIFFError(String),
+
+    #[error("Error parsing GPX: {0}")]
+    GPXParseError(String),
+
+    #[error("Error decoding visibility graph: {0}")]
+    GraphFormatError(String),
+
+    #[error("Heatmap of {columns} by {rows} cells is too large. Check the points are not spread too far for the cell size.")]
+    HeatmapSizeError { columns: usize, rows: usize },
+
     #[error(
         "Error reading input file: `{expected_path}`. Check it exists and contains valid UTF-8."
     )]
@@ -12,4 +54,49 @@ pub enum AppError {
 
     #[error("Error parsing JSON. Check the input JSON is valid and has expected structure: {0}")]
     JSONParseError(serde_json::Error),
+
+    #[error("Error subscribing to MQTT broker: {0}")]
+    MQTTError(String),
+
+    #[error("Error parsing NDJSON line {line}: {source}")]
+    NDJSONParseError {
+        line: usize,
+        source: serde_json::Error,
+    },
+
+    #[error("Error writing output: `{path}`. Check the parent directory exists and is writable.")]
+    OutputError {
+        path: String,
+        source: std::io::Error,
+    },
+
+    #[error("Error reading Parquet input: {0}")]
+    ParquetError(String),
+
+    #[error("Error reading point cloud: {0}")]
+    PointCloudError(String),
+
+    #[error("No point numbered `{0}`. Check the point number is in the points input.")]
+    PointNotFoundError(u32),
+
+    #[error("Error reading points cache: {0}")]
+    PointsCacheError(String),
+
+    #[error("Error reading PostgreSQL input: {0}")]
+    PostgresError(String),
+
+    #[error("Error parsing protobuf points: {0}")]
+    ProtobufParseError(String),
+
+    #[error("Error parsing queries: {0}")]
+    QueryParseError(String),
+
+    #[error("Error using Redis store: {0}")]
+    RedisError(String),
+
+    #[error("Error reading SQLite input: {0}")]
+    SQLiteError(String),
+
+    #[error("Error reading shapefile: {0}")]
+    ShapefileError(String),
 }
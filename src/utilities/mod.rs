@@ -1,3 +1,10 @@
+mod cancel;
 mod error;
+mod trace;
 
+pub use cancel::Cancellation;
 pub use error::AppError;
+pub use trace::{
+    format_profile, log, log_enabled, set_log_level, start_profiling, take_profile, LogLevel,
+    ProfileEntry, Span,
+};
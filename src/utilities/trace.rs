@@ -0,0 +1,316 @@
+use std::{
+    cell::RefCell,
+    fmt::Write,
+    str::FromStr,
+    sync::atomic::{AtomicU8, AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+/// Most detailed level currently logged, as a [`LogLevel`] discriminant
+static LOG_LEVEL: AtomicU8 = AtomicU8::new(LogLevel::Off as u8);
+
+/// Number of threads profiling, so spans skip the thread local profile while
+/// none are
+static PROFILING_THREADS: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    /// Span timings recorded on this thread, while profiling
+    static PROFILE: RefCell<Option<Profile>> = RefCell::new(None);
+}
+
+/// Total time spent in spans with the same name, collected while profiling
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProfileEntry {
+    pub name: &'static str,
+
+    /// Number of enclosing spans when this span was first entered
+    pub depth: usize,
+
+    pub calls: usize,
+    pub total: Duration,
+}
+
+/// Spans recorded while profiling, in the order first entered
+struct Profile {
+    depth: usize,
+    entries: Vec<ProfileEntry>,
+}
+
+/// Detail of diagnostic logging, written to standard error
+#[derive(Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum LogLevel {
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl FromStr for LogLevel {
+    type Err = String;
+
+    /// Parse a level name, ignoring case.  `RUST_LOG` style filters are also
+    /// accepted, taking the level for `neighbours`, or else the first bare
+    /// level, so `warn,neighbours=debug` gives [`LogLevel::Debug`], while a
+    /// filter only for other crates, such as `hyper=debug`, gives
+    /// [`LogLevel::Off`].
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let directives: Vec<&str> = value
+            .split(',')
+            .map(str::trim)
+            .filter(|directive| !directive.is_empty())
+            .collect();
+        let level = match directives
+            .iter()
+            .find_map(|directive| directive.strip_prefix("neighbours="))
+            .or_else(|| {
+                directives
+                    .iter()
+                    .find(|directive| !directive.contains('='))
+                    .copied()
+            }) {
+            Some(value) => value,
+            None => return Ok(LogLevel::Off),
+        };
+        match level.to_ascii_lowercase().as_str() {
+            "off" => Ok(LogLevel::Off),
+            "error" => Ok(LogLevel::Error),
+            "warn" => Ok(LogLevel::Warn),
+            "info" => Ok(LogLevel::Info),
+            "debug" => Ok(LogLevel::Debug),
+            "trace" => Ok(LogLevel::Trace),
+            _ => Err(format!(
+                "unknown log level `{value}`, expected `off`, `error`, `warn`, `info`, `debug` or `trace`"
+            )),
+        }
+    }
+}
+
+/// Set the most detailed level logged.  Logging is off until this is called.
+pub fn set_log_level(level: LogLevel) {
+    LOG_LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+/// Returns true if messages at `level` are logged
+pub fn log_enabled(level: LogLevel) -> bool {
+    level != LogLevel::Off && level as u8 <= LOG_LEVEL.load(Ordering::Relaxed)
+}
+
+/// Write `message` to standard error, if `level` is logged
+pub fn log(level: LogLevel, message: &str) {
+    if log_enabled(level) {
+        eprintln!("[{level:?}] {message}");
+    }
+}
+
+/// Start recording the time spent in each [`Span`] entered on this thread,
+/// discarding any earlier recording
+pub fn start_profiling() {
+    PROFILE.with(|profile| {
+        let earlier = profile.borrow_mut().replace(Profile {
+            depth: 0,
+            entries: vec![],
+        });
+        if earlier.is_none() {
+            PROFILING_THREADS.fetch_add(1, Ordering::Relaxed);
+        }
+    });
+}
+
+/// Stop profiling, returning time spent in spans entered on this thread since
+/// [`start_profiling`], with one entry for each span name, in the order first
+/// entered.  Times of nested spans are also included in times of the spans
+/// enclosing them.
+pub fn take_profile() -> Vec<ProfileEntry> {
+    PROFILE.with(|profile| match profile.borrow_mut().take() {
+        Some(value) => {
+            PROFILING_THREADS.fetch_sub(1, Ordering::Relaxed);
+            value.entries
+        }
+        None => vec![],
+    })
+}
+
+/// Render `entries` as a timing report table, indenting nested spans
+pub fn format_profile(entries: &[ProfileEntry]) -> String {
+    let name_width = entries
+        .iter()
+        .map(|entry| 2 * entry.depth + entry.name.len())
+        .max()
+        .unwrap_or(0)
+        .max(4);
+    let mut result = format!(
+        "{:<name_width$}  {:>6}  {:>12}\n",
+        "span", "calls", "total ms"
+    );
+    for entry in entries {
+        let name = format!("{}{}", "  ".repeat(entry.depth), entry.name);
+        let _ = writeln!(
+            result,
+            "{name:<name_width$}  {:>6}  {:>12.3}",
+            entry.calls,
+            entry.total.as_secs_f64() * 1000.0
+        );
+    }
+    result
+}
+
+/// Timed section of work, such as parsing or building a graph.  Entering is
+/// logged at [`LogLevel::Trace`], and leaving, with the elapsed time, at
+/// [`LogLevel::Debug`], when the span is dropped.  While profiling, the
+/// elapsed time is also recorded, see [`start_profiling`].  With logging off,
+/// and no thread profiling, a span neither allocates nor reads the clock.
+pub struct Span {
+    name: &'static str,
+
+    /// Detail included in messages, built only if they are logged
+    detail: Option<String>,
+
+    /// Time entered, unless neither logged nor profiled
+    start: Option<Instant>,
+
+    /// Whether the span was recorded in this thread's profile
+    profiled: bool,
+}
+
+impl Span {
+    /// Enter span `name`, with detail, such as input size, included in
+    /// messages.  `detail` is called only if messages are logged, so callers
+    /// may format it freely.
+    pub fn enter<F: FnOnce() -> String>(name: &'static str, detail: F) -> Self {
+        let detail = if log_enabled(LogLevel::Debug) {
+            let value = detail();
+            log(LogLevel::Trace, &format!("enter {name} {value}"));
+            Some(value)
+        } else {
+            None
+        };
+        let profiled = PROFILING_THREADS.load(Ordering::Relaxed) > 0
+            && PROFILE.with(|profile| match profile.borrow_mut().as_mut() {
+                Some(Profile { depth, entries }) => {
+                    if !entries.iter().any(|entry| entry.name == name) {
+                        entries.push(ProfileEntry {
+                            name,
+                            depth: *depth,
+                            calls: 0,
+                            total: Duration::default(),
+                        });
+                    }
+                    *depth += 1;
+                    true
+                }
+                None => false,
+            });
+        let start = if detail.is_some() || profiled {
+            Some(Instant::now())
+        } else {
+            None
+        };
+        Span {
+            name,
+            detail,
+            start,
+            profiled,
+        }
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        let elapsed = match self.start {
+            Some(value) => value.elapsed(),
+            None => return,
+        };
+        if self.profiled {
+            PROFILE.with(|profile| {
+                if let Some(Profile { depth, entries }) = profile.borrow_mut().as_mut() {
+                    *depth = depth.saturating_sub(1);
+                    if let Some(entry) = entries.iter_mut().find(|entry| entry.name == self.name) {
+                        entry.calls += 1;
+                        entry.total += elapsed;
+                    }
+                }
+            });
+        }
+        if let Some(detail) = &self.detail {
+            log(
+                LogLevel::Debug,
+                &format!(
+                    "{} {} took {:.3} ms",
+                    self.name,
+                    detail,
+                    elapsed.as_secs_f64() * 1000.0
+                ),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_profile, start_profiling, take_profile, LogLevel, Span};
+
+    #[test]
+    fn log_level_parses_filters() {
+        // arrange
+        let values = [
+            "debug",
+            "TRACE",
+            "warn,neighbours=debug",
+            "other=trace,info",
+            "hyper=debug",
+        ];
+
+        // act
+        let outcome: Vec<LogLevel> = values.iter().map(|val| val.parse().unwrap()).collect();
+
+        // assert
+        assert_eq!(
+            outcome,
+            vec![
+                LogLevel::Debug,
+                LogLevel::Trace,
+                LogLevel::Debug,
+                LogLevel::Info,
+                LogLevel::Off
+            ]
+        );
+        assert!("verbose".parse::<LogLevel>().is_err());
+        assert!("hyper=debug,neighbours=loud".parse::<LogLevel>().is_err());
+    }
+
+    #[test]
+    fn profile_records_nested_spans() {
+        // arrange
+        start_profiling();
+
+        // act
+        {
+            let _outer = Span::enter("outer", String::new);
+            for _ in 0..2 {
+                let _inner = Span::enter("inner", String::new);
+            }
+        }
+        let _ignored = Span::enter("discarded", String::new);
+        let outcome = take_profile();
+
+        // assert
+        let summary: Vec<(&str, usize, usize)> = outcome
+            .iter()
+            .map(|entry| (entry.name, entry.depth, entry.calls))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![("outer", 0, 1), ("inner", 1, 2), ("discarded", 0, 0)]
+        );
+        assert!(outcome[0].total >= outcome[1].total);
+        let report = format_profile(&outcome);
+        assert_eq!(
+            report.lines().next(),
+            Some("span        calls      total ms")
+        );
+        assert!(report.contains("\n  inner         2  "));
+        assert_eq!(take_profile().len(), 0);
+    }
+}